@@ -16,5 +16,7 @@ export EMBEDDINGS_SERVER_URL="http://localhost:11434" # assumes that you install
 
 # Add these lines to enable all log options
 export LOG_ALL=1
+# export LOG_FORMAT="json" # one JSON object per log line, with the job/agent/provider/tool span fields
+# export OTEL_EXPORTER_OTLP_ENDPOINT="http://localhost:4318" # export spans to an OTLP/HTTP collector
 
 cargo run
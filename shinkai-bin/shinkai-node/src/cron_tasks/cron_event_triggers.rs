@@ -0,0 +1,330 @@
+use std::{
+    collections::{HashMap, HashSet}, pin::Pin, sync::{Arc, Weak}, time::Duration
+};
+
+use ed25519_dalek::SigningKey;
+use futures::Future;
+use serde_json::Value;
+use shinkai_message_primitives::{
+    schemas::{crontab::CronTask, shinkai_name::ShinkaiName, webhook::WebhookEventType, ws_types::WSUpdateHandler}, shinkai_utils::shinkai_logging::{shinkai_log, ShinkaiLogLevel, ShinkaiLogOption}
+};
+use shinkai_sqlite::SqliteManager;
+use tokio::{sync::Mutex, time::Instant};
+use x25519_dalek::{PublicKey as EncryptionPublicKey, StaticSecret as EncryptionStaticKey};
+
+use super::cron_manager::{CronJobMessageSent, CronManager, CronManagerError};
+use crate::{llm_provider::job_manager::JobManager, managers::IdentityManager};
+
+/// How often queued node events are picked up
+const CRON_EVENT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// Node events picked up at a time
+const CRON_EVENT_BATCH_SIZE: u64 = 100;
+
+/// Run of an event-triggered task waiting for the end of its debounce window
+#[derive(Debug, Clone)]
+pub struct PendingCronEventRun {
+    pub task: CronTask,
+    pub due: Instant,
+    /// Payload of the latest event, injected into the job message
+    pub payload: Value,
+    pub events: u32,
+    /// Queued events that triggered the run, deleted from the queue once it starts
+    pub event_ids: Vec<i64>,
+}
+
+impl CronManager {
+    /// Starts the tasks triggered by node events. Events are queued in the database by whatever produced
+    /// them and stay there until the run they triggered starts, so the ones still waiting for the end of
+    /// a debounce window are picked up again after a restart.
+    #[allow(clippy::too_many_arguments)]
+    pub fn process_event_queue(
+        db: Weak<SqliteManager>,
+        node_profile_name: ShinkaiName,
+        identity_sk: SigningKey,
+        job_manager: Arc<Mutex<JobManager>>,
+        identity_manager: Arc<Mutex<IdentityManager>>,
+        node_encryption_sk: EncryptionStaticKey,
+        node_encryption_pk: EncryptionPublicKey,
+        ws_manager: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
+    ) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut pending: HashMap<i32, PendingCronEventRun> = HashMap::new();
+            let mut last_event_id = 0;
+
+            loop {
+                let Some(db_arc) = db.upgrade() else {
+                    shinkai_log(
+                        ShinkaiLogOption::CronExecution,
+                        ShinkaiLogLevel::Error,
+                        "Failed to upgrade Weak reference to Arc for SqliteManager access. Exiting cron event loop.",
+                    );
+                    return;
+                };
+
+                match db_arc.get_cron_task_events(last_event_id, CRON_EVENT_BATCH_SIZE) {
+                    Ok(events) => {
+                        let mut unused_event_ids = Vec::new();
+                        for (event_id, event_type, payload) in events {
+                            last_event_id = event_id;
+                            let tasks = db_arc.get_event_triggered_cron_tasks(event_type).unwrap_or_default();
+                            let mut triggered = false;
+                            for task in tasks {
+                                triggered |= Self::debounce_cron_event(
+                                    &mut pending,
+                                    task,
+                                    event_type,
+                                    event_id,
+                                    &payload,
+                                    Instant::now(),
+                                );
+                            }
+                            if !triggered {
+                                unused_event_ids.push(event_id);
+                            }
+                        }
+                        Self::delete_cron_task_events(&db_arc, &unused_event_ids);
+                    }
+                    Err(e) => {
+                        shinkai_log(
+                            ShinkaiLogOption::CronExecution,
+                            ShinkaiLogLevel::Error,
+                            format!("Failed to get cron task events: {}", e).as_str(),
+                        );
+                    }
+                }
+
+                let now = Instant::now();
+                let due: Vec<i32> = pending
+                    .iter()
+                    .filter(|(_, run)| run.due <= now)
+                    .map(|(task_id, _)| *task_id)
+                    .collect();
+                for task_id in due {
+                    let Some(run) = pending.remove(&task_id) else {
+                        continue;
+                    };
+                    shinkai_log(
+                        ShinkaiLogOption::CronExecution,
+                        ShinkaiLogLevel::Info,
+                        format!("Cron task {} triggered by {} event(s)", task_id, run.events).as_str(),
+                    );
+
+                    // Events that also wait for the run of another task stay queued
+                    let still_pending: HashSet<i64> =
+                        pending.values().flat_map(|run| run.event_ids.iter().copied()).collect();
+                    let started_event_ids: Vec<i64> = run
+                        .event_ids
+                        .iter()
+                        .copied()
+                        .filter(|event_id| !still_pending.contains(event_id))
+                        .collect();
+                    Self::delete_cron_task_events(&db_arc, &started_event_ids);
+
+                    let mut cron_task = run.task;
+                    cron_task.action = cron_task.action.with_event_payload(&run.payload);
+                    let db_clone = db.clone();
+                    let identity_sk_clone = identity_sk.clone();
+                    let job_manager_clone = job_manager.clone();
+                    let identity_manager_clone = identity_manager.clone();
+                    let node_encryption_sk_clone = node_encryption_sk.clone();
+                    let node_profile_name_clone = node_profile_name.clone();
+                    let profile = node_profile_name.get_profile_name_string().unwrap_or_default();
+                    let ws_manager = ws_manager.clone();
+
+                    tokio::spawn(async move {
+                        let Some(db_arc) = db_clone.upgrade() else {
+                            return;
+                        };
+                        let llm_stopper = job_manager_clone.lock().await.llm_stopper.clone();
+                        let attempt =
+                            || -> Pin<Box<dyn Future<Output = Result<CronJobMessageSent, CronManagerError>> + Send>> {
+                                Box::pin(CronManager::process_job_message_queued(
+                                    cron_task.clone(),
+                                    db_clone.clone(),
+                                    identity_sk_clone.clone(),
+                                    job_manager_clone.clone(),
+                                    identity_manager_clone.clone(),
+                                    node_encryption_sk_clone.clone(),
+                                    node_encryption_pk,
+                                    node_profile_name_clone.clone(),
+                                    profile.clone(),
+                                    ws_manager.clone(),
+                                ))
+                            };
+                        let result =
+                            Self::run_cron_task_with_policy(&db_arc, &llm_stopper, &cron_task, None, false, attempt)
+                                .await;
+                        if let Err(e) = result {
+                            shinkai_log(
+                                ShinkaiLogOption::CronExecution,
+                                ShinkaiLogLevel::Error,
+                                format!("Event-triggered cron task {} failed: {:?}", cron_task.task_id, e).as_str(),
+                            );
+                        }
+                    });
+                }
+
+                drop(db_arc);
+                tokio::time::sleep(CRON_EVENT_POLL_INTERVAL).await;
+            }
+        })
+    }
+
+    fn delete_cron_task_events(db: &SqliteManager, event_ids: &[i64]) {
+        if event_ids.is_empty() {
+            return;
+        }
+        if let Err(e) = db.delete_cron_task_events(event_ids) {
+            shinkai_log(
+                ShinkaiLogOption::CronExecution,
+                ShinkaiLogLevel::Error,
+                format!("Failed to delete cron task events: {}", e).as_str(),
+            );
+        }
+    }
+
+    /// Adds an event to the pending run of a task it triggers. The first event opens the debounce window
+    /// of the task, the ones after it within the window only replace the payload. Returns whether the
+    /// event triggered the task.
+    pub fn debounce_cron_event(
+        pending: &mut HashMap<i32, PendingCronEventRun>,
+        task: CronTask,
+        event_type: WebhookEventType,
+        event_id: i64,
+        payload: &Value,
+        now: Instant,
+    ) -> bool {
+        let Some(trigger) = task.event_trigger.clone() else {
+            return false;
+        };
+        if task.paused || !trigger.event.matches(event_type, payload) {
+            return false;
+        }
+        // A task is not triggered by its own executions
+        if event_type == WebhookEventType::CronTaskExecuted
+            && payload.get("task_id").and_then(Value::as_i64) == Some(task.task_id as i64)
+        {
+            return false;
+        }
+        // Nor by the events of the jobs it sends its messages to or created
+        let mut payload = payload.clone();
+        if let Some(Value::Array(task_ids)) = payload.as_object_mut().and_then(|event| event.remove("cron_task_ids")) {
+            if task_ids.iter().any(|id| id.as_i64() == Some(task.task_id as i64)) {
+                return false;
+            }
+        }
+
+        let run = pending
+            .entry(task.task_id)
+            .and_modify(|run| {
+                run.payload = payload.clone();
+                run.events += 1;
+            })
+            .or_insert_with(|| PendingCronEventRun {
+                task,
+                due: now + Duration::from_secs(trigger.debounce_secs),
+                payload,
+                events: 1,
+                event_ids: Vec::new(),
+            });
+        run.event_ids.push(event_id);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use shinkai_message_primitives::{
+        schemas::crontab::{CronEventTrigger, CronTaskAction, CronTaskEvent},
+        shinkai_message::shinkai_message_schemas::JobMessage,
+    };
+
+    fn create_event_task(task_id: i32, event: CronTaskEvent, debounce_secs: u64) -> CronTask {
+        CronTask {
+            task_id,
+            name: "Event Task".to_string(),
+            description: None,
+            cron: String::new(),
+            timezone: None,
+            created_at: "2024-01-01T00:00:00Z".to_string(),
+            last_modified: "2024-01-01T00:00:00Z".to_string(),
+            action: CronTaskAction::SendMessageToJob {
+                job_id: "job_id".to_string(),
+                message: JobMessage {
+                    job_id: "job_id".to_string(),
+                    content: "New message: {{event.content}}".to_string(),
+                    parent: None,
+                    sheet_job_data: None,
+                    callback: None,
+                    metadata: None,
+                    tool_key: None,
+                    fs_files_paths: vec![],
+                    job_filenames: vec![],
+                    tools: None,
+                },
+            },
+            paused: false,
+            policy: Default::default(),
+            event_trigger: Some(CronEventTrigger { event, debounce_secs }),
+        }
+    }
+
+    #[test]
+    fn test_debounce_cron_events() {
+        let mut pending = HashMap::new();
+        let now = Instant::now();
+        let inbox = CronTaskEvent::InboxMessage {
+            inbox_name: "inbox::main".to_string(),
+        };
+
+        // Events within the window are coalesced, the latest payload wins
+        for (event_id, content) in [(1, "first"), (2, "second"), (3, "third")] {
+            assert!(CronManager::debounce_cron_event(
+                &mut pending,
+                create_event_task(1, inbox.clone(), 30),
+                WebhookEventType::InboxMessage,
+                event_id,
+                &json!({ "inbox_name": "inbox::main", "content": content }),
+                now,
+            ));
+        }
+        let run = &pending[&1];
+        assert_eq!(run.events, 3);
+        assert_eq!(run.due, now + Duration::from_secs(30));
+        assert_eq!(run.payload["content"], "third");
+        assert_eq!(run.event_ids, vec![1, 2, 3]);
+
+        // Other inboxes do not trigger the task
+        assert!(!CronManager::debounce_cron_event(
+            &mut pending,
+            create_event_task(2, inbox.clone(), 0),
+            WebhookEventType::InboxMessage,
+            4,
+            &json!({ "inbox_name": "inbox::other", "content": "hi" }),
+            now,
+        ));
+
+        // Nor does a task trigger itself
+        assert!(!CronManager::debounce_cron_event(
+            &mut pending,
+            create_event_task(3, CronTaskEvent::TaskCompleted { task_id: 3 }, 0),
+            WebhookEventType::CronTaskExecuted,
+            5,
+            &json!({ "task_id": 3, "status": "success" }),
+            now,
+        ));
+
+        // Nor by the messages of its own job
+        assert!(!CronManager::debounce_cron_event(
+            &mut pending,
+            create_event_task(4, inbox, 0),
+            WebhookEventType::InboxMessage,
+            6,
+            &json!({ "inbox_name": "inbox::main", "content": "answer", "cron_task_ids": [4] }),
+            now,
+        ));
+        assert_eq!(pending.len(), 1);
+    }
+}
@@ -2,22 +2,26 @@ use std::{
     collections::HashMap, fmt, pin::Pin, sync::{Arc, Weak}
 };
 
-use chrono::{Local, Utc};
+use chrono::{DateTime, Local, SecondsFormat, Utc};
 use ed25519_dalek::SigningKey;
 use futures::Future;
 use shinkai_message_primitives::{
     schemas::{
-        crontab::{CronTask, CronTaskAction}, inbox_name::InboxNameError, shinkai_name::ShinkaiName, ws_types::WSUpdateHandler
+        crontab::{CronExecutionStatus, CronMisfirePolicy, CronTask, CronTaskAction}, inbox_name::{InboxName, InboxNameError}, job_pipeline::JobPipelineRunStatus, shinkai_name::ShinkaiName, webhook::WebhookEventType, ws_types::WSUpdateHandler
     }, shinkai_message::shinkai_message_schemas::{AssociatedUI, JobMessage}, shinkai_utils::{
         shinkai_logging::{shinkai_log, ShinkaiLogLevel, ShinkaiLogOption}, signatures::clone_signature_secret_key
     }
 };
 use shinkai_sqlite::{errors::SqliteManagerError, SqliteManager};
-use tokio::sync::Mutex;
+use serde_json::Value;
+use tokio::sync::{broadcast, Mutex};
 use x25519_dalek::{PublicKey as EncryptionPublicKey, StaticSecret as EncryptionStaticKey};
 
+use super::cron_schedule::CronSchedule;
 use crate::{
-    llm_provider::{error::LLMProviderError, job_manager::JobManager}, managers::IdentityManager, network::{node_error::NodeError, Node}
+    llm_provider::{error::LLMProviderError, job_manager::JobManager, llm_stopper::LLMStopper}, managers::IdentityManager, network::{
+        node_error::NodeError, node_metrics::node_metrics, webhook_dispatcher::{subscribe_node_events, wait_for_job_outcome}, Node
+    }
 };
 
 #[derive(Debug)]
@@ -59,6 +63,13 @@ impl From<NodeError> for CronManagerError {
     }
 }
 
+/// Missed runs looked at per task each time the schedule is checked
+const CRON_MAX_MISSED_RUNS: usize = 1000;
+/// Missed runs made up for per task under `CronMisfirePolicy::RunAll`, the most recent ones
+const CRON_MAX_CATCH_UP_RUNS: usize = 100;
+/// How often an execution that started a job pipeline run checks whether the run finished
+const CRON_PIPELINE_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+
 impl fmt::Display for CronManagerError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -71,6 +82,15 @@ impl fmt::Display for CronManagerError {
     }
 }
 
+/// Job message sent by one attempt of a cron task
+#[derive(Debug, Clone)]
+pub struct CronJobMessageSent {
+    pub job_id: String,
+    pub message_hash: String,
+    /// Set instead of the message when the task started a job pipeline run
+    pub pipeline_run_id: Option<String>,
+}
+
 pub struct CronManager {
     pub db: Weak<SqliteManager>,
     pub node_profile_name: ShinkaiName,
@@ -80,6 +100,8 @@ pub struct CronManager {
     pub node_encryption_sk: EncryptionStaticKey,
     pub node_encryption_pk: EncryptionPublicKey,
     pub _cron_processing_task: Option<tokio::task::JoinHandle<()>>,
+    pub _event_processing_task: Option<tokio::task::JoinHandle<()>>,
+    pub _scheduled_message_task: Option<tokio::task::JoinHandle<()>>,
     pub ws_manager: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
 }
 
@@ -94,6 +116,25 @@ impl CronManager {
         node_encryption_pk: EncryptionPublicKey,
         ws_manager: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
     ) -> Self {
+        let event_processing_task = CronManager::process_event_queue(
+            db.clone(),
+            node_name.clone(),
+            clone_signature_secret_key(&identity_secret_key),
+            job_manager.clone(),
+            identity_manager.clone(),
+            node_encryption_sk.clone(),
+            node_encryption_pk,
+            ws_manager.clone(),
+        );
+        let scheduled_message_task = CronManager::process_scheduled_job_messages(
+            db.clone(),
+            node_name.clone(),
+            clone_signature_secret_key(&identity_secret_key),
+            job_manager.clone(),
+            identity_manager.clone(),
+            node_encryption_sk.clone(),
+            node_encryption_pk,
+        );
         let cron_processing_task = CronManager::process_job_queue(
             db.clone(),
             node_name.clone(),
@@ -138,6 +179,8 @@ impl CronManager {
             node_encryption_sk,
             node_encryption_pk,
             _cron_processing_task: Some(cron_processing_task),
+            _event_processing_task: Some(event_processing_task),
+            _scheduled_message_task: Some(scheduled_message_task),
             ws_manager,
         }
     }
@@ -171,7 +214,7 @@ impl CronManager {
                 ShinkaiName,
                 String,
                 Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
-            ) -> Pin<Box<dyn Future<Output = Result<bool, CronManagerError>> + Send>>
+            ) -> Pin<Box<dyn Future<Output = Result<CronJobMessageSent, CronManagerError>> + Send>>
             + Send
             + Sync
             + 'static,
@@ -185,12 +228,27 @@ impl CronManager {
                 "Starting cron job queue processing loop",
             );
 
+            // Executions left running were cut short when the node stopped
+            if let Some(db) = db.upgrade() {
+                if let Err(e) = db.fail_running_cron_task_executions("The node stopped before the execution finished") {
+                    shinkai_log(
+                        ShinkaiLogOption::CronExecution,
+                        ShinkaiLogLevel::Error,
+                        format!("Failed to close interrupted cron executions: {}", e).as_str(),
+                    );
+                }
+            }
+
             let is_testing = std::env::var("IS_TESTING").unwrap_or_else(|_| String::from("false")) != "false";
+            let interval = chrono::Duration::seconds(cron_time_interval as i64);
+            let mut window_start = Utc::now();
 
             loop {
-                let jobs_to_process: HashMap<String, Vec<(String, CronTask)>> = {
-                    let db_arc = db.upgrade();
-                    if db_arc.is_none() {
+                // Each iteration handles the runs due in [window_start, window_end)
+                let window_end = window_start + interval;
+                let db_arc = match db.upgrade() {
+                    Some(db_arc) => db_arc,
+                    None => {
                         shinkai_log(
                             ShinkaiLogOption::CronExecution,
                             ShinkaiLogLevel::Error,
@@ -198,15 +256,21 @@ impl CronManager {
                         );
                         return;
                     }
-                    let db_arc = db_arc.unwrap();
-                    db_arc
-                        .get_all_cron_tasks()
-                        .unwrap_or_default()
-                        .into_iter()
-                        .filter(|task| !task.paused)
-                        .map(|task| (task.created_at.clone(), vec![(task.task_id.to_string(), task)]))
-                        .collect()
                 };
+                let all_tasks = db_arc.get_all_cron_tasks().unwrap_or_default();
+                if !is_testing {
+                    // Runs due while a task is paused are not missed runs
+                    let paused_until = Self::format_scheduled_run(window_end - chrono::Duration::milliseconds(1));
+                    for task in all_tasks.iter().filter(|task| task.paused) {
+                        let _ = db_arc.set_cron_task_last_scheduled_run(task.task_id.into(), &paused_until);
+                    }
+                }
+                let jobs_to_process: HashMap<String, Vec<(String, CronTask)>> = all_tasks
+                    .into_iter()
+                    // Event-triggered tasks are started by `process_event_queue`
+                    .filter(|task| !task.paused && task.event_trigger.is_none())
+                    .map(|task| (task.created_at.clone(), vec![(task.task_id.to_string(), task)]))
+                    .collect();
                 if !jobs_to_process.is_empty() {
                     shinkai_log(
                         ShinkaiLogOption::CronExecution,
@@ -214,12 +278,36 @@ impl CronManager {
                         format!("Cron Jobs retrieved from SqliteManager: {:?}", jobs_to_process.len()).as_str(),
                     );
                 }
-                let mut handles = Vec::new();
 
                 // Spawn tasks based on filtered job IDs
                 for (_time_created, tasks) in jobs_to_process {
                     for (_, cron_task) in tasks {
-                        if !is_testing && !Self::should_execute_cron_task(&cron_task, cron_time_interval) {
+                        // Runs paired with whether they were missed, oldest first
+                        let mut runs = Vec::new();
+                        if is_testing {
+                            runs.push((Utc::now(), false));
+                        } else {
+                            match Self::cron_task_misfired_runs(&db_arc, &cron_task, window_start) {
+                                Ok(missed) => runs.extend(missed.into_iter().map(|run| (run, true))),
+                                Err(e) => {
+                                    shinkai_log(
+                                        ShinkaiLogOption::CronExecution,
+                                        ShinkaiLogLevel::Error,
+                                        format!(
+                                            "Failed to check missed runs of cron task {}: {}",
+                                            cron_task.task_id, e
+                                        )
+                                        .as_str(),
+                                    );
+                                }
+                            }
+                            runs.extend(
+                                Self::cron_task_runs_between(&cron_task, window_start, window_end)
+                                    .into_iter()
+                                    .map(|run| (run, false)),
+                            );
+                        }
+                        if runs.is_empty() {
                             shinkai_log(
                                 ShinkaiLogOption::CronExecution,
                                 ShinkaiLogLevel::Debug,
@@ -239,47 +327,253 @@ impl CronManager {
                         let profile_clone = node_profile_name.clone().get_profile_name_string().unwrap_or_default();
                         let ws_manager = ws_manager.clone();
 
-                        let handle = tokio::spawn(async move {
-                            let result = job_processing_fn_clone(
-                                cron_task,
-                                db_clone,
-                                identity_sk_clone,
-                                job_manager_clone,
-                                identity_manager_clone,
-                                node_encryption_sk_clone,
-                                node_encryption_pk_clone,
-                                node_profile_name_clone,
-                                profile_clone,
-                                ws_manager,
-                            )
-                            .await;
-                            match result {
-                                Ok(_) => {
-                                    shinkai_log(
-                                        ShinkaiLogOption::JobExecution,
-                                        ShinkaiLogLevel::Debug,
-                                        "Cron Job processed successfully",
-                                    );
+                        // Retries can outlast the window, so the loop does not wait for the runs
+                        tokio::spawn(async move {
+                            let Some(db_arc) = db_clone.upgrade() else {
+                                return;
+                            };
+                            let llm_stopper = job_manager_clone.lock().await.llm_stopper.clone();
+                            let attempt = || {
+                                job_processing_fn_clone(
+                                    cron_task.clone(),
+                                    db_clone.clone(),
+                                    identity_sk_clone.clone(),
+                                    job_manager_clone.clone(),
+                                    identity_manager_clone.clone(),
+                                    node_encryption_sk_clone.clone(),
+                                    node_encryption_pk_clone,
+                                    node_profile_name_clone.clone(),
+                                    profile_clone.clone(),
+                                    ws_manager.clone(),
+                                )
+                            };
+
+                            // Runs of a task follow each other. Schedules with seconds are run on time
+                            // rather than at the start of the window.
+                            for (run, misfired) in runs {
+                                if let Ok(delay) = (run - Utc::now()).to_std() {
+                                    tokio::time::sleep(delay).await;
                                 }
-                                Err(e) => {
-                                    shinkai_log(
-                                        ShinkaiLogOption::CronExecution,
-                                        ShinkaiLogLevel::Error,
-                                        format!("Cron Job processing failed: {:?}", e).as_str(),
-                                    );
+                                let scheduled_time = if is_testing { None } else { Some(run) };
+                                let result = Self::run_cron_task_with_policy(
+                                    &db_arc,
+                                    &llm_stopper,
+                                    &cron_task,
+                                    scheduled_time,
+                                    misfired,
+                                    &attempt,
+                                )
+                                .await;
+                                match result {
+                                    Ok(_) => {
+                                        shinkai_log(
+                                            ShinkaiLogOption::JobExecution,
+                                            ShinkaiLogLevel::Debug,
+                                            "Cron Job processed successfully",
+                                        );
+                                    }
+                                    Err(e) => {
+                                        shinkai_log(
+                                            ShinkaiLogOption::CronExecution,
+                                            ShinkaiLogLevel::Error,
+                                            format!("Cron Job processing failed: {:?}", e).as_str(),
+                                        );
+                                    }
                                 }
                             }
                         });
-
-                        handles.push(handle);
                     }
                 }
-                futures::future::join_all(handles).await;
-                tokio::time::sleep(tokio::time::Duration::from_secs(cron_time_interval)).await;
+                drop(db_arc);
+                if let Ok(remaining) = (window_end - Utc::now()).to_std() {
+                    tokio::time::sleep(remaining).await;
+                }
+                // Windows follow each other so runs are neither repeated nor skipped, unless the loop fell
+                // behind by more than a whole window. Those runs are handled by the misfire policy.
+                window_start = window_end.max(Utc::now() - interval);
             }
         })
     }
 
+    /// Runs a task for the run due at `scheduled_time`, retrying failed attempts as its policy allows.
+    /// Every attempt is recorded in the execution log.
+    pub async fn run_cron_task_with_policy<F>(
+        db: &Arc<SqliteManager>,
+        llm_stopper: &LLMStopper,
+        cron_task: &CronTask,
+        scheduled_time: Option<DateTime<Utc>>,
+        misfired: bool,
+        attempt_fn: F,
+    ) -> Result<(), CronManagerError>
+    where
+        F: Fn() -> Pin<Box<dyn Future<Output = Result<CronJobMessageSent, CronManagerError>> + Send>>,
+    {
+        let task_id: i64 = cron_task.task_id.into();
+        let scheduled_time = scheduled_time.map(Self::format_scheduled_run);
+        if let Some(scheduled_time) = &scheduled_time {
+            db.set_cron_task_last_scheduled_run(task_id, scheduled_time)?;
+        }
+
+        let retry = &cron_task.policy.retry;
+        let mut attempt = 1;
+        loop {
+            let execution_id = db.start_cron_task_execution(task_id, scheduled_time.as_deref(), attempt, misfired)?;
+            // Subscribed before the message is sent so that its outcome can't be missed
+            let mut events = subscribe_node_events();
+            let sent = attempt_fn().await;
+            let status = Self::finish_cron_task_attempt(
+                db,
+                llm_stopper,
+                execution_id,
+                &sent,
+                &mut events,
+                cron_task.policy.timeout_secs,
+            )
+            .await?;
+            if status == CronExecutionStatus::Success {
+                return Ok(());
+            }
+            if attempt >= retry.max_attempts {
+                return Err(CronManagerError::SomeError(format!(
+                    "Cron task {} did not succeed after {} attempts",
+                    task_id, attempt
+                )));
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(retry.backoff(attempt))).await;
+            attempt += 1;
+        }
+    }
+
+    /// Records how an attempt ended. It succeeds once the agent answered the message without an error,
+    /// or once the job pipeline run completed. A message not answered within the timeout is stopped.
+    async fn finish_cron_task_attempt(
+        db: &Arc<SqliteManager>,
+        llm_stopper: &LLMStopper,
+        execution_id: i64,
+        sent: &Result<CronJobMessageSent, CronManagerError>,
+        events: &mut broadcast::Receiver<(WebhookEventType, Value)>,
+        timeout_secs: Option<u64>,
+    ) -> Result<CronExecutionStatus, CronManagerError> {
+        let (status, error_message, job_id) = match sent {
+            Ok(sent) => {
+                let outcome = Self::wait_for_cron_task_outcome(db, sent, events);
+                let outcome = match timeout_secs {
+                    Some(timeout_secs) => tokio::time::timeout(std::time::Duration::from_secs(timeout_secs), outcome)
+                        .await
+                        .ok(),
+                    None => Some(outcome.await),
+                };
+                let (status, error_message) = match outcome {
+                    Some(outcome) => outcome?,
+                    None => {
+                        if sent.pipeline_run_id.is_none() {
+                            let inbox_name = InboxName::get_job_inbox_name_from_params(sent.job_id.clone())?;
+                            llm_stopper.stop_message(&inbox_name.get_value(), &sent.message_hash);
+                        }
+                        let error_message = format!(
+                            "The agent did not answer within {} seconds",
+                            timeout_secs.unwrap_or_default()
+                        );
+                        (CronExecutionStatus::TimedOut, Some(error_message))
+                    }
+                };
+                (status, error_message, Some(sent.job_id.as_str()))
+            }
+            Err(e) => (CronExecutionStatus::Failed, Some(e.to_string()), None),
+        };
+        db.finish_cron_task_execution(execution_id, status, error_message.as_deref(), job_id)?;
+        node_metrics().record_cron_execution(status == CronExecutionStatus::Success);
+        Ok(status)
+    }
+
+    /// Waits for the job to finish processing the message, or for the job pipeline run to finish
+    async fn wait_for_cron_task_outcome(
+        db: &Arc<SqliteManager>,
+        sent: &CronJobMessageSent,
+        events: &mut broadcast::Receiver<(WebhookEventType, Value)>,
+    ) -> Result<(CronExecutionStatus, Option<String>), CronManagerError> {
+        if let Some(run_id) = &sent.pipeline_run_id {
+            loop {
+                match db.get_job_pipeline_run(run_id)?.map(|run| run.status) {
+                    Some(JobPipelineRunStatus::Completed) => return Ok((CronExecutionStatus::Success, None)),
+                    Some(JobPipelineRunStatus::Failed) | None => {
+                        let error_message = format!("Job pipeline run {} failed", run_id);
+                        return Ok((CronExecutionStatus::Failed, Some(error_message)));
+                    }
+                    Some(JobPipelineRunStatus::Running) => {}
+                }
+                tokio::time::sleep(CRON_PIPELINE_POLL_INTERVAL).await;
+            }
+        }
+
+        let error_message = match wait_for_job_outcome(events, &sent.job_id, &sent.message_hash).await {
+            Some((WebhookEventType::JobCompleted, _)) => return Ok((CronExecutionStatus::Success, None)),
+            Some((WebhookEventType::JobCancelled, _)) => "The job was stopped before the agent answered".to_string(),
+            Some((_, payload)) => format!(
+                "The agent failed to answer: {}",
+                payload.get("error").and_then(Value::as_str).unwrap_or_default()
+            ),
+            None => "Lost track of the job, whether the agent answered is unknown".to_string(),
+        };
+        Ok((CronExecutionStatus::Failed, Some(error_message)))
+    }
+
+    /// Runs missed between the last scheduled run of the task and `before` that its misfire policy
+    /// makes up for. The other missed runs are recorded as skipped.
+    pub fn cron_task_misfired_runs(
+        db: &Arc<SqliteManager>,
+        cron_task: &CronTask,
+        before: DateTime<Utc>,
+    ) -> Result<Vec<DateTime<Utc>>, CronManagerError> {
+        let task_id: i64 = cron_task.task_id.into();
+        let last_scheduled_run = match db.get_cron_task_last_scheduled_run(task_id)? {
+            Some(last_scheduled_run) => DateTime::parse_from_rfc3339(&last_scheduled_run)
+                .map_err(|e| CronManagerError::SomeError(e.to_string()))?
+                .with_timezone(&Utc),
+            None => return Ok(Vec::new()),
+        };
+        let schedule = CronSchedule::for_task(cron_task).map_err(CronManagerError::SomeError)?;
+        let mut missed = schedule.runs_between(
+            last_scheduled_run + chrono::Duration::milliseconds(1),
+            before,
+            CRON_MAX_MISSED_RUNS,
+        );
+        if missed.is_empty() {
+            return Ok(Vec::new());
+        }
+        // Whatever happens to them, the missed runs are handled
+        db.set_cron_task_last_scheduled_run(
+            task_id,
+            &Self::format_scheduled_run(before - chrono::Duration::milliseconds(1)),
+        )?;
+
+        let more_missed = missed.len() == CRON_MAX_MISSED_RUNS;
+        let catch_up = match cron_task.policy.misfire {
+            CronMisfirePolicy::Skip => 0,
+            CronMisfirePolicy::RunOnce => 1,
+            CronMisfirePolicy::RunAll => missed.len().min(CRON_MAX_CATCH_UP_RUNS),
+        };
+        let caught_up = missed.split_off(missed.len() - catch_up);
+        if let (Some(first), Some(last)) = (missed.first(), missed.last()) {
+            let error_message = format!(
+                "Skipped {}{} missed runs due from {} to {}",
+                if more_missed { "at least " } else { "" },
+                missed.len(),
+                first.to_rfc3339(),
+                last.to_rfc3339()
+            );
+            let execution_id =
+                db.start_cron_task_execution(task_id, Some(&Self::format_scheduled_run(*last)), 1, true)?;
+            db.finish_cron_task_execution(execution_id, CronExecutionStatus::Skipped, Some(&error_message), None)?;
+        }
+        Ok(caught_up)
+    }
+
+    /// Scheduled runs are stored so that they compare as text
+    fn format_scheduled_run(run: DateTime<Utc>) -> String {
+        run.to_rfc3339_opts(SecondsFormat::Millis, true)
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub async fn process_job_message_queued(
         cron_job: CronTask,
@@ -292,7 +586,7 @@ impl CronManager {
         node_profile_name: ShinkaiName,
         profile: String,
         _ws_manager: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
-    ) -> Result<bool, CronManagerError> {
+    ) -> Result<CronJobMessageSent, CronManagerError> {
         shinkai_log(
             ShinkaiLogOption::CronExecution,
             ShinkaiLogLevel::Debug,
@@ -308,7 +602,7 @@ impl CronManager {
 
         let shinkai_profile = ShinkaiName::from_node_and_profile_names(node_profile_name.to_string(), profile)?;
 
-        match cron_job.action {
+        let sent = match cron_job.action {
             CronTaskAction::CreateJobWithConfigAndMessage {
                 config,
                 message,
@@ -346,9 +640,8 @@ impl CronManager {
                     node_encryption_sk.clone(),
                     node_encryption_pk.clone(),
                     identity_secret_key.clone(),
-                    cron_job.task_id.into(),
                 )
-                .await?;
+                .await?
             }
             CronTaskAction::SendMessageToJob { job_id: _, message } => {
                 // Use send_job_message_with_bearer instead of ShinkaiMessageBuilder
@@ -361,63 +654,66 @@ impl CronManager {
                     node_encryption_sk.clone(),
                     node_encryption_pk.clone(),
                     identity_secret_key.clone(),
-                    cron_job.task_id.into(),
                 )
-                .await?;
+                .await?
             }
-        }
+            CronTaskAction::RunJobPipeline { pipeline_id, input } => {
+                let pipeline = db
+                    .get_job_pipeline(&pipeline_id)?
+                    .ok_or_else(|| CronManagerError::SomeError(format!("Job pipeline {} not found", pipeline_id)))?;
+                let run = JobManager::start_job_pipeline_run(job_manager.clone(), db.clone(), &pipeline, input).await?;
+                CronJobMessageSent {
+                    job_id: run.id.clone(),
+                    message_hash: String::new(),
+                    pipeline_run_id: Some(run.id),
+                }
+            }
+        };
 
-        Ok(true)
+        Ok(sent)
     }
 
     pub fn should_execute_cron_task(cron_task: &CronTask, cron_time_interval: u64) -> bool {
-        let now = Local::now();
-        let end_of_interval = now + chrono::Duration::seconds(cron_time_interval as i64);
-
-        println!("Evaluating cron task:");
-        println!("  Cron expression: {}", cron_task.cron);
-        println!("  Current time: {}", now.to_rfc3339());
-        println!("  End of interval: {}", end_of_interval.to_rfc3339());
-
-        // Validate that the cron expression has exactly 5 fields
-        if cron_task.cron.split_whitespace().count() != 5 {
-            println!("  Invalid cron expression: wrong number of fields");
+        if cron_task.event_trigger.is_some() {
             return false;
         }
-
-        let next_execution_time = match cron_parser::parse(&cron_task.cron, &now) {
-            Ok(datetime) => {
-                println!("  Next execution time: {}", datetime);
-                datetime
-            }
+        let now = Utc::now();
+        let end_of_interval = now + chrono::Duration::seconds(cron_time_interval as i64);
+        match CronSchedule::for_task(cron_task) {
+            Ok(schedule) => schedule
+                .next_run(now)
+                .map_or(false, |next_run| next_run <= end_of_interval),
             Err(e) => {
-                println!("  Failed to parse cron expression: {}", e);
-                return false;
+                shinkai_log(
+                    ShinkaiLogOption::CronExecution,
+                    ShinkaiLogLevel::Error,
+                    format!("Invalid schedule for cron task {}: {}", cron_task.task_id, e).as_str(),
+                );
+                false
             }
-        };
-
-        let is_after_now = next_execution_time >= now;
-        let is_before_end = next_execution_time <= end_of_interval;
-
-        println!("  Conditions:");
-        println!("    Is after current time? {}", is_after_now);
-        println!("    Is before interval end? {}", is_before_end);
-
-        let result = is_after_now && is_before_end;
-        println!("  Final result: {}", result);
-
-        result
+        }
     }
 
-    async fn log_success_to_sqlite(db: &Arc<SqliteManager>, task_id: i64, job_id: Option<String>) {
-        let execution_time = Local::now().to_rfc3339();
-        let db = db;
-        if let Err(err) = db.add_cron_task_execution(task_id, &execution_time, true, None, job_id) {
-            eprintln!("Failed to log success to SQLite: {}", err);
+    /// Times the task is due in `[start, end)`, none if its schedule is invalid
+    pub fn cron_task_runs_between(
+        cron_task: &CronTask,
+        start: chrono::DateTime<Utc>,
+        end: chrono::DateTime<Utc>,
+    ) -> Vec<chrono::DateTime<Utc>> {
+        match CronSchedule::for_task(cron_task) {
+            Ok(schedule) => schedule.runs_between(start, end, usize::MAX),
+            Err(e) => {
+                shinkai_log(
+                    ShinkaiLogOption::CronExecution,
+                    ShinkaiLogLevel::Error,
+                    format!("Invalid schedule for cron task {}: {}", cron_task.task_id, e).as_str(),
+                );
+                Vec::new()
+            }
         }
     }
 
-    async fn send_job_message_with_bearer(
+    pub async fn send_job_message_with_bearer(
         db: Arc<SqliteManager>,
         node_name_clone: ShinkaiName,
         identity_manager_clone: Arc<Mutex<IdentityManager>>,
@@ -426,27 +722,17 @@ impl CronManager {
         encryption_secret_key_clone: EncryptionStaticKey,
         encryption_public_key_clone: EncryptionPublicKey,
         signing_secret_key_clone: SigningKey,
-        task_id: i64,
-    ) -> Result<(), NodeError> {
+    ) -> Result<CronJobMessageSent, CronManagerError> {
         // Retrieve the bearer token from the database
-        let bearer = match db.read_api_v2_key() {
-            Ok(Some(token)) => token,
-            Ok(None) => {
-                Self::log_error_to_sqlite(&db, task_id, "Bearer token not found", None).await;
-                return Ok(());
-            }
-            Err(err) => {
-                Self::log_error_to_sqlite(&db, task_id, &format!("Failed to retrieve bearer token: {}", err), None)
-                    .await;
-                return Ok(());
-            }
-        };
+        let bearer = db
+            .read_api_v2_key()?
+            .ok_or_else(|| CronManagerError::SomeError("Bearer token not found".to_string()))?;
 
         // Create a local channel for the response
         let (res_tx, res_rx) = async_channel::bounded(1);
 
         // Send the job message
-        if let Err(err) = Node::v2_job_message(
+        Node::v2_job_message(
             db.clone(),
             node_name_clone,
             identity_manager_clone,
@@ -460,39 +746,22 @@ impl CronManager {
             res_tx,
         )
         .await
-        {
-            Self::log_error_to_sqlite(
-                &db,
-                task_id,
-                &format!("Failed to send job message: {}", err),
-                Some(job_message_clone.job_id),
-            )
-            .await;
-            return Ok(());
-        }
-
-        // Handle the response only if sending was successful
-        if let Err(err) = res_rx.recv().await {
-            Self::log_error_to_sqlite(
-                &db,
-                task_id,
-                &format!("Failed to receive response: {}", err),
-                Some(job_message_clone.job_id),
-            )
-            .await;
-        } else {
-            // Log success if the response is received successfully
-            Self::log_success_to_sqlite(&db, task_id, Some(job_message_clone.job_id)).await;
-        }
-
-        Ok(())
-    }
-
-    async fn log_error_to_sqlite(db: &Arc<SqliteManager>, task_id: i64, error_message: &str, job_id: Option<String>) {
-        let execution_time = Local::now().to_rfc3339();
-        let db = db;
-        if let Err(err) = db.add_cron_task_execution(task_id, &execution_time, false, Some(error_message), job_id) {
-            eprintln!("Failed to log error to SQLite: {}", err);
+        .map_err(|err| CronManagerError::SomeError(format!("Failed to send job message: {}", err)))?;
+
+        match res_rx.recv().await {
+            Ok(Ok(response)) => Ok(CronJobMessageSent {
+                job_id: job_message_clone.job_id,
+                message_hash: response.message_id,
+                pipeline_run_id: None,
+            }),
+            Ok(Err(api_error)) => Err(CronManagerError::SomeError(format!(
+                "Failed to send job message: {}",
+                api_error.message
+            ))),
+            Err(err) => Err(CronManagerError::SomeError(format!(
+                "Failed to receive response: {}",
+                err
+            ))),
         }
     }
 
@@ -507,7 +776,15 @@ impl CronManager {
         let profile = node_profile_name.get_profile_name_string().unwrap_or_default();
         let ws_manager = self.ws_manager.clone();
 
-        CronManager::process_job_message_queued(
+        let db_arc = db
+            .upgrade()
+            .ok_or_else(|| CronManagerError::SomeError("DB reference lost".to_string()))?;
+        let execution_id = db_arc.start_cron_task_execution(cron_task.task_id.into(), None, 1, false)?;
+        let timeout_secs = cron_task.policy.timeout_secs;
+        let llm_stopper = job_manager.lock().await.llm_stopper.clone();
+
+        let mut events = subscribe_node_events();
+        let result = CronManager::process_job_message_queued(
             cron_task,
             db,
             identity_secret_key,
@@ -519,9 +796,36 @@ impl CronManager {
             profile,
             ws_manager,
         )
-        .await?;
-
-        Ok(())
+        .await;
+        match result {
+            Ok(sent) => {
+                // The agent answers after the request is done, so the attempt is finished in the background
+                tokio::spawn(async move {
+                    let sent = Ok(sent);
+                    let finished = Self::finish_cron_task_attempt(
+                        &db_arc,
+                        &llm_stopper,
+                        execution_id,
+                        &sent,
+                        &mut events,
+                        timeout_secs,
+                    )
+                    .await;
+                    if let Err(e) = finished {
+                        shinkai_log(
+                            ShinkaiLogOption::CronExecution,
+                            ShinkaiLogLevel::Error,
+                            format!("Failed to record cron execution {}: {}", execution_id, e).as_str(),
+                        );
+                    }
+                });
+                Ok(())
+            }
+            result => {
+                Self::finish_cron_task_attempt(&db_arc, &llm_stopper, execution_id, &result, &mut events, None).await?;
+                result.map(|_| ())
+            }
+        }
     }
 
     /// Returns a schedule of when each active cron task is approximately going
@@ -551,16 +855,19 @@ impl CronManager {
         let mut schedule = Vec::new();
 
         for task in tasks {
-            if task.paused {
+            // Event-triggered tasks have no schedule
+            if task.paused || task.event_trigger.is_some() {
                 continue;
             }
 
-            // Parse the cron expression to find the next scheduled time
-            let parsed_next_time = cron_parser::parse(&task.cron, &now);
-            let next_time = match parsed_next_time {
-                Ok(t) => t,
-                Err(_) => {
-                    // If we fail to parse or determine next time, skip
+            // Parse the schedule to find the next scheduled time
+            let next_run = CronSchedule::for_task(&task)
+                .ok()
+                .and_then(|schedule| schedule.next_run(now.with_timezone(&Utc)));
+            let next_time = match next_run {
+                Some(t) => t.with_timezone(&Local),
+                None => {
+                    // If we fail to parse or the task won't run again, skip
                     continue;
                 }
             };
@@ -609,8 +916,12 @@ impl CronManager {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::network::webhook_dispatcher::emit_webhook_event;
     use chrono::Timelike;
-    use shinkai_message_primitives::schemas::crontab::CronTaskAction;
+    use serde_json::json;
+    use shinkai_embedding::model_type::{EmbeddingModelType, OllamaTextEmbeddingsInference};
+    use shinkai_message_primitives::schemas::crontab::{CronRetryPolicy, CronTaskAction, CronTaskPolicy};
+    use std::sync::atomic::{AtomicU32, Ordering};
 
     fn create_test_cron_task(cron: &str) -> CronTask {
         let job_message = JobMessage {
@@ -631,6 +942,7 @@ mod tests {
             description: Some("Test Description".to_string()),
             task_id: 1,
             cron: cron.to_string(),
+            timezone: None,
             created_at: "2024-01-01T00:00:00Z".to_string(),
             last_modified: "2024-01-01T00:00:00Z".to_string(),
             action: CronTaskAction::SendMessageToJob {
@@ -638,6 +950,8 @@ mod tests {
                 message: job_message,
             },
             paused: false,
+            policy: Default::default(),
+            event_trigger: None,
         }
     }
 
@@ -715,4 +1029,109 @@ mod tests {
         // Should not execute as it's outside the interval
         assert!(!CronManager::should_execute_cron_task(&task, 60));
     }
+
+    #[tokio::test]
+    async fn test_misfire_and_retry_policies() {
+        let dir = tempfile::tempdir().unwrap();
+        let model_type =
+            EmbeddingModelType::OllamaTextEmbeddingsInference(OllamaTextEmbeddingsInference::SnowflakeArcticEmbedM);
+        let db = Arc::new(SqliteManager::new(dir.path().join("cron.db"), String::new(), model_type).unwrap());
+
+        let mut task = create_test_cron_task("* * * * *");
+        task.policy = CronTaskPolicy {
+            misfire: CronMisfirePolicy::RunAll,
+            retry: CronRetryPolicy {
+                max_attempts: 2,
+                backoff_secs: 0,
+                ..Default::default()
+            },
+            timeout_secs: None,
+        };
+        let task_id = db
+            .add_cron_task(&task.name, None, &task.cron, None, &task.policy, None, &task.action)
+            .unwrap();
+        task.task_id = task_id as i32;
+
+        // Ten minutes of runs were missed, all of them are made up for
+        let now = Utc::now();
+        let missed = CronManager::cron_task_misfired_runs(&db, &task, now + chrono::Duration::minutes(10)).unwrap();
+        assert!((9..=10).contains(&missed.len()));
+        assert!(
+            CronManager::cron_task_misfired_runs(&db, &task, now + chrono::Duration::minutes(10))
+                .unwrap()
+                .is_empty()
+        );
+
+        // Skipped runs are only recorded
+        task.policy.misfire = CronMisfirePolicy::Skip;
+        let missed = CronManager::cron_task_misfired_runs(&db, &task, now + chrono::Duration::minutes(20)).unwrap();
+        assert!(missed.is_empty());
+        let logs = db.get_cron_task_execution_logs(task_id).unwrap();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].status, CronExecutionStatus::Skipped);
+        assert!(logs[0].misfired);
+
+        // Failed attempts are retried up to the limit
+        let attempts = AtomicU32::new(0);
+        let llm_stopper = LLMStopper::new();
+        let result = CronManager::run_cron_task_with_policy(&db, &llm_stopper, &task, Some(now), false, || {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Box::pin(async { Err(CronManagerError::SomeError("Agent unavailable".to_string())) })
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+
+        let logs = db.get_cron_task_execution_logs(task_id).unwrap();
+        assert_eq!(logs.len(), 3);
+        assert_eq!(logs[0].attempt, 2);
+        assert_eq!(logs[0].status, CronExecutionStatus::Failed);
+        assert!(logs[0].error_message.as_deref().unwrap().contains("Agent unavailable"));
+        assert!(logs[0].finished_at.is_some());
+
+        // An attempt only succeeds once the agent answered without an error
+        let attempts = AtomicU32::new(0);
+        let result = CronManager::run_cron_task_with_policy(&db, &llm_stopper, &task, Some(now), false, || {
+            let attempt = attempts.fetch_add(1, Ordering::SeqCst) + 1;
+            let db = db.clone();
+            Box::pin(async move {
+                let message_hash = format!("message_{}", attempt);
+                let (event_type, error) = match attempt {
+                    1 => (WebhookEventType::JobFailed, Some("Rate limited")),
+                    _ => (WebhookEventType::JobCompleted, None),
+                };
+                let payload = json!({ "job_id": "test", "message_id": message_hash, "error": error });
+                emit_webhook_event(&db, event_type, payload);
+                Ok(CronJobMessageSent {
+                    job_id: "test".to_string(),
+                    message_hash,
+                    pipeline_run_id: None,
+                })
+            })
+        })
+        .await;
+        assert!(result.is_ok());
+        let logs = db.get_cron_task_execution_logs(task_id).unwrap();
+        assert_eq!(logs[0].status, CronExecutionStatus::Success);
+        assert_eq!(logs[1].status, CronExecutionStatus::Failed);
+        assert!(logs[1].error_message.as_deref().unwrap().contains("Rate limited"));
+
+        // Past the timeout the message is stopped
+        task.policy.timeout_secs = Some(1);
+        task.policy.retry.max_attempts = 1;
+        let result = CronManager::run_cron_task_with_policy(&db, &llm_stopper, &task, Some(now), false, || {
+            Box::pin(async {
+                Ok(CronJobMessageSent {
+                    job_id: "test".to_string(),
+                    message_hash: "message_3".to_string(),
+                    pipeline_run_id: None,
+                })
+            })
+        })
+        .await;
+        assert!(result.is_err());
+        let logs = db.get_cron_task_execution_logs(task_id).unwrap();
+        assert_eq!(logs[0].status, CronExecutionStatus::TimedOut);
+        assert!(llm_stopper.stopped_messages.contains("message_3"));
+    }
 }
@@ -0,0 +1,305 @@
+use chrono::{DateTime, Duration, Local, NaiveDateTime, TimeZone, Timelike, Utc};
+use chrono_tz::Tz;
+use shinkai_message_primitives::schemas::crontab::{CronTask, CRON_INTERVAL_PREFIX, CRON_RUN_AT_PREFIX};
+
+const CRON_EXAMPLES: &str = "Examples: '*/30 * * * *', '*/10 * * * * *' (with seconds), '@every 15m', \
+                             '@at 2025-03-01T09:00:00'";
+
+/// Timezone a cron expression is evaluated in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CronTimezone {
+    Host,
+    Named(Tz),
+}
+
+impl CronTimezone {
+    pub fn parse(timezone: Option<&str>) -> Result<Self, String> {
+        match timezone.map(str::trim).filter(|timezone| !timezone.is_empty()) {
+            None => Ok(CronTimezone::Host),
+            Some(timezone) => timezone.parse::<Tz>().map(CronTimezone::Named).map_err(|_| {
+                format!(
+                    "Unknown timezone '{}', expected an IANA name such as 'Europe/Berlin'",
+                    timezone
+                )
+            }),
+        }
+    }
+}
+
+/// When a cron task runs, parsed from `CronTask::cron` and `CronTask::timezone`
+#[derive(Debug, Clone, PartialEq)]
+pub enum CronSchedule {
+    /// Standard five field expression evaluated by `cron_parser`, run on each of `seconds`
+    Cron {
+        expression: String,
+        seconds: Vec<u32>,
+        timezone: CronTimezone,
+    },
+    /// Every `every`, counted from `anchor`
+    Interval { every: Duration, anchor: DateTime<Utc> },
+    /// Once, at the given time
+    RunAt(DateTime<Utc>),
+}
+
+impl CronSchedule {
+    /// Parses a schedule. `anchor` is where intervals are counted from, the creation of the task.
+    pub fn parse(cron: &str, timezone: Option<&str>, anchor: DateTime<Utc>) -> Result<Self, String> {
+        let cron = cron.trim();
+        let timezone = CronTimezone::parse(timezone)?;
+
+        if let Some(interval) = cron.strip_prefix(CRON_INTERVAL_PREFIX) {
+            return Ok(CronSchedule::Interval {
+                every: parse_interval(interval.trim())?,
+                anchor,
+            });
+        }
+        if let Some(run_at) = cron.strip_prefix(CRON_RUN_AT_PREFIX) {
+            return Ok(CronSchedule::RunAt(parse_run_at(run_at.trim(), timezone)?));
+        }
+
+        let fields: Vec<&str> = cron.split_whitespace().collect();
+        let (seconds, expression) = match fields.len() {
+            5 => (vec![0], fields.join(" ")),
+            6 => (parse_seconds_field(fields[0])?, fields[1..].join(" ")),
+            count => {
+                return Err(format!(
+                    "Cron expression must have 5 fields (minute hour day month weekday) or 6 with seconds first, \
+                     found {}. {}",
+                    count, CRON_EXAMPLES
+                ))
+            }
+        };
+        cron_parser::parse(&expression, &Utc::now())
+            .map_err(|e| format!("Invalid cron expression '{}': {}. {}", cron, e, CRON_EXAMPLES))?;
+
+        Ok(CronSchedule::Cron {
+            expression,
+            seconds,
+            timezone,
+        })
+    }
+
+    pub fn for_task(task: &CronTask) -> Result<Self, String> {
+        let anchor = DateTime::parse_from_rfc3339(&task.created_at)
+            .map(|created_at| created_at.with_timezone(&Utc))
+            .unwrap_or_else(|_| Utc::now());
+        Self::parse(&task.cron, task.timezone.as_deref(), anchor)
+    }
+
+    /// First run at or after `after`, `None` once a one-shot schedule has passed
+    pub fn next_run(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            CronSchedule::Cron {
+                expression,
+                seconds,
+                timezone,
+            } => match timezone {
+                CronTimezone::Host => next_cron_run(&Local, expression, seconds, after),
+                CronTimezone::Named(tz) => next_cron_run(tz, expression, seconds, after),
+            },
+            CronSchedule::Interval { every, anchor } => {
+                if after <= *anchor {
+                    return Some(*anchor);
+                }
+                let every_ms = every.num_milliseconds();
+                let elapsed_ms = (after - *anchor).num_milliseconds();
+                let periods = (elapsed_ms + every_ms - 1) / every_ms;
+                Some(*anchor + Duration::milliseconds(periods * every_ms))
+            }
+            CronSchedule::RunAt(at) => (*at >= after).then_some(*at),
+        }
+    }
+
+    /// First `limit` runs in `[start, end)`
+    pub fn runs_between(&self, start: DateTime<Utc>, end: DateTime<Utc>, limit: usize) -> Vec<DateTime<Utc>> {
+        let mut runs = Vec::new();
+        let mut cursor = start;
+        while runs.len() < limit {
+            let Some(run) = self.next_run(cursor).filter(|run| *run < end) else {
+                break;
+            };
+            runs.push(run);
+            cursor = run + Duration::milliseconds(1);
+        }
+        runs
+    }
+}
+
+fn next_cron_run<Z: TimeZone>(
+    timezone: &Z,
+    expression: &str,
+    seconds: &[u32],
+    after: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    // Runs are on whole seconds
+    let mut after = after.with_timezone(timezone);
+    if after.nanosecond() > 0 {
+        after = after.with_nanosecond(0)? + Duration::seconds(1);
+    }
+    let minute_start = after.with_second(0)?;
+
+    // cron_parser returns the first matching minute after the minute it is given
+    let minute_matches =
+        cron_parser::parse(expression, &(minute_start.clone() - Duration::seconds(1))).ok()? == minute_start;
+    if minute_matches {
+        if let Some(second) = seconds.iter().find(|second| **second >= after.second()) {
+            return Some((minute_start + Duration::seconds(*second as i64)).with_timezone(&Utc));
+        }
+    }
+    let next_minute = cron_parser::parse(expression, &minute_start).ok()?;
+    Some((next_minute + Duration::seconds(*seconds.first()? as i64)).with_timezone(&Utc))
+}
+
+/// Seconds matched by a cron field: `*`, `5`, `10-20`, `*/15`, `10-40/10` or a comma separated list
+fn parse_seconds_field(field: &str) -> Result<Vec<u32>, String> {
+    let invalid = || format!("Invalid seconds field '{}', expected values between 0 and 59", field);
+    let mut seconds = Vec::new();
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().map_err(|_| invalid())?),
+            None => (part, 1),
+        };
+        let (start, end) = match range {
+            "*" => (0, 59),
+            _ => match range.split_once('-') {
+                Some((start, end)) => (
+                    start.parse::<u32>().map_err(|_| invalid())?,
+                    end.parse::<u32>().map_err(|_| invalid())?,
+                ),
+                None => {
+                    let second = range.parse::<u32>().map_err(|_| invalid())?;
+                    (second, if step > 1 { 59 } else { second })
+                }
+            },
+        };
+        if step == 0 || start > end || end > 59 {
+            return Err(invalid());
+        }
+        seconds.extend((start..=end).step_by(step as usize));
+    }
+    seconds.sort_unstable();
+    seconds.dedup();
+    Ok(seconds)
+}
+
+/// `30s`, `15m`, `2h` or `1d`
+fn parse_interval(interval: &str) -> Result<Duration, String> {
+    let invalid = || {
+        format!(
+            "Invalid interval '{}', expected a positive number followed by s, m, h or d, e.g. '@every 15m'",
+            interval
+        )
+    };
+    let unit_start = interval.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+    let amount: i64 = interval[..unit_start].parse().map_err(|_| invalid())?;
+    if amount <= 0 {
+        return Err(invalid());
+    }
+    match interval[unit_start..].trim() {
+        "s" => Ok(Duration::seconds(amount)),
+        "m" => Ok(Duration::minutes(amount)),
+        "h" => Ok(Duration::hours(amount)),
+        "d" => Ok(Duration::days(amount)),
+        _ => Err(invalid()),
+    }
+}
+
+/// RFC 3339, or a local date and time in the timezone of the task
+fn parse_run_at(run_at: &str, timezone: CronTimezone) -> Result<DateTime<Utc>, String> {
+    if let Ok(at) = DateTime::parse_from_rfc3339(run_at) {
+        return Ok(at.with_timezone(&Utc));
+    }
+    let naive = [
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%dT%H:%M",
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%d %H:%M",
+    ]
+    .iter()
+    .find_map(|format| NaiveDateTime::parse_from_str(run_at, format).ok())
+    .ok_or_else(|| format!("Invalid time '{}', expected e.g. '@at 2025-03-01T09:00:00'", run_at))?;
+    let at = match timezone {
+        CronTimezone::Host => Local
+            .from_local_datetime(&naive)
+            .earliest()
+            .map(|at| at.with_timezone(&Utc)),
+        CronTimezone::Named(tz) => tz
+            .from_local_datetime(&naive)
+            .earliest()
+            .map(|at| at.with_timezone(&Utc)),
+    };
+    at.ok_or_else(|| format!("'{}' does not exist in the timezone of the task", run_at))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(time: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(time).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_cron_schedules() {
+        let anchor = utc("2024-01-01T00:00:00Z");
+
+        // Seconds come first in six field expressions
+        let schedule = CronSchedule::parse("*/15 * * * * *", Some("UTC"), anchor).unwrap();
+        assert_eq!(
+            schedule.next_run(utc("2024-07-01T10:00:07Z")),
+            Some(utc("2024-07-01T10:00:15Z"))
+        );
+        assert_eq!(
+            schedule.next_run(utc("2024-07-01T10:00:50Z")),
+            Some(utc("2024-07-01T10:01:00Z"))
+        );
+        assert_eq!(
+            schedule.runs_between(utc("2024-07-01T10:00:00Z"), utc("2024-07-01T10:01:00Z"), usize::MAX),
+            vec![
+                utc("2024-07-01T10:00:00Z"),
+                utc("2024-07-01T10:00:15Z"),
+                utc("2024-07-01T10:00:30Z"),
+                utc("2024-07-01T10:00:45Z"),
+            ]
+        );
+        assert_eq!(
+            schedule.runs_between(utc("2024-07-01T10:00:00Z"), utc("2024-07-01T11:00:00Z"), 2),
+            vec![utc("2024-07-01T10:00:00Z"), utc("2024-07-01T10:00:15Z")]
+        );
+
+        // Evaluated in the timezone of the task, across daylight saving time
+        let schedule = CronSchedule::parse("0 9 * * *", Some("America/New_York"), anchor).unwrap();
+        assert_eq!(
+            schedule.next_run(utc("2024-07-01T12:00:00Z")),
+            Some(utc("2024-07-01T13:00:00Z"))
+        );
+        assert_eq!(
+            schedule.next_run(utc("2024-12-01T12:00:00Z")),
+            Some(utc("2024-12-01T14:00:00Z"))
+        );
+
+        // Intervals are counted from the creation of the task
+        let schedule = CronSchedule::parse("@every 15m", None, anchor).unwrap();
+        assert_eq!(
+            schedule.next_run(utc("2024-01-01T01:07:00Z")),
+            Some(utc("2024-01-01T01:15:00Z"))
+        );
+
+        // One-shot schedules run once
+        let schedule = CronSchedule::parse("@at 2024-03-01T09:00:00", Some("Asia/Tokyo"), anchor).unwrap();
+        assert_eq!(schedule, CronSchedule::RunAt(utc("2024-03-01T00:00:00Z")));
+        assert_eq!(schedule.next_run(utc("2024-03-01T00:00:01Z")), None);
+
+        for invalid in [
+            "* * * *",
+            "60 24 32 13 8",
+            "*/0 * * * * *",
+            "@every 0m",
+            "@every 5w",
+            "@at tomorrow",
+        ] {
+            assert!(CronSchedule::parse(invalid, None, anchor).is_err(), "{}", invalid);
+        }
+        assert!(CronSchedule::parse("* * * * *", Some("Mars/Olympus"), anchor).is_err());
+    }
+}
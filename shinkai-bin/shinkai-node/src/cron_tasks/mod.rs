@@ -1 +1,4 @@
+pub mod cron_event_triggers;
 pub mod cron_manager;
+pub mod cron_schedule;
+pub mod scheduled_job_messages;
@@ -0,0 +1,132 @@
+use std::{
+    sync::{Arc, Weak}, time::Duration
+};
+
+use chrono::Utc;
+use ed25519_dalek::SigningKey;
+use shinkai_message_primitives::schemas::shinkai_name::ShinkaiName;
+use shinkai_message_primitives::shinkai_utils::shinkai_logging::{shinkai_log, ShinkaiLogLevel, ShinkaiLogOption};
+use shinkai_sqlite::{errors::SqliteManagerError, SqliteManager};
+use tokio::sync::Mutex;
+use x25519_dalek::{PublicKey as EncryptionPublicKey, StaticSecret as EncryptionStaticKey};
+
+use super::cron_manager::CronManager;
+use crate::{llm_provider::job_manager::JobManager, managers::IdentityManager};
+
+/// How often due scheduled job messages are picked up
+const SCHEDULED_JOB_MESSAGE_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+impl CronManager {
+    /// Sends the scheduled job messages once they are due. Messages that came due while the node was
+    /// stopped are sent on startup. A message is removed once sent, or kept as failed.
+    pub fn process_scheduled_job_messages(
+        db: Weak<SqliteManager>,
+        node_profile_name: ShinkaiName,
+        identity_sk: SigningKey,
+        job_manager: Arc<Mutex<JobManager>>,
+        identity_manager: Arc<Mutex<IdentityManager>>,
+        node_encryption_sk: EncryptionStaticKey,
+        node_encryption_pk: EncryptionPublicKey,
+    ) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            // Messages that were being sent when the node stopped never reached their job
+            if let Some(db_arc) = db.upgrade() {
+                if let Err(e) = db_arc.release_sending_scheduled_job_messages() {
+                    shinkai_log(
+                        ShinkaiLogOption::CronExecution,
+                        ShinkaiLogLevel::Error,
+                        format!("Failed to release the scheduled job messages being sent: {}", e).as_str(),
+                    );
+                }
+            }
+
+            loop {
+                let Some(db_arc) = db.upgrade() else {
+                    shinkai_log(
+                        ShinkaiLogOption::CronExecution,
+                        ShinkaiLogLevel::Error,
+                        "Failed to upgrade Weak reference to SqliteManager. Exiting scheduled job message loop.",
+                    );
+                    return;
+                };
+
+                let due = match db_arc.get_due_scheduled_job_messages(Utc::now()) {
+                    Ok(due) => due,
+                    Err(e) => {
+                        shinkai_log(
+                            ShinkaiLogOption::CronExecution,
+                            ShinkaiLogLevel::Error,
+                            format!("Failed to read the scheduled job messages: {}", e).as_str(),
+                        );
+                        Vec::new()
+                    }
+                };
+
+                for scheduled in due {
+                    // Claimed before sending so that a message cancelled in the meantime is not sent
+                    match db_arc.claim_scheduled_job_message(&scheduled.id) {
+                        Ok(true) => {}
+                        Ok(false) => continue,
+                        Err(e) => {
+                            shinkai_log(
+                                ShinkaiLogOption::CronExecution,
+                                ShinkaiLogLevel::Error,
+                                format!("Failed to claim scheduled job message {}: {}", scheduled.id, e).as_str(),
+                            );
+                            continue;
+                        }
+                    }
+
+                    let sent = Self::send_job_message_with_bearer(
+                        db_arc.clone(),
+                        node_profile_name.clone(),
+                        identity_manager.clone(),
+                        job_manager.clone(),
+                        scheduled.job_message,
+                        node_encryption_sk.clone(),
+                        node_encryption_pk,
+                        identity_sk.clone(),
+                    )
+                    .await;
+                    match sent {
+                        Ok(sent) => {
+                            shinkai_log(
+                                ShinkaiLogOption::CronExecution,
+                                ShinkaiLogLevel::Info,
+                                format!("Sent scheduled job message {} to job {}", scheduled.id, sent.job_id).as_str(),
+                            );
+                            match db_arc.remove_scheduled_job_message(&scheduled.id) {
+                                Ok(()) | Err(SqliteManagerError::DataNotFound) => {}
+                                Err(e) => shinkai_log(
+                                    ShinkaiLogOption::CronExecution,
+                                    ShinkaiLogLevel::Error,
+                                    format!("Failed to remove sent scheduled job message {}: {}", scheduled.id, e)
+                                        .as_str(),
+                                ),
+                            }
+                        }
+                        Err(e) => {
+                            shinkai_log(
+                                ShinkaiLogOption::CronExecution,
+                                ShinkaiLogLevel::Error,
+                                format!("Failed to send scheduled job message {}: {}", scheduled.id, e).as_str(),
+                            );
+                            // Kept as failed so the user can see why it was not sent and cancel it
+                            if let Err(e) = db_arc.fail_scheduled_job_message(&scheduled.id, &e.to_string()) {
+                                shinkai_log(
+                                    ShinkaiLogOption::CronExecution,
+                                    ShinkaiLogLevel::Error,
+                                    format!("Failed to mark scheduled job message {} as failed: {}", scheduled.id, e)
+                                        .as_str(),
+                                );
+                            }
+                        }
+                    }
+                }
+
+                drop(db_arc);
+                tokio::time::sleep(SCHEDULED_JOB_MESSAGE_POLL_INTERVAL).await;
+            }
+        })
+    }
+}
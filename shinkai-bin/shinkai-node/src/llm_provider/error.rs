@@ -85,7 +85,17 @@ pub enum LLMProviderError {
     MessageTooLargeForLLM { max_tokens: usize, used_tokens: usize },
     SomeError(String),
     APIError(String),
+    /// An error answered by the provider with an HTTP error status
+    APIStatusError {
+        status: u16,
+        message: String,
+    },
     DatabaseError(String),
+    /// The job was stopped, with the output produced until then
+    Cancelled(String),
+    /// The message failed with a transient error and was queued again, to be retried after the
+    /// backoff
+    RetryScheduled(std::time::Duration),
 }
 
 impl fmt::Display for LLMProviderError {
@@ -179,7 +189,10 @@ impl fmt::Display for LLMProviderError {
             },
             LLMProviderError::SomeError(s) => write!(f, "{}", s),
             LLMProviderError::APIError(s) => write!(f, "{}", s),
+            LLMProviderError::APIStatusError { message, .. } => write!(f, "{}", message),
             LLMProviderError::DatabaseError(s) => write!(f, "{}", s),
+            LLMProviderError::Cancelled(_) => write!(f, "Job cancelled"),
+            LLMProviderError::RetryScheduled(backoff) => write!(f, "Job message retried in {:?}", backoff),
         }
     }
 }
@@ -261,11 +274,33 @@ impl LLMProviderError {
             LLMProviderError::MessageTooLargeForLLM { .. } => "MessageTooLargeForLLM",
             LLMProviderError::SomeError(_) => "SomeError",
             LLMProviderError::APIError(_) => "APIError",
+            LLMProviderError::APIStatusError { .. } => "APIStatusError",
             LLMProviderError::DatabaseError(_) => "DatabaseError",
+            LLMProviderError::Cancelled(_) => "Cancelled",
+            LLMProviderError::RetryScheduled(_) => "RetryScheduled",
         };
 
         format!("Error {} with message: {}", error_name, self)
     }
+
+    /// Whether the provider may answer if the same request is sent again later
+    pub fn is_transient(&self) -> bool {
+        match self {
+            LLMProviderError::ReqwestError(err) => {
+                err.is_timeout()
+                    || err.is_connect()
+                    || err.status().is_some_and(|status| {
+                        status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+                    })
+            }
+            LLMProviderError::NetworkError(_)
+            | LLMProviderError::LLMServiceInferenceLimitReached(_)
+            | LLMProviderError::ShinkaiBackendAIProviderError(_) => true,
+            LLMProviderError::ShinkaiBackendUnexpectedStatusCode(code) => *code == 429 || *code >= 500,
+            LLMProviderError::APIStatusError { status, .. } => *status == 429 || *status >= 500,
+            _ => false,
+        }
+    }
 }
 
 impl From<AnyhowError> for LLMProviderError {
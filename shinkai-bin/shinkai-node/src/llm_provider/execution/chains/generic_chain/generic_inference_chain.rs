@@ -1,10 +1,13 @@
 use crate::llm_provider::error::LLMProviderError;
 use crate::llm_provider::execution::chains::inference_chain_trait::{
-    InferenceChain, InferenceChainContext, InferenceChainContextTrait, InferenceChainResult
+    FunctionCall, InferenceChain, InferenceChainContext, InferenceChainContextTrait, InferenceChainResult,
+    LLMInferenceResponse
 };
 use crate::llm_provider::execution::prompts::general_prompts::JobPromptGenerator;
 use crate::llm_provider::execution::user_message_parser::ParsedUserMessage;
+use crate::llm_provider::job_budget::{JobBudget, JobBudgetExceeded, JobBudgetUsage};
 use crate::llm_provider::job_callback_manager::JobCallbackManager;
+use crate::llm_provider::job_checkpoint::ChainCheckpoint;
 use crate::llm_provider::job_manager::JobManager;
 use crate::llm_provider::llm_stopper::LLMStopper;
 use crate::managers::model_capabilities_manager::ModelCapabilitiesManager;
@@ -16,6 +19,7 @@ use shinkai_fs::shinkai_file_manager::ShinkaiFileManager;
 
 use crate::utils::environment::{fetch_node_environment, NodeEnvironment};
 use async_trait::async_trait;
+use chrono::Utc;
 use shinkai_embedding::embedding_generator::RemoteEmbeddingGenerator;
 use shinkai_fs::shinkai_fs_error::ShinkaiFsError;
 use shinkai_message_primitives::schemas::inbox_name::InboxName;
@@ -30,6 +34,7 @@ use shinkai_message_primitives::shinkai_message::shinkai_message_schemas::WSTopi
 use shinkai_message_primitives::shinkai_utils::job_scope::MinimalJobScope;
 use shinkai_message_primitives::shinkai_utils::shinkai_logging::{shinkai_log, ShinkaiLogLevel, ShinkaiLogOption};
 use shinkai_message_primitives::shinkai_utils::shinkai_path::ShinkaiPath;
+use shinkai_message_primitives::shinkai_utils::utils::count_tokens_from_message_llama3;
 use shinkai_sqlite::SqliteManager;
 
 use std::fmt;
@@ -468,15 +473,26 @@ impl GenericInferenceChain {
                 if let ProviderOrAgent::Agent(agent) = &llm_provider {
                     for tool in &agent.tools {
                         if let Some(tool_router) = &tool_router {
-                            match tool_router
-                                .get_tool_by_name_and_version(&tool.to_string_without_version(), tool.version())
-                                .await
-                            {
+                            // Pinned revisions are resolved from the tool history so later edits
+                            // to the tool don't affect this agent
+                            let tool_result = match tool.revision {
+                                Some(revision) => {
+                                    tool_router
+                                        .get_tool_by_revision(&tool.to_string_without_version(), revision)
+                                        .await
+                                }
+                                None => {
+                                    tool_router
+                                        .get_tool_by_name_and_version(&tool.to_string_without_version(), tool.version())
+                                        .await
+                                }
+                            };
+                            match tool_result {
                                 Ok(Some(tool)) => tools.push(tool),
                                 Ok(None) => {
                                     return Err(LLMProviderError::ToolNotFound(format!(
                                         "Tool not found for name: {}",
-                                        tool.to_string_with_version()
+                                        tool.to_string_with_revision()
                                     )));
                                 }
                                 Err(e) => {
@@ -610,8 +626,17 @@ impl GenericInferenceChain {
             additional_files
         );
 
+        // An interrupted or retried run of this message continues from its last checkpoint. The
+        // checkpoint is saved from the start, so that the budgets count from the first attempt.
+        let mut checkpoint = ChainCheckpoint::load(&db, &full_job.job_id, &message_hash_id).unwrap_or_default();
+        if checkpoint.started_at.is_none() {
+            checkpoint.started_at = Some(Utc::now());
+            checkpoint.save(&db, &full_job.job_id, &message_hash_id);
+        }
+        let budget_start = start_time.checked_sub(checkpoint.elapsed()).unwrap_or(start_time);
+
         // We'll keep a record of *every* function call + response across all iterations:
-        let mut all_function_responses = Vec::new();
+        let mut all_function_responses = checkpoint.function_responses.clone();
 
         let mut filled_prompt = JobPromptGenerator::generic_inference_prompt(
             db.clone(),
@@ -623,32 +648,39 @@ impl GenericInferenceChain {
             None,
             Some(full_job.step_history.clone()),
             tools.clone(),
-            None,
+            Some(all_function_responses.clone()).filter(|responses| !responses.is_empty()),
             full_job.job_id.clone(),
             additional_files.clone(),
         )
         .await;
 
-        let mut iteration_count = 0;
-        let mut tool_calls_history = Vec::new();
+        let mut iteration_count = checkpoint.iteration;
+        let mut tool_calls_history = checkpoint.tool_calls_history.clone();
+        let stop_key = full_job.conversation_inbox_name.get_value();
+        let budget = JobBudget::new(&JobBudget::job_config(&full_job, &llm_provider), max_iterations);
+        // Text the LLM wrote along with its last function calls, kept for a partial answer
+        let mut partial_answer = checkpoint.partial_answer.clone();
         loop {
-            // Check if max_iterations is reached
-            if iteration_count >= max_iterations {
-                let answer_duration_ms = Some(format!("{:.2}", start_time.elapsed().as_millis()));
-                let max_iterations_message = format!(
-                    "Maximum iterations ({}) reached. Process stopped after {} tool calls.",
-                    max_iterations,
-                    tool_calls_history.len()
-                );
-
-                let inference_result = InferenceChainResult::with_full_details(
-                    max_iterations_message,
-                    None,
-                    answer_duration_ms,
-                    Some(tool_calls_history.clone()),
-                );
+            // A stopped job does not start another iteration
+            if llm_stopper.is_cancelled(&stop_key) {
+                return Err(LLMProviderError::Cancelled(String::new()));
+            }
 
-                return Ok(inference_result);
+            // Check if one of the budgets is reached
+            let usage = JobBudgetUsage {
+                iterations: iteration_count,
+                elapsed: budget_start.elapsed(),
+                total_tokens: checkpoint.total_tokens,
+                tool_calls: tool_calls_history.len() as u64,
+            };
+            if let Some(exceeded) = budget.check(&usage) {
+                return Ok(Self::budget_exceeded_result(
+                    &full_job.job_id,
+                    exceeded,
+                    &partial_answer,
+                    &tool_calls_history,
+                    start_time,
+                ));
             }
 
             // 4) Call LLM
@@ -657,16 +689,53 @@ impl GenericInferenceChain {
                 Ok(name) => Some(name),
                 Err(_) => None,
             };
-            let response_res = JobManager::inference_with_llm_provider(
-                llm_provider.clone(),
-                filled_prompt.clone(),
-                inbox_name,
-                ws_manager_trait.clone(),
-                job_config.cloned(),
-                llm_stopper.clone(),
-                db.clone(),
-            )
-            .await;
+            let response_res = if checkpoint.pending_function_calls.is_empty() {
+                let inference = JobManager::inference_with_llm_provider(
+                    llm_provider.clone(),
+                    filled_prompt.clone(),
+                    inbox_name,
+                    ws_manager_trait.clone(),
+                    job_config.cloned(),
+                    llm_stopper.clone(),
+                    db.clone(),
+                );
+                let response_res = match budget.remaining_duration(budget_start.elapsed()) {
+                    Some(remaining) => match tokio::time::timeout(remaining, inference).await {
+                        Ok(response_res) => response_res,
+                        Err(_) => {
+                            return Ok(Self::budget_exceeded_result(
+                                &full_job.job_id,
+                                JobBudgetExceeded::Duration(budget.max_duration.unwrap_or_default()),
+                                &partial_answer,
+                                &tool_calls_history,
+                                start_time,
+                            ));
+                        }
+                    },
+                    None => inference.await,
+                };
+                if let Ok(response) = &response_res {
+                    let prompt_tokens: usize = filled_prompt
+                        .sub_prompts
+                        .iter()
+                        .map(|sub_prompt| {
+                            sub_prompt
+                                .count_tokens_as_completion_message(ModelCapabilitiesManager::num_tokens_from_llama3)
+                        })
+                        .sum();
+                    let response_tokens = count_tokens_from_message_llama3(&response.response_string);
+                    checkpoint.total_tokens += (prompt_tokens + response_tokens) as u64;
+                }
+                response_res
+            } else {
+                // The LLM asked for these calls before the interruption
+                Ok(LLMInferenceResponse::new(
+                    String::new(),
+                    serde_json::Value::Null,
+                    checkpoint.pending_function_calls.clone(),
+                    None,
+                ))
+            };
 
             // Error Codes
             if let Err(LLMProviderError::LLMServiceInferenceLimitReached(e)) = &response_res {
@@ -677,11 +746,26 @@ impl GenericInferenceChain {
 
             let response = response_res?;
 
+            // Streaming providers answer with the text received before the stop
+            if llm_stopper.is_cancelled(&stop_key) {
+                return Err(LLMProviderError::Cancelled(response.response_string));
+            }
+
             // 5) Check response if it requires a function call
             if !response.is_function_calls_empty() {
+                if !response.response_string.trim().is_empty() {
+                    partial_answer = response.response_string.clone();
+                }
                 let mut iteration_function_responses = Vec::new();
                 let mut should_retry = false;
 
+                checkpoint.iteration = iteration_count;
+                checkpoint.tool_calls_history = tool_calls_history.clone();
+                checkpoint.function_responses = all_function_responses.clone();
+                checkpoint.pending_function_calls = response.function_calls.clone();
+                checkpoint.partial_answer = partial_answer.clone();
+                checkpoint.save(&db, &full_job.job_id, &message_hash_id);
+
                 for function_call in response.function_calls {
                     let parsed_message = ParsedUserMessage::new(user_message.clone());
                     let image_files = HashMap::new();
@@ -726,12 +810,53 @@ impl GenericInferenceChain {
 
                     // Note: here we can add logic to handle the case that we have network tools
                     // TODO: if shinkai_tool is None we need to retry with the LLM (hallucination)
-                    let function_response = match tool_router
-                        .as_ref()
-                        .unwrap()
-                        .call_function(function_call.clone(), &context, &shinkai_tool, user_profile.clone())
-                        .await
-                    {
+                    // A call that completed before the interruption is not run again
+                    let completed_response = checkpoint.completed_response(&function_call);
+                    let is_completed = completed_response.is_some();
+                    let call_result = match completed_response {
+                        Some(response) => Ok(response),
+                        None => {
+                            // The remaining calls of the iteration count against the budgets as well
+                            let usage = JobBudgetUsage {
+                                iterations: iteration_count,
+                                elapsed: budget_start.elapsed(),
+                                total_tokens: checkpoint.total_tokens,
+                                tool_calls: tool_calls_history.len() as u64,
+                            };
+                            if let Some(exceeded) = budget.check(&usage) {
+                                return Ok(Self::budget_exceeded_result(
+                                    &full_job.job_id,
+                                    exceeded,
+                                    &partial_answer,
+                                    &tool_calls_history,
+                                    start_time,
+                                ));
+                            }
+
+                            let call = tool_router.as_ref().unwrap().call_function(
+                                function_call.clone(),
+                                &context,
+                                &shinkai_tool,
+                                user_profile.clone(),
+                            );
+                            match budget.remaining_duration(budget_start.elapsed()) {
+                                Some(remaining) => match tokio::time::timeout(remaining, call).await {
+                                    Ok(call_result) => call_result,
+                                    Err(_) => {
+                                        return Ok(Self::budget_exceeded_result(
+                                            &full_job.job_id,
+                                            JobBudgetExceeded::Duration(budget.max_duration.unwrap_or_default()),
+                                            &partial_answer,
+                                            &tool_calls_history,
+                                            start_time,
+                                        ));
+                                    }
+                                },
+                                None => call.await,
+                            }
+                        }
+                    };
+                    let function_response = match call_result {
                         Ok(response) => response,
                         Err(e) => {
                             match &e {
@@ -802,6 +927,11 @@ impl GenericInferenceChain {
                     function_call_with_router_key.response = Some(function_response.response.clone());
                     tool_calls_history.push(function_call_with_router_key);
 
+                    if !is_completed {
+                        checkpoint.completed_function_calls.push(function_response.clone());
+                        checkpoint.save(&db, &full_job.job_id, &message_hash_id);
+                    }
+
                     // Trigger WS update after receiving function_response
                     Self::trigger_ws_update(
                         &ws_manager_trait,
@@ -825,12 +955,24 @@ impl GenericInferenceChain {
 
                 // If we need to retry, continue the outer loop
                 if should_retry {
+                    checkpoint.iteration = iteration_count;
+                    checkpoint.tool_calls_history = tool_calls_history.clone();
+                    checkpoint.pending_function_calls.clear();
+                    checkpoint.completed_function_calls.clear();
+                    checkpoint.save(&db, &full_job.job_id, &message_hash_id);
                     continue;
                 }
 
                 // Add this iteration's responses to our cumulative collection
                 all_function_responses.extend(iteration_function_responses);
 
+                checkpoint.iteration = iteration_count + 1;
+                checkpoint.tool_calls_history = tool_calls_history.clone();
+                checkpoint.function_responses = all_function_responses.clone();
+                checkpoint.pending_function_calls.clear();
+                checkpoint.completed_function_calls.clear();
+                checkpoint.save(&db, &full_job.job_id, &message_hash_id);
+
                 // Call LLM again with ALL responses from all iterations
                 filled_prompt = JobPromptGenerator::generic_inference_prompt(
                     db.clone(),
@@ -866,6 +1008,29 @@ impl GenericInferenceChain {
         }
     }
 
+    /// Partial answer of a chain that ran out of one of its budgets
+    fn budget_exceeded_result(
+        job_id: &str,
+        exceeded: JobBudgetExceeded,
+        partial_answer: &str,
+        tool_calls_history: &[FunctionCall],
+        start_time: Instant,
+    ) -> InferenceChainResult {
+        shinkai_log(
+            ShinkaiLogOption::JobExecution,
+            ShinkaiLogLevel::Info,
+            &format!("Job {} stopped by its budget: {:?}", job_id, exceeded),
+        );
+        let answer_duration_ms = Some(format!("{:.2}", start_time.elapsed().as_millis()));
+
+        InferenceChainResult::with_full_details(
+            exceeded.answer(partial_answer, tool_calls_history.len()),
+            None,
+            answer_duration_ms,
+            Some(tool_calls_history.to_vec()),
+        )
+    }
+
     /// Triggers a WebSocket update after receiving a function response.
     async fn trigger_ws_update(
         ws_manager_trait: &Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
@@ -3,6 +3,7 @@ use super::inference_chain_trait::{InferenceChain, InferenceChainContext, Infere
 use super::sheet_ui_chain::sheet_ui_inference_chain::SheetUIInferenceChain;
 use crate::llm_provider::error::LLMProviderError;
 use crate::llm_provider::execution::user_message_parser::ParsedUserMessage;
+use crate::llm_provider::job_budget::JobBudget;
 use crate::llm_provider::job_callback_manager::JobCallbackManager;
 use crate::llm_provider::job_manager::JobManager;
 use crate::llm_provider::llm_stopper::LLMStopper;
@@ -65,7 +66,7 @@ impl JobManager {
 
         // Get max_iterations from preferences, default to 10 if not found
         // Try first as u64, then as String (in case it's stored as a string)
-        let max_iterations = match db.get_preference::<u64>("max_iterations") {
+        let default_max_iterations = match db.get_preference::<u64>("max_iterations") {
             Ok(Some(value)) => value,
             _ => {
                 // If it fails or is None, try as string and parse
@@ -75,6 +76,10 @@ impl JobManager {
                 }
             }
         };
+        // The job or its agent can set their own
+        let max_iterations = JobBudget::job_config(&full_job, &llm_provider)
+            .max_iterations
+            .unwrap_or(default_max_iterations);
 
         // Create the inference chain context
         let chain_context = InferenceChainContext::new(
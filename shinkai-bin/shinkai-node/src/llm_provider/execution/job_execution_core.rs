@@ -1,7 +1,9 @@
 use crate::llm_provider::error::LLMProviderError;
 use crate::llm_provider::execution::chains::inference_chain_trait::InferenceChainResult;
 use crate::llm_provider::job_callback_manager::JobCallbackManager;
+use crate::llm_provider::job_checkpoint::{ChainCheckpoint, JobResumePolicy};
 use crate::llm_provider::job_manager::JobManager;
+use crate::llm_provider::job_retry_policy::JobRetryPolicy;
 use crate::llm_provider::llm_stopper::LLMStopper;
 
 use crate::managers::model_capabilities_manager::{ModelCapabilitiesManager, ModelCapability};
@@ -9,14 +11,19 @@ use crate::managers::sheet_manager::SheetManager;
 use crate::managers::tool_router::ToolRouter;
 use crate::network::agent_payments_manager::external_agent_offerings_manager::ExtAgentOfferingsManager;
 use crate::network::agent_payments_manager::my_agent_offerings_manager::MyAgentOfferingsManager;
+use crate::network::webhook_dispatcher::emit_webhook_event;
+use chrono::Utc;
 use ed25519_dalek::SigningKey;
+use serde_json::json;
 
 use shinkai_embedding::embedding_generator::RemoteEmbeddingGenerator;
 use shinkai_fs::shinkai_file_manager::ShinkaiFileManager;
 use shinkai_job_queue_manager::job_queue_manager::{JobForProcessing, JobQueueManager};
 use shinkai_message_primitives::schemas::job::{Job, JobLike};
+use shinkai_message_primitives::schemas::job_dead_letter::JobDeadLetter;
 use shinkai_message_primitives::schemas::llm_providers::common_agent_llm_provider::ProviderOrAgent;
 use shinkai_message_primitives::schemas::sheet::WorkflowSheetJobData;
+use shinkai_message_primitives::schemas::webhook::WebhookEventType;
 use shinkai_message_primitives::schemas::ws_types::WSUpdateHandler;
 use shinkai_message_primitives::shinkai_message::shinkai_message_schemas::{CallbackAction, MessageMetadata};
 use shinkai_message_primitives::shinkai_utils::shinkai_logging::{shinkai_log, ShinkaiLogLevel, ShinkaiLogOption};
@@ -34,6 +41,15 @@ use tokio::sync::Mutex;
 impl JobManager {
     /// Processes a job message which will trigger a job step
     #[allow(clippy::too_many_arguments)]
+    #[tracing::instrument(
+        name = "job",
+        skip_all,
+        fields(
+            job_id = %job_message.job_message.job_id,
+            message_hash = job_message.message_hash_id.as_deref().unwrap_or_default(),
+            agent = tracing::field::Empty,
+        )
+    )]
     pub async fn process_job_message_queued(
         job_message: JobForProcessing,
         db: Weak<SqliteManager>,
@@ -52,6 +68,7 @@ impl JobManager {
     ) -> Result<String, LLMProviderError> {
         let db = db.upgrade().ok_or("Failed to upgrade shinkai_db").unwrap();
         let job_id = job_message.job_message.job_id.clone();
+        let message_hash_id = job_message.message_hash_id.clone();
         shinkai_log(
             ShinkaiLogOption::JobExecution,
             ShinkaiLogLevel::Info,
@@ -62,8 +79,22 @@ impl JobManager {
         let fetch_data_result = JobManager::fetch_relevant_job_data(&job_message.job_message.job_id, db.clone()).await;
         let (mut full_job, llm_provider_found, _, user_profile) = match fetch_data_result {
             Ok(data) => data,
-            Err(e) => return Self::handle_error(&db, None, &job_id, &identity_secret_key, e, ws_manager).await,
+            Err(e) => {
+                return Self::handle_error(
+                    &db,
+                    None,
+                    &job_id,
+                    message_hash_id.as_deref(),
+                    &identity_secret_key,
+                    e,
+                    ws_manager,
+                )
+                .await
+            }
         };
+        if let Some(llm_provider) = &llm_provider_found {
+            tracing::Span::current().record("agent", llm_provider.get_id());
+        }
 
         // Ensure the user profile exists before proceeding with inference chain
         let user_profile = match user_profile {
@@ -73,6 +104,7 @@ impl JobManager {
                     &db,
                     None,
                     &job_id,
+                    message_hash_id.as_deref(),
                     &identity_secret_key,
                     LLMProviderError::NoUserProfileFound,
                     ws_manager,
@@ -111,12 +143,19 @@ impl JobManager {
             return Ok(job_id);
         }
 
+        // A pipeline step that fails before its answer is saved still has to be recorded
+        let pipeline_step = match job_message.job_message.callback.as_deref() {
+            Some(CallbackAction::JobPipelineStep(action)) => Some(action.clone()),
+            _ => None,
+        };
+
         // Otherwise proceed forward with rest of logic.
         let inference_chain_result = JobManager::process_inference_chain(
             db.clone(),
             clone_signature_secret_key(&identity_secret_key),
-            job_message.job_message,
+            job_message.job_message.clone(),
             job_message.message_hash_id.clone(),
+            job_message.failed_attempts,
             full_job,
             llm_provider_found.clone(),
             user_profile.clone(),
@@ -132,8 +171,51 @@ impl JobManager {
         )
         .await;
 
+        // The message stays first in the queue of its job, while the worker and the limits of the
+        // job are free for other messages until the retry is due
+        if let Err(LLMProviderError::RetryScheduled(backoff)) = inference_chain_result {
+            let retry = job_message.retried_after(backoff);
+            if let Err(e) = job_queue_manager.lock().await.replace_first(&job_id, retry).await {
+                return Self::handle_error(
+                    &db,
+                    Some(user_profile),
+                    &job_id,
+                    message_hash_id.as_deref(),
+                    &identity_secret_key,
+                    e.into(),
+                    ws_manager,
+                )
+                .await;
+            }
+            return Err(LLMProviderError::RetryScheduled(backoff));
+        }
+
         if let Err(e) = inference_chain_result {
-            return Self::handle_error(&db, Some(user_profile), &job_id, &identity_secret_key, e, ws_manager).await;
+            if let Some(action) = &pipeline_step {
+                let error = e.to_string();
+                if let Err(e) = job_callback_manager
+                    .lock()
+                    .await
+                    .handle_job_pipeline_step_callback(db.clone(), action, Err(&error))
+                    .await
+                {
+                    shinkai_log(
+                        ShinkaiLogOption::JobExecution,
+                        ShinkaiLogLevel::Error,
+                        &format!("Failed to advance job pipeline run {}: {}", action.run_id, e),
+                    );
+                }
+            }
+            return Self::handle_error(
+                &db,
+                Some(user_profile),
+                &job_id,
+                message_hash_id.as_deref(),
+                &identity_secret_key,
+                e,
+                ws_manager,
+            )
+            .await;
         }
 
         Ok(job_id)
@@ -144,6 +226,7 @@ impl JobManager {
         db: &Arc<SqliteManager>,
         user_profile: Option<ShinkaiName>,
         job_id: &str,
+        message_hash_id: Option<&str>,
         identity_secret_key: &SigningKey,
         error: LLMProviderError,
         ws_manager: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
@@ -177,6 +260,12 @@ impl JobManager {
             .await
             .expect("Failed to add error message to job inbox");
 
+        emit_webhook_event(
+            db,
+            WebhookEventType::JobFailed,
+            json!({ "job_id": job_id, "message_id": message_hash_id, "error": error.to_string() }),
+        );
+
         Err(error)
     }
 
@@ -188,6 +277,7 @@ impl JobManager {
         identity_secret_key: SigningKey,
         job_message: JobMessage,
         message_hash_id: Option<String>,
+        failed_attempts: u32,
         full_job: Job,
         llm_provider_found: Option<ProviderOrAgent>,
         user_profile: ShinkaiName,
@@ -201,6 +291,7 @@ impl JobManager {
         llm_stopper: Arc<LLMStopper>,
     ) -> Result<(), LLMProviderError> {
         let job_id = full_job.job_id().to_string();
+        let agent_id = full_job.parent_agent_or_llm_provider_id.clone();
         shinkai_log(
             ShinkaiLogOption::JobExecution,
             ShinkaiLogLevel::Debug,
@@ -239,28 +330,118 @@ impl JobManager {
 
         let start = Instant::now();
 
+        // Stopping the job drops the chain, along with its in-flight requests and tool processes. A
+        // stop while the message waited for its retry cancels the retry too.
+        let stop_key = full_job.conversation_inbox_name.get_value();
+        let cancellation_token = if failed_attempts > 0 && llm_stopper.is_cancelled(&stop_key) {
+            llm_stopper.cancellation_token(&stop_key)
+        } else {
+            llm_stopper.start(&stop_key, message_hash_id.as_deref())
+        };
+
+        // A checkpoint already there means a restart interrupted this message, unless the
+        // checkpoint was kept for a retry
+        let interrupted = failed_attempts == 0
+            && db
+                .get_job_checkpoint(&job_id)
+                .ok()
+                .flatten()
+                .is_some_and(|checkpoint| checkpoint.message_hash_id == message_hash_id);
+
         // Call the inference chain router to choose which chain to use, and call it
-        let (inference_response, inference_response_content) = match JobManager::inference_chain_router(
-            db.clone(),
-            llm_provider_found,
-            full_job,
-            job_message.clone(),
-            message_hash_id,
-            image_files.clone(),
-            generator,
-            user_profile.clone(),
-            ws_manager.clone(),
-            tool_router.clone(),
-            sheet_manager.clone(),
-            my_agent_payments_manager.clone(),
-            ext_agent_payments_manager.clone(),
-            job_callback_manager.clone(),
-            // sqlite_logger.clone(),
-            llm_stopper.clone(),
-        )
-        .await
-        {
-            Ok(response) => (response.clone(), response.response),
+        let inference_result = if interrupted && JobResumePolicy::from_env() == JobResumePolicy::Fail {
+            Err(LLMProviderError::SomeError(
+                "The node restarted while this message was being answered".to_string(),
+            ))
+        } else {
+            let chain = JobManager::inference_chain_router(
+                db.clone(),
+                llm_provider_found.clone(),
+                full_job.clone(),
+                job_message.clone(),
+                message_hash_id.clone(),
+                image_files.clone(),
+                generator.clone(),
+                user_profile.clone(),
+                ws_manager.clone(),
+                tool_router.clone(),
+                sheet_manager.clone(),
+                my_agent_payments_manager.clone(),
+                ext_agent_payments_manager.clone(),
+                job_callback_manager.clone(),
+                // sqlite_logger.clone(),
+                llm_stopper.clone(),
+            );
+            tokio::select! {
+                biased;
+                _ = cancellation_token.cancelled() => Err(LLMProviderError::Cancelled(String::new())),
+                result = chain => result,
+            }
+        };
+
+        // Transient provider errors are retried with a backoff, then the message is dead-lettered
+        let retry_policy = JobRetryPolicy::from_env();
+        let attempt = failed_attempts + 1;
+        let inference_result = match inference_result {
+            Err(e) if e.is_transient() && attempt < retry_policy.max_attempts => {
+                let backoff = retry_policy.backoff(attempt);
+                shinkai_log(
+                    ShinkaiLogOption::JobExecution,
+                    ShinkaiLogLevel::Info,
+                    &format!(
+                        "Attempt {} of job {} failed, retrying in {:?}: {}",
+                        attempt, job_id, backoff, e
+                    ),
+                );
+                // The checkpoint and the stop token are kept for the retry
+                return Err(LLMProviderError::RetryScheduled(backoff));
+            }
+            Err(e) if e.is_transient() => {
+                let dead_letter = JobDeadLetter {
+                    id: format!("deadletter_{}", uuid::Uuid::new_v4()),
+                    job_id: job_id.clone(),
+                    job_message: job_message.clone(),
+                    profile: user_profile.clone(),
+                    message_hash_id: message_hash_id.clone(),
+                    attempts: attempt,
+                    error: e.to_string(),
+                    created_at: Utc::now(),
+                };
+                if let Err(db_error) = db.add_job_dead_letter(&dead_letter) {
+                    shinkai_log(
+                        ShinkaiLogOption::JobExecution,
+                        ShinkaiLogLevel::Error,
+                        &format!("Failed to dead-letter a message of job {}: {}", job_id, db_error),
+                    );
+                }
+                Err(e)
+            }
+            result => result,
+        };
+        let cancelled = matches!(inference_result, Err(LLMProviderError::Cancelled(_)));
+        // A stopped chain is dropped, what it produced until then is in its checkpoint
+        let checkpoint = if cancelled {
+            ChainCheckpoint::load(&db, &job_id, &message_hash_id).unwrap_or_default()
+        } else {
+            ChainCheckpoint::default()
+        };
+        let _ = db.remove_job_checkpoint(&job_id);
+        llm_stopper.finish(&stop_key);
+
+        let (inference_response, inference_response_content, inference_error) = match inference_result {
+            Ok(response) => (response.clone(), response.response, None),
+            // Saved as the answer, with whatever was produced before the stop
+            Err(LLMProviderError::Cancelled(streamed)) => {
+                let partial_output = checkpoint.cancelled_answer(&streamed);
+                let tool_calls = checkpoint.finished_tool_calls();
+                let partial_response = InferenceChainResult {
+                    response: partial_output.clone(),
+                    tps: None,
+                    answer_duration: Some(format!("{:.2}", start.elapsed().as_millis())),
+                    tool_calls: Some(tool_calls).filter(|tool_calls| !tool_calls.is_empty()),
+                };
+                (partial_response, partial_output, Some("Job cancelled".to_string()))
+            }
             Err(e) => {
                 let error_message = format!("{}", e);
                 // Create a minimal inference response with the error message
@@ -270,7 +451,7 @@ impl JobManager {
                     answer_duration: None,
                     tool_calls: None,
                 };
-                (error_response, error_message)
+                (error_response, error_message.clone(), Some(error_message))
             }
         };
 
@@ -285,6 +466,7 @@ impl JobManager {
             tps: inference_response.tps.clone(),
             duration_ms: inference_response.answer_duration.clone(),
             function_calls: inference_response.tool_calls_metadata(),
+            cancelled,
         };
 
         // Prepare data to save inference response to the DB
@@ -309,8 +491,55 @@ impl JobManager {
         db.add_message_to_job_inbox(&job_message.job_id.clone(), &shinkai_message, None, ws_manager)
             .await?;
 
+        match inference_error {
+            Some(_) if cancelled => emit_webhook_event(
+                &db,
+                WebhookEventType::JobCancelled,
+                json!({
+                    "job_id": job_id,
+                    "message_id": message_hash_id,
+                    "agent_id": agent_id,
+                    "partial_output": inference_response_content,
+                }),
+            ),
+            Some(error) => emit_webhook_event(
+                &db,
+                WebhookEventType::JobFailed,
+                json!({ "job_id": job_id, "message_id": message_hash_id, "agent_id": agent_id, "error": error }),
+            ),
+            None => emit_webhook_event(
+                &db,
+                WebhookEventType::JobCompleted,
+                json!({
+                    "job_id": job_id,
+                    "message_id": message_hash_id,
+                    "agent_id": agent_id,
+                    "content": inference_response_content,
+                    "duration_ms": duration.as_millis() as u64,
+                }),
+            ),
+        }
+
         // Check for callbacks and add them to the JobManagerQueue if required
         if let Some(callback) = &job_message.callback {
+            if let CallbackAction::JobPipelineStep(action) = callback.as_ref() {
+                let result = match &inference_error {
+                    Some(error) => Err(error.as_str()),
+                    None => Ok(inference_response_content.as_str()),
+                };
+                if let Err(e) = job_callback_manager
+                    .lock()
+                    .await
+                    .handle_job_pipeline_step_callback(db.clone(), action, result)
+                    .await
+                {
+                    shinkai_log(
+                        ShinkaiLogOption::JobExecution,
+                        ShinkaiLogLevel::Error,
+                        &format!("Failed to advance job pipeline run {}: {}", action.run_id, e),
+                    );
+                }
+            }
             if let CallbackAction::ImplementationCheck(tool_type, available_tools) = callback.as_ref() {
                 job_callback_manager
                     .lock()
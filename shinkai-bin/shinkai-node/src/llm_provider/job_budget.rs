@@ -0,0 +1,141 @@
+use std::time::Duration;
+
+use shinkai_message_primitives::schemas::job::{Job, JobLike};
+use shinkai_message_primitives::schemas::job_config::JobConfig;
+use shinkai_message_primitives::schemas::llm_providers::common_agent_llm_provider::ProviderOrAgent;
+
+/// Limits of the inference chain answering a message, set in the job config or else in the config
+/// of its agent. Only the iterations have a default.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobBudget {
+    pub max_iterations: u64,
+    pub max_duration: Option<Duration>,
+    pub max_total_tokens: Option<u64>,
+    pub max_tool_calls: Option<u64>,
+}
+
+/// Budget a chain ran out of
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JobBudgetExceeded {
+    Iterations(u64),
+    Duration(Duration),
+    TotalTokens(u64),
+    ToolCalls(u64),
+}
+
+/// Usage of a chain so far
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JobBudgetUsage {
+    pub iterations: u64,
+    pub elapsed: Duration,
+    pub total_tokens: u64,
+    pub tool_calls: u64,
+}
+
+impl JobBudget {
+    /// Config of the job merged with the config of its agent, preferring the job's
+    pub fn job_config(full_job: &Job, llm_provider: &ProviderOrAgent) -> JobConfig {
+        let job_config = full_job.config().cloned().unwrap_or_else(JobConfig::empty);
+        match llm_provider {
+            ProviderOrAgent::Agent(agent) => match &agent.config {
+                Some(agent_config) => job_config.merge(agent_config),
+                None => job_config,
+            },
+            ProviderOrAgent::LLMProvider(_) => job_config,
+        }
+    }
+
+    pub fn new(config: &JobConfig, default_max_iterations: u64) -> Self {
+        JobBudget {
+            max_iterations: config.max_iterations.unwrap_or(default_max_iterations),
+            max_duration: config.max_duration_secs.map(Duration::from_secs),
+            max_total_tokens: config.max_total_tokens,
+            max_tool_calls: config.max_tool_calls,
+        }
+    }
+
+    /// First budget `usage` has reached, if any
+    pub fn check(&self, usage: &JobBudgetUsage) -> Option<JobBudgetExceeded> {
+        if usage.iterations >= self.max_iterations {
+            return Some(JobBudgetExceeded::Iterations(self.max_iterations));
+        }
+        if let Some(max_duration) = self.max_duration.filter(|max| usage.elapsed >= *max) {
+            return Some(JobBudgetExceeded::Duration(max_duration));
+        }
+        if let Some(max_total_tokens) = self.max_total_tokens.filter(|max| usage.total_tokens >= *max) {
+            return Some(JobBudgetExceeded::TotalTokens(max_total_tokens));
+        }
+        if let Some(max_tool_calls) = self.max_tool_calls.filter(|max| usage.tool_calls >= *max) {
+            return Some(JobBudgetExceeded::ToolCalls(max_tool_calls));
+        }
+        None
+    }
+
+    /// Time left before the duration budget is reached, if there is one
+    pub fn remaining_duration(&self, elapsed: Duration) -> Option<Duration> {
+        self.max_duration
+            .map(|max_duration| max_duration.saturating_sub(elapsed))
+    }
+}
+
+impl JobBudgetExceeded {
+    /// Answer of a chain stopped by this budget, with the text the LLM wrote last if any
+    pub fn answer(&self, partial_answer: &str, tool_calls: usize) -> String {
+        let reason = match self {
+            JobBudgetExceeded::Iterations(max) => format!("Maximum iterations ({}) reached", max),
+            JobBudgetExceeded::Duration(max) => format!("Maximum duration ({}s) reached", max.as_secs()),
+            JobBudgetExceeded::TotalTokens(max) => format!("Maximum total tokens ({}) reached", max),
+            JobBudgetExceeded::ToolCalls(max) => format!("Maximum tool calls ({}) reached", max),
+        };
+        let message = format!("{}. Process stopped after {} tool calls.", reason, tool_calls);
+        if partial_answer.trim().is_empty() {
+            message
+        } else {
+            format!("{}\n\n{}", partial_answer.trim(), message)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_job_budget_check() {
+        let config = JobConfig {
+            max_duration_secs: Some(60),
+            max_tool_calls: Some(3),
+            ..JobConfig::empty()
+        };
+        let budget = JobBudget::new(&config, 10);
+        assert_eq!(budget.max_iterations, 10);
+
+        let mut usage = JobBudgetUsage {
+            iterations: 2,
+            elapsed: Duration::from_secs(5),
+            total_tokens: 1_000_000,
+            tool_calls: 2,
+        };
+        assert_eq!(budget.check(&usage), None);
+
+        usage.tool_calls = 3;
+        assert_eq!(budget.check(&usage), Some(JobBudgetExceeded::ToolCalls(3)));
+
+        usage.elapsed = Duration::from_secs(61);
+        assert_eq!(
+            budget.check(&usage),
+            Some(JobBudgetExceeded::Duration(Duration::from_secs(60)))
+        );
+        assert_eq!(budget.remaining_duration(usage.elapsed), Some(Duration::ZERO));
+
+        let exceeded = JobBudgetExceeded::ToolCalls(3);
+        assert_eq!(
+            exceeded.answer("", 3),
+            "Maximum tool calls (3) reached. Process stopped after 3 tool calls."
+        );
+        assert_eq!(
+            exceeded.answer("Found two results so far.", 3),
+            "Found two results so far.\n\nMaximum tool calls (3) reached. Process stopped after 3 tool calls."
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use shinkai_message_primitives::schemas::shinkai_tools::DynamicToolType;
 use shinkai_message_primitives::schemas::tool_router_key::ToolRouterKey;
+use shinkai_message_primitives::shinkai_message::shinkai_message_schemas::JobPipelineStepAction;
 use shinkai_message_primitives::shinkai_utils::shinkai_message_builder::ShinkaiMessageBuilder;
 use std::sync::Arc;
 use tokio::sync::Mutex;
@@ -33,7 +34,6 @@ pub struct JobCallbackManager {
     pub cron_manager: Option<Arc<Mutex<CronManager>>>,
 }
 
-// TODO: allow for chaining of multiple jobs some of the jobs may give a result that's used by another job A -> B -> C
 impl Default for JobCallbackManager {
     fn default() -> Self {
         Self::new()
@@ -84,6 +84,21 @@ impl JobCallbackManager {
     //     // }
     // }
 
+    /// Records the result of the job of a pipeline step and starts the steps it unblocked
+    pub async fn handle_job_pipeline_step_callback(
+        &self,
+        db: Arc<SqliteManager>,
+        action: &JobPipelineStepAction,
+        result: Result<&str, &str>,
+    ) -> Result<(), LLMProviderError> {
+        let Some(job_manager) = self.job_manager.clone() else {
+            eprintln!("Job manager is not set in JobCallbackManager");
+            return Ok(());
+        };
+        JobManager::finish_job_pipeline_step(job_manager, db, action, result).await?;
+        Ok(())
+    }
+
     pub async fn handle_implementation_check_callback(
         &self,
         db: Arc<SqliteManager>,
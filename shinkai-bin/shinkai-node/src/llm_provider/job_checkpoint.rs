@@ -0,0 +1,275 @@
+use std::env;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use shinkai_job_queue_manager::job_queue_manager::{JobForProcessing, JobQueueManager};
+use shinkai_message_primitives::schemas::job_checkpoint::JobCheckpoint;
+use shinkai_message_primitives::shinkai_utils::shinkai_logging::{shinkai_log, ShinkaiLogLevel, ShinkaiLogOption};
+use shinkai_sqlite::SqliteManager;
+use tokio::sync::Mutex;
+
+use super::execution::chains::inference_chain_trait::FunctionCall;
+use super::job_manager::JobManager;
+use crate::managers::tool_router::ToolCallFunctionResponse;
+
+/// What happens to a message that was being answered when the node stopped. Interrupted messages
+/// are still queued, so they are picked up again on startup.
+///
+/// Set with `JOB_RESUME_POLICY`, `resume` (default) or `fail`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobResumePolicy {
+    /// Continue from the last checkpoint, without running the completed tool calls again
+    Resume,
+    /// Answer with an error instead
+    Fail,
+}
+
+impl JobResumePolicy {
+    pub fn from_env() -> Self {
+        match env::var("JOB_RESUME_POLICY")
+            .map(|policy| policy.to_lowercase())
+            .as_deref()
+        {
+            Ok("fail") => JobResumePolicy::Fail,
+            _ => JobResumePolicy::Resume,
+        }
+    }
+}
+
+/// State of the generic inference chain saved in a `JobCheckpoint`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChainCheckpoint {
+    pub iteration: u64,
+    /// Tool calls made so far, with their responses
+    pub tool_calls_history: Vec<FunctionCall>,
+    /// Tool results of the finished iterations, sent back to the LLM
+    pub function_responses: Vec<ToolCallFunctionResponse>,
+    /// Function calls the LLM asked for in the current iteration
+    pub pending_function_calls: Vec<FunctionCall>,
+    /// Results of the pending function calls that already ran
+    pub completed_function_calls: Vec<ToolCallFunctionResponse>,
+    /// Estimated tokens of the prompts and responses so far, counted against the token budget
+    #[serde(default)]
+    pub total_tokens: u64,
+    /// Start of the first attempt at the message, the time budget is measured from it
+    #[serde(default)]
+    pub started_at: Option<DateTime<Utc>>,
+    /// Text the LLM wrote along with its last function calls
+    #[serde(default)]
+    pub partial_answer: String,
+}
+
+impl ChainCheckpoint {
+    /// Checkpoint left by an interrupted run of the same message
+    pub fn load(db: &SqliteManager, job_id: &str, message_hash_id: &Option<String>) -> Option<Self> {
+        let checkpoint = db.get_job_checkpoint(job_id).ok().flatten()?;
+        if checkpoint.message_hash_id != *message_hash_id {
+            return None;
+        }
+        serde_json::from_value(checkpoint.state).ok()
+    }
+
+    /// Time since the first attempt at the message started
+    pub fn elapsed(&self) -> Duration {
+        self.started_at
+            .and_then(|started_at| Utc::now().signed_duration_since(started_at).to_std().ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, db: &SqliteManager, job_id: &str, message_hash_id: &Option<String>) {
+        let state = match serde_json::to_value(self) {
+            Ok(state) => state,
+            Err(e) => {
+                shinkai_log(
+                    ShinkaiLogOption::JobExecution,
+                    ShinkaiLogLevel::Error,
+                    &format!("Failed to serialize the checkpoint of job {}: {}", job_id, e),
+                );
+                return;
+            }
+        };
+        let checkpoint = JobCheckpoint {
+            job_id: job_id.to_string(),
+            message_hash_id: message_hash_id.clone(),
+            iteration: self.iteration,
+            state,
+            updated_at: Utc::now(),
+        };
+        if let Err(e) = db.save_job_checkpoint(&checkpoint) {
+            shinkai_log(
+                ShinkaiLogOption::JobExecution,
+                ShinkaiLogLevel::Error,
+                &format!("Failed to save the checkpoint of job {}: {}", job_id, e),
+            );
+        }
+    }
+
+    /// Result of a pending function call that already ran, so it is not run again
+    pub fn completed_response(&self, function_call: &FunctionCall) -> Option<ToolCallFunctionResponse> {
+        self.completed_function_calls
+            .iter()
+            .find(|response| {
+                response.function_call.index == function_call.index
+                    && response.function_call.name == function_call.name
+                    && response.function_call.arguments == function_call.arguments
+            })
+            .cloned()
+    }
+
+    /// Tool calls that finished, with their responses, including those of the current iteration
+    pub fn finished_tool_calls(&self) -> Vec<FunctionCall> {
+        let mut tool_calls = self.tool_calls_history.clone();
+        tool_calls.extend(self.completed_function_calls.iter().map(|completed| FunctionCall {
+            response: Some(completed.response.clone()),
+            ..completed.function_call.clone()
+        }));
+        tool_calls
+    }
+
+    /// Answer of a message stopped before the chain finished: `streamed` if the LLM was stopped while
+    /// writing, otherwise the text it wrote last, followed by the tool calls that finished
+    pub fn cancelled_answer(&self, streamed: &str) -> String {
+        let text = match streamed.trim() {
+            "" => self.partial_answer.trim(),
+            streamed => streamed,
+        };
+        let tool_calls = self.finished_tool_calls();
+        if tool_calls.is_empty() {
+            return text.to_string();
+        }
+        let names: Vec<&str> = tool_calls.iter().map(|tool_call| tool_call.name.as_str()).collect();
+        let message = format!("Stopped after {} tool calls: {}.", tool_calls.len(), names.join(", "));
+        if text.is_empty() {
+            message
+        } else {
+            format!("{}\n\n{}", text, message)
+        }
+    }
+}
+
+impl JobManager {
+    /// Finds the messages a restart interrupted, which are picked up again from the queues
+    /// according to the resume policy, and drops the checkpoints of messages no longer queued
+    pub async fn detect_interrupted_jobs(
+        db: &Arc<SqliteManager>,
+        queues: &[Arc<Mutex<JobQueueManager<JobForProcessing>>>],
+        policy: JobResumePolicy,
+    ) {
+        let checkpoints = match db.get_all_job_checkpoints() {
+            Ok(checkpoints) => checkpoints,
+            Err(e) => {
+                shinkai_log(
+                    ShinkaiLogOption::JobExecution,
+                    ShinkaiLogLevel::Error,
+                    &format!("Failed to read the job checkpoints: {}", e),
+                );
+                return;
+            }
+        };
+
+        for checkpoint in checkpoints {
+            let mut queued = false;
+            for queue in queues {
+                if let Ok(Some(job)) = queue.lock().await.peek(&checkpoint.job_id).await {
+                    queued |= job.message_hash_id == checkpoint.message_hash_id;
+                }
+            }
+
+            if !queued {
+                let _ = db.remove_job_checkpoint(&checkpoint.job_id);
+                continue;
+            }
+            shinkai_log(
+                ShinkaiLogOption::JobExecution,
+                ShinkaiLogLevel::Info,
+                &format!(
+                    "Job {} was interrupted at iteration {}, policy {:?}",
+                    checkpoint.job_id, checkpoint.iteration, policy
+                ),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn function_call(index: u64, query: &str) -> FunctionCall {
+        FunctionCall {
+            name: "search".to_string(),
+            arguments: json!({ "query": query }).as_object().unwrap().clone(),
+            tool_router_key: None,
+            response: None,
+            index,
+            id: None,
+            call_type: None,
+        }
+    }
+
+    #[test]
+    fn test_chain_checkpoint_completed_response() {
+        let checkpoint = ChainCheckpoint {
+            iteration: 1,
+            pending_function_calls: vec![function_call(0, "rust"), function_call(1, "tokio")],
+            completed_function_calls: vec![ToolCallFunctionResponse {
+                response: "results".to_string(),
+                function_call: function_call(0, "rust"),
+            }],
+            ..Default::default()
+        };
+
+        // Survives a round trip through the stored state
+        let checkpoint: ChainCheckpoint = serde_json::from_value(serde_json::to_value(&checkpoint).unwrap()).unwrap();
+
+        assert_eq!(
+            checkpoint
+                .completed_response(&function_call(0, "rust"))
+                .map(|response| response.response),
+            Some("results".to_string())
+        );
+        assert!(checkpoint.completed_response(&function_call(1, "tokio")).is_none());
+        assert!(checkpoint.completed_response(&function_call(0, "python")).is_none());
+    }
+
+    #[test]
+    fn test_chain_checkpoint_elapsed() {
+        let checkpoint = ChainCheckpoint {
+            started_at: Some(Utc::now() - chrono::Duration::seconds(30)),
+            ..Default::default()
+        };
+        let checkpoint: ChainCheckpoint = serde_json::from_value(serde_json::to_value(&checkpoint).unwrap()).unwrap();
+        assert!(checkpoint.elapsed() >= Duration::from_secs(30));
+
+        // Checkpoints saved before the start time was recorded
+        assert_eq!(ChainCheckpoint::default().elapsed(), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_chain_checkpoint_cancelled_answer() {
+        let mut finished = function_call(0, "rust");
+        finished.response = Some("results".to_string());
+        let checkpoint = ChainCheckpoint {
+            tool_calls_history: vec![finished],
+            completed_function_calls: vec![ToolCallFunctionResponse {
+                response: "more results".to_string(),
+                function_call: function_call(1, "tokio"),
+            }],
+            partial_answer: "Let me search for both.".to_string(),
+            ..Default::default()
+        };
+
+        let tool_calls = checkpoint.finished_tool_calls();
+        assert_eq!(tool_calls.len(), 2);
+        assert_eq!(tool_calls[1].response.as_deref(), Some("more results"));
+        assert_eq!(
+            checkpoint.cancelled_answer(""),
+            "Let me search for both.\n\nStopped after 2 tool calls: search, search."
+        );
+        assert!(checkpoint.cancelled_answer("Rust is").starts_with("Rust is\n\n"));
+        assert_eq!(ChainCheckpoint::default().cancelled_answer("Rust is"), "Rust is");
+    }
+}
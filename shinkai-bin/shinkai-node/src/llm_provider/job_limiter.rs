@@ -0,0 +1,293 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use shinkai_message_primitives::schemas::job_concurrency_limit::{
+    JobConcurrencyLimit, JobLimitTarget, JobLimitTargetStatus,
+};
+
+/// Window of the requests and tokens per minute limits
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// An LLM provider or an agent, with its id
+pub type JobLimitKey = (JobLimitTarget, String);
+
+/// Tokens of the inferences that finished in the last minute, per provider. Shared by every
+/// limiter, as inferences are made deep inside the chains, away from the job queue.
+static TOKEN_USAGE: OnceLock<Mutex<HashMap<String, VecDeque<(Instant, u64)>>>> = OnceLock::new();
+
+fn token_usage() -> &'static Mutex<HashMap<String, VecDeque<(Instant, u64)>>> {
+    TOKEN_USAGE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Charges the prompt and completion tokens of an inference to the tokens per minute budget of
+/// its provider
+pub fn record_llm_provider_tokens(llm_provider_id: &str, tokens: u64) {
+    token_usage()
+        .lock()
+        .unwrap()
+        .entry(llm_provider_id.to_string())
+        .or_default()
+        .push_back((Instant::now(), tokens));
+}
+
+fn used_tokens(llm_provider_id: &str, now: Instant) -> u64 {
+    let mut usage = token_usage().lock().unwrap();
+    let Some(used) = usage.get_mut(llm_provider_id) else {
+        return 0;
+    };
+    while used
+        .front()
+        .is_some_and(|(used_at, _)| now.duration_since(*used_at) >= RATE_WINDOW)
+    {
+        used.pop_front();
+    }
+    let tokens = used.iter().map(|(_, tokens)| tokens).sum();
+    if used.is_empty() {
+        usage.remove(llm_provider_id);
+    }
+    tokens
+}
+
+#[derive(Debug, Default)]
+struct JobLimiterState {
+    running_total: usize,
+    running: HashMap<JobLimitKey, usize>,
+    /// Start time of the messages started in the last minute, per provider
+    started: HashMap<String, VecDeque<Instant>>,
+}
+
+impl JobLimiterState {
+    fn prune(&mut self, now: Instant) {
+        for started in self.started.values_mut() {
+            while started
+                .front()
+                .is_some_and(|started_at| now.duration_since(*started_at) >= RATE_WINDOW)
+            {
+                started.pop_front();
+            }
+        }
+        self.started.retain(|_, started| !started.is_empty());
+    }
+
+    /// Messages started and tokens used in the last minute
+    fn usage(&self, llm_provider_id: &str, now: Instant) -> (u32, u64) {
+        let requests = self
+            .started
+            .get(llm_provider_id)
+            .map_or(0, |started| started.len() as u32);
+        (requests, used_tokens(llm_provider_id, now))
+    }
+}
+
+/// Keeps count of the job messages in progress per LLM provider and agent, so that the job queue
+/// only starts the messages that are within the limits of both
+#[derive(Debug, Default)]
+pub struct JobLimiter {
+    state: Arc<Mutex<JobLimiterState>>,
+}
+
+impl JobLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Admits a message processed by `targets` unless one of them is at its limit. Its slots are
+    /// given back when the permit is dropped. `tokens` is the size of the message, a lower bound of
+    /// what its inferences use, which are charged as they finish.
+    pub fn try_acquire(
+        &self,
+        targets: &[JobLimitKey],
+        limits: &[JobConcurrencyLimit],
+        tokens: u64,
+    ) -> Option<JobLimitPermit> {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        state.prune(now);
+
+        for key in targets {
+            let (target, target_id) = key;
+            let Some(limit) = limits
+                .iter()
+                .find(|limit| limit.target == *target && limit.target_id == *target_id)
+            else {
+                continue;
+            };
+            if let Some(max_concurrent) = limit.max_concurrent {
+                if state.running.get(key).copied().unwrap_or_default() >= max_concurrent as usize {
+                    return None;
+                }
+            }
+            if *target == JobLimitTarget::LlmProvider {
+                let (requests, used_tokens) = state.usage(target_id, now);
+                if limit.requests_per_minute.is_some_and(|max| requests >= max) {
+                    return None;
+                }
+                // A message over the whole budget still runs once the window is clear
+                if limit
+                    .tokens_per_minute
+                    .is_some_and(|max| used_tokens > 0 && used_tokens + tokens > max)
+                {
+                    return None;
+                }
+            }
+        }
+
+        state.running_total += 1;
+        for key in targets {
+            *state.running.entry(key.clone()).or_default() += 1;
+            if key.0 == JobLimitTarget::LlmProvider {
+                state.started.entry(key.1.clone()).or_default().push_back(now);
+            }
+        }
+        Some(JobLimitPermit {
+            state: self.state.clone(),
+            targets: targets.to_vec(),
+        })
+    }
+
+    pub fn running(&self) -> usize {
+        self.state.lock().unwrap().running_total
+    }
+
+    /// Load of the targets with a limit or with messages in progress or waiting. `queued` counts
+    /// the messages of each target in the queues, including the ones in progress.
+    pub fn status(
+        &self,
+        limits: &[JobConcurrencyLimit],
+        queued: &HashMap<JobLimitKey, usize>,
+    ) -> Vec<JobLimitTargetStatus> {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        state.prune(now);
+
+        let mut keys: Vec<JobLimitKey> = limits
+            .iter()
+            .map(|limit| (limit.target, limit.target_id.clone()))
+            .chain(state.running.keys().cloned())
+            .chain(queued.keys().cloned())
+            .collect();
+        keys.sort();
+        keys.dedup();
+
+        keys.into_iter()
+            .map(|key| {
+                let running = state.running.get(&key).copied().unwrap_or_default();
+                let (requests_last_minute, tokens_last_minute) = match key.0 {
+                    JobLimitTarget::LlmProvider => state.usage(&key.1, now),
+                    JobLimitTarget::Agent => (0, 0),
+                };
+                JobLimitTargetStatus {
+                    running,
+                    queued: queued.get(&key).copied().unwrap_or_default().saturating_sub(running),
+                    requests_last_minute,
+                    tokens_last_minute,
+                    limit: limits
+                        .iter()
+                        .find(|limit| limit.target == key.0 && limit.target_id == key.1)
+                        .cloned(),
+                    target: key.0,
+                    target_id: key.1,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Slot of a message in progress
+#[derive(Debug)]
+pub struct JobLimitPermit {
+    state: Arc<Mutex<JobLimiterState>>,
+    targets: Vec<JobLimitKey>,
+}
+
+impl Drop for JobLimitPermit {
+    fn drop(&mut self) {
+        let mut state = self.state.lock().unwrap();
+        state.running_total = state.running_total.saturating_sub(1);
+        for key in &self.targets {
+            if let Some(running) = state.running.get_mut(key) {
+                *running = running.saturating_sub(1);
+                if *running == 0 {
+                    state.running.remove(key);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limit(target: JobLimitTarget, target_id: &str) -> JobConcurrencyLimit {
+        JobConcurrencyLimit {
+            target,
+            target_id: target_id.to_string(),
+            max_concurrent: None,
+            requests_per_minute: None,
+            tokens_per_minute: None,
+        }
+    }
+
+    #[test]
+    fn test_job_limiter() {
+        let limiter = JobLimiter::new();
+        let ollama = (JobLimitTarget::LlmProvider, "ollama".to_string());
+        let writer = (JobLimitTarget::Agent, "writer".to_string());
+        let limits = vec![
+            JobConcurrencyLimit {
+                max_concurrent: Some(2),
+                requests_per_minute: Some(3),
+                tokens_per_minute: Some(1_000),
+                ..limit(JobLimitTarget::LlmProvider, "ollama")
+            },
+            JobConcurrencyLimit {
+                max_concurrent: Some(1),
+                ..limit(JobLimitTarget::Agent, "writer")
+            },
+        ];
+
+        // The agent takes one of the two slots of its provider
+        let first = limiter
+            .try_acquire(&[writer.clone(), ollama.clone()], &limits, 100)
+            .unwrap();
+        assert!(limiter
+            .try_acquire(&[writer.clone(), ollama.clone()], &limits, 100)
+            .is_none());
+        let second = limiter.try_acquire(&[ollama.clone()], &limits, 100).unwrap();
+        assert!(limiter.try_acquire(&[ollama.clone()], &limits, 100).is_none());
+        assert_eq!(limiter.running(), 2);
+
+        // Slots come back when a message is done, the rate budget does not
+        drop(first);
+        let third = limiter
+            .try_acquire(&[writer.clone(), ollama.clone()], &limits, 100)
+            .unwrap();
+        drop(second);
+        drop(third);
+        assert_eq!(limiter.running(), 0);
+        assert!(limiter.try_acquire(&[ollama.clone()], &limits, 100).is_none());
+
+        // Tokens are charged per provider once the inferences finish
+        let other = (JobLimitTarget::LlmProvider, "limiter_test_openai".to_string());
+        let limits = vec![JobConcurrencyLimit {
+            tokens_per_minute: Some(1_000),
+            ..limit(JobLimitTarget::LlmProvider, "limiter_test_openai")
+        }];
+        let big = limiter.try_acquire(&[other.clone()], &limits, 10).unwrap();
+        assert!(limiter.try_acquire(&[other.clone()], &limits, 1).is_some());
+        record_llm_provider_tokens("limiter_test_openai", 5_000);
+        assert!(limiter.try_acquire(&[other.clone()], &limits, 1).is_none());
+        drop(big);
+
+        let queued = HashMap::from([(other.clone(), 3)]);
+        let status = limiter.status(&limits, &queued);
+        let openai = status
+            .iter()
+            .find(|status| status.target_id == "limiter_test_openai")
+            .unwrap();
+        assert_eq!((openai.running, openai.queued), (0, 3));
+        assert_eq!((openai.requests_last_minute, openai.tokens_last_minute), (2, 5_000));
+    }
+}
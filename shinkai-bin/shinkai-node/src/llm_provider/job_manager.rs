@@ -1,11 +1,14 @@
 use super::error::LLMProviderError;
 use super::job_callback_manager::JobCallbackManager;
+use super::job_checkpoint::JobResumePolicy;
+use super::job_limiter::{JobLimitKey, JobLimitPermit, JobLimiter};
 use super::llm_stopper::LLMStopper;
 use crate::managers::sheet_manager::SheetManager;
 use crate::managers::tool_router::ToolRouter;
 use crate::managers::IdentityManager;
 use crate::network::agent_payments_manager::external_agent_offerings_manager::ExtAgentOfferingsManager;
 use crate::network::agent_payments_manager::my_agent_offerings_manager::MyAgentOfferingsManager;
+use crate::network::node_metrics::node_metrics;
 use ed25519_dalek::SigningKey;
 use futures::Future;
 use shinkai_embedding::embedding_generator::RemoteEmbeddingGenerator;
@@ -13,9 +16,11 @@ use shinkai_fs::shinkai_file_manager::ShinkaiFileManager;
 use shinkai_job_queue_manager::job_queue_manager::{JobForProcessing, JobQueueManager};
 use shinkai_message_primitives::schemas::inbox_name::InboxName;
 use shinkai_message_primitives::schemas::job::JobLike;
+use shinkai_message_primitives::schemas::job_concurrency_limit::{JobConcurrencyLimit, JobLimitTarget, JobQueueStatus};
 use shinkai_message_primitives::schemas::ws_types::WSUpdateHandler;
 use shinkai_message_primitives::shinkai_message::shinkai_message_schemas::AssociatedUI;
 use shinkai_message_primitives::shinkai_utils::shinkai_logging::{shinkai_log, ShinkaiLogLevel, ShinkaiLogOption};
+use shinkai_message_primitives::shinkai_utils::utils::count_tokens_from_message_llama3;
 use shinkai_message_primitives::{
     schemas::shinkai_name::ShinkaiName, shinkai_message::{
         shinkai_message::{MessageBody, MessageData, ShinkaiMessage}, shinkai_message_schemas::{JobCreationInfo, JobMessage, MessageSchemaType}
@@ -27,10 +32,61 @@ use std::env;
 use std::pin::Pin;
 use std::result::Result::Ok;
 use std::sync::Weak;
+use std::time::Duration;
 use std::{collections::HashMap, sync::Arc};
 use tokio::sync::{Mutex, Semaphore};
 
 const NUM_THREADS: usize = 4;
+/// How often messages held back by a concurrency limit are checked again
+const LIMIT_RECHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Reports how long a job sat in `queue` before a worker picked it up
+fn record_queue_wait(queue: &str, job: &JobForProcessing) {
+    if let Ok(date_created) = chrono::DateTime::parse_from_rfc3339(&job.date_created) {
+        let wait = chrono::Utc::now().signed_duration_since(date_created);
+        node_metrics().record_job_queue_wait(queue, wait.to_std().unwrap_or_default());
+    }
+}
+
+/// Takes the slots of a queued message in the limits of its agent and provider, or `None` if
+/// it has to wait
+fn acquire_job_limit(
+    job_limiter: &JobLimiter,
+    db: &Weak<SqliteManager>,
+    limits: &[JobConcurrencyLimit],
+    job: &JobForProcessing,
+) -> Option<JobLimitPermit> {
+    let targets = match db.upgrade() {
+        Some(db) if !limits.is_empty() => JobManager::job_limit_targets(&db, &job.job_message.job_id),
+        _ => Vec::new(),
+    };
+    let tokens = count_tokens_from_message_llama3(&job.job_message.content) as u64;
+    job_limiter.try_acquire(&targets, limits, tokens)
+}
+
+/// Whether a queued message waits for a retry, either its own or one of an earlier message of its
+/// job. `next_retry` is set to the time left until the first retry is due.
+fn is_waiting_for_retry(
+    job: &JobForProcessing,
+    retrying: &mut HashSet<String>,
+    next_retry: &mut Option<Duration>,
+) -> bool {
+    if retrying.contains(&job.job_message.job_id) {
+        return true;
+    }
+    let Some(wait) = job.time_until_due() else {
+        return false;
+    };
+    retrying.insert(job.job_message.job_id.clone());
+    *next_retry = Some(next_retry.map_or(wait, |next| next.min(wait)));
+    true
+}
+
+fn job_concurrency_limits(db: &Weak<SqliteManager>) -> Vec<JobConcurrencyLimit> {
+    db.upgrade()
+        .and_then(|db| db.get_all_job_concurrency_limits().ok())
+        .unwrap_or_default()
+}
 
 pub trait JobManagerTrait {
     fn create_job<'a>(
@@ -57,6 +113,9 @@ pub struct JobManager {
     pub job_queue_manager_immediate: Arc<Mutex<JobQueueManager<JobForProcessing>>>,
     pub node_profile_name: ShinkaiName,
     pub job_processing_task: Option<tokio::task::JoinHandle<()>>,
+    pub job_limiter: Arc<JobLimiter>,
+    pub max_parallel_jobs: usize,
+    pub llm_stopper: Arc<LLMStopper>,
     // Websocket manager for sending updates to the frontend
     pub ws_manager: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
 }
@@ -105,10 +164,21 @@ impl JobManager {
         }
         let job_queue_immediate = Arc::new(Mutex::new(job_queue_result_immediate.unwrap()));
 
+        // Messages interrupted by a restart are still queued and get picked up again below
+        if let Some(db_arc) = db.upgrade() {
+            JobManager::detect_interrupted_jobs(
+                &db_arc,
+                &[job_queue_normal.clone(), job_queue_immediate.clone()],
+                JobResumePolicy::from_env(),
+            )
+            .await;
+        }
+
         let max_jobs = env::var("JOB_MANAGER_THREADS")
             .unwrap_or(NUM_THREADS.to_string())
             .parse::<usize>()
             .unwrap_or(NUM_THREADS);
+        let job_limiter = Arc::new(JobLimiter::new());
 
         // Start processing both queues
         let job_queue_handler = JobManager::process_job_queue(
@@ -126,6 +196,7 @@ impl JobManager {
             Some(my_agent_payments_manager.clone()),
             Some(ext_agent_payments_manager.clone()),
             llm_stopper.clone(),
+            job_limiter.clone(),
             |job,
              db,
              node_profile_name,
@@ -167,6 +238,9 @@ impl JobManager {
             job_queue_manager_normal: job_queue_normal,
             job_queue_manager_immediate: job_queue_immediate,
             job_processing_task: Some(job_queue_handler),
+            job_limiter,
+            max_parallel_jobs: max_jobs,
+            llm_stopper,
             ws_manager,
         }
     }
@@ -186,6 +260,7 @@ impl JobManager {
         my_agent_payments_manager: Option<Arc<Mutex<MyAgentOfferingsManager>>>,
         ext_agent_payments_manager: Option<Arc<Mutex<ExtAgentOfferingsManager>>>,
         llm_stopper: Arc<LLMStopper>,
+        job_limiter: Arc<JobLimiter>,
         job_processing_fn: impl Fn(
                 JobForProcessing,
                 Weak<SqliteManager>,
@@ -229,8 +304,16 @@ impl JobManager {
             );
 
             loop {
+                // Messages over the limits of their agent or provider stay queued until a slot frees up,
+                // and so do the later messages of their job
+                let limits = job_concurrency_limits(&db_clone);
+                let mut held_back = HashSet::new();
+                // Messages retried after a failed attempt stay queued until their backoff is over
+                let mut retrying = HashSet::new();
+                let mut next_retry = None;
+
                 // 1) Pull any immediate jobs if available
-                let immediate_jobs_to_process: Vec<(String, JobForProcessing)> = {
+                let immediate_jobs_to_process: Vec<(String, JobForProcessing, JobLimitPermit)> = {
                     let mut processing_lock = processing_jobs.lock().await;
                     let mgr = queue_immediate.lock().await;
                     let all_imm = mgr.get_all_elements_interleave().await.unwrap_or_else(|_| vec![]);
@@ -240,19 +323,25 @@ impl JobManager {
                         .into_iter()
                         .filter_map(|job| {
                             let jid = job.job_message.job_id.clone();
-                            if !processing_lock.contains(&jid) {
-                                processing_lock.insert(jid.clone());
-                                Some((jid, job))
-                            } else {
-                                None
+                            if processing_lock.contains(&jid)
+                                || held_back.contains(&jid)
+                                || is_waiting_for_retry(&job, &mut retrying, &mut next_retry)
+                            {
+                                return None;
                             }
+                            let Some(limit_permit) = acquire_job_limit(&job_limiter, &db_clone, &limits, &job) else {
+                                held_back.insert(jid);
+                                return None;
+                            };
+                            processing_lock.insert(jid.clone());
+                            Some((jid, job, limit_permit))
                         })
                         .collect()
                 };
 
                 if !immediate_jobs_to_process.is_empty() {
                     // 1A) Spawn all immediate jobs
-                    for (job_id, job) in immediate_jobs_to_process {
+                    for (job_id, job, limit_permit) in immediate_jobs_to_process {
                         let permit = immediate_semaphore.clone().acquire_owned().await.unwrap();
                         let job_processing_fn = Arc::clone(&job_processing_fn);
                         let db_clone = db_clone.clone();
@@ -270,6 +359,7 @@ impl JobManager {
                         let in_progress = processing_jobs.clone();
 
                         tokio::spawn(async move {
+                            record_queue_wait("immediate", &job);
                             let result = (job_processing_fn)(
                                 job,
                                 db_clone,
@@ -287,10 +377,14 @@ impl JobManager {
                             )
                             .await;
 
-                            let _ = queue_immediate.lock().await.dequeue(&job_id).await;
+                            // A message queued again for a retry stays first in the queue of its job
+                            if !matches!(result, Err(LLMProviderError::RetryScheduled(_))) {
+                                let _ = queue_immediate.lock().await.dequeue(&job_id).await;
+                            }
                             let mut inprog = in_progress.lock().await;
                             inprog.remove(&job_id);
                             drop(permit);
+                            drop(limit_permit);
                         });
                     }
                     // Done immediate; continue so we can re-check normal vs. immediate again
@@ -298,7 +392,7 @@ impl JobManager {
                 }
 
                 // 2) If no immediate jobs, let's gather normal jobs
-                let normal_jobs_to_process: Vec<(String, JobForProcessing)> = {
+                let normal_jobs_to_process: Vec<(String, JobForProcessing, JobLimitPermit)> = {
                     let mut processing_lock = processing_jobs.lock().await;
                     let mgr = queue_normal.lock().await;
                     let all_norm = mgr.get_all_elements_interleave().await.unwrap_or_else(|_| vec![]);
@@ -308,12 +402,18 @@ impl JobManager {
                         .into_iter()
                         .filter_map(|job| {
                             let jid = job.job_message.job_id.clone();
-                            if !processing_lock.contains(&jid) {
-                                processing_lock.insert(jid.clone());
-                                Some((jid, job))
-                            } else {
-                                None
+                            if processing_lock.contains(&jid)
+                                || held_back.contains(&jid)
+                                || is_waiting_for_retry(&job, &mut retrying, &mut next_retry)
+                            {
+                                return None;
                             }
+                            let Some(limit_permit) = acquire_job_limit(&job_limiter, &db_clone, &limits, &job) else {
+                                held_back.insert(jid);
+                                return None;
+                            };
+                            processing_lock.insert(jid.clone());
+                            Some((jid, job, limit_permit))
                         })
                         .take(max_parallel_jobs)
                         .collect()
@@ -346,11 +446,14 @@ impl JobManager {
                                 break;
                             }
                         }
+                        // Nothing signals a slot freeing up, so held back messages are polled
+                        _ = tokio::time::sleep(LIMIT_RECHECK_INTERVAL), if !held_back.is_empty() => {}
+                        _ = tokio::time::sleep(next_retry.unwrap_or_default()), if next_retry.is_some() => {}
                     }
                 } else {
                     // 2B) We have normal jobs; but we check again for immediate jobs while
                     // waiting for the normal semaphore
-                    for (job_id, job) in normal_jobs_to_process {
+                    for (job_id, job, limit_permit) in normal_jobs_to_process {
                         loop {
                             tokio::select! {
                                 permit = normal_semaphore.clone().acquire_owned() => {
@@ -371,6 +474,7 @@ impl JobManager {
                                     let in_progress = processing_jobs.clone();
 
                                     tokio::spawn(async move {
+                                        record_queue_wait("normal", &job);
                                         let result = (job_processing_fn)(
                                             job,
                                             db_clone,
@@ -388,10 +492,14 @@ impl JobManager {
                                         )
                                         .await;
 
-                                        let _ = queue_normal.lock().await.dequeue(&job_id).await;
+                                        // A message queued again for a retry stays first in the queue of its job
+                                        if !matches!(result, Err(LLMProviderError::RetryScheduled(_))) {
+                                            let _ = queue_normal.lock().await.dequeue(&job_id).await;
+                                        }
                                         let mut inprog = in_progress.lock().await;
                                         inprog.remove(&job_id);
                                         drop(permit);
+                                        drop(limit_permit);
                                     });
 
                                     // Done with this normal job
@@ -408,6 +516,23 @@ impl JobManager {
                                             &format!("Received new immediate job {:?} while waiting for normal job permit", imm_id),
                                         );
 
+                                        // Messages queued behind a running message or a retry are picked up
+                                        // from the queue later, in order
+                                        let queued_next = queue_immediate.lock().await.peek(&imm_id).await;
+                                        let is_next = matches!(queued_next, Ok(Some(next)) if next == imm_job);
+                                        if !is_next || imm_job.time_until_due().is_some() {
+                                            continue;
+                                        }
+
+                                        // Over its limits it is picked up from the queue later
+                                        let limits = job_concurrency_limits(&db_clone);
+                                        let imm_limit_permit =
+                                            acquire_job_limit(&job_limiter, &db_clone, &limits, &imm_job);
+                                        let Some(imm_limit_permit) = imm_limit_permit else {
+                                            continue;
+                                        };
+                                        processing_jobs.lock().await.insert(imm_id.clone());
+
                                         let permit = immediate_semaphore.clone().acquire_owned().await.unwrap();
                                         let job_processing_fn = Arc::clone(&job_processing_fn);
                                         let db_clone = db_clone.clone();
@@ -425,6 +550,7 @@ impl JobManager {
                                         let in_progress = processing_jobs.clone();
 
                                         tokio::spawn(async move {
+                                            record_queue_wait("immediate", &imm_job);
                                             let result = (job_processing_fn)(
                                                 imm_job,
                                                 db_clone,
@@ -442,10 +568,14 @@ impl JobManager {
                                             )
                                             .await;
 
-                                            let _ = queue_immediate.lock().await.dequeue(&imm_id).await;
+                                            // A message queued again for a retry stays first in the queue of its job
+                                            if !matches!(result, Err(LLMProviderError::RetryScheduled(_))) {
+                                                let _ = queue_immediate.lock().await.dequeue(&imm_id).await;
+                                            }
                                             let mut inprog = in_progress.lock().await;
                                             inprog.remove(&imm_id);
                                             drop(permit);
+                                            drop(imm_limit_permit);
                                         });
                                     } else {
                                         eprintln!("rx_immediate closed, shutting down...");
@@ -663,7 +793,10 @@ impl JobManager {
         message_hash_id: Option<String>,
         high_priority: bool,
     ) -> Result<String, LLMProviderError> {
-        let job_for_processing = JobForProcessing::new(job_message.clone(), profile.clone(), message_hash_id);
+        let db_arc = self.db.upgrade().ok_or("Failed to upgrade shinkai_db").unwrap();
+        let priority = Self::job_queue_priority(&db_arc, &job_message.job_id);
+        let job_for_processing =
+            JobForProcessing::new(job_message.clone(), profile.clone(), message_hash_id).with_priority(priority);
 
         if high_priority {
             let mut imm = self.job_queue_manager_immediate.lock().await;
@@ -675,6 +808,73 @@ impl JobManager {
 
         Ok(job_message.job_id.clone().to_string())
     }
+
+    /// Priority of the messages of a job, set in its config or else in the config of its agent
+    pub fn job_queue_priority(db: &SqliteManager, job_id: &str) -> i32 {
+        let Ok(job) = db.get_job_with_options(job_id, false) else {
+            return 0;
+        };
+        job.config()
+            .and_then(|config| config.priority)
+            .or_else(|| {
+                let agent = db.get_agent(&job.parent_agent_or_llm_provider_id).ok().flatten()?;
+                agent.config?.priority
+            })
+            .unwrap_or_default()
+    }
+
+    /// Agent and LLM provider that process the messages of a job, checked against their limits
+    pub fn job_limit_targets(db: &SqliteManager, job_id: &str) -> Vec<JobLimitKey> {
+        let Ok(job) = db.get_job_with_options(job_id, false) else {
+            return Vec::new();
+        };
+        let agent_or_llm_provider_id = job.parent_agent_or_llm_provider_id.clone();
+        match db.get_agent(&agent_or_llm_provider_id).ok().flatten() {
+            Some(agent) => vec![
+                (JobLimitTarget::Agent, agent.agent_id),
+                (JobLimitTarget::LlmProvider, agent.llm_provider_id),
+            ],
+            None => vec![(JobLimitTarget::LlmProvider, agent_or_llm_provider_id)],
+        }
+    }
+
+    /// Messages waiting and in progress, overall and per limited agent and provider
+    pub async fn queue_status(&self) -> Result<JobQueueStatus, LLMProviderError> {
+        let db = self.db.upgrade().ok_or(LLMProviderError::DatabaseError(
+            "Failed to upgrade shinkai_db".to_string(),
+        ))?;
+        let limits = db.get_all_job_concurrency_limits()?;
+
+        let normal = self
+            .job_queue_manager_normal
+            .lock()
+            .await
+            .get_all_elements_interleave()
+            .await
+            .unwrap_or_default();
+        let immediate = self
+            .job_queue_manager_immediate
+            .lock()
+            .await
+            .get_all_elements_interleave()
+            .await
+            .unwrap_or_default();
+
+        let mut queued: HashMap<JobLimitKey, usize> = HashMap::new();
+        for job in normal.iter().chain(immediate.iter()) {
+            for target in Self::job_limit_targets(&db, &job.job_message.job_id) {
+                *queued.entry(target).or_default() += 1;
+            }
+        }
+
+        Ok(JobQueueStatus {
+            max_parallel_jobs: self.max_parallel_jobs,
+            running: self.job_limiter.running(),
+            queued_normal: normal.len(),
+            queued_immediate: immediate.len(),
+            targets: self.job_limiter.status(&limits, &queued),
+        })
+    }
 }
 
 impl JobManagerTrait for JobManager {
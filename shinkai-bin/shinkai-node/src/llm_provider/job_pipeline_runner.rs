@@ -0,0 +1,241 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use serde_json::Value;
+use shinkai_message_primitives::{
+    schemas::{
+        job_pipeline::{JobPipeline, JobPipelineRun, JobPipelineRunStatus, JobPipelineStep, JobPipelineStepRun, JobPipelineStepStatus}, shinkai_name::ShinkaiName, ws_types::{JobPipelineStepMetadata, WSMessageType, WidgetMetadata}
+    }, shinkai_message::shinkai_message_schemas::{CallbackAction, JobCreationInfo, JobMessage, JobPipelineStepAction, WSTopic}, shinkai_utils::{
+        job_scope::MinimalJobScope, shinkai_logging::{shinkai_log, ShinkaiLogLevel, ShinkaiLogOption}, shinkai_message_builder::ShinkaiMessageBuilder, signatures::clone_signature_secret_key
+    }
+};
+use shinkai_sqlite::SqliteManager;
+use tokio::sync::Mutex;
+
+use super::{error::LLMProviderError, job_manager::JobManager};
+
+impl JobManager {
+    /// Starts a run of `pipeline` with the steps that do not depend on any other
+    pub async fn start_job_pipeline_run(
+        job_manager: Arc<Mutex<JobManager>>,
+        db: Arc<SqliteManager>,
+        pipeline: &JobPipeline,
+        input: Value,
+    ) -> Result<JobPipelineRun, LLMProviderError> {
+        pipeline.validate().map_err(LLMProviderError::SomeError)?;
+        let run = JobPipelineRun {
+            id: format!("pipelinerun_{}", uuid::Uuid::new_v4()),
+            pipeline: pipeline.clone(),
+            input,
+            status: JobPipelineRunStatus::Running,
+            error: None,
+            steps: pipeline
+                .steps
+                .iter()
+                .map(|step| JobPipelineStepRun {
+                    step_id: step.id.clone(),
+                    status: JobPipelineStepStatus::Pending,
+                    job_id: None,
+                    output: None,
+                    error: None,
+                    started_at: None,
+                    finished_at: None,
+                })
+                .collect(),
+            created_at: Utc::now(),
+            finished_at: None,
+        };
+        db.add_job_pipeline_run(&run)?;
+        Self::advance_job_pipeline_run(job_manager, db, &run.id).await
+    }
+
+    /// Runs the failed steps of a failed run again, and the steps waiting on them once they complete
+    pub async fn resume_job_pipeline_run(
+        job_manager: Arc<Mutex<JobManager>>,
+        db: Arc<SqliteManager>,
+        run_id: &str,
+    ) -> Result<JobPipelineRun, LLMProviderError> {
+        if !db.resume_job_pipeline_run(run_id)? {
+            return Err(LLMProviderError::SomeError(format!(
+                "Job pipeline run {} has not failed",
+                run_id
+            )));
+        }
+        Self::advance_job_pipeline_run(job_manager, db, run_id).await
+    }
+
+    /// Records the answer or the error of the job of a step, then starts the steps it unblocked
+    pub async fn finish_job_pipeline_step(
+        job_manager: Arc<Mutex<JobManager>>,
+        db: Arc<SqliteManager>,
+        action: &JobPipelineStepAction,
+        result: Result<&str, &str>,
+    ) -> Result<JobPipelineRun, LLMProviderError> {
+        if !db.finish_job_pipeline_step(&action.run_id, &action.step_id, result)? {
+            return db
+                .get_job_pipeline_run(&action.run_id)?
+                .ok_or_else(|| LLMProviderError::SomeError(format!("Job pipeline run {} not found", action.run_id)));
+        }
+        let run = Self::advance_job_pipeline_run(job_manager.clone(), db.clone(), &action.run_id).await?;
+        // Sent after advancing so that it carries the status of the run once its last step is done
+        Self::notify_job_pipeline_step(&job_manager, &db, &action.run_id, &action.step_id).await?;
+        Ok(run)
+    }
+
+    /// Starts every step whose dependencies completed. Once a step failed no other step is started,
+    /// and the run fails when the steps still running are done.
+    async fn advance_job_pipeline_run(
+        job_manager: Arc<Mutex<JobManager>>,
+        db: Arc<SqliteManager>,
+        run_id: &str,
+    ) -> Result<JobPipelineRun, LLMProviderError> {
+        loop {
+            let run = db
+                .get_job_pipeline_run(run_id)?
+                .ok_or_else(|| LLMProviderError::SomeError(format!("Job pipeline run {} not found", run_id)))?;
+            if run.status != JobPipelineRunStatus::Running {
+                return Ok(run);
+            }
+            let failed_step = run
+                .steps
+                .iter()
+                .find(|step_run| step_run.status == JobPipelineStepStatus::Failed);
+
+            let ready_steps = run.ready_steps();
+            if failed_step.is_none() && !ready_steps.is_empty() {
+                for step in ready_steps {
+                    // Another step finishing at the same time may have started it already
+                    if !db.claim_job_pipeline_step(run_id, &step.id)? {
+                        continue;
+                    }
+                    if let Err(e) = Self::start_job_pipeline_step(&job_manager, &db, &run, step).await {
+                        db.finish_job_pipeline_step(run_id, &step.id, Err(&e.to_string()))?;
+                    }
+                    Self::notify_job_pipeline_step(&job_manager, &db, run_id, &step.id).await?;
+                }
+                continue;
+            }
+
+            if run
+                .steps
+                .iter()
+                .any(|step_run| step_run.status == JobPipelineStepStatus::Running)
+            {
+                return Ok(run);
+            }
+            let (status, error) = match failed_step {
+                Some(step_run) => (
+                    JobPipelineRunStatus::Failed,
+                    Some(format!(
+                        "Step '{}' failed: {}",
+                        step_run.step_id,
+                        step_run.error.as_deref().unwrap_or_default()
+                    )),
+                ),
+                None => (JobPipelineRunStatus::Completed, None),
+            };
+            if db.finish_job_pipeline_run(run_id, status, error.as_deref())? {
+                shinkai_log(
+                    ShinkaiLogOption::JobExecution,
+                    ShinkaiLogLevel::Info,
+                    &format!("Job pipeline run {} finished: {}", run_id, status),
+                );
+            }
+        }
+    }
+
+    /// Creates the job of a step and sends it the prompt of the step
+    async fn start_job_pipeline_step(
+        job_manager: &Arc<Mutex<JobManager>>,
+        db: &Arc<SqliteManager>,
+        run: &JobPipelineRun,
+        step: &JobPipelineStep,
+    ) -> Result<String, LLMProviderError> {
+        let mut job_manager = job_manager.lock().await;
+        let user_profile = ShinkaiName::from_node_and_profile_names(
+            job_manager.node_profile_name.node_name.clone(),
+            "main".to_string(),
+        )
+        .map_err(|e| LLMProviderError::SomeError(e.to_string()))?;
+        let job_creation_info = JobCreationInfo {
+            scope: MinimalJobScope::default(),
+            is_hidden: Some(true),
+            associated_ui: None,
+        };
+        let job_id = job_manager
+            .process_job_creation(job_creation_info, &user_profile, &step.agent_id)
+            .await?;
+        db.set_job_pipeline_step_job(&run.id, &step.id, &job_id)?;
+
+        let job_message = JobMessage {
+            job_id: job_id.clone(),
+            content: run.render_prompt(step),
+            parent: None,
+            sheet_job_data: None,
+            tools: step.tools.clone(),
+            callback: Some(Box::new(CallbackAction::JobPipelineStep(JobPipelineStepAction {
+                run_id: run.id.clone(),
+                step_id: step.id.clone(),
+            }))),
+            metadata: None,
+            tool_key: None,
+            fs_files_paths: vec![],
+            job_filenames: vec![],
+        };
+        let message = ShinkaiMessageBuilder::job_message_unencrypted(
+            job_id.clone(),
+            job_message.content.clone(),
+            vec![],
+            "".to_string(),
+            clone_signature_secret_key(&job_manager.identity_secret_key),
+            user_profile.node_name.clone(),
+            user_profile.get_profile_name_string().unwrap_or_default(),
+            user_profile.node_name.clone(),
+            "".to_string(),
+        )
+        .map_err(|e| LLMProviderError::ShinkaiMessageBuilderError(e.to_string()))?;
+        job_manager
+            .add_to_job_processing_queue(message, job_message, false)
+            .await?;
+        Ok(job_id)
+    }
+
+    /// Sends the status of a step to the WebSocket subscribers of the run
+    async fn notify_job_pipeline_step(
+        job_manager: &Arc<Mutex<JobManager>>,
+        db: &Arc<SqliteManager>,
+        run_id: &str,
+        step_id: &str,
+    ) -> Result<(), LLMProviderError> {
+        let Some(ws_manager) = job_manager.lock().await.ws_manager.clone() else {
+            return Ok(());
+        };
+        let Some(run) = db.get_job_pipeline_run(run_id)? else {
+            return Ok(());
+        };
+        let Some(step_run) = run.step_run(step_id) else {
+            return Ok(());
+        };
+        let metadata = JobPipelineStepMetadata {
+            run_id: run.id.clone(),
+            pipeline_id: run.pipeline.id.clone(),
+            step_id: step_run.step_id.clone(),
+            status: step_run.status,
+            job_id: step_run.job_id.clone(),
+            error: step_run.error.clone(),
+            run_status: run.status,
+        };
+        ws_manager
+            .lock()
+            .await
+            .queue_message(
+                WSTopic::Widget,
+                run.id.clone(),
+                "".to_string(),
+                WSMessageType::Widget(WidgetMetadata::JobPipelineStep(metadata)),
+                false,
+            )
+            .await;
+        Ok(())
+    }
+}
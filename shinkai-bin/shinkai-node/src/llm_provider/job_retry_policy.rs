@@ -0,0 +1,73 @@
+use std::env;
+use std::time::Duration;
+
+/// How often a job message is sent again to its provider after a transient error, such as a
+/// timeout or a rate limit. Once the attempts run out the message is dead-lettered.
+///
+/// Set with `JOB_RETRY_MAX_ATTEMPTS`, `JOB_RETRY_BACKOFF_MS`, `JOB_RETRY_BACKOFF_MULTIPLIER` and
+/// `JOB_RETRY_MAX_BACKOFF_MS`. The wait after the nth failed attempt is
+/// `backoff_ms * backoff_multiplier^(n - 1)`, capped at `max_backoff_ms`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobRetryPolicy {
+    /// Attempts per message, including the first one
+    pub max_attempts: u32,
+    pub backoff_ms: u64,
+    pub backoff_multiplier: f64,
+    pub max_backoff_ms: u64,
+}
+
+impl Default for JobRetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            backoff_ms: 2_000,
+            backoff_multiplier: 2.0,
+            max_backoff_ms: 60_000,
+        }
+    }
+}
+
+impl JobRetryPolicy {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        Self {
+            max_attempts: env_or("JOB_RETRY_MAX_ATTEMPTS", default.max_attempts).max(1),
+            backoff_ms: env_or("JOB_RETRY_BACKOFF_MS", default.backoff_ms),
+            backoff_multiplier: env_or("JOB_RETRY_BACKOFF_MULTIPLIER", default.backoff_multiplier).max(1.0),
+            max_backoff_ms: env_or("JOB_RETRY_MAX_BACKOFF_MS", default.max_backoff_ms),
+        }
+    }
+
+    /// Time to wait after `failed_attempts` attempts failed
+    pub fn backoff(&self, failed_attempts: u32) -> Duration {
+        let exponent = failed_attempts.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let backoff = self.backoff_ms as f64 * self.backoff_multiplier.powi(exponent);
+        Duration::from_millis(backoff.min(self.max_backoff_ms as f64) as u64)
+    }
+}
+
+fn env_or<T: std::str::FromStr>(key: &str, default: T) -> T {
+    env::var(key)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_job_retry_backoff() {
+        let policy = JobRetryPolicy {
+            max_attempts: 5,
+            backoff_ms: 500,
+            backoff_multiplier: 3.0,
+            max_backoff_ms: 10_000,
+        };
+        assert_eq!(policy.backoff(1), Duration::from_millis(500));
+        assert_eq!(policy.backoff(2), Duration::from_millis(1_500));
+        assert_eq!(policy.backoff(3), Duration::from_millis(4_500));
+        assert_eq!(policy.backoff(4), Duration::from_millis(10_000));
+    }
+}
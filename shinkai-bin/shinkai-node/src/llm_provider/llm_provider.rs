@@ -1,9 +1,13 @@
 use std::sync::Arc;
+use std::time::Instant;
 
 use super::error::LLMProviderError;
 use super::execution::chains::inference_chain_trait::LLMInferenceResponse;
+use super::job_limiter::record_llm_provider_tokens;
 use super::llm_stopper::LLMStopper;
 use super::providers::LLMService;
+use crate::managers::model_capabilities_manager::ModelCapabilitiesManager;
+use crate::network::node_metrics::node_metrics;
 use reqwest::Client;
 use serde_json::{Map, Value as JsonValue};
 use shinkai_message_primitives::schemas::inbox_name::InboxName;
@@ -16,6 +20,7 @@ use shinkai_message_primitives::schemas::{
     llm_providers::serialized_llm_provider::{LLMProviderInterface, SerializedLLMProvider},
     shinkai_name::ShinkaiName,
 };
+use shinkai_message_primitives::shinkai_utils::utils::count_tokens_from_message_llama3;
 use shinkai_sqlite::SqliteManager;
 use tokio::sync::Mutex;
 
@@ -81,6 +86,7 @@ impl LLMProvider {
         }
     }
 
+    #[tracing::instrument(name = "inference", skip_all, fields(provider, model))]
     pub async fn inference(
         &self,
         prompt: Prompt,
@@ -103,13 +109,27 @@ impl LLMProvider {
             config
         };
 
+        let prompt_tokens: usize = prompt
+            .sub_prompts
+            .iter()
+            .map(|sub_prompt| sub_prompt.count_tokens_as_completion_message(ModelCapabilitiesManager::num_tokens_from_llama3))
+            .sum();
+        let (provider, model) = self.metrics_labels();
+        let span = tracing::Span::current();
+        span.record("provider", provider.as_str());
+        span.record("model", model.as_str());
+        let start = Instant::now();
+
+        // Stored sealed, only decrypted for the request itself
+        let api_key = self.db.reveal_optional_secret(&self.api_key)?;
+
         let response = match &self.model {
             LLMProviderInterface::OpenAI(openai) => {
                 openai
                     .call_api(
                         &self.client,
                         self.external_url.as_ref(),
-                        self.api_key.as_ref(),
+                        api_key.as_ref(),
                         prompt.clone(),
                         self.model.clone(),
                         inbox_name,
@@ -125,7 +145,7 @@ impl LLMProvider {
                     .call_api(
                         &self.client,
                         self.external_url.as_ref(),
-                        self.api_key.as_ref(),
+                        api_key.as_ref(),
                         prompt.clone(),
                         self.model.clone(),
                         inbox_name,
@@ -141,7 +161,7 @@ impl LLMProvider {
                     .call_api(
                         &self.client,
                         self.external_url.as_ref(),
-                        self.api_key.as_ref(),
+                        api_key.as_ref(),
                         prompt.clone(),
                         self.model.clone(),
                         inbox_name,
@@ -156,7 +176,7 @@ impl LLMProvider {
                 exo.call_api(
                     &self.client,
                     self.external_url.as_ref(),
-                    self.api_key.as_ref(),
+                    api_key.as_ref(),
                     prompt.clone(),
                     self.model.clone(),
                     inbox_name,
@@ -172,7 +192,7 @@ impl LLMProvider {
                     .call_api(
                         &self.client,
                         self.external_url.as_ref(),
-                        self.api_key.as_ref(),
+                        api_key.as_ref(),
                         prompt.clone(),
                         self.model.clone(),
                         inbox_name,
@@ -187,7 +207,7 @@ impl LLMProvider {
                 groq.call_api(
                     &self.client,
                     self.external_url.as_ref(),
-                    self.api_key.as_ref(),
+                    api_key.as_ref(),
                     prompt.clone(),
                     self.model.clone(),
                     inbox_name,
@@ -203,7 +223,7 @@ impl LLMProvider {
                     .call_api(
                         &self.client,
                         self.external_url.as_ref(),
-                        self.api_key.as_ref(),
+                        api_key.as_ref(),
                         prompt.clone(),
                         self.model.clone(),
                         inbox_name,
@@ -219,7 +239,7 @@ impl LLMProvider {
                     .call_api(
                         &self.client,
                         self.external_url.as_ref(),
-                        self.api_key.as_ref(),
+                        api_key.as_ref(),
                         prompt.clone(),
                         self.model.clone(),
                         inbox_name,
@@ -235,7 +255,7 @@ impl LLMProvider {
                     .call_api(
                         &self.client,
                         self.external_url.as_ref(),
-                        self.api_key.as_ref(),
+                        api_key.as_ref(),
                         prompt.clone(),
                         self.model.clone(),
                         inbox_name,
@@ -254,7 +274,7 @@ impl LLMProvider {
                     .call_api(
                         &self.client,
                         self.external_url.as_ref(),
-                        self.api_key.as_ref(),
+                        api_key.as_ref(),
                         prompt.clone(),
                         self.model.clone(),
                         inbox_name,
@@ -270,7 +290,7 @@ impl LLMProvider {
                     .call_api(
                         &self.client,
                         self.external_url.as_ref(),
-                        self.api_key.as_ref(),
+                        api_key.as_ref(),
                         prompt.clone(),
                         self.model.clone(),
                         inbox_name,
@@ -281,8 +301,34 @@ impl LLMProvider {
                     )
                     .await
             }
-        }?;
-        Ok(response)
+        };
+
+        match &response {
+            Ok(response) => {
+                let completion_tokens = count_tokens_from_message_llama3(&response.response_string);
+                record_llm_provider_tokens(&self.id, (prompt_tokens + completion_tokens) as u64);
+                node_metrics().record_inference(
+                    &provider,
+                    &model,
+                    start.elapsed(),
+                    Some((prompt_tokens, completion_tokens)),
+                );
+            }
+            Err(_) => node_metrics().record_inference(&provider, &model, start.elapsed(), None),
+        }
+        response
+    }
+
+    /// Provider and model labels used for metrics and tracing, e.g. ("openai", "gpt-4o")
+    fn metrics_labels(&self) -> (String, String) {
+        let model = serde_json::to_value(&self.model)
+            .ok()
+            .and_then(|value| value.as_str().map(|value| value.to_string()))
+            .unwrap_or_default();
+        match model.split_once(':') {
+            Some((provider, model)) => (provider.to_string(), model.to_string()),
+            None => (model, String::new()),
+        }
     }
 }
 
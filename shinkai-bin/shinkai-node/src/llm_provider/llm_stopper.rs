@@ -1,20 +1,101 @@
-use dashmap::DashMap;
+use dashmap::{DashMap, DashSet};
+use tokio_util::sync::CancellationToken;
 
 pub type JobId = String;
 
+/// Stops the work of a job, keyed by its inbox name. Streaming providers poll `should_stop`
+/// between chunks, everything else waits on the cancellation token of the job so that in-flight
+/// requests, tool processes and the remaining iterations of the chain are dropped. Work done for
+/// the job outside of its messages, e.g. the embeddings of an uploaded file, waits on a task token.
 pub struct LLMStopper {
     pub stop_signal: DashMap<JobId, bool>,
+    pub cancellation_tokens: DashMap<JobId, CancellationToken>,
+    /// Hash of the message each job is processing
+    pub running_messages: DashMap<JobId, String>,
+    /// Messages stopped before they were processed, cancelled as soon as they start
+    pub stopped_messages: DashSet<String>,
+    /// Parent of the task tokens of each job, replaced once cancelled
+    pub task_tokens: DashMap<JobId, CancellationToken>,
 }
 
 impl LLMStopper {
     pub fn new() -> Self {
         LLMStopper {
             stop_signal: DashMap::new(),
+            cancellation_tokens: DashMap::new(),
+            running_messages: DashMap::new(),
+            stopped_messages: DashSet::new(),
+            task_tokens: DashMap::new(),
         }
     }
 
     pub fn stop(&self, key: &str) {
+        self.stop_running_message(key);
+        if let Some((_, token)) = self.task_tokens.remove(key) {
+            token.cancel();
+        }
+    }
+
+    fn stop_running_message(&self, key: &str) {
         self.stop_signal.insert(key.to_string(), true);
+        if let Some(token) = self.cancellation_tokens.get(key) {
+            token.cancel();
+        }
+    }
+
+    /// Stops the message `message_hash` of a job: now if the job is processing it, otherwise as soon
+    /// as it starts. Other messages of the job are not affected.
+    pub fn stop_message(&self, key: &str, message_hash: &str) {
+        self.stopped_messages.insert(message_hash.to_string());
+        let running = self.running_messages.get(key).map(|running| running.clone());
+        if running.as_deref() == Some(message_hash) {
+            self.stopped_messages.remove(message_hash);
+            self.stop_running_message(key);
+        }
+    }
+
+    /// Fresh token for a job message about to be processed, so that a previous stop does not
+    /// cancel it, unless the message itself was stopped
+    pub fn start(&self, key: &str, message_hash: Option<&str>) -> CancellationToken {
+        let token = CancellationToken::new();
+        self.stop_signal.insert(key.to_string(), false);
+        self.cancellation_tokens.insert(key.to_string(), token.clone());
+        // Marked as running first so that a concurrent `stop_message` is never missed
+        if let Some(message_hash) = message_hash {
+            self.running_messages.insert(key.to_string(), message_hash.to_string());
+            if self.stopped_messages.remove(message_hash).is_some() {
+                self.stop_running_message(key);
+            }
+        }
+        token
+    }
+
+    /// Token cancelled when the job is stopped. Nothing is registered for a key without a message
+    /// being processed, e.g. a tool run outside of a job, whose token is never cancelled.
+    pub fn cancellation_token(&self, key: &str) -> CancellationToken {
+        self.cancellation_tokens
+            .get(key)
+            .map(|token| token.child_token())
+            .unwrap_or_default()
+    }
+
+    /// Token for work done for a job outside of processing a message, cancelled by the next stop of
+    /// the job
+    pub fn task_token(&self, key: &str) -> CancellationToken {
+        self.task_tokens.entry(key.to_string()).or_default().child_token()
+    }
+
+    /// Unlike `should_stop`, stays set after a streaming provider resets the stop signal
+    pub fn is_cancelled(&self, key: &str) -> bool {
+        self.cancellation_tokens
+            .get(key)
+            .map_or(false, |token| token.is_cancelled())
+    }
+
+    /// Forgets the token of a job once its message is processed
+    pub fn finish(&self, key: &str) {
+        self.cancellation_tokens.remove(key);
+        self.running_messages.remove(key);
     }
 
     pub fn reset(&self, key: &str) {
@@ -49,4 +130,73 @@ mod tests {
         // Test non-existent key
         assert!(!stopper.should_stop("non_existent_job"));
     }
+
+    #[test]
+    fn test_llm_stopper_cancellation_token() {
+        let stopper = LLMStopper::new();
+        let job_id = "test_job";
+
+        let token = stopper.start(job_id, None);
+        let child = stopper.cancellation_token(job_id);
+        assert!(!child.is_cancelled());
+
+        // Stopping cancels every holder of the token, even after the signal is reset
+        stopper.stop(job_id);
+        stopper.reset(job_id);
+        assert!(token.is_cancelled() && child.is_cancelled());
+        assert!(stopper.is_cancelled(job_id));
+
+        // The next message of the job starts uncancelled
+        let token = stopper.start(job_id, None);
+        assert!(!token.is_cancelled());
+        assert!(!stopper.is_cancelled(job_id));
+
+        stopper.finish(job_id);
+        assert!(!stopper.is_cancelled(job_id));
+        assert!(stopper.cancellation_tokens.is_empty());
+
+        // Tokens of jobs that aren't running are not kept
+        let token = stopper.cancellation_token("other_job");
+        assert!(!token.is_cancelled());
+        assert!(stopper.cancellation_tokens.is_empty());
+    }
+
+    #[test]
+    fn test_llm_stopper_stop_message() {
+        let stopper = LLMStopper::new();
+        let job_id = "test_job";
+
+        // A message stopped while another one is processed is cancelled once it starts
+        let token = stopper.start(job_id, Some("first"));
+        stopper.stop_message(job_id, "second");
+        assert!(!token.is_cancelled());
+        stopper.finish(job_id);
+        let token = stopper.start(job_id, Some("second"));
+        assert!(token.is_cancelled());
+        stopper.finish(job_id);
+        assert!(stopper.stopped_messages.is_empty());
+
+        // The message being processed is stopped right away
+        let token = stopper.start(job_id, Some("third"));
+        stopper.stop_message(job_id, "third");
+        assert!(token.is_cancelled());
+        assert!(stopper.stopped_messages.is_empty());
+    }
+
+    #[test]
+    fn test_llm_stopper_task_token() {
+        let stopper = LLMStopper::new();
+        let job_id = "test_job";
+
+        // Stopping a message leaves the other work of the job running
+        let task = stopper.task_token(job_id);
+        let message = stopper.start(job_id, Some("message"));
+        stopper.stop_message(job_id, "message");
+        assert!(message.is_cancelled() && !task.is_cancelled());
+
+        // Stopping the job cancels it, later tasks start uncancelled
+        stopper.stop(job_id);
+        assert!(task.is_cancelled());
+        assert!(!stopper.task_token(job_id).is_cancelled());
+    }
 }
@@ -3,7 +3,12 @@ pub mod llm_provider;
 pub mod llm_provider_to_serialization;
 pub mod error;
 pub mod execution;
+pub mod job_budget;
+pub mod job_checkpoint;
+pub mod job_limiter;
 pub mod job_manager;
+pub mod job_pipeline_runner;
+pub mod job_retry_policy;
 pub mod parsing_helper;
 pub mod providers;
 pub mod job_callback_manager;
@@ -29,7 +29,7 @@ use crate::llm_provider::{
 };
 use crate::managers::model_capabilities_manager::PromptResultEnum;
 
-use super::openai::truncate_image_url_in_payload;
+use super::openai::{error_status_response, truncate_image_url_in_payload};
 use super::shared::claude_api::claude_prepare_messages;
 use super::LLMService;
 
@@ -178,12 +178,7 @@ async fn handle_streaming_response(
 
     // Check if it's an error response
     if !res.status().is_success() {
-        let error_json: serde_json::Value = res.json().await?;
-        if let Some(error) = error_json.get("error") {
-            let error_message = error.get("message").and_then(|m| m.as_str()).unwrap_or("Unknown error");
-            return Err(LLMProviderError::APIError("AI Provider API Error: ".to_string() + error_message));
-        }
-        return Err(LLMProviderError::APIError("AI Provider API Error: Unknown error occurred".to_string()));
+        return Err(error_status_response(res, &payload).await);
     }
 
     // Check content type to determine if it's a stream
@@ -471,14 +466,7 @@ async fn handle_non_streaming_response(
 
                 // Check if it's an error response
                 if !res.status().is_success() {
-                    let error_json: serde_json::Value = res.json().await?;
-                    if let Some(error) = error_json.get("error") {
-                        let error_message = error.get("message")
-                            .and_then(|m| m.as_str())
-                            .unwrap_or("Unknown error");
-                        return Err(LLMProviderError::APIError(error_message.to_string()));
-                    }
-                    return Err(LLMProviderError::APIError("Unknown error occurred".to_string()));
+                    return Err(error_status_response(res, &payload).await);
                 }
 
                 let response_body = res.text().await?;
@@ -3,6 +3,7 @@ use std::sync::Arc;
 
 use super::super::error::LLMProviderError;
 use super::shared::openai_api_deprecated::{MessageContent, OpenAIResponse};
+use super::openai::error_status_response;
 use super::LLMService;
 use crate::llm_provider::execution::chains::inference_chain_trait::{FunctionCall, LLMInferenceResponse};
 use crate::llm_provider::llm_stopper::LLMStopper;
@@ -172,12 +173,7 @@ async fn handle_streaming_response(
 
     // Check if it's an error response
     if !res.status().is_success() {
-        let error_json: serde_json::Value = res.json().await?;
-        if let Some(error) = error_json.get("error") {
-            let error_message = error.get("message").and_then(|m| m.as_str()).unwrap_or("Unknown error");
-            return Err(LLMProviderError::APIError("AI Provider API Error: ".to_string() + error_message));
-        }
-        return Err(LLMProviderError::APIError("AI Provider API Error: Unknown error occurred".to_string()));
+        return Err(error_status_response(res, &payload).await);
     }
 
     // Check content type to determine if it's a stream
@@ -529,6 +525,10 @@ async fn handle_non_streaming_response(
             },
             response = &mut response_fut => {
                 let res = response?;
+                if !res.status().is_success() {
+                    return Err(error_status_response(res, &payload).await);
+                }
+
                 let response_text = res.text().await?;
                 let data_resp: Result<JsonValue, _> = serde_json::from_str(&response_text);
 
@@ -283,29 +283,20 @@ pub async fn parse_openai_stream_chunk(
                     // Handle error that's a direct string
                     if let Some(error_str) = error_obj.as_str() {
                         // Check if there's also a code field at the top level
-                        let code = json_data.get("code").and_then(|c| c.as_str()).unwrap_or("");
-
+                        let error = stream_error(json_data.get("code"), error_str, "");
                         buffer.clear();
-                        if !code.is_empty() {
-                            return Err(LLMProviderError::APIError(format!("{}: {}", code, error_str)));
-                        } else {
-                            return Err(LLMProviderError::APIError(error_str.to_string()));
-                        }
+                        return Err(error);
                     }
 
                     // Handle error as an object with code and message fields
-                    let code = error_obj
-                        .get("code")
-                        .and_then(|c| c.as_str())
-                        .unwrap_or("Unknown code")
-                        .to_string();
                     let msg = error_obj
                         .get("message")
                         .and_then(|m| m.as_str())
                         .unwrap_or("Unknown error");
+                    let error = stream_error(error_obj.get("code"), msg, "Unknown code");
                     // Clear the buffer since we've consumed it
                     buffer.clear();
-                    return Err(LLMProviderError::APIError(format!("{}: {}", code, msg)));
+                    return Err(error);
                 }
                 // Once parsed, clear the buffer since we've consumed it.
                 buffer.clear();
@@ -609,6 +600,64 @@ pub async fn parse_openai_stream_chunk(
     Ok(error_message)
 }
 
+/// Error sent as the whole body of a stream. Providers like OpenRouter give the HTTP status of
+/// the failure as its code, which is kept so that rate limits and server errors are retried.
+fn stream_error(code: Option<&JsonValue>, message: &str, default_code: &str) -> LLMProviderError {
+    if let Some(status) = code.and_then(|c| c.as_u64()).and_then(|c| u16::try_from(c).ok()) {
+        return LLMProviderError::APIStatusError {
+            status,
+            message: format!("{}: {}", status, message),
+        };
+    }
+    match code.and_then(|c| c.as_str()).unwrap_or(default_code) {
+        "" => LLMProviderError::APIError(message.to_string()),
+        code => LLMProviderError::APIError(format!("{}: {}", code, message)),
+    }
+}
+
+/// Error of a response with an error status, which keeps the status so that rate limits and
+/// server errors are retried
+pub async fn error_status_response(res: reqwest::Response, payload: &JsonValue) -> LLMProviderError {
+    let status = res.status().as_u16();
+    let error_text = res.text().await.unwrap_or_default();
+    let error_json = serde_json::from_str::<JsonValue>(&error_text).unwrap_or_default();
+    let code = error_json.get("code").and_then(|c| c.as_str()).unwrap_or("");
+
+    if status == 429
+        && code == "QUOTA_EXCEEDED"
+        && payload.get("model").and_then(|m| m.as_str()).map_or(false, |model| {
+            model == "FREE_TEXT_INFERENCE"
+                || model == "STANDARD_TEXT_INFERENCE"
+                || model == "PREMIUM_TEXT_INFERENCE"
+                || model == "CODE_GENERATOR"
+                || model == "CODE_GENERATOR_NO_FEEDBACK"
+        })
+    {
+        let error_msg = error_json
+            .get("error")
+            .and_then(|e| e.as_str())
+            .unwrap_or("Daily quota exceeded")
+            .to_string();
+        return LLMProviderError::LLMServiceInferenceLimitReached(error_msg);
+    }
+
+    let message = match error_json.get("error") {
+        // Case 1: error is an object with message field (standard OpenAI format)
+        Some(error) if error.is_object() => {
+            let error_message = error.get("message").and_then(|m| m.as_str()).unwrap_or("Unknown error");
+            "AI Provider API Error: ".to_string() + error_message
+        }
+        // Case 2: error is a string directly
+        Some(JsonValue::String(error_message)) if !code.is_empty() => {
+            format!("AI Provider API Error ({}): {}", code, error_message)
+        }
+        Some(JsonValue::String(error_message)) => "AI Provider API Error: ".to_string() + error_message,
+        // Fall back to generic error if we can't parse the specific format
+        _ => "AI Provider API Error: Unknown error occurred".to_string(),
+    };
+    LLMProviderError::APIStatusError { status, message }
+}
+
 pub async fn handle_streaming_response(
     client: &Client,
     url: String,
@@ -678,33 +727,7 @@ pub async fn handle_streaming_response(
 
     // Check if it's an error response
     if !res.status().is_success() {
-        let error_json: serde_json::Value = res.json().await?;
-
-        // Case 1: error is an object with message field (standard OpenAI format)
-        if let Some(error) = error_json.get("error") {
-            if error.is_object() {
-                let error_message = error.get("message").and_then(|m| m.as_str()).unwrap_or("Unknown error");
-                return Err(LLMProviderError::APIError(
-                    "AI Provider API Error: ".to_string() + error_message,
-                ));
-            } else if error.is_string() {
-                // Case 2: error is a string directly
-                let error_message = error.as_str().unwrap_or("Unknown error");
-                let code = error_json.get("code").and_then(|c| c.as_str()).unwrap_or("");
-                let error_prefix = if !code.is_empty() {
-                    format!("AI Provider API Error ({}): ", code)
-                } else {
-                    "AI Provider API Error: ".to_string()
-                };
-
-                return Err(LLMProviderError::APIError(error_prefix + error_message));
-            }
-        }
-
-        // Fall back to generic error if we can't parse the specific format
-        return Err(LLMProviderError::APIError(
-            "AI Provider API Error: Unknown error occurred".to_string(),
-        ));
+        return Err(error_status_response(res, &payload).await);
     }
 
     // Check content type to determine if it's a stream
@@ -731,34 +754,6 @@ pub async fn handle_streaming_response(
         ));
     }
 
-    // Check for 429 status code
-    if res.status() == 429 {
-        let error_text = res.text().await?;
-        if let Ok(error_json) = serde_json::from_str::<JsonValue>(&error_text) {
-            if let Some(code) = error_json.get("code").and_then(|c| c.as_str()) {
-                if code == "QUOTA_EXCEEDED"
-                    && payload.get("model").and_then(|m| m.as_str()).map_or(false, |model| {
-                        model == "FREE_TEXT_INFERENCE"
-                            || model == "STANDARD_TEXT_INFERENCE"
-                            || model == "PREMIUM_TEXT_INFERENCE"
-                            || model == "CODE_GENERATOR"
-                            || model == "CODE_GENERATOR_NO_FEEDBACK"
-                    })
-                {
-                    let error_msg = error_json
-                        .get("error")
-                        .and_then(|e| e.as_str())
-                        .unwrap_or("Daily quota exceeded")
-                        .to_string();
-                    return Err(LLMProviderError::LLMServiceInferenceLimitReached(error_msg));
-                }
-            }
-        }
-        return Err(LLMProviderError::LLMServiceUnexpectedError(
-            "Rate limit exceeded".to_string(),
-        ));
-    }
-
     let mut stream = res.bytes_stream();
     let mut response_text = String::new();
     let mut buffer = String::new();
@@ -977,28 +972,8 @@ pub async fn handle_non_streaming_response(
             response = &mut response_fut => {
                 let res = response?;
 
-                // Check for 429 status code
-                if res.status() == 429 {
-                    let error_text = res.text().await?;
-                    if let Ok(error_json) = serde_json::from_str::<JsonValue>(&error_text) {
-                        if let Some(code) = error_json.get("code").and_then(|c| c.as_str()) {
-                            if code == "QUOTA_EXCEEDED" &&
-                               payload.get("model").and_then(|m| m.as_str()).map_or(false, |model| {
-                                   model == "FREE_TEXT_INFERENCE" ||
-                                   model == "STANDARD_TEXT_INFERENCE" ||
-                                   model == "PREMIUM_TEXT_INFERENCE" ||
-                                   model == "CODE_GENERATOR" ||
-                                   model == "CODE_GENERATOR_NO_FEEDBACK"
-                               }) {
-                                let error_msg = error_json.get("error")
-                                    .and_then(|e| e.as_str())
-                                    .unwrap_or("Daily quota exceeded")
-                                    .to_string();
-                                return Err(LLMProviderError::LLMServiceInferenceLimitReached(error_msg));
-                            }
-                        }
-                    }
-                    return Err(LLMProviderError::LLMServiceUnexpectedError("Rate limit exceeded".to_string()));
+                if !res.status().is_success() {
+                    return Err(error_status_response(res, &payload).await);
                 }
 
                 let response_text = res.text().await?;
@@ -2151,6 +2126,103 @@ mod tests {
         });
         assert_eq!(arguments, expected_args);
     }
+
+    #[tokio::test]
+    async fn test_rate_limited_and_failing_responses_are_transient() {
+        let mut server = mockito::Server::new_async().await;
+        let rate_limited = server
+            .mock("POST", "/rate_limited")
+            .with_status(429)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"error": {"message": "Rate limit reached for gpt-4o-mini", "type": "requests", "code": "rate_limit_exceeded"}}"#)
+            .expect(2)
+            .create_async()
+            .await;
+
+        let client = Client::new();
+        let url = format!("{}/rate_limited", server.url());
+        let payload = json!({ "model": "gpt-4o-mini", "messages": [] });
+        let stopper = Arc::new(LLMStopper::new());
+
+        let result = handle_non_streaming_response(
+            &client,
+            url.clone(),
+            payload.clone(),
+            "api_key".to_string(),
+            None,
+            stopper.clone(),
+            None,
+            None,
+            None,
+        )
+        .await;
+        let error = result.unwrap_err();
+        assert!(matches!(error, LLMProviderError::APIStatusError { status: 429, .. }));
+        assert_eq!(
+            error.to_string(),
+            "AI Provider API Error: Rate limit reached for gpt-4o-mini"
+        );
+        assert!(error.is_transient());
+
+        let result = handle_streaming_response(
+            &client,
+            url.clone(),
+            payload.clone(),
+            "api_key".to_string(),
+            None,
+            None,
+            stopper.clone(),
+            "session_id".to_string(),
+            None,
+            None,
+        )
+        .await;
+        assert!(result.unwrap_err().is_transient());
+        rate_limited.assert_async().await;
+
+        // Server errors without a JSON body are retried too, bad requests are not
+        server
+            .mock("POST", "/unavailable")
+            .with_status(503)
+            .with_body("Service Unavailable")
+            .create_async()
+            .await;
+        let result = handle_streaming_response(
+            &client,
+            format!("{}/unavailable", server.url()),
+            payload.clone(),
+            "api_key".to_string(),
+            None,
+            None,
+            stopper.clone(),
+            "session_id".to_string(),
+            None,
+            None,
+        )
+        .await;
+        assert!(result.unwrap_err().is_transient());
+
+        server
+            .mock("POST", "/bad_request")
+            .with_status(400)
+            .with_body(r#"{"error": {"message": "Invalid model"}}"#)
+            .create_async()
+            .await;
+        let result = handle_streaming_response(
+            &client,
+            format!("{}/bad_request", server.url()),
+            payload,
+            "api_key".to_string(),
+            None,
+            None,
+            stopper,
+            "session_id".to_string(),
+            None,
+            None,
+        )
+        .await;
+        assert!(!result.unwrap_err().is_transient());
+    }
 }
 
 /// Log the response to a file if LOG_REQUESTS environment variable is set to true
@@ -10,13 +10,17 @@ mod wallet;
 mod tools;
 
 use runner::{initialize_node, run_node_tasks};
-use shinkai_message_primitives::shinkai_utils::shinkai_logging::init_default_tracing;
+use tools::tool_launcher::run_if_launcher;
+use utils::telemetry::init_tracing;
 
 #[cfg(feature = "console")]
 use console_subscriber;
 
 #[tokio::main]
 pub async fn main() {
+    // Started as the launcher of a tool runtime instead of as a node
+    run_if_launcher();
+
     // Initialize logging based on features
     #[cfg(feature = "console")] {
         // When using console subscriber, we don't need env_logger
@@ -28,7 +32,7 @@ pub async fn main() {
         env_logger::Builder::from_env(env_logger::Env::default())
             .format_timestamp_millis()
             .init();
-        init_default_tracing();
+        init_tracing();
     }
 
     let result = initialize_node().await.unwrap();
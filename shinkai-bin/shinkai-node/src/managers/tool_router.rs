@@ -6,15 +6,19 @@ use crate::llm_provider::error::LLMProviderError;
 use crate::llm_provider::execution::chains::generic_chain::generic_inference_chain::GenericInferenceChain;
 use crate::llm_provider::execution::chains::inference_chain_trait::{FunctionCall, InferenceChainContextTrait};
 use crate::llm_provider::job_manager::JobManager;
+use crate::network::node_metrics::node_metrics;
+use crate::network::webhook_dispatcher::emit_webhook_event;
 use crate::network::Node;
+use crate::tools::egress_proxy::{check_egress_session, ToolNetwork, EGRESS_SESSION_ENV};
 use crate::tools::tool_definitions::definition_generation::{generate_tool_definitions, get_rust_tools};
 use crate::tools::tool_execution::execute_agent_dynamic::execute_agent_tool;
 use crate::tools::tool_execution::execution_coordinator::override_tool_config;
 use crate::tools::tool_execution::execution_custom::try_to_execute_rust_tool;
 use crate::tools::tool_execution::execution_header_generator::{check_tool, generate_execution_environment};
+use crate::tools::tool_launcher::ToolProcesses;
 use crate::utils::environment::fetch_node_environment;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{json, Value};
 use shinkai_embedding::embedding_generator::EmbeddingGenerator;
 use shinkai_fs::shinkai_file_manager::ShinkaiFileManager;
 use shinkai_message_primitives::schemas::indexable_version::IndexableVersion;
@@ -29,6 +33,7 @@ use shinkai_message_primitives::schemas::shinkai_tool_offering::{
 use shinkai_message_primitives::schemas::shinkai_tools::CodeLanguage;
 use shinkai_message_primitives::schemas::tool_router_key::ToolRouterKey;
 use shinkai_message_primitives::schemas::wallet_mixed::{Asset, NetworkIdentifier};
+use shinkai_message_primitives::schemas::webhook::WebhookEventType;
 use shinkai_message_primitives::schemas::ws_types::{PaymentMetadata, WSMessageType, WidgetMetadata};
 use shinkai_message_primitives::shinkai_message::shinkai_message_schemas::{AssociatedUI, WSTopic};
 use shinkai_message_primitives::shinkai_utils::shinkai_logging::{shinkai_log, ShinkaiLogLevel, ShinkaiLogOption};
@@ -544,6 +549,15 @@ impl ToolRouter {
         }
     }
 
+    /// Returns the tool as it was stored in a specific (pinned) revision
+    pub async fn get_tool_by_revision(&self, name: &str, revision: u64) -> Result<Option<ShinkaiTool>, ToolError> {
+        match self.sqlite_manager.get_tool_revision(name, revision) {
+            Ok(tool_revision) => Ok(Some(tool_revision.tool)),
+            Err(SqliteManagerError::ToolRevisionNotFound(_)) => Ok(None),
+            Err(e) => Err(ToolError::DatabaseError(e.to_string())),
+        }
+    }
+
     pub async fn vector_search_enabled_tools(
         &self,
         query: &str,
@@ -589,12 +603,61 @@ impl ToolRouter {
         Ok(tool_headers)
     }
 
+    #[tracing::instrument(
+        name = "tool",
+        skip_all,
+        fields(tool = %shinkai_tool.tool_router_key().to_string_without_version())
+    )]
     pub async fn call_function(
         &self,
         function_call: FunctionCall,
         context: &dyn InferenceChainContextTrait,
         shinkai_tool: &ShinkaiTool,
         node_name: ShinkaiName,
+    ) -> Result<ToolCallFunctionResponse, LLMProviderError> {
+        let start = Instant::now();
+        // Config values are stored sealed and only decrypted to run the tool
+        let mut revealed_tool = shinkai_tool.clone();
+        self.sqlite_manager.reveal_tool_config(&mut revealed_tool)?;
+        // Stopping the job drops the call, which kills the process running the tool. The partial answer
+        // of the job is made from the checkpoint of its chain, with the tool calls that finished.
+        let cancellation_token = context
+            .llm_stopper()
+            .cancellation_token(&context.full_job().conversation_inbox_name.get_value());
+        let result = tokio::select! {
+            biased;
+            _ = cancellation_token.cancelled() => Err(LLMProviderError::Cancelled(String::new())),
+            result = self.call_function_inner(function_call, context, &revealed_tool, node_name) => result,
+        };
+        node_metrics().record_tool_execution(
+            &shinkai_tool.tool_router_key().to_string_without_version(),
+            start.elapsed(),
+            result.is_ok(),
+        );
+        let failed = result
+            .as_ref()
+            .err()
+            .filter(|e| !matches!(e, LLMProviderError::Cancelled(_)));
+        if let Some(e) = failed {
+            emit_webhook_event(
+                &self.sqlite_manager,
+                WebhookEventType::ToolFailed,
+                json!({
+                    "tool_router_key": shinkai_tool.tool_router_key().to_string_without_version(),
+                    "job_id": context.full_job().job_id(),
+                    "error": e.to_string(),
+                }),
+            );
+        }
+        result
+    }
+
+    async fn call_function_inner(
+        &self,
+        function_call: FunctionCall,
+        context: &dyn InferenceChainContextTrait,
+        shinkai_tool: &ShinkaiTool,
+        node_name: ShinkaiName,
     ) -> Result<ToolCallFunctionResponse, LLMProviderError> {
         let _function_name = function_call.name.clone();
         let function_args = function_call.arguments.clone();
@@ -687,7 +750,8 @@ impl ToolRouter {
                             ToolError::ExecutionError(format!("Failed to generate tool definitions: {:?}", e))
                         })?;
 
-                let envs = generate_execution_environment(
+                let mut envs = generate_execution_environment(
+                    context.db().read_api_v2_key().unwrap_or_default().unwrap_or_default(),
                     context.db(),
                     context.agent().clone().get_id().to_string(),
                     tool_id.clone(),
@@ -696,6 +760,7 @@ impl ToolRouter {
                     shinkai_tool.tool_router_key().to_string_without_version().clone(),
                     app_id.clone(),
                     &python_tool.oauth,
+                    ToolNetwork::Declared(python_tool.network_allowlist.clone()),
                 )
                 .await?;
 
@@ -707,6 +772,11 @@ impl ToolRouter {
                     &python_tool.oauth,
                 )?;
 
+                // Stopping the job drops the call, which kills the runtime and the processes it started
+                let tool_processes = ToolProcesses::start();
+                envs.extend(tool_processes.envs());
+
+                let egress_session = envs.get(EGRESS_SESSION_ENV).cloned();
                 let result = python_tool
                     .run(
                         envs,
@@ -723,6 +793,73 @@ impl ToolRouter {
                         Some(tool_id),
                         Some(all_files),
                     )
+                    .await;
+                tool_processes.finish();
+                let result = check_egress_session(egress_session, result)?;
+                let result_str = serde_json::to_string(&result)
+                    .map_err(|e| LLMProviderError::FunctionExecutionError(e.to_string()))?;
+                return Ok(ToolCallFunctionResponse {
+                    response: result_str,
+                    function_call,
+                });
+            }
+            ShinkaiTool::Wasm(wasm_tool, _is_enabled) => {
+                let node_env = fetch_node_environment();
+                let node_storage_path = node_env
+                    .node_storage_path
+                    .clone()
+                    .ok_or_else(|| ToolError::ExecutionError("Node storage path is not set".to_string()))?;
+
+                // Get app_id from Cron UI if present, otherwise use job_id
+                let app_id = match context.full_job().associated_ui().as_ref() {
+                    Some(AssociatedUI::Cron(cron_id)) => cron_id.clone(),
+                    _ => context.full_job().job_id().to_string(),
+                };
+
+                let tool_id = shinkai_tool.tool_router_key().to_string_without_version().clone();
+                let envs = generate_execution_environment(
+                    context.db().read_api_v2_key().unwrap_or_default().unwrap_or_default(),
+                    context.db(),
+                    context.agent().clone().get_id().to_string(),
+                    tool_id.clone(),
+                    app_id.clone(),
+                    agent_id,
+                    shinkai_tool.tool_router_key().to_string_without_version().clone(),
+                    app_id.clone(),
+                    &wasm_tool.oauth,
+                    ToolNetwork::Unmanaged,
+                )
+                .await?;
+
+                check_tool(
+                    shinkai_tool.tool_router_key().to_string_without_version().clone(),
+                    wasm_tool.config.clone(),
+                    function_args.clone(),
+                    wasm_tool.input_args.clone(),
+                    &wasm_tool.oauth,
+                )?;
+
+                // Only the capabilities approved by the user are granted
+                let grant = context
+                    .db()
+                    .get_wasm_tool_grant(&tool_id)
+                    .map_err(|e| ToolError::ExecutionError(format!("Failed to read WASM tool grant: {}", e)))?;
+
+                let result = wasm_tool
+                    .run(
+                        envs,
+                        node_env.api_listen_address.ip().to_string(),
+                        node_env.api_listen_address.port(),
+                        function_args,
+                        function_config_vec,
+                        node_storage_path,
+                        app_id.clone(),
+                        tool_id.clone(),
+                        node_name,
+                        Some(tool_id),
+                        Some(all_files),
+                        grant,
+                    )
                     .await?;
                 let result_str = serde_json::to_string(&result)
                     .map_err(|e| LLMProviderError::FunctionExecutionError(e.to_string()))?;
@@ -867,7 +1004,8 @@ impl ToolRouter {
                             ToolError::ExecutionError(format!("Failed to generate tool definitions: {:?}", e))
                         })?;
 
-                let envs = generate_execution_environment(
+                let mut envs = generate_execution_environment(
+                    context.db().read_api_v2_key().unwrap_or_default().unwrap_or_default(),
                     context.db(),
                     context.agent().clone().get_id().to_string(),
                     app_id.clone(),
@@ -876,6 +1014,7 @@ impl ToolRouter {
                     shinkai_tool.tool_router_key().to_string_without_version().clone(),
                     app_id.clone(),
                     &deno_tool.oauth,
+                    ToolNetwork::Declared(deno_tool.network_allowlist.clone()),
                 )
                 .await?;
 
@@ -887,6 +1026,11 @@ impl ToolRouter {
                     &deno_tool.oauth,
                 )?;
 
+                // Stopping the job drops the call, which kills the runtime and the processes it started
+                let tool_processes = ToolProcesses::start();
+                envs.extend(tool_processes.envs());
+
+                let egress_session = envs.get(EGRESS_SESSION_ENV).cloned();
                 let result = deno_tool
                     .run(
                         envs,
@@ -903,7 +1047,9 @@ impl ToolRouter {
                         Some(tool_id),
                         Some(all_files),
                     )
-                    .await?;
+                    .await;
+                tool_processes.finish();
+                let result = check_egress_session(egress_session, result)?;
 
                 let result_str = serde_json::to_string(&result)
                     .map_err(|e| LLMProviderError::FunctionExecutionError(e.to_string()))?;
@@ -1195,7 +1341,8 @@ impl ToolRouter {
             return Err(LLMProviderError::FunctionNotFound(js_tool_name.to_string()));
         }
 
-        let shinkai_tool = shinkai_tool.unwrap();
+        let mut shinkai_tool = shinkai_tool.unwrap();
+        self.sqlite_manager.reveal_tool_config(&mut shinkai_tool)?;
         let function_config = shinkai_tool.get_config_from_env();
         let function_config_vec: Vec<ToolConfig> = function_config.into_iter().collect();
 
@@ -1232,7 +1379,9 @@ impl ToolRouter {
             _ => return Err(LLMProviderError::FunctionNotFound(js_tool_name.to_string())),
         };
 
+        // Run for another node, which gets no access to the node API
         let env = generate_execution_environment(
+            String::new(),
             self.sqlite_manager.clone(),
             "".to_string(),
             format!("xid-{}", app_id),
@@ -1242,6 +1391,7 @@ impl ToolRouter {
             // TODO: Pass data from the API
             "".to_string(),
             &oauth,
+            ToolNetwork::Declared(shinkai_tool.get_network_allowlist()),
         )
         .await
         .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
@@ -1254,6 +1404,7 @@ impl ToolRouter {
             &oauth,
         )?;
 
+        let egress_session = env.get(EGRESS_SESSION_ENV).cloned();
         let result = js_tool
             .run(
                 env,
@@ -1271,7 +1422,8 @@ impl ToolRouter {
                 Some(tool_id),
                 None,
             )
-            .await?;
+            .await;
+        let result = check_egress_session(egress_session, result)?;
         let result_str =
             serde_json::to_string(&result).map_err(|e| LLMProviderError::FunctionExecutionError(e.to_string()))?;
 
@@ -1,8 +1,13 @@
 use std::sync::Arc;
 
+use serde_json::json;
 use shinkai_http_api::node_commands::NodeCommand;
+use shinkai_message_primitives::schemas::audit_log::AuditAction;
 
-use crate::{network::Node, utils::environment::fetch_node_environment};
+use crate::{
+    network::{v2_api::api_v2_commands_audit::audit_fields, Node},
+    utils::environment::fetch_node_environment,
+};
 
 impl Node {
     pub async fn handle_command(&self, command: NodeCommand) {
@@ -244,6 +249,14 @@ impl Node {
                 let node_name = self.node_name.node_name.clone();
                 let signing_secret_key = self.identity_secret_key.clone();
                 let node_env = fetch_node_environment();
+                let res = Node::audited(
+                    db_clone.clone(),
+                    &bearer,
+                    AuditAction::AgentCreated,
+                    None,
+                    json!({ "url": url }),
+                    res,
+                );
                 tokio::spawn(async move {
                     let _ =
                         Node::v2_api_import_agent(db_clone, bearer, url, node_name, node_env, signing_secret_key, res)
@@ -253,6 +266,14 @@ impl Node {
             NodeCommand::V2ApiImportAgentZip { bearer, file_data, res } => {
                 let db_clone = Arc::clone(&self.db);
                 let node_env = fetch_node_environment();
+                let res = Node::audited(
+                    db_clone.clone(),
+                    &bearer,
+                    AuditAction::AgentCreated,
+                    None,
+                    json!({ "zip_size": file_data.len() }),
+                    res,
+                );
                 tokio::spawn(async move {
                     let _ = Node::v2_api_import_agent_zip(db_clone, bearer, node_env, file_data, res).await;
                 });
@@ -1600,6 +1621,14 @@ impl Node {
                 let db_clone = Arc::clone(&self.db);
 
                 let identity_manager_clone = self.identity_manager.clone();
+                let res = Node::audited(
+                    db_clone.clone(),
+                    &bearer,
+                    AuditAction::FileDeleted,
+                    Some(payload.path.clone()),
+                    json!({ "folder": true }),
+                    res,
+                );
                 tokio::spawn(async move {
                     let _ = Node::v2_delete_folder(db_clone, identity_manager_clone, payload, bearer, res).await;
                 });
@@ -1609,6 +1638,14 @@ impl Node {
                 let db_clone = Arc::clone(&self.db);
 
                 let identity_manager_clone = self.identity_manager.clone();
+                let res = Node::audited(
+                    db_clone.clone(),
+                    &bearer,
+                    AuditAction::FileDeleted,
+                    Some(payload.path.clone()),
+                    json!({ "folder": false }),
+                    res,
+                );
                 tokio::spawn(async move {
                     let _ = Node::v2_delete_item(db_clone, identity_manager_clone, payload, bearer, res).await;
                 });
@@ -1725,11 +1762,13 @@ impl Node {
                 let db_clone = Arc::clone(&self.db);
                 let identity_manager_clone = self.identity_manager.clone();
                 let embedding_generator_clone = self.embedding_generator.clone();
+                let stopper_clone = self.llm_stopper.clone();
                 tokio::spawn(async move {
                     let _ = Node::v2_upload_file_to_job(
                         db_clone,
                         identity_manager_clone,
                         Arc::new(embedding_generator_clone),
+                        stopper_clone,
                         bearer,
                         job_id,
                         filename,
@@ -1776,6 +1815,14 @@ impl Node {
                 let job_manager_clone = self.job_manager.clone();
                 let identity_secret_key_clone = self.identity_secret_key.clone();
                 let ws_manager_trait = self.ws_manager_trait.clone();
+                let res = Node::audited(
+                    db_clone.clone(),
+                    &bearer,
+                    AuditAction::LlmProviderCreated,
+                    Some(agent.id.clone()),
+                    json!({ "model": agent.model, "external_url": agent.external_url }),
+                    res,
+                );
                 tokio::spawn(async move {
                     let _ = Node::v2_api_add_llm_provider(
                         db_clone,
@@ -1855,6 +1902,14 @@ impl Node {
             } => {
                 let db_clone = Arc::clone(&self.db);
                 let identity_manager_clone = self.identity_manager.clone();
+                let res = Node::audited(
+                    db_clone.clone(),
+                    &bearer,
+                    AuditAction::LlmProviderRemoved,
+                    Some(llm_provider_id.clone()),
+                    json!({}),
+                    res,
+                );
                 tokio::spawn(async move {
                     let _ = Node::v2_api_remove_llm_provider(
                         db_clone,
@@ -1869,6 +1924,14 @@ impl Node {
             NodeCommand::V2ApiModifyLlmProvider { bearer, agent, res } => {
                 let db_clone = Arc::clone(&self.db);
                 let identity_manager_clone = self.identity_manager.clone();
+                let res = Node::audited(
+                    db_clone.clone(),
+                    &bearer,
+                    AuditAction::LlmProviderUpdated,
+                    Some(agent.id.clone()),
+                    json!({ "model": agent.model, "external_url": agent.external_url }),
+                    res,
+                );
                 tokio::spawn(async move {
                     let _ =
                         Node::v2_api_modify_llm_provider(db_clone, identity_manager_clone, bearer, agent, res).await;
@@ -1967,6 +2030,14 @@ impl Node {
                 res,
             } => {
                 let db_clone = Arc::clone(&self.db);
+                let res = Node::audited(
+                    db_clone.clone(),
+                    &bearer,
+                    AuditAction::ToolConfigChanged,
+                    Some(tool_key.clone()),
+                    json!({ "fields": audit_fields(&payload) }),
+                    res,
+                );
                 tokio::spawn(async move {
                     let _ = Node::v2_api_set_shinkai_tool(db_clone, bearer, tool_key, payload, res).await;
                 });
@@ -1978,6 +2049,14 @@ impl Node {
             } => {
                 let db_clone = Arc::clone(&self.db);
                 let node_env = fetch_node_environment();
+                let res = Node::audited(
+                    db_clone.clone(),
+                    &bearer,
+                    AuditAction::ToolInstalled,
+                    Some(shinkai_tool.tool.tool_router_key().to_string_without_version()),
+                    json!({ "version": shinkai_tool.tool.version() }),
+                    res,
+                );
                 tokio::spawn(async move {
                     let _ = Node::v2_api_add_shinkai_tool(db_clone, bearer, node_env, shinkai_tool, res).await;
                 });
@@ -2080,6 +2159,14 @@ impl Node {
             } => {
                 let db_clone = Arc::clone(&self.db);
                 let wallet_manager_clone = self.wallet_manager.clone();
+                let res = Node::audited(
+                    db_clone.clone(),
+                    &bearer,
+                    AuditAction::WalletRestored,
+                    None,
+                    json!({ "network": network, "role": role }),
+                    res,
+                );
                 tokio::spawn(async move {
                     let _ = Node::v2_api_restore_local_ethers_wallet(
                         db_clone,
@@ -2101,6 +2188,14 @@ impl Node {
             } => {
                 let db_clone = Arc::clone(&self.db);
                 let wallet_manager_clone = self.wallet_manager.clone();
+                let res = Node::audited(
+                    db_clone.clone(),
+                    &bearer,
+                    AuditAction::WalletCreated,
+                    None,
+                    json!({ "network": network, "role": role }),
+                    res,
+                );
                 tokio::spawn(async move {
                     let _ = Node::v2_api_create_local_ethers_wallet(
                         db_clone,
@@ -2124,6 +2219,14 @@ impl Node {
                 let db_clone = Arc::clone(&self.db);
                 let wallet_manager_clone = self.wallet_manager.clone();
                 let node_name = self.node_name.clone();
+                let res = Node::audited(
+                    db_clone.clone(),
+                    &bearer,
+                    AuditAction::WalletRestored,
+                    Some(wallet_id.clone()),
+                    json!({ "network": network, "role": role }),
+                    res,
+                );
                 tokio::spawn(async move {
                     let _ = Node::v2_api_restore_coinbase_mpc_wallet(
                         db_clone,
@@ -2149,6 +2252,14 @@ impl Node {
                 let db_clone = Arc::clone(&self.db);
                 let wallet_manager_clone = self.wallet_manager.clone();
                 let node_name = self.node_name.clone();
+                let res = Node::audited(
+                    db_clone.clone(),
+                    &bearer,
+                    AuditAction::WalletCreated,
+                    None,
+                    json!({ "network": network, "role": role }),
+                    res,
+                );
                 tokio::spawn(async move {
                     let _ = Node::v2_api_create_coinbase_mpc_wallet(
                         db_clone,
@@ -2205,6 +2316,14 @@ impl Node {
                 let db_clone = Arc::clone(&self.db);
                 let my_agent_payments_manager_clone = self.my_agent_payments_manager.clone();
                 let node_name = self.node_name.clone();
+                let res = Node::audited(
+                    db_clone.clone(),
+                    &bearer,
+                    AuditAction::PaymentMade,
+                    Some(invoice_id.clone()),
+                    json!({}),
+                    res,
+                );
                 tokio::spawn(async move {
                     let _ = Node::v2_api_pay_invoice(
                         db_clone,
@@ -2284,6 +2403,18 @@ impl Node {
             NodeCommand::V2ApiAddAgent { bearer, agent, res } => {
                 let db_clone = Arc::clone(&self.db);
                 let identity_manager_clone = self.identity_manager.clone();
+                let res = Node::audited(
+                    db_clone.clone(),
+                    &bearer,
+                    AuditAction::AgentCreated,
+                    Some(agent.agent_id.clone()),
+                    json!({
+                        "name": agent.name,
+                        "llm_provider_id": agent.llm_provider_id,
+                        "tools": agent.tools.iter().map(|tool| tool.to_string_without_version()).collect::<Vec<_>>(),
+                    }),
+                    res,
+                );
                 tokio::spawn(async move {
                     let _ = Node::v2_api_add_agent(db_clone, identity_manager_clone, bearer, agent, res).await;
                 });
@@ -2291,6 +2422,14 @@ impl Node {
             NodeCommand::V2ApiRemoveAgent { bearer, agent_id, res } => {
                 let db_clone = Arc::clone(&self.db);
                 let node_name = self.node_name.clone();
+                let res = Node::audited(
+                    db_clone.clone(),
+                    &bearer,
+                    AuditAction::AgentRemoved,
+                    Some(agent_id.clone()),
+                    json!({}),
+                    res,
+                );
                 tokio::spawn(async move {
                     let _ = Node::v2_api_remove_agent(db_clone, bearer, node_name, agent_id, res).await;
                 });
@@ -2301,6 +2440,14 @@ impl Node {
                 res,
             } => {
                 let db_clone = Arc::clone(&self.db);
+                let res = Node::audited(
+                    db_clone.clone(),
+                    &bearer,
+                    AuditAction::AgentUpdated,
+                    partial_agent.get("agent_id").and_then(|id| id.as_str()).map(str::to_string),
+                    json!({ "fields": audit_fields(&partial_agent) }),
+                    res,
+                );
                 tokio::spawn(async move {
                     let _ = Node::v2_api_update_agent(db_clone, bearer, partial_agent, res).await;
                 });
@@ -2690,6 +2837,14 @@ impl Node {
                 let node_env = fetch_node_environment();
                 let node_name = self.node_name.node_name.clone();
                 let signing_secret_key = self.identity_secret_key.clone();
+                let res = Node::audited(
+                    db_clone.clone(),
+                    &bearer,
+                    AuditAction::ToolInstalled,
+                    None,
+                    json!({ "url": url }),
+                    res,
+                );
                 tokio::spawn(async move {
                     let _ =
                         Node::v2_api_import_tool(db_clone, bearer, node_env, url, node_name, signing_secret_key, res)
@@ -2699,14 +2854,114 @@ impl Node {
             NodeCommand::V2ApiImportToolZip { bearer, file_data, res } => {
                 let db_clone = Arc::clone(&self.db);
                 let node_env = fetch_node_environment();
+                let node_name = self.node_name.node_name.clone();
+                let signing_secret_key = self.identity_secret_key.clone();
+                let res = Node::audited(
+                    db_clone.clone(),
+                    &bearer,
+                    AuditAction::ToolInstalled,
+                    None,
+                    json!({ "zip_size": file_data.len() }),
+                    res,
+                );
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_import_tool_zip(
+                        db_clone,
+                        bearer,
+                        node_env,
+                        node_name,
+                        signing_secret_key,
+                        file_data,
+                        res,
+                    )
+                    .await;
+                });
+            }
+            NodeCommand::V2ApiRemoveTool {
+                bearer,
+                tool_key,
+                force,
+                res,
+            } => {
+                let db_clone = Arc::clone(&self.db);
+                let res = Node::audited(
+                    db_clone.clone(),
+                    &bearer,
+                    AuditAction::ToolRemoved,
+                    Some(tool_key.clone()),
+                    json!({ "force": force }),
+                    res,
+                );
                 tokio::spawn(async move {
-                    let _ = Node::v2_api_import_tool_zip(db_clone, bearer, node_env, file_data, res).await;
+                    let _ = Node::v2_api_remove_tool(db_clone, bearer, tool_key, force, res).await;
                 });
             }
-            NodeCommand::V2ApiRemoveTool { bearer, tool_key, res } => {
+            NodeCommand::V2ApiGetToolDependencyGraph {
+                bearer,
+                tool_router_key,
+                res,
+            } => {
                 let db_clone = Arc::clone(&self.db);
                 tokio::spawn(async move {
-                    let _ = Node::v2_api_remove_tool(db_clone, bearer, tool_key, res).await;
+                    let _ = Node::v2_api_get_tool_dependency_graph(db_clone, bearer, tool_router_key, res).await;
+                });
+            }
+            NodeCommand::V2ApiSetToolNetworkApproval {
+                bearer,
+                tool_router_key,
+                approved,
+                res,
+            } => {
+                let db_clone = Arc::clone(&self.db);
+                let res = Node::audited(
+                    db_clone.clone(),
+                    &bearer,
+                    AuditAction::ToolConfigChanged,
+                    Some(tool_router_key.clone()),
+                    json!({ "network_approved": approved }),
+                    res,
+                );
+                tokio::spawn(async move {
+                    let _ =
+                        Node::v2_api_set_tool_network_approval(db_clone, bearer, tool_router_key, approved, res).await;
+                });
+            }
+            NodeCommand::V2ApiGetToolNetworkAccess {
+                bearer,
+                tool_router_key,
+                res,
+            } => {
+                let db_clone = Arc::clone(&self.db);
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_get_tool_network_access(db_clone, bearer, tool_router_key, res).await;
+                });
+            }
+            NodeCommand::V2ApiSetWasmToolGrant {
+                bearer,
+                tool_router_key,
+                approved,
+                allow_outside_storage,
+                res,
+            } => {
+                let db_clone = Arc::clone(&self.db);
+                let res = Node::audited(
+                    db_clone.clone(),
+                    &bearer,
+                    AuditAction::ToolConfigChanged,
+                    Some(tool_router_key.clone()),
+                    json!({ "wasm_capabilities_approved": approved, "allow_outside_storage": allow_outside_storage }),
+                    res,
+                );
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_set_wasm_tool_grant(
+                        db_clone,
+                        bearer,
+                        tool_router_key,
+                        approved,
+                        allow_outside_storage,
+                        res,
+                    )
+                    .await;
                 });
             }
             NodeCommand::V2ApiResolveShinkaiFileProtocol {
@@ -2732,20 +2987,53 @@ impl Node {
             NodeCommand::V2ApiAddCronTask {
                 bearer,
                 cron,
+                timezone,
+                policy,
+                event_trigger,
                 action,
                 name,
                 description,
                 res,
             } => {
                 let db_clone = Arc::clone(&self.db);
-                tokio::spawn(async move {
-                    let _ = Node::v2_api_add_cron_task(db_clone, bearer, cron, action, name, description, res).await;
+                let res = Node::audited(
+                    db_clone.clone(),
+                    &bearer,
+                    AuditAction::CronTaskCreated,
+                    None,
+                    json!({
+                        "name": name,
+                        "cron": cron,
+                        "timezone": timezone,
+                        "policy": policy,
+                        "event_trigger": event_trigger,
+                        "description": description
+                    }),
+                    res,
+                );
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_add_cron_task(
+                        db_clone,
+                        bearer,
+                        cron,
+                        timezone,
+                        policy,
+                        event_trigger,
+                        action,
+                        name,
+                        description,
+                        res,
+                    )
+                    .await;
                 });
             }
             NodeCommand::V2ApiUpdateCronTask {
                 bearer,
                 cron_task_id,
                 cron,
+                timezone,
+                policy,
+                event_trigger,
                 action,
                 name,
                 description,
@@ -2753,12 +3041,31 @@ impl Node {
                 res,
             } => {
                 let db_clone = Arc::clone(&self.db);
+                let res = Node::audited(
+                    db_clone.clone(),
+                    &bearer,
+                    AuditAction::CronTaskUpdated,
+                    Some(cron_task_id.to_string()),
+                    json!({
+                        "name": name,
+                        "cron": cron,
+                        "timezone": timezone,
+                        "policy": policy,
+                        "event_trigger": event_trigger,
+                        "description": description,
+                        "paused": paused
+                    }),
+                    res,
+                );
                 tokio::spawn(async move {
                     let _ = Node::v2_api_update_cron_task(
                         db_clone,
                         bearer,
                         cron_task_id,
                         cron,
+                        timezone,
+                        policy,
+                        event_trigger,
                         action,
                         name,
                         description,
@@ -2810,6 +3117,14 @@ impl Node {
                 res,
             } => {
                 let db_clone = Arc::clone(&self.db);
+                let res = Node::audited(
+                    db_clone.clone(),
+                    &bearer,
+                    AuditAction::CronTaskRemoved,
+                    Some(cron_task_id.to_string()),
+                    json!({}),
+                    res,
+                );
                 tokio::spawn(async move {
                     let _ = Node::v2_api_remove_cron_task(db_clone, bearer, cron_task_id, res).await;
                 });
@@ -2828,6 +3143,14 @@ impl Node {
                 let db_clone = Arc::clone(&self.db);
                 let node_name = self.node_name.node_name.clone();
                 let signing_secret_key = self.identity_secret_key.clone();
+                let res = Node::audited(
+                    db_clone.clone(),
+                    &bearer,
+                    AuditAction::CronTaskCreated,
+                    None,
+                    json!({ "url": url }),
+                    res,
+                );
                 tokio::spawn(async move {
                     let _ =
                         Node::v2_api_import_cron_task(db_clone, bearer, url, node_name, signing_secret_key, res).await;
@@ -2958,6 +3281,19 @@ impl Node {
                 res,
             } => {
                 let db_clone = Arc::clone(&self.db);
+                let connection = db_clone
+                    .get_oauth_token_by_state(&state)
+                    .ok()
+                    .flatten()
+                    .map(|token| format!("{}/{}", token.tool_key, token.connection_name));
+                let res = Node::audited(
+                    db_clone.clone(),
+                    &bearer,
+                    AuditAction::OAuthTokenChanged,
+                    connection,
+                    json!({}),
+                    res,
+                );
                 tokio::spawn(async move {
                     let _ = Node::v2_api_set_oauth_token(db_clone, bearer, code, state, res).await;
                 });
@@ -3167,6 +3503,14 @@ impl Node {
                 res,
             } => {
                 let db_clone = Arc::clone(&self.db);
+                let res = Node::audited(
+                    db_clone.clone(),
+                    &bearer,
+                    AuditAction::ToolConfigChanged,
+                    Some(tool_router_key.clone()),
+                    json!({ "enabled": enabled }),
+                    res,
+                );
                 tokio::spawn(async move {
                     let _ = Node::v2_api_set_tool_enabled(db_clone, bearer, tool_router_key, enabled, res).await;
                 });
@@ -3183,6 +3527,88 @@ impl Node {
                         Node::v2_api_set_tool_mcp_enabled(db_clone, bearer, tool_router_key, mcp_enabled, res).await;
                 });
             }
+            NodeCommand::V2ApiListToolRevisions {
+                bearer,
+                tool_router_key,
+                res,
+            } => {
+                let db_clone = Arc::clone(&self.db);
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_list_tool_revisions(db_clone, bearer, tool_router_key, res).await;
+                });
+            }
+            NodeCommand::V2ApiDiffToolRevisions {
+                bearer,
+                tool_router_key,
+                from_revision,
+                to_revision,
+                res,
+            } => {
+                let db_clone = Arc::clone(&self.db);
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_diff_tool_revisions(
+                        db_clone,
+                        bearer,
+                        tool_router_key,
+                        from_revision,
+                        to_revision,
+                        res,
+                    )
+                    .await;
+                });
+            }
+            NodeCommand::V2ApiRollbackToolRevision {
+                bearer,
+                tool_router_key,
+                revision,
+                res,
+            } => {
+                let db_clone = Arc::clone(&self.db);
+                let res = Node::audited(
+                    db_clone.clone(),
+                    &bearer,
+                    AuditAction::ToolConfigChanged,
+                    Some(tool_router_key.clone()),
+                    json!({ "rollback_to_revision": revision }),
+                    res,
+                );
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_rollback_tool_revision(db_clone, bearer, tool_router_key, revision, res).await;
+                });
+            }
+            NodeCommand::V2ApiRunToolTests {
+                bearer,
+                tool_router_key,
+                skip_network_tests,
+                llm_provider,
+                res,
+            } => {
+                let db_clone = Arc::clone(&self.db);
+                let node_name = self.node_name.clone();
+                let job_manager = self.job_manager.clone().unwrap();
+                let identity_manager = self.identity_manager.clone();
+                let encryption_secret_key = self.encryption_secret_key.clone();
+                let encryption_public_key = self.encryption_public_key;
+                let signing_secret_key = self.identity_secret_key.clone();
+
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_run_tool_tests(
+                        bearer,
+                        node_name,
+                        db_clone,
+                        tool_router_key,
+                        skip_network_tests,
+                        llm_provider,
+                        identity_manager,
+                        job_manager,
+                        encryption_secret_key,
+                        encryption_public_key,
+                        signing_secret_key,
+                        res,
+                    )
+                    .await;
+                });
+            }
             NodeCommand::V2ApiCopyToolAssets {
                 bearer,
                 is_first_playground,
@@ -3222,6 +3648,14 @@ impl Node {
             }
             NodeCommand::V2ApiSetPreferences { bearer, payload, res } => {
                 let db_clone = Arc::clone(&self.db);
+                let res = Node::audited(
+                    db_clone.clone(),
+                    &bearer,
+                    AuditAction::PreferencesChanged,
+                    None,
+                    json!(payload),
+                    res,
+                );
                 tokio::spawn(async move {
                     let _ = Node::v2_api_set_preferences(db_clone, bearer, payload, res).await;
                 });
@@ -3232,6 +3666,492 @@ impl Node {
                     let _ = Node::v2_api_get_preferences(db_clone, bearer, res).await;
                 });
             }
+            NodeCommand::V2ApiCreateApiKey {
+                bearer,
+                name,
+                scopes,
+                agent_ids,
+                expires_at,
+                res,
+            } => {
+                let db_clone = Arc::clone(&self.db);
+                tokio::spawn(async move {
+                    let _ =
+                        Node::v2_api_create_api_key(db_clone, bearer, name, scopes, agent_ids, expires_at, res).await;
+                });
+            }
+            NodeCommand::V2ApiListApiKeys { bearer, res } => {
+                let db_clone = Arc::clone(&self.db);
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_list_api_keys(db_clone, bearer, res).await;
+                });
+            }
+            NodeCommand::V2ApiRevokeApiKey { bearer, id, res } => {
+                let db_clone = Arc::clone(&self.db);
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_revoke_api_key(db_clone, bearer, id, res).await;
+                });
+            }
+            NodeCommand::V2ApiGetAuditLog { bearer, filter, res } => {
+                let db_clone = Arc::clone(&self.db);
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_get_audit_log(db_clone, bearer, filter, res).await;
+                });
+            }
+            NodeCommand::V2ApiExportAuditLog { bearer, filter, res } => {
+                let db_clone = Arc::clone(&self.db);
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_export_audit_log(db_clone, bearer, filter, res).await;
+                });
+            }
+            NodeCommand::V2ApiVerifyAuditLog { bearer, res } => {
+                let db_clone = Arc::clone(&self.db);
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_verify_audit_log(db_clone, bearer, res).await;
+                });
+            }
+            NodeCommand::V2ApiCreateBackup { bearer, payload, res } => {
+                let db_clone = Arc::clone(&self.db);
+                let node_name = self.node_name.clone();
+                let identity_public_key = self.identity_public_key;
+                let res = Node::audited(
+                    db_clone.clone(),
+                    &bearer,
+                    AuditAction::BackupCreated,
+                    None,
+                    json!({ "categories": payload.categories, "encrypted": payload.passphrase.is_some() }),
+                    res,
+                );
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_create_backup(db_clone, node_name, identity_public_key, bearer, payload, res)
+                        .await;
+                });
+            }
+            NodeCommand::V2ApiRestoreBackup {
+                bearer,
+                file,
+                payload,
+                res,
+            } => {
+                let db_clone = Arc::clone(&self.db);
+                let node_name = self.node_name.clone();
+                let job_manager_clone = self.job_manager.clone().unwrap();
+                let res = Node::audited(
+                    db_clone.clone(),
+                    &bearer,
+                    AuditAction::BackupRestored,
+                    None,
+                    json!({ "categories": payload.categories }),
+                    res,
+                );
+                tokio::spawn(async move {
+                    let _ =
+                        Node::v2_api_restore_backup(db_clone, node_name, job_manager_clone, bearer, file, payload, res)
+                            .await;
+                });
+            }
+            NodeCommand::V2ApiAddWebhook { bearer, payload, res } => {
+                let db_clone = Arc::clone(&self.db);
+                let res = Node::audited(
+                    db_clone.clone(),
+                    &bearer,
+                    AuditAction::WebhookCreated,
+                    None,
+                    // Webhook URLs often embed a token, so only the events are recorded
+                    json!({ "events": payload.events }),
+                    res,
+                );
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_add_webhook(db_clone, bearer, payload, res).await;
+                });
+            }
+            NodeCommand::V2ApiListWebhooks { bearer, res } => {
+                let db_clone = Arc::clone(&self.db);
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_list_webhooks(db_clone, bearer, res).await;
+                });
+            }
+            NodeCommand::V2ApiUpdateWebhook { bearer, payload, res } => {
+                let db_clone = Arc::clone(&self.db);
+                let res = Node::audited(
+                    db_clone.clone(),
+                    &bearer,
+                    AuditAction::WebhookUpdated,
+                    Some(payload.id.clone()),
+                    json!({
+                        "url_changed": payload.url.is_some(),
+                        "events": payload.events,
+                        "secret_changed": payload.secret.is_some(),
+                        "enabled": payload.enabled,
+                    }),
+                    res,
+                );
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_update_webhook(db_clone, bearer, payload, res).await;
+                });
+            }
+            NodeCommand::V2ApiRemoveWebhook { bearer, id, res } => {
+                let db_clone = Arc::clone(&self.db);
+                let res = Node::audited(
+                    db_clone.clone(),
+                    &bearer,
+                    AuditAction::WebhookRemoved,
+                    Some(id.clone()),
+                    json!({}),
+                    res,
+                );
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_remove_webhook(db_clone, bearer, id, res).await;
+                });
+            }
+            NodeCommand::V2ApiListWebhookDeliveries { bearer, filter, res } => {
+                let db_clone = Arc::clone(&self.db);
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_list_webhook_deliveries(db_clone, bearer, filter, res).await;
+                });
+            }
+            NodeCommand::V2ApiRedeliverWebhook {
+                bearer,
+                delivery_id,
+                res,
+            } => {
+                let db_clone = Arc::clone(&self.db);
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_redeliver_webhook(db_clone, bearer, delivery_id, res).await;
+                });
+            }
+            NodeCommand::V2ApiAddWebhookTrigger { bearer, payload, res } => {
+                let db_clone = Arc::clone(&self.db);
+                let res = Node::audited(
+                    db_clone.clone(),
+                    &bearer,
+                    AuditAction::WebhookTriggerCreated,
+                    None,
+                    json!({
+                        "name": payload.name,
+                        "agent_id": payload.agent_id,
+                        "rate_limit_per_minute": payload.rate_limit_per_minute,
+                        "mode": payload.mode,
+                    }),
+                    res,
+                );
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_add_webhook_trigger(db_clone, bearer, payload, res).await;
+                });
+            }
+            NodeCommand::V2ApiListWebhookTriggers { bearer, res } => {
+                let db_clone = Arc::clone(&self.db);
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_list_webhook_triggers(db_clone, bearer, res).await;
+                });
+            }
+            NodeCommand::V2ApiUpdateWebhookTrigger { bearer, payload, res } => {
+                let db_clone = Arc::clone(&self.db);
+                let res = Node::audited(
+                    db_clone.clone(),
+                    &bearer,
+                    AuditAction::WebhookTriggerUpdated,
+                    Some(payload.id.clone()),
+                    json!({
+                        "agent_id": payload.agent_id,
+                        "prompt_changed": payload.prompt_template.is_some(),
+                        "secret_changed": payload.secret.is_some(),
+                        "rate_limit_per_minute": payload.rate_limit_per_minute,
+                        "mode": payload.mode,
+                        "enabled": payload.enabled,
+                    }),
+                    res,
+                );
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_update_webhook_trigger(db_clone, bearer, payload, res).await;
+                });
+            }
+            NodeCommand::V2ApiRemoveWebhookTrigger { bearer, id, res } => {
+                let db_clone = Arc::clone(&self.db);
+                let res = Node::audited(
+                    db_clone.clone(),
+                    &bearer,
+                    AuditAction::WebhookTriggerRemoved,
+                    Some(id.clone()),
+                    json!({}),
+                    res,
+                );
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_remove_webhook_trigger(db_clone, bearer, id, res).await;
+                });
+            }
+            NodeCommand::V2ApiInvokeWebhookTrigger {
+                id,
+                authorization,
+                signature,
+                body,
+                sync,
+                res,
+            } => {
+                let db_clone = Arc::clone(&self.db);
+                let node_name_clone = self.node_name.clone();
+                let identity_manager_clone = self.identity_manager.clone();
+                let job_manager_clone = self.job_manager.clone().unwrap();
+                let encryption_secret_key_clone = self.encryption_secret_key.clone();
+                let encryption_public_key_clone = self.encryption_public_key;
+                let signing_secret_key_clone = self.identity_secret_key.clone();
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_invoke_webhook_trigger(
+                        db_clone,
+                        node_name_clone,
+                        identity_manager_clone,
+                        job_manager_clone,
+                        id,
+                        authorization,
+                        signature,
+                        body,
+                        sync,
+                        encryption_secret_key_clone,
+                        encryption_public_key_clone,
+                        signing_secret_key_clone,
+                        res,
+                    )
+                    .await;
+                });
+            }
+            NodeCommand::V2ApiSearchInboxMessages { bearer, request, res } => {
+                let db_clone = Arc::clone(&self.db);
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_search_inbox_messages(db_clone, bearer, request, res).await;
+                });
+            }
+            NodeCommand::V2ApiAddJobPipeline { bearer, payload, res } => {
+                let db_clone = Arc::clone(&self.db);
+                let res = Node::audited(
+                    db_clone.clone(),
+                    &bearer,
+                    AuditAction::JobPipelineCreated,
+                    None,
+                    json!({
+                        "name": payload.name,
+                        "steps": payload.steps.iter().map(|step| &step.id).collect::<Vec<_>>(),
+                    }),
+                    res,
+                );
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_add_job_pipeline(db_clone, bearer, payload, res).await;
+                });
+            }
+            NodeCommand::V2ApiListJobPipelines { bearer, res } => {
+                let db_clone = Arc::clone(&self.db);
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_list_job_pipelines(db_clone, bearer, res).await;
+                });
+            }
+            NodeCommand::V2ApiRemoveJobPipeline { bearer, id, res } => {
+                let db_clone = Arc::clone(&self.db);
+                let res = Node::audited(
+                    db_clone.clone(),
+                    &bearer,
+                    AuditAction::JobPipelineRemoved,
+                    Some(id.clone()),
+                    json!({}),
+                    res,
+                );
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_remove_job_pipeline(db_clone, bearer, id, res).await;
+                });
+            }
+            NodeCommand::V2ApiRunJobPipeline { bearer, payload, res } => {
+                let db_clone = Arc::clone(&self.db);
+                let job_manager_clone = self.job_manager.clone().unwrap();
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_run_job_pipeline(db_clone, job_manager_clone, bearer, payload, res).await;
+                });
+            }
+            NodeCommand::V2ApiGetJobPipelineRun { bearer, run_id, res } => {
+                let db_clone = Arc::clone(&self.db);
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_get_job_pipeline_run(db_clone, bearer, run_id, res).await;
+                });
+            }
+            NodeCommand::V2ApiResumeJobPipelineRun { bearer, run_id, res } => {
+                let db_clone = Arc::clone(&self.db);
+                let job_manager_clone = self.job_manager.clone().unwrap();
+                tokio::spawn(async move {
+                    let _ =
+                        Node::v2_api_resume_job_pipeline_run(db_clone, job_manager_clone, bearer, run_id, res).await;
+                });
+            }
+            NodeCommand::V2ApiListJobDeadLetters { bearer, job_id, res } => {
+                let db_clone = Arc::clone(&self.db);
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_list_job_dead_letters(db_clone, bearer, job_id, res).await;
+                });
+            }
+            NodeCommand::V2ApiRequeueJobDeadLetter { bearer, id, res } => {
+                let db_clone = Arc::clone(&self.db);
+                let job_manager_clone = self.job_manager.clone().unwrap();
+                let res = Node::audited(
+                    db_clone.clone(),
+                    &bearer,
+                    AuditAction::JobDeadLetterRequeued,
+                    Some(id.clone()),
+                    json!({}),
+                    res,
+                );
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_requeue_job_dead_letter(db_clone, job_manager_clone, bearer, id, res).await;
+                });
+            }
+            NodeCommand::V2ApiDiscardJobDeadLetter { bearer, id, res } => {
+                let db_clone = Arc::clone(&self.db);
+                let res = Node::audited(
+                    db_clone.clone(),
+                    &bearer,
+                    AuditAction::JobDeadLetterDiscarded,
+                    Some(id.clone()),
+                    json!({}),
+                    res,
+                );
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_discard_job_dead_letter(db_clone, bearer, id, res).await;
+                });
+            }
+            NodeCommand::V2ApiSetJobConcurrencyLimit { bearer, limit, res } => {
+                let db_clone = Arc::clone(&self.db);
+                let res = Node::audited(
+                    db_clone.clone(),
+                    &bearer,
+                    AuditAction::JobConcurrencyLimitSet,
+                    Some(limit.target_id.clone()),
+                    json!({ "target": limit.target }),
+                    res,
+                );
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_set_job_concurrency_limit(db_clone, bearer, limit, res).await;
+                });
+            }
+            NodeCommand::V2ApiRemoveJobConcurrencyLimit {
+                bearer,
+                target,
+                target_id,
+                res,
+            } => {
+                let db_clone = Arc::clone(&self.db);
+                let res = Node::audited(
+                    db_clone.clone(),
+                    &bearer,
+                    AuditAction::JobConcurrencyLimitRemoved,
+                    Some(target_id.clone()),
+                    json!({ "target": target }),
+                    res,
+                );
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_remove_job_concurrency_limit(db_clone, bearer, target, target_id, res).await;
+                });
+            }
+            NodeCommand::V2ApiGetJobQueueStatus { bearer, res } => {
+                let db_clone = Arc::clone(&self.db);
+                let job_manager_clone = self.job_manager.clone().unwrap();
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_get_job_queue_status(db_clone, job_manager_clone, bearer, res).await;
+                });
+            }
+            NodeCommand::V2ApiScheduleJobMessage {
+                bearer,
+                job_message,
+                send_at,
+                res,
+            } => {
+                let db_clone = Arc::clone(&self.db);
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_schedule_job_message(db_clone, bearer, job_message, send_at, res).await;
+                });
+            }
+            NodeCommand::V2ApiListScheduledJobMessages { bearer, job_id, res } => {
+                let db_clone = Arc::clone(&self.db);
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_list_scheduled_job_messages(db_clone, bearer, job_id, res).await;
+                });
+            }
+            NodeCommand::V2ApiCancelScheduledJobMessage { bearer, id, res } => {
+                let db_clone = Arc::clone(&self.db);
+                let res = Node::audited(
+                    db_clone.clone(),
+                    &bearer,
+                    AuditAction::ScheduledJobMessageCancelled,
+                    Some(id.clone()),
+                    json!({}),
+                    res,
+                );
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_cancel_scheduled_job_message(db_clone, bearer, id, res).await;
+                });
+            }
+            NodeCommand::V2ApiRotateSecretsKey {
+                bearer,
+                new_passphrase,
+                res,
+            } => {
+                let db_clone = Arc::clone(&self.db);
+                let node_name = self.node_name.node_name.clone();
+                let res = Node::audited(
+                    db_clone.clone(),
+                    &bearer,
+                    AuditAction::SecretsKeyRotated,
+                    None,
+                    json!({ "passphrase": new_passphrase.is_some() }),
+                    res,
+                );
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_rotate_secrets_key(db_clone, node_name, bearer, new_passphrase, res).await;
+                });
+            }
+            NodeCommand::V2ApiOpenAIListModels { bearer, res } => {
+                let db_clone = Arc::clone(&self.db);
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_openai_list_models(db_clone, bearer, res).await;
+                });
+            }
+            NodeCommand::V2ApiOpenAIChatCompletion { bearer, request, res } => {
+                let db_clone = Arc::clone(&self.db);
+                let node_name_clone = self.node_name.clone();
+                let identity_manager_clone = self.identity_manager.clone();
+                let job_manager_clone = self.job_manager.clone().unwrap();
+                let ws_manager_clone = self.ws_manager.clone();
+                let encryption_secret_key_clone = self.encryption_secret_key.clone();
+                let encryption_public_key_clone = self.encryption_public_key;
+                let signing_secret_key_clone = self.identity_secret_key.clone();
+                tokio::spawn(async move {
+                    let _ = Node::v2_api_openai_chat_completion(
+                        db_clone,
+                        node_name_clone,
+                        identity_manager_clone,
+                        job_manager_clone,
+                        ws_manager_clone,
+                        bearer,
+                        request,
+                        encryption_secret_key_clone,
+                        encryption_public_key_clone,
+                        signing_secret_key_clone,
+                        res,
+                    )
+                    .await;
+                });
+            }
+            NodeCommand::GetNodeMetrics { bearer, res } => {
+                let db_clone = Arc::clone(&self.db);
+                let job_manager_clone = self.job_manager.clone();
+                let ws_manager_clone = self.ws_manager.clone();
+                let proxy_connection_info_clone = self.proxy_connection_info.clone();
+                tokio::spawn(async move {
+                    let _ = Node::get_node_metrics(
+                        db_clone,
+                        bearer,
+                        job_manager_clone,
+                        ws_manager_clone,
+                        proxy_connection_info_clone,
+                        res,
+                    )
+                    .await;
+                });
+            }
             _ => (),
         }
     }
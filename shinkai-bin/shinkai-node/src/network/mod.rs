@@ -2,6 +2,7 @@ pub mod node;
 pub use node::Node;
 pub mod node_error;
 pub mod ws_manager;
+pub mod node_metrics;
 pub mod ws_routes;
 pub mod node_shareable_logic;
 pub mod network_limiter;
@@ -10,4 +11,5 @@ pub mod handle_commands_list;
 pub mod v1_api;
 pub mod v2_api;
 pub mod agent_payments_manager;
-pub mod network_manager_utils;
\ No newline at end of file
+pub mod network_manager_utils;
+pub mod webhook_dispatcher;
\ No newline at end of file
@@ -2,6 +2,7 @@ use super::agent_payments_manager::external_agent_offerings_manager::ExtAgentOff
 use super::agent_payments_manager::my_agent_offerings_manager::MyAgentOfferingsManager;
 use super::network_manager::network_job_manager::{NetworkJobManager, NetworkJobQueue};
 use super::node_error::NodeError;
+use super::webhook_dispatcher::deliver_due_webhooks;
 use super::ws_manager::WebSocketManager;
 use crate::cron_tasks::cron_manager::CronManager;
 use crate::llm_provider::job_callback_manager::JobCallbackManager;
@@ -13,6 +14,9 @@ use crate::managers::tool_router::ToolRouter;
 use crate::managers::IdentityManager;
 use crate::network::network_limiter::ConnectionLimiter;
 use crate::network::ws_routes::run_ws_api;
+use crate::tools::egress_proxy::EgressProxy;
+use crate::tools::tool_launcher::prepare_tool_launchers;
+use crate::utils::environment::fetch_node_environment;
 use crate::wallet::coinbase_mpc_wallet::CoinbaseMPCWallet;
 use crate::wallet::wallet_manager::WalletManager;
 use async_channel::Receiver;
@@ -61,6 +65,9 @@ pub type TcpReadHalf = Arc<Mutex<ReadHalf<TcpStream>>>;
 pub type TcpWriteHalf = Arc<Mutex<WriteHalf<TcpStream>>>;
 pub type TcpConnection = Option<(TcpReadHalf, TcpWriteHalf)>;
 
+// Inbox messages embedded for semantic search on each retry tick
+const INBOX_EMBEDDING_BATCH_SIZE: u64 = 16;
+
 // Define the ConnectionInfo struct
 #[derive(Clone, Debug)]
 pub struct ProxyConnectionInfo {
@@ -168,7 +175,7 @@ impl Node {
         default_embedding_model: EmbeddingModelType,
         supported_embedding_models: Vec<EmbeddingModelType>,
         api_v2_key: Option<String>,
-    ) -> Arc<Mutex<Node>> {
+    ) -> Result<Arc<Mutex<Node>>, NodeError> {
         // if is_valid_node_identity_name_and_no_subidentities is false panic
         match ShinkaiName::new(node_name.to_string().clone()) {
             Ok(_) => (),
@@ -187,6 +194,16 @@ impl Node {
                     panic!("Failed to open database: {}", main_db_path)
                 }),
         );
+        // Secrets can't be read or written with a locked vault, so the node doesn't start
+        Node::unlock_secret_vault(&db_arc, &node_name).map_err(|e| NodeError {
+            message: format!("Cannot start the node: {}", e),
+        })?;
+        // Entries dropped from the end of the audit log only show against a head signed by the node
+        db_arc
+            .set_audit_log_signing_key(clone_signature_secret_key(&identity_secret_key))
+            .map_err(|e| NodeError {
+                message: format!("Cannot start the node: {}", e),
+            })?;
 
         // Get public keys, and update the local node keys in the db
         let identity_public_key = identity_secret_key.verifying_key();
@@ -384,7 +401,7 @@ impl Node {
 
         let llm_stopper = Arc::new(LLMStopper::new());
 
-        Arc::new(Mutex::new(Node {
+        Ok(Arc::new(Mutex::new(Node {
             node_name: node_name.clone(),
             identity_secret_key: clone_signature_secret_key(&identity_secret_key),
             identity_public_key,
@@ -421,7 +438,7 @@ impl Node {
             my_agent_payments_manager,
             ext_agent_payments_manager,
             llm_stopper,
-        }))
+        })))
     }
 
     // Start the node's operations.
@@ -472,6 +489,24 @@ impl Node {
             ShinkaiLogLevel::Info,
             &format!("Starting node with name: {}", self.node_name),
         );
+
+        // Tools with a network allowlist reach the outside through this proxy
+        if let Err(e) = EgressProxy::start(Arc::downgrade(&self.db)).await {
+            shinkai_log(
+                ShinkaiLogOption::Node,
+                ShinkaiLogLevel::Error,
+                &format!("Failed to start the egress proxy: {}", e),
+            );
+        }
+        if let Some(node_storage_path) = fetch_node_environment().node_storage_path {
+            if let Err(e) = prepare_tool_launchers(Path::new(&node_storage_path)) {
+                shinkai_log(
+                    ShinkaiLogOption::Node,
+                    ShinkaiLogLevel::Error,
+                    &format!("Failed to prepare the tool launchers: {}", e),
+                );
+            }
+        }
         let db_weak = Arc::downgrade(&self.db);
 
         let cron_manager_result = CronManager::new(
@@ -620,6 +655,18 @@ impl Node {
                                 ws_manager_trait,
                             ).await;
                         });
+
+                        // Webhook deliveries are polled from the outbox on the same tick
+                        let db_clone = self.db.clone();
+                        tokio::spawn(async move {
+                            let _ = deliver_due_webhooks(db_clone).await;
+                        });
+
+                        // New inbox messages get their embeddings for semantic search in small batches
+                        let db_clone = self.db.clone();
+                        tokio::spawn(async move {
+                            let _ = db_clone.embed_pending_inbox_messages(INBOX_EMBEDDING_BATCH_SIZE).await;
+                        });
                     },
                     _six_hour = six_hour_future => {
                         // Clone necessary variables for periodic tasks
@@ -0,0 +1,446 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex as StdMutex, OnceLock};
+use std::time::Duration;
+
+use async_channel::Sender;
+use shinkai_embedding::embedding_generator::{embedding_stats, EmbeddingStats};
+use shinkai_http_api::node_api_router::APIError;
+use shinkai_message_primitives::schemas::api_key::ApiKeyScope;
+use shinkai_sqlite::{SqliteManager, SqlitePoolUsage};
+use tokio::sync::Mutex;
+
+use super::node::ProxyConnectionInfo;
+use super::ws_manager::WebSocketManager;
+use super::Node;
+use crate::llm_provider::job_manager::JobManager;
+
+/// Upper bounds, in seconds, of the duration histogram buckets
+const DURATION_BUCKETS: [f64; 14] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0,
+];
+
+static NODE_METRICS: OnceLock<NodeMetrics> = OnceLock::new();
+
+/// Metrics recorded by the node, shared by every component that reports to `/metrics`
+pub fn node_metrics() -> &'static NodeMetrics {
+    NODE_METRICS.get_or_init(NodeMetrics::default)
+}
+
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    buckets: [u64; DURATION_BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, value: f64) {
+        for (bucket, upper_bound) in self.buckets.iter_mut().zip(DURATION_BUCKETS) {
+            if value <= upper_bound {
+                *bucket += 1;
+            }
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+}
+
+#[derive(Debug, Default)]
+struct MetricsState {
+    job_queue_wait: BTreeMap<String, Histogram>,
+    // Keyed by (provider, model)
+    inference_duration: BTreeMap<(String, String), Histogram>,
+    inference_errors: BTreeMap<(String, String), u64>,
+    // Keyed by (provider, model, token type)
+    inference_tokens: BTreeMap<(String, String, &'static str), u64>,
+    tool_duration: BTreeMap<String, Histogram>,
+    // Keyed by (tool, status)
+    tool_executions: BTreeMap<(String, &'static str), u64>,
+    cron_executions: BTreeMap<&'static str, u64>,
+    audit_log_write_failures: u64,
+}
+
+/// Values read from the node components when the metrics are scraped
+#[derive(Debug, Clone, Default)]
+pub struct NodeMetricsSnapshot {
+    pub job_queue_depth: Vec<(String, usize)>,
+    pub ws_connections: Option<usize>,
+    /// Whether the relayer proxy is connected, `None` when the node doesn't use one
+    pub relayer_connected: Option<bool>,
+    pub sqlite_pools: Vec<SqlitePoolUsage>,
+    pub embeddings: EmbeddingStats,
+}
+
+#[derive(Debug, Default)]
+pub struct NodeMetrics {
+    state: StdMutex<MetricsState>,
+}
+
+fn status_label(success: bool) -> &'static str {
+    if success {
+        "success"
+    } else {
+        "failure"
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn write_header(output: &mut String, name: &str, metric_type: &str, help: &str) {
+    let _ = writeln!(output, "# HELP {} {}", name, help);
+    let _ = writeln!(output, "# TYPE {} {}", name, metric_type);
+}
+
+fn write_histogram(output: &mut String, name: &str, labels: &str, histogram: &Histogram) {
+    let separator = if labels.is_empty() { "" } else { "," };
+    for (count, upper_bound) in histogram.buckets.iter().zip(DURATION_BUCKETS) {
+        let _ = writeln!(
+            output,
+            "{}_bucket{{{}{}le=\"{}\"}} {}",
+            name, labels, separator, upper_bound, count
+        );
+    }
+    let _ = writeln!(
+        output,
+        "{}_bucket{{{}{}le=\"+Inf\"}} {}",
+        name, labels, separator, histogram.count
+    );
+    let _ = writeln!(output, "{}_sum{{{}}} {}", name, labels, histogram.sum);
+    let _ = writeln!(output, "{}_count{{{}}} {}", name, labels, histogram.count);
+}
+
+impl NodeMetrics {
+    fn state(&self) -> std::sync::MutexGuard<'_, MetricsState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Time a job spent in `queue` before a worker picked it up
+    pub fn record_job_queue_wait(&self, queue: &str, wait: Duration) {
+        self.state()
+            .job_queue_wait
+            .entry(queue.to_string())
+            .or_default()
+            .observe(wait.as_secs_f64());
+    }
+
+    /// Records an inference call. Token counts are `None` when the call failed.
+    pub fn record_inference(&self, provider: &str, model: &str, duration: Duration, tokens: Option<(usize, usize)>) {
+        let key = (provider.to_string(), model.to_string());
+        let mut state = self.state();
+        state
+            .inference_duration
+            .entry(key.clone())
+            .or_default()
+            .observe(duration.as_secs_f64());
+        match tokens {
+            Some((prompt_tokens, completion_tokens)) => {
+                *state
+                    .inference_tokens
+                    .entry((key.0.clone(), key.1.clone(), "prompt"))
+                    .or_default() += prompt_tokens as u64;
+                *state
+                    .inference_tokens
+                    .entry((key.0, key.1, "completion"))
+                    .or_default() += completion_tokens as u64;
+            }
+            None => *state.inference_errors.entry(key).or_default() += 1,
+        }
+    }
+
+    pub fn record_tool_execution(&self, tool: &str, duration: Duration, success: bool) {
+        let mut state = self.state();
+        state
+            .tool_duration
+            .entry(tool.to_string())
+            .or_default()
+            .observe(duration.as_secs_f64());
+        *state
+            .tool_executions
+            .entry((tool.to_string(), status_label(success)))
+            .or_default() += 1;
+    }
+
+    pub fn record_cron_execution(&self, success: bool) {
+        *self.state().cron_executions.entry(status_label(success)).or_default() += 1;
+    }
+
+    /// Audited requests whose entry couldn't be written to the audit log
+    pub fn record_audit_log_write_failure(&self) {
+        self.state().audit_log_write_failures += 1;
+    }
+
+    /// Renders the recorded metrics and the scraped values in the Prometheus text format
+    pub fn render(&self, snapshot: &NodeMetricsSnapshot) -> String {
+        let state = self.state();
+        let mut output = String::new();
+
+        write_header(&mut output, "shinkai_job_queue_depth", "gauge", "Jobs waiting in the job queue");
+        for (queue, depth) in &snapshot.job_queue_depth {
+            let _ = writeln!(output, "shinkai_job_queue_depth{{queue=\"{}\"}} {}", escape_label(queue), depth);
+        }
+
+        write_header(
+            &mut output,
+            "shinkai_job_queue_wait_seconds",
+            "histogram",
+            "Time jobs wait in the queue before processing starts",
+        );
+        for (queue, histogram) in &state.job_queue_wait {
+            let labels = format!("queue=\"{}\"", escape_label(queue));
+            write_histogram(&mut output, "shinkai_job_queue_wait_seconds", &labels, histogram);
+        }
+
+        write_header(
+            &mut output,
+            "shinkai_inference_duration_seconds",
+            "histogram",
+            "Latency of LLM inference calls",
+        );
+        for ((provider, model), histogram) in &state.inference_duration {
+            let labels = format!("provider=\"{}\",model=\"{}\"", escape_label(provider), escape_label(model));
+            write_histogram(&mut output, "shinkai_inference_duration_seconds", &labels, histogram);
+        }
+
+        write_header(
+            &mut output,
+            "shinkai_inference_tokens_total",
+            "counter",
+            "Estimated tokens sent to and received from LLM providers",
+        );
+        for ((provider, model, token_type), tokens) in &state.inference_tokens {
+            let _ = writeln!(
+                output,
+                "shinkai_inference_tokens_total{{provider=\"{}\",model=\"{}\",type=\"{}\"}} {}",
+                escape_label(provider),
+                escape_label(model),
+                token_type,
+                tokens
+            );
+        }
+
+        write_header(&mut output, "shinkai_inference_errors_total", "counter", "Failed LLM inference calls");
+        for ((provider, model), errors) in &state.inference_errors {
+            let _ = writeln!(
+                output,
+                "shinkai_inference_errors_total{{provider=\"{}\",model=\"{}\"}} {}",
+                escape_label(provider),
+                escape_label(model),
+                errors
+            );
+        }
+
+        write_header(&mut output, "shinkai_tool_executions_total", "counter", "Tool executions by outcome");
+        for ((tool, status), count) in &state.tool_executions {
+            let _ = writeln!(
+                output,
+                "shinkai_tool_executions_total{{tool=\"{}\",status=\"{}\"}} {}",
+                escape_label(tool),
+                status,
+                count
+            );
+        }
+
+        write_header(
+            &mut output,
+            "shinkai_tool_execution_duration_seconds",
+            "histogram",
+            "Duration of tool executions",
+        );
+        for (tool, histogram) in &state.tool_duration {
+            let labels = format!("tool=\"{}\"", escape_label(tool));
+            write_histogram(&mut output, "shinkai_tool_execution_duration_seconds", &labels, histogram);
+        }
+
+        let embeddings = &snapshot.embeddings;
+        write_header(&mut output, "shinkai_embedding_requests_total", "counter", "Embedding generation requests");
+        let _ = writeln!(output, "shinkai_embedding_requests_total {}", embeddings.requests);
+        write_header(&mut output, "shinkai_embedding_inputs_total", "counter", "Texts embedded");
+        let _ = writeln!(output, "shinkai_embedding_inputs_total {}", embeddings.inputs);
+        write_header(&mut output, "shinkai_embedding_errors_total", "counter", "Failed embedding requests");
+        let _ = writeln!(output, "shinkai_embedding_errors_total {}", embeddings.errors);
+        write_header(
+            &mut output,
+            "shinkai_embedding_duration_seconds_total",
+            "counter",
+            "Time spent generating embeddings",
+        );
+        let _ = writeln!(
+            output,
+            "shinkai_embedding_duration_seconds_total {}",
+            embeddings.duration_micros as f64 / 1_000_000.0
+        );
+
+        if let Some(ws_connections) = snapshot.ws_connections {
+            write_header(&mut output, "shinkai_websocket_connections", "gauge", "Open WebSocket connections");
+            let _ = writeln!(output, "shinkai_websocket_connections {}", ws_connections);
+        }
+
+        write_header(&mut output, "shinkai_cron_executions_total", "counter", "Cron task executions by outcome");
+        for (status, count) in &state.cron_executions {
+            let _ = writeln!(output, "shinkai_cron_executions_total{{status=\"{}\"}} {}", status, count);
+        }
+
+        write_header(
+            &mut output,
+            "shinkai_audit_log_write_failures_total",
+            "counter",
+            "Audited requests missing from the audit log because the entry couldn't be written",
+        );
+        let _ = writeln!(
+            output,
+            "shinkai_audit_log_write_failures_total {}",
+            state.audit_log_write_failures
+        );
+
+        if let Some(connected) = snapshot.relayer_connected {
+            write_header(
+                &mut output,
+                "shinkai_relayer_connected",
+                "gauge",
+                "Whether the connection to the relayer proxy is up",
+            );
+            let _ = writeln!(output, "shinkai_relayer_connected {}", connected as u8);
+        }
+
+        write_header(&mut output, "shinkai_sqlite_pool_connections", "gauge", "SQLite pool connections by state");
+        for pool in &snapshot.sqlite_pools {
+            let _ = writeln!(
+                output,
+                "shinkai_sqlite_pool_connections{{pool=\"{}\",state=\"in_use\"}} {}",
+                pool.pool,
+                pool.connections - pool.idle_connections
+            );
+            let _ = writeln!(
+                output,
+                "shinkai_sqlite_pool_connections{{pool=\"{}\",state=\"idle\"}} {}",
+                pool.pool, pool.idle_connections
+            );
+        }
+        write_header(
+            &mut output,
+            "shinkai_sqlite_pool_max_connections",
+            "gauge",
+            "Maximum size of the SQLite pools",
+        );
+        for pool in &snapshot.sqlite_pools {
+            let _ = writeln!(
+                output,
+                "shinkai_sqlite_pool_max_connections{{pool=\"{}\"}} {}",
+                pool.pool, pool.max_size
+            );
+        }
+
+        output
+    }
+}
+
+impl Node {
+    pub async fn get_node_metrics(
+        db: Arc<SqliteManager>,
+        bearer: String,
+        job_manager: Option<Arc<Mutex<JobManager>>>,
+        ws_manager: Option<Arc<Mutex<WebSocketManager>>>,
+        proxy_connection_info: Arc<Mutex<Option<ProxyConnectionInfo>>>,
+        res: Sender<Result<String, APIError>>,
+    ) {
+        if Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::MetricsRead)
+            .await
+            .is_err()
+        {
+            return;
+        }
+
+        let mut job_queue_depth = Vec::new();
+        if let Some(job_manager) = job_manager {
+            let (queue_normal, queue_immediate) = {
+                let job_manager = job_manager.lock().await;
+                (
+                    job_manager.job_queue_manager_normal.clone(),
+                    job_manager.job_queue_manager_immediate.clone(),
+                )
+            };
+            for (name, queue) in [("normal", queue_normal), ("immediate", queue_immediate)] {
+                let depth = queue
+                    .lock()
+                    .await
+                    .get_all_elements_interleave()
+                    .await
+                    .map(|jobs| jobs.len())
+                    .unwrap_or_default();
+                job_queue_depth.push((name.to_string(), depth));
+            }
+        }
+
+        let ws_connections = match ws_manager {
+            Some(ws_manager) => Some(ws_manager.lock().await.connection_count()),
+            None => None,
+        };
+        let relayer_connected = proxy_connection_info
+            .lock()
+            .await
+            .as_ref()
+            .map(|proxy_connection_info| proxy_connection_info.tcp_connection.is_some());
+
+        let snapshot = NodeMetricsSnapshot {
+            job_queue_depth,
+            ws_connections,
+            relayer_connected,
+            sqlite_pools: db.pool_usage(),
+            embeddings: embedding_stats(),
+        };
+        let _ = res.send(Ok(node_metrics().render(&snapshot))).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_metrics() {
+        let metrics = NodeMetrics::default();
+        metrics.record_job_queue_wait("normal", Duration::from_millis(300));
+        metrics.record_inference("openai", "gpt-4o", Duration::from_secs(2), Some((120, 30)));
+        metrics.record_inference("openai", "gpt-4o", Duration::from_secs(1), None);
+        metrics.record_tool_execution("local:::__official_shinkai:::web \"search\"", Duration::from_millis(40), false);
+        metrics.record_cron_execution(true);
+        metrics.record_audit_log_write_failure();
+
+        let output = metrics.render(&NodeMetricsSnapshot {
+            job_queue_depth: vec![("normal".to_string(), 3)],
+            ws_connections: Some(2),
+            relayer_connected: Some(false),
+            sqlite_pools: vec![SqlitePoolUsage {
+                pool: "main",
+                connections: 4,
+                idle_connections: 1,
+                max_size: 10,
+            }],
+            embeddings: EmbeddingStats {
+                requests: 5,
+                inputs: 12,
+                errors: 1,
+                duration_micros: 1_500_000,
+            },
+        });
+
+        assert!(output.contains("shinkai_job_queue_depth{queue=\"normal\"} 3"));
+        assert!(output.contains("shinkai_job_queue_wait_seconds_bucket{queue=\"normal\",le=\"0.25\"} 0"));
+        assert!(output.contains("shinkai_job_queue_wait_seconds_bucket{queue=\"normal\",le=\"0.5\"} 1"));
+        assert!(output.contains("shinkai_inference_duration_seconds_count{provider=\"openai\",model=\"gpt-4o\"} 2"));
+        assert!(output.contains("shinkai_inference_tokens_total{provider=\"openai\",model=\"gpt-4o\",type=\"prompt\"} 120"));
+        assert!(output.contains("shinkai_inference_errors_total{provider=\"openai\",model=\"gpt-4o\"} 1"));
+        assert!(output.contains(
+            "shinkai_tool_executions_total{tool=\"local:::__official_shinkai:::web \\\"search\\\"\",status=\"failure\"} 1"
+        ));
+        assert!(output.contains("shinkai_embedding_duration_seconds_total 1.5"));
+        assert!(output.contains("shinkai_websocket_connections 2"));
+        assert!(output.contains("shinkai_cron_executions_total{status=\"success\"} 1"));
+        assert!(output.contains("shinkai_audit_log_write_failures_total 1"));
+        assert!(output.contains("shinkai_relayer_connected 0"));
+        assert!(output.contains("shinkai_sqlite_pool_connections{pool=\"main\",state=\"in_use\"} 3"));
+    }
+}
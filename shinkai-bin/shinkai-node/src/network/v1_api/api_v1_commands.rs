@@ -2765,7 +2765,7 @@ impl Node {
         let merged_value = Self::merge_json(existing_tool_value, input_value);
 
         // Convert merged_value to ShinkaiTool
-        let merged_tool: ShinkaiTool = match serde_json::from_value(merged_value) {
+        let mut merged_tool: ShinkaiTool = match serde_json::from_value(merged_value) {
             Ok(tool) => tool,
             Err(err) => {
                 let api_error = APIError {
@@ -2778,6 +2778,9 @@ impl Node {
             }
         };
 
+        // Config values the UI sent back redacted keep what was stored
+        merged_tool.restore_redacted_config(&existing_tool);
+
         // Save the tool to the LanceShinkaiDb
         let save_result = { sqlite_manager.update_tool(merged_tool).await };
 
@@ -2787,7 +2790,8 @@ impl Node {
                 let updated_tool = { sqlite_manager.get_tool_by_key(&tool_router_key) };
 
                 match updated_tool {
-                    Ok(tool) => {
+                    Ok(mut tool) => {
+                        tool.redact_config();
                         let response = serde_json::to_value(tool).unwrap_or_else(|_| json!({}));
                         let _ = res.send(Ok(response)).await;
                         Ok(())
@@ -2861,7 +2865,10 @@ impl Node {
 
         // Fetch the tool from the LanceShinkaiDb
         let tool = match sqlite_manager.get_tool_by_key(&tool_key) {
-            Ok(tool) => tool,
+            Ok(mut tool) => {
+                tool.redact_config();
+                tool
+            }
             Err(SqliteManagerError::ToolNotFound(_)) => {
                 let api_error = APIError {
                     code: StatusCode::NOT_FOUND.as_u16(),
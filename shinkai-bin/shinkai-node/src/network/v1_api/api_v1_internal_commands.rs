@@ -23,6 +23,7 @@ use shinkai_message_primitives::shinkai_utils::job_scope::MinimalJobScope;
 use shinkai_message_primitives::shinkai_utils::search_mode::VectorSearchMode;
 use shinkai_message_primitives::shinkai_utils::shinkai_message_builder::ShinkaiMessageBuilder;
 use shinkai_message_primitives::shinkai_utils::shinkai_path::ShinkaiPath;
+use shinkai_message_primitives::shinkai_utils::secret_vault::redact_secret;
 use shinkai_message_primitives::{
     schemas::{
         inbox_name::InboxName, llm_providers::serialized_llm_provider::{LLMProviderInterface, Ollama, SerializedLLMProvider}, shinkai_name::ShinkaiName
@@ -392,7 +393,14 @@ impl Node {
             }
         };
 
-        Ok(result)
+        // Only used for API responses, which never include the API keys
+        Ok(result
+            .into_iter()
+            .map(|llm_provider| SerializedLLMProvider {
+                api_key: redact_secret(&llm_provider.api_key),
+                ..llm_provider
+            })
+            .collect())
     }
 
     pub async fn internal_job_message(
@@ -27,6 +27,7 @@ use shinkai_message_primitives::{
 };
 use shinkai_message_primitives::{
     schemas::{
+        api_key::{ApiKey, ApiKeyScope},
         identity::{Identity, IdentityType, RegistrationCode},
         inbox_name::InboxName,
         llm_providers::{agent::Agent, serialized_llm_provider::SerializedLLMProvider},
@@ -45,6 +46,7 @@ use shinkai_message_primitives::{
         signatures::signature_public_key_to_string,
     },
 };
+use shinkai_message_primitives::shinkai_utils::shinkai_logging::{shinkai_log, ShinkaiLogLevel, ShinkaiLogOption};
 use shinkai_sqlite::SqliteManager;
 use tokio::sync::Mutex;
 use x25519_dalek::PublicKey as EncryptionPublicKey;
@@ -66,6 +68,8 @@ use crate::{
 
 use shinkai_message_primitives::schemas::shinkai_preferences::ShinkaiInternalComms;
 use std::collections::HashMap;
+use chrono::{DateTime, Utc};
+use rand::Rng;
 use std::time::Instant;
 use tokio::time::Duration;
 use x25519_dalek::StaticSecret as EncryptionStaticKey;
@@ -89,41 +93,160 @@ fn check_bearer_token(api_key: &str, bearer: &str) -> Result<(), ()> {
 }
 
 impl Node {
+    /// Accepts the node API key or a named API key with the `admin` scope
     pub async fn validate_bearer_token<T>(
         bearer: &str,
         db: Arc<SqliteManager>,
         res: &Sender<Result<T, APIError>>,
     ) -> Result<(), ()> {
+        Self::validate_bearer_scope(bearer, db, res, ApiKeyScope::Admin)
+            .await
+            .map(|_| ())
+    }
+
+    /// Accepts the node API key, or a named API key holding `scope`. Returns the named key when
+    /// one was used so the caller can apply its agent restriction.
+    pub async fn validate_bearer_scope<T>(
+        bearer: &str,
+        db: Arc<SqliteManager>,
+        res: &Sender<Result<T, APIError>>,
+        scope: ApiKeyScope,
+    ) -> Result<Option<ApiKey>, ()> {
         // Compare bearer token to the environment variable API_V2_KEY
         let api_key = match env::var("API_V2_KEY") {
-            Ok(api_key) => api_key,
-            Err(_) => {
-                // If the environment variable is not set, read from the database
-                match db.read_api_v2_key() {
-                    Ok(Some(api_key)) => api_key,
-                    Ok(None) | Err(_) => {
-                        let api_error = APIError {
-                            code: StatusCode::UNAUTHORIZED.as_u16(),
-                            error: "Unauthorized".to_string(),
-                            message: "Invalid bearer token".to_string(),
-                        };
-                        let _ = res.send(Err(api_error)).await;
-                        return Err(());
-                    }
-                }
-            }
+            Ok(api_key) => Some(api_key),
+            // If the environment variable is not set, read from the database
+            Err(_) => db.read_api_v2_key().ok().flatten(),
         };
+        if let Some(api_key) = api_key {
+            if check_bearer_token(&api_key, bearer).is_ok() {
+                return Ok(None);
+            }
+        }
 
-        let result = check_bearer_token(&api_key, bearer);
-        match result {
-            Ok(_) => Ok(()),
-            Err(_) => {
+        let named_key = match db.get_api_key_by_secret(bearer) {
+            Ok(Some(named_key)) if named_key.is_active(Utc::now()) => named_key,
+            _ => {
                 let api_error = APIError {
                     code: StatusCode::UNAUTHORIZED.as_u16(),
                     error: "Unauthorized".to_string(),
                     message: "Invalid bearer token".to_string(),
                 };
                 let _ = res.send(Err(api_error)).await;
+                return Err(());
+            }
+        };
+
+        if !named_key.has_scope(scope) {
+            let api_error = APIError {
+                code: StatusCode::FORBIDDEN.as_u16(),
+                error: "Forbidden".to_string(),
+                message: format!("API key '{}' is missing the {} scope", named_key.name, scope),
+            };
+            let _ = res.send(Err(api_error)).await;
+            return Err(());
+        }
+
+        if let Err(e) = db.update_api_key_last_used(&named_key.id) {
+            shinkai_log(
+                ShinkaiLogOption::Api,
+                ShinkaiLogLevel::Error,
+                &format!("Failed to update API key last use: {}", e),
+            );
+        }
+        Ok(Some(named_key))
+    }
+
+    /// Actor name and API key id recorded in the audit log for a bearer token. Tokens that match
+    /// no key are still recorded, as `unauthenticated`.
+    pub fn audit_actor(bearer: &str, db: &SqliteManager) -> (String, Option<String>) {
+        let api_key = match env::var("API_V2_KEY") {
+            Ok(api_key) => Some(api_key),
+            Err(_) => db.read_api_v2_key().ok().flatten(),
+        };
+        if api_key.map_or(false, |api_key| check_bearer_token(&api_key, bearer).is_ok()) {
+            return ("node".to_string(), None);
+        }
+
+        match db.get_api_key_by_secret(bearer) {
+            Ok(Some(named_key)) => (named_key.name, Some(named_key.id)),
+            _ => ("unauthenticated".to_string(), None),
+        }
+    }
+
+    /// Rejects the request when the named API key is restricted to other agents
+    pub async fn validate_api_key_agent<T>(
+        api_key: &Option<ApiKey>,
+        agent_id: &str,
+        res: &Sender<Result<T, APIError>>,
+    ) -> Result<(), ()> {
+        match api_key {
+            Some(api_key) if !api_key.allows_agent(agent_id) => {
+                let api_error = APIError {
+                    code: StatusCode::FORBIDDEN.as_u16(),
+                    error: "Forbidden".to_string(),
+                    message: format!("API key '{}' is not allowed to use agent {}", api_key.name, agent_id),
+                };
+                let _ = res.send(Err(api_error)).await;
+                Err(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Rejects the request when the named API key is restricted to agents other than the one of
+    /// the job. Unrestricted keys skip the job lookup.
+    pub async fn validate_api_key_job<T>(
+        db: &SqliteManager,
+        api_key: &Option<ApiKey>,
+        job_id: &str,
+        res: &Sender<Result<T, APIError>>,
+    ) -> Result<(), ()> {
+        if !api_key.as_ref().map_or(false, |api_key| api_key.agent_ids.is_some()) {
+            return Ok(());
+        }
+
+        match db.get_job_with_options(job_id, false) {
+            Ok(job) => Self::validate_api_key_agent(api_key, &job.parent_agent_or_llm_provider_id, res).await,
+            Err(err) => {
+                let api_error = APIError {
+                    code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                    error: "Internal Server Error".to_string(),
+                    message: format!("Failed to retrieve job: {}", err),
+                };
+                let _ = res.send(Err(api_error)).await;
+                Err(())
+            }
+        }
+    }
+
+    /// Same as `validate_api_key_job` for the job of an inbox. Keys restricted to some agents
+    /// can't read inboxes that don't belong to a job.
+    pub async fn validate_api_key_inbox<T>(
+        db: &SqliteManager,
+        api_key: &Option<ApiKey>,
+        inbox_name: &str,
+        res: &Sender<Result<T, APIError>>,
+    ) -> Result<(), ()> {
+        let Some(restricted_key) = api_key.as_ref().filter(|api_key| api_key.agent_ids.is_some()) else {
+            return Ok(());
+        };
+
+        let job_id = InboxName::new(inbox_name.to_string())
+            .ok()
+            .and_then(|inbox| inbox.get_job_id());
+        match job_id {
+            Some(job_id) => Self::validate_api_key_job(db, api_key, &job_id, res).await,
+            None => {
+                let api_error = APIError {
+                    code: StatusCode::FORBIDDEN.as_u16(),
+                    error: "Forbidden".to_string(),
+                    message: format!(
+                        "API key '{}' is restricted to some agents and can only access job inboxes",
+                        restricted_key.name
+                    ),
+                };
+                let _ = res.send(Err(api_error)).await;
                 Err(())
             }
         }
@@ -1331,7 +1454,7 @@ impl Node {
         res: Sender<Result<Agent, APIError>>,
     ) -> Result<(), NodeError> {
         // Validate the bearer token
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+        if Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::JobsRead).await.is_err() {
             return Ok(());
         }
 
@@ -1376,13 +1499,18 @@ impl Node {
         res: Sender<Result<Vec<Agent>, APIError>>,
     ) -> Result<(), NodeError> {
         // Validate the bearer token
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
-            return Ok(());
-        }
+        let api_key = match Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::JobsRead).await {
+            Ok(api_key) => api_key,
+            Err(_) => return Ok(()),
+        };
 
         // Retrieve all agents from the database
         match db.get_all_agents() {
             Ok(mut agents) => {
+                // Keys restricted to some agents only see those
+                if let Some(api_key) = &api_key {
+                    agents.retain(|agent| api_key.allows_agent(&agent.agent_id));
+                }
                 // Get cron tasks for each agent
                 for agent in &mut agents {
                     match db.get_cron_tasks_by_llm_provider_id(&agent.agent_id) {
@@ -2324,4 +2452,124 @@ impl Node {
         }
         Ok(())
     }
+
+    pub async fn v2_api_create_api_key(
+        db: Arc<SqliteManager>,
+        bearer: String,
+        name: String,
+        scopes: Vec<ApiKeyScope>,
+        agent_ids: Option<Vec<String>>,
+        expires_at: Option<DateTime<Utc>>,
+        res: Sender<Result<Value, APIError>>,
+    ) -> Result<(), NodeError> {
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        let invalid_reason = if name.trim().is_empty() {
+            Some("API key name can't be empty")
+        } else if scopes.is_empty() {
+            Some("API key needs at least one scope")
+        } else if expires_at.map_or(false, |expires_at| expires_at <= Utc::now()) {
+            Some("API key expiry must be in the future")
+        } else {
+            None
+        };
+        if let Some(reason) = invalid_reason {
+            let api_error = APIError {
+                code: StatusCode::BAD_REQUEST.as_u16(),
+                error: "Bad Request".to_string(),
+                message: reason.to_string(),
+            };
+            let _ = res.send(Err(api_error)).await;
+            return Ok(());
+        }
+
+        let secret = format!("sk_shinkai_{}", hex::encode(rand::thread_rng().gen::<[u8; 32]>()));
+        let api_key = ApiKey {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.trim().to_string(),
+            key_prefix: secret[..16].to_string(),
+            scopes,
+            agent_ids,
+            expires_at,
+            created_at: Utc::now(),
+            last_used_at: None,
+            revoked: false,
+        };
+
+        match db.add_api_key(&api_key, &secret) {
+            Ok(_) => {
+                let _ = res.send(Ok(json!({ "api_key": api_key, "secret": secret }))).await;
+            }
+            Err(e) => {
+                let api_error = APIError {
+                    code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                    error: "Internal Server Error".to_string(),
+                    message: format!("Failed to create API key: {}", e),
+                };
+                let _ = res.send(Err(api_error)).await;
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn v2_api_list_api_keys(
+        db: Arc<SqliteManager>,
+        bearer: String,
+        res: Sender<Result<Vec<ApiKey>, APIError>>,
+    ) -> Result<(), NodeError> {
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        match db.list_api_keys() {
+            Ok(api_keys) => {
+                let _ = res.send(Ok(api_keys)).await;
+            }
+            Err(e) => {
+                let api_error = APIError {
+                    code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                    error: "Internal Server Error".to_string(),
+                    message: format!("Failed to list API keys: {}", e),
+                };
+                let _ = res.send(Err(api_error)).await;
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn v2_api_revoke_api_key(
+        db: Arc<SqliteManager>,
+        bearer: String,
+        id: String,
+        res: Sender<Result<Value, APIError>>,
+    ) -> Result<(), NodeError> {
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        match db.revoke_api_key(&id) {
+            Ok(true) => {
+                let _ = res.send(Ok(json!({ "id": id, "revoked": true }))).await;
+            }
+            Ok(false) => {
+                let api_error = APIError {
+                    code: StatusCode::NOT_FOUND.as_u16(),
+                    error: "Not Found".to_string(),
+                    message: format!("API key not found: {}", id),
+                };
+                let _ = res.send(Err(api_error)).await;
+            }
+            Err(e) => {
+                let api_error = APIError {
+                    code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                    error: "Internal Server Error".to_string(),
+                    message: format!("Failed to revoke API key: {}", e),
+                };
+                let _ = res.send(Err(api_error)).await;
+            }
+        }
+        Ok(())
+    }
 }
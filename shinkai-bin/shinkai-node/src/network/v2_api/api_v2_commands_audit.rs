@@ -0,0 +1,174 @@
+use std::sync::Arc;
+
+use async_channel::Sender;
+use reqwest::StatusCode;
+use serde_json::Value;
+use shinkai_http_api::node_api_router::APIError;
+use shinkai_message_primitives::schemas::audit_log::{
+    AuditAction, AuditLogEntry, AuditLogFilter, AuditLogVerification, AuditOutcome,
+};
+use shinkai_message_primitives::shinkai_utils::shinkai_logging::{shinkai_log, ShinkaiLogLevel, ShinkaiLogOption};
+use shinkai_sqlite::SqliteManager;
+
+use crate::network::{node_error::NodeError, node_metrics::node_metrics, Node};
+
+/// Top-level fields of a JSON payload. Tool and agent payloads can hold API keys in their
+/// config, so the audit log records which fields changed rather than their values.
+pub fn audit_fields(payload: &Value) -> Vec<String> {
+    payload
+        .as_object()
+        .map(|fields| fields.keys().cloned().collect())
+        .unwrap_or_default()
+}
+
+impl Node {
+    /// Returns a response channel that records the outcome of the request in the audit log
+    /// before forwarding it to `res`. Requests rejected by the bearer check are recorded too.
+    pub fn audited<T: Send + 'static>(
+        db: Arc<SqliteManager>,
+        bearer: &str,
+        action: AuditAction,
+        target: Option<String>,
+        parameters: Value,
+        res: Sender<Result<T, APIError>>,
+    ) -> Sender<Result<T, APIError>> {
+        let bearer = bearer.to_string();
+        let (audited_res, audited_receiver) = async_channel::bounded(1);
+
+        tokio::spawn(async move {
+            let result = audited_receiver.recv().await;
+            let outcome = match &result {
+                Ok(Ok(_)) => AuditOutcome::Success,
+                Ok(Err(api_error)) => AuditOutcome::Failure {
+                    code: api_error.code,
+                    message: api_error.message.clone(),
+                },
+                Err(_) => AuditOutcome::Failure {
+                    code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                    message: "The request ended without a response".to_string(),
+                },
+            };
+
+            let (actor, api_key_id) = Self::audit_actor(&bearer, &db);
+            if let Err(e) = db.append_audit_log_entry(
+                &actor,
+                api_key_id.as_deref(),
+                action,
+                target.as_deref(),
+                parameters,
+                outcome,
+            ) {
+                node_metrics().record_audit_log_write_failure();
+                shinkai_log(
+                    ShinkaiLogOption::Api,
+                    ShinkaiLogLevel::Error,
+                    &format!("Failed to write audit log entry for {}: {}", action, e),
+                );
+            }
+
+            if let Ok(result) = result {
+                let _ = res.send(result).await;
+            }
+        });
+
+        audited_res
+    }
+
+    pub async fn v2_api_get_audit_log(
+        db: Arc<SqliteManager>,
+        bearer: String,
+        filter: AuditLogFilter,
+        res: Sender<Result<Vec<AuditLogEntry>, APIError>>,
+    ) -> Result<(), NodeError> {
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        match db.query_audit_log(&filter) {
+            Ok(entries) => {
+                let _ = res.send(Ok(entries)).await;
+            }
+            Err(e) => {
+                let api_error = APIError {
+                    code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                    error: "Internal Server Error".to_string(),
+                    message: format!("Failed to query audit log: {}", e),
+                };
+                let _ = res.send(Err(api_error)).await;
+            }
+        }
+        Ok(())
+    }
+
+    /// Matching entries as JSON Lines, one entry per line
+    pub async fn v2_api_export_audit_log(
+        db: Arc<SqliteManager>,
+        bearer: String,
+        filter: AuditLogFilter,
+        res: Sender<Result<String, APIError>>,
+    ) -> Result<(), NodeError> {
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        let entries = match db.query_audit_log(&filter) {
+            Ok(entries) => entries,
+            Err(e) => {
+                let api_error = APIError {
+                    code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                    error: "Internal Server Error".to_string(),
+                    message: format!("Failed to query audit log: {}", e),
+                };
+                let _ = res.send(Err(api_error)).await;
+                return Ok(());
+            }
+        };
+
+        let mut jsonl = String::new();
+        for entry in entries {
+            match serde_json::to_string(&entry) {
+                Ok(line) => {
+                    jsonl.push_str(&line);
+                    jsonl.push('\n');
+                }
+                Err(e) => {
+                    let api_error = APIError {
+                        code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                        error: "Internal Server Error".to_string(),
+                        message: format!("Failed to serialize audit log entry {}: {}", entry.id, e),
+                    };
+                    let _ = res.send(Err(api_error)).await;
+                    return Ok(());
+                }
+            }
+        }
+
+        let _ = res.send(Ok(jsonl)).await;
+        Ok(())
+    }
+
+    pub async fn v2_api_verify_audit_log(
+        db: Arc<SqliteManager>,
+        bearer: String,
+        res: Sender<Result<AuditLogVerification, APIError>>,
+    ) -> Result<(), NodeError> {
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        match db.verify_audit_log() {
+            Ok(verification) => {
+                let _ = res.send(Ok(verification)).await;
+            }
+            Err(e) => {
+                let api_error = APIError {
+                    code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                    error: "Internal Server Error".to_string(),
+                    message: format!("Failed to verify audit log: {}", e),
+                };
+                let _ = res.send(Err(api_error)).await;
+            }
+        }
+        Ok(())
+    }
+}
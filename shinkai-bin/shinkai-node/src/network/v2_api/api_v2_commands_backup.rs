@@ -0,0 +1,630 @@
+use std::fs;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use async_channel::Sender;
+use chrono::Utc;
+use ed25519_dalek::VerifyingKey;
+use reqwest::StatusCode;
+use shinkai_http_api::node_api_router::APIError;
+use shinkai_message_primitives::schemas::indexable_version::IndexableVersion;
+use shinkai_message_primitives::schemas::node_backup::{
+    BackupCategory, BackupManifest, BackupRestoreReport, CreateBackupRequest, RestoreBackupRequest,
+    NODE_BACKUP_FORMAT_VERSION,
+};
+use shinkai_message_primitives::schemas::shinkai_name::ShinkaiName;
+use shinkai_message_primitives::shinkai_utils::secret_vault::SecretVault;
+use shinkai_message_primitives::shinkai_utils::shinkai_path::ShinkaiPath;
+use shinkai_message_primitives::shinkai_utils::signatures::signature_public_key_to_string;
+use shinkai_sqlite::SqliteManager;
+use tokio::sync::Mutex;
+use zip::{write::FileOptions, ZipArchive, ZipWriter};
+
+use crate::llm_provider::job_manager::JobManager;
+use crate::network::{node_error::NodeError, Node};
+use crate::utils::environment::fetch_node_environment;
+
+/// Start of encrypted archives, followed by the Argon2 salt and the encrypted zip
+const ENCRYPTED_BACKUP_MAGIC: &[u8] = b"SHKBAK01";
+const BACKUP_SALT_LEN: usize = 16;
+/// Encrypted archives are split in chunks of this size, so they never have to fit in memory
+const BACKUP_CHUNK_SIZE: usize = 1024 * 1024;
+/// Nonce and authentication tag added to each encrypted chunk
+const BACKUP_CHUNK_OVERHEAD: usize = 12 + 16;
+const MANIFEST_FILE: &str = "manifest.json";
+const DATABASE_FILE: &str = "database.sqlite";
+const FILES_DIR: &str = "files";
+const TOOL_HOMES_DIR: &str = "tool_homes";
+
+fn bad_request(message: String) -> APIError {
+    APIError {
+        code: StatusCode::BAD_REQUEST.as_u16(),
+        error: "Bad Request".to_string(),
+        message,
+    }
+}
+
+fn internal_error(message: String) -> APIError {
+    APIError {
+        code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+        error: "Internal Server Error".to_string(),
+        message,
+    }
+}
+
+fn truncated_backup() -> APIError {
+    bad_request("The backup archive is truncated".to_string())
+}
+
+/// Folders stored with each category, as their name in the archive and their path on disk.
+/// `tool_app_ids` are the tools whose home folder, `tools_storage/{app_id}/home`, is included.
+fn backup_category_folders(category: BackupCategory, tool_app_ids: &[String]) -> Vec<(String, PathBuf)> {
+    match category {
+        BackupCategory::Tools => {
+            let storage_path = PathBuf::from(fetch_node_environment().node_storage_path.unwrap_or_default());
+            let mut folders = vec![("tools_storage".to_string(), storage_path.join(".tools_storage"))];
+            folders.extend(tool_app_ids.iter().map(|app_id| {
+                (
+                    format!("{}/{}", TOOL_HOMES_DIR, app_id),
+                    storage_path.join("tools_storage").join(app_id).join("home"),
+                )
+            }));
+            folders
+        }
+        BackupCategory::VectorFs => vec![("filesystem".to_string(), ShinkaiPath::base_path())],
+        _ => vec![],
+    }
+}
+
+/// Tools with a home folder on disk
+fn tool_home_app_ids() -> Vec<String> {
+    let storage_path = PathBuf::from(fetch_node_environment().node_storage_path.unwrap_or_default());
+    let Ok(entries) = fs::read_dir(storage_path.join("tools_storage")) else {
+        return vec![];
+    };
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().join("home").is_dir())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect()
+}
+
+/// Tools with a home folder in the archive
+fn archived_tool_home_app_ids(archive: &ZipArchive<fs::File>) -> Vec<String> {
+    let prefix = Path::new(FILES_DIR).join(TOOL_HOMES_DIR);
+    let mut app_ids: Vec<String> = archive
+        .file_names()
+        .filter_map(|name| {
+            let relative_path = Path::new(name).strip_prefix(&prefix).ok()?;
+            match relative_path.components().next()? {
+                std::path::Component::Normal(app_id) => Some(app_id.to_string_lossy().to_string()),
+                _ => None,
+            }
+        })
+        .collect();
+    app_ids.sort();
+    app_ids.dedup();
+    app_ids
+}
+
+fn add_folder_to_zip<W: Write + Seek>(zip: &mut ZipWriter<W>, folder: &Path, prefix: &str) -> Result<u64, String> {
+    if !folder.is_dir() {
+        return Ok(0);
+    }
+
+    let mut files = 0;
+    for entry in fs::read_dir(folder).map_err(|e| e.to_string())? {
+        let entry = entry.map_err(|e| e.to_string())?;
+        let file_type = entry.file_type().map_err(|e| e.to_string())?;
+        let name = format!("{}/{}", prefix, entry.file_name().to_string_lossy());
+        if file_type.is_dir() {
+            files += add_folder_to_zip(zip, &entry.path(), &name)?;
+        } else if file_type.is_file() {
+            zip.start_file::<_, ()>(name, FileOptions::default())
+                .map_err(|e| e.to_string())?;
+            let mut file = fs::File::open(entry.path()).map_err(|e| e.to_string())?;
+            std::io::copy(&mut file, zip).map_err(|e| e.to_string())?;
+            files += 1;
+        }
+    }
+    Ok(files)
+}
+
+/// Extracts `files/{name}/` to `staging`
+fn extract_folder_from_zip(archive: &mut ZipArchive<fs::File>, name: &str, staging: &Path) -> Result<u64, String> {
+    let prefix = Path::new(FILES_DIR).join(name);
+    fs::create_dir_all(staging).map_err(|e| e.to_string())?;
+
+    let mut files = 0;
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index).map_err(|e| e.to_string())?;
+        // Entries that would escape the folder are ignored
+        let Some(relative_path) = file
+            .enclosed_name()
+            .and_then(|path| path.strip_prefix(&prefix).ok().map(Path::to_path_buf))
+        else {
+            continue;
+        };
+        if file.is_dir() {
+            continue;
+        }
+
+        let target = staging.join(relative_path);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let mut output = fs::File::create(&target).map_err(|e| e.to_string())?;
+        std::io::copy(&mut file, &mut output).map_err(|e| e.to_string())?;
+        files += 1;
+    }
+    Ok(files)
+}
+
+/// Storage folder restored from an archive. It is extracted next to the current folder, swapped in
+/// while the current one is kept aside, and the current one is only removed once the database is
+/// restored too, so a failure at any step puts every folder back as it was.
+struct StagedFolder {
+    folder: PathBuf,
+    staging: PathBuf,
+    replaced: PathBuf,
+    swapped: bool,
+}
+
+impl StagedFolder {
+    fn new(folder: PathBuf) -> Result<Self, String> {
+        let staged = StagedFolder {
+            staging: folder.with_extension("restoring"),
+            replaced: folder.with_extension("replaced"),
+            folder,
+            swapped: false,
+        };
+        // Left over by a restore that was interrupted
+        for leftover in [&staged.staging, &staged.replaced] {
+            if leftover.exists() {
+                fs::remove_dir_all(leftover).map_err(|e| e.to_string())?;
+            }
+        }
+        Ok(staged)
+    }
+
+    fn swap_in(&mut self) -> Result<(), String> {
+        if self.folder.exists() {
+            fs::rename(&self.folder, &self.replaced).map_err(|e| e.to_string())?;
+        }
+        if let Err(e) = fs::rename(&self.staging, &self.folder) {
+            if self.replaced.exists() {
+                let _ = fs::rename(&self.replaced, &self.folder);
+            }
+            return Err(e.to_string());
+        }
+        self.swapped = true;
+        Ok(())
+    }
+
+    fn roll_back(&self) {
+        if self.swapped {
+            let _ = fs::remove_dir_all(&self.folder);
+            if self.replaced.exists() {
+                let _ = fs::rename(&self.replaced, &self.folder);
+            }
+        }
+        if self.staging.exists() {
+            let _ = fs::remove_dir_all(&self.staging);
+        }
+    }
+
+    fn finish(&self) {
+        if self.replaced.exists() {
+            let _ = fs::remove_dir_all(&self.replaced);
+        }
+    }
+}
+
+// The index of each chunk and whether it is the last one are authenticated, so chunks can't be
+// reordered or dropped
+fn backup_chunk_aad(index: u64, last: bool) -> [u8; 9] {
+    let mut aad = [0u8; 9];
+    aad[..8].copy_from_slice(&index.to_be_bytes());
+    aad[8] = last as u8;
+    aad
+}
+
+fn read_backup_chunk(reader: &mut impl Read) -> std::io::Result<Vec<u8>> {
+    let mut chunk = Vec::with_capacity(BACKUP_CHUNK_SIZE);
+    reader.take(BACKUP_CHUNK_SIZE as u64).read_to_end(&mut chunk)?;
+    Ok(chunk)
+}
+
+/// Writes the magic, the Argon2 salt of `vault` and then each encrypted chunk prefixed by its
+/// length
+fn encrypt_backup(
+    archive: &mut impl Read,
+    output: &mut impl Write,
+    vault: &SecretVault,
+    salt: &[u8],
+) -> Result<(), String> {
+    output.write_all(ENCRYPTED_BACKUP_MAGIC).map_err(|e| e.to_string())?;
+    output.write_all(salt).map_err(|e| e.to_string())?;
+
+    let mut chunk = read_backup_chunk(archive).map_err(|e| e.to_string())?;
+    let mut index = 0;
+    loop {
+        let next_chunk = if chunk.len() == BACKUP_CHUNK_SIZE {
+            read_backup_chunk(archive).map_err(|e| e.to_string())?
+        } else {
+            Vec::new()
+        };
+        let last = next_chunk.is_empty();
+        let payload = vault
+            .encrypt_bytes_with_aad(&chunk, &backup_chunk_aad(index, last))
+            .map_err(|e| e.to_string())?;
+        output
+            .write_all(&(payload.len() as u32).to_be_bytes())
+            .map_err(|e| e.to_string())?;
+        output.write_all(&payload).map_err(|e| e.to_string())?;
+        if last {
+            return Ok(());
+        }
+        chunk = next_chunk;
+        index += 1;
+    }
+}
+
+/// Reads until `buf` is full or the end of the input, returning how much was read
+fn read_up_to(input: &mut impl Read, buf: &mut [u8]) -> Result<usize, APIError> {
+    let mut read = 0;
+    while read < buf.len() {
+        match input.read(&mut buf[read..]) {
+            Ok(0) => break,
+            Ok(n) => read += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(internal_error(format!("Failed to read the backup: {}", e))),
+        }
+    }
+    Ok(read)
+}
+
+/// Length of the next encrypted chunk, `None` at the end of the archive
+fn read_backup_chunk_length(input: &mut impl Read) -> Result<Option<usize>, APIError> {
+    let mut length = [0u8; 4];
+    match read_up_to(input, &mut length)? {
+        0 => Ok(None),
+        4 => Ok(Some(u32::from_be_bytes(length) as usize)),
+        _ => Err(truncated_backup()),
+    }
+}
+
+/// Decrypts an archive chunk by chunk to an unnamed temporary file. Plain archives are returned
+/// as they are. Encrypted ones come with the vault derived from the passphrase, which also seals
+/// their secrets.
+fn decrypt_backup(mut input: fs::File, passphrase: Option<&str>) -> Result<(fs::File, Option<SecretVault>), APIError> {
+    let mut magic = [0u8; ENCRYPTED_BACKUP_MAGIC.len()];
+    let read = read_up_to(&mut input, &mut magic)?;
+    if read < magic.len() || magic != ENCRYPTED_BACKUP_MAGIC {
+        input
+            .seek(SeekFrom::Start(0))
+            .map_err(|e| internal_error(e.to_string()))?;
+        return Ok((input, None));
+    }
+    let passphrase =
+        passphrase.ok_or_else(|| bad_request("The backup is encrypted, a passphrase is required".to_string()))?;
+    let mut salt = [0u8; BACKUP_SALT_LEN];
+    if read_up_to(&mut input, &mut salt)? < BACKUP_SALT_LEN {
+        return Err(truncated_backup());
+    }
+    let vault = SecretVault::from_passphrase(passphrase, &salt).map_err(|e| internal_error(e.to_string()))?;
+
+    let mut archive = tempfile::tempfile().map_err(|e| internal_error(e.to_string()))?;
+    let mut length = read_backup_chunk_length(&mut input)?.ok_or_else(truncated_backup)?;
+    let mut index = 0;
+    loop {
+        if length > BACKUP_CHUNK_SIZE + BACKUP_CHUNK_OVERHEAD {
+            return Err(bad_request("Wrong passphrase or corrupted backup".to_string()));
+        }
+        let mut payload = vec![0u8; length];
+        if read_up_to(&mut input, &mut payload)? < length {
+            return Err(truncated_backup());
+        }
+        let next_length = read_backup_chunk_length(&mut input)?;
+        let last = next_length.is_none();
+        let chunk = vault
+            .decrypt_bytes_with_aad(&payload, &backup_chunk_aad(index, last))
+            .map_err(|_| bad_request("Wrong passphrase or corrupted backup".to_string()))?;
+        archive
+            .write_all(&chunk)
+            .map_err(|e| internal_error(format!("Failed to write the decrypted backup: {}", e)))?;
+        let Some(next_length) = next_length else {
+            break;
+        };
+        length = next_length;
+        index += 1;
+    }
+
+    archive
+        .seek(SeekFrom::Start(0))
+        .map_err(|e| internal_error(e.to_string()))?;
+    Ok((archive, Some(vault)))
+}
+
+impl Node {
+    pub async fn v2_api_create_backup(
+        db: Arc<SqliteManager>,
+        node_name: ShinkaiName,
+        identity_public_key: VerifyingKey,
+        bearer: String,
+        payload: CreateBackupRequest,
+        res: Sender<Result<fs::File, APIError>>,
+    ) -> Result<(), NodeError> {
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        // Copying the database and the storage folders is blocking work
+        let result = tokio::task::spawn_blocking(move || {
+            Self::create_backup_archive(&db, &node_name, identity_public_key, payload)
+        })
+        .await
+        .unwrap_or_else(|e| Err(internal_error(format!("Backup task failed: {}", e))));
+
+        let _ = res.send(result).await;
+        Ok(())
+    }
+
+    pub async fn v2_api_restore_backup(
+        db: Arc<SqliteManager>,
+        node_name: ShinkaiName,
+        job_manager: Arc<Mutex<JobManager>>,
+        bearer: String,
+        file: fs::File,
+        payload: RestoreBackupRequest,
+        res: Sender<Result<BackupRestoreReport, APIError>>,
+    ) -> Result<(), NodeError> {
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        // Jobs in progress would read and write the data while it is replaced
+        let queue_status = job_manager.lock().await.queue_status().await;
+        match queue_status {
+            Ok(status) if status.queued_normal + status.queued_immediate > 0 => {
+                let api_error = APIError {
+                    code: StatusCode::CONFLICT.as_u16(),
+                    error: "Conflict".to_string(),
+                    message: "Jobs are in progress, wait for them to finish or stop them before restoring a backup"
+                        .to_string(),
+                };
+                let _ = res.send(Err(api_error)).await;
+                return Ok(());
+            }
+            Ok(_) => {}
+            Err(e) => {
+                let _ = res
+                    .send(Err(internal_error(format!("Failed to read the job queue: {}", e))))
+                    .await;
+                return Ok(());
+            }
+        }
+
+        let result = tokio::task::spawn_blocking(move || Self::restore_backup_archive(&db, &node_name, file, payload))
+            .await
+            .unwrap_or_else(|e| Err(internal_error(format!("Restore task failed: {}", e))));
+
+        let _ = res.send(result).await;
+        Ok(())
+    }
+
+    fn create_backup_archive(
+        db: &SqliteManager,
+        node_name: &ShinkaiName,
+        identity_public_key: VerifyingKey,
+        payload: CreateBackupRequest,
+    ) -> Result<fs::File, APIError> {
+        let categories = if payload.categories.is_empty() {
+            BackupCategory::all()
+        } else {
+            payload.categories
+        };
+        let passphrase = payload.passphrase.filter(|passphrase| !passphrase.is_empty());
+        // Encrypted archives seal their secrets with the passphrase, so another node can restore them
+        let backup_vault = passphrase
+            .map(|passphrase| {
+                let salt = SecretVault::random_salt();
+                SecretVault::from_passphrase(&passphrase, &salt).map(|vault| (vault, salt))
+            })
+            .transpose()
+            .map_err(|e| internal_error(format!("Failed to derive the backup key: {}", e)))?;
+
+        let embedding_model = db
+            .get_default_embedding_model()
+            .map_err(|e| internal_error(format!("Failed to read the embedding model: {}", e)))?;
+        let manifest = BackupManifest {
+            format_version: NODE_BACKUP_FORMAT_VERSION,
+            node_version: env!("CARGO_PKG_VERSION").to_string(),
+            created_at: Utc::now(),
+            node_name: node_name.get_node_name_string(),
+            identity_public_key: signature_public_key_to_string(identity_public_key),
+            embedding_model: embedding_model.to_string(),
+            categories: categories.clone(),
+            secret_vault_key_id: match &backup_vault {
+                Some((vault, _)) => Some(vault.key_id().to_string()),
+                None => db.secret_vault_key_id(),
+            },
+        };
+
+        let temp_dir = tempfile::tempdir().map_err(|e| internal_error(e.to_string()))?;
+        let db_path = temp_dir.path().join(DATABASE_FILE);
+        db.backup_database(&db_path)
+            .and_then(|_| {
+                db.prepare_database_backup(&db_path, &categories, backup_vault.as_ref().map(|(vault, _)| vault))
+            })
+            .map_err(|e| internal_error(format!("Failed to back up the database: {}", e)))?;
+
+        // The archive is written to an unnamed temporary file, removed once the caller drops it
+        let write_archive = || -> Result<fs::File, String> {
+            let mut zip = ZipWriter::new(tempfile::tempfile().map_err(|e| e.to_string())?);
+            zip.start_file::<_, ()>(MANIFEST_FILE, FileOptions::default())
+                .map_err(|e| e.to_string())?;
+            zip.write_all(&serde_json::to_vec_pretty(&manifest).map_err(|e| e.to_string())?)
+                .map_err(|e| e.to_string())?;
+
+            zip.start_file::<_, ()>(DATABASE_FILE, FileOptions::default())
+                .map_err(|e| e.to_string())?;
+            let mut db_file = fs::File::open(&db_path).map_err(|e| e.to_string())?;
+            std::io::copy(&mut db_file, &mut zip).map_err(|e| e.to_string())?;
+
+            let tool_app_ids = tool_home_app_ids();
+            for category in &categories {
+                for (name, folder) in backup_category_folders(*category, &tool_app_ids) {
+                    add_folder_to_zip(&mut zip, &folder, &format!("{}/{}", FILES_DIR, name))?;
+                }
+            }
+            let mut archive = zip.finish().map_err(|e| e.to_string())?;
+            archive.seek(SeekFrom::Start(0)).map_err(|e| e.to_string())?;
+            Ok(archive)
+        };
+        let mut archive =
+            write_archive().map_err(|e| internal_error(format!("Failed to write the backup archive: {}", e)))?;
+
+        let Some((vault, salt)) = backup_vault else {
+            return Ok(archive);
+        };
+        let encrypt_archive = || -> Result<fs::File, String> {
+            let mut encrypted = tempfile::tempfile().map_err(|e| e.to_string())?;
+            encrypt_backup(&mut archive, &mut encrypted, &vault, &salt)?;
+            encrypted.seek(SeekFrom::Start(0)).map_err(|e| e.to_string())?;
+            Ok(encrypted)
+        };
+        encrypt_archive().map_err(|e| internal_error(format!("Failed to encrypt the backup: {}", e)))
+    }
+
+    fn restore_backup_archive(
+        db: &SqliteManager,
+        node_name: &ShinkaiName,
+        file: fs::File,
+        payload: RestoreBackupRequest,
+    ) -> Result<BackupRestoreReport, APIError> {
+        let passphrase = payload.passphrase.filter(|passphrase| !passphrase.is_empty());
+        let (archive, passphrase_vault) = decrypt_backup(file, passphrase.as_deref())?;
+        let mut archive = ZipArchive::new(archive).map_err(|e| bad_request(format!("Not a backup archive: {}", e)))?;
+
+        let manifest: BackupManifest = {
+            let mut manifest_file = archive
+                .by_name(MANIFEST_FILE)
+                .map_err(|_| bad_request(format!("The archive has no {}", MANIFEST_FILE)))?;
+            let mut manifest = Vec::new();
+            manifest_file
+                .read_to_end(&mut manifest)
+                .map_err(|e| bad_request(format!("Failed to read {}: {}", MANIFEST_FILE, e)))?;
+            serde_json::from_slice(&manifest).map_err(|e| bad_request(format!("Invalid {}: {}", MANIFEST_FILE, e)))?
+        };
+
+        if manifest.format_version != NODE_BACKUP_FORMAT_VERSION {
+            return Err(bad_request(format!(
+                "Unsupported backup format version {}, expected {}",
+                manifest.format_version, NODE_BACKUP_FORMAT_VERSION
+            )));
+        }
+        let backup_version = IndexableVersion::from_string(&manifest.node_version).map_err(bad_request)?;
+        let node_version = IndexableVersion::from_string(env!("CARGO_PKG_VERSION")).map_err(internal_error)?;
+        if backup_version > node_version {
+            return Err(bad_request(format!(
+                "The backup was created by node version {}, update this node (version {}) before restoring it",
+                manifest.node_version,
+                env!("CARGO_PKG_VERSION")
+            )));
+        }
+
+        let categories = if payload.categories.is_empty() {
+            manifest.categories.clone()
+        } else {
+            payload.categories
+        };
+        if let Some(missing) = categories.iter().find(|category| !manifest.categories.contains(category)) {
+            return Err(bad_request(format!("The backup doesn't include {}", missing)));
+        }
+
+        if categories.iter().any(BackupCategory::has_embeddings) {
+            let embedding_model = db
+                .get_default_embedding_model()
+                .map_err(|e| internal_error(format!("Failed to read the embedding model: {}", e)))?;
+            if embedding_model.to_string() != manifest.embedding_model {
+                return Err(bad_request(format!(
+                    "The backup embeddings were made with {}, but this node uses {}",
+                    manifest.embedding_model, embedding_model
+                )));
+            }
+        }
+        let backup_vault = match &manifest.secret_vault_key_id {
+            Some(key_id) if passphrase_vault.as_ref().is_some_and(|vault| vault.key_id() == key_id) => {
+                passphrase_vault.as_ref()
+            }
+            Some(key_id) if db.secret_vault_key_id().as_ref() == Some(key_id) => None,
+            Some(_) => {
+                return Err(bad_request(
+                    "The backup secrets are sealed with another vault key, restore an encrypted backup instead"
+                        .to_string(),
+                ))
+            }
+            // Secrets stored in plaintext
+            None => None,
+        };
+
+        let temp_dir = tempfile::tempdir().map_err(|e| internal_error(e.to_string()))?;
+        let db_path = temp_dir.path().join(DATABASE_FILE);
+        {
+            let mut db_file = archive
+                .by_name(DATABASE_FILE)
+                .map_err(|_| bad_request(format!("The archive has no {}", DATABASE_FILE)))?;
+            let mut output = fs::File::create(&db_path).map_err(|e| internal_error(e.to_string()))?;
+            std::io::copy(&mut db_file, &mut output).map_err(|e| internal_error(e.to_string()))?;
+        }
+
+        // Every folder is staged before anything is replaced
+        let mut staged_folders = Vec::new();
+        let mut files_restored = 0;
+        let tool_app_ids = archived_tool_home_app_ids(&archive);
+        let mut stage = || -> Result<(), String> {
+            for category in &categories {
+                for (name, folder) in backup_category_folders(*category, &tool_app_ids) {
+                    let staged = StagedFolder::new(folder)?;
+                    let staging = staged.staging.clone();
+                    staged_folders.push(staged);
+                    files_restored += extract_folder_from_zip(&mut archive, &name, &staging)
+                        .map_err(|e| format!("Failed to restore the {} files: {}", category, e))?;
+                }
+            }
+            for staged in staged_folders.iter_mut() {
+                staged.swap_in()?;
+            }
+            Ok(())
+        };
+        if let Err(e) = stage() {
+            staged_folders.iter().for_each(StagedFolder::roll_back);
+            return Err(internal_error(e));
+        }
+
+        // Data of another node is rewritten to use the name of this one
+        let current_node_name = node_name.get_node_name_string();
+        let renamed_from = (manifest.node_name != current_node_name).then(|| manifest.node_name.clone());
+        let rename = renamed_from
+            .as_deref()
+            .map(|old_name| (old_name, current_node_name.as_str()));
+        let rows_restored = match db.restore_database_backup(&db_path, &categories, rename, backup_vault) {
+            Ok(rows_restored) => rows_restored,
+            Err(e) => {
+                staged_folders.iter().for_each(StagedFolder::roll_back);
+                return Err(internal_error(format!("Failed to restore the database: {}", e)));
+            }
+        };
+        staged_folders.iter().for_each(StagedFolder::finish);
+
+        Ok(BackupRestoreReport {
+            restart_required: categories.contains(&BackupCategory::Wallets),
+            categories,
+            rows_restored,
+            files_restored,
+            renamed_from,
+        })
+    }
+}
@@ -1,5 +1,5 @@
 use crate::{
-    cron_tasks::cron_manager::CronManager,
+    cron_tasks::{cron_manager::CronManager, cron_schedule::CronSchedule},
     network::{node_error::NodeError, Node, node_shareable_logic::download_zip_file},
 };
 use async_channel::Sender;
@@ -7,11 +7,11 @@ use ed25519_dalek::SigningKey;
 use reqwest::StatusCode;
 use serde_json::{json, Value};
 use shinkai_http_api::node_api_router::APIError;
-use shinkai_message_primitives::schemas::crontab::{CronTask, CronTaskAction};
+use shinkai_message_primitives::schemas::crontab::{CronEventTrigger, CronTask, CronTaskAction, CronTaskPolicy};
 use shinkai_sqlite::SqliteManager;
 use std::sync::Arc;
 use tokio::sync::Mutex;
-use chrono::Local;
+use chrono::{Local, Utc};
 use std::fs::File;
 use std::io::Write;
 use tokio::fs;
@@ -22,6 +22,9 @@ impl Node {
         db: Arc<SqliteManager>,
         bearer: String,
         cron: String,
+        timezone: Option<String>,
+        policy: CronTaskPolicy,
+        event_trigger: Option<CronEventTrigger>,
         action: CronTaskAction,
         name: String,
         description: Option<String>,
@@ -32,24 +35,52 @@ impl Node {
             return Ok(());
         }
 
-        // Validate cron expression
-        let components: Vec<&str> = cron.split_whitespace().collect();
-        if components.len() != 5 {
+        // Validate the schedule, a one-shot run in the past would never happen. Event-triggered tasks
+        // have none.
+        let schedule = CronSchedule::parse(&cron, timezone.as_deref(), Utc::now()).and_then(|schedule| {
+            match schedule.next_run(Utc::now()) {
+                Some(_) => Ok(schedule),
+                None => Err(format!("The scheduled time of '{}' has already passed", cron)),
+            }
+        });
+        if let (None, Err(message)) = (&event_trigger, schedule) {
             let api_error = APIError {
                 code: StatusCode::BAD_REQUEST.as_u16(),
                 error: "Invalid Cron Expression".to_string(),
-                message: format!(
-                    "Cron expression must have exactly 5 components (minute hour day month weekday), found {}. \
-                 Example of valid cron: '*/30 * * * *'",
-                    components.len()
-                ),
+                message,
+            };
+            let _ = res.send(Err(api_error)).await;
+            return Ok(());
+        }
+        if let Err(message) = policy.validate() {
+            let api_error = APIError {
+                code: StatusCode::BAD_REQUEST.as_u16(),
+                error: "Invalid Policy".to_string(),
+                message,
+            };
+            let _ = res.send(Err(api_error)).await;
+            return Ok(());
+        }
+        if let Some(Err(message)) = event_trigger.as_ref().map(|trigger| trigger.validate(&action)) {
+            let api_error = APIError {
+                code: StatusCode::BAD_REQUEST.as_u16(),
+                error: "Invalid Event Trigger".to_string(),
+                message,
             };
             let _ = res.send(Err(api_error)).await;
             return Ok(());
         }
 
         // Add the cron task
-        match db.add_cron_task(&name, description.as_deref(), &cron, &action) {
+        match db.add_cron_task(
+            &name,
+            description.as_deref(),
+            &cron,
+            timezone.as_deref(),
+            &policy,
+            event_trigger.as_ref(),
+            &action,
+        ) {
             Ok(task_id) => {
                 let response = json!({ "status": "success", "task_id": task_id });
                 let _ = res.send(Ok(response)).await;
@@ -166,18 +197,24 @@ impl Node {
         }
 
         // Get the logs for the cron task
-        match db.get_cron_task_executions(task_id) {
+        match db.get_cron_task_execution_logs(task_id) {
             Ok(logs) => {
                 // Map the logs to the desired structure
                 let formatted_logs: Vec<_> = logs
                     .into_iter()
                     .map(|log| {
                         json!({
-                            "job_id": log.3.as_ref().map_or("", |j| j),
+                            "execution_id": log.execution_id,
+                            "job_id": log.job_id.as_deref().unwrap_or(""),
                             "task_id": task_id.to_string(),
-                            "execution_time": log.0,
-                            "success": log.1,
-                            "error_message": log.2.as_ref().map_or("", |e| e)
+                            "execution_time": log.execution_time,
+                            "scheduled_time": log.scheduled_time,
+                            "attempt": log.attempt,
+                            "misfired": log.misfired,
+                            "status": log.status,
+                            "success": log.success,
+                            "error_message": log.error_message.as_deref().unwrap_or(""),
+                            "finished_at": log.finished_at
                         })
                     })
                     .collect();
@@ -203,6 +240,9 @@ impl Node {
         bearer: String,
         task_id: i64,
         cron: String,
+        timezone: Option<String>,
+        policy: Option<CronTaskPolicy>,
+        event_trigger: Option<CronEventTrigger>,
         action: CronTaskAction,
         name: String,
         description: Option<String>,
@@ -214,8 +254,47 @@ impl Node {
             return Ok(());
         }
 
+        let schedule = CronSchedule::parse(&cron, timezone.as_deref(), Utc::now());
+        if let (None, Err(message)) = (&event_trigger, schedule) {
+            let api_error = APIError {
+                code: StatusCode::BAD_REQUEST.as_u16(),
+                error: "Invalid Cron Expression".to_string(),
+                message,
+            };
+            let _ = res.send(Err(api_error)).await;
+            return Ok(());
+        }
+        if let Some(Err(message)) = policy.as_ref().map(CronTaskPolicy::validate) {
+            let api_error = APIError {
+                code: StatusCode::BAD_REQUEST.as_u16(),
+                error: "Invalid Policy".to_string(),
+                message,
+            };
+            let _ = res.send(Err(api_error)).await;
+            return Ok(());
+        }
+        if let Some(Err(message)) = event_trigger.as_ref().map(|trigger| trigger.validate(&action)) {
+            let api_error = APIError {
+                code: StatusCode::BAD_REQUEST.as_u16(),
+                error: "Invalid Event Trigger".to_string(),
+                message,
+            };
+            let _ = res.send(Err(api_error)).await;
+            return Ok(());
+        }
+
         // Update the cron task
-        match db.update_cron_task(task_id, &name, description.as_deref(), &cron, &action, paused) {
+        match db.update_cron_task(
+            task_id,
+            &name,
+            description.as_deref(),
+            &cron,
+            timezone.as_deref(),
+            policy.as_ref(),
+            event_trigger.as_ref(),
+            &action,
+            paused,
+        ) {
             Ok(_) => {
                 let response = json!({ "status": "success", "message": "Cron task updated successfully" });
                 let _ = res.send(Ok(response)).await;
@@ -357,8 +436,19 @@ impl Node {
                     .ok_or_else(|| NodeError::from("Missing 'action' field".to_string()))?)
                     .map_err(|e| NodeError::from(format!("Invalid action format: {}", e)))?;
                 let description = obj.get("description").and_then(|v| v.as_str()).map(String::from);
+                let timezone = obj.get("timezone").and_then(|v| v.as_str()).map(String::from);
+                let policy: CronTaskPolicy = match obj.get("policy") {
+                    Some(policy) => serde_json::from_value(policy.clone())
+                        .map_err(|e| NodeError::from(format!("Invalid policy format: {}", e)))?,
+                    None => CronTaskPolicy::default(),
+                };
+                let event_trigger: Option<CronEventTrigger> = match obj.get("event_trigger") {
+                    Some(event_trigger) => serde_json::from_value(event_trigger.clone())
+                        .map_err(|e| NodeError::from(format!("Invalid event_trigger format: {}", e)))?,
+                    None => None,
+                };
 
-                (name.to_string(), cron.to_string(), action, description)
+                (name.to_string(), cron.to_string(), action, description, timezone, policy, event_trigger)
             }
             None => {
                 let api_error = APIError {
@@ -373,8 +463,45 @@ impl Node {
 
         println!("cron_task: {:?}", cron_task);
 
+        let schedule = CronSchedule::parse(&cron_task.1, cron_task.4.as_deref(), Utc::now());
+        if let (None, Err(message)) = (&cron_task.6, schedule) {
+            let api_error = APIError {
+                code: StatusCode::BAD_REQUEST.as_u16(),
+                error: "Invalid Cron Expression".to_string(),
+                message,
+            };
+            let _ = res.send(Err(api_error)).await;
+            return Ok(());
+        }
+        if let Err(message) = cron_task.5.validate() {
+            let api_error = APIError {
+                code: StatusCode::BAD_REQUEST.as_u16(),
+                error: "Invalid Policy".to_string(),
+                message,
+            };
+            let _ = res.send(Err(api_error)).await;
+            return Ok(());
+        }
+        if let Some(Err(message)) = cron_task.6.as_ref().map(|trigger| trigger.validate(&cron_task.2)) {
+            let api_error = APIError {
+                code: StatusCode::BAD_REQUEST.as_u16(),
+                error: "Invalid Event Trigger".to_string(),
+                message,
+            };
+            let _ = res.send(Err(api_error)).await;
+            return Ok(());
+        }
+
         // Add the cron task to the database
-        match db.add_cron_task(&cron_task.0, cron_task.3.as_deref(), &cron_task.1, &cron_task.2) {
+        match db.add_cron_task(
+            &cron_task.0,
+            cron_task.3.as_deref(),
+            &cron_task.1,
+            cron_task.4.as_deref(),
+            &cron_task.5,
+            cron_task.6.as_ref(),
+            &cron_task.2,
+        ) {
             Ok(_) => {
                 let response = json!({
                     "status": "success",
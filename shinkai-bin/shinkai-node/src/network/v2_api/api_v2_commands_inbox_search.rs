@@ -0,0 +1,77 @@
+use std::sync::Arc;
+
+use async_channel::Sender;
+use reqwest::StatusCode;
+use shinkai_http_api::node_api_router::APIError;
+use shinkai_message_primitives::schemas::api_key::ApiKeyScope;
+use shinkai_message_primitives::schemas::inbox_search::{InboxMessageSearchRequest, InboxMessageSearchResults};
+use shinkai_sqlite::SqliteManager;
+
+use crate::network::{node_error::NodeError, Node};
+
+impl Node {
+    /// Searches the content of inbox messages by terms or, with `semantic`, by embedding similarity
+    pub async fn v2_api_search_inbox_messages(
+        db: Arc<SqliteManager>,
+        bearer: String,
+        request: InboxMessageSearchRequest,
+        res: Sender<Result<InboxMessageSearchResults, APIError>>,
+    ) -> Result<(), NodeError> {
+        let api_key = match Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::JobsRead).await {
+            Ok(api_key) => api_key,
+            Err(_) => return Ok(()),
+        };
+
+        // Keys restricted to some agents only search the jobs of one of them
+        match (&api_key, &request.agent_id) {
+            (_, Some(agent_id)) => {
+                if Self::validate_api_key_agent(&api_key, agent_id, &res).await.is_err() {
+                    return Ok(());
+                }
+            }
+            (Some(api_key), None) if api_key.agent_ids.is_some() => {
+                let api_error = APIError {
+                    code: StatusCode::FORBIDDEN.as_u16(),
+                    error: "Forbidden".to_string(),
+                    message: format!(
+                        "API key '{}' is restricted to some agents, set agent_id to one of them",
+                        api_key.name
+                    ),
+                };
+                let _ = res.send(Err(api_error)).await;
+                return Ok(());
+            }
+            _ => {}
+        }
+
+        if request.query.trim().is_empty() {
+            let api_error = APIError {
+                code: StatusCode::BAD_REQUEST.as_u16(),
+                error: "Bad Request".to_string(),
+                message: "The search query is empty".to_string(),
+            };
+            let _ = res.send(Err(api_error)).await;
+            return Ok(());
+        }
+
+        let results = if request.semantic {
+            db.search_inbox_messages_semantic(&request).await
+        } else {
+            db.search_inbox_messages(&request)
+        };
+        match results {
+            Ok(results) => {
+                let _ = res.send(Ok(results)).await;
+            }
+            Err(e) => {
+                let api_error = APIError {
+                    code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                    error: "Internal Server Error".to_string(),
+                    message: format!("Failed to search inbox messages: {}", e),
+                };
+                let _ = res.send(Err(api_error)).await;
+            }
+        }
+        Ok(())
+    }
+}
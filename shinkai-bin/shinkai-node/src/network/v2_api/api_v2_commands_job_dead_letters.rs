@@ -0,0 +1,143 @@
+use std::sync::Arc;
+
+use async_channel::Sender;
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use shinkai_http_api::node_api_router::APIError;
+use shinkai_message_primitives::schemas::job_dead_letter::JobDeadLetter;
+use shinkai_sqlite::{errors::SqliteManagerError, SqliteManager};
+use tokio::sync::Mutex;
+
+use crate::{
+    llm_provider::job_manager::JobManager,
+    network::{node_error::NodeError, Node},
+};
+
+fn dead_letter_error(code: StatusCode, message: String) -> APIError {
+    APIError {
+        code: code.as_u16(),
+        error: code.canonical_reason().unwrap_or_default().to_string(),
+        message,
+    }
+}
+
+impl Node {
+    pub async fn v2_api_list_job_dead_letters(
+        db: Arc<SqliteManager>,
+        bearer: String,
+        job_id: Option<String>,
+        res: Sender<Result<Vec<JobDeadLetter>, APIError>>,
+    ) -> Result<(), NodeError> {
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        let result = db.get_job_dead_letters(job_id.as_deref()).map_err(|e| {
+            dead_letter_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to list job dead letters: {}", e),
+            )
+        });
+        let _ = res.send(result).await;
+        Ok(())
+    }
+
+    pub async fn v2_api_requeue_job_dead_letter(
+        db: Arc<SqliteManager>,
+        job_manager: Arc<Mutex<JobManager>>,
+        bearer: String,
+        id: String,
+        res: Sender<Result<Value, APIError>>,
+    ) -> Result<(), NodeError> {
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        let dead_letter = match db.get_job_dead_letter(&id) {
+            Ok(Some(dead_letter)) => dead_letter,
+            Ok(None) => {
+                let _ = res
+                    .send(Err(dead_letter_error(
+                        StatusCode::NOT_FOUND,
+                        format!("Job dead letter not found: {}", id),
+                    )))
+                    .await;
+                return Ok(());
+            }
+            Err(e) => {
+                let _ = res
+                    .send(Err(dead_letter_error(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        format!("Failed to read job dead letter: {}", e),
+                    )))
+                    .await;
+                return Ok(());
+            }
+        };
+
+        // Removed first so that a message failing again right away is dead-lettered anew
+        if let Err(e) = db.remove_job_dead_letter(&id) {
+            let _ = res
+                .send(Err(dead_letter_error(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("Failed to remove job dead letter: {}", e),
+                )))
+                .await;
+            return Ok(());
+        }
+
+        let queued = job_manager
+            .lock()
+            .await
+            .add_job_message_to_job_queue(
+                &dead_letter.job_message,
+                &dead_letter.profile,
+                dead_letter.message_hash_id.clone(),
+                false,
+            )
+            .await;
+        match queued {
+            Ok(job_id) => {
+                let _ = res
+                    .send(Ok(json!({ "id": id, "job_id": job_id, "requeued": true })))
+                    .await;
+            }
+            Err(e) => {
+                // Kept so that it can be requeued again later
+                let _ = db.add_job_dead_letter(&dead_letter);
+                let _ = res
+                    .send(Err(dead_letter_error(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        format!("Failed to requeue job message: {}", e),
+                    )))
+                    .await;
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn v2_api_discard_job_dead_letter(
+        db: Arc<SqliteManager>,
+        bearer: String,
+        id: String,
+        res: Sender<Result<Value, APIError>>,
+    ) -> Result<(), NodeError> {
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        let result = match db.remove_job_dead_letter(&id) {
+            Ok(()) => Ok(json!({ "id": id, "discarded": true })),
+            Err(SqliteManagerError::DataNotFound) => Err(dead_letter_error(
+                StatusCode::NOT_FOUND,
+                format!("Job dead letter not found: {}", id),
+            )),
+            Err(e) => Err(dead_letter_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to discard job dead letter: {}", e),
+            )),
+        };
+        let _ = res.send(result).await;
+        Ok(())
+    }
+}
@@ -0,0 +1,123 @@
+use std::sync::Arc;
+
+use async_channel::Sender;
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use shinkai_http_api::node_api_router::APIError;
+use shinkai_message_primitives::schemas::job_concurrency_limit::{JobConcurrencyLimit, JobLimitTarget, JobQueueStatus};
+use shinkai_sqlite::{errors::SqliteManagerError, SqliteManager};
+use tokio::sync::Mutex;
+
+use crate::{
+    llm_provider::job_manager::JobManager,
+    network::{node_error::NodeError, Node},
+};
+
+fn job_limit_error(code: StatusCode, message: String) -> APIError {
+    APIError {
+        code: code.as_u16(),
+        error: code.canonical_reason().unwrap_or_default().to_string(),
+        message,
+    }
+}
+
+impl Node {
+    pub async fn v2_api_set_job_concurrency_limit(
+        db: Arc<SqliteManager>,
+        bearer: String,
+        limit: JobConcurrencyLimit,
+        res: Sender<Result<JobConcurrencyLimit, APIError>>,
+    ) -> Result<(), NodeError> {
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        if let Err(e) = limit.validate() {
+            let _ = res.send(Err(job_limit_error(StatusCode::BAD_REQUEST, e))).await;
+            return Ok(());
+        }
+
+        let exists = match limit.target {
+            JobLimitTarget::LlmProvider => db
+                .get_all_llm_providers()
+                .map(|providers| providers.iter().any(|provider| provider.id == limit.target_id)),
+            JobLimitTarget::Agent => db.get_agent(&limit.target_id).map(|agent| agent.is_some()),
+        };
+        match exists {
+            Ok(true) => {}
+            Ok(false) => {
+                let _ = res
+                    .send(Err(job_limit_error(
+                        StatusCode::NOT_FOUND,
+                        format!("{} not found: {}", limit.target, limit.target_id),
+                    )))
+                    .await;
+                return Ok(());
+            }
+            Err(e) => {
+                let _ = res
+                    .send(Err(job_limit_error(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        format!("Failed to read {}: {}", limit.target, e),
+                    )))
+                    .await;
+                return Ok(());
+            }
+        }
+
+        let result = db.set_job_concurrency_limit(&limit).map(|_| limit).map_err(|e| {
+            job_limit_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to set job concurrency limit: {}", e),
+            )
+        });
+        let _ = res.send(result).await;
+        Ok(())
+    }
+
+    pub async fn v2_api_remove_job_concurrency_limit(
+        db: Arc<SqliteManager>,
+        bearer: String,
+        target: JobLimitTarget,
+        target_id: String,
+        res: Sender<Result<Value, APIError>>,
+    ) -> Result<(), NodeError> {
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        let result = match db.remove_job_concurrency_limit(target, &target_id) {
+            Ok(()) => Ok(json!({ "target": target, "target_id": target_id, "removed": true })),
+            Err(SqliteManagerError::DataNotFound) => Err(job_limit_error(
+                StatusCode::NOT_FOUND,
+                format!("No concurrency limit for {} {}", target, target_id),
+            )),
+            Err(e) => Err(job_limit_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to remove job concurrency limit: {}", e),
+            )),
+        };
+        let _ = res.send(result).await;
+        Ok(())
+    }
+
+    pub async fn v2_api_get_job_queue_status(
+        db: Arc<SqliteManager>,
+        job_manager: Arc<Mutex<JobManager>>,
+        bearer: String,
+        res: Sender<Result<JobQueueStatus, APIError>>,
+    ) -> Result<(), NodeError> {
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        let result = job_manager.lock().await.queue_status().await.map_err(|e| {
+            job_limit_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to read the job queue status: {}", e),
+            )
+        });
+        let _ = res.send(result).await;
+        Ok(())
+    }
+}
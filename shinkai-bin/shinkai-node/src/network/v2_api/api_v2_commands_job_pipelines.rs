@@ -0,0 +1,266 @@
+use std::sync::Arc;
+
+use async_channel::Sender;
+use chrono::Utc;
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use shinkai_http_api::node_api_router::APIError;
+use shinkai_message_primitives::schemas::job_pipeline::{
+    AddJobPipelineRequest, JobPipeline, JobPipelineRun, JobPipelineRunStatus, RunJobPipelineRequest,
+};
+use shinkai_sqlite::{errors::SqliteManagerError, SqliteManager};
+use tokio::sync::Mutex;
+
+use crate::{
+    llm_provider::job_manager::JobManager,
+    network::{node_error::NodeError, Node},
+};
+
+fn pipeline_error(code: StatusCode, message: String) -> APIError {
+    APIError {
+        code: code.as_u16(),
+        error: code.canonical_reason().unwrap_or_default().to_string(),
+        message,
+    }
+}
+
+impl Node {
+    fn validate_job_pipeline(db: &SqliteManager, pipeline: &JobPipeline) -> Result<(), APIError> {
+        pipeline
+            .validate()
+            .map_err(|message| pipeline_error(StatusCode::BAD_REQUEST, message))?;
+
+        let llm_providers = db.get_all_llm_providers().unwrap_or_default();
+        for step in &pipeline.steps {
+            let is_agent = db.get_agent(&step.agent_id).ok().flatten().is_some();
+            let is_llm_provider = llm_providers
+                .iter()
+                .any(|llm_provider| llm_provider.id == step.agent_id);
+            if !is_agent && !is_llm_provider {
+                return Err(pipeline_error(
+                    StatusCode::BAD_REQUEST,
+                    format!("Agent of step '{}' not found: {}", step.id, step.agent_id),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn v2_api_add_job_pipeline(
+        db: Arc<SqliteManager>,
+        bearer: String,
+        payload: AddJobPipelineRequest,
+        res: Sender<Result<JobPipeline, APIError>>,
+    ) -> Result<(), NodeError> {
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        let pipeline = JobPipeline {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: payload.name,
+            description: payload.description,
+            steps: payload.steps,
+            created_at: Utc::now(),
+        };
+        if let Err(api_error) = Self::validate_job_pipeline(&db, &pipeline) {
+            let _ = res.send(Err(api_error)).await;
+            return Ok(());
+        }
+
+        match db.add_job_pipeline(&pipeline) {
+            Ok(()) => {
+                let _ = res.send(Ok(pipeline)).await;
+            }
+            Err(e) => {
+                let _ = res
+                    .send(Err(pipeline_error(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        format!("Failed to add job pipeline: {}", e),
+                    )))
+                    .await;
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn v2_api_list_job_pipelines(
+        db: Arc<SqliteManager>,
+        bearer: String,
+        res: Sender<Result<Vec<JobPipeline>, APIError>>,
+    ) -> Result<(), NodeError> {
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        match db.get_all_job_pipelines() {
+            Ok(pipelines) => {
+                let _ = res.send(Ok(pipelines)).await;
+            }
+            Err(e) => {
+                let _ = res
+                    .send(Err(pipeline_error(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        format!("Failed to list job pipelines: {}", e),
+                    )))
+                    .await;
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn v2_api_remove_job_pipeline(
+        db: Arc<SqliteManager>,
+        bearer: String,
+        id: String,
+        res: Sender<Result<Value, APIError>>,
+    ) -> Result<(), NodeError> {
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        match db.remove_job_pipeline(&id) {
+            Ok(()) => {
+                let _ = res.send(Ok(json!({ "id": id, "removed": true }))).await;
+            }
+            Err(SqliteManagerError::DataNotFound) => {
+                let _ = res
+                    .send(Err(pipeline_error(
+                        StatusCode::NOT_FOUND,
+                        format!("Job pipeline not found: {}", id),
+                    )))
+                    .await;
+            }
+            Err(e) => {
+                let _ = res
+                    .send(Err(pipeline_error(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        format!("Failed to remove job pipeline: {}", e),
+                    )))
+                    .await;
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn v2_api_run_job_pipeline(
+        db: Arc<SqliteManager>,
+        job_manager: Arc<Mutex<JobManager>>,
+        bearer: String,
+        payload: RunJobPipelineRequest,
+        res: Sender<Result<JobPipelineRun, APIError>>,
+    ) -> Result<(), NodeError> {
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        let pipeline = match db.get_job_pipeline(&payload.pipeline_id) {
+            Ok(Some(pipeline)) => pipeline,
+            Ok(None) => {
+                let _ = res
+                    .send(Err(pipeline_error(
+                        StatusCode::NOT_FOUND,
+                        format!("Job pipeline not found: {}", payload.pipeline_id),
+                    )))
+                    .await;
+                return Ok(());
+            }
+            Err(e) => {
+                let _ = res
+                    .send(Err(pipeline_error(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        format!("Failed to read job pipeline: {}", e),
+                    )))
+                    .await;
+                return Ok(());
+            }
+        };
+
+        match JobManager::start_job_pipeline_run(job_manager, db, &pipeline, payload.input).await {
+            Ok(run) => {
+                let _ = res.send(Ok(run)).await;
+            }
+            Err(e) => {
+                let _ = res
+                    .send(Err(pipeline_error(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        format!("Failed to run job pipeline: {}", e),
+                    )))
+                    .await;
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn v2_api_get_job_pipeline_run(
+        db: Arc<SqliteManager>,
+        bearer: String,
+        run_id: String,
+        res: Sender<Result<JobPipelineRun, APIError>>,
+    ) -> Result<(), NodeError> {
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        let result = match db.get_job_pipeline_run(&run_id) {
+            Ok(Some(run)) => Ok(run),
+            Ok(None) => Err(pipeline_error(
+                StatusCode::NOT_FOUND,
+                format!("Job pipeline run not found: {}", run_id),
+            )),
+            Err(e) => Err(pipeline_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to read job pipeline run: {}", e),
+            )),
+        };
+        let _ = res.send(result).await;
+        Ok(())
+    }
+
+    pub async fn v2_api_resume_job_pipeline_run(
+        db: Arc<SqliteManager>,
+        job_manager: Arc<Mutex<JobManager>>,
+        bearer: String,
+        run_id: String,
+        res: Sender<Result<JobPipelineRun, APIError>>,
+    ) -> Result<(), NodeError> {
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        let api_error = match db.get_job_pipeline_run(&run_id) {
+            Ok(Some(run)) if run.status == JobPipelineRunStatus::Failed => None,
+            Ok(Some(run)) => Some(pipeline_error(
+                StatusCode::BAD_REQUEST,
+                format!("Only failed runs can be resumed, run {} is {}", run_id, run.status),
+            )),
+            Ok(None) => Some(pipeline_error(
+                StatusCode::NOT_FOUND,
+                format!("Job pipeline run not found: {}", run_id),
+            )),
+            Err(e) => Some(pipeline_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to read job pipeline run: {}", e),
+            )),
+        };
+        if let Some(api_error) = api_error {
+            let _ = res.send(Err(api_error)).await;
+            return Ok(());
+        }
+
+        match JobManager::resume_job_pipeline_run(job_manager, db, &run_id).await {
+            Ok(run) => {
+                let _ = res.send(Ok(run)).await;
+            }
+            Err(e) => {
+                let _ = res
+                    .send(Err(pipeline_error(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        format!("Failed to resume job pipeline run: {}", e),
+                    )))
+                    .await;
+            }
+        }
+        Ok(())
+    }
+}
@@ -6,6 +6,7 @@ use reqwest::StatusCode;
 use serde_json::{json, Value};
 
 use shinkai_http_api::node_api_router::{APIError, SendResponseBody, SendResponseBodyData};
+use shinkai_message_primitives::schemas::api_key::{ApiKey, ApiKeyScope};
 use shinkai_message_primitives::{
     schemas::{
         identity::Identity, inbox_name::InboxName, job::{ForkedJob, JobLike}, job_config::JobConfig, llm_providers::{common_agent_llm_provider::ProviderOrAgent, serialized_llm_provider::SerializedLLMProvider}, shinkai_name::{ShinkaiName, ShinkaiSubidentityType}, smart_inbox::{LLMProviderSubset, ProviderType, SmartInbox, V2SmartInbox}
@@ -30,6 +31,17 @@ use crate::{
 
 use x25519_dalek::StaticSecret as EncryptionStaticKey;
 impl Node {
+    /// Keys restricted to some agents only list the inboxes of jobs run by one of them
+    fn api_key_allows_smart_inbox(api_key: &Option<ApiKey>, smart_inbox: &SmartInbox) -> bool {
+        match api_key {
+            Some(api_key) if api_key.agent_ids.is_some() => smart_inbox
+                .agent
+                .as_ref()
+                .map_or(false, |agent| api_key.allows_agent(&agent.id)),
+            _ => true,
+        }
+    }
+
     pub fn convert_smart_inbox_to_v2_smart_inbox(smart_inbox: SmartInbox) -> Result<V2SmartInbox, NodeError> {
         let last_message = match smart_inbox.last_message {
             Some(msg) => Some(Node::convert_shinkai_message_to_v2_chat_message(msg)?),
@@ -63,7 +75,11 @@ impl Node {
         res: Sender<Result<String, APIError>>,
     ) -> Result<(), NodeError> {
         // Validate the bearer token and extract the sender identity
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+        let api_key = match Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::JobsWrite).await {
+            Ok(api_key) => api_key,
+            Err(_) => return Ok(()),
+        };
+        if Self::validate_api_key_agent(&api_key, &llm_provider, &res).await.is_err() {
             return Ok(());
         }
 
@@ -170,9 +186,10 @@ impl Node {
         res: Sender<Result<SendResponseBodyData, APIError>>,
     ) -> Result<(), NodeError> {
         // Validate the bearer token and extract the sender identity
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
-            return Ok(());
-        }
+        let api_key = match Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::JobsWrite).await {
+            Ok(api_key) => api_key,
+            Err(_) => return Ok(()),
+        };
 
         // Get the main identity from the identity manager
         let main_identity = {
@@ -204,6 +221,9 @@ impl Node {
                 return Ok(());
             }
         };
+        if Self::validate_api_key_agent(&api_key, &llm_provider, &res).await.is_err() {
+            return Ok(());
+        }
 
         // Create a new job message
         let sender = match ShinkaiName::new(main_identity.get_full_identity_name()) {
@@ -310,7 +330,14 @@ impl Node {
         res: Sender<Result<Vec<V2ChatMessage>, APIError>>,
     ) -> Result<(), NodeError> {
         // Validate the bearer token
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+        let api_key = match Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::JobsRead).await {
+            Ok(api_key) => api_key,
+            Err(_) => return Ok(()),
+        };
+        if Self::validate_api_key_inbox(&db, &api_key, &inbox_name, &res)
+            .await
+            .is_err()
+        {
             return Ok(());
         }
 
@@ -364,7 +391,14 @@ impl Node {
         res: Sender<Result<Vec<Vec<V2ChatMessage>>, APIError>>,
     ) -> Result<(), NodeError> {
         // Validate the bearer token
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+        let api_key = match Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::JobsRead).await {
+            Ok(api_key) => api_key,
+            Err(_) => return Ok(()),
+        };
+        if Self::validate_api_key_inbox(&db, &api_key, &inbox_name, &res)
+            .await
+            .is_err()
+        {
             return Ok(());
         }
 
@@ -420,9 +454,10 @@ impl Node {
         res: Sender<Result<Vec<V2SmartInbox>, APIError>>,
     ) -> Result<(), NodeError> {
         // Validate the bearer token
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
-            return Ok(());
-        }
+        let api_key = match Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::JobsRead).await {
+            Ok(api_key) => api_key,
+            Err(_) => return Ok(()),
+        };
 
         // Get the main identity from the identity manager
         let main_identity = {
@@ -458,6 +493,7 @@ impl Node {
         // Convert SmartInbox to V2SmartInbox, collecting into a Result
         let v2_smart_inboxes_result: Result<Vec<V2SmartInbox>, NodeError> = smart_inboxes
             .into_iter()
+            .filter(|inbox| Self::api_key_allows_smart_inbox(&api_key, inbox))
             .map(Self::convert_smart_inbox_to_v2_smart_inbox)
             .collect();
 
@@ -497,9 +533,10 @@ impl Node {
         res: Sender<Result<serde_json::Value, APIError>>,
     ) -> Result<(), NodeError> {
         // Validate the bearer token
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
-            return Ok(());
-        }
+        let api_key = match Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::JobsRead).await {
+            Ok(api_key) => api_key,
+            Err(_) => return Ok(()),
+        };
 
         // Get the main identity from the identity manager
         let main_identity = {
@@ -537,6 +574,7 @@ impl Node {
         let v2_smart_inboxes_result: Result<Vec<V2SmartInbox>, NodeError> = paginated_inboxes
             .inboxes
             .into_iter()
+            .filter(|inbox| Self::api_key_allows_smart_inbox(&api_key, inbox))
             .map(Self::convert_smart_inbox_to_v2_smart_inbox)
             .collect();
 
@@ -576,7 +614,7 @@ impl Node {
         res: Sender<Result<Vec<SerializedLLMProvider>, APIError>>,
     ) -> Result<(), NodeError> {
         // Validate the bearer token
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+        if Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::JobsRead).await.is_err() {
             return Ok(());
         }
 
@@ -606,7 +644,7 @@ impl Node {
         res: Sender<Result<(), APIError>>,
     ) -> Result<(), NodeError> {
         // Validate the bearer token
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+        if Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::JobsWrite).await.is_err() {
             return Ok(());
         }
 
@@ -707,7 +745,11 @@ impl Node {
         res: Sender<Result<String, APIError>>,
     ) -> Result<(), NodeError> {
         // Validate the bearer token
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+        let api_key = match Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::JobsWrite).await {
+            Ok(api_key) => api_key,
+            Err(_) => return Ok(()),
+        };
+        if Self::validate_api_key_agent(&api_key, &payload.new_agent_id, &res).await.is_err() {
             return Ok(());
         }
 
@@ -739,13 +781,20 @@ impl Node {
         res: Sender<Result<String, APIError>>,
     ) -> Result<(), NodeError> {
         // Validate the bearer token
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
-            return Ok(());
-        }
+        let api_key = match Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::JobsWrite).await {
+            Ok(api_key) => api_key,
+            Err(_) => return Ok(()),
+        };
 
         // Check if the job exists
         match db.get_job_with_options(&job_id, false) {
-            Ok(_) => {
+            Ok(job) => {
+                if Self::validate_api_key_agent(&api_key, &job.parent_agent_or_llm_provider_id, &res)
+                    .await
+                    .is_err()
+                {
+                    return Ok(());
+                }
                 // Job exists, proceed with updating the config
                 match db.update_job_config(&job_id, config) {
                     Ok(_) => {
@@ -783,15 +832,22 @@ impl Node {
         res: Sender<Result<JobConfig, APIError>>,
     ) -> Result<(), NodeError> {
         // Validate the bearer token
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
-            return Ok(());
-        }
+        let api_key = match Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::JobsRead).await {
+            Ok(api_key) => api_key,
+            Err(_) => return Ok(()),
+        };
 
         // TODO: Get default values for Ollama
 
         // Check if the job exists
         match db.get_job_with_options(&job_id, false) {
             Ok(job) => {
+                if Self::validate_api_key_agent(&api_key, &job.parent_agent_or_llm_provider_id, &res)
+                    .await
+                    .is_err()
+                {
+                    return Ok(());
+                }
                 let config = job.config().cloned().unwrap_or_else(|| JobConfig {
                     custom_system_prompt: None,
                     custom_prompt: None,
@@ -803,6 +859,11 @@ impl Node {
                     max_tokens: None,
                     other_model_params: None,
                     use_tools: None,
+                    priority: None,
+                    max_iterations: None,
+                    max_duration_secs: None,
+                    max_total_tokens: None,
+                    max_tool_calls: None,
                 });
                 let _ = res.send(Ok(config)).await;
                 Ok(())
@@ -854,7 +915,7 @@ impl Node {
         res: Sender<Result<Value, APIError>>,
     ) -> Result<(), NodeError> {
         // Validate the bearer token
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+        if Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::JobsRead).await.is_err() {
             return Ok(());
         }
 
@@ -927,7 +988,14 @@ impl Node {
         res: Sender<Result<SendResponseBodyData, APIError>>,
     ) -> Result<(), NodeError> {
         // Validate the bearer token
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+        let api_key = match Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::JobsWrite).await {
+            Ok(api_key) => api_key,
+            Err(_) => return Ok(()),
+        };
+        if Self::validate_api_key_inbox(&db, &api_key, &inbox_name, &res)
+            .await
+            .is_err()
+        {
             return Ok(());
         }
 
@@ -1194,13 +1262,20 @@ impl Node {
         res: Sender<Result<Value, APIError>>,
     ) -> Result<(), NodeError> {
         // Validate the bearer token
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
-            return Ok(());
-        }
+        let api_key = match Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::JobsWrite).await {
+            Ok(api_key) => api_key,
+            Err(_) => return Ok(()),
+        };
 
         // Check if the job exists
         match db.get_job_with_options(&job_id, false) {
-            Ok(_) => {
+            Ok(job) => {
+                if Self::validate_api_key_agent(&api_key, &job.parent_agent_or_llm_provider_id, &res)
+                    .await
+                    .is_err()
+                {
+                    return Ok(());
+                }
                 // Job exists, proceed with updating the job scope
                 match db.update_job_scope(job_id.clone(), job_scope.clone()) {
                     Ok(_) => {
@@ -1249,7 +1324,7 @@ impl Node {
         res: Sender<Result<Value, APIError>>,
     ) -> Result<(), NodeError> {
         // Validate the bearer token
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+        if Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::JobsRead).await.is_err() {
             return Ok(());
         }
 
@@ -1298,7 +1373,11 @@ impl Node {
         res: Sender<Result<String, APIError>>,
     ) -> Result<(), NodeError> {
         // Validate the bearer token and extract the sender identity
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+        let api_key = match Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::JobsWrite).await {
+            Ok(api_key) => api_key,
+            Err(_) => return Ok(()),
+        };
+        if Self::validate_api_key_job(&db, &api_key, &job_id, &res).await.is_err() {
             return Ok(());
         }
         let forked_job_id = Self::fork_job(
@@ -1499,7 +1578,7 @@ impl Node {
         res: Sender<Result<SendResponseBody, APIError>>,
     ) -> Result<(), NodeError> {
         // Validate the bearer token
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+        if Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::JobsWrite).await.is_err() {
             return Ok(());
         }
 
@@ -1585,7 +1664,14 @@ impl Node {
         res: Sender<Result<Value, APIError>>,
     ) -> Result<(), NodeError> {
         // Validate the bearer token
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+        let api_key = match Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::JobsRead).await {
+            Ok(api_key) => api_key,
+            Err(_) => return Ok(()),
+        };
+        if Self::validate_api_key_inbox(&db, &api_key, &inbox_name, &res)
+            .await
+            .is_err()
+        {
             return Ok(());
         }
 
@@ -1933,3 +2019,150 @@ impl Node {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use shinkai_embedding::model_type::{EmbeddingModelType, OllamaTextEmbeddingsInference};
+    use shinkai_message_primitives::schemas::llm_providers::serialized_llm_provider::{LLMProviderInterface, OpenAI};
+
+    const RESTRICTED_SECRET: &str = "sk_shinkai_restricted_0123456789";
+
+    fn setup_test_db(dir: &tempfile::TempDir) -> Arc<SqliteManager> {
+        let model_type =
+            EmbeddingModelType::OllamaTextEmbeddingsInference(OllamaTextEmbeddingsInference::SnowflakeArcticEmbedM);
+        let db = SqliteManager::new(dir.path().join("jobs.db"), String::new(), model_type).unwrap();
+
+        let api_key = ApiKey {
+            id: "restricted".to_string(),
+            name: "restricted".to_string(),
+            key_prefix: RESTRICTED_SECRET[..14].to_string(),
+            scopes: vec![ApiKeyScope::JobsWrite],
+            agent_ids: Some(vec!["my_agent".to_string()]),
+            expires_at: None,
+            created_at: Utc::now(),
+            last_used_at: None,
+            revoked: false,
+        };
+        db.add_api_key(&api_key, RESTRICTED_SECRET).unwrap();
+        db.create_new_job(
+            "other_job".to_string(),
+            "other_agent".to_string(),
+            MinimalJobScope::default(),
+            false,
+            None,
+            None,
+        )
+        .unwrap();
+        Arc::new(db)
+    }
+
+    fn smart_inbox(agent_id: Option<&str>) -> SmartInbox {
+        SmartInbox {
+            inbox_id: "job_inbox::job::false".to_string(),
+            custom_name: "job".to_string(),
+            datetime_created: Utc::now().to_rfc3339(),
+            last_message: None,
+            is_finished: false,
+            job_scope: None,
+            agent: agent_id.map(|agent_id| LLMProviderSubset {
+                id: agent_id.to_string(),
+                full_identity_name: ShinkaiName::new("@@localhost.sep-shinkai/main".to_string()).unwrap(),
+                model: LLMProviderInterface::OpenAI(OpenAI {
+                    model_type: "gpt-4o".to_string(),
+                }),
+            }),
+            job_config: None,
+            provider_type: ProviderType::Agent,
+        }
+    }
+
+    async fn assert_forbidden<T: std::fmt::Debug>(receiver: async_channel::Receiver<Result<T, APIError>>) {
+        let error = receiver.recv().await.unwrap().unwrap_err();
+        assert_eq!(error.code, StatusCode::FORBIDDEN.as_u16());
+    }
+
+    #[tokio::test]
+    async fn test_agent_restricted_key_cant_reach_other_agent_jobs() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = setup_test_db(&dir);
+        let bearer = RESTRICTED_SECRET.to_string();
+        let inbox_name = "job_inbox::other_job::false".to_string();
+        let job_id = "other_job".to_string();
+
+        let (res, receiver) = async_channel::bounded(1);
+        Node::v2_get_last_messages_from_inbox(db.clone(), bearer.clone(), inbox_name.clone(), 10, None, res)
+            .await
+            .unwrap();
+        assert_forbidden(receiver).await;
+
+        let (res, receiver) = async_channel::bounded(1);
+        Node::v2_get_last_messages_from_inbox_with_branches(
+            db.clone(),
+            bearer.clone(),
+            inbox_name.clone(),
+            10,
+            None,
+            res,
+        )
+        .await
+        .unwrap();
+        assert_forbidden(receiver).await;
+
+        let (res, receiver) = async_channel::bounded(1);
+        Node::v2_export_messages_from_inbox(
+            db.clone(),
+            bearer.clone(),
+            inbox_name.clone(),
+            ExportInboxMessagesFormat::JSON,
+            res,
+        )
+        .await
+        .unwrap();
+        assert_forbidden(receiver).await;
+
+        let (res, receiver) = async_channel::bounded(1);
+        Node::v2_api_update_job_config(db.clone(), bearer.clone(), job_id.clone(), JobConfig::empty(), res)
+            .await
+            .unwrap();
+        assert_forbidden(receiver).await;
+
+        let (res, receiver) = async_channel::bounded(1);
+        Node::v2_api_get_job_config(db.clone(), bearer.clone(), job_id.clone(), res)
+            .await
+            .unwrap();
+        assert_forbidden(receiver).await;
+
+        let (res, receiver) = async_channel::bounded(1);
+        Node::v2_api_update_job_scope(
+            db.clone(),
+            bearer.clone(),
+            job_id.clone(),
+            MinimalJobScope::default(),
+            res,
+        )
+        .await
+        .unwrap();
+        assert_forbidden(receiver).await;
+
+        // Retrying and forking check the job of the inbox before anything else
+        let api_key = db.get_api_key_by_secret(RESTRICTED_SECRET).unwrap();
+        let (res, receiver) = async_channel::bounded::<Result<(), APIError>>(1);
+        let allowed = Node::validate_api_key_inbox(&db, &api_key, &inbox_name, &res).await;
+        assert!(allowed.is_err());
+        assert_forbidden(receiver).await;
+
+        let (res, receiver) = async_channel::bounded::<Result<(), APIError>>(1);
+        assert!(Node::validate_api_key_job(&db, &api_key, &job_id, &res).await.is_err());
+        assert_forbidden(receiver).await;
+
+        // Smart inbox listings only keep the inboxes of the allowed agents
+        let own_inbox = smart_inbox(Some("my_agent"));
+        let other_inbox = smart_inbox(Some("other_agent"));
+        assert!(Node::api_key_allows_smart_inbox(&api_key, &own_inbox));
+        assert!(!Node::api_key_allows_smart_inbox(&api_key, &other_inbox));
+        assert!(!Node::api_key_allows_smart_inbox(&api_key, &smart_inbox(None)));
+        assert!(Node::api_key_allows_smart_inbox(&None, &other_inbox));
+    }
+}
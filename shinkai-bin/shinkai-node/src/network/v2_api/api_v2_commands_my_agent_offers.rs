@@ -5,6 +5,7 @@ use reqwest::StatusCode;
 use serde_json::Value;
 
 use shinkai_http_api::node_api_router::APIError;
+use shinkai_message_primitives::schemas::api_key::ApiKeyScope;
 use shinkai_message_primitives::schemas::shinkai_name::ShinkaiName;
 use shinkai_message_primitives::schemas::shinkai_tool_offering::UsageTypeInquiry;
 use shinkai_sqlite::{errors::SqliteManagerError, SqliteManager};
@@ -25,7 +26,7 @@ impl Node {
         res: Sender<Result<Value, APIError>>,
     ) -> Result<(), NodeError> {
         // Validate the bearer token
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+        if Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::WalletPay).await.is_err() {
             return Ok(());
         }
 
@@ -108,7 +109,7 @@ impl Node {
         res: Sender<Result<Value, APIError>>,
     ) -> Result<(), NodeError> {
         // Validate the bearer token
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+        if Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::WalletPay).await.is_err() {
             return Ok(());
         }
 
@@ -265,7 +266,7 @@ impl Node {
         res: Sender<Result<Value, APIError>>,
     ) -> Result<(), NodeError> {
         // Validate the bearer token
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+        if Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::WalletRead).await.is_err() {
             return Ok(());
         }
 
@@ -0,0 +1,631 @@
+use std::{sync::Arc, time::Duration};
+
+use async_channel::Sender;
+use chrono::Utc;
+use ed25519_dalek::SigningKey;
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use shinkai_http_api::{
+    api_openai::api_openai_handlers::{OpenAIChatCompletionRequest, OpenAIChatMessage},
+    node_api_router::APIError,
+};
+use shinkai_message_primitives::{
+    schemas::{api_key::ApiKeyScope, inbox_name::InboxName, shinkai_name::ShinkaiName, webhook::WebhookEventType},
+    shinkai_message::shinkai_message_schemas::{JobCreationInfo, JobMessage, V2ChatMessage},
+    shinkai_utils::{
+        job_scope::MinimalJobScope,
+        shinkai_logging::{shinkai_log, ShinkaiLogLevel, ShinkaiLogOption},
+        utils::count_tokens_from_message_llama3,
+    },
+};
+use shinkai_sqlite::SqliteManager;
+use tokio::sync::Mutex;
+use x25519_dalek::{PublicKey as EncryptionPublicKey, StaticSecret as EncryptionStaticKey};
+
+use crate::{
+    llm_provider::{job_manager::JobManager, llm_stopper::LLMStopper},
+    managers::IdentityManager,
+    network::{
+        node_error::NodeError,
+        webhook_dispatcher::{subscribe_node_events, wait_for_job_outcome},
+        ws_manager::WebSocketManager,
+        Node,
+    },
+    tools::tool_generation::v2_send_basic_job_message_for_existing_job,
+};
+
+/// How long a completion may take before the request fails
+const OPENAI_COMPLETION_TIMEOUT: Duration = Duration::from_secs(60 * 5);
+
+/// How long the stopped message of a hidden job may take to end before the job is removed anyway
+const OPENAI_JOB_CLEANUP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Hidden job of a completion, removed once the request is over. If its message is still running
+/// by then, the message is stopped first.
+struct OpenAICompletionJob {
+    db: Arc<SqliteManager>,
+    llm_stopper: Arc<LLMStopper>,
+    job_id: String,
+    running_message: Option<String>,
+}
+
+impl Drop for OpenAICompletionJob {
+    fn drop(&mut self) {
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let db = self.db.clone();
+        let llm_stopper = self.llm_stopper.clone();
+        let job_id = std::mem::take(&mut self.job_id);
+        let running_message = self.running_message.take();
+        runtime.spawn(async move {
+            if let Some(message_id) = running_message {
+                let mut events = subscribe_node_events();
+                if let Ok(inbox_name) = InboxName::get_job_inbox_name_from_params(job_id.clone()) {
+                    llm_stopper.stop_message(&inbox_name.get_value(), &message_id);
+                }
+                let _ = tokio::time::timeout(
+                    OPENAI_JOB_CLEANUP_TIMEOUT,
+                    wait_for_job_outcome(&mut events, &job_id, &message_id),
+                )
+                .await;
+            }
+            if let Err(e) = db.remove_job(&job_id) {
+                shinkai_log(
+                    ShinkaiLogOption::Api,
+                    ShinkaiLogLevel::Error,
+                    &format!("Failed to remove the completion job {}: {}", job_id, e),
+                );
+            }
+        });
+    }
+}
+
+/// An OpenAI `messages` list split into what the job system needs
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpenAIConversation {
+    pub system_prompt: Option<String>,
+    /// Earlier turns as alternating user / assistant contents, starting with the user
+    pub history: Vec<String>,
+    pub prompt: String,
+}
+
+impl OpenAIConversation {
+    pub fn from_messages(messages: &[OpenAIChatMessage]) -> Result<Self, String> {
+        let (last, earlier) = messages.split_last().ok_or("messages must not be empty")?;
+        if last.role != "user" {
+            return Err(format!("The last message must have the user role, found {}", last.role));
+        }
+
+        let mut system_prompts = Vec::new();
+        let mut turns: Vec<(bool, String)> = Vec::new();
+        for message in earlier {
+            let is_user = match message.role.as_str() {
+                "system" | "developer" => {
+                    system_prompts.push(message.text());
+                    continue;
+                }
+                "user" => true,
+                "assistant" => false,
+                // Tool results are produced by the node itself, clients can't replay them
+                _ => continue,
+            };
+
+            if turns.is_empty() && !is_user {
+                turns.push((true, String::new()));
+            }
+            match turns.last_mut() {
+                Some((last_is_user, content)) if *last_is_user == is_user => {
+                    content.push_str("\n\n");
+                    content.push_str(&message.text());
+                }
+                _ => turns.push((is_user, message.text())),
+            }
+        }
+
+        let mut prompt = last.text();
+        // A trailing user turn is merged into the prompt so the history keeps alternating
+        if let Some((true, content)) = turns.last() {
+            prompt = format!("{}\n\n{}", content, prompt);
+            turns.pop();
+        }
+
+        Ok(Self {
+            system_prompt: (!system_prompts.is_empty()).then(|| system_prompts.join("\n\n")),
+            history: turns.into_iter().map(|(_, content)| content).collect(),
+            prompt,
+        })
+    }
+}
+
+impl Node {
+    fn openai_completion_error(code: StatusCode, message: &str) -> APIError {
+        APIError {
+            code: code.as_u16(),
+            error: code.canonical_reason().unwrap_or("Error").to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    pub async fn v2_api_openai_list_models(
+        db: Arc<SqliteManager>,
+        bearer: String,
+        res: Sender<Result<Value, APIError>>,
+    ) -> Result<(), NodeError> {
+        let api_key = match Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::JobsRead).await {
+            Ok(api_key) => api_key,
+            Err(_) => return Ok(()),
+        };
+
+        let (agents, llm_providers) = match (db.get_all_agents(), db.get_all_llm_providers()) {
+            (Ok(agents), Ok(llm_providers)) => (agents, llm_providers),
+            (Err(e), _) | (_, Err(e)) => {
+                let api_error = Self::openai_completion_error(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    &format!("Failed to list models: {}", e),
+                );
+                let _ = res.send(Err(api_error)).await;
+                return Ok(());
+            }
+        };
+
+        let models: Vec<Value> = agents
+            .into_iter()
+            .map(|agent| (agent.agent_id, "agent".to_string()))
+            .chain(
+                llm_providers
+                    .into_iter()
+                    .map(|llm_provider| (llm_provider.id.clone(), llm_provider.get_provider_string())),
+            )
+            .filter(|(id, _)| api_key.as_ref().map_or(true, |api_key| api_key.allows_agent(id)))
+            .map(|(id, owned_by)| {
+                json!({
+                    "id": id,
+                    "object": "model",
+                    "created": 0,
+                    "owned_by": owned_by,
+                })
+            })
+            .collect();
+
+        let _ = res.send(Ok(json!({ "object": "list", "data": models }))).await;
+        Ok(())
+    }
+
+    /// Answers an OpenAI chat completion request with a hidden job of the requested agent
+    /// or LLM provider, so agent tools, knowledge and system prompts apply.
+    pub async fn v2_api_openai_chat_completion(
+        db: Arc<SqliteManager>,
+        node_name: ShinkaiName,
+        identity_manager: Arc<Mutex<IdentityManager>>,
+        job_manager: Arc<Mutex<JobManager>>,
+        ws_manager: Option<Arc<Mutex<WebSocketManager>>>,
+        bearer: String,
+        request: OpenAIChatCompletionRequest,
+        node_encryption_sk: EncryptionStaticKey,
+        node_encryption_pk: EncryptionPublicKey,
+        node_signing_sk: SigningKey,
+        res: Sender<Result<async_channel::Receiver<Result<Value, APIError>>, APIError>>,
+    ) -> Result<(), NodeError> {
+        let api_key = match Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::JobsWrite).await {
+            Ok(api_key) => api_key,
+            Err(_) => return Ok(()),
+        };
+        if Self::validate_api_key_agent(&api_key, &request.model, &res).await.is_err() {
+            return Ok(());
+        }
+
+        let conversation = match OpenAIConversation::from_messages(&request.messages) {
+            Ok(conversation) => conversation,
+            Err(message) => {
+                let _ = res
+                    .send(Err(Self::openai_completion_error(StatusCode::BAD_REQUEST, &message)))
+                    .await;
+                return Ok(());
+            }
+        };
+
+        let agent = db.get_agent(&request.model).ok().flatten();
+        let is_llm_provider = db
+            .get_all_llm_providers()
+            .map(|llm_providers| llm_providers.iter().any(|llm_provider| llm_provider.id == request.model))
+            .unwrap_or(false);
+        if agent.is_none() && !is_llm_provider {
+            let api_error = Self::openai_completion_error(
+                StatusCode::NOT_FOUND,
+                &format!("The model `{}` does not exist", request.model),
+            );
+            let _ = res.send(Err(api_error)).await;
+            return Ok(());
+        }
+
+        let mut job = match Self::openai_prepare_job(
+            db.clone(),
+            node_name.clone(),
+            identity_manager.clone(),
+            job_manager.clone(),
+            bearer.clone(),
+            &request,
+            &conversation,
+            agent.as_ref().map(|agent| !agent.tools.is_empty()),
+            node_encryption_sk.clone(),
+            node_encryption_pk,
+            node_signing_sk.clone(),
+        )
+        .await
+        {
+            Ok(job) => job,
+            Err(api_error) => {
+                let _ = res.send(Err(api_error)).await;
+                return Ok(());
+            }
+        };
+        let job_id = job.job_id.clone();
+
+        let inbox_name = match InboxName::get_job_inbox_name_from_params(job_id.clone()) {
+            Ok(inbox_name) => inbox_name.to_string(),
+            Err(e) => {
+                let api_error =
+                    Self::openai_completion_error(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string());
+                let _ = res.send(Err(api_error)).await;
+                return Ok(());
+            }
+        };
+
+        // Subscribe before sending the message so no chunk nor the outcome is missed
+        let mut stream_tap = match (&ws_manager, request.stream) {
+            (Some(ws_manager), true) => Some(ws_manager.lock().await.subscribe_inbox_stream(&inbox_name).await),
+            _ => None,
+        };
+        let mut events = subscribe_node_events();
+
+        let prompt = match &conversation.system_prompt {
+            // The agent keeps its own system prompt, the client one is passed as context
+            Some(system_prompt) if agent.is_some() => format!("{}\n\n{}", system_prompt, conversation.prompt),
+            _ => conversation.prompt.clone(),
+        };
+        let message_id = match v2_send_basic_job_message_for_existing_job(
+            bearer.clone(),
+            job_id.clone(),
+            prompt.clone(),
+            None,
+            None,
+            None,
+            db.clone(),
+            node_name,
+            identity_manager,
+            job_manager,
+            node_encryption_sk,
+            node_encryption_pk,
+            node_signing_sk,
+        )
+        .await
+        {
+            Ok(message_id) => message_id,
+            Err(api_error) => {
+                let _ = res.send(Err(api_error)).await;
+                return Ok(());
+            }
+        };
+        job.running_message = Some(message_id.clone());
+
+        let (chunk_sender, chunk_receiver) = async_channel::unbounded();
+        if res.send(Ok(chunk_receiver)).await.is_err() {
+            return Ok(());
+        }
+
+        let completion_id = format!("chatcmpl-{}", job_id);
+        let created = Utc::now().timestamp();
+        let chunk = |delta: Value, finish_reason: Option<&str>| {
+            json!({
+                "id": completion_id,
+                "object": "chat.completion.chunk",
+                "created": created,
+                "model": request.model,
+                "choices": [{ "index": 0, "delta": delta, "finish_reason": finish_reason }],
+            })
+        };
+
+        if request.stream {
+            let _ = chunk_sender
+                .send(Ok(chunk(json!({ "role": "assistant", "content": "" }), None)))
+                .await;
+        }
+
+        let mut streamed = String::new();
+        let outcome = tokio::time::timeout(OPENAI_COMPLETION_TIMEOUT, async {
+            loop {
+                let Some(tap) = stream_tap.as_mut() else {
+                    return wait_for_job_outcome(&mut events, &job_id, &message_id).await;
+                };
+                // Forward chunks as they arrive until the job ends
+                let mut tap_closed = false;
+                tokio::select! {
+                    outcome = wait_for_job_outcome(&mut events, &job_id, &message_id) => return outcome,
+                    update = tap.recv() => match update {
+                        Some((delta, _)) if !delta.is_empty() => {
+                            streamed.push_str(&delta);
+                            let _ = chunk_sender.send(Ok(chunk(json!({ "content": delta }), None))).await;
+                        }
+                        Some(_) => {}
+                        None => tap_closed = true,
+                    },
+                }
+                if tap_closed {
+                    stream_tap = None;
+                }
+            }
+        })
+        .await;
+
+        let content = match outcome {
+            Ok(Some((WebhookEventType::JobCompleted, payload))) => {
+                job.running_message = None;
+                payload
+                    .get("content")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string()
+            }
+            Ok(Some((event_type, payload))) => {
+                job.running_message = None;
+                let message = match event_type {
+                    WebhookEventType::JobCancelled => "The agent job was stopped".to_string(),
+                    _ => format!(
+                        "The agent job failed: {}",
+                        payload.get("error").and_then(Value::as_str).unwrap_or_default()
+                    ),
+                };
+                let api_error = Self::openai_completion_error(StatusCode::INTERNAL_SERVER_ERROR, &message);
+                let _ = chunk_sender.send(Err(api_error)).await;
+                return Ok(());
+            }
+            Ok(None) => {
+                let api_error = Self::openai_completion_error(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Lost track of the agent response",
+                );
+                let _ = chunk_sender.send(Err(api_error)).await;
+                return Ok(());
+            }
+            Err(_) => {
+                let api_error = Self::openai_completion_error(
+                    StatusCode::GATEWAY_TIMEOUT,
+                    "Timed out waiting for the agent response",
+                );
+                let _ = chunk_sender.send(Err(api_error)).await;
+                return Ok(());
+            }
+        };
+
+        let prompt_tokens = count_tokens_from_message_llama3(&prompt)
+            + conversation
+                .history
+                .iter()
+                .map(|content| count_tokens_from_message_llama3(content))
+                .sum::<usize>();
+        let completion_tokens = count_tokens_from_message_llama3(&content);
+        let usage = json!({
+            "prompt_tokens": prompt_tokens,
+            "completion_tokens": completion_tokens,
+            "total_tokens": prompt_tokens + completion_tokens,
+        });
+
+        if request.stream {
+            // Send whatever part of the final answer was not streamed
+            let remaining = content.strip_prefix(streamed.as_str()).unwrap_or_default();
+            if !remaining.is_empty() {
+                let _ = chunk_sender
+                    .send(Ok(chunk(json!({ "content": remaining }), None)))
+                    .await;
+            }
+            let mut last_chunk = chunk(json!({}), Some("stop"));
+            last_chunk["usage"] = usage;
+            let _ = chunk_sender.send(Ok(last_chunk)).await;
+        } else {
+            let completion = json!({
+                "id": completion_id,
+                "object": "chat.completion",
+                "created": created,
+                "model": request.model,
+                "choices": [{
+                    "index": 0,
+                    "message": { "role": "assistant", "content": content },
+                    "finish_reason": "stop",
+                }],
+                "usage": usage,
+            });
+            let _ = chunk_sender.send(Ok(completion)).await;
+        }
+
+        Ok(())
+    }
+
+    /// Creates the hidden job of a completion, applies the request parameters to its config
+    /// and replays the earlier turns of the conversation into its inbox. The job is removed
+    /// again if any of this fails.
+    async fn openai_prepare_job(
+        db: Arc<SqliteManager>,
+        node_name: ShinkaiName,
+        identity_manager: Arc<Mutex<IdentityManager>>,
+        job_manager: Arc<Mutex<JobManager>>,
+        bearer: String,
+        request: &OpenAIChatCompletionRequest,
+        conversation: &OpenAIConversation,
+        agent_has_tools: Option<bool>,
+        node_encryption_sk: EncryptionStaticKey,
+        node_encryption_pk: EncryptionPublicKey,
+        node_signing_sk: SigningKey,
+    ) -> Result<OpenAICompletionJob, APIError> {
+        let llm_stopper = job_manager.lock().await.llm_stopper.clone();
+        let (res_sender, res_receiver) = async_channel::bounded(1);
+        let _ = Node::v2_create_new_job(
+            db.clone(),
+            node_name.clone(),
+            identity_manager.clone(),
+            job_manager,
+            bearer.clone(),
+            JobCreationInfo {
+                scope: MinimalJobScope::default(),
+                is_hidden: Some(true),
+                associated_ui: None,
+            },
+            request.model.clone(),
+            node_encryption_sk.clone(),
+            node_encryption_pk,
+            node_signing_sk.clone(),
+            res_sender,
+        )
+        .await;
+        let job_id = res_receiver
+            .recv()
+            .await
+            .map_err(|e| Node::generic_api_error(&e.to_string()))??;
+        let job = OpenAICompletionJob {
+            db: db.clone(),
+            llm_stopper,
+            job_id: job_id.clone(),
+            running_message: None,
+        };
+
+        let (config_sender, config_receiver) = async_channel::bounded(1);
+        let _ = Node::v2_api_get_job_config(db.clone(), bearer.clone(), job_id.clone(), config_sender).await;
+        let mut config = config_receiver
+            .recv()
+            .await
+            .map_err(|e| Node::generic_api_error(&e.to_string()))??;
+
+        config.temperature = request.temperature.or(config.temperature);
+        config.top_p = request.top_p.or(config.top_p);
+        config.max_tokens = request.max_completion_tokens.or(request.max_tokens).or(config.max_tokens);
+        config.seed = request.seed.or(config.seed);
+        config.stream = Some(request.stream);
+        match agent_has_tools {
+            Some(true) => config.use_tools = Some(true),
+            Some(false) => {}
+            None => config.custom_system_prompt = conversation.system_prompt.clone().or(config.custom_system_prompt),
+        }
+
+        let (update_sender, update_receiver) = async_channel::bounded(1);
+        let _ = Node::v2_api_update_job_config(db.clone(), bearer, job_id.clone(), config, update_sender).await;
+        update_receiver
+            .recv()
+            .await
+            .map_err(|e| Node::generic_api_error(&e.to_string()))??;
+
+        if !conversation.history.is_empty() {
+            let messages = conversation
+                .history
+                .iter()
+                .map(|content| JobMessage {
+                    job_id: job_id.clone(),
+                    content: content.clone(),
+                    parent: None,
+                    sheet_job_data: None,
+                    callback: None,
+                    metadata: None,
+                    tool_key: None,
+                    fs_files_paths: vec![],
+                    job_filenames: vec![],
+                    tools: None,
+                })
+                .collect();
+
+            // Replaying turns is an admin operation, the caller was authorized for this job above
+            let (history_sender, history_receiver) = async_channel::bounded(1);
+            let master_bearer = Self::get_bearer_token(db.clone(), &history_sender)
+                .await
+                .map_err(|e| Node::generic_api_error(&e.to_string()))?;
+            let _ = Node::v2_add_messages_god_mode(
+                db,
+                node_name,
+                identity_manager,
+                master_bearer,
+                job_id.clone(),
+                messages,
+                node_encryption_sk,
+                node_encryption_pk,
+                node_signing_sk,
+                history_sender,
+            )
+            .await;
+            history_receiver
+                .recv()
+                .await
+                .map_err(|e| Node::generic_api_error(&e.to_string()))??;
+        }
+
+        Ok(job)
+    }
+
+    /// Returns the job answer once the inbox holds `expected_messages` messages
+    pub(crate) async fn job_answer(
+        db: Arc<SqliteManager>,
+        bearer: String,
+        inbox_name: String,
+        expected_messages: usize,
+    ) -> Result<Option<String>, APIError> {
+        let (res_sender, res_receiver) = async_channel::bounded(1);
+        let _ = Node::v2_get_last_messages_from_inbox_with_branches(
+            db,
+            bearer,
+            inbox_name,
+            expected_messages,
+            None,
+            res_sender,
+        )
+        .await;
+        let messages: Vec<Vec<V2ChatMessage>> = res_receiver
+            .recv()
+            .await
+            .map_err(|e| Node::generic_api_error(&e.to_string()))??;
+
+        if messages.len() < expected_messages {
+            return Ok(None);
+        }
+        Ok(messages
+            .last()
+            .and_then(|branches| branches.last())
+            .map(|message| message.job_message.content.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(role: &str, content: Value) -> OpenAIChatMessage {
+        OpenAIChatMessage {
+            role: role.to_string(),
+            content: Some(content),
+        }
+    }
+
+    #[test]
+    fn test_openai_conversation_from_messages() {
+        let conversation = OpenAIConversation::from_messages(&[
+            message("system", json!("Be brief")),
+            message("user", json!("Hi")),
+            message("assistant", json!("Hello!")),
+            message("user", json!([{ "type": "text", "text": "What is" }, { "type": "text", "text": "2+2?" }])),
+        ])
+        .unwrap();
+        assert_eq!(conversation.system_prompt, Some("Be brief".to_string()));
+        assert_eq!(conversation.history, vec!["Hi".to_string(), "Hello!".to_string()]);
+        assert_eq!(conversation.prompt, "What is\n2+2?");
+
+        // Consecutive user turns and a leading assistant turn keep the history alternating
+        let conversation = OpenAIConversation::from_messages(&[
+            message("assistant", json!("How can I help?")),
+            message("user", json!("First")),
+            message("user", json!("Second")),
+        ])
+        .unwrap();
+        assert_eq!(conversation.system_prompt, None);
+        assert_eq!(conversation.history, vec!["".to_string(), "How can I help?".to_string()]);
+        assert_eq!(conversation.prompt, "First\n\nSecond");
+
+        assert!(OpenAIConversation::from_messages(&[]).is_err());
+        assert!(OpenAIConversation::from_messages(&[message("assistant", json!("Hi"))]).is_err());
+    }
+}
@@ -0,0 +1,276 @@
+use std::sync::Arc;
+
+use async_channel::Sender;
+use chrono::{DateTime, Utc};
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use shinkai_http_api::node_api_router::APIError;
+use shinkai_message_primitives::schemas::api_key::{ApiKey, ApiKeyScope};
+use shinkai_message_primitives::schemas::scheduled_job_message::{ScheduledJobMessage, ScheduledJobMessageStatus};
+use shinkai_message_primitives::shinkai_message::shinkai_message_schemas::JobMessage;
+use shinkai_sqlite::{errors::SqliteManagerError, SqliteManager};
+
+use crate::network::{node_error::NodeError, Node};
+
+fn scheduled_message_error(code: StatusCode, message: String) -> APIError {
+    APIError {
+        code: code.as_u16(),
+        error: code.canonical_reason().unwrap_or_default().to_string(),
+        message,
+    }
+}
+
+impl Node {
+    /// Keys restricted to some agents only see the messages scheduled for jobs run by one of them
+    fn api_key_allows_scheduled_job_message(
+        db: &SqliteManager,
+        api_key: &Option<ApiKey>,
+        scheduled: &ScheduledJobMessage,
+    ) -> bool {
+        match api_key {
+            Some(api_key) if api_key.agent_ids.is_some() => db
+                .get_job_with_options(&scheduled.job_id, false)
+                .map_or(false, |job| api_key.allows_agent(&job.parent_agent_or_llm_provider_id)),
+            _ => true,
+        }
+    }
+
+    pub async fn v2_api_schedule_job_message(
+        db: Arc<SqliteManager>,
+        bearer: String,
+        job_message: JobMessage,
+        send_at: DateTime<Utc>,
+        res: Sender<Result<ScheduledJobMessage, APIError>>,
+    ) -> Result<(), NodeError> {
+        let api_key = match Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::JobsWrite).await {
+            Ok(api_key) => api_key,
+            Err(_) => return Ok(()),
+        };
+
+        if send_at <= Utc::now() {
+            let _ = res
+                .send(Err(scheduled_message_error(
+                    StatusCode::BAD_REQUEST,
+                    "send_at must be in the future".to_string(),
+                )))
+                .await;
+            return Ok(());
+        }
+
+        let llm_provider = match db.get_job_with_options(&job_message.job_id, false) {
+            Ok(job) => job.parent_agent_or_llm_provider_id,
+            Err(_) => {
+                let _ = res
+                    .send(Err(scheduled_message_error(
+                        StatusCode::NOT_FOUND,
+                        format!("Job not found: {}", job_message.job_id),
+                    )))
+                    .await;
+                return Ok(());
+            }
+        };
+        if Self::validate_api_key_agent(&api_key, &llm_provider, &res)
+            .await
+            .is_err()
+        {
+            return Ok(());
+        }
+
+        let scheduled = ScheduledJobMessage {
+            id: format!("scheduled_{}", uuid::Uuid::new_v4()),
+            job_id: job_message.job_id.clone(),
+            job_message,
+            send_at,
+            created_at: Utc::now(),
+            status: ScheduledJobMessageStatus::Pending,
+            error: None,
+        };
+        let result = match db.add_scheduled_job_message(&scheduled) {
+            Ok(()) => Ok(scheduled),
+            Err(e) => Err(scheduled_message_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to schedule job message: {}", e),
+            )),
+        };
+        let _ = res.send(result).await;
+        Ok(())
+    }
+
+    pub async fn v2_api_list_scheduled_job_messages(
+        db: Arc<SqliteManager>,
+        bearer: String,
+        job_id: Option<String>,
+        res: Sender<Result<Vec<ScheduledJobMessage>, APIError>>,
+    ) -> Result<(), NodeError> {
+        let api_key = match Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::JobsRead).await {
+            Ok(api_key) => api_key,
+            Err(_) => return Ok(()),
+        };
+        if let Some(job_id) = &job_id {
+            if Self::validate_api_key_job(&db, &api_key, job_id, &res).await.is_err() {
+                return Ok(());
+            }
+        }
+
+        let result = match db.get_scheduled_job_messages(job_id.as_deref()) {
+            Ok(mut scheduled) => {
+                scheduled.retain(|scheduled| Self::api_key_allows_scheduled_job_message(&db, &api_key, scheduled));
+                Ok(scheduled)
+            }
+            Err(e) => Err(scheduled_message_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to list scheduled job messages: {}", e),
+            )),
+        };
+        let _ = res.send(result).await;
+        Ok(())
+    }
+
+    pub async fn v2_api_cancel_scheduled_job_message(
+        db: Arc<SqliteManager>,
+        bearer: String,
+        id: String,
+        res: Sender<Result<Value, APIError>>,
+    ) -> Result<(), NodeError> {
+        let api_key = match Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::JobsWrite).await {
+            Ok(api_key) => api_key,
+            Err(_) => return Ok(()),
+        };
+
+        match db.get_scheduled_job_message(&id) {
+            Ok(Some(scheduled)) => {
+                if Self::validate_api_key_job(&db, &api_key, &scheduled.job_id, &res)
+                    .await
+                    .is_err()
+                {
+                    return Ok(());
+                }
+            }
+            Ok(None) => {}
+            Err(e) => {
+                let _ = res
+                    .send(Err(scheduled_message_error(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        format!("Failed to cancel scheduled job message: {}", e),
+                    )))
+                    .await;
+                return Ok(());
+            }
+        }
+
+        let result = match db.remove_scheduled_job_message(&id) {
+            Ok(()) => Ok(json!({ "id": id, "cancelled": true })),
+            Err(SqliteManagerError::DataNotFound) => Err(scheduled_message_error(
+                StatusCode::NOT_FOUND,
+                format!("Scheduled job message not found: {}", id),
+            )),
+            Err(e) => Err(scheduled_message_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to cancel scheduled job message: {}", e),
+            )),
+        };
+        let _ = res.send(result).await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shinkai_embedding::model_type::{EmbeddingModelType, OllamaTextEmbeddingsInference};
+    use shinkai_message_primitives::shinkai_utils::job_scope::MinimalJobScope;
+
+    const RESTRICTED_SECRET: &str = "sk_shinkai_restricted_0123456789";
+
+    fn setup_test_db(dir: &tempfile::TempDir) -> Arc<SqliteManager> {
+        let model_type =
+            EmbeddingModelType::OllamaTextEmbeddingsInference(OllamaTextEmbeddingsInference::SnowflakeArcticEmbedM);
+        let db = SqliteManager::new(dir.path().join("scheduled.db"), String::new(), model_type).unwrap();
+
+        let api_key = ApiKey {
+            id: "restricted".to_string(),
+            name: "restricted".to_string(),
+            key_prefix: RESTRICTED_SECRET[..14].to_string(),
+            scopes: vec![ApiKeyScope::JobsWrite],
+            agent_ids: Some(vec!["my_agent".to_string()]),
+            expires_at: None,
+            created_at: Utc::now(),
+            last_used_at: None,
+            revoked: false,
+        };
+        db.add_api_key(&api_key, RESTRICTED_SECRET).unwrap();
+        for (job_id, agent_id) in [("my_job", "my_agent"), ("other_job", "other_agent")] {
+            db.create_new_job(
+                job_id.to_string(),
+                agent_id.to_string(),
+                MinimalJobScope::default(),
+                false,
+                None,
+                None,
+            )
+            .unwrap();
+            db.add_scheduled_job_message(&scheduled_message(job_id)).unwrap();
+        }
+        Arc::new(db)
+    }
+
+    fn scheduled_message(job_id: &str) -> ScheduledJobMessage {
+        ScheduledJobMessage {
+            id: format!("scheduled_{}", job_id),
+            job_id: job_id.to_string(),
+            job_message: JobMessage {
+                job_id: job_id.to_string(),
+                content: "Run the weekly analysis".to_string(),
+                parent: None,
+                sheet_job_data: None,
+                tools: None,
+                callback: None,
+                metadata: None,
+                tool_key: None,
+                fs_files_paths: vec![],
+                job_filenames: vec![],
+            },
+            send_at: Utc::now() + chrono::Duration::days(1),
+            created_at: Utc::now(),
+            status: ScheduledJobMessageStatus::Pending,
+            error: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_agent_restricted_key_only_reaches_its_scheduled_job_messages() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = setup_test_db(&dir);
+        let bearer = RESTRICTED_SECRET.to_string();
+
+        let (res, receiver) = async_channel::bounded(1);
+        Node::v2_api_list_scheduled_job_messages(db.clone(), bearer.clone(), Some("other_job".to_string()), res)
+            .await
+            .unwrap();
+        let error = receiver.recv().await.unwrap().unwrap_err();
+        assert_eq!(error.code, StatusCode::FORBIDDEN.as_u16());
+
+        let (res, receiver) = async_channel::bounded(1);
+        Node::v2_api_list_scheduled_job_messages(db.clone(), bearer.clone(), None, res)
+            .await
+            .unwrap();
+        let listed = receiver.recv().await.unwrap().unwrap();
+        let listed_ids: Vec<&str> = listed.iter().map(|scheduled| scheduled.id.as_str()).collect();
+        assert_eq!(listed_ids, vec!["scheduled_my_job"]);
+
+        let (res, receiver) = async_channel::bounded(1);
+        Node::v2_api_cancel_scheduled_job_message(db.clone(), bearer.clone(), "scheduled_other_job".to_string(), res)
+            .await
+            .unwrap();
+        let error = receiver.recv().await.unwrap().unwrap_err();
+        assert_eq!(error.code, StatusCode::FORBIDDEN.as_u16());
+        assert!(db.get_scheduled_job_message("scheduled_other_job").unwrap().is_some());
+
+        let (res, receiver) = async_channel::bounded(1);
+        Node::v2_api_cancel_scheduled_job_message(db.clone(), bearer, "scheduled_my_job".to_string(), res)
+            .await
+            .unwrap();
+        assert!(receiver.recv().await.unwrap().is_ok());
+        assert!(db.get_scheduled_job_message("scheduled_my_job").unwrap().is_none());
+    }
+}
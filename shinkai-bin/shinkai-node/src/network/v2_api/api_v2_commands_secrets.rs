@@ -0,0 +1,188 @@
+use std::sync::Arc;
+
+use async_channel::Sender;
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use shinkai_http_api::node_api_router::APIError;
+use shinkai_message_primitives::shinkai_utils::shinkai_logging::{shinkai_log, ShinkaiLogLevel, ShinkaiLogOption};
+use shinkai_sqlite::SqliteManager;
+
+use crate::network::{node_error::NodeError, Node};
+
+#[cfg(feature = "os-keyring")]
+const KEYRING_SERVICE: &str = "shinkai-node";
+
+#[cfg(feature = "os-keyring")]
+fn keyring_entry(node_name: &str) -> Result<keyring::Entry, String> {
+    keyring::Entry::new(KEYRING_SERVICE, &format!("{}-secret-vault", node_name)).map_err(|e| e.to_string())
+}
+
+/// Entry holding the new key while a rotation is in progress
+#[cfg(feature = "os-keyring")]
+fn staging_keyring_entry(node_name: &str) -> Result<keyring::Entry, String> {
+    keyring::Entry::new(KEYRING_SERVICE, &format!("{}-secret-vault-staging", node_name)).map_err(|e| e.to_string())
+}
+
+#[cfg(feature = "os-keyring")]
+fn read_keyring_key(entry: &keyring::Entry) -> Result<Option<[u8; 32]>, String> {
+    match entry.get_password() {
+        Ok(encoded) => {
+            let bytes = hex::decode(encoded.trim()).map_err(|e| e.to_string())?;
+            let key = bytes
+                .try_into()
+                .map_err(|_| "The vault key stored in the OS keyring is not 32 bytes long".to_string())?;
+            Ok(Some(key))
+        }
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Reads the vault key from the OS keyring, creating and storing a new one on first use
+#[cfg(feature = "os-keyring")]
+fn load_or_create_keyring_key(node_name: &str) -> Result<[u8; 32], String> {
+    use shinkai_message_primitives::shinkai_utils::secret_vault::SecretVault;
+
+    let entry = keyring_entry(node_name)?;
+    match read_keyring_key(&entry)? {
+        Some(key) => Ok(key),
+        None => store_keyring_key(&entry, SecretVault::random_key()),
+    }
+}
+
+#[cfg(feature = "os-keyring")]
+fn store_keyring_key(entry: &keyring::Entry, key: [u8; 32]) -> Result<[u8; 32], String> {
+    entry.set_password(&hex::encode(key)).map_err(|e| e.to_string())?;
+    Ok(key)
+}
+
+impl Node {
+    /// Unlocks the secret vault at startup. `SECRETS_VAULT_PASSPHRASE` takes precedence over the OS
+    /// keyring; without either, secrets stay in plaintext as before and an error is logged.
+    pub fn unlock_secret_vault(db: &SqliteManager, node_name: &str) -> Result<(), NodeError> {
+        if let Ok(passphrase) = std::env::var("SECRETS_VAULT_PASSPHRASE") {
+            if !passphrase.is_empty() {
+                let resealed = db
+                    .unlock_secret_vault_with_passphrase(&passphrase)
+                    .map_err(|e| NodeError::from(format!("Failed to unlock the secret vault: {}", e)))?;
+                if resealed > 0 {
+                    shinkai_log(
+                        ShinkaiLogOption::Node,
+                        ShinkaiLogLevel::Info,
+                        &format!("Encrypted {} stored secrets with the vault passphrase", resealed),
+                    );
+                }
+                return Ok(());
+            }
+        }
+
+        #[cfg(feature = "os-keyring")]
+        {
+            let keyring_error =
+                |e: String| NodeError::from(format!("Failed to read the vault key from the OS keyring: {}", e));
+            let key = load_or_create_keyring_key(node_name).map_err(keyring_error)?;
+            let staging = staging_keyring_entry(node_name).map_err(keyring_error)?;
+            let staged = read_keyring_key(&staging).map_err(keyring_error)?;
+            let resealed = match (db.unlock_secret_vault_with_key(key), staged) {
+                (Ok(resealed), _) => resealed,
+                // A rotation committed the staged key but stopped before promoting it
+                (Err(_), Some(staged)) => {
+                    let resealed = db
+                        .unlock_secret_vault_with_key(staged)
+                        .map_err(|e| NodeError::from(format!("Failed to unlock the secret vault: {}", e)))?;
+                    keyring_entry(node_name)
+                        .and_then(|entry| store_keyring_key(&entry, staged))
+                        .map_err(keyring_error)?;
+                    resealed
+                }
+                (Err(e), None) => {
+                    return Err(NodeError::from(format!("Failed to unlock the secret vault: {}", e)));
+                }
+            };
+            // Either promoted above or left by a rotation that never committed
+            if staged.is_some() {
+                let _ = staging.delete_credential();
+            }
+            if resealed > 0 {
+                shinkai_log(
+                    ShinkaiLogOption::Node,
+                    ShinkaiLogLevel::Info,
+                    &format!("Encrypted {} stored secrets with the OS keyring key", resealed),
+                );
+            }
+            Ok(())
+        }
+
+        #[cfg(not(feature = "os-keyring"))]
+        {
+            let _ = node_name;
+            shinkai_log(
+                ShinkaiLogOption::Node,
+                ShinkaiLogLevel::Error,
+                "SECRETS_VAULT_PASSPHRASE is not set and the node is built without the os-keyring feature, \
+                 API keys and OAuth tokens are stored unencrypted",
+            );
+            Ok(())
+        }
+    }
+
+    /// Re-encrypts every stored secret with a new key: derived from `new_passphrase` if given,
+    /// otherwise a fresh random key kept in the OS keyring
+    pub async fn v2_api_rotate_secrets_key(
+        db: Arc<SqliteManager>,
+        node_name: String,
+        bearer: String,
+        new_passphrase: Option<String>,
+        res: Sender<Result<Value, APIError>>,
+    ) -> Result<(), NodeError> {
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        let result = match new_passphrase.filter(|passphrase| !passphrase.is_empty()) {
+            Some(passphrase) => db.rotate_secret_vault_passphrase(&passphrase).map_err(|e| e.to_string()),
+            None => Self::rotate_secret_vault_keyring_key(&db, &node_name),
+        };
+
+        match result {
+            Ok(resealed) => {
+                let _ = res.send(Ok(json!({ "resealed": resealed }))).await;
+            }
+            Err(e) => {
+                let api_error = APIError {
+                    code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                    error: "Internal Server Error".to_string(),
+                    message: format!("Failed to rotate the secrets key: {}", e),
+                };
+                let _ = res.send(Err(api_error)).await;
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "os-keyring")]
+    fn rotate_secret_vault_keyring_key(db: &SqliteManager, node_name: &str) -> Result<u64, String> {
+        use shinkai_message_primitives::shinkai_utils::secret_vault::SecretVault;
+
+        // The new key is staged in its own entry until the secrets resealed with it are committed,
+        // so the entry in use always holds the key of the stored secrets. A crash after the commit
+        // leaves the staged key to be promoted on the next start.
+        let staging = staging_keyring_entry(node_name)?;
+        let key = store_keyring_key(&staging, SecretVault::random_key())?;
+        let resealed = match db.rotate_secret_vault_key(key) {
+            Ok(resealed) => resealed,
+            Err(e) => {
+                let _ = staging.delete_credential();
+                return Err(e.to_string());
+            }
+        };
+        store_keyring_key(&keyring_entry(node_name)?, key)?;
+        let _ = staging.delete_credential();
+        Ok(resealed)
+    }
+
+    #[cfg(not(feature = "os-keyring"))]
+    fn rotate_secret_vault_keyring_key(_db: &SqliteManager, _node_name: &str) -> Result<u64, String> {
+        Err("A new passphrase is required when the node is built without the os-keyring feature".to_string())
+    }
+}
@@ -8,7 +8,9 @@ use crate::{
     },
     tools::{
         tool_definitions::definition_generation::{generate_tool_definitions, get_all_tools},
+        tool_dependencies::{dependency_graph, dependents_of, installed_tools, unmet_dependencies},
         tool_execution::execution_coordinator::{execute_code, execute_mcp_tool_cmd, execute_tool_cmd},
+        tool_execution::execution_test_cases::{get_tools_with_test_cases, run_tool_test_cases},
         tool_generation::v2_create_and_send_job_message,
         tool_prompts::{generate_code_prompt, tool_metadata_implementation_prompt},
     },
@@ -20,6 +22,7 @@ use ed25519_dalek::{ed25519::signature::SignerMut, SigningKey};
 use reqwest::StatusCode;
 use serde_json::{json, Map, Value};
 use shinkai_http_api::node_api_router::{APIError, SendResponseBodyData};
+use shinkai_message_primitives::schemas::api_key::ApiKeyScope;
 use shinkai_message_primitives::{
     schemas::{
         inbox_name::InboxName, indexable_version::IndexableVersion, job::JobLike, job_config::JobConfig,
@@ -44,9 +47,15 @@ use shinkai_tools_primitives::tools::{
 };
 use shinkai_tools_primitives::tools::{
     shinkai_tool::ShinkaiToolHeader,
+    tool_network::ANY_HOST,
     tool_types::{OperatingSystem, RunnerType, ToolResult},
 };
-use std::{collections::HashMap, path::PathBuf};
+use std::{
+    collections::{HashMap, HashSet},
+    future::Future,
+    path::PathBuf,
+    pin::Pin,
+};
 use std::{
     env,
     fs::File,
@@ -134,7 +143,7 @@ impl Node {
         res: Sender<Result<Value, APIError>>,
     ) -> Result<(), NodeError> {
         // Validate the bearer token
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+        if Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::ToolsRead).await.is_err() {
             return Ok(());
         }
 
@@ -255,7 +264,7 @@ impl Node {
         res: Sender<Result<Value, APIError>>,
     ) -> Result<(), NodeError> {
         // Validate the bearer token
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+        if Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::ToolsRead).await.is_err() {
             return Ok(());
         }
 
@@ -633,7 +642,7 @@ impl Node {
         let merged_value = Self::merge_tool(&existing_tool_value, &input_value);
 
         // Convert merged_value to ShinkaiTool
-        let merged_tool: ShinkaiTool = match serde_json::from_value(merged_value) {
+        let mut merged_tool: ShinkaiTool = match serde_json::from_value(merged_value) {
             Ok(tool) => tool,
             Err(err) => {
                 let api_error = APIError {
@@ -645,12 +654,15 @@ impl Node {
                 return Ok(());
             }
         };
+        // Config values the UI sent back redacted keep what was stored
+        merged_tool.restore_redacted_config(&existing_tool);
 
         // Save the tool to the LanceShinkaiDb
         let save_result = db.update_tool(merged_tool).await;
 
         match save_result {
-            Ok(tool) => {
+            Ok(mut tool) => {
+                tool.redact_config();
                 let _ = res.send(Ok(tool)).await;
                 Ok(())
             }
@@ -679,18 +691,27 @@ impl Node {
         }
 
         let new_tool = new_tool_with_assets.tool;
-        let dependencies = new_tool.get_tools();
-        for dependency in dependencies {
-            let tool = db.get_tool_by_key(&dependency.to_string_without_version());
-            if tool.is_err() {
-                let api_error = APIError {
-                    code: StatusCode::BAD_REQUEST.as_u16(),
-                    error: "Bad Request".to_string(),
-                    message: format!("Tool not found: {}", dependency.to_string_without_version()),
-                };
-                let _ = res.send(Err(api_error)).await;
+        let tools = match installed_tools(&db) {
+            Ok(tools) => tools,
+            Err(err) => {
+                let _ = res.send(Err(APIError::from(err.to_string()))).await;
                 return Ok(());
             }
+        };
+        if let Some(dependency) = unmet_dependencies(&tools, &new_tool).first() {
+            let api_error = APIError {
+                code: StatusCode::BAD_REQUEST.as_u16(),
+                error: "Bad Request".to_string(),
+                message: format!(
+                    "Tool not found: {} {}",
+                    dependency.to_string_without_version(),
+                    dependency.version.clone().unwrap_or_default()
+                )
+                .trim_end()
+                .to_string(),
+            };
+            let _ = res.send(Err(api_error)).await;
+            return Ok(());
         }
 
         let save_result = db.add_tool(new_tool).await;
@@ -759,14 +780,16 @@ impl Node {
         res: Sender<Result<Value, APIError>>,
     ) -> Result<(), NodeError> {
         // Validate the bearer token
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+        if Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::ToolsRead).await.is_err() {
             return Ok(());
         }
 
         // Get the tool from the database using the tool_key directly
         match db.get_tool_by_key(&tool_key) {
             // Use tool_key directly
-            Ok(tool) => {
+            Ok(mut tool) => {
+                tool.redact_config();
+
                 // Serialize the tool object to JSON value first.
                 let mut response_value = match serde_json::to_value(&tool) {
                     Ok(val) => val,
@@ -929,6 +952,8 @@ impl Node {
                     runner: payload.metadata.runner,
                     operating_system: payload.metadata.operating_system,
                     tool_set: payload.metadata.tool_set,
+                    test_cases: payload.metadata.test_cases,
+                    network_allowlist: payload.metadata.network_allowlist,
                 };
                 ShinkaiTool::Deno(tool, false)
             }
@@ -965,6 +990,8 @@ impl Node {
                     runner: payload.metadata.runner,
                     operating_system: payload.metadata.operating_system,
                     tool_set: payload.metadata.tool_set,
+                    test_cases: payload.metadata.test_cases,
+                    network_allowlist: payload.metadata.network_allowlist,
                 };
                 ShinkaiTool::Python(tool, false)
             }
@@ -1109,7 +1136,7 @@ impl Node {
         res: Sender<Result<Value, APIError>>,
     ) -> Result<(), NodeError> {
         // Validate the bearer token
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+        if Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::ToolsRead).await.is_err() {
             return Ok(());
         }
 
@@ -1185,7 +1212,7 @@ impl Node {
         res: Sender<Result<Value, APIError>>,
     ) -> Result<(), NodeError> {
         // Validate the bearer token
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+        if Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::ToolsRead).await.is_err() {
             return Ok(());
         }
 
@@ -1228,7 +1255,7 @@ impl Node {
         tools: Vec<ToolRouterKey>,
         res: Sender<Result<Value, APIError>>,
     ) -> Result<(), NodeError> {
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+        if Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::ToolsRead).await.is_err() {
             return Ok(());
         }
 
@@ -1268,7 +1295,12 @@ impl Node {
         mounts: Option<Vec<String>>,
         res: Sender<Result<Value, APIError>>,
     ) -> Result<(), NodeError> {
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+        let api_key = match Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::ToolsExecute).await {
+            Ok(api_key) => api_key,
+            Err(_) => return Ok(()),
+        };
+        let requested_agent = agent_id.as_deref().unwrap_or(&llm_provider);
+        if !requested_agent.is_empty() && Self::validate_api_key_agent(&api_key, requested_agent, &res).await.is_err() {
             return Ok(());
         }
 
@@ -2217,6 +2249,8 @@ impl Node {
     pub async fn get_tool_zip(tool: ShinkaiTool, node_env: NodeEnvironment) -> Result<Vec<u8>, NodeError> {
         let mut tool = tool;
         tool.sanitize_config();
+        // Exported and published zips leave the node, sanitizing only covers some tool types
+        tool.redact_config();
 
         let tool_bytes = serde_json::to_vec(&tool).unwrap();
 
@@ -2266,7 +2300,7 @@ impl Node {
         res: Sender<Result<Vec<u8>, APIError>>,
     ) -> Result<(), NodeError> {
         // Validate the bearer token
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+        if Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::ToolsRead).await.is_err() {
             return Ok(());
         }
 
@@ -2462,27 +2496,148 @@ impl Node {
         node_name: String,
         signing_secret_key: SigningKey,
     ) -> Result<Value, APIError> {
-        let zip_contents: ZipFileContents =
-            match download_zip_file(url, "__tool.json".to_string(), node_name, signing_secret_key).await {
+        Self::import_tool_from_url(db, node_env, url, node_name, signing_secret_key, &mut HashSet::new()).await
+    }
+
+    /// Downloads and imports a tool, installing first the tools it depends on.
+    /// `installing` holds the keys already being installed so dependency cycles terminate.
+    fn import_tool_from_url<'a>(
+        db: Arc<SqliteManager>,
+        node_env: NodeEnvironment,
+        url: String,
+        node_name: String,
+        signing_secret_key: SigningKey,
+        installing: &'a mut HashSet<String>,
+    ) -> Pin<Box<dyn Future<Output = Result<Value, APIError>> + Send + 'a>> {
+        Box::pin(async move {
+            let zip_contents: ZipFileContents = match download_zip_file(
+                url,
+                "__tool.json".to_string(),
+                node_name.clone(),
+                signing_secret_key.clone(),
+            )
+            .await
+            {
                 Ok(contents) => contents,
                 Err(err) => {
                     return Err(err);
                 }
             };
 
-        // Parse the JSON into a ShinkaiTool
-        let tool: ShinkaiTool = match serde_json::from_slice(&zip_contents.buffer) {
-            Ok(tool) => tool,
-            Err(err) => {
-                return Err(APIError {
-                    code: StatusCode::BAD_REQUEST.as_u16(),
-                    error: "Invalid Tool JSON".to_string(),
-                    message: format!("Failed to parse tool.json: {}", err),
-                });
+            // Parse the JSON into a ShinkaiTool
+            let tool: ShinkaiTool = match serde_json::from_slice(&zip_contents.buffer) {
+                Ok(tool) => tool,
+                Err(err) => {
+                    return Err(APIError {
+                        code: StatusCode::BAD_REQUEST.as_u16(),
+                        error: "Invalid Tool JSON".to_string(),
+                        message: format!("Failed to parse tool.json: {}", err),
+                    });
+                }
+            };
+
+            installing.insert(tool.tool_router_key().to_string_without_version());
+            let dependencies = Self::install_tool_dependencies(
+                db.clone(),
+                node_env.clone(),
+                node_name,
+                signing_secret_key,
+                &tool,
+                installing,
+            )
+            .await?;
+
+            let network_access = Self::tool_network_access(&db, &tool);
+            let wasm_capabilities = Self::wasm_tool_capabilities(&db, &tool);
+            let mut response = Node::import_tool(db, node_env, zip_contents, tool).await?;
+            response["installed_dependencies"] = json!(dependencies);
+            response["network_access"] = network_access;
+            response["wasm_capabilities"] = wasm_capabilities;
+            Ok(response)
+        })
+    }
+
+    /// Installs the missing dependencies of `tool` (transitively) from the tools directory.
+    /// Returns the keys of the installed tools.
+    async fn install_tool_dependencies(
+        db: Arc<SqliteManager>,
+        node_env: NodeEnvironment,
+        node_name: String,
+        signing_secret_key: SigningKey,
+        tool: &ShinkaiTool,
+        installing: &mut HashSet<String>,
+    ) -> Result<Vec<String>, APIError> {
+        let tools = installed_tools(&db).map_err(|e| APIError::from(e.to_string()))?;
+        let missing: Vec<ToolRouterKey> = unmet_dependencies(&tools, tool)
+            .into_iter()
+            .filter(|dependency| !installing.contains(&dependency.to_string_without_version()))
+            .collect();
+        if missing.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let directory = Self::fetch_tools_directory().await?;
+        let mut installed = Vec::new();
+        for dependency in missing {
+            let dependency_key = dependency.to_string_without_version();
+            if installing.contains(&dependency_key) {
+                continue;
             }
-        };
 
-        Node::import_tool(db, node_env, zip_contents, tool).await
+            let url = directory
+                .iter()
+                .find(|entry| {
+                    entry["routerKey"].as_str().map(|key| key.to_lowercase()) == Some(dependency_key.clone())
+                        && dependency.version_requirement_matches(entry["version"].as_str().unwrap_or("1.0.0"))
+                })
+                .and_then(|entry| entry["file"].as_str())
+                .ok_or_else(|| APIError {
+                    code: StatusCode::FAILED_DEPENDENCY.as_u16(),
+                    error: "Unresolved Dependency".to_string(),
+                    message: format!(
+                        "Tool {} requires {} {} which is not available in the tools directory",
+                        tool.tool_router_key().to_string_without_version(),
+                        dependency_key,
+                        dependency.version.clone().unwrap_or_default()
+                    ),
+                })?
+                .to_string();
+
+            Self::import_tool_from_url(
+                db.clone(),
+                node_env.clone(),
+                url,
+                node_name.clone(),
+                signing_secret_key.clone(),
+                installing,
+            )
+            .await?;
+            installed.push(dependency_key);
+        }
+        Ok(installed)
+    }
+
+    // Same directory used to sync the default tools, see `ToolRouter::import_tools_from_directory`
+    async fn fetch_tools_directory() -> Result<Vec<Value>, APIError> {
+        let url = env::var("SHINKAI_TOOLS_DIRECTORY_URL")
+            .unwrap_or_else(|_| format!("https://store-api.shinkai.com/store/defaults"));
+
+        let response = reqwest::Client::new()
+            .get(url)
+            .header("X-Shinkai-Version", env!("CARGO_PKG_VERSION"))
+            .send()
+            .await
+            .map_err(|e| APIError {
+                code: StatusCode::BAD_GATEWAY.as_u16(),
+                error: "Tools Directory Unavailable".to_string(),
+                message: format!("Failed to fetch the tools directory: {}", e),
+            })?;
+
+        response.json::<Vec<Value>>().await.map_err(|e| APIError {
+            code: StatusCode::BAD_GATEWAY.as_u16(),
+            error: "Tools Directory Unavailable".to_string(),
+            message: format!("Failed to parse the tools directory: {}", e),
+        })
     }
 
     pub async fn import_tool(
@@ -2505,6 +2660,9 @@ impl Node {
             ShinkaiTool::Python(_, _) => {
                 println!("Python tool detected {}", tool_router_key);
             }
+            ShinkaiTool::Wasm(_, _) => {
+                println!("Wasm tool detected {}", tool_router_key);
+            }
             ShinkaiTool::Network(_, _) => {
                 println!("Network tool detected {}", tool_router_key);
             }
@@ -2801,7 +2959,7 @@ impl Node {
         res: Sender<Result<Vec<String>, APIError>>,
     ) -> Result<(), NodeError> {
         // Validate the bearer token
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+        if Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::ToolsRead).await.is_err() {
             return Ok(());
         }
 
@@ -2866,6 +3024,7 @@ impl Node {
         db: Arc<SqliteManager>,
         bearer: String,
         tool_key: String,
+        force: bool,
         res: Sender<Result<Value, APIError>>,
     ) -> Result<(), NodeError> {
         // Validate the bearer token
@@ -2889,6 +3048,30 @@ impl Node {
         let tool_key_name = tool_router_key.to_string_without_version();
         let version = tool_router_key.version;
 
+        // Removing a tool that other tools call requires explicit confirmation
+        if !force {
+            let dependents = match installed_tools(&db_write) {
+                Ok(tools) => dependents_of(&tools, &tool_key_name),
+                Err(err) => {
+                    let _ = res.send(Err(APIError::from(err.to_string()))).await;
+                    return Ok(());
+                }
+            };
+            if !dependents.is_empty() {
+                let api_error = APIError {
+                    code: StatusCode::CONFLICT.as_u16(),
+                    error: "Tool Has Dependents".to_string(),
+                    message: format!(
+                        "Tool {} is required by: {}. Set force=true to remove it anyway.",
+                        tool_key_name,
+                        dependents.join(", ")
+                    ),
+                };
+                let _ = res.send(Err(api_error)).await;
+                return Ok(());
+            }
+        }
+
         // Attempt to remove the playground tool first, warn on failure but continue
         if let Err(e) = db_write.remove_tool_playground(&tool_key) {
             log::warn!(
@@ -2917,6 +3100,257 @@ impl Node {
         }
     }
 
+    pub async fn v2_api_get_tool_dependency_graph(
+        db: Arc<SqliteManager>,
+        bearer: String,
+        tool_router_key: Option<String>,
+        res: Sender<Result<Value, APIError>>,
+    ) -> Result<(), NodeError> {
+        // Validate the bearer token
+        if Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::ToolsRead).await.is_err() {
+            return Ok(());
+        }
+
+        let tools = match installed_tools(&db) {
+            Ok(tools) => tools,
+            Err(err) => {
+                let _ = res.send(Err(APIError::from(err.to_string()))).await;
+                return Ok(());
+            }
+        };
+
+        let root = match tool_router_key {
+            Some(key) => match ToolRouterKey::from_string(&key) {
+                Ok(key) => Some(key.to_string_without_version()),
+                Err(err) => {
+                    let api_error = APIError {
+                        code: StatusCode::BAD_REQUEST.as_u16(),
+                        error: "Bad Request".to_string(),
+                        message: format!("Invalid tool key: {}", err),
+                    };
+                    let _ = res.send(Err(api_error)).await;
+                    return Ok(());
+                }
+            },
+            None => None,
+        };
+        if let Some(root) = &root {
+            if !tools.iter().any(|tool| &tool.tool_router_key().to_string_without_version() == root) {
+                let api_error = APIError {
+                    code: StatusCode::NOT_FOUND.as_u16(),
+                    error: "Not Found".to_string(),
+                    message: format!("Tool not found: {}", root),
+                };
+                let _ = res.send(Err(api_error)).await;
+                return Ok(());
+            }
+        }
+
+        let graph = dependency_graph(&tools, root.as_deref());
+        let _ = res.send(Ok(json!(graph))).await;
+        Ok(())
+    }
+
+    /// Network access declared by the tool and approved by the user. Tools without an approval
+    /// run with no outbound access until one is given.
+    fn tool_network_access(db: &SqliteManager, tool: &ShinkaiTool) -> Value {
+        if !matches!(tool, ShinkaiTool::Deno(..) | ShinkaiTool::Python(..)) {
+            return json!({
+                "declared": null,
+                "approved": null,
+                "approval_required": false
+            });
+        }
+        let declared = tool.get_network_allowlist();
+        let approval = db
+            .get_tool_network_approval(&tool.tool_router_key().to_string_without_version())
+            .ok()
+            .flatten();
+        // Without an allowlist the tool has no network access until the user approves any host
+        let approval_required = match (&declared, &approval) {
+            (None, approval) => !approval
+                .as_ref()
+                .is_some_and(|approval| approval.allowed_hosts.iter().any(|host| host == ANY_HOST)),
+            (Some(declared), None) => !declared.is_empty(),
+            (Some(declared), Some(approval)) => declared.iter().any(|host| !approval.allowed_hosts.contains(host)),
+        };
+        json!({
+            "declared": declared,
+            "approved": approval,
+            "approval_required": approval_required
+        })
+    }
+
+    pub async fn v2_api_set_tool_network_approval(
+        db: Arc<SqliteManager>,
+        bearer: String,
+        tool_router_key: String,
+        approved: bool,
+        res: Sender<Result<Value, APIError>>,
+    ) -> Result<(), NodeError> {
+        // Validate the bearer token
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        let tool = match db.get_tool_by_key(&tool_router_key) {
+            Ok(tool) => tool,
+            Err(err) => {
+                let api_error = APIError {
+                    code: StatusCode::NOT_FOUND.as_u16(),
+                    error: "Not Found".to_string(),
+                    message: format!("Tool not found: {}", err),
+                };
+                let _ = res.send(Err(api_error)).await;
+                return Ok(());
+            }
+        };
+
+        if !matches!(tool, ShinkaiTool::Deno(..) | ShinkaiTool::Python(..)) {
+            let api_error = APIError {
+                code: StatusCode::BAD_REQUEST.as_u16(),
+                error: "Bad Request".to_string(),
+                message: "Network access is only approved for Deno and Python tools".to_string(),
+            };
+            let _ = res.send(Err(api_error)).await;
+            return Ok(());
+        }
+        // Approving a tool that declares no allowlist lets it reach any host
+        let declared = tool
+            .get_network_allowlist()
+            .unwrap_or_else(|| vec![ANY_HOST.to_string()]);
+
+        let tool_key = tool.tool_router_key().to_string_without_version();
+        let result = if approved {
+            db.set_tool_network_approval(&tool_key, &declared).map(|_| ())
+        } else {
+            db.remove_tool_network_approval(&tool_key).map(|_| ())
+        };
+        if let Err(err) = result {
+            let _ = res.send(Err(APIError::from(err.to_string()))).await;
+            return Ok(());
+        }
+
+        let _ = res.send(Ok(Self::tool_network_access(&db, &tool))).await;
+        Ok(())
+    }
+
+    /// Capabilities requested by a WASM tool and granted by the user. The tool runs without any of
+    /// them until a grant is given.
+    fn wasm_tool_capabilities(db: &SqliteManager, tool: &ShinkaiTool) -> Value {
+        let ShinkaiTool::Wasm(wasm_tool, _) = tool else {
+            return json!({
+                "requested": null,
+                "granted": null,
+                "approval_required": false
+            });
+        };
+        let grant = db
+            .get_wasm_tool_grant(&tool.tool_router_key().to_string_without_version())
+            .ok()
+            .flatten();
+        let approval_required = match &grant {
+            Some(grant) => wasm_tool.capabilities.granted(&grant.capabilities) != wasm_tool.capabilities,
+            None => !wasm_tool.capabilities.is_empty(),
+        };
+        json!({
+            "requested": wasm_tool.capabilities,
+            "granted": grant,
+            "approval_required": approval_required
+        })
+    }
+
+    pub async fn v2_api_set_wasm_tool_grant(
+        db: Arc<SqliteManager>,
+        bearer: String,
+        tool_router_key: String,
+        approved: bool,
+        allow_outside_storage: bool,
+        res: Sender<Result<Value, APIError>>,
+    ) -> Result<(), NodeError> {
+        // Validate the bearer token
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        let tool = match db.get_tool_by_key(&tool_router_key) {
+            Ok(tool) => tool,
+            Err(err) => {
+                let api_error = APIError {
+                    code: StatusCode::NOT_FOUND.as_u16(),
+                    error: "Not Found".to_string(),
+                    message: format!("Tool not found: {}", err),
+                };
+                let _ = res.send(Err(api_error)).await;
+                return Ok(());
+            }
+        };
+
+        let ShinkaiTool::Wasm(wasm_tool, _) = &tool else {
+            let api_error = APIError {
+                code: StatusCode::BAD_REQUEST.as_u16(),
+                error: "Bad Request".to_string(),
+                message: "Only WASM tools have capability grants".to_string(),
+            };
+            let _ = res.send(Err(api_error)).await;
+            return Ok(());
+        };
+
+        let tool_key = tool.tool_router_key().to_string_without_version();
+        let result = if approved {
+            db.set_wasm_tool_grant(&tool_key, &wasm_tool.capabilities, allow_outside_storage)
+                .map(|_| ())
+        } else {
+            db.remove_wasm_tool_grant(&tool_key).map(|_| ())
+        };
+        if let Err(err) = result {
+            let _ = res.send(Err(APIError::from(err.to_string()))).await;
+            return Ok(());
+        }
+
+        let _ = res.send(Ok(Self::wasm_tool_capabilities(&db, &tool))).await;
+        Ok(())
+    }
+
+    pub async fn v2_api_get_tool_network_access(
+        db: Arc<SqliteManager>,
+        bearer: String,
+        tool_router_key: String,
+        res: Sender<Result<Value, APIError>>,
+    ) -> Result<(), NodeError> {
+        // Validate the bearer token
+        if Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::ToolsRead).await.is_err() {
+            return Ok(());
+        }
+
+        let tool = match db.get_tool_by_key(&tool_router_key) {
+            Ok(tool) => tool,
+            Err(err) => {
+                let api_error = APIError {
+                    code: StatusCode::NOT_FOUND.as_u16(),
+                    error: "Not Found".to_string(),
+                    message: format!("Tool not found: {}", err),
+                };
+                let _ = res.send(Err(api_error)).await;
+                return Ok(());
+            }
+        };
+
+        let blocked_attempts =
+            match db.get_tool_egress_blocks(&tool.tool_router_key().to_string_without_version(), 100) {
+                Ok(blocks) => blocks,
+                Err(err) => {
+                    let _ = res.send(Err(APIError::from(err.to_string()))).await;
+                    return Ok(());
+                }
+            };
+
+        let mut response = Self::tool_network_access(&db, &tool);
+        response["blocked_attempts"] = json!(blocked_attempts);
+        let _ = res.send(Ok(response)).await;
+        Ok(())
+    }
+
     pub async fn v2_api_enable_all_tools(
         db: Arc<SqliteManager>,
         bearer: String,
@@ -3261,6 +3695,8 @@ impl Node {
                         runner: new_tool.get_runner(),
                         operating_system: new_tool.get_operating_system(),
                         tool_set: new_tool.get_tool_set(),
+                        test_cases: new_tool.get_test_cases(),
+                        network_allowlist: new_tool.get_network_allowlist(),
                     },
                     tool_router_key: Some(new_tool.tool_router_key().to_string_without_version()),
                     job_id: Self::create_job_for_duplicate_tool(
@@ -3330,6 +3766,8 @@ impl Node {
     pub async fn process_tool_zip(
         db: Arc<SqliteManager>,
         node_env: NodeEnvironment,
+        node_name: String,
+        signing_secret_key: SigningKey,
         zip_data: Vec<u8>,
     ) -> Result<Value, APIError> {
         // Create a cursor from the zip data
@@ -3380,6 +3818,17 @@ impl Node {
             }
         };
 
+        let mut installing = HashSet::from([tool.tool_router_key().to_string_without_version()]);
+        let dependencies = Self::install_tool_dependencies(
+            db.clone(),
+            node_env.clone(),
+            node_name,
+            signing_secret_key,
+            &tool,
+            &mut installing,
+        )
+        .await?;
+
         // Save the tool to the database
         match db.add_tool(tool).await {
             Ok(tool) => {
@@ -3435,7 +3884,10 @@ impl Node {
                     "status": "success",
                     "message": "Tool imported successfully",
                     "tool_key": tool.tool_router_key().to_string_without_version(),
-                    "tool": tool
+                    "network_access": Self::tool_network_access(&db, &tool),
+                    "wasm_capabilities": Self::wasm_tool_capabilities(&db, &tool),
+                    "tool": tool,
+                    "installed_dependencies": dependencies
                 }))
             }
             Err(err) => Err(APIError {
@@ -3450,6 +3902,8 @@ impl Node {
         db: Arc<SqliteManager>,
         bearer: String,
         node_env: NodeEnvironment,
+        node_name: String,
+        signing_secret_key: SigningKey,
         file_data: Vec<u8>,
         res: Sender<Result<Value, APIError>>,
     ) -> Result<(), NodeError> {
@@ -3458,7 +3912,7 @@ impl Node {
             return Ok(());
         }
 
-        let result = Self::process_tool_zip(db, node_env, file_data).await;
+        let result = Self::process_tool_zip(db, node_env, node_name, signing_secret_key, file_data).await;
         let _ = res.send(result).await;
         Ok(())
     }
@@ -3912,7 +4366,7 @@ LANGUAGE={env_language}
         res: Sender<Result<Value, APIError>>,
     ) -> Result<(), NodeError> {
         // Validate the bearer token
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+        if Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::ToolsRead).await.is_err() {
             return Ok(());
         }
 
@@ -4081,6 +4535,192 @@ LANGUAGE={env_language}
         Ok(())
     }
 
+    pub async fn v2_api_list_tool_revisions(
+        db: Arc<SqliteManager>,
+        bearer: String,
+        tool_router_key: String,
+        res: Sender<Result<Value, APIError>>,
+    ) -> Result<(), NodeError> {
+        if Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::ToolsRead).await.is_err() {
+            return Ok(());
+        }
+
+        match db.get_tool_revisions(&tool_router_key) {
+            Ok(revisions) if revisions.is_empty() => {
+                let _ = res
+                    .send(Err(APIError {
+                        code: 404,
+                        error: "Tool not found".to_string(),
+                        message: format!("No revisions found for tool '{}'", tool_router_key),
+                    }))
+                    .await;
+            }
+            Ok(revisions) => {
+                let _ = res.send(Ok(json!(revisions))).await;
+            }
+            Err(e) => {
+                let _ = res
+                    .send(Err(APIError {
+                        code: 500,
+                        error: "Internal Server Error".to_string(),
+                        message: format!("Failed to list tool revisions: {}", e),
+                    }))
+                    .await;
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn v2_api_diff_tool_revisions(
+        db: Arc<SqliteManager>,
+        bearer: String,
+        tool_router_key: String,
+        from_revision: u64,
+        to_revision: u64,
+        res: Sender<Result<Value, APIError>>,
+    ) -> Result<(), NodeError> {
+        if Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::ToolsRead).await.is_err() {
+            return Ok(());
+        }
+
+        match db.diff_tool_revisions(&tool_router_key, from_revision, to_revision) {
+            Ok(diff) => {
+                let _ = res.send(Ok(json!(diff))).await;
+            }
+            Err(e) => {
+                let _ = res.send(Err(Self::tool_revision_error(e))).await;
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn v2_api_rollback_tool_revision(
+        db: Arc<SqliteManager>,
+        bearer: String,
+        tool_router_key: String,
+        revision: u64,
+        res: Sender<Result<Value, APIError>>,
+    ) -> Result<(), NodeError> {
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        match db.rollback_tool_to_revision(&tool_router_key, revision).await {
+            Ok(tool) => {
+                let current_revision = db
+                    .get_tool_revisions(&tool_router_key)
+                    .ok()
+                    .and_then(|revisions| revisions.first().map(|r| r.revision));
+                let response = json!({
+                    "tool_router_key": tool_router_key,
+                    "restored_revision": revision,
+                    "current_revision": current_revision,
+                    "version": tool.version(),
+                    "success": true
+                });
+                let _ = res.send(Ok(response)).await;
+            }
+            Err(e) => {
+                let _ = res.send(Err(Self::tool_revision_error(e))).await;
+            }
+        }
+        Ok(())
+    }
+
+    fn tool_revision_error(e: SqliteManagerError) -> APIError {
+        match e {
+            SqliteManagerError::ToolRevisionNotFound(message) => APIError {
+                code: 404,
+                error: "Tool revision not found".to_string(),
+                message,
+            },
+            e => APIError {
+                code: 500,
+                error: "Internal Server Error".to_string(),
+                message: format!("Tool revision operation failed: {}", e),
+            },
+        }
+    }
+
+    pub async fn v2_api_run_tool_tests(
+        bearer: String,
+        node_name: ShinkaiName,
+        db: Arc<SqliteManager>,
+        tool_router_key: Option<String>,
+        skip_network_tests: bool,
+        llm_provider: String,
+        identity_manager: Arc<Mutex<IdentityManager>>,
+        job_manager: Arc<Mutex<JobManager>>,
+        encryption_secret_key: EncryptionStaticKey,
+        encryption_public_key: EncryptionPublicKey,
+        signing_secret_key: SigningKey,
+        res: Sender<Result<Value, APIError>>,
+    ) -> Result<(), NodeError> {
+        if Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::ToolsExecute).await.is_err() {
+            return Ok(());
+        }
+
+        let tool_router_keys = match tool_router_key {
+            Some(tool_router_key) => {
+                if let Err(e) = db.get_tool_by_key(&tool_router_key) {
+                    let api_error = APIError {
+                        code: StatusCode::NOT_FOUND.as_u16(),
+                        error: "Not Found".to_string(),
+                        message: format!("Tool not found: {}", e),
+                    };
+                    let _ = res.send(Err(api_error)).await;
+                    return Ok(());
+                }
+                vec![tool_router_key]
+            }
+            None => match get_tools_with_test_cases(db.clone()) {
+                Ok(keys) => keys,
+                Err(e) => {
+                    let api_error = APIError {
+                        code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                        error: "Internal Server Error".to_string(),
+                        message: format!("Failed to list tools with test cases: {}", e),
+                    };
+                    let _ = res.send(Err(api_error)).await;
+                    return Ok(());
+                }
+            },
+        };
+
+        let mut reports = Vec::new();
+        for tool_router_key in tool_router_keys {
+            match run_tool_test_cases(
+                bearer.clone(),
+                node_name.clone(),
+                db.clone(),
+                tool_router_key.clone(),
+                skip_network_tests,
+                llm_provider.clone(),
+                identity_manager.clone(),
+                job_manager.clone(),
+                encryption_secret_key.clone(),
+                encryption_public_key,
+                signing_secret_key.clone(),
+            )
+            .await
+            {
+                Ok(report) => reports.push(report),
+                Err(e) => {
+                    let api_error = APIError {
+                        code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                        error: "Internal Server Error".to_string(),
+                        message: format!("Failed to run tests for {}: {}", tool_router_key, e),
+                    };
+                    let _ = res.send(Err(api_error)).await;
+                    return Ok(());
+                }
+            }
+        }
+
+        let _ = res.send(Ok(json!(reports))).await;
+        Ok(())
+    }
+
     pub async fn v2_api_copy_tool_assets(
         db: Arc<SqliteManager>,
         bearer: String,
@@ -4312,6 +4952,8 @@ LANGUAGE={env_language}
                     runner: RunnerType::Any,
                     operating_system: vec![],
                     tool_set: None,
+                    test_cases: None,
+                    network_allowlist: None,
                 };
                 tool.check_code(code.clone(), support_files).await
             }
@@ -9,8 +9,9 @@ use serde_json::Value;
 use shinkai_embedding::embedding_generator::EmbeddingGenerator;
 use shinkai_fs::{shinkai_file_manager::{FileProcessingMode, ShinkaiFileManager}, shinkai_fs_error::ShinkaiFsError};
 use shinkai_http_api::node_api_router::APIError;
+use shinkai_message_primitives::schemas::api_key::ApiKeyScope;
 use shinkai_message_primitives::{
-    schemas::shinkai_fs::ShinkaiFileChunkCollection,
+    schemas::{inbox_name::InboxName, shinkai_fs::ShinkaiFileChunkCollection},
     shinkai_message::shinkai_message_schemas::{
         APIVecFsCopyFolder, APIVecFsCopyItem, APIVecFsCreateFolder, APIVecFsDeleteFolder, APIVecFsDeleteItem,
         APIVecFsMoveFolder, APIVecFsMoveItem, APIVecFsRetrievePathSimplifiedJson, APIVecFsRetrieveSourceFile,
@@ -22,6 +23,7 @@ use shinkai_sqlite::SqliteManager;
 use tokio::sync::Mutex;
 
 use crate::{
+    llm_provider::llm_stopper::LLMStopper,
     managers::IdentityManager,
     network::{node_error::NodeError, Node},
 };
@@ -35,7 +37,7 @@ impl Node {
         res: Sender<Result<Value, APIError>>,
     ) -> Result<(), NodeError> {
         // Validate the bearer token
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+        if Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::VecFsRead).await.is_err() {
             return Ok(());
         }
 
@@ -72,7 +74,7 @@ impl Node {
         res: Sender<Result<String, APIError>>,
     ) -> Result<(), NodeError> {
         // Validate the bearer token
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+        if Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::VecFsWrite).await.is_err() {
             return Ok(());
         }
 
@@ -136,7 +138,7 @@ impl Node {
         res: Sender<Result<String, APIError>>,
     ) -> Result<(), NodeError> {
         // Validate the bearer token
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+        if Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::VecFsWrite).await.is_err() {
             return Ok(());
         }
 
@@ -190,7 +192,7 @@ impl Node {
         res: Sender<Result<String, APIError>>,
     ) -> Result<(), NodeError> {
         // Validate the bearer token
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+        if Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::VecFsWrite).await.is_err() {
             return Ok(());
         }
 
@@ -234,7 +236,7 @@ impl Node {
         bearer: String,
         res: Sender<Result<String, APIError>>,
     ) -> Result<(), NodeError> {
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+        if Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::VecFsWrite).await.is_err() {
             return Ok(());
         }
 
@@ -287,7 +289,7 @@ impl Node {
         bearer: String,
         res: Sender<Result<String, APIError>>,
     ) -> Result<(), NodeError> {
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+        if Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::VecFsWrite).await.is_err() {
             return Ok(());
         }
 
@@ -373,7 +375,7 @@ impl Node {
         bearer: String,
         res: Sender<Result<String, APIError>>,
     ) -> Result<(), NodeError> {
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+        if Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::VecFsWrite).await.is_err() {
             return Ok(());
         }
 
@@ -415,7 +417,7 @@ impl Node {
         bearer: String,
         res: Sender<Result<String, APIError>>,
     ) -> Result<(), NodeError> {
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+        if Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::VecFsWrite).await.is_err() {
             return Ok(());
         }
 
@@ -470,7 +472,7 @@ impl Node {
         res: Sender<Result<Value, APIError>>,
     ) -> Result<(), NodeError> {
         // Validate the bearer token
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+        if Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::VecFsRead).await.is_err() {
             return Ok(());
         }
 
@@ -582,7 +584,7 @@ impl Node {
         res: Sender<Result<Value, APIError>>,
     ) -> Result<(), NodeError> {
         // Validate the bearer token
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+        if Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::VecFsRead).await.is_err() {
             return Ok(());
         }
 
@@ -672,7 +674,7 @@ impl Node {
         res: Sender<Result<Value, APIError>>,
     ) -> Result<(), NodeError> {
         // Validate the bearer token
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+        if Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::VecFsWrite).await.is_err() {
             return Ok(());
         }
 
@@ -719,7 +721,7 @@ impl Node {
         res: Sender<Result<String, APIError>>,
     ) -> Result<(), NodeError> {
         // Validate the bearer token
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+        if Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::VecFsRead).await.is_err() {
             return Ok(());
         }
 
@@ -752,6 +754,7 @@ impl Node {
         db: Arc<SqliteManager>,
         _identity_manager: Arc<Mutex<IdentityManager>>,
         embedding_generator: Arc<dyn EmbeddingGenerator>,
+        llm_stopper: Arc<LLMStopper>,
         bearer: String,
         job_id: String,
         filename: String,
@@ -760,21 +763,37 @@ impl Node {
         res: Sender<Result<Value, APIError>>,
     ) -> Result<(), NodeError> {
         // Validate the bearer token
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+        if Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::VecFsWrite).await.is_err() {
             return Ok(());
         }
 
-        // Save and process the file with the job ID
-        match ShinkaiFileManager::save_and_process_file_with_jobid(
+        // Stopping the job drops the embeddings still being generated, the file is kept without them
+        let stop_key = InboxName::get_job_inbox_name_from_params(job_id.clone())
+            .map(|inbox_name| inbox_name.get_value())
+            .unwrap_or_default();
+        let cancellation_token = llm_stopper.task_token(&stop_key);
+        let processing = ShinkaiFileManager::save_and_process_file_with_jobid(
             &job_id,
             filename.clone(),
             file,
             &db,
             FileProcessingMode::Auto,
             &*embedding_generator,
-        )
-        .await
-        {
+        );
+        let result = tokio::select! {
+            biased;
+            _ = cancellation_token.cancelled() => {
+                let api_error = APIError {
+                    code: StatusCode::CONFLICT.as_u16(),
+                    error: "Cancelled".to_string(),
+                    message: format!("Job {} was stopped while {} was being processed", job_id, filename),
+                };
+                let _ = res.send(Err(api_error)).await;
+                return Ok(());
+            }
+            result = processing => result,
+        };
+        match result {
             Ok(response) => {
                 let success_message = format!(
                     "File uploaded and processed successfully for job {}: {}",
@@ -807,7 +826,7 @@ impl Node {
         res: Sender<Result<Value, APIError>>,
     ) -> Result<(), NodeError> {
         // Validate the bearer token
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+        if Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::VecFsRead).await.is_err() {
             return Ok(());
         }
 
@@ -848,7 +867,7 @@ impl Node {
         res: Sender<Result<Value, APIError>>,
     ) -> Result<(), NodeError> {
         // Validate the bearer token
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+        if Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::VecFsRead).await.is_err() {
             return Ok(());
         }
 
@@ -883,7 +902,7 @@ impl Node {
         res: Sender<Result<Value, APIError>>,
     ) -> Result<(), NodeError> {
         // Validate the bearer token
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+        if Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::VecFsRead).await.is_err() {
             return Ok(());
         }
 
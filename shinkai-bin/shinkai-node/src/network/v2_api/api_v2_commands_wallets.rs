@@ -5,6 +5,7 @@ use reqwest::StatusCode;
 use serde_json::{json, Value};
 
 use shinkai_http_api::node_api_router::APIError;
+use shinkai_message_primitives::schemas::api_key::ApiKeyScope;
 use shinkai_message_primitives::schemas::{
     coinbase_mpc_config::CoinbaseMPCWalletConfig,
     shinkai_name::ShinkaiName,
@@ -385,7 +386,7 @@ impl Node {
         res: Sender<Result<Value, APIError>>,
     ) -> Result<(), NodeError> {
         // Validate the bearer token
-        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+        if Self::validate_bearer_scope(&bearer, db.clone(), &res, ApiKeyScope::WalletRead).await.is_err() {
             return Ok(());
         }
 
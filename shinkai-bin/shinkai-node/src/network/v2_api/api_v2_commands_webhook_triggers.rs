@@ -0,0 +1,517 @@
+use std::num::NonZeroU32;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use async_channel::Sender;
+use chrono::Utc;
+use dashmap::DashMap;
+use ed25519_dalek::SigningKey;
+use governor::{DefaultDirectRateLimiter, Quota, RateLimiter};
+use rand::Rng;
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use shinkai_http_api::node_api_router::APIError;
+use shinkai_message_primitives::schemas::inbox_name::InboxName;
+use shinkai_message_primitives::schemas::shinkai_name::ShinkaiName;
+use shinkai_message_primitives::schemas::webhook_trigger::{
+    AddWebhookTriggerRequest, UpdateWebhookTriggerRequest, WebhookTrigger, WebhookTriggerMode, WebhookTriggerRun,
+    WEBHOOK_TRIGGER_DEFAULT_RATE_LIMIT,
+};
+use shinkai_message_primitives::shinkai_message::shinkai_message_schemas::JobCreationInfo;
+use shinkai_message_primitives::shinkai_utils::job_scope::MinimalJobScope;
+use shinkai_message_primitives::shinkai_utils::secret_vault::REDACTED_SECRET;
+use shinkai_sqlite::{errors::SqliteManagerError, SqliteManager};
+use tokio::{sync::Mutex, time::Instant};
+use x25519_dalek::{PublicKey as EncryptionPublicKey, StaticSecret as EncryptionStaticKey};
+
+use crate::{
+    llm_provider::job_manager::JobManager,
+    managers::IdentityManager,
+    network::{node_error::NodeError, webhook_dispatcher::verify_webhook_signature, Node},
+    tools::tool_generation::v2_send_basic_job_message_for_existing_job,
+};
+
+/// How long a synchronous invocation waits for the agent
+const WEBHOOK_TRIGGER_SYNC_TIMEOUT: Duration = Duration::from_secs(60 * 5);
+/// Accepted clock difference for signed invocations, in seconds
+const WEBHOOK_TRIGGER_SIGNATURE_TOLERANCE: i64 = 5 * 60;
+
+/// Limiter of each trigger along with the rate it was built for
+static WEBHOOK_TRIGGER_LIMITERS: OnceLock<DashMap<String, (u32, DefaultDirectRateLimiter)>> = OnceLock::new();
+
+fn trigger_error(code: StatusCode, message: String) -> APIError {
+    APIError {
+        code: code.as_u16(),
+        error: code.canonical_reason().unwrap_or_default().to_string(),
+        message,
+    }
+}
+
+fn redacted(mut trigger: WebhookTrigger) -> WebhookTrigger {
+    trigger.secret = REDACTED_SECRET.to_string();
+    trigger
+}
+
+fn secrets_match(expected: &str, given: &str) -> bool {
+    expected.len() == given.len()
+        && expected
+            .bytes()
+            .zip(given.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Takes one request from the trigger's budget, false once it is spent for the current minute
+fn check_trigger_rate_limit(trigger: &WebhookTrigger) -> bool {
+    let rate = NonZeroU32::new(trigger.rate_limit_per_minute).unwrap_or(NonZeroU32::MIN);
+    let limiters = WEBHOOK_TRIGGER_LIMITERS.get_or_init(DashMap::new);
+    let mut entry = limiters
+        .entry(trigger.id.clone())
+        .or_insert_with(|| (rate.get(), RateLimiter::direct(Quota::per_minute(rate))));
+    // The rate was changed since the limiter was built
+    if entry.0 != rate.get() {
+        *entry = (rate.get(), RateLimiter::direct(Quota::per_minute(rate)));
+    }
+    entry.1.check().is_ok()
+}
+
+impl Node {
+    fn validate_webhook_trigger(db: &SqliteManager, trigger: &WebhookTrigger) -> Result<(), APIError> {
+        if trigger.rate_limit_per_minute == 0 {
+            return Err(trigger_error(
+                StatusCode::BAD_REQUEST,
+                "The rate limit must be at least one request per minute".to_string(),
+            ));
+        }
+        let is_agent = db.get_agent(&trigger.agent_id).ok().flatten().is_some();
+        let is_llm_provider = db
+            .get_all_llm_providers()
+            .map(|llm_providers| {
+                llm_providers
+                    .iter()
+                    .any(|llm_provider| llm_provider.id == trigger.agent_id)
+            })
+            .unwrap_or(false);
+        if !is_agent && !is_llm_provider {
+            return Err(trigger_error(
+                StatusCode::BAD_REQUEST,
+                format!("Agent not found: {}", trigger.agent_id),
+            ));
+        }
+        Ok(())
+    }
+
+    pub async fn v2_api_add_webhook_trigger(
+        db: Arc<SqliteManager>,
+        bearer: String,
+        payload: AddWebhookTriggerRequest,
+        res: Sender<Result<WebhookTrigger, APIError>>,
+    ) -> Result<(), NodeError> {
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        let trigger = WebhookTrigger {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: payload.name,
+            agent_id: payload.agent_id,
+            prompt_template: payload.prompt_template,
+            secret: payload
+                .secret
+                .filter(|secret| !secret.is_empty())
+                .unwrap_or_else(|| format!("whsec_{}", hex::encode(rand::thread_rng().gen::<[u8; 32]>()))),
+            rate_limit_per_minute: payload
+                .rate_limit_per_minute
+                .unwrap_or(WEBHOOK_TRIGGER_DEFAULT_RATE_LIMIT),
+            mode: payload.mode,
+            enabled: true,
+            created_at: Utc::now(),
+        };
+        if let Err(api_error) = Self::validate_webhook_trigger(&db, &trigger) {
+            let _ = res.send(Err(api_error)).await;
+            return Ok(());
+        }
+
+        match db.add_webhook_trigger(&trigger) {
+            Ok(()) => {
+                let _ = res.send(Ok(trigger)).await;
+            }
+            Err(e) => {
+                let _ = res
+                    .send(Err(trigger_error(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        format!("Failed to add webhook trigger: {}", e),
+                    )))
+                    .await;
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn v2_api_list_webhook_triggers(
+        db: Arc<SqliteManager>,
+        bearer: String,
+        res: Sender<Result<Vec<WebhookTrigger>, APIError>>,
+    ) -> Result<(), NodeError> {
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        match db.get_all_webhook_triggers() {
+            Ok(triggers) => {
+                let _ = res.send(Ok(triggers.into_iter().map(redacted).collect())).await;
+            }
+            Err(e) => {
+                let _ = res
+                    .send(Err(trigger_error(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        format!("Failed to list webhook triggers: {}", e),
+                    )))
+                    .await;
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn v2_api_update_webhook_trigger(
+        db: Arc<SqliteManager>,
+        bearer: String,
+        payload: UpdateWebhookTriggerRequest,
+        res: Sender<Result<WebhookTrigger, APIError>>,
+    ) -> Result<(), NodeError> {
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        let mut trigger = match db.get_webhook_trigger(&payload.id) {
+            Ok(Some(trigger)) => trigger,
+            Ok(None) => {
+                let _ = res
+                    .send(Err(trigger_error(
+                        StatusCode::NOT_FOUND,
+                        format!("Webhook trigger not found: {}", payload.id),
+                    )))
+                    .await;
+                return Ok(());
+            }
+            Err(e) => {
+                let _ = res
+                    .send(Err(trigger_error(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        format!("Failed to read webhook trigger: {}", e),
+                    )))
+                    .await;
+                return Ok(());
+            }
+        };
+
+        if let Some(name) = payload.name {
+            trigger.name = name;
+        }
+        if let Some(agent_id) = payload.agent_id {
+            trigger.agent_id = agent_id;
+        }
+        if let Some(prompt_template) = payload.prompt_template {
+            trigger.prompt_template = prompt_template;
+        }
+        if let Some(secret) = payload.secret.filter(|secret| !secret.is_empty()) {
+            trigger.secret = secret;
+        }
+        if let Some(rate_limit_per_minute) = payload.rate_limit_per_minute {
+            trigger.rate_limit_per_minute = rate_limit_per_minute;
+        }
+        if let Some(mode) = payload.mode {
+            trigger.mode = mode;
+        }
+        if let Some(enabled) = payload.enabled {
+            trigger.enabled = enabled;
+        }
+        if let Err(api_error) = Self::validate_webhook_trigger(&db, &trigger) {
+            let _ = res.send(Err(api_error)).await;
+            return Ok(());
+        }
+
+        match db.update_webhook_trigger(&trigger) {
+            Ok(()) => {
+                let _ = res.send(Ok(redacted(trigger))).await;
+            }
+            Err(e) => {
+                let _ = res
+                    .send(Err(trigger_error(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        format!("Failed to update webhook trigger: {}", e),
+                    )))
+                    .await;
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn v2_api_remove_webhook_trigger(
+        db: Arc<SqliteManager>,
+        bearer: String,
+        id: String,
+        res: Sender<Result<Value, APIError>>,
+    ) -> Result<(), NodeError> {
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        match db.remove_webhook_trigger(&id) {
+            Ok(()) => {
+                if let Some(limiters) = WEBHOOK_TRIGGER_LIMITERS.get() {
+                    limiters.remove(&id);
+                }
+                let _ = res.send(Ok(json!({ "id": id, "removed": true }))).await;
+            }
+            Err(SqliteManagerError::DataNotFound) => {
+                let _ = res
+                    .send(Err(trigger_error(
+                        StatusCode::NOT_FOUND,
+                        format!("Webhook trigger not found: {}", id),
+                    )))
+                    .await;
+            }
+            Err(e) => {
+                let _ = res
+                    .send(Err(trigger_error(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        format!("Failed to remove webhook trigger: {}", e),
+                    )))
+                    .await;
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the agent of a trigger on a posted payload. The caller proves it knows the trigger
+    /// secret either as the bearer token or by signing the body like outbound webhooks do.
+    pub async fn v2_api_invoke_webhook_trigger(
+        db: Arc<SqliteManager>,
+        node_name: ShinkaiName,
+        identity_manager: Arc<Mutex<IdentityManager>>,
+        job_manager: Arc<Mutex<JobManager>>,
+        id: String,
+        authorization: Option<String>,
+        signature: Option<String>,
+        body: String,
+        sync: Option<bool>,
+        node_encryption_sk: EncryptionStaticKey,
+        node_encryption_pk: EncryptionPublicKey,
+        node_signing_sk: SigningKey,
+        res: Sender<Result<WebhookTriggerRun, APIError>>,
+    ) -> Result<(), NodeError> {
+        // Unknown and disabled triggers look the same to callers
+        let trigger = match db.get_webhook_trigger(&id) {
+            Ok(Some(trigger)) if trigger.enabled => trigger,
+            Ok(_) => {
+                let _ = res
+                    .send(Err(trigger_error(
+                        StatusCode::NOT_FOUND,
+                        format!("Webhook trigger not found: {}", id),
+                    )))
+                    .await;
+                return Ok(());
+            }
+            Err(e) => {
+                let _ = res
+                    .send(Err(trigger_error(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        format!("Failed to read webhook trigger: {}", e),
+                    )))
+                    .await;
+                return Ok(());
+            }
+        };
+
+        let bearer_matches = authorization
+            .as_deref()
+            .and_then(|authorization| authorization.strip_prefix("Bearer "))
+            .is_some_and(|secret| secrets_match(&trigger.secret, secret));
+        let signature_matches = signature.as_deref().is_some_and(|signature| {
+            verify_webhook_signature(&trigger.secret, signature, &body, WEBHOOK_TRIGGER_SIGNATURE_TOLERANCE)
+        });
+        if !bearer_matches && !signature_matches {
+            let _ = res
+                .send(Err(trigger_error(
+                    StatusCode::UNAUTHORIZED,
+                    "Invalid webhook trigger secret or signature".to_string(),
+                )))
+                .await;
+            return Ok(());
+        }
+
+        if !check_trigger_rate_limit(&trigger) {
+            let _ = res
+                .send(Err(trigger_error(
+                    StatusCode::TOO_MANY_REQUESTS,
+                    format!(
+                        "The trigger accepts {} requests per minute",
+                        trigger.rate_limit_per_minute
+                    ),
+                )))
+                .await;
+            return Ok(());
+        }
+
+        let payload = if body.trim().is_empty() {
+            Value::Null
+        } else {
+            match serde_json::from_str::<Value>(&body) {
+                Ok(payload) => payload,
+                Err(e) => {
+                    let _ = res
+                        .send(Err(trigger_error(
+                            StatusCode::BAD_REQUEST,
+                            format!("The payload is not valid JSON: {}", e),
+                        )))
+                        .await;
+                    return Ok(());
+                }
+            }
+        };
+
+        // The trigger secret was checked above, the job itself runs as the node
+        let bearer = match Self::get_bearer_token(db.clone(), &res).await {
+            Ok(bearer) => bearer,
+            Err(_) => return Ok(()),
+        };
+
+        let (job_sender, job_receiver) = async_channel::bounded(1);
+        let _ = Node::v2_create_new_job(
+            db.clone(),
+            node_name.clone(),
+            identity_manager.clone(),
+            job_manager.clone(),
+            bearer.clone(),
+            JobCreationInfo {
+                scope: MinimalJobScope::default(),
+                is_hidden: Some(true),
+                associated_ui: None,
+            },
+            trigger.agent_id.clone(),
+            node_encryption_sk.clone(),
+            node_encryption_pk,
+            node_signing_sk.clone(),
+            job_sender,
+        )
+        .await;
+        let job_id = match job_receiver.recv().await {
+            Ok(Ok(job_id)) => job_id,
+            Ok(Err(api_error)) => {
+                let _ = res.send(Err(api_error)).await;
+                return Ok(());
+            }
+            Err(e) => {
+                let _ = res.send(Err(Self::generic_api_error(&e.to_string()))).await;
+                return Ok(());
+            }
+        };
+
+        if let Err(api_error) = v2_send_basic_job_message_for_existing_job(
+            bearer.clone(),
+            job_id.clone(),
+            trigger.render(&payload),
+            None,
+            None,
+            None,
+            db.clone(),
+            node_name,
+            identity_manager,
+            job_manager,
+            node_encryption_sk,
+            node_encryption_pk,
+            node_signing_sk,
+        )
+        .await
+        {
+            let _ = res.send(Err(api_error)).await;
+            return Ok(());
+        }
+
+        let wait = sync.unwrap_or(trigger.mode == WebhookTriggerMode::Sync);
+        if !wait {
+            let _ = res.send(Ok(WebhookTriggerRun { job_id, answer: None })).await;
+            return Ok(());
+        }
+
+        let inbox_name = match InboxName::get_job_inbox_name_from_params(job_id.clone()) {
+            Ok(inbox_name) => inbox_name.to_string(),
+            Err(e) => {
+                let _ = res.send(Err(Self::generic_api_error(&e.to_string()))).await;
+                return Ok(());
+            }
+        };
+
+        // The prompt and the answer
+        let expected_messages = 2;
+        let start_time = Instant::now();
+        loop {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            match Self::job_answer(db.clone(), bearer.clone(), inbox_name.clone(), expected_messages).await {
+                Ok(Some(answer)) => {
+                    let _ = res
+                        .send(Ok(WebhookTriggerRun {
+                            job_id,
+                            answer: Some(answer),
+                        }))
+                        .await;
+                    return Ok(());
+                }
+                Ok(None) => {}
+                Err(api_error) => {
+                    let _ = res.send(Err(api_error)).await;
+                    return Ok(());
+                }
+            }
+
+            if start_time.elapsed() >= WEBHOOK_TRIGGER_SYNC_TIMEOUT {
+                let _ = res
+                    .send(Err(trigger_error(
+                        StatusCode::GATEWAY_TIMEOUT,
+                        format!("Timed out waiting for the answer of job {}", job_id),
+                    )))
+                    .await;
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_trigger(id: &str, rate_limit_per_minute: u32) -> WebhookTrigger {
+        WebhookTrigger {
+            id: id.to_string(),
+            name: id.to_string(),
+            agent_id: "agent".to_string(),
+            prompt_template: "{{payload}}".to_string(),
+            secret: "secret".to_string(),
+            rate_limit_per_minute,
+            mode: WebhookTriggerMode::Async,
+            enabled: true,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_trigger_rate_limit_follows_rate_changes() {
+        let trigger = test_trigger("rate_limited", 2);
+        assert!(check_trigger_rate_limit(&trigger));
+        assert!(check_trigger_rate_limit(&trigger));
+        assert!(!check_trigger_rate_limit(&trigger));
+
+        // Other triggers have their own budget
+        assert!(check_trigger_rate_limit(&test_trigger("other", 1)));
+
+        // Raising the rate starts a new budget
+        let trigger = test_trigger("rate_limited", 3);
+        assert!(check_trigger_rate_limit(&trigger));
+
+        assert!(secrets_match("secret", "secret"));
+        assert!(!secrets_match("secret", "secreT"));
+        assert!(!secrets_match("secret", "secret2"));
+    }
+}
@@ -0,0 +1,254 @@
+use std::sync::Arc;
+
+use async_channel::Sender;
+use chrono::Utc;
+use rand::Rng;
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use shinkai_http_api::node_api_router::APIError;
+use shinkai_message_primitives::schemas::webhook::{
+    AddWebhookRequest, UpdateWebhookRequest, Webhook, WebhookDelivery, WebhookDeliveryFilter,
+};
+use shinkai_message_primitives::shinkai_utils::secret_vault::REDACTED_SECRET;
+use shinkai_sqlite::{errors::SqliteManagerError, SqliteManager};
+
+use crate::network::{node_error::NodeError, Node};
+
+fn bad_request(message: String) -> APIError {
+    APIError {
+        code: StatusCode::BAD_REQUEST.as_u16(),
+        error: "Bad Request".to_string(),
+        message,
+    }
+}
+
+fn not_found(message: String) -> APIError {
+    APIError {
+        code: StatusCode::NOT_FOUND.as_u16(),
+        error: "Not Found".to_string(),
+        message,
+    }
+}
+
+fn internal_error(message: String) -> APIError {
+    APIError {
+        code: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+        error: "Internal Server Error".to_string(),
+        message,
+    }
+}
+
+fn validate_webhook_url(url: &str) -> Result<(), APIError> {
+    match reqwest::Url::parse(url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => Ok(()),
+        Ok(_) => Err(bad_request(format!("Webhook URLs must use http or https: {}", url))),
+        Err(e) => Err(bad_request(format!("Invalid webhook URL {}: {}", url, e))),
+    }
+}
+
+fn redacted(mut webhook: Webhook) -> Webhook {
+    webhook.secret = REDACTED_SECRET.to_string();
+    webhook
+}
+
+impl Node {
+    pub async fn v2_api_add_webhook(
+        db: Arc<SqliteManager>,
+        bearer: String,
+        payload: AddWebhookRequest,
+        res: Sender<Result<Webhook, APIError>>,
+    ) -> Result<(), NodeError> {
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        if let Err(api_error) = validate_webhook_url(&payload.url) {
+            let _ = res.send(Err(api_error)).await;
+            return Ok(());
+        }
+
+        let webhook = Webhook {
+            id: uuid::Uuid::new_v4().to_string(),
+            url: payload.url,
+            events: payload.events,
+            secret: payload
+                .secret
+                .filter(|secret| !secret.is_empty())
+                .unwrap_or_else(|| format!("whsec_{}", hex::encode(rand::thread_rng().gen::<[u8; 32]>()))),
+            description: payload.description,
+            enabled: true,
+            created_at: Utc::now(),
+        };
+
+        match db.add_webhook(&webhook) {
+            Ok(()) => {
+                let _ = res.send(Ok(webhook)).await;
+            }
+            Err(e) => {
+                let _ = res
+                    .send(Err(internal_error(format!("Failed to add webhook: {}", e))))
+                    .await;
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn v2_api_list_webhooks(
+        db: Arc<SqliteManager>,
+        bearer: String,
+        res: Sender<Result<Vec<Webhook>, APIError>>,
+    ) -> Result<(), NodeError> {
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        match db.get_all_webhooks() {
+            Ok(webhooks) => {
+                let _ = res.send(Ok(webhooks.into_iter().map(redacted).collect())).await;
+            }
+            Err(e) => {
+                let _ = res
+                    .send(Err(internal_error(format!("Failed to list webhooks: {}", e))))
+                    .await;
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn v2_api_update_webhook(
+        db: Arc<SqliteManager>,
+        bearer: String,
+        payload: UpdateWebhookRequest,
+        res: Sender<Result<Webhook, APIError>>,
+    ) -> Result<(), NodeError> {
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        let mut webhook = match db.get_webhook(&payload.id) {
+            Ok(Some(webhook)) => webhook,
+            Ok(None) => {
+                let _ = res
+                    .send(Err(not_found(format!("Webhook not found: {}", payload.id))))
+                    .await;
+                return Ok(());
+            }
+            Err(e) => {
+                let _ = res
+                    .send(Err(internal_error(format!("Failed to read webhook: {}", e))))
+                    .await;
+                return Ok(());
+            }
+        };
+
+        if let Some(url) = payload.url {
+            if let Err(api_error) = validate_webhook_url(&url) {
+                let _ = res.send(Err(api_error)).await;
+                return Ok(());
+            }
+            webhook.url = url;
+        }
+        if let Some(events) = payload.events {
+            webhook.events = events;
+        }
+        if let Some(secret) = payload.secret.filter(|secret| !secret.is_empty()) {
+            webhook.secret = secret;
+        }
+        if let Some(description) = payload.description {
+            webhook.description = Some(description);
+        }
+        if let Some(enabled) = payload.enabled {
+            webhook.enabled = enabled;
+        }
+
+        match db.update_webhook(&webhook) {
+            Ok(()) => {
+                let _ = res.send(Ok(redacted(webhook))).await;
+            }
+            Err(e) => {
+                let _ = res
+                    .send(Err(internal_error(format!("Failed to update webhook: {}", e))))
+                    .await;
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn v2_api_remove_webhook(
+        db: Arc<SqliteManager>,
+        bearer: String,
+        id: String,
+        res: Sender<Result<Value, APIError>>,
+    ) -> Result<(), NodeError> {
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        match db.remove_webhook(&id) {
+            Ok(()) => {
+                let _ = res.send(Ok(json!({ "id": id, "removed": true }))).await;
+            }
+            Err(SqliteManagerError::DataNotFound) => {
+                let _ = res.send(Err(not_found(format!("Webhook not found: {}", id)))).await;
+            }
+            Err(e) => {
+                let _ = res
+                    .send(Err(internal_error(format!("Failed to remove webhook: {}", e))))
+                    .await;
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn v2_api_list_webhook_deliveries(
+        db: Arc<SqliteManager>,
+        bearer: String,
+        filter: WebhookDeliveryFilter,
+        res: Sender<Result<Vec<WebhookDelivery>, APIError>>,
+    ) -> Result<(), NodeError> {
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        match db.get_webhook_deliveries(&filter) {
+            Ok(deliveries) => {
+                let _ = res.send(Ok(deliveries)).await;
+            }
+            Err(e) => {
+                let _ = res
+                    .send(Err(internal_error(format!("Failed to list webhook deliveries: {}", e))))
+                    .await;
+            }
+        }
+        Ok(())
+    }
+
+    /// Sends a delivery again on the next poll, whether it was delivered or gave up
+    pub async fn v2_api_redeliver_webhook(
+        db: Arc<SqliteManager>,
+        bearer: String,
+        delivery_id: u64,
+        res: Sender<Result<WebhookDelivery, APIError>>,
+    ) -> Result<(), NodeError> {
+        if Self::validate_bearer_token(&bearer, db.clone(), &res).await.is_err() {
+            return Ok(());
+        }
+
+        match db.redeliver_webhook_delivery(delivery_id) {
+            Ok(delivery) => {
+                let _ = res.send(Ok(delivery)).await;
+            }
+            Err(SqliteManagerError::DataNotFound) => {
+                let _ = res
+                    .send(Err(not_found(format!("Webhook delivery not found: {}", delivery_id))))
+                    .await;
+            }
+            Err(e) => {
+                let _ = res
+                    .send(Err(internal_error(format!("Failed to redeliver webhook: {}", e))))
+                    .await;
+            }
+        }
+        Ok(())
+    }
+}
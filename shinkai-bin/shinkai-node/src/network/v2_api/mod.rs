@@ -1,11 +1,22 @@
 pub mod api_v2_commands;
+pub mod api_v2_commands_audit;
+pub mod api_v2_commands_backup;
 pub mod api_v2_commands_cron;
 pub mod api_v2_commands_ext_agent_offers;
+pub mod api_v2_commands_inbox_search;
+pub mod api_v2_commands_job_dead_letters;
+pub mod api_v2_commands_job_limits;
+pub mod api_v2_commands_job_pipelines;
 pub mod api_v2_commands_jobs;
 pub mod api_v2_commands_my_agent_offers;
 pub mod api_v2_commands_oauth;
 pub mod api_v2_commands_prompts;
+pub mod api_v2_commands_scheduled_job_messages;
+pub mod api_v2_commands_secrets;
 pub mod api_v2_commands_sheets;
 pub mod api_v2_commands_tools;
 pub mod api_v2_commands_vecfs;
 pub mod api_v2_commands_wallets;
+pub mod api_v2_commands_webhook_triggers;
+pub mod api_v2_commands_webhooks;
+pub mod api_v2_commands_openai;
@@ -0,0 +1,328 @@
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use chrono::Utc;
+use futures::future::join_all;
+use hmac::{Hmac, Mac};
+use serde_json::Value;
+use sha2::Sha256;
+use shinkai_message_primitives::schemas::webhook::{WebhookDelivery, WebhookEventType, WEBHOOK_SIGNATURE_HEADER};
+use shinkai_message_primitives::shinkai_utils::shinkai_logging::{shinkai_log, ShinkaiLogLevel, ShinkaiLogOption};
+use shinkai_sqlite::SqliteManager;
+use tokio::sync::broadcast;
+
+use super::node_error::NodeError;
+
+/// Deliveries sent per poll
+const WEBHOOK_BATCH_SIZE: u64 = 20;
+const WEBHOOK_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long a claimed delivery is hidden from other polls, well above the request timeout
+const WEBHOOK_CLAIM_LEASE: Duration = Duration::from_secs(60);
+/// Response bodies kept in the delivery log are cut to this length
+const WEBHOOK_MAX_ERROR_LENGTH: usize = 512;
+/// Events buffered for each in-process subscriber that hasn't received them yet
+const NODE_EVENT_CAPACITY: usize = 4096;
+
+static WEBHOOK_CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+static NODE_EVENTS: OnceLock<broadcast::Sender<(WebhookEventType, Value)>> = OnceLock::new();
+
+fn webhook_client() -> &'static reqwest::Client {
+    WEBHOOK_CLIENT.get_or_init(|| {
+        reqwest::Client::builder()
+            .timeout(WEBHOOK_REQUEST_TIMEOUT)
+            .build()
+            .unwrap_or_default()
+    })
+}
+
+fn node_events() -> &'static broadcast::Sender<(WebhookEventType, Value)> {
+    NODE_EVENTS.get_or_init(|| broadcast::channel(NODE_EVENT_CAPACITY).0)
+}
+
+/// Queues `event_type` for the subscribed webhooks and sends it to the subscribers inside the node.
+/// Failing to queue is logged and never fails the operation that produced the event.
+pub fn emit_webhook_event(db: &SqliteManager, event_type: WebhookEventType, payload: Value) {
+    if let Err(e) = db.enqueue_webhook_event(event_type, &payload) {
+        shinkai_log(
+            ShinkaiLogOption::Node,
+            ShinkaiLogLevel::Error,
+            &format!("Failed to queue the {} webhook event: {}", event_type, e),
+        );
+    }
+    // Fails only when nothing inside the node listens
+    let _ = node_events().send((event_type, payload));
+}
+
+/// Receives the events emitted from now on. Subscribe before starting what is waited on, so that
+/// its events can't be missed.
+pub fn subscribe_node_events() -> broadcast::Receiver<(WebhookEventType, Value)> {
+    node_events().subscribe()
+}
+
+/// Waits for the event that ends the processing of the message `message_id` of a job: `JobCompleted`,
+/// `JobFailed` or `JobCancelled`. `None` if the events stopped, or if the receiver lagged behind and
+/// may have missed it.
+pub async fn wait_for_job_outcome(
+    events: &mut broadcast::Receiver<(WebhookEventType, Value)>,
+    job_id: &str,
+    message_id: &str,
+) -> Option<(WebhookEventType, Value)> {
+    loop {
+        let (event_type, payload) = events.recv().await.ok()?;
+        let outcome = matches!(
+            event_type,
+            WebhookEventType::JobCompleted | WebhookEventType::JobFailed | WebhookEventType::JobCancelled
+        );
+        if outcome
+            && payload.get("job_id").and_then(Value::as_str) == Some(job_id)
+            && payload.get("message_id").and_then(Value::as_str) == Some(message_id)
+        {
+            return Some((event_type, payload));
+        }
+    }
+}
+
+/// Value of the signature header: `t=<timestamp>,v1=<hex HMAC-SHA256 of "<timestamp>.<body>">`
+pub fn sign_webhook_payload(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(format!("{}.{}", timestamp, body).as_bytes());
+    format!("t={},v1={}", timestamp, hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Checks a signature header made like `sign_webhook_payload`, rejecting timestamps more than
+/// `tolerance` seconds away from now so captured requests can't be replayed later
+pub fn verify_webhook_signature(secret: &str, header: &str, body: &str, tolerance: i64) -> bool {
+    let mut timestamp = None;
+    let mut signature = None;
+    for part in header.split(',') {
+        match part.trim().split_once('=') {
+            Some(("t", value)) => timestamp = value.parse::<i64>().ok(),
+            Some(("v1", value)) => signature = hex::decode(value).ok(),
+            _ => {}
+        }
+    }
+    let (Some(timestamp), Some(signature)) = (timestamp, signature) else {
+        return false;
+    };
+    if (Utc::now().timestamp() - timestamp).abs() > tolerance {
+        return false;
+    }
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(format!("{}.{}", timestamp, body).as_bytes());
+    mac.verify_slice(&signature).is_ok()
+}
+
+/// Sends the deliveries that are due and records the outcome of each one. Returns how many were
+/// delivered.
+pub async fn deliver_due_webhooks(db: Arc<SqliteManager>) -> Result<u64, NodeError> {
+    let deliveries = db
+        .claim_due_webhook_deliveries(WEBHOOK_BATCH_SIZE, WEBHOOK_CLAIM_LEASE)
+        .map_err(|e| NodeError::from(e.to_string()))?;
+    if deliveries.is_empty() {
+        return Ok(0);
+    }
+
+    let results = join_all(deliveries.into_iter().map(|delivery| {
+        let db = db.clone();
+        async move { deliver_webhook(&db, delivery).await }
+    }))
+    .await;
+
+    let mut delivered = 0;
+    for result in results {
+        match result {
+            Ok(true) => delivered += 1,
+            Ok(false) => {}
+            Err(e) => shinkai_log(
+                ShinkaiLogOption::Node,
+                ShinkaiLogLevel::Error,
+                &format!("Failed to record a webhook delivery: {}", e),
+            ),
+        }
+    }
+    Ok(delivered)
+}
+
+async fn deliver_webhook(db: &SqliteManager, delivery: WebhookDelivery) -> Result<bool, NodeError> {
+    let webhook = match db.get_webhook(&delivery.webhook_id) {
+        Ok(Some(webhook)) if webhook.enabled => webhook,
+        Ok(Some(_)) => return record_failure(db, &delivery, None, "The webhook is disabled".to_string()),
+        Ok(None) => return record_failure(db, &delivery, None, "The webhook no longer exists".to_string()),
+        Err(e) => return record_failure(db, &delivery, None, format!("Failed to read the webhook: {}", e)),
+    };
+
+    let body = delivery.body().to_string();
+    let signature = sign_webhook_payload(&webhook.secret, Utc::now().timestamp(), &body);
+    let response = webhook_client()
+        .post(&webhook.url)
+        .header("Content-Type", "application/json")
+        .header(WEBHOOK_SIGNATURE_HEADER, signature)
+        .header("X-Shinkai-Event", delivery.event_type.to_string())
+        .header("X-Shinkai-Delivery", delivery.id.to_string())
+        .body(body)
+        .send()
+        .await;
+
+    match response {
+        Ok(response) if response.status().is_success() => {
+            db.mark_webhook_delivery_delivered(delivery.id, response.status().as_u16())
+                .map_err(|e| NodeError::from(e.to_string()))?;
+            Ok(true)
+        }
+        Ok(response) => {
+            let status = response.status();
+            let mut error = response.text().await.unwrap_or_default();
+            if error.is_empty() {
+                error = status.to_string();
+            }
+            record_failure(db, &delivery, Some(status.as_u16()), error)
+        }
+        Err(e) => record_failure(db, &delivery, None, e.to_string()),
+    }
+}
+
+fn record_failure(
+    db: &SqliteManager,
+    delivery: &WebhookDelivery,
+    status_code: Option<u16>,
+    mut error: String,
+) -> Result<bool, NodeError> {
+    if error.len() > WEBHOOK_MAX_ERROR_LENGTH {
+        let mut end = WEBHOOK_MAX_ERROR_LENGTH;
+        while !error.is_char_boundary(end) {
+            end -= 1;
+        }
+        error.truncate(end);
+    }
+    let status = db
+        .mark_webhook_delivery_failed(delivery.id, status_code, &error)
+        .map_err(|e| NodeError::from(e.to_string()))?;
+    shinkai_log(
+        ShinkaiLogOption::Node,
+        ShinkaiLogLevel::Info,
+        &format!(
+            "Webhook delivery {} to {} failed ({}), now {}",
+            delivery.id, delivery.webhook_id, error, status
+        ),
+    );
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use shinkai_embedding::model_type::{EmbeddingModelType, OllamaTextEmbeddingsInference};
+    use shinkai_message_primitives::schemas::webhook::{Webhook, WebhookDeliveryFilter, WebhookDeliveryStatus};
+    use tokio::sync::mpsc;
+    use warp::Filter;
+
+    fn setup_test_db(dir: &tempfile::TempDir) -> Arc<SqliteManager> {
+        let model_type =
+            EmbeddingModelType::OllamaTextEmbeddingsInference(OllamaTextEmbeddingsInference::SnowflakeArcticEmbedM);
+        Arc::new(SqliteManager::new(dir.path().join("webhooks.db"), String::new(), model_type).unwrap())
+    }
+
+    fn test_webhook(id: &str, url: String) -> Webhook {
+        Webhook {
+            id: id.to_string(),
+            url,
+            events: vec![WebhookEventType::JobCompleted],
+            secret: format!("{}-secret", id),
+            description: None,
+            enabled: true,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_deliver_webhooks_to_local_receiver() {
+        // Accepts deliveries on /ok and rejects them on /fail
+        let (received_sender, mut received) = mpsc::unbounded_channel::<(String, String)>();
+        let ok_route = warp::path("ok")
+            .and(warp::post())
+            .and(warp::header::<String>(WEBHOOK_SIGNATURE_HEADER))
+            .and(warp::body::bytes())
+            .map(move |signature: String, body: warp::hyper::body::Bytes| {
+                let _ = received_sender.send((signature, String::from_utf8_lossy(&body).to_string()));
+                warp::reply()
+            });
+        let fail_route = warp::path("fail")
+            .and(warp::post())
+            .map(|| warp::reply::with_status("unavailable", warp::http::StatusCode::SERVICE_UNAVAILABLE));
+        let (address, server) = warp::serve(ok_route.or(fail_route)).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+
+        let dir = tempfile::tempdir().unwrap();
+        let db = setup_test_db(&dir);
+        db.add_webhook(&test_webhook("ok", format!("http://{}/ok", address)))
+            .unwrap();
+        db.add_webhook(&test_webhook("fail", format!("http://{}/fail", address)))
+            .unwrap();
+
+        emit_webhook_event(&db, WebhookEventType::JobCompleted, json!({ "job_id": "jobid_1" }));
+        // Not subscribed
+        emit_webhook_event(&db, WebhookEventType::ToolFailed, json!({ "tool_router_key": "tool" }));
+
+        assert_eq!(deliver_due_webhooks(db.clone()).await.unwrap(), 1);
+
+        let (signature, body) = received.recv().await.unwrap();
+        let timestamp: i64 = signature
+            .strip_prefix("t=")
+            .and_then(|rest| rest.split(',').next())
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert_eq!(signature, sign_webhook_payload("ok-secret", timestamp, &body));
+        let body: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["type"], "job_completed");
+        assert_eq!(body["data"]["job_id"], "jobid_1");
+
+        let deliveries = db.get_webhook_deliveries(&WebhookDeliveryFilter::default()).unwrap();
+        assert_eq!(deliveries.len(), 2);
+        let ok = deliveries.iter().find(|delivery| delivery.webhook_id == "ok").unwrap();
+        assert_eq!(ok.status, WebhookDeliveryStatus::Delivered);
+        assert_eq!(ok.last_status_code, Some(200));
+        let failed = deliveries
+            .iter()
+            .find(|delivery| delivery.webhook_id == "fail")
+            .unwrap();
+        assert_eq!(failed.status, WebhookDeliveryStatus::Pending);
+        assert_eq!(failed.attempts, 1);
+        assert_eq!(failed.last_status_code, Some(503));
+        assert_eq!(failed.last_error.as_deref(), Some("unavailable"));
+
+        // Retries wait for their backoff, a redelivery is sent on the next poll
+        assert_eq!(deliver_due_webhooks(db.clone()).await.unwrap(), 0);
+        db.update_webhook(&test_webhook("fail", format!("http://{}/ok", address)))
+            .unwrap();
+        db.redeliver_webhook_delivery(failed.id).unwrap();
+        assert_eq!(deliver_due_webhooks(db.clone()).await.unwrap(), 1);
+        let (_, body) = received.recv().await.unwrap();
+        assert!(body.contains(&format!("\"id\":{}", failed.id)));
+    }
+    #[tokio::test]
+    async fn test_wait_for_job_outcome() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = setup_test_db(&dir);
+        let mut events = subscribe_node_events();
+
+        // Events of other messages and jobs are skipped
+        for (event_type, job_id, message_id) in [
+            (WebhookEventType::JobCompleted, "job", "a"),
+            (WebhookEventType::InboxMessage, "job", "b"),
+            (WebhookEventType::JobFailed, "other", "b"),
+        ] {
+            emit_webhook_event(&db, event_type, json!({ "job_id": job_id, "message_id": message_id }));
+        }
+        emit_webhook_event(
+            &db,
+            WebhookEventType::JobFailed,
+            json!({ "job_id": "job", "message_id": "b", "error": "Rate limited" }),
+        );
+        let (event_type, payload) = wait_for_job_outcome(&mut events, "job", "b").await.unwrap();
+        assert_eq!(event_type, WebhookEventType::JobFailed);
+        assert_eq!(payload["error"], "Rate limited");
+    }
+}
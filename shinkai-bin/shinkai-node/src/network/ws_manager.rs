@@ -26,6 +26,7 @@ use std::fmt;
 use std::sync::Weak;
 use std::time::Duration;
 use std::{collections::HashMap, sync::Arc};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
 use tokio::sync::Mutex;
 use tokio::sync::RwLock;
 use tokio::time::sleep;
@@ -47,6 +48,43 @@ pub struct WebSocketManager {
     identity_manager_trait: Arc<Mutex<dyn IdentityManagerTrait + Send>>,
     encryption_secret_key: EncryptionStaticKey,
     message_queue: MessageQueue,
+    // Local listeners of the stream chunks of an inbox, fed as (content, is_done)
+    stream_taps: Arc<Mutex<HashMap<String, Vec<UnboundedSender<(String, bool)>>>>>,
+}
+
+/// Local listener of the stream chunks of an inbox, see `WebSocketManager::subscribe_inbox_stream`
+pub struct InboxStreamTap {
+    receiver: UnboundedReceiver<(String, bool)>,
+    inbox_name: String,
+    stream_taps: Arc<Mutex<HashMap<String, Vec<UnboundedSender<(String, bool)>>>>>,
+}
+
+impl InboxStreamTap {
+    /// Next chunk as (content, is_done), `None` once the listener was removed
+    pub async fn recv(&mut self) -> Option<(String, bool)> {
+        self.receiver.recv().await
+    }
+}
+
+impl Drop for InboxStreamTap {
+    fn drop(&mut self) {
+        // The inbox may never stream again, so the listener can't wait for a failed send to go
+        self.receiver.close();
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let stream_taps = Arc::clone(&self.stream_taps);
+        let inbox_name = std::mem::take(&mut self.inbox_name);
+        runtime.spawn(async move {
+            let mut stream_taps = stream_taps.lock().await;
+            if let Some(listeners) = stream_taps.get_mut(&inbox_name) {
+                listeners.retain(|listener| !listener.is_closed());
+                if listeners.is_empty() {
+                    stream_taps.remove(&inbox_name);
+                }
+            }
+        });
+    }
 }
 
 impl Clone for WebSocketManager {
@@ -60,6 +98,7 @@ impl Clone for WebSocketManager {
             identity_manager_trait: Arc::clone(&self.identity_manager_trait),
             encryption_secret_key: self.encryption_secret_key.clone(),
             message_queue: Arc::clone(&self.message_queue),
+            stream_taps: Arc::clone(&self.stream_taps),
         }
     }
 }
@@ -92,6 +131,7 @@ impl WebSocketManager {
             identity_manager_trait,
             encryption_secret_key,
             message_queue: Arc::new(Mutex::new(VecDeque::new())),
+            stream_taps: Arc::new(Mutex::new(HashMap::new())),
         }));
 
         let manager_clone = Arc::clone(&manager);
@@ -103,6 +143,27 @@ impl WebSocketManager {
         manager
     }
 
+    /// Receives the stream chunks sent to an inbox without going through a websocket
+    /// connection. The listener is removed once the tap is dropped.
+    pub async fn subscribe_inbox_stream(&self, inbox_name: &str) -> InboxStreamTap {
+        let (sender, receiver) = unbounded_channel();
+        self.stream_taps
+            .lock()
+            .await
+            .entry(inbox_name.to_string())
+            .or_default()
+            .push(sender);
+        InboxStreamTap {
+            receiver,
+            inbox_name: inbox_name.to_string(),
+            stream_taps: Arc::clone(&self.stream_taps),
+        }
+    }
+
+    pub fn connection_count(&self) -> usize {
+        self.connections.len()
+    }
+
     pub async fn start_message_sender(manager: Arc<Mutex<Self>>, message_queue: MessageQueue) {
         loop {
             let message = {
@@ -562,6 +623,17 @@ impl WSUpdateHandler for WebSocketManager {
         metadata: WSMessageType,
         is_stream: bool,
     ) {
+        if is_stream && topic == WSTopic::Inbox {
+            let mut stream_taps = self.stream_taps.lock().await;
+            if let Some(listeners) = stream_taps.get_mut(&subtopic) {
+                let is_done = matches!(&metadata, WSMessageType::Metadata(metadata) if metadata.is_done);
+                listeners.retain(|listener| listener.send((update.clone(), is_done)).is_ok());
+                if listeners.is_empty() {
+                    stream_taps.remove(&subtopic);
+                }
+            }
+        }
+
         let mut queue = self.message_queue.lock().await;
         queue.push_back((topic, subtopic, update, metadata, is_stream));
     }
@@ -1,7 +1,9 @@
 use super::network::Node;
 use super::utils::environment::NodeEnvironment;
 use crate::utils::args::parse_args;
-use crate::utils::cli::cli_handle_create_message;
+use crate::utils::cli::{
+    cli_handle_create_backup, cli_handle_create_message, cli_handle_restore_backup, cli_handle_run_tool_tests,
+};
 use crate::utils::environment::{fetch_llm_provider_env, fetch_node_environment};
 use crate::utils::keys::generate_or_load_keys;
 use crate::utils::qr_code_setup::generate_qr_codes;
@@ -193,7 +195,9 @@ pub async fn initialize_node() -> Result<
         node_env.supported_embedding_models.clone(),
         node_env.api_v2_key.clone(),
     )
-    .await;
+    .await?;
+
+    let api_v2_key = node.lock().await.api_v2_key.clone();
 
     // Put the Node in an Arc<Mutex<Node>> for use in a task
     let start_node = Arc::clone(&node);
@@ -248,6 +252,44 @@ pub async fn initialize_node() -> Result<
         }
     });
 
+    // CLI: back up or restore the node once it is up and exit with the result
+    if let Some(path) = args.create_backup.clone() {
+        let node_commands_sender = node_commands_sender_copy.clone();
+        let api_v2_key = api_v2_key.clone();
+        let categories = args.backup_categories.clone();
+        let passphrase = args.backup_passphrase.clone();
+        tokio::spawn(async move {
+            let created =
+                cli_handle_create_backup(node_commands_sender, api_v2_key, path, categories, passphrase).await;
+            std::process::exit(if created { 0 } else { 1 });
+        });
+    } else if let Some(path) = args.restore_backup.clone() {
+        let node_commands_sender = node_commands_sender_copy.clone();
+        let api_v2_key = api_v2_key.clone();
+        let categories = args.backup_categories.clone();
+        let passphrase = args.backup_passphrase.clone();
+        tokio::spawn(async move {
+            let restored =
+                cli_handle_restore_backup(node_commands_sender, api_v2_key, path, categories, passphrase).await;
+            std::process::exit(if restored { 0 } else { 1 });
+        });
+    }
+
+    // CLI: run the tool test cases once the node is up and exit with the result
+    if args.run_tool_tests {
+        let node_commands_sender = node_commands_sender_copy.clone();
+        tokio::spawn(async move {
+            let all_passed = cli_handle_run_tool_tests(
+                node_commands_sender,
+                api_v2_key,
+                args.tool_router_key,
+                args.skip_network_tests,
+            )
+            .await;
+            std::process::exit(if all_passed { 0 } else { 1 });
+        });
+    }
+
     tokio::spawn(async {
         match ShinkaiFileParser::initialize_local_file_parser().await {
             Ok(_) => {}
@@ -0,0 +1,529 @@
+use crate::network::node_metrics::node_metrics;
+use crate::tools::tool_launcher::{tool_launchers, EGRESS_ALLOWED_ENV};
+use crate::utils::environment::fetch_node_environment;
+use base64::Engine;
+use serde_json::json;
+use shinkai_message_primitives::schemas::audit_log::{AuditAction, AuditOutcome};
+use shinkai_message_primitives::schemas::tool_router_key::ToolRouterKey;
+use shinkai_message_primitives::shinkai_utils::shinkai_logging::{shinkai_log, ShinkaiLogLevel, ShinkaiLogOption};
+use shinkai_sqlite::SqliteManager;
+use shinkai_tools_primitives::tools::deno_tools::DENO_LAUNCHER_ENV;
+use shinkai_tools_primitives::tools::error::ToolError;
+use shinkai_tools_primitives::tools::tool_network::{NetworkAllowlist, ANY_HOST};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex, OnceLock, Weak};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Env var holding the session token of the current tool execution
+pub const EGRESS_SESSION_ENV: &str = "SHINKAI_EGRESS_SESSION";
+
+/// Hosts the runtimes download the dependencies of a tool from. Every tool can reach them, as it
+/// couldn't start otherwise.
+const PACKAGE_REGISTRY_HOSTS: [&str; 5] = [
+    "registry.npmjs.org",
+    "jsr.io",
+    "deno.land",
+    "pypi.org",
+    "files.pythonhosted.org",
+];
+const MAX_REQUEST_HEAD: usize = 16 * 1024;
+const SESSION_TTL: Duration = Duration::from_secs(60 * 60);
+
+static EGRESS_PROXY: OnceLock<Arc<EgressProxy>> = OnceLock::new();
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockedAttempt {
+    pub host: String,
+    pub port: u16,
+    pub reason: String,
+}
+
+/// Network access of a tool execution
+#[derive(Debug, Clone, PartialEq)]
+pub enum ToolNetwork {
+    /// Allowlist declared by the version of the tool that runs, if any
+    Declared(Option<Vec<String>>),
+    /// Not handled by the proxy: WASM tools, limited by their capability grants, and code run
+    /// directly by the user
+    Unmanaged,
+}
+
+struct EgressSession {
+    tool_router_key: String,
+    allowlist: NetworkAllowlist,
+    blocked: Vec<BlockedAttempt>,
+    created_at: Instant,
+}
+
+/// Local HTTP proxy that tool executions are pointed to through
+/// `HTTP_PROXY`/`HTTPS_PROXY`. Every execution gets its own session token, which the tool
+/// sends back as the proxy credentials, so the proxy knows which allowlist to enforce.
+pub struct EgressProxy {
+    address: SocketAddr,
+    db: Weak<SqliteManager>,
+    sessions: Mutex<HashMap<String, EgressSession>>,
+}
+
+/// Returns the proxy started by the node, if any
+pub fn egress_proxy() -> Option<Arc<EgressProxy>> {
+    EGRESS_PROXY.get().cloned()
+}
+
+impl EgressProxy {
+    /// Binds the proxy on localhost and starts accepting connections. The port can be pinned
+    /// with `EGRESS_PROXY_PORT`, otherwise a free one is picked.
+    pub async fn start(db: Weak<SqliteManager>) -> std::io::Result<Arc<EgressProxy>> {
+        if let Some(proxy) = egress_proxy() {
+            return Ok(proxy);
+        }
+
+        let port: u16 = std::env::var("EGRESS_PROXY_PORT")
+            .ok()
+            .and_then(|port| port.parse().ok())
+            .unwrap_or(0);
+        let listener = TcpListener::bind(("127.0.0.1", port)).await?;
+        let proxy = Arc::new(EgressProxy {
+            address: listener.local_addr()?,
+            db,
+            sessions: Mutex::new(HashMap::new()),
+        });
+        let proxy = EGRESS_PROXY.get_or_init(|| proxy).clone();
+
+        let accepting = proxy.clone();
+        tokio::spawn(async move {
+            loop {
+                match listener.accept().await {
+                    Ok((stream, _)) => {
+                        let proxy = accepting.clone();
+                        tokio::spawn(async move {
+                            if let Err(e) = proxy.handle_connection(stream).await {
+                                shinkai_log(
+                                    ShinkaiLogOption::Node,
+                                    ShinkaiLogLevel::Debug,
+                                    &format!("Egress proxy connection error: {}", e),
+                                );
+                            }
+                        });
+                    }
+                    Err(e) => {
+                        shinkai_log(
+                            ShinkaiLogOption::Node,
+                            ShinkaiLogLevel::Error,
+                            &format!("Egress proxy failed to accept connection: {}", e),
+                        );
+                    }
+                }
+            }
+        });
+
+        shinkai_log(
+            ShinkaiLogOption::Node,
+            ShinkaiLogLevel::Info,
+            &format!("Egress proxy listening on {}", proxy.address),
+        );
+        Ok(proxy)
+    }
+
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Opens a session for a tool execution and returns its token
+    pub fn register_session(&self, tool_router_key: String, allowlist: NetworkAllowlist) -> String {
+        let token = uuid::Uuid::new_v4().simple().to_string();
+        let mut sessions = self.sessions.lock().unwrap();
+        sessions.retain(|_, session| session.created_at.elapsed() < SESSION_TTL);
+        sessions.insert(
+            token.clone(),
+            EgressSession {
+                tool_router_key,
+                allowlist,
+                blocked: Vec::new(),
+                created_at: Instant::now(),
+            },
+        );
+        token
+    }
+
+    /// Closes a session and returns the attempts that were blocked while it was open
+    pub fn finish_session(&self, token: &str) -> Vec<BlockedAttempt> {
+        self.sessions
+            .lock()
+            .unwrap()
+            .remove(token)
+            .map(|session| session.blocked)
+            .unwrap_or_default()
+    }
+
+    /// Proxy URL, with the session token as credentials, to export to the tool
+    pub fn proxy_url(&self, token: &str) -> String {
+        format!("http://shinkai:{}@{}", token, self.address)
+    }
+
+    async fn handle_connection(&self, mut client: TcpStream) -> std::io::Result<()> {
+        let mut buffer = Vec::new();
+        let head_end = loop {
+            let mut chunk = [0u8; 4096];
+            let read = client.read(&mut chunk).await?;
+            if read == 0 {
+                return Ok(());
+            }
+            buffer.extend_from_slice(&chunk[..read]);
+            if let Some(position) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+                break position + 4;
+            }
+            if buffer.len() > MAX_REQUEST_HEAD {
+                return respond(&mut client, "431 Request Header Fields Too Large", "Request head too large").await;
+            }
+        };
+
+        let head = String::from_utf8_lossy(&buffer[..head_end]).to_string();
+        let body_start = buffer[head_end..].to_vec();
+        let mut lines = head.split("\r\n");
+        let request_line = lines.next().unwrap_or_default();
+        let mut parts = request_line.split_whitespace();
+        let (method, target, version) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(method), Some(target), Some(version)) => (method, target, version),
+            _ => return respond(&mut client, "400 Bad Request", "Malformed request line").await,
+        };
+        let headers: Vec<(String, String)> = lines
+            .filter(|line| !line.is_empty())
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+            .collect();
+
+        let token = headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("proxy-authorization"))
+            .and_then(|(_, value)| session_token(value));
+        let Some(token) = token else {
+            return respond(&mut client, "407 Proxy Authentication Required", "Missing egress session").await;
+        };
+
+        let (host, port, upstream_head) = if method.eq_ignore_ascii_case("CONNECT") {
+            let Some((host, port)) = split_host_port(target, 443) else {
+                return respond(&mut client, "400 Bad Request", "Invalid CONNECT target").await;
+            };
+            (host, port, None)
+        } else {
+            let Ok(url) = reqwest::Url::parse(target) else {
+                return respond(&mut client, "400 Bad Request", "Proxy requests need an absolute URL").await;
+            };
+            let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) else {
+                return respond(&mut client, "400 Bad Request", "Invalid request URL").await;
+            };
+            let path = match url.query() {
+                Some(query) => format!("{}?{}", url.path(), query),
+                None => url.path().to_string(),
+            };
+            let mut upstream_head = format!("{} {} {}\r\n", method, path, version);
+            for (name, value) in &headers {
+                let name_lower = name.to_lowercase();
+                if name_lower.starts_with("proxy-") || name_lower == "connection" {
+                    continue;
+                }
+                upstream_head.push_str(&format!("{}: {}\r\n", name, value));
+            }
+            // One request per connection, so every request goes through the allowlist check
+            upstream_head.push_str("Connection: close\r\n\r\n");
+            (host.trim_matches(['[', ']']).to_string(), port, Some(upstream_head))
+        };
+
+        let address = match self.authorize(&token, &host, port).await {
+            Ok(address) => address,
+            Err(reason) => {
+                return respond(
+                    &mut client,
+                    "403 Forbidden",
+                    &format!("Outbound access to {}:{} blocked: {}", host, port, reason),
+                )
+                .await
+            }
+        };
+
+        let mut upstream = match TcpStream::connect(address).await {
+            Ok(upstream) => upstream,
+            Err(e) => return respond(&mut client, "502 Bad Gateway", &e.to_string()).await,
+        };
+        match upstream_head {
+            None => {
+                client.write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n").await?;
+            }
+            Some(upstream_head) => {
+                upstream.write_all(upstream_head.as_bytes()).await?;
+            }
+        }
+        if !body_start.is_empty() {
+            upstream.write_all(&body_start).await?;
+        }
+        tokio::io::copy_bidirectional(&mut client, &mut upstream).await?;
+        Ok(())
+    }
+
+    /// Checks the destination against the session allowlist and returns the address to connect
+    /// to. IP ranges are checked against every resolved address, so a domain is only reachable
+    /// through a range entry when all its addresses fall inside it.
+    async fn authorize(&self, token: &str, host: &str, port: u16) -> Result<SocketAddr, String> {
+        let (tool_router_key, allowlist) = {
+            let sessions = self.sessions.lock().unwrap();
+            match sessions.get(token) {
+                Some(session) => (session.tool_router_key.clone(), session.allowlist.clone()),
+                None => return Err("Unknown or expired egress session".to_string()),
+            }
+        };
+
+        let resolved: Vec<SocketAddr> = match host.parse::<IpAddr>() {
+            Ok(ip) => vec![SocketAddr::new(ip, port)],
+            Err(_) => tokio::net::lookup_host((host, port))
+                .await
+                .map(|addresses| addresses.collect())
+                .unwrap_or_default(),
+        };
+
+        let allowed = allowlist.allows_host(host, port)
+            || (!resolved.is_empty() && resolved.iter().all(|address| allowlist.allows_ip(address.ip())));
+        if allowed {
+            return resolved
+                .first()
+                .copied()
+                .ok_or_else(|| format!("Could not resolve {}", host));
+        }
+
+        let reason = "Host is not in the tool network allowlist".to_string();
+        if let Some(session) = self.sessions.lock().unwrap().get_mut(token) {
+            session.blocked.push(BlockedAttempt {
+                host: host.to_string(),
+                port,
+                reason: reason.clone(),
+            });
+        }
+        if let Some(db) = self.db.upgrade() {
+            if let Err(e) = db.record_tool_egress_block(&tool_router_key, host, port, &reason) {
+                shinkai_log(
+                    ShinkaiLogOption::Node,
+                    ShinkaiLogLevel::Error,
+                    &format!("Failed to record blocked egress attempt: {}", e),
+                );
+            }
+            if let Err(e) = db.append_audit_log_entry(
+                "node",
+                None,
+                AuditAction::ToolEgressBlocked,
+                Some(&tool_router_key),
+                json!({ "host": host, "port": port }),
+                AuditOutcome::Failure {
+                    code: 403,
+                    message: reason.clone(),
+                },
+            ) {
+                node_metrics().record_audit_log_write_failure();
+                shinkai_log(
+                    ShinkaiLogOption::Node,
+                    ShinkaiLogLevel::Error,
+                    &format!(
+                        "Failed to write audit log entry for {}: {}",
+                        AuditAction::ToolEgressBlocked,
+                        e
+                    ),
+                );
+            }
+        }
+        shinkai_log(
+            ShinkaiLogOption::Node,
+            ShinkaiLogLevel::Info,
+            &format!("Blocked outbound access from {} to {}:{}", tool_router_key, host, port),
+        );
+        Err(reason)
+    }
+}
+
+/// Network settings for the execution of a tool, from the allowlist declared by the version of
+/// the tool that runs. Tools are limited to the declared hosts the user approved, or to nothing
+/// until an approval exists, plus the package registries. A tool that doesn't declare an
+/// allowlist is cut off as well, unless the user approved access to any host for it. Besides
+/// pointing `HTTP_PROXY`/`HTTPS_PROXY` to the proxy, direct connections to anything but the proxy
+/// and the node API are refused by the runtime.
+pub fn tool_network_envs(
+    db: &SqliteManager,
+    tool_router_key: &str,
+    network: ToolNetwork,
+) -> Result<HashMap<String, String>, ToolError> {
+    let mut envs = HashMap::new();
+    let ToolNetwork::Declared(declared) = network else {
+        return Ok(envs);
+    };
+
+    let tool_key = ToolRouterKey::from_string(tool_router_key)
+        .map(|key| key.to_string_without_version())
+        .unwrap_or_else(|_| tool_router_key.to_string());
+    let approved = db
+        .get_tool_network_approval(&tool_key)
+        .map_err(|e| ToolError::DatabaseError(e.to_string()))?
+        .map(|approval| approval.allowed_hosts)
+        .unwrap_or_default();
+    let mut effective: Vec<String> = match declared {
+        None if approved.iter().any(|host| host == ANY_HOST) => return Ok(envs),
+        None => Vec::new(),
+        Some(declared) => declared.into_iter().filter(|host| approved.contains(host)).collect(),
+    };
+    effective.extend(PACKAGE_REGISTRY_HOSTS.iter().map(|host| host.to_string()));
+    let allowlist = NetworkAllowlist::parse(&effective).map_err(ToolError::InvalidFunctionArguments)?;
+
+    let proxy = egress_proxy().ok_or_else(|| {
+        ToolError::ExecutionError("Egress proxy is not running, tools without network approval can't run".to_string())
+    })?;
+    let launchers = tool_launchers().ok_or_else(|| {
+        ToolError::ExecutionError("Tool launchers are not ready, tools without network approval can't run".to_string())
+    })?;
+
+    let token = proxy.register_session(tool_key, allowlist);
+    let proxy_url = proxy.proxy_url(&token);
+    for name in ["HTTP_PROXY", "HTTPS_PROXY", "http_proxy", "https_proxy"] {
+        envs.insert(name.to_string(), proxy_url.clone());
+    }
+    envs.insert(EGRESS_SESSION_ENV.to_string(), token);
+
+    let api_port = fetch_node_environment().api_listen_address.port();
+    let proxy_port = proxy.address().port();
+    let allowed: Vec<String> = ["127.0.0.1", "localhost"]
+        .iter()
+        .flat_map(|host| [format!("{}:{}", host, proxy_port), format!("{}:{}", host, api_port)])
+        .collect();
+    envs.insert(EGRESS_ALLOWED_ENV.to_string(), allowed.join(","));
+    envs.insert(
+        DENO_LAUNCHER_ENV.to_string(),
+        launchers.deno.to_string_lossy().to_string(),
+    );
+    Ok(envs)
+}
+
+/// Closes the egress session of an execution and turns blocked attempts into a tool error
+pub fn check_egress_session<T>(session: Option<String>, result: Result<T, ToolError>) -> Result<T, ToolError> {
+    let (Some(token), Some(proxy)) = (session, egress_proxy()) else {
+        return result;
+    };
+    let blocked = proxy.finish_session(&token);
+    if blocked.is_empty() {
+        return result;
+    }
+    let destinations: Vec<String> = blocked
+        .iter()
+        .map(|attempt| format!("{}:{}", attempt.host, attempt.port))
+        .collect();
+    Err(ToolError::ExecutionError(format!(
+        "Outbound network access blocked by the tool allowlist: {}",
+        destinations.join(", ")
+    )))
+}
+
+fn session_token(header_value: &str) -> Option<String> {
+    let (scheme, credentials) = header_value.split_once(' ')?;
+    if !scheme.eq_ignore_ascii_case("basic") {
+        return None;
+    }
+    let decoded = base64::engine::general_purpose::STANDARD
+        .decode(credentials.trim())
+        .ok()?;
+    let decoded = String::from_utf8(decoded).ok()?;
+    decoded.split_once(':').map(|(_, token)| token.to_string())
+}
+
+fn split_host_port(target: &str, default_port: u16) -> Option<(String, u16)> {
+    if let Some(rest) = target.strip_prefix('[') {
+        let (host, port) = rest.split_once(']')?;
+        let port = match port.strip_prefix(':') {
+            Some(port) => port.parse().ok()?,
+            None => default_port,
+        };
+        return Some((host.to_string(), port));
+    }
+    match target.rsplit_once(':') {
+        Some((host, port)) => Some((host.to_string(), port.parse().ok()?)),
+        None => Some((target.to_string(), default_port)),
+    }
+}
+
+async fn respond(client: &mut TcpStream, status: &str, message: &str) -> std::io::Result<()> {
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        message.len(),
+        message
+    );
+    client.write_all(response.as_bytes()).await?;
+    client.shutdown().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shinkai_embedding::model_type::{EmbeddingModelType, OllamaTextEmbeddingsInference};
+    use shinkai_message_primitives::schemas::audit_log::AuditLogFilter;
+
+    #[tokio::test]
+    async fn test_egress_proxy_blocks_hosts_outside_allowlist() {
+        let dir = tempfile::tempdir().unwrap();
+        let model_type =
+            EmbeddingModelType::OllamaTextEmbeddingsInference(OllamaTextEmbeddingsInference::SnowflakeArcticEmbedM);
+        let db = Arc::new(SqliteManager::new(dir.path().join("egress.db"), String::new(), model_type).unwrap());
+        let proxy = EgressProxy::start(Arc::downgrade(&db)).await.unwrap();
+        let upstream = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let upstream_port = upstream.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut stream, _) = upstream.accept().await.unwrap();
+            stream.write_all(b"pong").await.unwrap();
+        });
+
+        let allowlist = NetworkAllowlist::parse(&["127.0.0.1".to_string()]).unwrap();
+        let token = proxy.register_session("local:::__test_shinkai:::fetcher".to_string(), allowlist);
+        let credentials = base64::engine::general_purpose::STANDARD.encode(format!("shinkai:{}", token));
+
+        let mut allowed = TcpStream::connect(proxy.address()).await.unwrap();
+        allowed
+            .write_all(
+                format!(
+                    "CONNECT 127.0.0.1:{} HTTP/1.1\r\nProxy-Authorization: Basic {}\r\n\r\n",
+                    upstream_port, credentials
+                )
+                .as_bytes(),
+            )
+            .await
+            .unwrap();
+        let mut response = Vec::new();
+        allowed.read_to_end(&mut response).await.unwrap();
+        let response = String::from_utf8(response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200"));
+        assert!(response.ends_with("pong"));
+
+        let mut blocked = TcpStream::connect(proxy.address()).await.unwrap();
+        blocked
+            .write_all(
+                format!(
+                    "CONNECT 10.255.255.1:443 HTTP/1.1\r\nProxy-Authorization: Basic {}\r\n\r\n",
+                    credentials
+                )
+                .as_bytes(),
+            )
+            .await
+            .unwrap();
+        let mut response = String::new();
+        blocked.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 403"));
+
+        let result = check_egress_session(Some(token), Ok(()));
+        assert!(matches!(result, Err(ToolError::ExecutionError(message)) if message.contains("10.255.255.1:443")));
+
+        let filter = AuditLogFilter {
+            action: Some(AuditAction::ToolEgressBlocked),
+            ..Default::default()
+        };
+        let entries = db.query_audit_log(&filter).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].target.as_deref(), Some("local:::__test_shinkai:::fetcher"));
+        assert_eq!(entries[0].parameters, json!({ "host": "10.255.255.1", "port": 443 }));
+        assert!(!entries[0].outcome.is_success());
+    }
+}
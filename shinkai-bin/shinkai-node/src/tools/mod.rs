@@ -11,5 +11,8 @@ pub mod llm_language_support {
 }
 
 pub mod agent_execution;
+pub mod egress_proxy;
+pub mod tool_dependencies;
 pub mod tool_generation;
 pub mod tool_implementation;
+pub mod tool_launcher;
@@ -139,6 +139,7 @@ pub async fn generate_tool_definitions(
         let tool_result = match tool_data.clone() {
             ShinkaiTool::Deno(deno_tool, _) => deno_tool.result,
             ShinkaiTool::Python(python_tool, _) => python_tool.result,
+            ShinkaiTool::Wasm(wasm_tool, _) => wasm_tool.result,
             ShinkaiTool::Rust(rust_tool, _) => {
                 let value = serde_json::from_str::<serde_json::Value>(&rust_tool.output_arg.json).unwrap();
                 let result_type = value["result_type"].as_str().unwrap_or("object");
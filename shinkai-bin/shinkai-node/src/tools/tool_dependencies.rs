@@ -0,0 +1,204 @@
+use serde::{Deserialize, Serialize};
+use shinkai_message_primitives::schemas::tool_router_key::ToolRouterKey;
+use shinkai_sqlite::SqliteManager;
+use shinkai_tools_primitives::tools::error::ToolError;
+use shinkai_tools_primitives::tools::shinkai_tool::ShinkaiTool;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolDependencyNode {
+    pub tool_router_key: String,
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub installed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolDependencyEdge {
+    /// Tool declaring the dependency
+    pub from: String,
+    /// Tool being depended on
+    pub to: String,
+    /// Version requirement declared in the tool metadata, if any
+    pub requirement: Option<String>,
+    /// Whether the installed version of `to` satisfies `requirement`
+    pub satisfied: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ToolDependencyGraph {
+    pub nodes: Vec<ToolDependencyNode>,
+    pub edges: Vec<ToolDependencyEdge>,
+}
+
+/// Returns the dependencies of `tool` that are not installed or whose installed version does not
+/// satisfy the declared requirement.
+pub fn unmet_dependencies(tools: &[ShinkaiTool], tool: &ShinkaiTool) -> Vec<ToolRouterKey> {
+    let installed = installed_versions(tools);
+    tool.get_tools()
+        .into_iter()
+        .filter(|dependency| match installed.get(&dependency.to_string_without_version()) {
+            Some(version) => !dependency.version_requirement_matches(version),
+            None => true,
+        })
+        .collect()
+}
+
+/// Returns the keys of the installed tools that declare `tool_router_key` as a dependency
+pub fn dependents_of(tools: &[ShinkaiTool], tool_router_key: &str) -> Vec<String> {
+    let tool_router_key = tool_router_key.to_lowercase();
+    tools
+        .iter()
+        .filter(|tool| {
+            tool.get_tools()
+                .iter()
+                .any(|dependency| dependency.to_string_without_version() == tool_router_key)
+        })
+        .map(|tool| tool.tool_router_key().to_string_without_version())
+        .collect()
+}
+
+/// Builds the dependency graph of the installed tools. When `root` is set only the tools
+/// reachable from it are included.
+pub fn dependency_graph(tools: &[ShinkaiTool], root: Option<&str>) -> ToolDependencyGraph {
+    let by_key: HashMap<String, &ShinkaiTool> = tools
+        .iter()
+        .map(|tool| (tool.tool_router_key().to_string_without_version(), tool))
+        .collect();
+
+    let mut queue: VecDeque<String> = match root {
+        Some(root) => VecDeque::from([root.to_lowercase()]),
+        None => by_key.keys().cloned().collect(),
+    };
+    let mut visited: HashSet<String> = HashSet::new();
+    let mut graph = ToolDependencyGraph::default();
+
+    while let Some(key) = queue.pop_front() {
+        if !visited.insert(key.clone()) {
+            continue;
+        }
+
+        let Some(tool) = by_key.get(&key) else {
+            graph.nodes.push(ToolDependencyNode {
+                tool_router_key: key,
+                name: None,
+                version: None,
+                installed: false,
+            });
+            continue;
+        };
+        graph.nodes.push(ToolDependencyNode {
+            tool_router_key: key.clone(),
+            name: Some(tool.name()),
+            version: Some(tool.version()),
+            installed: true,
+        });
+
+        for dependency in tool.get_tools() {
+            let to = dependency.to_string_without_version();
+            let satisfied = by_key
+                .get(&to)
+                .map_or(false, |installed| dependency.version_requirement_matches(&installed.version()));
+            graph.edges.push(ToolDependencyEdge {
+                from: key.clone(),
+                to: to.clone(),
+                requirement: dependency.version.clone(),
+                satisfied,
+            });
+            queue.push_back(to);
+        }
+    }
+
+    graph.nodes.sort_by(|a, b| a.tool_router_key.cmp(&b.tool_router_key));
+    graph
+}
+
+/// Loads every installed tool, used as input for the dependency helpers above
+pub fn installed_tools(db: &SqliteManager) -> Result<Vec<ShinkaiTool>, ToolError> {
+    db.get_all_tool_headers()
+        .map_err(|e| ToolError::DatabaseError(e.to_string()))?
+        .into_iter()
+        .map(|header| {
+            db.get_tool_by_key(&header.tool_router_key)
+                .map_err(|e| ToolError::DatabaseError(e.to_string()))
+        })
+        .collect()
+}
+
+fn installed_versions(tools: &[ShinkaiTool]) -> HashMap<String, String> {
+    tools
+        .iter()
+        .map(|tool| (tool.tool_router_key().to_string_without_version(), tool.version()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shinkai_tools_primitives::tools::deno_tools::DenoTool;
+    use shinkai_tools_primitives::tools::parameters::Parameters;
+    use shinkai_tools_primitives::tools::tool_output_arg::ToolOutputArg;
+    use shinkai_tools_primitives::tools::tool_types::{RunnerType, ToolResult};
+
+    fn deno_tool(name: &str, version: &str, dependencies: Vec<&str>) -> ShinkaiTool {
+        let tool = DenoTool::new(
+            name.to_string(),
+            None,
+            "@@test.shinkai".to_string(),
+            version.to_string(),
+            Some(false),
+            String::new(),
+            dependencies
+                .into_iter()
+                .map(|key| ToolRouterKey::from_string(key).unwrap())
+                .collect(),
+            vec![],
+            String::new(),
+            vec![],
+            Parameters::new(),
+            ToolOutputArg::empty(),
+            true,
+            None,
+            ToolResult::new("object".to_string(), serde_json::Value::Null, vec![]),
+            None,
+            None,
+            None,
+            None,
+            None,
+            RunnerType::Any,
+            vec![],
+            None,
+        );
+        ShinkaiTool::Deno(tool, true)
+    }
+
+    #[test]
+    fn test_dependency_graph() {
+        let tools = vec![
+            deno_tool("app", "1.0.0", vec!["local:::__test_shinkai:::fetch:::^1.0"]),
+            deno_tool("fetch", "1.4.0", vec!["local:::__test_shinkai:::parse:::>=2.0.0"]),
+            deno_tool("parse", "1.9.0", vec![]),
+            deno_tool("other", "1.0.0", vec![]),
+        ];
+
+        let unmet = unmet_dependencies(&tools, &tools[1]);
+        assert_eq!(unmet.len(), 1);
+        assert_eq!(unmet[0].to_string_without_version(), "local:::__test_shinkai:::parse");
+        assert!(unmet_dependencies(&tools, &tools[0]).is_empty());
+
+        assert_eq!(
+            dependents_of(&tools, "local:::__test_shinkai:::fetch"),
+            vec!["local:::__test_shinkai:::app".to_string()]
+        );
+        assert!(dependents_of(&tools, "local:::__test_shinkai:::app").is_empty());
+
+        let graph = dependency_graph(&tools, Some("local:::__test_shinkai:::app"));
+        assert_eq!(graph.nodes.len(), 3);
+        assert_eq!(graph.edges.len(), 2);
+        assert!(graph.edges[0].satisfied);
+        assert!(!graph.edges[1].satisfied);
+        assert_eq!(graph.edges[1].requirement, Some(">=2.0.0".to_string()));
+
+        assert_eq!(dependency_graph(&tools, None).nodes.len(), 4);
+    }
+}
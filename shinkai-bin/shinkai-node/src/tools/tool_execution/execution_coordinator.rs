@@ -1,5 +1,8 @@
 use crate::llm_provider::job_manager::JobManager;
 use crate::managers::IdentityManager;
+use crate::network::node_metrics::node_metrics;
+use crate::network::webhook_dispatcher::emit_webhook_event;
+use crate::tools::egress_proxy::{check_egress_session, ToolNetwork, EGRESS_SESSION_ENV};
 use crate::tools::tool_definitions::definition_generation::generate_tool_definitions;
 use crate::tools::tool_execution::execute_agent_dynamic::execute_agent_tool;
 use crate::tools::tool_execution::execution_custom::try_to_execute_rust_tool;
@@ -20,6 +23,7 @@ use shinkai_message_primitives::schemas::shinkai_name::ShinkaiName;
 use shinkai_message_primitives::schemas::shinkai_tools::CodeLanguage;
 use shinkai_message_primitives::schemas::shinkai_tools::DynamicToolType;
 use shinkai_message_primitives::schemas::tool_router_key::ToolRouterKey;
+use shinkai_message_primitives::schemas::webhook::WebhookEventType;
 use shinkai_sqlite::oauth_manager::OAuthToken;
 use shinkai_sqlite::SqliteManager;
 use shinkai_tools_primitives::tools::error::ToolError;
@@ -28,6 +32,7 @@ use shinkai_tools_primitives::tools::tool_config::{BasicConfig, OAuth, ToolConfi
 use shinkai_tools_primitives::tools::tool_types::{OperatingSystem, RunnerType};
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::sync::Mutex;
 use x25519_dalek::PublicKey as EncryptionPublicKey;
 use x25519_dalek::StaticSecret as EncryptionStaticKey;
@@ -43,10 +48,14 @@ pub async fn handle_oauth(
     if let Some(oauth_vec) = oauth {
         for o in oauth_vec {
             // Check if OAuth token already exists
-            let existing_token = db
+            let mut existing_token = db
                 .get_oauth_token(o.name.clone(), tool_router_key.clone())
                 .ok()
                 .unwrap_or(None);
+            if let Some(token) = existing_token.as_mut() {
+                db.reveal_oauth_token(token)
+                    .map_err(|e| ToolError::ExecutionError(format!("Failed to read OAuth token: {}", e)))?;
+            }
 
             let (state_uuid, pkce_uuid) = if let Some(token) = existing_token.clone() {
                 if let Some(_) = token.access_token.clone() {
@@ -293,6 +302,7 @@ pub fn override_tool_config(tool_router_key: String, agent: Agent, extra_config:
     final_config
 }
 
+#[tracing::instrument(name = "tool", skip_all, fields(tool = %tool_router_key))]
 pub async fn execute_tool_cmd(
     bearer: String,
     node_name: ShinkaiName,
@@ -310,11 +320,62 @@ pub async fn execute_tool_cmd(
     encryption_public_key: EncryptionPublicKey,
     signing_secret_key: SigningKey,
     mounts: Option<Vec<String>>,
+) -> Result<Value, ToolError> {
+    let start = Instant::now();
+    let result = execute_tool_cmd_inner(
+        bearer,
+        node_name,
+        db.clone(),
+        tool_router_key.clone(),
+        parameters,
+        tool_id,
+        app_id,
+        agent_id,
+        llm_provider,
+        extra_config,
+        identity_manager,
+        job_manager,
+        encryption_secret_key,
+        encryption_public_key,
+        signing_secret_key,
+        mounts,
+    )
+    .await;
+    node_metrics().record_tool_execution(&tool_router_key, start.elapsed(), result.is_ok());
+    if let Err(e) = &result {
+        emit_webhook_event(
+            &db,
+            WebhookEventType::ToolFailed,
+            json!({ "tool_router_key": tool_router_key, "error": e.to_string() }),
+        );
+    }
+    result
+}
+
+async fn execute_tool_cmd_inner(
+    bearer: String,
+    node_name: ShinkaiName,
+    db: Arc<SqliteManager>,
+    tool_router_key: String,
+    parameters: Map<String, Value>,
+    tool_id: String,
+    app_id: String,
+    agent_id: Option<String>,
+    llm_provider: String,
+    extra_config: Vec<ToolConfig>,
+    identity_manager: Arc<Mutex<IdentityManager>>,
+    job_manager: Arc<Mutex<JobManager>>,
+    encryption_secret_key: EncryptionStaticKey,
+    encryption_public_key: EncryptionPublicKey,
+    signing_secret_key: SigningKey,
+    mounts: Option<Vec<String>>,
 ) -> Result<Value, ToolError> {
     println!("[execute_tool] with tool_router_key: {}", tool_router_key);
-    let tool = db
+    let mut tool = db
         .get_tool_by_key(&tool_router_key)
         .map_err(|e| ToolError::ExecutionError(format!("Failed to get tool: {}", e)))?;
+    db.reveal_tool_config(&mut tool)
+        .map_err(|e| ToolError::ExecutionError(format!("Failed to read tool config: {}", e)))?;
 
     // If agent_id is provided, get the agent's tool config overrides and merge with extra_config
     let mut extra_config = extra_config.clone();
@@ -368,6 +429,7 @@ pub async fn execute_tool_cmd(
         }
         ShinkaiTool::Python(python_tool, _) => {
             let env = generate_execution_environment(
+                bearer.clone(),
                 db.clone(),
                 llm_provider.clone(),
                 app_id.clone(),
@@ -376,6 +438,7 @@ pub async fn execute_tool_cmd(
                 tool_router_key.clone(),
                 "".to_string(), // TODO Pass data from the API
                 &python_tool.oauth,
+                ToolNetwork::Declared(python_tool.network_allowlist.clone()),
             )
             .await?;
 
@@ -406,7 +469,8 @@ pub async fn execute_tool_cmd(
             let support_files = generate_tool_definitions(tools, CodeLanguage::Python, db, false)
                 .await
                 .map_err(|_| ToolError::ExecutionError("Failed to generate tool definitions".to_string()))?;
-            python_tool
+            let egress_session = env.get(EGRESS_SESSION_ENV).cloned();
+            let result = python_tool
                 .run(
                     env,
                     node_env.api_listen_address.ip().to_string(),
@@ -422,11 +486,67 @@ pub async fn execute_tool_cmd(
                     Some(tool_router_key),
                     mounts,
                 )
+                .await;
+            check_egress_session(egress_session, result)
+                .map(|result| json!(result.data))
+        }
+        ShinkaiTool::Wasm(wasm_tool, _) => {
+            let env = generate_execution_environment(
+                bearer.clone(),
+                db.clone(),
+                llm_provider.clone(),
+                app_id.clone(),
+                tool_id.clone(),
+                agent_id.clone(),
+                tool_router_key.clone(),
+                "".to_string(), // TODO Pass data from the API
+                &wasm_tool.oauth,
+                ToolNetwork::Unmanaged,
+            )
+            .await?;
+
+            check_tool(
+                tool_router_key.clone(),
+                wasm_tool.config.clone(),
+                parameters.clone(),
+                wasm_tool.input_args.clone(),
+                &wasm_tool.oauth,
+            )?;
+
+            // Only the capabilities approved by the user are granted
+            let grant_key = ToolRouterKey::from_string(&tool_router_key)
+                .map(|key| key.to_string_without_version())
+                .unwrap_or_else(|_| tool_router_key.clone());
+            let grant = db
+                .get_wasm_tool_grant(&grant_key)
+                .map_err(|e| ToolError::ExecutionError(format!("Failed to read WASM tool grant: {}", e)))?;
+
+            let node_env = fetch_node_environment();
+            let node_storage_path = node_env
+                .node_storage_path
+                .clone()
+                .ok_or_else(|| ToolError::ExecutionError("Node storage path is not set".to_string()))?;
+            wasm_tool
+                .run(
+                    env,
+                    node_env.api_listen_address.ip().to_string(),
+                    node_env.api_listen_address.port(),
+                    parameters,
+                    extra_config,
+                    node_storage_path,
+                    app_id.clone(),
+                    tool_id.clone(),
+                    node_name,
+                    Some(tool_router_key),
+                    mounts,
+                    grant,
+                )
                 .await
                 .map(|result| json!(result.data))
         }
         ShinkaiTool::Deno(deno_tool, _) => {
             let env = generate_execution_environment(
+                bearer.clone(),
                 db.clone(),
                 llm_provider.clone(),
                 app_id.clone(),
@@ -435,6 +555,7 @@ pub async fn execute_tool_cmd(
                 tool_router_key.clone(),
                 "".to_string(), // TODO Pass data from the API
                 &deno_tool.oauth,
+                ToolNetwork::Declared(deno_tool.network_allowlist.clone()),
             )
             .await?;
 
@@ -464,7 +585,8 @@ pub async fn execute_tool_cmd(
             let support_files = generate_tool_definitions(tools, CodeLanguage::Typescript, db, false)
                 .await
                 .map_err(|_| ToolError::ExecutionError("Failed to generate tool definitions".to_string()))?;
-            deno_tool
+            let egress_session = env.get(EGRESS_SESSION_ENV).cloned();
+            let result = deno_tool
                 .run(
                     env,
                     node_env.api_listen_address.ip().to_string(),
@@ -480,7 +602,8 @@ pub async fn execute_tool_cmd(
                     Some(tool_router_key),
                     mounts,
                 )
-                .await
+                .await;
+            check_egress_session(egress_session, result)
                 .map(|result| json!(result.data))
                 .map_err(|e| ToolError::ExecutionError(e.to_string()))
         }
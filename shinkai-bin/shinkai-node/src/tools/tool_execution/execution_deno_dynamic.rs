@@ -12,12 +12,13 @@ use shinkai_tools_primitives::tools::tool_output_arg::ToolOutputArg;
 use shinkai_tools_primitives::tools::tool_types::{OperatingSystem, RunnerType, ToolResult};
 
 use super::execution_header_generator::{check_tool, generate_execution_environment};
+use crate::tools::egress_proxy::ToolNetwork;
 use crate::utils::environment::fetch_node_environment;
 use shinkai_sqlite::SqliteManager;
 use std::sync::Arc;
 
 pub async fn execute_deno_tool(
-    _bearer: String,
+    bearer: String,
     db: Arc<SqliteManager>,
     node_name: ShinkaiName,
     parameters: Map<String, Value>,
@@ -70,9 +71,12 @@ pub async fn execute_deno_tool(
             OperatingSystem::Windows,
         ]),
         tool_set: None,
+        test_cases: None,
+        network_allowlist: None,
     };
 
     let env = generate_execution_environment(
+        bearer,
         db.clone(),
         llm_provider.clone(),
         app_id.clone(),
@@ -82,6 +86,8 @@ pub async fn execute_deno_tool(
         "code-execution".to_string(),
         "".to_string(),
         &oauth,
+        // Code run directly by the user, not an installed tool
+        ToolNetwork::Unmanaged,
     )
     .await?;
 
@@ -182,6 +188,8 @@ pub async fn check_deno_tool(
         runner: RunnerType::Any,
         operating_system: vec![OperatingSystem::Linux, OperatingSystem::MacOS, OperatingSystem::Windows],
         tool_set: None,
+        test_cases: None,
+        network_allowlist: None,
     };
 
     let node_env = fetch_node_environment();
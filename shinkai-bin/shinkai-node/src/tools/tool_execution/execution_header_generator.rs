@@ -9,8 +9,13 @@ use shinkai_tools_primitives::tools::{
 };
 
 use super::execution_coordinator::handle_oauth;
+use crate::tools::egress_proxy::{tool_network_envs, ToolNetwork};
+use crate::utils::telemetry::{current_traceparent, TRACEPARENT_ENV};
 
+/// Environment of a tool process. `bearer` is the token the tool uses to call the node API: the
+/// key of the caller, never the node key unless the caller used it.
 pub async fn generate_execution_environment(
+    bearer: String,
     db: Arc<SqliteManager>,
     llm_provider: String,
     app_id: String,
@@ -19,10 +24,10 @@ pub async fn generate_execution_environment(
     tool_router_key: String,
     instance_id: String,
     oauth: &Option<Vec<OAuth>>,
+    network: ToolNetwork,
 ) -> Result<HashMap<String, String>, ToolError> {
     let mut envs = HashMap::new();
 
-    let bearer = db.read_api_v2_key().unwrap_or_default().unwrap_or_default();
     envs.insert("BEARER".to_string(), bearer);
     envs.insert("X_SHINKAI_TOOL_ID".to_string(), tool_id.clone());
     envs.insert("X_SHINKAI_APP_ID".to_string(), app_id.clone());
@@ -36,6 +41,10 @@ pub async fn generate_execution_environment(
     let oauth = handle_oauth(oauth, &db, app_id.clone(), tool_id.clone(), tool_router_key.clone()).await?;
 
     envs.insert("SHINKAI_OAUTH".to_string(), oauth.to_string());
+    envs.extend(tool_network_envs(&db, &tool_router_key, network)?);
+    if let Some(traceparent) = current_traceparent() {
+        envs.insert(TRACEPARENT_ENV.to_string(), traceparent);
+    }
 
     Ok(envs)
 }
@@ -1,6 +1,7 @@
 use std::{collections::HashMap, path::PathBuf};
 
 use super::execution_header_generator::{check_tool, generate_execution_environment};
+use crate::tools::egress_proxy::ToolNetwork;
 use crate::utils::environment::fetch_node_environment;
 use serde_json::{Map, Value};
 use shinkai_message_primitives::schemas::{shinkai_name::ShinkaiName, tool_router_key::ToolRouterKey};
@@ -11,7 +12,7 @@ use shinkai_tools_primitives::tools::{
 use std::sync::Arc;
 
 pub async fn execute_python_tool(
-    _bearer: String,
+    bearer: String,
     db: Arc<SqliteManager>,
     node_name: ShinkaiName,
     parameters: Map<String, Value>,
@@ -64,9 +65,12 @@ pub async fn execute_python_tool(
             OperatingSystem::Windows,
         ]),
         tool_set: None,
+        test_cases: None,
+        network_allowlist: None,
     };
 
     let env = generate_execution_environment(
+        bearer,
         db.clone(),
         llm_provider.clone(),
         app_id.clone(),
@@ -75,6 +79,8 @@ pub async fn execute_python_tool(
         "code-execution".to_string(),
         "".to_string(),
         &oauth,
+        // Code run directly by the user, not an installed tool
+        ToolNetwork::Unmanaged,
     )
     .await?;
 
@@ -0,0 +1,130 @@
+use crate::llm_provider::job_manager::JobManager;
+use crate::managers::IdentityManager;
+use crate::tools::tool_execution::execution_coordinator::execute_tool_cmd;
+use ed25519_dalek::SigningKey;
+use serde_json::Value;
+use shinkai_message_primitives::schemas::shinkai_name::ShinkaiName;
+use shinkai_sqlite::SqliteManager;
+use shinkai_tools_primitives::tools::error::ToolError;
+use shinkai_tools_primitives::tools::tool_config::ToolConfig;
+use shinkai_tools_primitives::tools::tool_test_case::{ToolTestCaseResult, ToolTestReport, ToolTestStatus};
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::Mutex;
+use x25519_dalek::PublicKey as EncryptionPublicKey;
+use x25519_dalek::StaticSecret as EncryptionStaticKey;
+
+/// Returns the keys of every installed tool that declares at least one test case
+pub fn get_tools_with_test_cases(db: Arc<SqliteManager>) -> Result<Vec<String>, ToolError> {
+    let headers = db
+        .get_all_tool_headers()
+        .map_err(|e| ToolError::ExecutionError(format!("Failed to get tool headers: {}", e)))?;
+
+    Ok(headers
+        .into_iter()
+        .filter(|header| {
+            db.get_tool_by_key(&header.tool_router_key)
+                .map(|tool| tool.get_test_cases().map_or(false, |cases| !cases.is_empty()))
+                .unwrap_or(false)
+        })
+        .map(|header| header.tool_router_key)
+        .collect())
+}
+
+/// Runs all the test cases declared in the tool metadata and reports the result of each one.
+/// Test cases tagged as `network` are skipped when `skip_network_tests` is set.
+pub async fn run_tool_test_cases(
+    bearer: String,
+    node_name: ShinkaiName,
+    db: Arc<SqliteManager>,
+    tool_router_key: String,
+    skip_network_tests: bool,
+    llm_provider: String,
+    identity_manager: Arc<Mutex<IdentityManager>>,
+    job_manager: Arc<Mutex<JobManager>>,
+    encryption_secret_key: EncryptionStaticKey,
+    encryption_public_key: EncryptionPublicKey,
+    signing_secret_key: SigningKey,
+) -> Result<ToolTestReport, ToolError> {
+    let tool = db
+        .get_tool_by_key(&tool_router_key)
+        .map_err(|e| ToolError::ExecutionError(format!("Failed to get tool: {}", e)))?;
+    let test_cases = tool.get_test_cases().unwrap_or_default();
+    let app_id = format!("tool_tests_{}", uuid::Uuid::new_v4());
+
+    let mut results = Vec::new();
+    for (index, test_case) in test_cases.into_iter().enumerate() {
+        if skip_network_tests && test_case.requires_network() {
+            results.push(ToolTestCaseResult {
+                name: test_case.name,
+                status: ToolTestStatus::Skipped,
+                messages: vec!["Requires network access".to_string()],
+                output: None,
+                duration_ms: 0,
+            });
+            continue;
+        }
+
+        let extra_config = test_case
+            .config
+            .clone()
+            .map(|config| ToolConfig::basic_config_from_value(&Value::Object(config)))
+            .unwrap_or_default();
+
+        let start = Instant::now();
+        let output = execute_tool_cmd(
+            bearer.clone(),
+            node_name.clone(),
+            db.clone(),
+            tool_router_key.clone(),
+            test_case.input.clone(),
+            format!("test_case_{}", index),
+            app_id.clone(),
+            None,
+            llm_provider.clone(),
+            extra_config,
+            identity_manager.clone(),
+            job_manager.clone(),
+            encryption_secret_key.clone(),
+            encryption_public_key,
+            signing_secret_key.clone(),
+            None,
+        )
+        .await;
+        let duration_ms = start.elapsed().as_millis() as u64;
+
+        let result = match output {
+            Ok(output) => {
+                let failures = test_case.evaluate(&output);
+                ToolTestCaseResult {
+                    name: test_case.name,
+                    status: if failures.is_empty() {
+                        ToolTestStatus::Passed
+                    } else {
+                        ToolTestStatus::Failed
+                    },
+                    messages: failures,
+                    output: Some(output),
+                    duration_ms,
+                }
+            }
+            Err(e) => ToolTestCaseResult {
+                name: test_case.name,
+                status: ToolTestStatus::Error,
+                messages: vec![e.to_string()],
+                output: None,
+                duration_ms,
+            },
+        };
+        results.push(result);
+    }
+
+    let count = |status: ToolTestStatus| results.iter().filter(|r| r.status == status).count();
+    Ok(ToolTestReport {
+        tool_router_key,
+        passed: count(ToolTestStatus::Passed),
+        failed: count(ToolTestStatus::Failed) + count(ToolTestStatus::Error),
+        skipped: count(ToolTestStatus::Skipped),
+        results,
+    })
+}
@@ -4,3 +4,4 @@ pub mod execution_custom;
 pub mod execution_deno_dynamic;
 pub mod execution_header_generator;
 pub mod execution_python_dynamic;
+pub mod execution_test_cases;
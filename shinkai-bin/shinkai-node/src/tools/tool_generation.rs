@@ -123,7 +123,7 @@ pub async fn v2_send_basic_job_message_for_existing_job(
     encryption_secret_key_clone: EncryptionStaticKey,
     encryption_public_key_clone: EncryptionPublicKey,
     signing_secret_key_clone: SigningKey,
-) -> Result<(), APIError> {
+) -> Result<String, APIError> {
     // Send message
     let job_message = JobMessage {
         job_id: job_id.clone(),
@@ -159,9 +159,9 @@ pub async fn v2_send_basic_job_message_for_existing_job(
     )
     .await;
 
-    res_receiver
+    let response = res_receiver
         .recv()
         .await
         .map_err(|e| Node::generic_api_error(&e.to_string()))??;
-    Ok(())
+    Ok(response.message_id)
 }
@@ -110,9 +110,12 @@ async fn config_update(
         shinkai_tools_primitives::tools::shinkai_tool::ShinkaiTool::Python(python_tool, _) => {
             python_tool.config = new_configs;
         }
+        shinkai_tools_primitives::tools::shinkai_tool::ShinkaiTool::Wasm(wasm_tool, _) => {
+            wasm_tool.config = new_configs;
+        }
         _ => {
             return Err(ToolError::ExecutionError(
-                "Config update is only supported for Deno and Python tools".to_string(),
+                "Config update is only supported for Deno, Python and WASM tools".to_string(),
             ))
         }
     }
@@ -385,6 +388,8 @@ mod tests {
                 runner: RunnerType::Any,
                 operating_system: vec![OperatingSystem::Windows],
                 tool_set: None,
+                test_cases: None,
+                network_allowlist: None,
             },
             true,
         );
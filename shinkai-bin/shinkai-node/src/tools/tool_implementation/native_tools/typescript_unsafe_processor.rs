@@ -17,14 +17,16 @@ use x25519_dalek::StaticSecret as EncryptionStaticKey;
 use crate::llm_provider::job_manager::JobManager;
 use crate::managers::IdentityManager;
 use crate::network::Node;
+use crate::tools::egress_proxy::ToolNetwork;
 use crate::tools::tool_execution::execution_header_generator::generate_execution_environment;
 use crate::tools::tool_implementation::tool_traits::ToolExecutor;
 
 use tokio::sync::Mutex;
 
 use async_trait::async_trait;
-use std::process::{Command, Stdio};
+use std::process::Stdio;
 use std::{env, fs};
+use tokio::process::Command;
 
 // LLM Tool
 pub struct TypescriptUnsafeProcessorTool {
@@ -135,7 +137,9 @@ impl TypescriptUnsafeProcessorTool {
                 .current_dir(temp_path.clone())
                 .stdout(Stdio::inherit())
                 // .stderr(Stdio::inherit())
+                .kill_on_drop(true)
                 .output()
+                .await
                 .map_err(|e| {
                     if e.kind() == std::io::ErrorKind::NotFound {
                         ToolError::ExecutionError(
@@ -155,14 +159,16 @@ impl TypescriptUnsafeProcessorTool {
             }
         }
 
-        // Run node index.ts
+        // Run node index.ts, killed if the job is stopped
         let node_output = Command::new(&node_binary)
             .envs(envs)
             .arg("--experimental-strip-types")
             .arg("index.ts")
             .current_dir(temp_path.clone())
             .stderr(Stdio::inherit())
+            .kill_on_drop(true)
             .output()
+            .await
             .map_err(|e| {
                 if e.kind() == std::io::ErrorKind::NotFound {
                     ToolError::ExecutionError(
@@ -206,7 +212,7 @@ impl TypescriptUnsafeProcessorTool {
 #[async_trait]
 impl ToolExecutor for TypescriptUnsafeProcessorTool {
     async fn execute(
-        _bearer: String,
+        bearer: String,
         tool_id: String,
         app_id: String,
         db: Arc<SqliteManager>,
@@ -228,10 +234,12 @@ impl ToolExecutor for TypescriptUnsafeProcessorTool {
             .iter()
             .find(|provider| provider.external_url == Some("https://api.openai.com".to_string()))
         {
-            Some(provider) => provider.api_key.clone().unwrap_or_default(),
+            Some(provider) => db
+                .reveal_optional_secret(&provider.api_key)
+                .map_err(|e| ToolError::ExecutionError(format!("Failed to read LLM provider API key: {}", e)))?
+                .unwrap_or_default(),
             None => "".to_string(),
         };
-        println!("OpenAI Provider: {:?}", open_ai_key);
 
         // Extract required parameters
         let code = parameters
@@ -258,6 +266,7 @@ impl ToolExecutor for TypescriptUnsafeProcessorTool {
         };
 
         let mut envs = generate_execution_environment(
+            bearer,
             db,
             llm_provider,
             app_id,
@@ -267,6 +276,8 @@ impl ToolExecutor for TypescriptUnsafeProcessorTool {
             "".to_string(), // Tool router key needed for oauth validation.
             "unknown".to_string(),
             &None,
+            // Runs with Node.js directly instead of the tool runtimes, which the proxy can't limit
+            ToolNetwork::Unmanaged,
         )
         .await?;
 
@@ -0,0 +1,399 @@
+use shinkai_tools_primitives::tools::deno_tools::DENO_LAUNCHER_ENV;
+use shinkai_tools_primitives::tools::python_tools::UV_LAUNCHER_ENV;
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+
+/// Env var holding the `host:port` list a tool limited by the egress proxy can connect to directly:
+/// the egress proxy and the node API. Everything else has to go through the proxy.
+pub const EGRESS_ALLOWED_ENV: &str = "SHINKAI_EGRESS_ALLOWED";
+/// Env var holding the folder where the launchers of a tool call record their process ids
+pub const PROCESS_DIR_ENV: &str = "SHINKAI_TOOL_PROCESS_DIR";
+
+const LAUNCHERS_FOLDER: &str = "tool_launchers";
+const PROCESSES_FOLDER: &str = "processes";
+const DENO_LAUNCHER: &str = "deno";
+const UV_LAUNCHER: &str = "uv";
+const TARGET_SUFFIX: &str = "target";
+
+/// Sandboxes the runtimes started by `shinkai_tools_runner`, which only lets the node pick the
+/// binary it runs. The node binary is linked under the name of the runtime and, when started
+/// through that link, applies the restrictions of the execution and runs the real runtime.
+pub struct ToolLaunchers {
+    /// Link to the node binary used as the Deno binary
+    pub deno: PathBuf,
+    /// Link to the node binary used as the uv binary, which runs the Python tools
+    pub uv: PathBuf,
+    /// Folder with the process folders of the running tool calls
+    pub processes: PathBuf,
+}
+
+static TOOL_LAUNCHERS: OnceLock<ToolLaunchers> = OnceLock::new();
+
+pub fn tool_launchers() -> Option<&'static ToolLaunchers> {
+    TOOL_LAUNCHERS.get()
+}
+
+/// Links the node binary as the Deno and uv launchers under the node storage
+pub fn prepare_tool_launchers(node_storage_path: &Path) -> std::io::Result<&'static ToolLaunchers> {
+    if let Some(launchers) = tool_launchers() {
+        return Ok(launchers);
+    }
+
+    let folder = node_storage_path.join(LAUNCHERS_FOLDER);
+    std::fs::create_dir_all(&folder)?;
+
+    let deno_target = std::env::var("SHINKAI_TOOLS_RUNNER_DENO_BINARY_PATH")
+        .unwrap_or_else(|_| "./shinkai-tools-runner-resources/deno".to_string());
+    let deno = link_launcher(&folder, DENO_LAUNCHER, Path::new(&deno_target))?;
+    let uv_target = std::env::var("SHINKAI_TOOLS_RUNNER_UV_BINARY_PATH")
+        .unwrap_or_else(|_| "./shinkai-tools-runner-resources/uv".to_string());
+    let uv = link_launcher(&folder, UV_LAUNCHER, Path::new(&uv_target))?;
+
+    // Left over by tool calls running when the node stopped
+    let processes = folder.join(PROCESSES_FOLDER);
+    let _ = std::fs::remove_dir_all(&processes);
+    std::fs::create_dir_all(&processes)?;
+
+    Ok(TOOL_LAUNCHERS.get_or_init(|| ToolLaunchers { deno, uv, processes }))
+}
+
+/// Runtimes started for one tool call. The launchers run each runtime in its own process group
+/// and record it here, so that dropping the call before it finishes, e.g. when its job is
+/// stopped, kills the runtimes along with the processes they started.
+pub struct ToolProcesses {
+    dir: Option<PathBuf>,
+    finished: bool,
+}
+
+impl ToolProcesses {
+    pub fn start() -> Self {
+        let dir = tool_launchers().and_then(|launchers| {
+            let dir = launchers.processes.join(uuid::Uuid::new_v4().to_string());
+            std::fs::create_dir_all(&dir).ok().map(|_| dir)
+        });
+        ToolProcesses { dir, finished: false }
+    }
+
+    /// Env vars making the runtimes of the call start through the launchers
+    pub fn envs(&self) -> HashMap<String, String> {
+        let mut envs = HashMap::new();
+        let (Some(dir), Some(launchers)) = (&self.dir, tool_launchers()) else {
+            return envs;
+        };
+        envs.insert(PROCESS_DIR_ENV.to_string(), dir.to_string_lossy().to_string());
+        envs.insert(
+            DENO_LAUNCHER_ENV.to_string(),
+            launchers.deno.to_string_lossy().to_string(),
+        );
+        envs.insert(UV_LAUNCHER_ENV.to_string(), launchers.uv.to_string_lossy().to_string());
+        envs
+    }
+
+    /// The call is over and its runtimes exited, so nothing is left to kill
+    pub fn finish(mut self) {
+        self.finished = true;
+    }
+}
+
+impl Drop for ToolProcesses {
+    fn drop(&mut self) {
+        let Some(dir) = &self.dir else {
+            return;
+        };
+        if !self.finished {
+            let pids = std::fs::read_dir(dir)
+                .into_iter()
+                .flatten()
+                .flatten()
+                .filter_map(|entry| entry.file_name().to_str()?.parse::<u32>().ok());
+            for pid in pids {
+                kill_process_group(pid);
+            }
+        }
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
+
+// On unix the launcher is the leader of the group of the runtime, elsewhere it is the parent of
+// the runtime
+fn kill_process_group(pid: u32) {
+    #[cfg(unix)]
+    let _ = Command::new("kill")
+        .args(["-KILL", "--", &format!("-{}", pid)])
+        .status();
+    #[cfg(not(unix))]
+    let _ = Command::new("taskkill")
+        .args(["/T", "/F", "/PID", &pid.to_string()])
+        .status();
+}
+
+// The real binary is kept next to the link, so the launcher finds it whatever env it gets
+fn link_launcher(folder: &Path, name: &str, target: &Path) -> std::io::Result<PathBuf> {
+    let launcher = folder.join(format!("{}{}", name, std::env::consts::EXE_SUFFIX));
+    let _ = std::fs::remove_file(&launcher);
+    let node_binary = std::env::current_exe()?;
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(&node_binary, &launcher)?;
+    #[cfg(not(unix))]
+    if std::fs::hard_link(&node_binary, &launcher).is_err() {
+        std::fs::copy(&node_binary, &launcher)?;
+    }
+
+    let target = std::fs::canonicalize(target).unwrap_or_else(|_| target.to_path_buf());
+    std::fs::write(
+        folder.join(format!("{}.{}", name, TARGET_SUFFIX)),
+        target.to_string_lossy().as_bytes(),
+    )?;
+    Ok(launcher)
+}
+
+/// Runs the real runtime and exits when the node binary was started through a launcher link.
+/// Must be called before anything else in `main`.
+pub fn run_if_launcher() {
+    let mut args = std::env::args_os();
+    let Some(launcher) = args.next().map(PathBuf::from) else {
+        return;
+    };
+    let name = match launcher.file_stem().and_then(|stem| stem.to_str()) {
+        Some(DENO_LAUNCHER) => DENO_LAUNCHER,
+        Some(UV_LAUNCHER) => UV_LAUNCHER,
+        _ => return,
+    };
+
+    let target_file = launcher.with_file_name(format!("{}.{}", name, TARGET_SUFFIX));
+    let target = match std::fs::read_to_string(&target_file) {
+        Ok(target) => PathBuf::from(target.trim()),
+        Err(e) => {
+            eprintln!("Failed to read the runtime of {}: {}", launcher.display(), e);
+            std::process::exit(1);
+        }
+    };
+
+    // Recorded so that the node can kill the runtime if the tool call is dropped
+    if let Some(dir) = std::env::var_os(PROCESS_DIR_ENV) {
+        let pid_file = Path::new(&dir).join(std::process::id().to_string());
+        if let Err(e) = std::fs::write(&pid_file, "") {
+            eprintln!("Failed to record the process of {}: {}", launcher.display(), e);
+        }
+    }
+
+    let args: Vec<OsString> = args.collect();
+    let command = match std::env::var(EGRESS_ALLOWED_ENV) {
+        Ok(allowed) if name == DENO_LAUNCHER => deno_args(args, &allowed).map(|args| {
+            let mut command = Command::new(&target);
+            command.args(args);
+            command
+        }),
+        Ok(allowed) => restricted_command(&target, args, &allowed),
+        Err(_) => {
+            let mut command = Command::new(&target);
+            command.args(args);
+            Ok(command)
+        }
+    };
+    let mut command = match command {
+        Ok(command) => command,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        // The runtime becomes the leader of a new process group, which also holds its children
+        command.process_group(0);
+        let e = command.exec();
+        eprintln!("Failed to start {}: {}", target.display(), e);
+        std::process::exit(1);
+    }
+    #[cfg(not(unix))]
+    match command.status() {
+        Ok(status) => std::process::exit(status.code().unwrap_or(1)),
+        Err(e) => {
+            eprintln!("Failed to start {}: {}", target.display(), e);
+            std::process::exit(1);
+        }
+    }
+}
+
+// Network permissions of Deno are narrowed to the allowed addresses
+fn deno_args(args: Vec<OsString>, allowed: &str) -> Result<Vec<OsString>, String> {
+    args.into_iter()
+        .map(|arg| match arg.to_str() {
+            Some("-A") | Some("--allow-all") => {
+                Err("Tools with a network allowlist can't run with --allow-all".to_string())
+            }
+            Some(flag) if flag == "--allow-net" || flag.starts_with("--allow-net=") => {
+                Ok(OsString::from(format!("--allow-net={}", allowed)))
+            }
+            _ => Ok(arg),
+        })
+        .collect()
+}
+
+// Python has no permission flags and can't be limited from the inside, `python -S`, ctypes or a
+// subprocess get around anything it loads, so uv and everything it starts are limited by the
+// kernel to TCP connections to the allowed ports
+fn restricted_command(target: &Path, args: Vec<OsString>, allowed: &str) -> Result<Command, String> {
+    let ports: Vec<u16> = allowed
+        .split(',')
+        .filter_map(|entry| entry.trim().rsplit_once(':')?.1.parse().ok())
+        .collect();
+
+    #[cfg(target_os = "linux")]
+    {
+        restrict_network(&ports)?;
+        let mut command = Command::new(target);
+        command.args(args);
+        Ok(command)
+    }
+    #[cfg(target_os = "macos")]
+    {
+        let mut profile = String::from("(version 1)(allow default)(deny network-outbound)");
+        profile.push_str("(allow network-outbound (remote unix-socket))");
+        for port in ports {
+            profile.push_str(&format!("(allow network-outbound (remote tcp \"localhost:{}\"))", port));
+        }
+        let mut command = Command::new("/usr/bin/sandbox-exec");
+        command.arg("-p").arg(profile).arg(target).args(args);
+        Ok(command)
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    {
+        let _ = (target, args, ports);
+        Err("Network access of Python tools can't be limited on this system, \
+             approve access to any host to run the tool"
+            .to_string())
+    }
+}
+
+// Landlock (Linux 6.7+) filters TCP connections by port only, so other hosts stay reachable on
+// the ports of the proxy and the node API
+#[cfg(target_os = "linux")]
+fn restrict_network(ports: &[u16]) -> Result<(), String> {
+    const LANDLOCK_CREATE_RULESET_VERSION: u32 = 1 << 0;
+    const LANDLOCK_ACCESS_NET_CONNECT_TCP: u64 = 1 << 1;
+    const LANDLOCK_RULE_NET_PORT: libc::c_int = 2;
+
+    #[repr(C)]
+    struct RulesetAttr {
+        handled_access_fs: u64,
+        handled_access_net: u64,
+    }
+    #[repr(C)]
+    struct NetPortAttr {
+        allowed_access: u64,
+        port: u64,
+    }
+
+    let unsupported = || {
+        "Network access of Python tools needs Landlock network rules (Linux 6.7 or later), \
+         approve access to any host to run the tool"
+            .to_string()
+    };
+    // SAFETY: the syscalls get pointers to live structs of the size they are told
+    unsafe {
+        let abi = libc::syscall(
+            libc::SYS_landlock_create_ruleset,
+            std::ptr::null::<RulesetAttr>(),
+            0usize,
+            LANDLOCK_CREATE_RULESET_VERSION,
+        );
+        if abi < 4 {
+            return Err(unsupported());
+        }
+
+        let ruleset = RulesetAttr {
+            handled_access_fs: 0,
+            handled_access_net: LANDLOCK_ACCESS_NET_CONNECT_TCP,
+        };
+        let fd = libc::syscall(
+            libc::SYS_landlock_create_ruleset,
+            &ruleset as *const RulesetAttr,
+            std::mem::size_of::<RulesetAttr>(),
+            0u32,
+        );
+        if fd < 0 {
+            return Err(unsupported());
+        }
+        let fd = fd as libc::c_int;
+
+        let mut result = Ok(());
+        for port in ports {
+            let rule = NetPortAttr {
+                allowed_access: LANDLOCK_ACCESS_NET_CONNECT_TCP,
+                port: u64::from(*port),
+            };
+            if libc::syscall(
+                libc::SYS_landlock_add_rule,
+                fd,
+                LANDLOCK_RULE_NET_PORT,
+                &rule as *const NetPortAttr,
+                0u32,
+            ) != 0
+            {
+                result = Err(format!(
+                    "Failed to allow port {}: {}",
+                    port,
+                    std::io::Error::last_os_error()
+                ));
+                break;
+            }
+        }
+        if result.is_ok() && libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0 {
+            result = Err(format!(
+                "Failed to restrict the process: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+        if result.is_ok() && libc::syscall(libc::SYS_landlock_restrict_self, fd, 0u32) != 0 {
+            result = Err(format!(
+                "Failed to restrict the process: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+        libc::close(fd);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deno_args_narrow_network_access() {
+        let args = vec![
+            OsString::from("run"),
+            OsString::from("--allow-env"),
+            OsString::from("--allow-net"),
+            OsString::from("index.ts"),
+        ];
+        let args = deno_args(args, "127.0.0.1:9560,127.0.0.1:9550").unwrap();
+        assert_eq!(args[2], OsString::from("--allow-net=127.0.0.1:9560,127.0.0.1:9550"));
+        assert_eq!(args[3], OsString::from("index.ts"));
+
+        assert!(deno_args(vec![OsString::from("run"), OsString::from("-A")], "127.0.0.1:9560").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_dropped_tool_processes_are_killed() {
+        use std::os::unix::process::CommandExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut child = Command::new("sleep").arg("30").process_group(0).spawn().unwrap();
+        std::fs::write(dir.path().join(child.id().to_string()), "").unwrap();
+
+        drop(ToolProcesses {
+            dir: Some(dir.path().to_path_buf()),
+            finished: false,
+        });
+        assert!(!child.wait().unwrap().success());
+        assert!(!dir.path().exists());
+    }
+}
@@ -10,6 +10,13 @@ pub struct Args {
     pub receiver_subidentity: Option<String>,
     pub inbox: Option<String>,
     pub body_content: Option<String>,
+    pub run_tool_tests: bool,
+    pub tool_router_key: Option<String>,
+    pub skip_network_tests: bool,
+    pub create_backup: Option<String>,
+    pub restore_backup: Option<String>,
+    pub backup_categories: Option<String>,
+    pub backup_passphrase: Option<String>,
 }
 
 pub fn parse_args() -> Args {
@@ -59,6 +66,41 @@ pub fn parse_args() -> Args {
                 .long("body_content")
                 .takes_value(true),
         )
+        .arg(
+            clap::Arg::new("run_tool_tests")
+                .long("run_tool_tests")
+                .takes_value(false),
+        )
+        .arg(
+            clap::Arg::new("tool_router_key")
+                .long("tool_router_key")
+                .takes_value(true),
+        )
+        .arg(
+            clap::Arg::new("skip_network_tests")
+                .long("skip_network_tests")
+                .takes_value(false),
+        )
+        .arg(
+            clap::Arg::new("create_backup")
+                .long("create_backup")
+                .takes_value(true),
+        )
+        .arg(
+            clap::Arg::new("restore_backup")
+                .long("restore_backup")
+                .takes_value(true),
+        )
+        .arg(
+            clap::Arg::new("backup_categories")
+                .long("backup_categories")
+                .takes_value(true),
+        )
+        .arg(
+            clap::Arg::new("backup_passphrase")
+                .long("backup_passphrase")
+                .takes_value(true),
+        )
         .get_matches();
 
     Args {
@@ -71,5 +113,16 @@ pub fn parse_args() -> Args {
         receiver_subidentity: matches.value_of("receiver_subidentity").map(String::from),
         inbox: matches.value_of("inbox").map(String::from),
         body_content: matches.value_of("body_content").map(String::from),
+        run_tool_tests: matches.is_present("run_tool_tests"),
+        tool_router_key: matches.value_of("tool_router_key").map(String::from),
+        skip_network_tests: matches.is_present("skip_network_tests"),
+        create_backup: matches.value_of("create_backup").map(String::from),
+        restore_backup: matches.value_of("restore_backup").map(String::from),
+        backup_categories: matches.value_of("backup_categories").map(String::from),
+        // Also read from the environment so the passphrase doesn't show up in the process list
+        backup_passphrase: matches
+            .value_of("backup_passphrase")
+            .map(String::from)
+            .or_else(|| std::env::var("BACKUP_PASSPHRASE").ok()),
     }
 }
@@ -1,6 +1,9 @@
 // src/utils/cli.rs
 use super::{args::Args, keys::NodeKeys};
+use async_channel::Sender;
+use shinkai_http_api::node_commands::NodeCommand;
 use shinkai_message_primitives::{
+    schemas::node_backup::{BackupCategory, CreateBackupRequest, RestoreBackupRequest},
     shinkai_message::shinkai_message_schemas::MessageSchemaType,
     shinkai_utils::{
         encryption::{string_to_encryption_public_key, EncryptionMethod},
@@ -8,6 +11,7 @@ use shinkai_message_primitives::{
         signatures::clone_signature_secret_key,
     },
 };
+use shinkai_tools_primitives::tools::tool_test_case::{ToolTestReport, ToolTestStatus};
 use x25519_dalek::PublicKey as EncryptionPublicKey;
 
 pub fn cli_handle_create_message(args: Args, node_keys: &NodeKeys, global_identity_name: &str) {
@@ -86,3 +90,200 @@ fn handle_create_message(
         Err(e) => println!("Error creating JSON: {}", e),
     }
 }
+
+/// Runs the declarative test cases of one tool (or every installed tool) through the
+/// running node and prints a report. Returns true if no test failed.
+pub async fn cli_handle_run_tool_tests(
+    node_commands_sender: Sender<NodeCommand>,
+    api_v2_key: String,
+    tool_router_key: Option<String>,
+    skip_network_tests: bool,
+) -> bool {
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    let sent = node_commands_sender
+        .send(NodeCommand::V2ApiRunToolTests {
+            bearer: api_v2_key,
+            tool_router_key,
+            skip_network_tests,
+            llm_provider: "".to_string(),
+            res: res_sender,
+        })
+        .await;
+    if sent.is_err() {
+        println!("Error: failed to send the tool tests command to the node");
+        return false;
+    }
+
+    let reports = match res_receiver.recv().await {
+        Ok(Ok(value)) => match serde_json::from_value::<Vec<ToolTestReport>>(value) {
+            Ok(reports) => reports,
+            Err(e) => {
+                println!("Error parsing tool test reports: {}", e);
+                return false;
+            }
+        },
+        Ok(Err(e)) => {
+            println!("Error running tool tests: {}", e.message);
+            return false;
+        }
+        Err(e) => {
+            println!("Error receiving tool test reports: {}", e);
+            return false;
+        }
+    };
+
+    let mut all_passed = true;
+    for report in reports {
+        println!(
+            "{}: {} passed, {} failed, {} skipped",
+            report.tool_router_key, report.passed, report.failed, report.skipped
+        );
+        for result in report.results {
+            let status = match result.status {
+                ToolTestStatus::Passed => "PASS",
+                ToolTestStatus::Failed => "FAIL",
+                ToolTestStatus::Skipped => "SKIP",
+                ToolTestStatus::Error => "ERROR",
+            };
+            println!("  [{}] {} ({} ms)", status, result.name, result.duration_ms);
+            for message in result.messages {
+                println!("      {}", message);
+            }
+        }
+        all_passed &= report.failed == 0;
+    }
+    all_passed
+}
+
+/// Parses a comma separated list of backup categories, e.g. `agents,llm_providers,tools`
+fn parse_backup_categories(categories: Option<String>) -> Result<Vec<BackupCategory>, String> {
+    categories
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|category| !category.is_empty())
+        .map(|category| {
+            serde_json::from_value(serde_json::Value::String(category.to_string()))
+                .map_err(|_| format!("Unknown backup category: {}", category))
+        })
+        .collect()
+}
+
+/// Backs up the running node to `path`. Returns true if the archive was written.
+pub async fn cli_handle_create_backup(
+    node_commands_sender: Sender<NodeCommand>,
+    api_v2_key: String,
+    path: String,
+    categories: Option<String>,
+    passphrase: Option<String>,
+) -> bool {
+    let categories = match parse_backup_categories(categories) {
+        Ok(categories) => categories,
+        Err(e) => {
+            println!("Error: {}", e);
+            return false;
+        }
+    };
+
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    let sent = node_commands_sender
+        .send(NodeCommand::V2ApiCreateBackup {
+            bearer: api_v2_key,
+            payload: CreateBackupRequest { categories, passphrase },
+            res: res_sender,
+        })
+        .await;
+    if sent.is_err() {
+        println!("Error: failed to send the backup command to the node");
+        return false;
+    }
+
+    match res_receiver.recv().await {
+        Ok(Ok(mut archive)) => {
+            let written = std::fs::File::create(&path).and_then(|mut file| std::io::copy(&mut archive, &mut file));
+            match written {
+                Ok(_) => {
+                    println!("Backup written to {}", path);
+                    true
+                }
+                Err(e) => {
+                    println!("Error writing the backup to {}: {}", path, e);
+                    false
+                }
+            }
+        }
+        Ok(Err(e)) => {
+            println!("Error creating the backup: {}", e.message);
+            false
+        }
+        Err(e) => {
+            println!("Error receiving the backup: {}", e);
+            false
+        }
+    }
+}
+
+/// Restores the backup at `path` into the running node. Returns true if it was restored.
+pub async fn cli_handle_restore_backup(
+    node_commands_sender: Sender<NodeCommand>,
+    api_v2_key: String,
+    path: String,
+    categories: Option<String>,
+    passphrase: Option<String>,
+) -> bool {
+    let categories = match parse_backup_categories(categories) {
+        Ok(categories) => categories,
+        Err(e) => {
+            println!("Error: {}", e);
+            return false;
+        }
+    };
+    let file = match std::fs::File::open(&path) {
+        Ok(file) => file,
+        Err(e) => {
+            println!("Error reading the backup {}: {}", path, e);
+            return false;
+        }
+    };
+
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    let sent = node_commands_sender
+        .send(NodeCommand::V2ApiRestoreBackup {
+            bearer: api_v2_key,
+            file,
+            payload: RestoreBackupRequest { categories, passphrase },
+            res: res_sender,
+        })
+        .await;
+    if sent.is_err() {
+        println!("Error: failed to send the restore command to the node");
+        return false;
+    }
+
+    match res_receiver.recv().await {
+        Ok(Ok(report)) => {
+            let categories: Vec<String> = report.categories.iter().map(|category| category.to_string()).collect();
+            println!(
+                "Restored {} ({} rows, {} files)",
+                categories.join(", "),
+                report.rows_restored,
+                report.files_restored
+            );
+            if let Some(renamed_from) = report.renamed_from {
+                println!("Renamed {} to the name of this node", renamed_from);
+            }
+            if report.restart_required {
+                println!("Restart the node to load the restored wallets");
+            }
+            true
+        }
+        Ok(Err(e)) => {
+            println!("Error restoring the backup: {}", e.message);
+            false
+        }
+        Err(e) => {
+            println!("Error receiving the restore result: {}", e);
+            false
+        }
+    }
+}
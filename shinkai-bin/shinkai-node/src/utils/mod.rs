@@ -5,4 +5,5 @@ pub mod keys;
 pub mod logging_helpers;
 pub mod printer;
 pub mod qr_code_setup;
+pub mod telemetry;
 pub mod update_global_identity;
\ No newline at end of file
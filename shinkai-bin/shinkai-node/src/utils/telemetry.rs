@@ -0,0 +1,343 @@
+use std::env;
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use chrono::Utc;
+use serde_json::{json, Map, Value};
+use shinkai_message_primitives::shinkai_utils::shinkai_logging::tracing_filter_level;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields};
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{EnvFilter, Layer, Registry};
+
+/// Environment variable carrying the W3C trace context to Deno and Python tool processes
+pub const TRACEPARENT_ENV: &str = "TRACEPARENT";
+
+/// Only spans created by the node are exported, not the ones wrapping `shinkai_log` calls
+const EXPORTED_TARGET_PREFIX: &str = "shinkai_node";
+const EXPORT_INTERVAL: Duration = Duration::from_secs(5);
+const MAX_EXPORT_BATCH: usize = 512;
+
+#[derive(Debug, Clone)]
+pub struct TelemetryConfig {
+    /// `LOG_FORMAT=json` writes one JSON object per log line
+    pub json_logs: bool,
+    /// `OTEL_EXPORTER_OTLP_ENDPOINT`, e.g. http://localhost:4318. Spans are only exported when set.
+    pub otlp_endpoint: Option<String>,
+    /// `OTEL_SERVICE_NAME`, defaults to shinkai-node
+    pub service_name: String,
+}
+
+impl TelemetryConfig {
+    pub fn from_env() -> Self {
+        TelemetryConfig {
+            json_logs: env::var("LOG_FORMAT")
+                .map(|format| format.eq_ignore_ascii_case("json"))
+                .unwrap_or(false),
+            otlp_endpoint: env::var("OTEL_EXPORTER_OTLP_ENDPOINT")
+                .ok()
+                .filter(|endpoint| !endpoint.is_empty()),
+            service_name: env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| "shinkai-node".to_string()),
+        }
+    }
+}
+
+/// Installs the global tracing subscriber. Must be called from within the tokio runtime
+/// when the OTLP exporter is enabled.
+pub fn init_tracing() {
+    let config = TelemetryConfig::from_env();
+
+    let exporter = config.otlp_endpoint.clone().map(|endpoint| {
+        let (sender, receiver) = unbounded_channel();
+        tokio::spawn(export_spans(endpoint, config.service_name.clone(), receiver));
+        sender
+    });
+
+    let fmt_layer = if config.json_logs {
+        tracing_subscriber::fmt::layer().event_format(JsonEventFormat).boxed()
+    } else {
+        tracing_subscriber::fmt::layer()
+            .with_timer(tracing_subscriber::fmt::time::time())
+            .with_target(true)
+            .boxed()
+    };
+
+    let subscriber = Registry::default()
+        .with(EnvFilter::new(tracing_filter_level()))
+        .with(SpanContextLayer::new(exporter))
+        .with(fmt_layer);
+    let _ = tracing::subscriber::set_global_default(subscriber);
+}
+
+/// W3C `traceparent` of the current span, if any
+pub fn current_traceparent() -> Option<String> {
+    let id = tracing::Span::current().id()?;
+    tracing::dispatcher::get_default(|dispatch| {
+        let registry = dispatch.downcast_ref::<Registry>()?;
+        let span = registry.span(&id)?;
+        let extensions = span.extensions();
+        extensions.get::<SpanContext>().map(SpanContext::traceparent)
+    })
+}
+
+/// Trace identifiers and fields of a span, stored in the span extensions
+#[derive(Debug, Clone)]
+pub struct SpanContext {
+    pub trace_id: String,
+    pub span_id: String,
+    pub parent_span_id: Option<String>,
+    start: SystemTime,
+    attributes: Map<String, Value>,
+}
+
+impl SpanContext {
+    pub fn traceparent(&self) -> String {
+        format!("00-{}-{}-01", self.trace_id, self.span_id)
+    }
+
+    fn to_otlp_span(&self, name: &str, end: SystemTime) -> Value {
+        let attributes: Vec<Value> = self
+            .attributes
+            .iter()
+            .map(|(key, value)| {
+                let value = match value {
+                    Value::Bool(value) => json!({ "boolValue": value }),
+                    Value::Number(number) if number.is_i64() || number.is_u64() => {
+                        json!({ "intValue": number.to_string() })
+                    }
+                    Value::Number(number) => json!({ "doubleValue": number.as_f64() }),
+                    Value::String(value) => json!({ "stringValue": value }),
+                    other => json!({ "stringValue": other.to_string() }),
+                };
+                json!({ "key": key, "value": value })
+            })
+            .collect();
+
+        json!({
+            "traceId": self.trace_id,
+            "spanId": self.span_id,
+            "parentSpanId": self.parent_span_id.clone().unwrap_or_default(),
+            "name": name,
+            // SPAN_KIND_INTERNAL
+            "kind": 1,
+            "startTimeUnixNano": unix_nanos(self.start).to_string(),
+            "endTimeUnixNano": unix_nanos(end).to_string(),
+            "attributes": attributes,
+        })
+    }
+}
+
+fn unix_nanos(time: SystemTime) -> u128 {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos()
+}
+
+struct JsonVisitor<'a>(&'a mut Map<String, Value>);
+
+impl Visit for JsonVisitor<'_> {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.0.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.insert(field.name().to_string(), json!(format!("{:?}", value)));
+    }
+}
+
+/// Assigns trace and span ids to every span and sends the node spans to the OTLP exporter
+/// when they close
+pub struct SpanContextLayer {
+    exporter: Option<UnboundedSender<Value>>,
+}
+
+impl SpanContextLayer {
+    pub fn new(exporter: Option<UnboundedSender<Value>>) -> Self {
+        SpanContextLayer { exporter }
+    }
+}
+
+impl<S> Layer<S> for SpanContextLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let parent = span.parent().and_then(|parent| {
+            parent
+                .extensions()
+                .get::<SpanContext>()
+                .map(|parent| (parent.trace_id.clone(), parent.span_id.clone()))
+        });
+        let (trace_id, parent_span_id) = match parent {
+            Some((trace_id, parent_span_id)) => (trace_id, Some(parent_span_id)),
+            None => (hex::encode(rand::random::<[u8; 16]>()), None),
+        };
+
+        let mut attributes = Map::new();
+        attrs.record(&mut JsonVisitor(&mut attributes));
+        span.extensions_mut().insert(SpanContext {
+            trace_id,
+            span_id: hex::encode(rand::random::<[u8; 8]>()),
+            parent_span_id,
+            start: SystemTime::now(),
+            attributes,
+        });
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            if let Some(span_context) = span.extensions_mut().get_mut::<SpanContext>() {
+                values.record(&mut JsonVisitor(&mut span_context.attributes));
+            }
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let Some(exporter) = &self.exporter else {
+            return;
+        };
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+        if !span.metadata().target().starts_with(EXPORTED_TARGET_PREFIX) {
+            return;
+        }
+        let extensions = span.extensions();
+        if let Some(span_context) = extensions.get::<SpanContext>() {
+            let _ = exporter.send(span_context.to_otlp_span(span.name(), SystemTime::now()));
+        }
+    }
+}
+
+/// Writes each event as a single JSON object with the fields of the spans it happened in
+pub struct JsonEventFormat;
+
+impl<S, N> FormatEvent<S, N> for JsonEventFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(&self, ctx: &FmtContext<'_, S, N>, mut writer: Writer<'_>, event: &Event<'_>) -> fmt::Result {
+        let metadata = event.metadata();
+        let mut fields = Map::new();
+        event.record(&mut JsonVisitor(&mut fields));
+
+        let mut line = Map::new();
+        line.insert("timestamp".to_string(), json!(Utc::now().to_rfc3339()));
+        line.insert("level".to_string(), json!(metadata.level().to_string()));
+        line.insert("target".to_string(), json!(metadata.target()));
+        line.insert("fields".to_string(), Value::Object(fields));
+
+        if let Some(scope) = ctx.event_scope() {
+            let mut spans = Vec::new();
+            for span in scope.from_root() {
+                let mut entry = Map::new();
+                entry.insert("name".to_string(), json!(span.name()));
+                if let Some(span_context) = span.extensions().get::<SpanContext>() {
+                    entry.extend(span_context.attributes.clone());
+                    line.insert("trace_id".to_string(), json!(span_context.trace_id));
+                    line.insert("span_id".to_string(), json!(span_context.span_id));
+                }
+                spans.push(Value::Object(entry));
+            }
+            line.insert("spans".to_string(), Value::Array(spans));
+        }
+
+        writeln!(writer, "{}", Value::Object(line))
+    }
+}
+
+async fn export_spans(endpoint: String, service_name: String, mut receiver: UnboundedReceiver<Value>) {
+    let client = reqwest::Client::new();
+    let url = format!("{}/v1/traces", endpoint.trim_end_matches('/'));
+    let mut interval = tokio::time::interval(EXPORT_INTERVAL);
+    let mut batch = Vec::new();
+
+    loop {
+        tokio::select! {
+            span = receiver.recv() => match span {
+                Some(span) => {
+                    batch.push(span);
+                    if batch.len() < MAX_EXPORT_BATCH {
+                        continue;
+                    }
+                }
+                None => break,
+            },
+            _ = interval.tick() => {}
+        }
+        if batch.is_empty() {
+            continue;
+        }
+
+        let body = json!({
+            "resourceSpans": [{
+                "resource": {
+                    "attributes": [{ "key": "service.name", "value": { "stringValue": service_name } }]
+                },
+                "scopeSpans": [{
+                    "scope": { "name": EXPORTED_TARGET_PREFIX },
+                    "spans": std::mem::take(&mut batch),
+                }]
+            }]
+        });
+        // Errors go to stderr; logging them through tracing would feed back into the exporter
+        match client.post(&url).json(&body).send().await {
+            Ok(response) if !response.status().is_success() => {
+                eprintln!("OTLP exporter got status {} from {}", response.status(), url);
+            }
+            Err(e) => eprintln!("OTLP exporter failed to reach {}: {}", url, e),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_traceparent_follows_span_tree() {
+        let subscriber = Registry::default().with(SpanContextLayer::new(None));
+        tracing::subscriber::with_default(subscriber, || {
+            assert!(current_traceparent().is_none());
+
+            let job_span = tracing::info_span!("job", job_id = "job-1");
+            let _job = job_span.enter();
+            let job_traceparent = current_traceparent().unwrap();
+            let tool_traceparent = tracing::info_span!("tool", tool = "local:::echo")
+                .in_scope(current_traceparent)
+                .unwrap();
+
+            let job_parts: Vec<&str> = job_traceparent.split('-').collect();
+            let tool_parts: Vec<&str> = tool_traceparent.split('-').collect();
+            assert_eq!(job_parts.len(), 4);
+            assert_eq!(job_parts[1].len(), 32);
+            assert_eq!(job_parts[2].len(), 16);
+            assert_eq!(job_parts[1], tool_parts[1]);
+            assert_ne!(job_parts[2], tool_parts[2]);
+        });
+    }
+}
@@ -100,9 +100,12 @@ impl CoinbaseMPCWallet {
             Some(cfg) => cfg,
             None => {
                 let tool_id = ShinkaiToolCoinbase::CreateWallet.definition_id();
-                let shinkai_tool = sqlite_manager_strong
+                let mut shinkai_tool = sqlite_manager_strong
                     .get_tool_by_key(tool_id)
                     .map_err(|e| WalletError::SqliteManagerError(e.to_string()))?;
+                sqlite_manager_strong
+                    .reveal_tool_config(&mut shinkai_tool)
+                    .map_err(|e| WalletError::SqliteManagerError(e.to_string()))?;
 
                 // Extract the required configuration from the JSTool
                 let mut name = String::new();
@@ -200,9 +203,12 @@ impl CoinbaseMPCWallet {
             Some(cfg) => cfg,
             None => {
                 let tool_id = ShinkaiToolCoinbase::CreateWallet.definition_id();
-                let shinkai_tool = sqlite_manager_strong
+                let mut shinkai_tool = sqlite_manager_strong
                     .get_tool_by_key(tool_id)
                     .map_err(|e| WalletError::SqliteManagerError(e.to_string()))?;
+                sqlite_manager_strong
+                    .reveal_tool_config(&mut shinkai_tool)
+                    .map_err(|e| WalletError::SqliteManagerError(e.to_string()))?;
 
                 // Extract the required configuration from the JSTool
                 let mut name = String::new();
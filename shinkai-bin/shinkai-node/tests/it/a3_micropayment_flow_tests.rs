@@ -114,7 +114,8 @@ fn micropayment_flow_test() {
             supported_embedding_models(),
             Some(api_v2_key.to_string()),
         )
-        .await;
+        .await
+        .unwrap();
 
         let addr2 = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8081);
         let node2 = Node::new(
@@ -137,7 +138,8 @@ fn micropayment_flow_test() {
             supported_embedding_models(),
             Some(api_v2_key.to_string()),
         )
-        .await;
+        .await
+        .unwrap();
 
         // Printing
         eprintln!(
@@ -72,7 +72,7 @@ fn change_nodes_name_test() {
         );
 
         let node1_handler = tokio::spawn(async move {
-            let _ = node1.await.lock().await.start().await;
+            let _ = node1.await.unwrap().lock().await.start().await;
         });
 
         let abort_handler = node1_handler.abort_handle();
@@ -167,7 +167,7 @@ fn change_nodes_name_test() {
         );
 
         let node1_handler = tokio::spawn(async move {
-            let _ = node1.await.lock().await.start().await;
+            let _ = node1.await.unwrap().lock().await.start().await;
         });
 
         let abort_handler = node1_handler.abort_handle();
@@ -196,6 +196,8 @@ fn tool_duplicate_tests() {
                     runner: RunnerType::OnlyHost,
                     operating_system: vec![OperatingSystem::Windows],
                     tool_set: None,
+                    test_cases: None,
+                    network_allowlist: None,
                 };
                 eprintln!("\nCreate a tool");
                 let (res_sender, res_receiver) = async_channel::bounded(1);
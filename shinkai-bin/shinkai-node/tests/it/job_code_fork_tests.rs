@@ -212,6 +212,8 @@ fn test_job_code_fork() {
                         runner: RunnerType::Any,
                         operating_system: vec![OperatingSystem::Linux, OperatingSystem::MacOS, OperatingSystem::Windows],
                         tool_set: None,
+                        test_cases: None,
+                        network_allowlist: None,
                     },
                     tool_router_key: None,
                     job_id: job_id.clone(),
@@ -16,7 +16,9 @@ use shinkai_message_primitives::{
     },
     shinkai_utils::{shinkai_message_builder::ShinkaiMessageBuilder, signatures::clone_signature_secret_key},
 };
+use shinkai_node::llm_provider::error::LLMProviderError;
 use shinkai_node::llm_provider::job_callback_manager::JobCallbackManager;
+use shinkai_node::llm_provider::job_limiter::JobLimiter;
 use shinkai_node::llm_provider::job_manager::JobManager;
 use shinkai_node::llm_provider::llm_stopper::LLMStopper;
 use shinkai_node::managers::sheet_manager::SheetManager;
@@ -159,6 +161,7 @@ async fn test_process_job_queue_concurrency() {
         None,
         None,
         llm_stopper.clone(),
+        Arc::new(JobLimiter::new()),
         move |job: JobForProcessing,
               _db: Weak<SqliteManager>,
               node_name: ShinkaiName,
@@ -308,6 +311,7 @@ async fn test_sequential_process_for_same_job_id() {
         None,
         None,
         llm_stopper.clone(),
+        Arc::new(JobLimiter::new()),
         move |job: JobForProcessing,
               _db: Weak<SqliteManager>,
               node_name: ShinkaiName,
@@ -378,3 +382,112 @@ async fn test_sequential_process_for_same_job_id() {
         // Handle the error case if necessary
     }
 }
+
+#[tokio::test]
+async fn test_retried_message_frees_the_worker() {
+    let db = utils::db_handlers::setup_test_db();
+    let db = Arc::new(db);
+    let db_weak = Arc::downgrade(&db);
+    let (node_identity_sk, _) = unsafe_deterministic_signature_keypair(0);
+    let node_name = ShinkaiName::new("@@node1.shinkai".to_string()).unwrap();
+
+    let mut job_queue = JobQueueManager::<JobForProcessing>::new(db_weak.clone(), None)
+        .await
+        .unwrap();
+    let job_queue_manager = Arc::new(Mutex::new(job_queue.clone()));
+    let job_queue_manager_immediate = Arc::new(Mutex::new(job_queue.clone()));
+    let sheet_manager = Arc::new(Mutex::new(
+        SheetManager::new(db_weak.clone(), node_name.clone(), None)
+            .await
+            .unwrap(),
+    ));
+    let processed = Arc::new(Mutex::new(Vec::new()));
+
+    // The first attempt of job_a fails and is queued again, like a transient provider error
+    let processed_clone = processed.clone();
+    let job_queue_handler = JobManager::process_job_queue(
+        job_queue_manager.clone(),
+        job_queue_manager_immediate.clone(),
+        db_weak.clone(),
+        node_name.clone(),
+        1,
+        clone_signature_secret_key(&node_identity_sk),
+        RemoteEmbeddingGenerator::new_default(),
+        None,
+        None,
+        sheet_manager,
+        Arc::new(Mutex::new(JobCallbackManager::new())),
+        None,
+        None,
+        Arc::new(LLMStopper::new()),
+        Arc::new(JobLimiter::new()),
+        move |job: JobForProcessing,
+              _db: Weak<SqliteManager>,
+              _node_name: ShinkaiName,
+              _identity_sk: SigningKey,
+              _generator: RemoteEmbeddingGenerator,
+              _ws_manager: Option<Arc<Mutex<dyn WSUpdateHandler + Send>>>,
+              _tool_router: Option<Arc<ToolRouter>>,
+              _sheet_manager: Arc<Mutex<SheetManager>>,
+              _callback_manager: Arc<Mutex<JobCallbackManager>>,
+              job_queue_manager: Arc<Mutex<JobQueueManager<JobForProcessing>>>,
+              _my_agent_payments_manager: Option<Arc<Mutex<MyAgentOfferingsManager>>>,
+              _ext_agent_payments_manager: Option<Arc<Mutex<ExtAgentOfferingsManager>>>,
+              _llm_stopper: Arc<LLMStopper>| {
+            let processed = processed_clone.clone();
+            Box::pin(async move {
+                let job_id = job.job_message.job_id.clone();
+                processed
+                    .lock()
+                    .await
+                    .push(format!("{}:{}", job_id, job.failed_attempts));
+                if job_id == "job_a" && job.failed_attempts == 0 {
+                    let backoff = Duration::from_millis(300);
+                    let retry = job.retried_after(backoff);
+                    job_queue_manager
+                        .lock()
+                        .await
+                        .replace_first(&job_id, retry)
+                        .await
+                        .unwrap();
+                    return Err(LLMProviderError::RetryScheduled(backoff));
+                }
+                Ok(job_id)
+            })
+        },
+    )
+    .await;
+
+    for job_id in ["job_a", "job_b"] {
+        let job = JobForProcessing::new(
+            JobMessage {
+                job_id: job_id.to_string(),
+                content: "my content".to_string(),
+                parent: None,
+                sheet_job_data: None,
+                callback: None,
+                metadata: None,
+                tool_key: None,
+                fs_files_paths: vec![],
+                job_filenames: vec![],
+                tools: None,
+            },
+            ShinkaiName::new("@@node1.shinkai/main".to_string()).unwrap(),
+            None,
+        );
+        job_queue.push(job_id, job).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    // job_b runs while job_a waits for its retry, without holding the only worker slot
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    assert_eq!(*processed.lock().await, vec!["job_a:0", "job_b:0"]);
+    assert_eq!(job_queue.peek("job_a").await.unwrap().unwrap().failed_attempts, 1);
+
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert_eq!(*processed.lock().await, vec!["job_a:0", "job_b:0", "job_a:1"]);
+    assert_eq!(job_queue.peek("job_a").await.unwrap(), None);
+    assert_eq!(job_queue.peek("job_b").await.unwrap(), None);
+
+    job_queue_handler.abort();
+}
@@ -102,7 +102,8 @@ fn subidentity_registration() {
             supported_embedding_models(),
             None,
         )
-        .await;
+        .await
+        .unwrap();
 
         let addr2 = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8081);
         let node2 = Node::new(
@@ -125,7 +126,8 @@ fn subidentity_registration() {
             supported_embedding_models(),
             None,
         )
-        .await;
+        .await
+        .unwrap();
 
         // Printing
         eprintln!(
@@ -102,7 +102,8 @@ fn node_retrying_test() {
             supported_embedding_models(),
             None,
         )
-        .await;
+        .await
+        .unwrap();
 
         let addr2 = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8081);
         let node2 = Node::new(
@@ -126,7 +127,8 @@ fn node_retrying_test() {
             supported_embedding_models(),
             None,
         )
-        .await;
+        .await
+        .unwrap();
 
         eprintln!("Starting nodes");
         // Start node1 and node2
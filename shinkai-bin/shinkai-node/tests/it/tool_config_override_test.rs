@@ -105,7 +105,7 @@ fn test_tool_execution_with_config_override() {
 
         let node1_handler = tokio::spawn(async move {
             shinkai_log(ShinkaiLogOption::Tests, ShinkaiLogLevel::Debug, "Starting Node 1");
-            let _ = node1.await.lock().await.start().await;
+            let _ = node1.await.unwrap().lock().await.start().await;
         });
 
         let abort_handler = node1_handler.abort_handle();
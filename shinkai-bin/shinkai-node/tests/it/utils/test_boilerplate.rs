@@ -133,7 +133,8 @@ where
             supported_embedding_models(),
             Some(node1_api_key.clone()),
         )
-        .await;
+        .await
+        .unwrap();
 
         let node1_locked = node1.lock().await;
         let node1_db = node1_locked.db.clone();
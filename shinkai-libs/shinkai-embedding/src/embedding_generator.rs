@@ -9,7 +9,8 @@ use reqwest::blocking::Client;
 use reqwest::Client as AsyncClient;
 use reqwest::ClientBuilder;
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
 
 // TODO: remove duplicate methods
 // TODO: remove blocking / non-blocking methods
@@ -19,6 +20,38 @@ lazy_static! {
     pub static ref DEFAULT_EMBEDDINGS_LOCAL_URL: &'static str = "http://localhost:11434/";
 }
 
+static EMBEDDING_REQUESTS: AtomicU64 = AtomicU64::new(0);
+static EMBEDDING_INPUTS: AtomicU64 = AtomicU64::new(0);
+static EMBEDDING_ERRORS: AtomicU64 = AtomicU64::new(0);
+static EMBEDDING_DURATION_MICROS: AtomicU64 = AtomicU64::new(0);
+
+/// Running totals of the embedding requests made by this process
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct EmbeddingStats {
+    pub requests: u64,
+    pub inputs: u64,
+    pub errors: u64,
+    pub duration_micros: u64,
+}
+
+pub fn embedding_stats() -> EmbeddingStats {
+    EmbeddingStats {
+        requests: EMBEDDING_REQUESTS.load(Ordering::Relaxed),
+        inputs: EMBEDDING_INPUTS.load(Ordering::Relaxed),
+        errors: EMBEDDING_ERRORS.load(Ordering::Relaxed),
+        duration_micros: EMBEDDING_DURATION_MICROS.load(Ordering::Relaxed),
+    }
+}
+
+fn record_embedding_request(inputs: usize, duration: Duration, success: bool) {
+    EMBEDDING_REQUESTS.fetch_add(1, Ordering::Relaxed);
+    EMBEDDING_INPUTS.fetch_add(inputs as u64, Ordering::Relaxed);
+    EMBEDDING_DURATION_MICROS.fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+    if !success {
+        EMBEDDING_ERRORS.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
 /// A trait for types that can generate embeddings from text.
 #[async_trait]
 pub trait EmbeddingGenerator: Sync + Send {
@@ -137,18 +170,27 @@ impl EmbeddingGenerator for RemoteEmbeddingGenerator {
             .map(|s| s.chars().take(self.model_type.max_input_token_count()).collect())
             .collect();
 
-        match self.model_type.clone() {
+        let started = Instant::now();
+        let embeddings = match self.model_type.clone() {
             EmbeddingModelType::OllamaTextEmbeddingsInference(model) => {
                 let mut embeddings = Vec::new();
                 for input_string in input_strings.iter() {
-                    let embedding = self
+                    match self
                         .generate_embedding_ollama(input_string.clone(), model.to_string())
-                        .await?;
-                    embeddings.push(embedding);
+                        .await
+                    {
+                        Ok(embedding) => embeddings.push(embedding),
+                        Err(e) => {
+                            record_embedding_request(input_strings.len(), started.elapsed(), false);
+                            return Err(e);
+                        }
+                    }
                 }
-                Ok(embeddings)
+                embeddings
             }
-        }
+        };
+        record_embedding_request(input_strings.len(), started.elapsed(), true);
+        Ok(embeddings)
     }
 
     /// Generate an Embedding for an input string by using the external API.
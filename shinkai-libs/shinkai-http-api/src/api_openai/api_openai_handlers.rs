@@ -0,0 +1,168 @@
+use std::convert::Infallible;
+
+use async_channel::Sender;
+use bytes::Bytes;
+use futures::StreamExt;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use utoipa::ToSchema;
+use warp::sse::Event;
+
+use crate::{node_api_router::APIError, node_commands::NodeCommand};
+
+#[derive(Deserialize, Serialize, ToSchema, Debug, Clone)]
+pub struct OpenAIChatCompletionRequest {
+    /// Id of the agent or LLM provider answering the conversation
+    pub model: String,
+    pub messages: Vec<OpenAIChatMessage>,
+    #[serde(default)]
+    pub stream: bool,
+    pub temperature: Option<f64>,
+    pub top_p: Option<f64>,
+    pub max_tokens: Option<u64>,
+    pub max_completion_tokens: Option<u64>,
+    pub seed: Option<u64>,
+}
+
+#[derive(Deserialize, Serialize, ToSchema, Debug, Clone)]
+pub struct OpenAIChatMessage {
+    pub role: String,
+    /// Either a string or a list of content parts
+    #[schema(value_type = Object)]
+    pub content: Option<Value>,
+}
+
+impl OpenAIChatMessage {
+    /// Text of the message. Non text content parts (images, audio) are ignored.
+    pub fn text(&self) -> String {
+        match &self.content {
+            Some(Value::String(text)) => text.clone(),
+            Some(Value::Array(parts)) => parts
+                .iter()
+                .filter_map(|part| part.get("text").and_then(|text| text.as_str()))
+                .collect::<Vec<&str>>()
+                .join("\n"),
+            _ => String::new(),
+        }
+    }
+}
+
+fn bearer_from_header(authorization: Option<String>) -> String {
+    authorization
+        .and_then(|authorization| authorization.strip_prefix("Bearer ").map(|bearer| bearer.to_string()))
+        .unwrap_or_default()
+}
+
+/// Error body in the format OpenAI clients expect
+pub fn openai_error_body(error: &APIError) -> Value {
+    let error_type = match error.code {
+        400 | 404 => "invalid_request_error",
+        401 => "authentication_error",
+        403 => "permission_error",
+        _ => "server_error",
+    };
+    json!({
+        "error": {
+            "message": error.message,
+            "type": error_type,
+            "param": null,
+            "code": null,
+        }
+    })
+}
+
+fn openai_error_reply(error: &APIError) -> Box<dyn warp::Reply> {
+    let status = StatusCode::from_u16(error.code).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    Box::new(warp::reply::with_status(
+        warp::reply::json(&openai_error_body(error)),
+        status,
+    ))
+}
+
+pub async fn chat_completions_handler(
+    node_commands_sender: Sender<NodeCommand>,
+    authorization: Option<String>,
+    body: Bytes,
+) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
+    let bearer = bearer_from_header(authorization);
+    let request: OpenAIChatCompletionRequest = match serde_json::from_slice(&body) {
+        Ok(request) => request,
+        Err(e) => {
+            return Ok(openai_error_reply(&APIError::new(
+                StatusCode::BAD_REQUEST,
+                "Bad Request",
+                &format!("Invalid request body: {}", e),
+            )))
+        }
+    };
+    let stream = request.stream;
+
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    node_commands_sender
+        .send(NodeCommand::V2ApiOpenAIChatCompletion {
+            bearer,
+            request,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+
+    let completions = match result {
+        Ok(completions) => completions,
+        Err(error) => return Ok(openai_error_reply(&error)),
+    };
+
+    if stream {
+        let events = completions
+            .map(|chunk| {
+                let data = match chunk {
+                    Ok(chunk) => chunk.to_string(),
+                    Err(error) => openai_error_body(&error).to_string(),
+                };
+                Ok::<_, Infallible>(Event::default().data(data))
+            })
+            .chain(futures::stream::once(async {
+                Ok::<_, Infallible>(Event::default().data("[DONE]"))
+            }));
+        return Ok(Box::new(warp::sse::reply(warp::sse::keep_alive().stream(events))));
+    }
+
+    match completions.recv().await {
+        Ok(Ok(completion)) => Ok(Box::new(warp::reply::with_status(
+            warp::reply::json(&completion),
+            StatusCode::OK,
+        ))),
+        Ok(Err(error)) => Ok(openai_error_reply(&error)),
+        Err(_) => Ok(openai_error_reply(&APIError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Internal Server Error",
+            "The completion ended without a response",
+        ))),
+    }
+}
+
+pub async fn list_models_handler(
+    node_commands_sender: Sender<NodeCommand>,
+    authorization: Option<String>,
+) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
+    let bearer = bearer_from_header(authorization);
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    node_commands_sender
+        .send(NodeCommand::V2ApiOpenAIListModels {
+            bearer,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+
+    match result {
+        Ok(models) => Ok(Box::new(warp::reply::with_status(
+            warp::reply::json(&models),
+            StatusCode::OK,
+        ))),
+        Err(error) => Ok(openai_error_reply(&error)),
+    }
+}
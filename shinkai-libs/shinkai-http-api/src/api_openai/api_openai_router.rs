@@ -0,0 +1,29 @@
+use async_channel::Sender;
+use warp::Filter;
+
+use crate::api_v2::api_v2_router::with_sender;
+use crate::node_commands::NodeCommand;
+
+use super::api_openai_handlers::{chat_completions_handler, list_models_handler};
+
+pub fn openai_routes(
+    node_commands_sender: Sender<NodeCommand>,
+    _node_name: String,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    // Authorization and body are parsed by the handlers so every failure is answered
+    // with an OpenAI style error instead of a warp rejection
+    let chat_completions_route = warp::path!("chat" / "completions")
+        .and(warp::post())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::body::bytes())
+        .and_then(chat_completions_handler);
+
+    let models_route = warp::path!("models")
+        .and(warp::get())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::optional::<String>("authorization"))
+        .and_then(list_models_handler);
+
+    chat_completions_route.or(models_route)
+}
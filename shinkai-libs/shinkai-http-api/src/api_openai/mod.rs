@@ -0,0 +1,5 @@
+//! OpenAI-compatible endpoints (`/v1/chat/completions` and `/v1/models`) backed by node
+//! agents and LLM providers.
+
+pub mod api_openai_handlers;
+pub mod api_openai_router;
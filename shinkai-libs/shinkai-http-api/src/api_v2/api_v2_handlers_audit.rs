@@ -0,0 +1,193 @@
+use async_channel::Sender;
+use reqwest::StatusCode;
+use shinkai_message_primitives::schemas::audit_log::{
+    AuditAction, AuditLogEntry, AuditLogFilter, AuditLogVerification, AuditOutcome,
+};
+use utoipa::OpenApi;
+use warp::Filter;
+
+use super::api_v2_router::with_sender;
+use crate::{node_api_router::APIError, node_commands::NodeCommand};
+
+pub fn audit_routes(
+    node_commands_sender: Sender<NodeCommand>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    let get_audit_log_route = warp::path("audit_log")
+        .and(warp::get())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::query::<AuditLogFilter>())
+        .and_then(get_audit_log_handler);
+
+    let export_audit_log_route = warp::path("export_audit_log")
+        .and(warp::get())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::query::<AuditLogFilter>())
+        .and_then(export_audit_log_handler);
+
+    let verify_audit_log_route = warp::path("verify_audit_log")
+        .and(warp::get())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and_then(verify_audit_log_handler);
+
+    get_audit_log_route
+        .or(export_audit_log_route)
+        .or(verify_audit_log_route)
+}
+
+#[utoipa::path(
+    get,
+    path = "/v2/audit_log",
+    params(
+        ("action" = Option<AuditAction>, Query, description = "Only entries for this action"),
+        ("actor" = Option<String>, Query, description = "Name of the API key, or `node` for the node API key"),
+        ("api_key_id" = Option<String>, Query, description = "Id of the API key used"),
+        ("target" = Option<String>, Query, description = "Agent id, tool key, file path, etc."),
+        ("success" = Option<bool>, Query, description = "Only successful or only failed actions"),
+        ("from" = Option<String>, Query, description = "RFC 3339 start of the time range"),
+        ("to" = Option<String>, Query, description = "RFC 3339 end of the time range"),
+        ("limit" = Option<u64>, Query, description = "Maximum number of entries"),
+        ("offset" = Option<u64>, Query, description = "Number of entries to skip")
+    ),
+    responses(
+        (status = 200, description = "Matching audit log entries, oldest first", body = Vec<AuditLogEntry>),
+        (status = 401, description = "Unauthorized", body = APIError),
+        (status = 403, description = "Missing the admin scope", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn get_audit_log_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    filter: AuditLogFilter,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiGetAuditLog {
+            bearer,
+            filter,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+
+    match result {
+        Ok(response) => Ok(warp::reply::with_status(warp::reply::json(&response), StatusCode::OK)),
+        Err(error) => Ok(warp::reply::with_status(
+            warp::reply::json(&error),
+            StatusCode::from_u16(error.code).unwrap(),
+        )),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v2/export_audit_log",
+    params(
+        ("action" = Option<AuditAction>, Query, description = "Only entries for this action"),
+        ("actor" = Option<String>, Query, description = "Name of the API key, or `node` for the node API key"),
+        ("api_key_id" = Option<String>, Query, description = "Id of the API key used"),
+        ("target" = Option<String>, Query, description = "Agent id, tool key, file path, etc."),
+        ("success" = Option<bool>, Query, description = "Only successful or only failed actions"),
+        ("from" = Option<String>, Query, description = "RFC 3339 start of the time range"),
+        ("to" = Option<String>, Query, description = "RFC 3339 end of the time range"),
+        ("limit" = Option<u64>, Query, description = "Maximum number of entries"),
+        ("offset" = Option<u64>, Query, description = "Number of entries to skip")
+    ),
+    responses(
+        (status = 200, description = "Matching audit log entries as JSON Lines", body = String, content_type = "application/x-ndjson"),
+        (status = 401, description = "Unauthorized", body = APIError),
+        (status = 403, description = "Missing the admin scope", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn export_audit_log_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    filter: AuditLogFilter,
+) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiExportAuditLog {
+            bearer,
+            filter,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+
+    match result {
+        Ok(jsonl) => Ok(Box::new(warp::reply::with_header(
+            warp::reply::with_header(
+                warp::reply::with_status(jsonl, StatusCode::OK),
+                "Content-Type",
+                "application/x-ndjson",
+            ),
+            "Content-Disposition",
+            "attachment; filename=\"audit_log.jsonl\"",
+        ))),
+        Err(error) => Ok(Box::new(warp::reply::with_status(
+            warp::reply::json(&error),
+            StatusCode::from_u16(error.code).unwrap(),
+        ))),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v2/verify_audit_log",
+    responses(
+        (status = 200, description = "Whether the hash chain of the audit log is intact", body = AuditLogVerification),
+        (status = 401, description = "Unauthorized", body = APIError),
+        (status = 403, description = "Missing the admin scope", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn verify_audit_log_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiVerifyAuditLog {
+            bearer,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+
+    match result {
+        Ok(response) => Ok(warp::reply::with_status(warp::reply::json(&response), StatusCode::OK)),
+        Err(error) => Ok(warp::reply::with_status(
+            warp::reply::json(&error),
+            StatusCode::from_u16(error.code).unwrap(),
+        )),
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        get_audit_log_handler,
+        export_audit_log_handler,
+        verify_audit_log_handler,
+    ),
+    components(
+        schemas(APIError, AuditAction, AuditOutcome, AuditLogEntry, AuditLogFilter, AuditLogVerification)
+    ),
+    tags(
+        (name = "audit", description = "Audit log API endpoints")
+    )
+)]
+pub struct AuditApiDoc;
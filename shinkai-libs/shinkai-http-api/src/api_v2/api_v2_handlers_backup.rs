@@ -0,0 +1,234 @@
+use async_channel::Sender;
+use bytes::{Buf, Bytes};
+use futures::TryStreamExt;
+use reqwest::StatusCode;
+use shinkai_message_primitives::schemas::node_backup::{
+    BackupCategory, BackupManifest, BackupRestoreReport, CreateBackupRequest, RestoreBackupRequest,
+};
+use std::io::{Seek, SeekFrom};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use utoipa::OpenApi;
+use warp::multipart::{FormData, Part};
+use warp::Filter;
+
+use super::api_v2_router::with_sender;
+use crate::{node_api_router::APIError, node_commands::NodeCommand};
+
+// Archives are written to a temporary file as they are uploaded, same limit as file uploads
+const MAX_BACKUP_SIZE: u64 = 1024 * 1024 * 1024;
+const BACKUP_STREAM_CHUNK_SIZE: usize = 64 * 1024;
+
+pub fn backup_routes(
+    node_commands_sender: Sender<NodeCommand>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    let create_backup_route = warp::path("create_backup")
+        .and(warp::post())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(create_backup_handler);
+
+    let restore_backup_route = warp::path("restore_backup")
+        .and(warp::post())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::multipart::form().max_length(MAX_BACKUP_SIZE))
+        .and_then(restore_backup_handler);
+
+    create_backup_route.or(restore_backup_route)
+}
+
+#[utoipa::path(
+    post,
+    path = "/v2/create_backup",
+    request_body = CreateBackupRequest,
+    responses(
+        (status = 200, description = "Backup archive, encrypted if a passphrase was given", body = Vec<u8>, content_type = "application/octet-stream"),
+        (status = 401, description = "Unauthorized", body = APIError),
+        (status = 403, description = "Missing the admin scope", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn create_backup_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    payload: CreateBackupRequest,
+) -> Result<Box<dyn warp::Reply>, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiCreateBackup {
+            bearer,
+            payload,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+
+    match result {
+        Ok(archive) => {
+            let archive_size = archive.metadata().map(|metadata| metadata.len()).ok();
+            let mut response = warp::http::Response::builder()
+                .status(StatusCode::OK)
+                .header("Content-Type", "application/octet-stream")
+                .header("Content-Disposition", "attachment; filename=\"shinkai_backup.zip\"");
+            if let Some(archive_size) = archive_size {
+                response = response.header("Content-Length", archive_size);
+            }
+            let body = warp::hyper::Body::wrap_stream(archive_stream(tokio::fs::File::from_std(archive)));
+            Ok(Box::new(response.body(body).map_err(|_| warp::reject::reject())?))
+        }
+        Err(error) => Ok(Box::new(warp::reply::with_status(
+            warp::reply::json(&error),
+            StatusCode::from_u16(error.code).unwrap(),
+        ))),
+    }
+}
+
+/// Streams the archive in chunks instead of reading it into memory
+fn archive_stream(archive: tokio::fs::File) -> impl futures::Stream<Item = std::io::Result<Bytes>> {
+    futures::stream::try_unfold(archive, |mut archive| async move {
+        let mut chunk = vec![0u8; BACKUP_STREAM_CHUNK_SIZE];
+        let read = archive.read(&mut chunk).await?;
+        if read == 0 {
+            return Ok(None);
+        }
+        chunk.truncate(read);
+        Ok(Some((Bytes::from(chunk), archive)))
+    })
+}
+
+async fn read_part(part: Part) -> Result<Vec<u8>, warp::Error> {
+    let mut bytes = Vec::new();
+    let mut stream = part.stream();
+    while let Some(chunk) = stream.try_next().await? {
+        bytes.extend_from_slice(chunk.chunk());
+    }
+    Ok(bytes)
+}
+
+/// Writes the part to an unnamed temporary file as it is received, instead of reading it into
+/// memory
+async fn write_part_to_temp_file(part: Part) -> Result<std::fs::File, String> {
+    let file = tempfile::tempfile().map_err(|e| e.to_string())?;
+    let mut output = tokio::fs::File::from_std(file);
+    let mut stream = part.stream();
+    while let Some(chunk) = stream.try_next().await.map_err(|e| e.to_string())? {
+        output.write_all(chunk.chunk()).await.map_err(|e| e.to_string())?;
+    }
+    output.flush().await.map_err(|e| e.to_string())?;
+
+    let mut file = output.into_std().await;
+    file.seek(SeekFrom::Start(0)).map_err(|e| e.to_string())?;
+    Ok(file)
+}
+
+fn bad_request(message: String) -> warp::reply::WithStatus<warp::reply::Json> {
+    warp::reply::with_status(
+        warp::reply::json(&APIError {
+            code: StatusCode::BAD_REQUEST.as_u16(),
+            error: "Bad Request".to_string(),
+            message,
+        }),
+        StatusCode::BAD_REQUEST,
+    )
+}
+
+#[utoipa::path(
+    post,
+    path = "/v2/restore_backup",
+    request_body(
+        content_type = "multipart/form-data",
+        description = "`file`: the backup archive. `passphrase`: required for encrypted archives. `categories`: optional comma separated list of categories to import, every category in the archive by default."
+    ),
+    responses(
+        (status = 200, description = "What was restored", body = BackupRestoreReport),
+        (status = 400, description = "Invalid archive, passphrase or categories", body = APIError),
+        (status = 401, description = "Unauthorized", body = APIError),
+        (status = 403, description = "Missing the admin scope", body = APIError),
+        (status = 409, description = "Jobs are in progress", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn restore_backup_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    mut form: FormData,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+
+    let mut file: Option<std::fs::File> = None;
+    let mut payload = RestoreBackupRequest::default();
+    while let Ok(Some(part)) = form.try_next().await {
+        let name = part.name().to_string();
+        if name == "file" {
+            match write_part_to_temp_file(part).await {
+                Ok(archive) => file = Some(archive),
+                Err(e) => return Ok(bad_request(format!("Failed to read the file field: {}", e))),
+            }
+            continue;
+        }
+        let bytes = match read_part(part).await {
+            Ok(bytes) => bytes,
+            Err(e) => return Ok(bad_request(format!("Failed to read the {} field: {}", name, e))),
+        };
+        match name.as_str() {
+            "passphrase" => payload.passphrase = Some(String::from_utf8_lossy(&bytes).to_string()),
+            "categories" => {
+                for category in String::from_utf8_lossy(&bytes).split(',').map(str::trim) {
+                    if category.is_empty() {
+                        continue;
+                    }
+                    match serde_json::from_value::<BackupCategory>(serde_json::Value::String(category.to_string())) {
+                        Ok(category) => payload.categories.push(category),
+                        Err(_) => return Ok(bad_request(format!("Unknown backup category: {}", category))),
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let file = match file {
+        Some(file) if file.metadata().map(|metadata| metadata.len() > 0).unwrap_or(false) => file,
+        _ => return Ok(bad_request("The backup archive is required in the file field".to_string())),
+    };
+
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiRestoreBackup {
+            bearer,
+            file,
+            payload,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+
+    match result {
+        Ok(response) => Ok(warp::reply::with_status(warp::reply::json(&response), StatusCode::OK)),
+        Err(error) => Ok(warp::reply::with_status(
+            warp::reply::json(&error),
+            StatusCode::from_u16(error.code).unwrap(),
+        )),
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        create_backup_handler,
+        restore_backup_handler,
+    ),
+    components(
+        schemas(APIError, BackupCategory, BackupManifest, BackupRestoreReport, CreateBackupRequest, RestoreBackupRequest)
+    ),
+    tags(
+        (name = "backup", description = "Node backup and restore API endpoints")
+    )
+)]
+pub struct BackupApiDoc;
@@ -1,7 +1,10 @@
 use crate::{node_api_router::APIError, node_commands::NodeCommand};
 use async_channel::Sender;
 use serde::Deserialize;
-use shinkai_message_primitives::schemas::crontab::{CronTask, CronTaskAction};
+use shinkai_message_primitives::schemas::crontab::{
+    CronEventTrigger, CronExecutionStatus, CronMisfirePolicy, CronRetryPolicy, CronTask, CronTaskAction, CronTaskEvent,
+    CronTaskExecution, CronTaskPolicy,
+};
 use utoipa::OpenApi;
 use warp::http::StatusCode;
 use warp::Filter;
@@ -95,7 +98,16 @@ pub fn cron_routes(
 
 #[derive(Deserialize)]
 pub struct AddCronTaskRequest {
+    /// Not used by event-triggered tasks
+    #[serde(default)]
     cron: String,
+    /// IANA timezone of the schedule, the timezone of the host if omitted
+    timezone: Option<String>,
+    /// Misfire, retry and timeout policy, the defaults skip missed runs and do not retry
+    #[serde(default)]
+    policy: CronTaskPolicy,
+    /// Runs the task on node events instead of on a schedule
+    event_trigger: Option<CronEventTrigger>,
     action: CronTaskAction,
     name: String,
     description: Option<String>,
@@ -104,7 +116,13 @@ pub struct AddCronTaskRequest {
 #[derive(Deserialize)]
 pub struct UpdateCronTaskRequest {
     cron_task_id: String,
+    #[serde(default)]
     cron: String,
+    timezone: Option<String>,
+    /// Keeps the current policy if omitted
+    policy: Option<CronTaskPolicy>,
+    /// The task runs on its schedule if omitted
+    event_trigger: Option<CronEventTrigger>,
     action: CronTaskAction,
     name: String,
     description: Option<String>,
@@ -135,6 +153,9 @@ pub async fn add_cron_task_handler(
             name: payload.name,
             description: payload.description,
             cron: payload.cron,
+            timezone: payload.timezone,
+            policy: payload.policy,
+            event_trigger: payload.event_trigger,
             action: payload.action,
             res: res_sender,
         })
@@ -402,6 +423,9 @@ pub async fn update_cron_task_handler(
             bearer,
             cron_task_id,
             cron: payload.cron,
+            timezone: payload.timezone,
+            policy: payload.policy,
+            event_trigger: payload.event_trigger,
             action: payload.action,
             name: payload.name,
             description: payload.description,
@@ -647,7 +671,10 @@ pub async fn export_cron_task_handler(
         export_cron_task_handler,
     ),
     components(
-        schemas(CronTask, CronTaskAction, APIError)
+        schemas(
+            CronTask, CronTaskAction, CronTaskPolicy, CronMisfirePolicy, CronRetryPolicy, CronTaskExecution,
+            CronExecutionStatus, CronEventTrigger, CronTaskEvent, APIError
+        )
     ),
     tags(
         (name = "cron", description = "Cron Task API endpoints")
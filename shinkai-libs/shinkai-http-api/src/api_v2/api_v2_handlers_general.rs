@@ -3,7 +3,9 @@ use bytes::Buf;
 use futures::TryStreamExt;
 use reqwest::StatusCode;
 use serde::Deserialize;
+use chrono::{DateTime, Utc};
 use serde_json::json;
+use shinkai_message_primitives::schemas::api_key::{ApiKey, ApiKeyScope};
 use shinkai_message_primitives::schemas::llm_providers::agent::Agent;
 use shinkai_message_primitives::schemas::llm_providers::serialized_llm_provider::{
     Exo, Gemini, Groq, LLMProviderInterface, LocalLLM, Ollama, OpenAI, ShinkaiBackend,
@@ -244,6 +246,26 @@ pub fn general_routes(
         .and(warp::header::<String>("authorization"))
         .and_then(get_preferences_handler);
 
+    let create_api_key_route = warp::path("create_api_key")
+        .and(warp::post())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(create_api_key_handler);
+
+    let list_api_keys_route = warp::path("list_api_keys")
+        .and(warp::get())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and_then(list_api_keys_handler);
+
+    let revoke_api_key_route = warp::path("revoke_api_key")
+        .and(warp::post())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(revoke_api_key_handler);
+
     public_keys_route
         .or(health_check_route)
         .or(initial_registration_route)
@@ -275,6 +297,9 @@ pub fn general_routes(
         .or(compute_and_send_quests_status_route)
         .or(set_preferences_route)
         .or(get_preferences_route)
+        .or(create_api_key_route)
+        .or(list_api_keys_route)
+        .or(revoke_api_key_route)
 }
 
 #[derive(Deserialize)]
@@ -1417,6 +1442,137 @@ pub async fn get_preferences_handler(
     }
 }
 
+#[derive(Deserialize, ToSchema)]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+    pub scopes: Vec<ApiKeyScope>,
+    /// Agents (or LLM providers) the key can use. Omit to allow every agent.
+    pub agent_ids: Option<Vec<String>>,
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct RevokeApiKeyRequest {
+    pub id: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/v2/create_api_key",
+    request_body = CreateApiKeyRequest,
+    responses(
+        (status = 200, description = "API key created. The secret is only returned here.", body = Value),
+        (status = 400, description = "Invalid request", body = APIError),
+        (status = 401, description = "Unauthorized", body = APIError),
+        (status = 403, description = "Missing the admin scope", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn create_api_key_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    payload: CreateApiKeyRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiCreateApiKey {
+            bearer,
+            name: payload.name,
+            scopes: payload.scopes,
+            agent_ids: payload.agent_ids,
+            expires_at: payload.expires_at,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+
+    match result {
+        Ok(response) => Ok(warp::reply::with_status(warp::reply::json(&response), StatusCode::OK)),
+        Err(error) => Ok(warp::reply::with_status(
+            warp::reply::json(&error),
+            StatusCode::from_u16(error.code).unwrap(),
+        )),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v2/list_api_keys",
+    responses(
+        (status = 200, description = "Named API keys, without their secrets", body = Vec<ApiKey>),
+        (status = 401, description = "Unauthorized", body = APIError),
+        (status = 403, description = "Missing the admin scope", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn list_api_keys_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiListApiKeys {
+            bearer,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+
+    match result {
+        Ok(response) => Ok(warp::reply::with_status(warp::reply::json(&response), StatusCode::OK)),
+        Err(error) => Ok(warp::reply::with_status(
+            warp::reply::json(&error),
+            StatusCode::from_u16(error.code).unwrap(),
+        )),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v2/revoke_api_key",
+    request_body = RevokeApiKeyRequest,
+    responses(
+        (status = 200, description = "API key revoked", body = Value),
+        (status = 401, description = "Unauthorized", body = APIError),
+        (status = 403, description = "Missing the admin scope", body = APIError),
+        (status = 404, description = "API key not found", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn revoke_api_key_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    payload: RevokeApiKeyRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiRevokeApiKey {
+            bearer,
+            id: payload.id,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+
+    match result {
+        Ok(response) => Ok(warp::reply::with_status(warp::reply::json(&response), StatusCode::OK)),
+        Err(error) => Ok(warp::reply::with_status(
+            warp::reply::json(&error),
+            StatusCode::from_u16(error.code).unwrap(),
+        )),
+    }
+}
+
 #[derive(OpenApi)]
 #[openapi(
     paths(
@@ -1452,6 +1608,9 @@ pub async fn get_preferences_handler(
         compute_and_send_quests_status_handler,
         set_preferences_handler,
         get_preferences_handler,
+        create_api_key_handler,
+        list_api_keys_handler,
+        revoke_api_key_handler,
     ),
     components(
         schemas(APIAddOllamaModels, SerializedLLMProvider, ShinkaiName, LLMProviderInterface,
@@ -1460,7 +1619,8 @@ pub async fn get_preferences_handler(
             ShinkaiSubidentityType, ShinkaiBackend, InternalMetadata, MessageData, StopLLMRequest,
             NodeApiData, EncryptedShinkaiData, ShinkaiData, MessageSchemaType,
             APIUseRegistrationCodeSuccessResponse, GetPublicKeysResponse, APIError, Agent,
-            AddRegexPatternRequest, QuotaResponse)
+            AddRegexPatternRequest, QuotaResponse, ApiKey, ApiKeyScope, CreateApiKeyRequest,
+            RevokeApiKeyRequest)
     ),
     tags(
         (name = "general", description = "General API endpoints")
@@ -0,0 +1,69 @@
+use async_channel::Sender;
+use reqwest::StatusCode;
+use shinkai_message_primitives::schemas::inbox_search::{
+    InboxMessageSearchHit, InboxMessageSearchRequest, InboxMessageSearchResults,
+};
+use utoipa::OpenApi;
+use warp::Filter;
+
+use super::api_v2_router::with_sender;
+use crate::{node_api_router::APIError, node_commands::NodeCommand};
+
+pub fn inbox_search_routes(
+    node_commands_sender: Sender<NodeCommand>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path("search_inbox_messages")
+        .and(warp::post())
+        .and(with_sender(node_commands_sender))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(search_inbox_messages_handler)
+}
+
+#[utoipa::path(
+    post,
+    path = "/v2/search_inbox_messages",
+    request_body = InboxMessageSearchRequest,
+    responses(
+        (status = 200, description = "A page of matching messages, best match first", body = InboxMessageSearchResults),
+        (status = 400, description = "Empty query", body = APIError),
+        (status = 401, description = "Unauthorized", body = APIError),
+        (status = 403, description = "Missing the jobs:read scope or not allowed to use the agent", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn search_inbox_messages_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    request: InboxMessageSearchRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiSearchInboxMessages {
+            bearer,
+            request,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+    match result {
+        Ok(response) => Ok(warp::reply::with_status(warp::reply::json(&response), StatusCode::OK)),
+        Err(error) => Ok(warp::reply::with_status(
+            warp::reply::json(&error),
+            StatusCode::from_u16(error.code).unwrap(),
+        )),
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(search_inbox_messages_handler),
+    components(schemas(APIError, InboxMessageSearchRequest, InboxMessageSearchHit, InboxMessageSearchResults)),
+    tags(
+        (name = "inbox search", description = "Full-text and semantic search over inbox messages")
+    )
+)]
+pub struct InboxSearchApiDoc;
@@ -0,0 +1,166 @@
+use async_channel::Sender;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use serde_json::Value;
+use shinkai_message_primitives::schemas::job_dead_letter::JobDeadLetter;
+use utoipa::{IntoParams, OpenApi, ToSchema};
+use warp::Filter;
+
+use super::api_v2_router::with_sender;
+use crate::{node_api_router::APIError, node_commands::NodeCommand};
+
+pub fn job_dead_letter_routes(
+    node_commands_sender: Sender<NodeCommand>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    let list_job_dead_letters_route = warp::path("list_job_dead_letters")
+        .and(warp::get())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::query::<ListJobDeadLettersQuery>())
+        .and_then(list_job_dead_letters_handler);
+
+    let requeue_job_dead_letter_route = warp::path("requeue_job_dead_letter")
+        .and(warp::post())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(requeue_job_dead_letter_handler);
+
+    let discard_job_dead_letter_route = warp::path("discard_job_dead_letter")
+        .and(warp::post())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(discard_job_dead_letter_handler);
+
+    list_job_dead_letters_route
+        .or(requeue_job_dead_letter_route)
+        .or(discard_job_dead_letter_route)
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListJobDeadLettersQuery {
+    /// Only the messages of this job
+    pub job_id: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct JobDeadLetterRequest {
+    pub id: String,
+}
+
+fn into_reply<T: serde::Serialize>(result: Result<T, APIError>) -> warp::reply::WithStatus<warp::reply::Json> {
+    match result {
+        Ok(response) => warp::reply::with_status(warp::reply::json(&response), StatusCode::OK),
+        Err(error) => warp::reply::with_status(warp::reply::json(&error), StatusCode::from_u16(error.code).unwrap()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v2/list_job_dead_letters",
+    params(ListJobDeadLettersQuery),
+    responses(
+        (status = 200, description = "Messages that failed after their retries, newest first", body = Vec<JobDeadLetter>),
+        (status = 401, description = "Unauthorized", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn list_job_dead_letters_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    query: ListJobDeadLettersQuery,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiListJobDeadLetters {
+            bearer,
+            job_id: query.job_id,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+    Ok(into_reply(result))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v2/requeue_job_dead_letter",
+    request_body = JobDeadLetterRequest,
+    responses(
+        (status = 200, description = "Message queued again and removed from the dead letters", body = Value),
+        (status = 401, description = "Unauthorized", body = APIError),
+        (status = 404, description = "Dead letter not found", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn requeue_job_dead_letter_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    payload: JobDeadLetterRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiRequeueJobDeadLetter {
+            bearer,
+            id: payload.id,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+    Ok(into_reply(result))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v2/discard_job_dead_letter",
+    request_body = JobDeadLetterRequest,
+    responses(
+        (status = 200, description = "Dead letter removed without running the message again", body = Value),
+        (status = 401, description = "Unauthorized", body = APIError),
+        (status = 404, description = "Dead letter not found", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn discard_job_dead_letter_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    payload: JobDeadLetterRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiDiscardJobDeadLetter {
+            bearer,
+            id: payload.id,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+    Ok(into_reply(result))
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        list_job_dead_letters_handler,
+        requeue_job_dead_letter_handler,
+        discard_job_dead_letter_handler,
+    ),
+    components(
+        schemas(APIError, JobDeadLetter, JobDeadLetterRequest)
+    ),
+    tags(
+        (name = "job dead letters", description = "Job messages whose provider kept failing after the automatic retries")
+    )
+)]
+pub struct JobDeadLettersApiDoc;
@@ -0,0 +1,160 @@
+use async_channel::Sender;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use serde_json::Value;
+use shinkai_message_primitives::schemas::job_concurrency_limit::{
+    JobConcurrencyLimit, JobLimitTarget, JobLimitTargetStatus, JobQueueStatus,
+};
+use utoipa::{OpenApi, ToSchema};
+use warp::Filter;
+
+use super::api_v2_router::with_sender;
+use crate::{node_api_router::APIError, node_commands::NodeCommand};
+
+pub fn job_limit_routes(
+    node_commands_sender: Sender<NodeCommand>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    let set_job_concurrency_limit_route = warp::path("set_job_concurrency_limit")
+        .and(warp::post())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(set_job_concurrency_limit_handler);
+
+    let remove_job_concurrency_limit_route = warp::path("remove_job_concurrency_limit")
+        .and(warp::post())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(remove_job_concurrency_limit_handler);
+
+    let get_job_queue_status_route = warp::path("get_job_queue_status")
+        .and(warp::get())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and_then(get_job_queue_status_handler);
+
+    set_job_concurrency_limit_route
+        .or(remove_job_concurrency_limit_route)
+        .or(get_job_queue_status_route)
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct RemoveJobConcurrencyLimitRequest {
+    pub target: JobLimitTarget,
+    pub target_id: String,
+}
+
+fn into_reply<T: serde::Serialize>(result: Result<T, APIError>) -> warp::reply::WithStatus<warp::reply::Json> {
+    match result {
+        Ok(response) => warp::reply::with_status(warp::reply::json(&response), StatusCode::OK),
+        Err(error) => warp::reply::with_status(warp::reply::json(&error), StatusCode::from_u16(error.code).unwrap()),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v2/set_job_concurrency_limit",
+    request_body = JobConcurrencyLimit,
+    responses(
+        (status = 200, description = "Limit set, replacing the previous one of the target", body = JobConcurrencyLimit),
+        (status = 400, description = "Invalid limit", body = APIError),
+        (status = 401, description = "Unauthorized", body = APIError),
+        (status = 404, description = "LLM provider or agent not found", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn set_job_concurrency_limit_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    payload: JobConcurrencyLimit,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiSetJobConcurrencyLimit {
+            bearer,
+            limit: payload,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+    Ok(into_reply(result))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v2/remove_job_concurrency_limit",
+    request_body = RemoveJobConcurrencyLimitRequest,
+    responses(
+        (status = 200, description = "Limit removed", body = Value),
+        (status = 401, description = "Unauthorized", body = APIError),
+        (status = 404, description = "Limit not found", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn remove_job_concurrency_limit_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    payload: RemoveJobConcurrencyLimitRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiRemoveJobConcurrencyLimit {
+            bearer,
+            target: payload.target,
+            target_id: payload.target_id,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+    Ok(into_reply(result))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v2/get_job_queue_status",
+    responses(
+        (status = 200, description = "Messages queued and in progress, overall and per LLM provider and agent", body = JobQueueStatus),
+        (status = 401, description = "Unauthorized", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn get_job_queue_status_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiGetJobQueueStatus {
+            bearer,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+    Ok(into_reply(result))
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        set_job_concurrency_limit_handler,
+        remove_job_concurrency_limit_handler,
+        get_job_queue_status_handler,
+    ),
+    components(
+        schemas(APIError, JobConcurrencyLimit, JobLimitTarget, JobLimitTargetStatus, JobQueueStatus, RemoveJobConcurrencyLimitRequest)
+    ),
+    tags(
+        (name = "job limits", description = "Concurrency and rate limits of the job messages per LLM provider and agent")
+    )
+)]
+pub struct JobLimitsApiDoc;
@@ -0,0 +1,292 @@
+use async_channel::Sender;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use serde_json::Value;
+use shinkai_message_primitives::schemas::job_pipeline::{
+    AddJobPipelineRequest, JobPipeline, JobPipelineRun, JobPipelineRunStatus, JobPipelineStep, JobPipelineStepRun,
+    JobPipelineStepStatus, RunJobPipelineRequest,
+};
+use utoipa::{IntoParams, OpenApi, ToSchema};
+use warp::Filter;
+
+use super::api_v2_router::with_sender;
+use crate::{node_api_router::APIError, node_commands::NodeCommand};
+
+pub fn job_pipeline_routes(
+    node_commands_sender: Sender<NodeCommand>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    let add_job_pipeline_route = warp::path("add_job_pipeline")
+        .and(warp::post())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(add_job_pipeline_handler);
+
+    let list_job_pipelines_route = warp::path("list_job_pipelines")
+        .and(warp::get())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and_then(list_job_pipelines_handler);
+
+    let remove_job_pipeline_route = warp::path("remove_job_pipeline")
+        .and(warp::post())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(remove_job_pipeline_handler);
+
+    let run_job_pipeline_route = warp::path("run_job_pipeline")
+        .and(warp::post())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(run_job_pipeline_handler);
+
+    let get_job_pipeline_run_route = warp::path("get_job_pipeline_run")
+        .and(warp::get())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::query::<GetJobPipelineRunQuery>())
+        .and_then(get_job_pipeline_run_handler);
+
+    let resume_job_pipeline_run_route = warp::path("resume_job_pipeline_run")
+        .and(warp::post())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(resume_job_pipeline_run_handler);
+
+    add_job_pipeline_route
+        .or(list_job_pipelines_route)
+        .or(remove_job_pipeline_route)
+        .or(run_job_pipeline_route)
+        .or(get_job_pipeline_run_route)
+        .or(resume_job_pipeline_run_route)
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct RemoveJobPipelineRequest {
+    pub id: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ResumeJobPipelineRunRequest {
+    pub run_id: String,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GetJobPipelineRunQuery {
+    pub run_id: String,
+}
+
+fn into_reply<T: serde::Serialize>(result: Result<T, APIError>) -> warp::reply::WithStatus<warp::reply::Json> {
+    match result {
+        Ok(response) => warp::reply::with_status(warp::reply::json(&response), StatusCode::OK),
+        Err(error) => warp::reply::with_status(warp::reply::json(&error), StatusCode::from_u16(error.code).unwrap()),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v2/add_job_pipeline",
+    request_body = AddJobPipelineRequest,
+    responses(
+        (status = 200, description = "Pipeline created", body = JobPipeline),
+        (status = 400, description = "Invalid steps or unknown agent", body = APIError),
+        (status = 401, description = "Unauthorized", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn add_job_pipeline_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    payload: AddJobPipelineRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiAddJobPipeline {
+            bearer,
+            payload,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+    Ok(into_reply(result))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v2/list_job_pipelines",
+    responses(
+        (status = 200, description = "Every pipeline", body = Vec<JobPipeline>),
+        (status = 401, description = "Unauthorized", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn list_job_pipelines_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiListJobPipelines {
+            bearer,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+    Ok(into_reply(result))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v2/remove_job_pipeline",
+    request_body = RemoveJobPipelineRequest,
+    responses(
+        (status = 200, description = "Pipeline removed. Its runs are kept.", body = Value),
+        (status = 401, description = "Unauthorized", body = APIError),
+        (status = 404, description = "Pipeline not found", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn remove_job_pipeline_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    payload: RemoveJobPipelineRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiRemoveJobPipeline {
+            bearer,
+            id: payload.id,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+    Ok(into_reply(result))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v2/run_job_pipeline",
+    request_body = RunJobPipelineRequest,
+    responses(
+        (status = 200, description = "Run started with the steps that do not depend on any other", body = JobPipelineRun),
+        (status = 401, description = "Unauthorized", body = APIError),
+        (status = 404, description = "Pipeline not found", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn run_job_pipeline_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    payload: RunJobPipelineRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiRunJobPipeline {
+            bearer,
+            payload,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+    Ok(into_reply(result))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v2/get_job_pipeline_run",
+    params(GetJobPipelineRunQuery),
+    responses(
+        (status = 200, description = "Run with the status and answer of each step", body = JobPipelineRun),
+        (status = 401, description = "Unauthorized", body = APIError),
+        (status = 404, description = "Run not found", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn get_job_pipeline_run_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    query: GetJobPipelineRunQuery,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiGetJobPipelineRun {
+            bearer,
+            run_id: query.run_id,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+    Ok(into_reply(result))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v2/resume_job_pipeline_run",
+    request_body = ResumeJobPipelineRunRequest,
+    responses(
+        (status = 200, description = "Failed steps started again", body = JobPipelineRun),
+        (status = 400, description = "The run has not failed", body = APIError),
+        (status = 401, description = "Unauthorized", body = APIError),
+        (status = 404, description = "Run not found", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn resume_job_pipeline_run_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    payload: ResumeJobPipelineRunRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiResumeJobPipelineRun {
+            bearer,
+            run_id: payload.run_id,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+    Ok(into_reply(result))
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        add_job_pipeline_handler,
+        list_job_pipelines_handler,
+        remove_job_pipeline_handler,
+        run_job_pipeline_handler,
+        get_job_pipeline_run_handler,
+        resume_job_pipeline_run_handler,
+    ),
+    components(
+        schemas(APIError, AddJobPipelineRequest, RemoveJobPipelineRequest, RunJobPipelineRequest,
+            ResumeJobPipelineRunRequest, JobPipeline, JobPipelineStep, JobPipelineRun, JobPipelineStepRun,
+            JobPipelineRunStatus, JobPipelineStepStatus)
+    ),
+    tags(
+        (name = "job pipelines", description = "Jobs that run one after the other, each using the answers of the ones before")
+    )
+)]
+pub struct JobPipelinesApiDoc;
@@ -1,5 +1,6 @@
 use async_channel::Sender;
 use bytes::Buf;
+use chrono::{DateTime, Utc};
 use futures::StreamExt;
 use reqwest::StatusCode;
 use serde::Deserialize;
@@ -222,6 +223,10 @@ pub struct CreateJobRequest {
 #[derive(Deserialize, ToSchema)]
 pub struct JobMessageRequest {
     pub job_message: JobMessage,
+    /// Sends the message at this time instead of right away
+    #[serde(default)]
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub send_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Deserialize, ToSchema)]
@@ -383,7 +388,7 @@ pub async fn create_job_handler(
     path = "/v2/job_message",
     request_body = JobMessageRequest,
     responses(
-        (status = 200, description = "Successfully processed job message", body = SendResponseBody),
+        (status = 200, description = "Successfully processed or scheduled job message", body = SendResponseBody),
         (status = 400, description = "Bad request", body = APIError),
         (status = 500, description = "Internal server error", body = APIError)
     )
@@ -395,6 +400,29 @@ pub async fn job_message_handler(
 ) -> Result<impl warp::Reply, warp::Rejection> {
     let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
     let node_commands_sender = node_commands_sender.clone();
+
+    if let Some(send_at) = payload.send_at {
+        let (res_sender, res_receiver) = async_channel::bounded(1);
+        node_commands_sender
+            .send(NodeCommand::V2ApiScheduleJobMessage {
+                bearer,
+                job_message: payload.job_message,
+                send_at,
+                res: res_sender,
+            })
+            .await
+            .map_err(|_| warp::reject::reject())?;
+        let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+
+        return match result {
+            Ok(scheduled) => Ok(warp::reply::with_status(warp::reply::json(&scheduled), StatusCode::OK)),
+            Err(error) => Ok(warp::reply::with_status(
+                warp::reply::json(&error),
+                StatusCode::from_u16(error.code).unwrap(),
+            )),
+        };
+    }
+
     let (res_sender, res_receiver) = async_channel::bounded(1);
     node_commands_sender
         .send(NodeCommand::V2ApiJobMessage {
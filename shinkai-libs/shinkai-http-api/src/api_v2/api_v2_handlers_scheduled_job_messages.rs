@@ -0,0 +1,127 @@
+use async_channel::Sender;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use serde_json::Value;
+use shinkai_message_primitives::schemas::scheduled_job_message::{ScheduledJobMessage, ScheduledJobMessageStatus};
+use utoipa::{IntoParams, OpenApi, ToSchema};
+use warp::Filter;
+
+use super::api_v2_router::with_sender;
+use crate::{node_api_router::APIError, node_commands::NodeCommand};
+
+pub fn scheduled_job_message_routes(
+    node_commands_sender: Sender<NodeCommand>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    let list_scheduled_job_messages_route = warp::path("list_scheduled_job_messages")
+        .and(warp::get())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::query::<ListScheduledJobMessagesQuery>())
+        .and_then(list_scheduled_job_messages_handler);
+
+    let cancel_scheduled_job_message_route = warp::path("cancel_scheduled_job_message")
+        .and(warp::post())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(cancel_scheduled_job_message_handler);
+
+    list_scheduled_job_messages_route.or(cancel_scheduled_job_message_route)
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListScheduledJobMessagesQuery {
+    /// Only the messages of this job
+    pub job_id: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CancelScheduledJobMessageRequest {
+    pub id: String,
+}
+
+fn into_reply<T: serde::Serialize>(result: Result<T, APIError>) -> warp::reply::WithStatus<warp::reply::Json> {
+    match result {
+        Ok(response) => warp::reply::with_status(warp::reply::json(&response), StatusCode::OK),
+        Err(error) => warp::reply::with_status(warp::reply::json(&error), StatusCode::from_u16(error.code).unwrap()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/v2/list_scheduled_job_messages",
+    params(ListScheduledJobMessagesQuery),
+    responses(
+        (status = 200, description = "Messages waiting to be sent or that failed, next to be sent first", body = Vec<ScheduledJobMessage>),
+        (status = 401, description = "Unauthorized", body = APIError),
+        (status = 403, description = "API key not allowed to use the agent of the job", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn list_scheduled_job_messages_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    query: ListScheduledJobMessagesQuery,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiListScheduledJobMessages {
+            bearer,
+            job_id: query.job_id,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+    Ok(into_reply(result))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v2/cancel_scheduled_job_message",
+    request_body = CancelScheduledJobMessageRequest,
+    responses(
+        (status = 200, description = "Scheduled message removed before it was sent", body = Value),
+        (status = 401, description = "Unauthorized", body = APIError),
+        (status = 403, description = "API key not allowed to use the agent of the job", body = APIError),
+        (status = 404, description = "Scheduled message not found", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn cancel_scheduled_job_message_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    payload: CancelScheduledJobMessageRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiCancelScheduledJobMessage {
+            bearer,
+            id: payload.id,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+    Ok(into_reply(result))
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        list_scheduled_job_messages_handler,
+        cancel_scheduled_job_message_handler,
+    ),
+    components(
+        schemas(APIError, ScheduledJobMessage, ScheduledJobMessageStatus, CancelScheduledJobMessageRequest)
+    ),
+    tags(
+        (name = "scheduled job messages", description = "Job messages sent at a later time")
+    )
+)]
+pub struct ScheduledJobMessagesApiDoc;
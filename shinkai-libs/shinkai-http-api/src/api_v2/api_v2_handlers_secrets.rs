@@ -0,0 +1,78 @@
+use async_channel::Sender;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use serde_json::Value;
+use utoipa::{OpenApi, ToSchema};
+use warp::Filter;
+
+use super::api_v2_router::with_sender;
+use crate::{node_api_router::APIError, node_commands::NodeCommand};
+
+#[derive(Deserialize, ToSchema)]
+pub struct RotateSecretsKeyRequest {
+    /// Passphrase to derive the new key from. Without one, a random key is stored in the OS keyring.
+    pub new_passphrase: Option<String>,
+}
+
+pub fn secrets_routes(
+    node_commands_sender: Sender<NodeCommand>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path("rotate_secrets_key")
+        .and(warp::post())
+        .and(with_sender(node_commands_sender))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(rotate_secrets_key_handler)
+}
+
+#[utoipa::path(
+    post,
+    path = "/v2/rotate_secrets_key",
+    request_body = RotateSecretsKeyRequest,
+    responses(
+        (status = 200, description = "Number of stored secrets re-encrypted with the new key", body = Value),
+        (status = 401, description = "Unauthorized", body = APIError),
+        (status = 403, description = "Missing the admin scope", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn rotate_secrets_key_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    payload: RotateSecretsKeyRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiRotateSecretsKey {
+            bearer,
+            new_passphrase: payload.new_passphrase,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+
+    match result {
+        Ok(response) => Ok(warp::reply::with_status(warp::reply::json(&response), StatusCode::OK)),
+        Err(error) => Ok(warp::reply::with_status(
+            warp::reply::json(&error),
+            StatusCode::from_u16(error.code).unwrap(),
+        )),
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        rotate_secrets_key_handler,
+    ),
+    components(
+        schemas(APIError, RotateSecretsKeyRequest)
+    ),
+    tags(
+        (name = "secrets", description = "Secret vault API endpoints")
+    )
+)]
+pub struct SecretsApiDoc;
@@ -283,6 +283,62 @@ pub fn tool_routes(
         .and(warp::body::json())
         .and_then(tool_check_handler);
 
+    let list_tool_revisions_route = warp::path("list_tool_revisions")
+        .and(warp::get())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::query::<ListToolRevisionsRequest>())
+        .and_then(list_tool_revisions_handler);
+
+    let diff_tool_revisions_route = warp::path("diff_tool_revisions")
+        .and(warp::get())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::query::<DiffToolRevisionsRequest>())
+        .and_then(diff_tool_revisions_handler);
+
+    let rollback_tool_revision_route = warp::path("rollback_tool_revision")
+        .and(warp::post())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(rollback_tool_revision_handler);
+
+    let run_tool_tests_route = warp::path("run_tool_tests")
+        .and(warp::post())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(run_tool_tests_handler);
+
+    let tool_dependency_graph_route = warp::path("tool_dependency_graph")
+        .and(warp::get())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::query::<ToolDependencyGraphRequest>())
+        .and_then(tool_dependency_graph_handler);
+
+    let set_tool_network_approval_route = warp::path("set_tool_network_approval")
+        .and(warp::post())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(set_tool_network_approval_handler);
+
+    let tool_network_access_route = warp::path("tool_network_access")
+        .and(warp::get())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::query::<ToolNetworkAccessRequest>())
+        .and_then(tool_network_access_handler);
+
+    let set_wasm_tool_grant_route = warp::path("set_wasm_tool_grant")
+        .and(warp::post())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(set_wasm_tool_grant_handler);
+
     tool_execution_route
         .or(code_execution_route)
         .or(tool_definitions_route)
@@ -319,6 +375,14 @@ pub fn tool_routes(
         .or(set_tool_mcp_enabled_route)
         .or(copy_tool_asset_route)
         .or(tool_check_route)
+        .or(list_tool_revisions_route)
+        .or(diff_tool_revisions_route)
+        .or(rollback_tool_revision_route)
+        .or(run_tool_tests_route)
+        .or(tool_dependency_graph_route)
+        .or(set_tool_network_approval_route)
+        .or(tool_network_access_route)
+        .or(set_wasm_tool_grant_route)
 }
 
 pub fn safe_folder_name(tool_router_key: &str) -> String {
@@ -1652,11 +1716,13 @@ pub async fn resolve_shinkai_file_protocol_handler(
     delete,
     path = "/v2/remove_tool",
     params(
-        ("tool_key" = String, Query, description = "Key of the tool to remove")
+        ("tool_key" = String, Query, description = "Key of the tool to remove"),
+        ("force" = Option<bool>, Query, description = "Remove the tool even if other tools depend on it")
     ),
     responses(
         (status = 200, description = "Successfully removed tool", body = bool),
         (status = 400, description = "Bad request", body = APIError),
+        (status = 409, description = "Other installed tools depend on this tool", body = APIError),
         (status = 500, description = "Internal server error", body = APIError)
     )
 )]
@@ -1676,11 +1742,13 @@ pub async fn remove_tool_handler(
             })
         })?
         .to_string();
+    let force = query_params.get("force").map_or(false, |force| force == "true");
     let (res_sender, res_receiver) = async_channel::bounded(1);
     sender
         .send(NodeCommand::V2ApiRemoveTool {
             bearer,
             tool_key,
+            force,
             res: res_sender,
         })
         .await
@@ -2362,6 +2430,404 @@ pub async fn tool_check_handler(
     }
 }
 
+#[derive(Deserialize, ToSchema)]
+pub struct ListToolRevisionsRequest {
+    pub tool_router_key: String,
+}
+
+#[utoipa::path(
+    get,
+    path = "/v2/list_tool_revisions",
+    params(
+        ("tool_router_key" = String, Query, description = "Key of the tool")
+    ),
+    responses(
+        (status = 200, description = "Successfully listed tool revisions", body = Value),
+        (status = 404, description = "Tool not found", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn list_tool_revisions_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    query: ListToolRevisionsRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiListToolRevisions {
+            bearer,
+            tool_router_key: query.tool_router_key,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+
+    match result {
+        Ok(response) => {
+            let response = create_success_response(response);
+            Ok(warp::reply::with_status(warp::reply::json(&response), StatusCode::OK))
+        }
+        Err(error) => Ok(warp::reply::with_status(
+            warp::reply::json(&error),
+            StatusCode::from_u16(error.code).unwrap(),
+        )),
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct DiffToolRevisionsRequest {
+    pub tool_router_key: String,
+    pub from_revision: u64,
+    pub to_revision: u64,
+}
+
+#[utoipa::path(
+    get,
+    path = "/v2/diff_tool_revisions",
+    params(
+        ("tool_router_key" = String, Query, description = "Key of the tool"),
+        ("from_revision" = u64, Query, description = "Base revision"),
+        ("to_revision" = u64, Query, description = "Revision to compare against the base")
+    ),
+    responses(
+        (status = 200, description = "Successfully computed the diff", body = Value),
+        (status = 404, description = "Revision not found", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn diff_tool_revisions_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    query: DiffToolRevisionsRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiDiffToolRevisions {
+            bearer,
+            tool_router_key: query.tool_router_key,
+            from_revision: query.from_revision,
+            to_revision: query.to_revision,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+
+    match result {
+        Ok(response) => {
+            let response = create_success_response(response);
+            Ok(warp::reply::with_status(warp::reply::json(&response), StatusCode::OK))
+        }
+        Err(error) => Ok(warp::reply::with_status(
+            warp::reply::json(&error),
+            StatusCode::from_u16(error.code).unwrap(),
+        )),
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct RollbackToolRevisionRequest {
+    pub tool_router_key: String,
+    pub revision: u64,
+}
+
+#[utoipa::path(
+    post,
+    path = "/v2/rollback_tool_revision",
+    request_body = RollbackToolRevisionRequest,
+    responses(
+        (status = 200, description = "Successfully rolled back tool", body = Value),
+        (status = 404, description = "Revision not found", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn rollback_tool_revision_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    payload: RollbackToolRevisionRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiRollbackToolRevision {
+            bearer,
+            tool_router_key: payload.tool_router_key,
+            revision: payload.revision,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+
+    match result {
+        Ok(response) => {
+            let response = create_success_response(response);
+            Ok(warp::reply::with_status(warp::reply::json(&response), StatusCode::OK))
+        }
+        Err(error) => Ok(warp::reply::with_status(
+            warp::reply::json(&error),
+            StatusCode::from_u16(error.code).unwrap(),
+        )),
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct RunToolTestsRequest {
+    /// Tool to test. When omitted, the test cases of every installed tool are run.
+    pub tool_router_key: Option<String>,
+    /// Skip the test cases tagged as `network`
+    #[serde(default)]
+    pub skip_network_tests: bool,
+    #[serde(default)]
+    pub llm_provider: Option<String>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/v2/run_tool_tests",
+    request_body = RunToolTestsRequest,
+    responses(
+        (status = 200, description = "Test reports for the requested tools", body = Value),
+        (status = 404, description = "Tool not found", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn run_tool_tests_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    payload: RunToolTestsRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiRunToolTests {
+            bearer,
+            tool_router_key: payload.tool_router_key,
+            skip_network_tests: payload.skip_network_tests,
+            llm_provider: payload.llm_provider.unwrap_or_default(),
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+
+    match result {
+        Ok(response) => {
+            let response = create_success_response(response);
+            Ok(warp::reply::with_status(warp::reply::json(&response), StatusCode::OK))
+        }
+        Err(error) => Ok(warp::reply::with_status(
+            warp::reply::json(&error),
+            StatusCode::from_u16(error.code).unwrap(),
+        )),
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ToolDependencyGraphRequest {
+    /// Only include the tools reachable from this tool. When omitted, every installed tool is included.
+    pub tool_router_key: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/v2/tool_dependency_graph",
+    params(
+        ("tool_router_key" = Option<String>, Query, description = "Root tool of the graph")
+    ),
+    responses(
+        (status = 200, description = "Dependency graph of the installed tools", body = Value),
+        (status = 404, description = "Tool not found", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn tool_dependency_graph_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    query: ToolDependencyGraphRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiGetToolDependencyGraph {
+            bearer,
+            tool_router_key: query.tool_router_key,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+
+    match result {
+        Ok(response) => {
+            let response = create_success_response(response);
+            Ok(warp::reply::with_status(warp::reply::json(&response), StatusCode::OK))
+        }
+        Err(error) => Ok(warp::reply::with_status(
+            warp::reply::json(&error),
+            StatusCode::from_u16(error.code).unwrap(),
+        )),
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct SetToolNetworkApprovalRequest {
+    pub tool_router_key: String,
+    /// Approve the hosts declared in the tool network allowlist, or any host when the tool
+    /// declares none. `false` revokes the approval.
+    pub approved: bool,
+}
+
+#[utoipa::path(
+    post,
+    path = "/v2/set_tool_network_approval",
+    request_body = SetToolNetworkApprovalRequest,
+    responses(
+        (status = 200, description = "Network access of the tool after the change", body = Value),
+        (status = 400, description = "Tool is not a Deno or Python tool", body = APIError),
+        (status = 404, description = "Tool not found", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn set_tool_network_approval_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    payload: SetToolNetworkApprovalRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiSetToolNetworkApproval {
+            bearer,
+            tool_router_key: payload.tool_router_key,
+            approved: payload.approved,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+
+    match result {
+        Ok(response) => {
+            let response = create_success_response(response);
+            Ok(warp::reply::with_status(warp::reply::json(&response), StatusCode::OK))
+        }
+        Err(error) => Ok(warp::reply::with_status(
+            warp::reply::json(&error),
+            StatusCode::from_u16(error.code).unwrap(),
+        )),
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ToolNetworkAccessRequest {
+    pub tool_router_key: String,
+}
+
+#[utoipa::path(
+    get,
+    path = "/v2/tool_network_access",
+    params(
+        ("tool_router_key" = String, Query, description = "Tool router key")
+    ),
+    responses(
+        (status = 200, description = "Declared and approved network access of the tool, with its blocked attempts", body = Value),
+        (status = 404, description = "Tool not found", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn tool_network_access_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    query: ToolNetworkAccessRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiGetToolNetworkAccess {
+            bearer,
+            tool_router_key: query.tool_router_key,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+
+    match result {
+        Ok(response) => {
+            let response = create_success_response(response);
+            Ok(warp::reply::with_status(warp::reply::json(&response), StatusCode::OK))
+        }
+        Err(error) => Ok(warp::reply::with_status(
+            warp::reply::json(&error),
+            StatusCode::from_u16(error.code).unwrap(),
+        )),
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct SetWasmToolGrantRequest {
+    pub tool_router_key: String,
+    /// Grant the capabilities requested by the WASM tool. `false` revokes the grant.
+    pub approved: bool,
+    /// Let the filesystem grants reach host paths outside the storage folder of the tool
+    #[serde(default)]
+    pub allow_outside_storage: bool,
+}
+
+#[utoipa::path(
+    post,
+    path = "/v2/set_wasm_tool_grant",
+    request_body = SetWasmToolGrantRequest,
+    responses(
+        (status = 200, description = "Requested and granted capabilities of the tool after the change", body = Value),
+        (status = 400, description = "Tool is not a WASM tool", body = APIError),
+        (status = 404, description = "Tool not found", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn set_wasm_tool_grant_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    payload: SetWasmToolGrantRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiSetWasmToolGrant {
+            bearer,
+            tool_router_key: payload.tool_router_key,
+            approved: payload.approved,
+            allow_outside_storage: payload.allow_outside_storage,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+
+    match result {
+        Ok(response) => {
+            let response = create_success_response(response);
+            Ok(warp::reply::with_status(warp::reply::json(&response), StatusCode::OK))
+        }
+        Err(error) => Ok(warp::reply::with_status(
+            warp::reply::json(&error),
+            StatusCode::from_u16(error.code).unwrap(),
+        )),
+    }
+}
+
 #[derive(OpenApi)]
 #[openapi(
     paths(
@@ -2397,6 +2863,14 @@ pub async fn tool_check_handler(
         set_tool_mcp_enabled_handler,
         copy_tool_assets_handler,
         tool_check_handler,
+        list_tool_revisions_handler,
+        diff_tool_revisions_handler,
+        rollback_tool_revision_handler,
+        run_tool_tests_handler,
+        tool_dependency_graph_handler,
+        set_tool_network_approval_handler,
+        tool_network_access_handler,
+        set_wasm_tool_grant_handler,
     ),
     components(
         schemas(
@@ -2404,6 +2878,14 @@ pub async fn tool_check_handler(
             ToolExecutionRequest,
             SetToolEnabledRequest,
             SetToolMcpEnabledRequest,
+            ListToolRevisionsRequest,
+            DiffToolRevisionsRequest,
+            RollbackToolRevisionRequest,
+            RunToolTestsRequest,
+            ToolDependencyGraphRequest,
+            SetToolNetworkApprovalRequest,
+            ToolNetworkAccessRequest,
+            SetWasmToolGrantRequest,
         )
     ),
     tags(
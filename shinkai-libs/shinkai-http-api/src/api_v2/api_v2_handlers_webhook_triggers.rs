@@ -0,0 +1,270 @@
+use async_channel::Sender;
+use bytes::Bytes;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use serde_json::Value;
+use shinkai_message_primitives::schemas::webhook::WEBHOOK_SIGNATURE_HEADER;
+use shinkai_message_primitives::schemas::webhook_trigger::{
+    AddWebhookTriggerRequest, UpdateWebhookTriggerRequest, WebhookTrigger, WebhookTriggerMode, WebhookTriggerRun,
+};
+use utoipa::{IntoParams, OpenApi, ToSchema};
+use warp::Filter;
+
+use super::api_v2_router::with_sender;
+use crate::{node_api_router::APIError, node_commands::NodeCommand};
+
+/// Largest payload an inbound webhook accepts
+const WEBHOOK_TRIGGER_MAX_BODY: u64 = 1024 * 1024;
+
+pub fn webhook_trigger_routes(
+    node_commands_sender: Sender<NodeCommand>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    let add_webhook_trigger_route = warp::path("add_webhook_trigger")
+        .and(warp::post())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(add_webhook_trigger_handler);
+
+    let list_webhook_triggers_route = warp::path("list_webhook_triggers")
+        .and(warp::get())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and_then(list_webhook_triggers_handler);
+
+    let update_webhook_trigger_route = warp::path("update_webhook_trigger")
+        .and(warp::post())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(update_webhook_trigger_handler);
+
+    let remove_webhook_trigger_route = warp::path("remove_webhook_trigger")
+        .and(warp::post())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(remove_webhook_trigger_handler);
+
+    // Called by external systems, authenticated with the trigger secret instead of an API key
+    let invoke_webhook_trigger_route = warp::path!("hooks" / String)
+        .and(warp::post())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::header::optional::<String>(WEBHOOK_SIGNATURE_HEADER))
+        .and(warp::query::<InvokeWebhookTriggerQuery>())
+        .and(warp::body::content_length_limit(WEBHOOK_TRIGGER_MAX_BODY))
+        .and(warp::body::bytes())
+        .and_then(invoke_webhook_trigger_handler);
+
+    add_webhook_trigger_route
+        .or(list_webhook_triggers_route)
+        .or(update_webhook_trigger_route)
+        .or(remove_webhook_trigger_route)
+        .or(invoke_webhook_trigger_route)
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct RemoveWebhookTriggerRequest {
+    pub id: String,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct InvokeWebhookTriggerQuery {
+    /// Wait for the answer of the agent, defaults to the mode of the trigger
+    pub sync: Option<bool>,
+}
+
+fn into_reply<T: serde::Serialize>(result: Result<T, APIError>) -> warp::reply::WithStatus<warp::reply::Json> {
+    match result {
+        Ok(response) => warp::reply::with_status(warp::reply::json(&response), StatusCode::OK),
+        Err(error) => warp::reply::with_status(warp::reply::json(&error), StatusCode::from_u16(error.code).unwrap()),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v2/add_webhook_trigger",
+    request_body = AddWebhookTriggerRequest,
+    responses(
+        (status = 200, description = "Trigger created. The secret is only returned here.", body = WebhookTrigger),
+        (status = 400, description = "Unknown agent or invalid rate limit", body = APIError),
+        (status = 401, description = "Unauthorized", body = APIError),
+        (status = 403, description = "Missing the admin scope", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn add_webhook_trigger_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    payload: AddWebhookTriggerRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiAddWebhookTrigger {
+            bearer,
+            payload,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+    Ok(into_reply(result))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v2/list_webhook_triggers",
+    responses(
+        (status = 200, description = "Triggers with their secrets redacted", body = Vec<WebhookTrigger>),
+        (status = 401, description = "Unauthorized", body = APIError),
+        (status = 403, description = "Missing the admin scope", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn list_webhook_triggers_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiListWebhookTriggers {
+            bearer,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+    Ok(into_reply(result))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v2/update_webhook_trigger",
+    request_body = UpdateWebhookTriggerRequest,
+    responses(
+        (status = 200, description = "Updated trigger with its secret redacted", body = WebhookTrigger),
+        (status = 400, description = "Unknown agent or invalid rate limit", body = APIError),
+        (status = 401, description = "Unauthorized", body = APIError),
+        (status = 403, description = "Missing the admin scope", body = APIError),
+        (status = 404, description = "Trigger not found", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn update_webhook_trigger_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    payload: UpdateWebhookTriggerRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiUpdateWebhookTrigger {
+            bearer,
+            payload,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+    Ok(into_reply(result))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v2/remove_webhook_trigger",
+    request_body = RemoveWebhookTriggerRequest,
+    responses(
+        (status = 200, description = "Trigger removed", body = Value),
+        (status = 401, description = "Unauthorized", body = APIError),
+        (status = 403, description = "Missing the admin scope", body = APIError),
+        (status = 404, description = "Trigger not found", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn remove_webhook_trigger_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    payload: RemoveWebhookTriggerRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiRemoveWebhookTrigger {
+            bearer,
+            id: payload.id,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+    Ok(into_reply(result))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v2/hooks/{id}",
+    params(
+        ("id" = String, Path, description = "Trigger id"),
+        InvokeWebhookTriggerQuery
+    ),
+    request_body(content = Value, description = "Any JSON, injected into the prompt template"),
+    responses(
+        (status = 200, description = "Job started, with the answer when waited for", body = WebhookTriggerRun),
+        (status = 400, description = "The payload is not JSON", body = APIError),
+        (status = 401, description = "Missing or wrong trigger secret or signature", body = APIError),
+        (status = 404, description = "Trigger not found or disabled", body = APIError),
+        (status = 429, description = "Rate limit of the trigger reached", body = APIError),
+        (status = 504, description = "The agent did not answer in time", body = APIError)
+    )
+)]
+pub async fn invoke_webhook_trigger_handler(
+    id: String,
+    sender: Sender<NodeCommand>,
+    authorization: Option<String>,
+    signature: Option<String>,
+    query: InvokeWebhookTriggerQuery,
+    body: Bytes,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiInvokeWebhookTrigger {
+            id,
+            authorization,
+            signature,
+            body: String::from_utf8_lossy(&body).to_string(),
+            sync: query.sync,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+    Ok(into_reply(result))
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        add_webhook_trigger_handler,
+        list_webhook_triggers_handler,
+        update_webhook_trigger_handler,
+        remove_webhook_trigger_handler,
+        invoke_webhook_trigger_handler,
+    ),
+    components(
+        schemas(APIError, AddWebhookTriggerRequest, UpdateWebhookTriggerRequest, RemoveWebhookTriggerRequest,
+            WebhookTrigger, WebhookTriggerMode, WebhookTriggerRun)
+    ),
+    tags(
+        (name = "webhook triggers", description = "Inbound webhooks that start agent jobs")
+    )
+)]
+pub struct WebhookTriggersApiDoc;
@@ -0,0 +1,294 @@
+use async_channel::Sender;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use serde_json::Value;
+use shinkai_message_primitives::schemas::webhook::{
+    AddWebhookRequest, UpdateWebhookRequest, Webhook, WebhookDelivery, WebhookDeliveryFilter, WebhookDeliveryStatus,
+    WebhookEventType,
+};
+use utoipa::{OpenApi, ToSchema};
+use warp::Filter;
+
+use super::api_v2_router::with_sender;
+use crate::{node_api_router::APIError, node_commands::NodeCommand};
+
+pub fn webhook_routes(
+    node_commands_sender: Sender<NodeCommand>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    let add_webhook_route = warp::path("add_webhook")
+        .and(warp::post())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(add_webhook_handler);
+
+    let list_webhooks_route = warp::path("list_webhooks")
+        .and(warp::get())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and_then(list_webhooks_handler);
+
+    let update_webhook_route = warp::path("update_webhook")
+        .and(warp::post())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(update_webhook_handler);
+
+    let remove_webhook_route = warp::path("remove_webhook")
+        .and(warp::post())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(remove_webhook_handler);
+
+    let list_webhook_deliveries_route = warp::path("webhook_deliveries")
+        .and(warp::get())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::query::<WebhookDeliveryFilter>())
+        .and_then(list_webhook_deliveries_handler);
+
+    let redeliver_webhook_route = warp::path("redeliver_webhook")
+        .and(warp::post())
+        .and(with_sender(node_commands_sender.clone()))
+        .and(warp::header::<String>("authorization"))
+        .and(warp::body::json())
+        .and_then(redeliver_webhook_handler);
+
+    add_webhook_route
+        .or(list_webhooks_route)
+        .or(update_webhook_route)
+        .or(remove_webhook_route)
+        .or(list_webhook_deliveries_route)
+        .or(redeliver_webhook_route)
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct RemoveWebhookRequest {
+    pub id: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct RedeliverWebhookRequest {
+    pub delivery_id: u64,
+}
+
+fn into_reply<T: serde::Serialize>(result: Result<T, APIError>) -> warp::reply::WithStatus<warp::reply::Json> {
+    match result {
+        Ok(response) => warp::reply::with_status(warp::reply::json(&response), StatusCode::OK),
+        Err(error) => warp::reply::with_status(warp::reply::json(&error), StatusCode::from_u16(error.code).unwrap()),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/v2/add_webhook",
+    request_body = AddWebhookRequest,
+    responses(
+        (status = 200, description = "Webhook registered. The secret is only returned here.", body = Webhook),
+        (status = 400, description = "Invalid URL", body = APIError),
+        (status = 401, description = "Unauthorized", body = APIError),
+        (status = 403, description = "Missing the admin scope", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn add_webhook_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    payload: AddWebhookRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiAddWebhook {
+            bearer,
+            payload,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+    Ok(into_reply(result))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v2/list_webhooks",
+    responses(
+        (status = 200, description = "Registered webhooks with their secrets redacted", body = Vec<Webhook>),
+        (status = 401, description = "Unauthorized", body = APIError),
+        (status = 403, description = "Missing the admin scope", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn list_webhooks_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiListWebhooks {
+            bearer,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+    Ok(into_reply(result))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v2/update_webhook",
+    request_body = UpdateWebhookRequest,
+    responses(
+        (status = 200, description = "Updated webhook with its secret redacted", body = Webhook),
+        (status = 400, description = "Invalid URL", body = APIError),
+        (status = 401, description = "Unauthorized", body = APIError),
+        (status = 403, description = "Missing the admin scope", body = APIError),
+        (status = 404, description = "Webhook not found", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn update_webhook_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    payload: UpdateWebhookRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiUpdateWebhook {
+            bearer,
+            payload,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+    Ok(into_reply(result))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v2/remove_webhook",
+    request_body = RemoveWebhookRequest,
+    responses(
+        (status = 200, description = "Webhook and its deliveries removed", body = Value),
+        (status = 401, description = "Unauthorized", body = APIError),
+        (status = 403, description = "Missing the admin scope", body = APIError),
+        (status = 404, description = "Webhook not found", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn remove_webhook_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    payload: RemoveWebhookRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiRemoveWebhook {
+            bearer,
+            id: payload.id,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+    Ok(into_reply(result))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v2/webhook_deliveries",
+    params(
+        ("webhook_id" = Option<String>, Query, description = "Only deliveries of this webhook"),
+        ("status" = Option<WebhookDeliveryStatus>, Query, description = "Only deliveries with this status"),
+        ("limit" = Option<u64>, Query, description = "Maximum number of deliveries")
+    ),
+    responses(
+        (status = 200, description = "Delivery log, newest first", body = Vec<WebhookDelivery>),
+        (status = 401, description = "Unauthorized", body = APIError),
+        (status = 403, description = "Missing the admin scope", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn list_webhook_deliveries_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    filter: WebhookDeliveryFilter,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiListWebhookDeliveries {
+            bearer,
+            filter,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+    Ok(into_reply(result))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v2/redeliver_webhook",
+    request_body = RedeliverWebhookRequest,
+    responses(
+        (status = 200, description = "Delivery queued again with a fresh set of attempts", body = WebhookDelivery),
+        (status = 401, description = "Unauthorized", body = APIError),
+        (status = 403, description = "Missing the admin scope", body = APIError),
+        (status = 404, description = "Delivery not found", body = APIError),
+        (status = 500, description = "Internal server error", body = APIError)
+    )
+)]
+pub async fn redeliver_webhook_handler(
+    sender: Sender<NodeCommand>,
+    authorization: String,
+    payload: RedeliverWebhookRequest,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization.strip_prefix("Bearer ").unwrap_or("").to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    sender
+        .send(NodeCommand::V2ApiRedeliverWebhook {
+            bearer,
+            delivery_id: payload.delivery_id,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+    Ok(into_reply(result))
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        add_webhook_handler,
+        list_webhooks_handler,
+        update_webhook_handler,
+        remove_webhook_handler,
+        list_webhook_deliveries_handler,
+        redeliver_webhook_handler,
+    ),
+    components(
+        schemas(APIError, AddWebhookRequest, UpdateWebhookRequest, RemoveWebhookRequest, RedeliverWebhookRequest,
+            Webhook, WebhookDelivery, WebhookDeliveryStatus, WebhookEventType)
+    ),
+    tags(
+        (name = "webhooks", description = "Outbound webhook API endpoints")
+    )
+)]
+pub struct WebhooksApiDoc;
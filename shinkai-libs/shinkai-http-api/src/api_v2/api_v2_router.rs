@@ -1,16 +1,26 @@
 use crate::node_commands::NodeCommand;
 
+use super::api_v2_handlers_audit::audit_routes;
+use super::api_v2_handlers_backup::backup_routes;
 use super::api_v2_handlers_cron::cron_routes;
 use super::api_v2_handlers_ext_agent_offers::ext_agent_offers_routes;
 use super::api_v2_handlers_general::general_routes;
+use super::api_v2_handlers_inbox_search::inbox_search_routes;
+use super::api_v2_handlers_job_dead_letters::job_dead_letter_routes;
+use super::api_v2_handlers_job_limits::job_limit_routes;
+use super::api_v2_handlers_job_pipelines::job_pipeline_routes;
 use super::api_v2_handlers_jobs::job_routes;
 use super::api_v2_handlers_oauth::oauth_routes;
 use super::api_v2_handlers_prompts::prompt_routes;
+use super::api_v2_handlers_scheduled_job_messages::scheduled_job_message_routes;
+use super::api_v2_handlers_secrets::secrets_routes;
 use super::api_v2_handlers_sheets::sheets_routes;
 use super::api_v2_handlers_swagger_ui::swagger_ui_routes;
 use super::api_v2_handlers_tools::tool_routes;
 use super::api_v2_handlers_vecfs::vecfs_routes;
 use super::api_v2_handlers_wallets::wallet_routes;
+use super::api_v2_handlers_webhook_triggers::webhook_trigger_routes;
+use super::api_v2_handlers_webhooks::webhook_routes;
 use async_channel::Sender;
 use serde::Serialize;
 use serde_json::{json, Value};
@@ -32,6 +42,16 @@ pub fn v2_routes(
     let tool_routes = tool_routes(node_commands_sender.clone());
     let cron_routes = cron_routes(node_commands_sender.clone(), node_name.clone());
     let oauth_routes = oauth_routes(node_commands_sender.clone());
+    let audit_routes = audit_routes(node_commands_sender.clone());
+    let secrets_routes = secrets_routes(node_commands_sender.clone());
+    let backup_routes = backup_routes(node_commands_sender.clone());
+    let webhook_routes = webhook_routes(node_commands_sender.clone());
+    let webhook_trigger_routes = webhook_trigger_routes(node_commands_sender.clone());
+    let inbox_search_routes = inbox_search_routes(node_commands_sender.clone());
+    let job_pipeline_routes = job_pipeline_routes(node_commands_sender.clone());
+    let job_dead_letter_routes = job_dead_letter_routes(node_commands_sender.clone());
+    let job_limit_routes = job_limit_routes(node_commands_sender.clone());
+    let scheduled_job_message_routes = scheduled_job_message_routes(node_commands_sender.clone());
 
     general_routes
         .or(vecfs_routes)
@@ -44,6 +64,16 @@ pub fn v2_routes(
         .or(tool_routes)
         .or(cron_routes)
         .or(oauth_routes)
+        .or(audit_routes)
+        .or(secrets_routes)
+        .or(backup_routes)
+        .or(webhook_routes)
+        .or(webhook_trigger_routes)
+        .or(inbox_search_routes)
+        .or(job_pipeline_routes)
+        .or(job_dead_letter_routes)
+        .or(job_limit_routes)
+        .or(scheduled_job_message_routes)
 }
 
 pub fn with_sender(
@@ -1,13 +1,23 @@
+pub mod api_v2_handlers_audit;
+pub mod api_v2_handlers_backup;
 pub mod api_v2_handlers_cron;
 pub mod api_v2_handlers_ext_agent_offers;
 pub mod api_v2_handlers_general;
+pub mod api_v2_handlers_inbox_search;
+pub mod api_v2_handlers_job_dead_letters;
+pub mod api_v2_handlers_job_limits;
+pub mod api_v2_handlers_job_pipelines;
 pub mod api_v2_handlers_jobs;
 pub mod api_v2_handlers_my_agent_offers;
 pub mod api_v2_handlers_oauth;
 pub mod api_v2_handlers_prompts;
+pub mod api_v2_handlers_scheduled_job_messages;
+pub mod api_v2_handlers_secrets;
 pub mod api_v2_handlers_sheets;
 pub mod api_v2_handlers_swagger_ui;
 pub mod api_v2_handlers_tools;
 pub mod api_v2_handlers_vecfs;
 pub mod api_v2_handlers_wallets;
+pub mod api_v2_handlers_webhook_triggers;
+pub mod api_v2_handlers_webhooks;
 pub mod api_v2_router;
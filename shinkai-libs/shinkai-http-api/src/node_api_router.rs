@@ -1,4 +1,5 @@
 use crate::api_v1;
+use crate::api_openai;
 use crate::api_v2;
 use crate::api_sse;
 
@@ -132,6 +133,10 @@ pub async fn run_api(
 
     let v1_routes = warp::path("v1").and(
         api_v1::api_v1_router::v1_routes(node_commands_sender.clone(), node_name.clone())
+            .or(api_openai::api_openai_router::openai_routes(
+                node_commands_sender.clone(),
+                node_name.clone(),
+            ))
             .recover(handle_rejection)
             .with(log)
             .with(cors.clone()),
@@ -151,10 +156,21 @@ pub async fn run_api(
             .with(cors.clone()),
     );
 
+    // Scrapers authenticate with a key holding the `metrics:read` scope
+    let metrics_route = warp::path("metrics").and(
+        warp::path::end()
+            .and(warp::get())
+            .and(warp::any().map(move || node_commands_sender.clone()))
+            .and(warp::header::optional::<String>("authorization"))
+            .and_then(metrics_handler)
+            .recover(handle_rejection),
+    );
+
     // Combine all routes
     let routes = v1_routes
         .or(v2_routes)
         .or(mcp_routes)
+        .or(metrics_route)
         .with(log).with(cors);
 
     // Wrap the HTTP server in an async block that returns a Result
@@ -237,6 +253,35 @@ pub async fn run_api(
     Ok(())
 }
 
+async fn metrics_handler(
+    node_commands_sender: Sender<NodeCommand>,
+    authorization: Option<String>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bearer = authorization
+        .as_deref()
+        .and_then(|authorization| authorization.strip_prefix("Bearer "))
+        .unwrap_or("")
+        .to_string();
+    let (res_sender, res_receiver) = async_channel::bounded(1);
+    node_commands_sender
+        .send(NodeCommand::GetNodeMetrics {
+            bearer,
+            res: res_sender,
+        })
+        .await
+        .map_err(|_| warp::reject::reject())?;
+    let result = res_receiver.recv().await.map_err(|_| warp::reject::reject())?;
+
+    match result {
+        Ok(metrics) => Ok(warp::reply::with_header(
+            metrics,
+            "content-type",
+            "text/plain; version=0.0.4",
+        )),
+        Err(error) => Err(warp::reject::custom(error)),
+    }
+}
+
 pub async fn handle_node_command<T, U, V>(
     node_commands_sender: Sender<NodeCommand>,
     message: V,
@@ -6,12 +6,20 @@ use ed25519_dalek::VerifyingKey;
 use serde_json::{Map, Value};
 use shinkai_message_primitives::{
     schemas::{
+        api_key::{ApiKey, ApiKeyScope},
+        audit_log::{AuditLogEntry, AuditLogFilter, AuditLogVerification},
         coinbase_mpc_config::CoinbaseMPCWalletConfig,
-        crontab::{CronTask, CronTaskAction},
+        crontab::{CronEventTrigger, CronTask, CronTaskAction, CronTaskPolicy},
         custom_prompt::CustomPrompt,
         identity::{Identity, StandardIdentity},
+        inbox_search::{InboxMessageSearchRequest, InboxMessageSearchResults},
+        job_concurrency_limit::{JobConcurrencyLimit, JobLimitTarget, JobQueueStatus},
         job_config::JobConfig,
+        job_dead_letter::JobDeadLetter,
+        job_pipeline::{AddJobPipelineRequest, JobPipeline, JobPipelineRun, RunJobPipelineRequest},
         llm_providers::{agent::Agent, serialized_llm_provider::SerializedLLMProvider, shinkai_backend::QuotaResponse},
+        node_backup::{BackupRestoreReport, CreateBackupRequest, RestoreBackupRequest},
+        scheduled_job_message::ScheduledJobMessage,
         shinkai_name::ShinkaiName,
         shinkai_subscription::ShinkaiSubscription,
         shinkai_tool_offering::{ShinkaiToolOffering, UsageTypeInquiry},
@@ -20,6 +28,8 @@ use shinkai_message_primitives::{
         tool_router_key::ToolRouterKey,
         wallet_complementary::{WalletRole, WalletSource},
         wallet_mixed::NetworkIdentifier,
+        webhook::{AddWebhookRequest, UpdateWebhookRequest, Webhook, WebhookDelivery, WebhookDeliveryFilter},
+        webhook_trigger::{AddWebhookTriggerRequest, UpdateWebhookTriggerRequest, WebhookTrigger, WebhookTriggerRun},
     },
     shinkai_message::{
         shinkai_message::ShinkaiMessage,
@@ -51,6 +61,7 @@ use x25519_dalek::PublicKey as EncryptionPublicKey;
 use crate::node_api_router::SendResponseBody;
 
 use super::{
+    api_openai::api_openai_handlers::OpenAIChatCompletionRequest,
     api_v1::api_v1_handlers::APIUseRegistrationCodeSuccessResponse,
     api_v2::api_v2_handlers_general::InitialRegistrationRequest,
     node_api_router::{APIError, GetPublicKeysResponse, SendResponseBodyData},
@@ -1116,6 +1127,30 @@ pub enum NodeCommand {
     V2ApiRemoveTool {
         bearer: String,
         tool_key: String,
+        force: bool,
+        res: Sender<Result<Value, APIError>>,
+    },
+    V2ApiGetToolDependencyGraph {
+        bearer: String,
+        tool_router_key: Option<String>,
+        res: Sender<Result<Value, APIError>>,
+    },
+    V2ApiSetToolNetworkApproval {
+        bearer: String,
+        tool_router_key: String,
+        approved: bool,
+        res: Sender<Result<Value, APIError>>,
+    },
+    V2ApiGetToolNetworkAccess {
+        bearer: String,
+        tool_router_key: String,
+        res: Sender<Result<Value, APIError>>,
+    },
+    V2ApiSetWasmToolGrant {
+        bearer: String,
+        tool_router_key: String,
+        approved: bool,
+        allow_outside_storage: bool,
         res: Sender<Result<Value, APIError>>,
     },
     V2ApiResolveShinkaiFileProtocol {
@@ -1126,6 +1161,9 @@ pub enum NodeCommand {
     V2ApiAddCronTask {
         bearer: String,
         cron: String,
+        timezone: Option<String>,
+        policy: CronTaskPolicy,
+        event_trigger: Option<CronEventTrigger>,
         action: CronTaskAction,
         name: String,
         description: Option<String>,
@@ -1154,6 +1192,9 @@ pub enum NodeCommand {
         bearer: String,
         cron_task_id: i64,
         cron: String,
+        timezone: Option<String>,
+        policy: Option<CronTaskPolicy>,
+        event_trigger: Option<CronEventTrigger>,
         action: CronTaskAction,
         name: String,
         description: Option<String>,
@@ -1325,6 +1366,31 @@ pub enum NodeCommand {
         mcp_enabled: bool,
         res: Sender<Result<Value, APIError>>,
     },
+    V2ApiListToolRevisions {
+        bearer: String,
+        tool_router_key: String,
+        res: Sender<Result<Value, APIError>>,
+    },
+    V2ApiDiffToolRevisions {
+        bearer: String,
+        tool_router_key: String,
+        from_revision: u64,
+        to_revision: u64,
+        res: Sender<Result<Value, APIError>>,
+    },
+    V2ApiRollbackToolRevision {
+        bearer: String,
+        tool_router_key: String,
+        revision: u64,
+        res: Sender<Result<Value, APIError>>,
+    },
+    V2ApiRunToolTests {
+        bearer: String,
+        tool_router_key: Option<String>,
+        skip_network_tests: bool,
+        llm_provider: String,
+        res: Sender<Result<Value, APIError>>,
+    },
     V2ApiCopyToolAssets {
         bearer: String,
         is_first_playground: bool,
@@ -1342,4 +1408,206 @@ pub enum NodeCommand {
         bearer: String,
         res: Sender<Result<Value, APIError>>,
     },
+    V2ApiCreateApiKey {
+        bearer: String,
+        name: String,
+        scopes: Vec<ApiKeyScope>,
+        agent_ids: Option<Vec<String>>,
+        expires_at: Option<DateTime<Utc>>,
+        res: Sender<Result<Value, APIError>>,
+    },
+    V2ApiListApiKeys {
+        bearer: String,
+        res: Sender<Result<Vec<ApiKey>, APIError>>,
+    },
+    V2ApiRevokeApiKey {
+        bearer: String,
+        id: String,
+        res: Sender<Result<Value, APIError>>,
+    },
+    V2ApiOpenAIListModels {
+        bearer: String,
+        res: Sender<Result<Value, APIError>>,
+    },
+    V2ApiOpenAIChatCompletion {
+        bearer: String,
+        request: OpenAIChatCompletionRequest,
+        // Completion chunks when streaming, otherwise a single completion
+        res: Sender<Result<async_channel::Receiver<Result<Value, APIError>>, APIError>>,
+    },
+    // Prometheus text exposition of the node metrics
+    GetNodeMetrics {
+        bearer: String,
+        res: Sender<Result<String, APIError>>,
+    },
+    V2ApiGetAuditLog {
+        bearer: String,
+        filter: AuditLogFilter,
+        res: Sender<Result<Vec<AuditLogEntry>, APIError>>,
+    },
+    // JSON Lines export of the matching audit log entries
+    V2ApiExportAuditLog {
+        bearer: String,
+        filter: AuditLogFilter,
+        res: Sender<Result<String, APIError>>,
+    },
+    V2ApiVerifyAuditLog {
+        bearer: String,
+        res: Sender<Result<AuditLogVerification, APIError>>,
+    },
+    V2ApiRotateSecretsKey {
+        bearer: String,
+        new_passphrase: Option<String>,
+        res: Sender<Result<Value, APIError>>,
+    },
+    V2ApiCreateBackup {
+        bearer: String,
+        payload: CreateBackupRequest,
+        /// Unnamed temporary file holding the archive, removed when it is dropped
+        res: Sender<Result<std::fs::File, APIError>>,
+    },
+    V2ApiRestoreBackup {
+        bearer: String,
+        /// Unnamed temporary file holding the uploaded archive
+        file: std::fs::File,
+        payload: RestoreBackupRequest,
+        res: Sender<Result<BackupRestoreReport, APIError>>,
+    },
+    V2ApiAddWebhook {
+        bearer: String,
+        payload: AddWebhookRequest,
+        res: Sender<Result<Webhook, APIError>>,
+    },
+    V2ApiListWebhooks {
+        bearer: String,
+        res: Sender<Result<Vec<Webhook>, APIError>>,
+    },
+    V2ApiUpdateWebhook {
+        bearer: String,
+        payload: UpdateWebhookRequest,
+        res: Sender<Result<Webhook, APIError>>,
+    },
+    V2ApiRemoveWebhook {
+        bearer: String,
+        id: String,
+        res: Sender<Result<Value, APIError>>,
+    },
+    V2ApiListWebhookDeliveries {
+        bearer: String,
+        filter: WebhookDeliveryFilter,
+        res: Sender<Result<Vec<WebhookDelivery>, APIError>>,
+    },
+    V2ApiRedeliverWebhook {
+        bearer: String,
+        delivery_id: u64,
+        res: Sender<Result<WebhookDelivery, APIError>>,
+    },
+    V2ApiAddWebhookTrigger {
+        bearer: String,
+        payload: AddWebhookTriggerRequest,
+        res: Sender<Result<WebhookTrigger, APIError>>,
+    },
+    V2ApiListWebhookTriggers {
+        bearer: String,
+        res: Sender<Result<Vec<WebhookTrigger>, APIError>>,
+    },
+    V2ApiUpdateWebhookTrigger {
+        bearer: String,
+        payload: UpdateWebhookTriggerRequest,
+        res: Sender<Result<WebhookTrigger, APIError>>,
+    },
+    V2ApiRemoveWebhookTrigger {
+        bearer: String,
+        id: String,
+        res: Sender<Result<Value, APIError>>,
+    },
+    /// Authenticated by the trigger secret, given as the bearer token or as a body signature
+    V2ApiInvokeWebhookTrigger {
+        id: String,
+        authorization: Option<String>,
+        signature: Option<String>,
+        body: String,
+        sync: Option<bool>,
+        res: Sender<Result<WebhookTriggerRun, APIError>>,
+    },
+    V2ApiSearchInboxMessages {
+        bearer: String,
+        request: InboxMessageSearchRequest,
+        res: Sender<Result<InboxMessageSearchResults, APIError>>,
+    },
+    V2ApiAddJobPipeline {
+        bearer: String,
+        payload: AddJobPipelineRequest,
+        res: Sender<Result<JobPipeline, APIError>>,
+    },
+    V2ApiListJobPipelines {
+        bearer: String,
+        res: Sender<Result<Vec<JobPipeline>, APIError>>,
+    },
+    V2ApiRemoveJobPipeline {
+        bearer: String,
+        id: String,
+        res: Sender<Result<Value, APIError>>,
+    },
+    V2ApiRunJobPipeline {
+        bearer: String,
+        payload: RunJobPipelineRequest,
+        res: Sender<Result<JobPipelineRun, APIError>>,
+    },
+    V2ApiGetJobPipelineRun {
+        bearer: String,
+        run_id: String,
+        res: Sender<Result<JobPipelineRun, APIError>>,
+    },
+    V2ApiResumeJobPipelineRun {
+        bearer: String,
+        run_id: String,
+        res: Sender<Result<JobPipelineRun, APIError>>,
+    },
+    V2ApiListJobDeadLetters {
+        bearer: String,
+        job_id: Option<String>,
+        res: Sender<Result<Vec<JobDeadLetter>, APIError>>,
+    },
+    V2ApiRequeueJobDeadLetter {
+        bearer: String,
+        id: String,
+        res: Sender<Result<Value, APIError>>,
+    },
+    V2ApiDiscardJobDeadLetter {
+        bearer: String,
+        id: String,
+        res: Sender<Result<Value, APIError>>,
+    },
+    V2ApiSetJobConcurrencyLimit {
+        bearer: String,
+        limit: JobConcurrencyLimit,
+        res: Sender<Result<JobConcurrencyLimit, APIError>>,
+    },
+    V2ApiRemoveJobConcurrencyLimit {
+        bearer: String,
+        target: JobLimitTarget,
+        target_id: String,
+        res: Sender<Result<Value, APIError>>,
+    },
+    V2ApiGetJobQueueStatus {
+        bearer: String,
+        res: Sender<Result<JobQueueStatus, APIError>>,
+    },
+    V2ApiScheduleJobMessage {
+        bearer: String,
+        job_message: JobMessage,
+        send_at: DateTime<Utc>,
+        res: Sender<Result<ScheduledJobMessage, APIError>>,
+    },
+    V2ApiListScheduledJobMessages {
+        bearer: String,
+        job_id: Option<String>,
+        res: Sender<Result<Vec<ScheduledJobMessage>, APIError>>,
+    },
+    V2ApiCancelScheduledJobMessage {
+        bearer: String,
+        id: String,
+        res: Sender<Result<Value, APIError>>,
+    },
 }
@@ -1,4 +1,4 @@
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
@@ -10,6 +10,7 @@ use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::fmt::Debug;
 use std::sync::{Arc, Weak};
+use std::time::Duration;
 use tokio::sync::{mpsc, Mutex};
 
 type MutexQueue<T> = Arc<Mutex<Vec<T>>>;
@@ -21,6 +22,16 @@ pub struct JobForProcessing {
     pub profile: ShinkaiName,
     pub date_created: String,
     pub message_hash_id: Option<String>,
+    /// Jobs whose next message has a higher priority are processed first. Messages of the same
+    /// job are still processed in the order they were sent.
+    #[serde(default)]
+    pub priority: i32,
+    /// Attempts of the message that failed with a transient error
+    #[serde(default)]
+    pub failed_attempts: u32,
+    /// A message retried after a failed attempt is not picked up before this time (RFC3339)
+    #[serde(default)]
+    pub not_before: Option<String>,
     // TODO: add a new optional field for callbacks
 }
 
@@ -31,8 +42,30 @@ impl JobForProcessing {
             profile,
             date_created: Utc::now().to_rfc3339(),
             message_hash_id,
+            priority: 0,
+            failed_attempts: 0,
+            not_before: None,
         }
     }
+
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// The message after one more failed attempt, due once `backoff` has passed
+    pub fn retried_after(mut self, backoff: Duration) -> Self {
+        let backoff = chrono::Duration::from_std(backoff).unwrap_or_else(|_| chrono::Duration::zero());
+        self.failed_attempts += 1;
+        self.not_before = Some((Utc::now() + backoff).to_rfc3339());
+        self
+    }
+
+    /// Time left before a retried message can be picked up, `None` once it is due
+    pub fn time_until_due(&self) -> Option<Duration> {
+        let not_before = DateTime::parse_from_rfc3339(self.not_before.as_deref()?).ok()?;
+        not_before.signed_duration_since(Utc::now()).to_std().ok()
+    }
 }
 
 impl PartialOrd for JobForProcessing {
@@ -41,9 +74,13 @@ impl PartialOrd for JobForProcessing {
     }
 }
 
+// Higher priority first, then oldest first
 impl Ord for JobForProcessing {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.date_created.cmp(&other.date_created)
+        other
+            .priority
+            .cmp(&self.priority)
+            .then_with(|| self.date_created.cmp(&other.date_created))
     }
 }
 
@@ -130,7 +167,39 @@ impl<T: Clone + Send + 'static + DeserializeOwned + Serialize + Ord + Debug> Job
         let db_arc = self.db.upgrade().ok_or("Failed to upgrade shinkai_db").unwrap();
         db_arc.persist_queue(key, &guarded_queue, self.prefix.clone())?;
         drop(db_arc);
+        drop(guarded_queue);
+        drop(queues);
+
+        self.notify_subscribers(key, value).await;
+        Ok(())
+    }
+
+    /// Replaces the next element of `key`, e.g. to retry it later, and notifies the subscribers.
+    /// Returns false if the queue is empty.
+    pub async fn replace_first(&mut self, key: &str, value: T) -> Result<bool, SqliteManagerError> {
+        let queues = self.queues.lock().await;
+        let Some(queue) = queues.get(key) else {
+            return Ok(false);
+        };
+
+        let mut guarded_queue = queue.lock().await;
+        let Some(first) = guarded_queue.first_mut() else {
+            return Ok(false);
+        };
+        *first = value.clone();
+
+        // Persist queue to the database
+        let db_arc = self.db.upgrade().ok_or("Failed to upgrade shinkai_db").unwrap();
+        db_arc.persist_queue(key, &guarded_queue, self.prefix.clone())?;
+        drop(db_arc);
+        drop(guarded_queue);
+        drop(queues);
 
+        self.notify_subscribers(key, value).await;
+        Ok(true)
+    }
+
+    async fn notify_subscribers(&self, key: &str, value: T) {
         // Notify subscribers
         let subscribers = self.subscribers.lock().await;
         if let Some(subs) = subscribers.get(key) {
@@ -150,7 +219,6 @@ impl<T: Clone + Send + 'static + DeserializeOwned + Serialize + Ord + Debug> Job
                 let _ = sub.send(value.clone()).await;
             }
         }
-        Ok(())
     }
 
     pub async fn dequeue(&mut self, key: &str) -> Result<Option<T>, SqliteManagerError> {
@@ -577,4 +645,84 @@ mod tests {
         // Check if the elements are in the correct order
         assert_eq!(all_elements, vec![job_a1, job_b1, job_c1, job_a2, job_c2, job_a3]);
     }
+
+    #[tokio::test]
+    async fn test_get_all_elements_interleave_by_priority() {
+        let db = setup();
+        let db = Arc::new(db);
+        let db_weak = Arc::downgrade(&db);
+        let mut manager = JobQueueManager::<JobForProcessing>::new(db_weak, None).await.unwrap();
+
+        let job = |job_id: &str, priority: i32| {
+            JobForProcessing::new(
+                JobMessage {
+                    job_id: job_id.to_string(),
+                    content: format!("content {}", job_id),
+                    fs_files_paths: vec![],
+                    job_filenames: vec![],
+                    parent: None,
+                    sheet_job_data: None,
+                    callback: None,
+                    metadata: None,
+                    tool_key: None,
+                    tools: None,
+                },
+                ShinkaiName::new("@@node1.shinkai/main".to_string()).unwrap(),
+                None,
+            )
+            .with_priority(priority)
+        };
+        let job_a1 = job("a1", 0);
+        let job_b1 = job("b1", 10);
+        let job_b2 = job("b2", 0);
+        let job_c1 = job("c1", 5);
+
+        manager.push("job_a", job_a1.clone()).await.unwrap();
+        manager.push("job_b", job_b1.clone()).await.unwrap();
+        manager.push("job_b", job_b2.clone()).await.unwrap();
+        manager.push("job_c", job_c1.clone()).await.unwrap();
+
+        // Jobs are ordered by the priority of their next message, which keeps the order of a job
+        let all_elements = manager.get_all_elements_interleave().await.unwrap();
+        assert_eq!(all_elements, vec![job_b1, job_c1, job_a1, job_b2]);
+    }
+
+    #[tokio::test]
+    async fn test_replace_first_reschedules_a_retry() {
+        let db = setup();
+        let db = Arc::new(db);
+        let db_weak = Arc::downgrade(&db);
+        let mut manager = JobQueueManager::<JobForProcessing>::new(db_weak.clone(), None)
+            .await
+            .unwrap();
+
+        let job = JobForProcessing::new(
+            JobMessage {
+                job_id: "job_a".to_string(),
+                content: "my content".to_string(),
+                fs_files_paths: vec![],
+                job_filenames: vec![],
+                parent: None,
+                sheet_job_data: None,
+                callback: None,
+                metadata: None,
+                tool_key: None,
+                tools: None,
+            },
+            ShinkaiName::new("@@node1.shinkai/main".to_string()).unwrap(),
+            None,
+        );
+        assert_eq!(job.time_until_due(), None);
+        assert!(!manager.replace_first("job_a", job.clone()).await.unwrap());
+
+        manager.push("job_a", job.clone()).await.unwrap();
+        let retry = job.clone().retried_after(std::time::Duration::from_secs(60));
+        assert_eq!(retry.failed_attempts, 1);
+        assert!(retry.time_until_due().is_some());
+        assert!(manager.replace_first("job_a", retry.clone()).await.unwrap());
+
+        // The retry keeps its place in the queue and survives a restart
+        let manager = JobQueueManager::<JobForProcessing>::new(db_weak, None).await.unwrap();
+        assert_eq!(manager.peek("job_a").await.unwrap(), Some(retry));
+    }
 }
@@ -0,0 +1,130 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Permission granted to an API key. Write scopes include the matching read scope and
+/// `admin` includes every scope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+pub enum ApiKeyScope {
+    #[serde(rename = "jobs:read")]
+    JobsRead,
+    #[serde(rename = "jobs:write")]
+    JobsWrite,
+    #[serde(rename = "tools:read")]
+    ToolsRead,
+    #[serde(rename = "tools:execute")]
+    ToolsExecute,
+    #[serde(rename = "vecfs:read")]
+    VecFsRead,
+    #[serde(rename = "vecfs:write")]
+    VecFsWrite,
+    #[serde(rename = "wallet:read")]
+    WalletRead,
+    #[serde(rename = "wallet:pay")]
+    WalletPay,
+    /// Scraping `/metrics`
+    #[serde(rename = "metrics:read")]
+    MetricsRead,
+    #[serde(rename = "admin")]
+    Admin,
+}
+
+impl ApiKeyScope {
+    /// Whether holding `self` is enough for an endpoint requiring `required`
+    pub fn grants(&self, required: ApiKeyScope) -> bool {
+        use ApiKeyScope::*;
+        *self == required
+            || matches!(
+                (self, required),
+                (Admin, _)
+                    | (JobsWrite, JobsRead)
+                    | (ToolsExecute, ToolsRead)
+                    | (VecFsWrite, VecFsRead)
+                    | (WalletPay, WalletRead)
+            )
+    }
+}
+
+impl std::fmt::Display for ApiKeyScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = serde_json::to_value(self).map_err(|_| std::fmt::Error)?;
+        write!(f, "{}", value.as_str().unwrap_or_default())
+    }
+}
+
+/// A named API key. Only a hash of the secret is stored, the secret itself is returned
+/// once when the key is created.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ApiKey {
+    pub id: String,
+    pub name: String,
+    /// First characters of the secret, to tell keys apart
+    pub key_prefix: String,
+    pub scopes: Vec<ApiKeyScope>,
+    /// Agents (or LLM providers) the key is restricted to. `None` allows every agent.
+    pub agent_ids: Option<Vec<String>>,
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub expires_at: Option<DateTime<Utc>>,
+    #[schema(value_type = String, format = DateTime)]
+    pub created_at: DateTime<Utc>,
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked: bool,
+}
+
+impl ApiKey {
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        !self.revoked && self.expires_at.map_or(true, |expires_at| expires_at > now)
+    }
+
+    pub fn has_scope(&self, required: ApiKeyScope) -> bool {
+        self.scopes.iter().any(|scope| scope.grants(required))
+    }
+
+    pub fn allows_agent(&self, agent_id: &str) -> bool {
+        self.agent_ids
+            .as_ref()
+            .map_or(true, |agent_ids| agent_ids.iter().any(|id| id == agent_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_api_key_scopes_and_restrictions() {
+        let now = Utc::now();
+        let mut key = ApiKey {
+            id: "key-1".to_string(),
+            name: "ci".to_string(),
+            key_prefix: "sk_shinkai_ab".to_string(),
+            scopes: serde_json::from_str(r#"["jobs:write", "vecfs:read"]"#).unwrap(),
+            agent_ids: Some(vec!["my_agent".to_string()]),
+            expires_at: Some(now + Duration::hours(1)),
+            created_at: now,
+            last_used_at: None,
+            revoked: false,
+        };
+
+        assert!(key.has_scope(ApiKeyScope::JobsRead));
+        assert!(key.has_scope(ApiKeyScope::JobsWrite));
+        assert!(key.has_scope(ApiKeyScope::VecFsRead));
+        assert!(!key.has_scope(ApiKeyScope::VecFsWrite));
+        assert!(!key.has_scope(ApiKeyScope::WalletPay));
+        assert!(ApiKeyScope::Admin.grants(ApiKeyScope::WalletPay));
+        assert_eq!(ApiKeyScope::ToolsExecute.to_string(), "tools:execute");
+        assert!(!key.has_scope(ApiKeyScope::MetricsRead));
+        assert!(ApiKeyScope::Admin.grants(ApiKeyScope::MetricsRead));
+        assert_eq!(ApiKeyScope::MetricsRead.to_string(), "metrics:read");
+
+        assert!(key.allows_agent("my_agent"));
+        assert!(!key.allows_agent("other_agent"));
+
+        assert!(key.is_active(now));
+        assert!(!key.is_active(now + Duration::hours(2)));
+        key.revoked = true;
+        assert!(!key.is_active(now));
+    }
+}
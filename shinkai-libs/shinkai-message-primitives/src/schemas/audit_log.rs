@@ -0,0 +1,176 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::ToSchema;
+
+/// `prev_hash` of the first entry in the chain
+pub const AUDIT_LOG_GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Security-relevant action recorded in the audit log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    AgentCreated,
+    AgentUpdated,
+    AgentRemoved,
+    LlmProviderCreated,
+    LlmProviderUpdated,
+    LlmProviderRemoved,
+    ToolInstalled,
+    ToolConfigChanged,
+    ToolRemoved,
+    ToolEgressBlocked,
+    #[serde(rename = "oauth_token_changed")]
+    OAuthTokenChanged,
+    WalletCreated,
+    WalletRestored,
+    PaymentMade,
+    PreferencesChanged,
+    FileDeleted,
+    CronTaskCreated,
+    CronTaskUpdated,
+    CronTaskRemoved,
+    SecretsKeyRotated,
+    BackupCreated,
+    BackupRestored,
+    WebhookCreated,
+    WebhookUpdated,
+    WebhookRemoved,
+    WebhookTriggerCreated,
+    WebhookTriggerUpdated,
+    WebhookTriggerRemoved,
+    JobPipelineCreated,
+    JobPipelineRemoved,
+    JobDeadLetterRequeued,
+    JobDeadLetterDiscarded,
+    JobConcurrencyLimitSet,
+    JobConcurrencyLimitRemoved,
+    ScheduledJobMessageCancelled,
+}
+
+impl std::fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = serde_json::to_value(self).map_err(|_| std::fmt::Error)?;
+        write!(f, "{}", value.as_str().unwrap_or_default())
+    }
+}
+
+/// Result of the audited request, with the API error when it failed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum AuditOutcome {
+    Success,
+    Failure { code: u16, message: String },
+}
+
+impl AuditOutcome {
+    pub fn is_success(&self) -> bool {
+        matches!(self, AuditOutcome::Success)
+    }
+}
+
+/// One append-only entry of the audit log. Each entry stores the hash of the previous one,
+/// so editing or removing an entry breaks the chain from that point on.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct AuditLogEntry {
+    pub id: u64,
+    #[schema(value_type = String, format = DateTime)]
+    pub timestamp: DateTime<Utc>,
+    /// Name of the API key used, or `node` for the node API key
+    pub actor: String,
+    pub api_key_id: Option<String>,
+    pub action: AuditAction,
+    /// Agent id, tool key, file path, etc. the action applies to
+    pub target: Option<String>,
+    pub parameters: Value,
+    pub outcome: AuditOutcome,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+impl AuditLogEntry {
+    /// Blake3 hash of every field except `hash` itself
+    pub fn compute_hash(&self) -> String {
+        let content = serde_json::json!({
+            "id": self.id,
+            "timestamp": self.timestamp.to_rfc3339(),
+            "actor": self.actor,
+            "api_key_id": self.api_key_id,
+            "action": self.action,
+            "target": self.target,
+            "parameters": self.parameters,
+            "outcome": self.outcome,
+            "prev_hash": self.prev_hash,
+        });
+        blake3::hash(content.to_string().as_bytes()).to_hex().to_string()
+    }
+}
+
+/// Filters for querying the audit log. Every field is optional and they are combined with AND.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct AuditLogFilter {
+    pub action: Option<AuditAction>,
+    pub actor: Option<String>,
+    pub api_key_id: Option<String>,
+    pub target: Option<String>,
+    /// Only successful (`true`) or failed (`false`) actions
+    pub success: Option<bool>,
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub from: Option<DateTime<Utc>>,
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub to: Option<DateTime<Utc>>,
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+}
+
+/// Result of walking the whole hash chain
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct AuditLogVerification {
+    pub valid: bool,
+    pub entries_checked: u64,
+    /// First entry whose hash or link to the previous entry doesn't match, or the first one
+    /// missing from the end of the chain
+    pub first_invalid_id: Option<u64>,
+    /// Whether the chain ends where the head signed with the node identity key says it does
+    pub anchored: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_audit_log_entry_hash_detects_tampering() {
+        let mut entry = AuditLogEntry {
+            id: 1,
+            timestamp: Utc::now(),
+            actor: "node".to_string(),
+            api_key_id: None,
+            action: AuditAction::AgentRemoved,
+            target: Some("my_agent".to_string()),
+            parameters: json!({}),
+            outcome: AuditOutcome::Success,
+            prev_hash: AUDIT_LOG_GENESIS_HASH.to_string(),
+            hash: String::new(),
+        };
+        entry.hash = entry.compute_hash();
+
+        let roundtrip: AuditLogEntry = serde_json::from_str(&serde_json::to_string(&entry).unwrap()).unwrap();
+        assert_eq!(roundtrip.compute_hash(), entry.hash);
+
+        let mut tampered = entry.clone();
+        tampered.target = Some("other_agent".to_string());
+        assert_ne!(tampered.compute_hash(), entry.hash);
+
+        let failure = AuditOutcome::Failure {
+            code: 401,
+            message: "Invalid bearer token".to_string(),
+        };
+        assert_eq!(
+            serde_json::to_value(&failure).unwrap(),
+            json!({ "status": "failure", "code": 401, "message": "Invalid bearer token" })
+        );
+        assert_eq!(AuditAction::OAuthTokenChanged.to_string(), "oauth_token_changed");
+    }
+}
@@ -1,20 +1,41 @@
 use crate::shinkai_message::shinkai_message_schemas::{JobCreationInfo, JobMessage};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use utoipa::ToSchema;
 
+use super::inbox_name::InboxName;
 use super::job_config::JobConfig;
+use super::webhook::WebhookEventType;
+use super::webhook_trigger::render_payload_template;
+
+/// Prefix of an interval schedule, e.g. `@every 15m`
+pub const CRON_INTERVAL_PREFIX: &str = "@every";
+/// Prefix of a one-shot schedule, e.g. `@at 2025-03-01T09:00:00`
+pub const CRON_RUN_AT_PREFIX: &str = "@at";
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
 pub struct CronTask {
     pub name: String,
     pub description: Option<String>,
     pub task_id: i32,
+    /// A cron expression with five fields, or six with seconds first, an interval such as
+    /// `@every 15m` counted from the creation of the task, or a single run such as
+    /// `@at 2025-03-01T09:00:00`
     pub cron: String,
+    /// IANA timezone the schedule is evaluated in, e.g. `Europe/Berlin`. Tasks without one use
+    /// the timezone of the host.
+    #[serde(default)]
+    pub timezone: Option<String>,
     pub created_at: String,
     pub last_modified: String,
     pub action: CronTaskAction,
     #[serde(default)]
     pub paused: bool,
+    #[serde(default)]
+    pub policy: CronTaskPolicy,
+    /// Runs the task on a node event instead of on its schedule, `cron` is then ignored
+    #[serde(default)]
+    pub event_trigger: Option<CronEventTrigger>,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
@@ -29,4 +50,339 @@ pub enum CronTaskAction {
         job_creation_info: JobCreationInfo,
         llm_provider: String,
     },
+    /// Starts a run of a job pipeline with `input`
+    RunJobPipeline {
+        pipeline_id: String,
+        #[serde(default)]
+        input: Value,
+    },
+}
+
+impl CronTaskAction {
+    /// The action with `{{event}}` and `{{event.a.b}}` in its message replaced by the payload of
+    /// the event that triggered it, or by one of its fields. Pipelines get the payload as the
+    /// `event` field of their input.
+    pub fn with_event_payload(&self, payload: &Value) -> Self {
+        let mut action = self.clone();
+        let message = match &mut action {
+            CronTaskAction::SendMessageToJob { message, .. } => message,
+            CronTaskAction::CreateJobWithConfigAndMessage { message, .. } => message,
+            // The step prompts of a pipeline read the payload from the run input
+            CronTaskAction::RunJobPipeline { input, .. } => {
+                if !input.is_object() {
+                    *input = Value::Object(Default::default());
+                }
+                input["event"] = payload.clone();
+                return action;
+            }
+        };
+        message.content = render_payload_template(&message.content, "event", payload);
+        action
+    }
+}
+
+/// Node event an event-triggered task listens to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CronTaskEvent {
+    /// A file was added or changed in a vector FS folder or one of its subfolders
+    FileChanged { folder: String },
+    /// A job of the agent finished without error
+    JobCompleted { agent_id: String },
+    /// A message was added to the inbox
+    InboxMessage { inbox_name: String },
+    /// An invoice was paid
+    InvoicePaid,
+    /// Another task ran successfully
+    TaskCompleted { task_id: i64 },
+}
+
+impl CronTaskEvent {
+    /// Type of the node events that can fire the trigger
+    pub fn event_type(&self) -> WebhookEventType {
+        match self {
+            CronTaskEvent::FileChanged { .. } => WebhookEventType::FileChanged,
+            CronTaskEvent::JobCompleted { .. } => WebhookEventType::JobCompleted,
+            CronTaskEvent::InboxMessage { .. } => WebhookEventType::InboxMessage,
+            CronTaskEvent::InvoicePaid => WebhookEventType::InvoiceUpdated,
+            CronTaskEvent::TaskCompleted { .. } => WebhookEventType::CronTaskExecuted,
+        }
+    }
+
+    /// Whether the node event `event_type` with `payload` fires the trigger
+    pub fn matches(&self, event_type: WebhookEventType, payload: &Value) -> bool {
+        if event_type != self.event_type() {
+            return false;
+        }
+        let field = |name: &str| payload.get(name).and_then(Value::as_str);
+        match self {
+            CronTaskEvent::FileChanged { folder } => {
+                // Paths are relative to the vector FS root, with or without a leading slash
+                let folder = folder.trim_matches('/');
+                field("path")
+                    .map(|path| path.trim_start_matches('/'))
+                    .map_or(false, |path| {
+                        folder.is_empty() || path == folder || path.starts_with(&format!("{}/", folder))
+                    })
+            }
+            CronTaskEvent::JobCompleted { agent_id } => field("agent_id") == Some(agent_id.as_str()),
+            CronTaskEvent::InboxMessage { inbox_name } => field("inbox_name") == Some(inbox_name.as_str()),
+            CronTaskEvent::InvoicePaid => field("status") == Some("Paid"),
+            CronTaskEvent::TaskCompleted { task_id } => {
+                payload.get("task_id").and_then(Value::as_i64) == Some(*task_id) && field("status") == Some("success")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct CronEventTrigger {
+    pub event: CronTaskEvent,
+    /// Events within this many seconds of the first one are coalesced into a single run, with the
+    /// payload of the latest one
+    #[serde(default)]
+    pub debounce_secs: u64,
+}
+
+impl CronEventTrigger {
+    /// Rejects triggers fired by the messages the task itself sends, which would run it in a loop
+    pub fn validate(&self, action: &CronTaskAction) -> Result<(), String> {
+        if let (CronTaskEvent::InboxMessage { inbox_name }, CronTaskAction::SendMessageToJob { job_id, .. }) =
+            (&self.event, action)
+        {
+            let job_inbox = InboxName::get_job_inbox_name_from_params(job_id.clone());
+            if job_inbox.map_or(false, |job_inbox| job_inbox.get_value() == inbox_name.to_lowercase()) {
+                return Err(format!(
+                    "The task sends its message to '{}', it can't be triggered by the messages of that inbox",
+                    inbox_name
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What happens to runs that were due while the node was offline or busy
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CronMisfirePolicy {
+    /// Missed runs are only recorded in the execution log
+    #[default]
+    Skip,
+    /// The latest missed run is made up for once
+    RunOnce,
+    /// Every missed run is made up for, oldest first
+    RunAll,
+}
+
+/// How failed executions are retried. The delay before attempt `n + 1` is
+/// `backoff_secs * backoff_multiplier^(n - 1)`, capped at `max_backoff_secs`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct CronRetryPolicy {
+    /// Attempts per run, including the first one
+    pub max_attempts: u32,
+    pub backoff_secs: u64,
+    pub backoff_multiplier: f64,
+    pub max_backoff_secs: u64,
+}
+
+impl Default for CronRetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 1,
+            backoff_secs: 60,
+            backoff_multiplier: 2.0,
+            max_backoff_secs: 3600,
+        }
+    }
+}
+
+impl CronRetryPolicy {
+    /// Seconds to wait after `failed_attempts` attempts failed
+    pub fn backoff(&self, failed_attempts: u32) -> u64 {
+        let exponent = failed_attempts.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let backoff = self.backoff_secs as f64 * self.backoff_multiplier.powi(exponent);
+        backoff.min(self.max_backoff_secs as f64) as u64
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct CronTaskPolicy {
+    pub misfire: CronMisfirePolicy,
+    pub retry: CronRetryPolicy,
+    /// Seconds an execution may take, from sending the message until the agent answers. Past
+    /// that the message is stopped and the execution is marked as timed out. Without one the
+    /// execution waits for the answer however long it takes.
+    pub timeout_secs: Option<u64>,
+}
+
+impl CronTaskPolicy {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.retry.max_attempts == 0 {
+            return Err("retry.max_attempts must be at least 1".to_string());
+        }
+        if !(self.retry.backoff_multiplier >= 1.0) {
+            return Err("retry.backoff_multiplier must be at least 1".to_string());
+        }
+        if self.timeout_secs == Some(0) {
+            return Err("timeout_secs must be greater than 0".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CronExecutionStatus {
+    Running,
+    Success,
+    Failed,
+    TimedOut,
+    /// Missed runs that were not made up for
+    Skipped,
+}
+
+impl std::fmt::Display for CronExecutionStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = serde_json::to_value(self).map_err(|_| std::fmt::Error)?;
+        write!(f, "{}", value.as_str().unwrap_or_default())
+    }
+}
+
+/// One attempt at running a task, or a record of skipped runs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct CronTaskExecution {
+    pub execution_id: i64,
+    pub task_id: i64,
+    /// When the attempt started
+    pub execution_time: String,
+    /// When the run was due, `None` for runs forced through the API
+    pub scheduled_time: Option<String>,
+    pub attempt: u32,
+    /// Whether the run was made up for after being missed
+    pub misfired: bool,
+    pub status: CronExecutionStatus,
+    pub success: bool,
+    pub error_message: Option<String>,
+    pub job_id: Option<String>,
+    pub finished_at: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_cron_task_policy() {
+        let policy: CronTaskPolicy =
+            serde_json::from_str(r#"{ "misfire": "run_once", "retry": { "max_attempts": 4, "backoff_secs": 10 } }"#)
+                .unwrap();
+        assert_eq!(policy.misfire, CronMisfirePolicy::RunOnce);
+        assert_eq!(policy.retry.max_attempts, 4);
+        assert_eq!(policy.timeout_secs, None);
+        assert!(policy.validate().is_ok());
+
+        assert_eq!(policy.retry.backoff(1), 10);
+        assert_eq!(policy.retry.backoff(2), 20);
+        assert_eq!(policy.retry.backoff(3), 40);
+        let capped = CronRetryPolicy {
+            max_backoff_secs: 30,
+            ..policy.retry.clone()
+        };
+        assert_eq!(capped.backoff(3), 30);
+
+        let invalid = CronTaskPolicy {
+            timeout_secs: Some(0),
+            ..policy
+        };
+        assert!(invalid.validate().is_err());
+        assert!(CronTaskPolicy::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_cron_task_event_triggers() {
+        let trigger: CronEventTrigger =
+            serde_json::from_str(r#"{ "event": { "type": "file_changed", "folder": "/reports/" } }"#).unwrap();
+        assert_eq!(trigger.debounce_secs, 0);
+        let event = trigger.event;
+        assert!(event.matches(
+            WebhookEventType::FileChanged,
+            &json!({ "path": "/reports/2024/q1.pdf" })
+        ));
+        assert!(event.matches(WebhookEventType::FileChanged, &json!({ "path": "reports/q1.pdf" })));
+        assert!(!event.matches(WebhookEventType::FileChanged, &json!({ "path": "/reports-old/q1.pdf" })));
+        assert!(!event.matches(WebhookEventType::InboxMessage, &json!({ "path": "/reports/q1.pdf" })));
+
+        let event = CronTaskEvent::TaskCompleted { task_id: 7 };
+        assert!(event.matches(
+            WebhookEventType::CronTaskExecuted,
+            &json!({ "task_id": 7, "status": "success" })
+        ));
+        assert!(!event.matches(
+            WebhookEventType::CronTaskExecuted,
+            &json!({ "task_id": 7, "status": "failed" })
+        ));
+
+        let action = CronTaskAction::SendMessageToJob {
+            job_id: "job".to_string(),
+            message: JobMessage {
+                job_id: "job".to_string(),
+                content: "Summarize {{event.path}} ({{ event.change }})".to_string(),
+                fs_files_paths: vec![],
+                job_filenames: vec![],
+                parent: None,
+                sheet_job_data: None,
+                callback: None,
+                metadata: None,
+                tool_key: None,
+                tools: None,
+            },
+        };
+        let CronTaskAction::SendMessageToJob { message, .. } =
+            action.with_event_payload(&json!({ "path": "/reports/q1.pdf", "change": "added" }))
+        else {
+            unreachable!()
+        };
+        assert_eq!(message.content, "Summarize /reports/q1.pdf (added)");
+
+        let action = CronTaskAction::RunJobPipeline {
+            pipeline_id: "digest".to_string(),
+            input: json!({ "language": "fr" }),
+        };
+        let CronTaskAction::RunJobPipeline { input, .. } = action.with_event_payload(&json!({ "path": "/a.txt" }))
+        else {
+            unreachable!()
+        };
+        assert_eq!(input, json!({ "language": "fr", "event": { "path": "/a.txt" } }));
+    }
+
+    #[test]
+    fn test_cron_event_trigger_validation() {
+        let action = CronTaskAction::SendMessageToJob {
+            job_id: "job".to_string(),
+            message: JobMessage {
+                job_id: "job".to_string(),
+                content: "Answer".to_string(),
+                fs_files_paths: vec![],
+                job_filenames: vec![],
+                parent: None,
+                sheet_job_data: None,
+                callback: None,
+                metadata: None,
+                tool_key: None,
+                tools: None,
+            },
+        };
+        let trigger = |inbox_name: &str| CronEventTrigger {
+            event: CronTaskEvent::InboxMessage {
+                inbox_name: inbox_name.to_string(),
+            },
+            debounce_secs: 0,
+        };
+        assert!(trigger("job_inbox::job::false").validate(&action).is_err());
+        assert!(trigger("job_inbox::other_job::false").validate(&action).is_ok());
+    }
 }
@@ -0,0 +1,86 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+pub const INBOX_SEARCH_DEFAULT_LIMIT: u64 = 20;
+pub const INBOX_SEARCH_MAX_LIMIT: u64 = 100;
+
+/// Markers around the matched terms of a snippet
+pub const INBOX_SEARCH_HIGHLIGHT_START: &str = "<mark>";
+pub const INBOX_SEARCH_HIGHLIGHT_END: &str = "</mark>";
+
+/// Search over the content of inbox messages. Every filter that is set has to match.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct InboxMessageSearchRequest {
+    pub query: String,
+    /// Rank by similarity of the message embeddings instead of by matching terms
+    #[serde(default)]
+    pub semantic: bool,
+    pub inbox_name: Option<String>,
+    /// Agent or LLM provider of the job the message belongs to
+    pub agent_id: Option<String>,
+    /// Node name of the sender, with or without its subidentity
+    pub sender: Option<String>,
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub from: Option<DateTime<Utc>>,
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub to: Option<DateTime<Utc>>,
+    pub offset: Option<u64>,
+    pub limit: Option<u64>,
+}
+
+impl InboxMessageSearchRequest {
+    pub fn limit(&self) -> u64 {
+        self.limit
+            .unwrap_or(INBOX_SEARCH_DEFAULT_LIMIT)
+            .clamp(1, INBOX_SEARCH_MAX_LIMIT)
+    }
+
+    pub fn offset(&self) -> u64 {
+        self.offset.unwrap_or(0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct InboxMessageSearchHit {
+    pub message_hash: String,
+    pub inbox_name: String,
+    /// Set for messages of a job inbox
+    pub agent_id: Option<String>,
+    pub sender: String,
+    pub time_key: String,
+    /// Part of the content around the match, matched terms wrapped in `<mark>`
+    pub snippet: String,
+    /// Higher is better. BM25 for term matches, inverse of the embedding distance for semantic matches.
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct InboxMessageSearchResults {
+    pub hits: Vec<InboxMessageSearchHit>,
+    pub has_more: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search_request_paging_bounds() {
+        let request: InboxMessageSearchRequest = serde_json::from_str(r#"{ "query": "invoice" }"#).unwrap();
+        assert!(!request.semantic);
+        assert_eq!(request.limit(), INBOX_SEARCH_DEFAULT_LIMIT);
+        assert_eq!(request.offset(), 0);
+
+        let request = InboxMessageSearchRequest {
+            limit: Some(10_000),
+            ..request
+        };
+        assert_eq!(request.limit(), INBOX_SEARCH_MAX_LIMIT);
+        let request = InboxMessageSearchRequest {
+            limit: Some(0),
+            ..request
+        };
+        assert_eq!(request.limit(), 1);
+    }
+}
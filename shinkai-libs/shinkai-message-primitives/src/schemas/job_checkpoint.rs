@@ -0,0 +1,17 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Progress of the inference chain answering a job message, saved after every iteration and
+/// tool call so that the message can be picked up where it was left if the node restarts
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JobCheckpoint {
+    pub job_id: String,
+    /// Hash of the message being answered, a checkpoint of another message is not resumed
+    pub message_hash_id: Option<String>,
+    /// Iterations of the chain done so far
+    pub iteration: u64,
+    /// State of the chain, such as the function calls pending and the tool results received
+    pub state: Value,
+    pub updated_at: DateTime<Utc>,
+}
@@ -0,0 +1,105 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum JobLimitTarget {
+    LlmProvider,
+    Agent,
+}
+
+impl std::fmt::Display for JobLimitTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = serde_json::to_value(self).map_err(|_| std::fmt::Error)?;
+        write!(f, "{}", value.as_str().unwrap_or_default())
+    }
+}
+
+/// Caps on the job messages of an LLM provider or an agent, checked when the job queue picks the
+/// next messages to process. Messages over a cap wait in the queue.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct JobConcurrencyLimit {
+    pub target: JobLimitTarget,
+    /// Id of the LLM provider or of the agent
+    pub target_id: String,
+    /// Messages processed at the same time
+    #[serde(default)]
+    pub max_concurrent: Option<u32>,
+    /// Messages started per minute. Only for LLM providers.
+    #[serde(default)]
+    pub requests_per_minute: Option<u32>,
+    /// Estimated prompt tokens of the messages started per minute. Only for LLM providers.
+    #[serde(default)]
+    pub tokens_per_minute: Option<u64>,
+}
+
+impl JobConcurrencyLimit {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.target_id.trim().is_empty() {
+            return Err("target_id is required".to_string());
+        }
+        if self.max_concurrent == Some(0) || self.requests_per_minute == Some(0) || self.tokens_per_minute == Some(0) {
+            return Err("Limits must be greater than 0".to_string());
+        }
+        if self.target == JobLimitTarget::Agent
+            && (self.requests_per_minute.is_some() || self.tokens_per_minute.is_some())
+        {
+            return Err("Requests and tokens per minute can only be limited for LLM providers".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Load of an LLM provider or an agent that has a limit or messages in progress
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct JobLimitTargetStatus {
+    pub target: JobLimitTarget,
+    pub target_id: String,
+    pub running: usize,
+    /// Messages waiting in the queue
+    pub queued: usize,
+    pub requests_last_minute: u32,
+    pub tokens_last_minute: u64,
+    pub limit: Option<JobConcurrencyLimit>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct JobQueueStatus {
+    /// Normal messages processed at the same time across every provider
+    pub max_parallel_jobs: usize,
+    pub running: usize,
+    /// Messages in the normal queue, including the ones in progress
+    pub queued_normal: usize,
+    /// Messages in the immediate queue, including the ones in progress
+    pub queued_immediate: usize,
+    pub targets: Vec<JobLimitTargetStatus>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_job_concurrency_limit() {
+        let limit = JobConcurrencyLimit {
+            target: JobLimitTarget::LlmProvider,
+            target_id: "ollama".to_string(),
+            max_concurrent: Some(2),
+            requests_per_minute: Some(30),
+            tokens_per_minute: None,
+        };
+        assert!(limit.validate().is_ok());
+
+        let agent_rate_limit = JobConcurrencyLimit {
+            target: JobLimitTarget::Agent,
+            ..limit.clone()
+        };
+        assert!(agent_rate_limit.validate().is_err());
+
+        let zero = JobConcurrencyLimit {
+            max_concurrent: Some(0),
+            ..limit
+        };
+        assert!(zero.validate().is_err());
+    }
+}
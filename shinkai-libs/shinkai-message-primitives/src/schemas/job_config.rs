@@ -14,6 +14,15 @@ pub struct JobConfig {
     pub stream: Option<bool>,
     pub other_model_params: Option<Value>,
     pub use_tools: Option<bool>,
+    /// Messages of jobs with a higher priority are processed first. 0 if not set.
+    pub priority: Option<i32>,
+    /// Budgets of a single message. When one is reached the inference chain stops and answers with
+    /// what it has so far.
+    pub max_iterations: Option<u64>,
+    pub max_duration_secs: Option<u64>,
+    /// Estimated tokens of all the prompts and responses of the inference chain
+    pub max_total_tokens: Option<u64>,
+    pub max_tool_calls: Option<u64>,
     // TODO: add ctx_...
 }
 
@@ -31,6 +40,11 @@ impl JobConfig {
             top_p: self.top_p.or(other.top_p),
             stream: self.stream.or(other.stream),
             use_tools: self.use_tools.or(other.use_tools),
+            priority: self.priority.or(other.priority),
+            max_iterations: self.max_iterations.or(other.max_iterations),
+            max_duration_secs: self.max_duration_secs.or(other.max_duration_secs),
+            max_total_tokens: self.max_total_tokens.or(other.max_total_tokens),
+            max_tool_calls: self.max_tool_calls.or(other.max_tool_calls),
             other_model_params: self
                 .other_model_params
                 .clone()
@@ -51,6 +65,11 @@ impl JobConfig {
             stream: None,
             other_model_params: None,
             use_tools: None,
+            priority: None,
+            max_iterations: None,
+            max_duration_secs: None,
+            max_total_tokens: None,
+            max_tool_calls: None,
         }
     }
 }
@@ -87,5 +106,26 @@ mod tests {
         assert_eq!(job_config.stream, Some(true));
         assert_eq!(job_config.other_model_params, None);
         assert_eq!(job_config.use_tools, Some(false));
+        assert_eq!(job_config.max_iterations, None);
+    }
+
+    #[test]
+    fn test_merge_job_config_budgets() {
+        let job_config = JobConfig {
+            max_iterations: Some(3),
+            ..JobConfig::empty()
+        };
+        let agent_config = JobConfig {
+            max_iterations: Some(20),
+            max_duration_secs: Some(300),
+            max_tool_calls: Some(5),
+            ..JobConfig::empty()
+        };
+
+        let merged = job_config.merge(&agent_config);
+        assert_eq!(merged.max_iterations, Some(3));
+        assert_eq!(merged.max_duration_secs, Some(300));
+        assert_eq!(merged.max_total_tokens, None);
+        assert_eq!(merged.max_tool_calls, Some(5));
     }
 }
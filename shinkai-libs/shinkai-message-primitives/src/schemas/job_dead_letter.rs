@@ -0,0 +1,26 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::shinkai_message::shinkai_message_schemas::JobMessage;
+
+use super::shinkai_name::ShinkaiName;
+
+/// Job message whose provider kept failing after its automatic retries. It stays here until it
+/// is requeued or discarded.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct JobDeadLetter {
+    pub id: String,
+    pub job_id: String,
+    pub job_message: JobMessage,
+    /// Profile the message was sent from
+    #[schema(value_type = String)]
+    pub profile: ShinkaiName,
+    pub message_hash_id: Option<String>,
+    /// Attempts made, including the first one
+    pub attempts: u32,
+    /// Error of the last attempt
+    pub error: String,
+    #[schema(value_type = String, format = DateTime)]
+    pub created_at: DateTime<Utc>,
+}
@@ -0,0 +1,305 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use utoipa::ToSchema;
+
+use super::webhook_trigger::render_payload_template;
+
+/// One job of a pipeline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct JobPipelineStep {
+    /// Referenced by later steps as `{{steps.<id>.output}}`
+    pub id: String,
+    /// Agent or LLM provider that runs the step
+    pub agent_id: String,
+    /// Prompt of the step. `{{input.a.b}}` is replaced by a field of the run input and
+    /// `{{steps.<id>.output}}` by the answer of an earlier step.
+    pub prompt: String,
+    /// Steps that have to complete first. The previous step if omitted, none if empty.
+    #[serde(default)]
+    pub depends_on: Option<Vec<String>>,
+    /// Tools the agent is restricted to, all of its tools if omitted
+    #[serde(default)]
+    pub tools: Option<Vec<String>>,
+}
+
+/// Jobs that run one after the other or, with `depends_on`, as a DAG, each one able to use the
+/// answers of the steps it depends on
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct JobPipeline {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub steps: Vec<JobPipelineStep>,
+    #[schema(value_type = String, format = DateTime)]
+    pub created_at: DateTime<Utc>,
+}
+
+impl JobPipeline {
+    /// Steps each step waits for
+    pub fn dependencies(&self) -> HashMap<&str, Vec<&str>> {
+        let mut previous: Option<&str> = None;
+        self.steps
+            .iter()
+            .map(|step| {
+                let dependencies = match &step.depends_on {
+                    Some(depends_on) => depends_on.iter().map(String::as_str).collect(),
+                    None => previous.into_iter().collect(),
+                };
+                previous = Some(&step.id);
+                (step.id.as_str(), dependencies)
+            })
+            .collect()
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.steps.is_empty() {
+            return Err("A pipeline needs at least one step".to_string());
+        }
+        let mut ids = HashSet::new();
+        for step in &self.steps {
+            if step.id.trim().is_empty() {
+                return Err("Every step needs an id".to_string());
+            }
+            if !ids.insert(step.id.as_str()) {
+                return Err(format!("Duplicate step id '{}'", step.id));
+            }
+            if step.agent_id.trim().is_empty() {
+                return Err(format!("Step '{}' has no agent", step.id));
+            }
+        }
+
+        let dependencies = self.dependencies();
+        for step in &self.steps {
+            if let Some(unknown) = dependencies[step.id.as_str()].iter().find(|id| !ids.contains(*id)) {
+                return Err(format!("Step '{}' depends on unknown step '{}'", step.id, unknown));
+            }
+        }
+
+        // Steps are ordered until only steps waiting on each other are left
+        let mut ordered = HashSet::new();
+        loop {
+            let ready: Vec<&str> = dependencies
+                .iter()
+                .filter(|(id, waits_for)| !ordered.contains(*id) && waits_for.iter().all(|id| ordered.contains(id)))
+                .map(|(id, _)| *id)
+                .collect();
+            if ready.is_empty() {
+                break;
+            }
+            ordered.extend(ready);
+        }
+        if ordered.len() != self.steps.len() {
+            return Err("The steps of the pipeline depend on each other in a cycle".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum JobPipelineRunStatus {
+    Running,
+    Completed,
+    Failed,
+}
+
+impl std::fmt::Display for JobPipelineRunStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = serde_json::to_value(self).map_err(|_| std::fmt::Error)?;
+        write!(f, "{}", value.as_str().unwrap_or_default())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum JobPipelineStepStatus {
+    Pending,
+    Running,
+    Completed,
+    Failed,
+}
+
+impl std::fmt::Display for JobPipelineStepStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = serde_json::to_value(self).map_err(|_| std::fmt::Error)?;
+        write!(f, "{}", value.as_str().unwrap_or_default())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct JobPipelineStepRun {
+    pub step_id: String,
+    pub status: JobPipelineStepStatus,
+    pub job_id: Option<String>,
+    /// Answer of the agent
+    pub output: Option<String>,
+    pub error: Option<String>,
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub started_at: Option<DateTime<Utc>>,
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct JobPipelineRun {
+    pub id: String,
+    /// The pipeline as it was when the run started
+    pub pipeline: JobPipeline,
+    pub input: Value,
+    pub status: JobPipelineRunStatus,
+    pub error: Option<String>,
+    /// In the order of the pipeline steps
+    pub steps: Vec<JobPipelineStepRun>,
+    #[schema(value_type = String, format = DateTime)]
+    pub created_at: DateTime<Utc>,
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+impl JobPipelineRun {
+    pub fn step_run(&self, step_id: &str) -> Option<&JobPipelineStepRun> {
+        self.steps.iter().find(|step_run| step_run.step_id == step_id)
+    }
+
+    /// Pending steps whose dependencies all completed, in the order of the pipeline
+    pub fn ready_steps(&self) -> Vec<&JobPipelineStep> {
+        let completed = |step_id: &str| {
+            self.step_run(step_id)
+                .is_some_and(|step_run| step_run.status == JobPipelineStepStatus::Completed)
+        };
+        let dependencies = self.pipeline.dependencies();
+        self.pipeline
+            .steps
+            .iter()
+            .filter(|step| {
+                self.step_run(&step.id)
+                    .is_some_and(|step_run| step_run.status == JobPipelineStepStatus::Pending)
+                    && dependencies[step.id.as_str()].iter().all(|id| completed(id))
+            })
+            .collect()
+    }
+
+    /// Prompt of a step with the run input and the answers of the completed steps filled in
+    pub fn render_prompt(&self, step: &JobPipelineStep) -> String {
+        let outputs: Map<String, Value> = self
+            .steps
+            .iter()
+            .filter_map(|step_run| {
+                let output = step_run.output.as_ref()?;
+                Some((step_run.step_id.clone(), json!({ "output": output })))
+            })
+            .collect();
+        let prompt = render_payload_template(&step.prompt, "steps", &Value::Object(outputs));
+        render_payload_template(&prompt, "input", &self.input)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct AddJobPipelineRequest {
+    pub name: String,
+    pub description: Option<String>,
+    pub steps: Vec<JobPipelineStep>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct RunJobPipelineRequest {
+    pub pipeline_id: String,
+    /// Available to the step prompts as `{{input}}`
+    #[serde(default)]
+    pub input: Value,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(id: &str, prompt: &str, depends_on: Option<Vec<&str>>) -> JobPipelineStep {
+        JobPipelineStep {
+            id: id.to_string(),
+            agent_id: "agent".to_string(),
+            prompt: prompt.to_string(),
+            depends_on: depends_on.map(|ids| ids.into_iter().map(String::from).collect()),
+            tools: None,
+        }
+    }
+
+    fn step_run(step_id: &str, status: JobPipelineStepStatus, output: Option<&str>) -> JobPipelineStepRun {
+        JobPipelineStepRun {
+            step_id: step_id.to_string(),
+            status,
+            job_id: None,
+            output: output.map(String::from),
+            error: None,
+            started_at: None,
+            finished_at: None,
+        }
+    }
+
+    #[test]
+    fn test_job_pipeline_steps() {
+        // `research` and `outline` start right away, `write` waits for both
+        let pipeline = JobPipeline {
+            id: "blog".to_string(),
+            name: "Blog post".to_string(),
+            description: None,
+            steps: vec![
+                step("research", "Research {{input.topic}}", None),
+                step("outline", "Outline a post on {{input.topic}}", Some(vec![])),
+                step(
+                    "write",
+                    "Write it from {{steps.outline.output}} using {{ steps.research.output }}",
+                    Some(vec!["research", "outline"]),
+                ),
+                step("review", "Review {{steps.write.output}}", None),
+            ],
+            created_at: Utc::now(),
+        };
+        assert!(pipeline.validate().is_ok());
+        assert_eq!(pipeline.dependencies()["review"], vec!["write"]);
+
+        let mut run = JobPipelineRun {
+            id: "run".to_string(),
+            pipeline: pipeline.clone(),
+            input: json!({ "topic": "bees" }),
+            status: JobPipelineRunStatus::Running,
+            error: None,
+            steps: pipeline
+                .steps
+                .iter()
+                .map(|step| step_run(&step.id, JobPipelineStepStatus::Pending, None))
+                .collect(),
+            created_at: Utc::now(),
+            finished_at: None,
+        };
+        let ready: Vec<&str> = run.ready_steps().iter().map(|step| step.id.as_str()).collect();
+        assert_eq!(ready, vec!["research", "outline"]);
+
+        run.steps[0] = step_run("research", JobPipelineStepStatus::Completed, Some("Bees dance"));
+        run.steps[1] = step_run("outline", JobPipelineStepStatus::Running, None);
+        assert!(run.ready_steps().is_empty());
+
+        run.steps[1] = step_run("outline", JobPipelineStepStatus::Completed, Some("1. Dance"));
+        assert_eq!(run.ready_steps()[0].id, "write");
+        assert_eq!(
+            run.render_prompt(&pipeline.steps[2]),
+            "Write it from 1. Dance using Bees dance"
+        );
+
+        let invalid = [
+            vec![],
+            vec![step("a", "", None), step("a", "", None)],
+            vec![step("a", "", Some(vec!["missing"]))],
+            vec![step("a", "", Some(vec!["b"])), step("b", "", None)],
+        ];
+        for steps in invalid {
+            let pipeline = JobPipeline {
+                steps,
+                ..pipeline.clone()
+            };
+            assert!(pipeline.validate().is_err());
+        }
+    }
+}
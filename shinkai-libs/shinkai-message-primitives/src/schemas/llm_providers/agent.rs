@@ -17,6 +17,8 @@ pub struct Agent {
     pub ui_description: String,
     pub knowledge: Vec<String>,
     pub storage_path: String,
+    /// Tools available to the agent. An entry may pin a specific tool revision using the
+    /// `source:::author:::name:::version:::revision` format.
     #[serde(
         serialize_with = "ToolRouterKey::serialize_tool_router_keys",
         deserialize_with = "ToolRouterKey::deserialize_tool_router_keys"
@@ -1,3 +1,5 @@
+pub mod api_key;
+pub mod audit_log;
 pub mod coinbase_mpc_config;
 pub mod cron_task;
 pub mod crontab;
@@ -7,15 +9,22 @@ pub mod identity;
 pub mod identity_registration;
 pub mod inbox_name;
 pub mod inbox_permission;
+pub mod inbox_search;
 pub mod indexable_version;
 pub mod invoices;
 pub mod job;
+pub mod job_checkpoint;
+pub mod job_concurrency_limit;
 pub mod job_config;
+pub mod job_dead_letter;
+pub mod job_pipeline;
 pub mod llm_message;
 pub mod llm_providers;
+pub mod node_backup;
 pub mod prompts;
 pub mod registration_code;
 pub mod retry;
+pub mod scheduled_job_message;
 pub mod sheet;
 pub mod shinkai_fs;
 pub mod shinkai_name;
@@ -31,4 +40,6 @@ pub mod subprompts;
 pub mod tool_router_key;
 pub mod wallet_complementary;
 pub mod wallet_mixed;
+pub mod webhook;
+pub mod webhook_trigger;
 pub mod ws_types;
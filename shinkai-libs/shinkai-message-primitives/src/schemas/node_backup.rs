@@ -0,0 +1,125 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Version of the archive layout, bumped whenever a restore can't read older archives as they are
+pub const NODE_BACKUP_FORMAT_VERSION: u32 = 1;
+
+/// Part of the node that can be backed up and restored on its own
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupCategory {
+    Agents,
+    LlmProviders,
+    /// Tools with their revisions, OAuth tokens and tool home folders
+    Tools,
+    Prompts,
+    /// Jobs, inboxes and their messages, and sheets
+    Jobs,
+    /// Parsed files and embeddings, plus the files themselves
+    VectorFs,
+    CronTasks,
+    /// Wallets, invoices and payment requirements
+    Wallets,
+    Preferences,
+}
+
+impl BackupCategory {
+    pub fn all() -> Vec<BackupCategory> {
+        vec![
+            BackupCategory::Agents,
+            BackupCategory::LlmProviders,
+            BackupCategory::Tools,
+            BackupCategory::Prompts,
+            BackupCategory::Jobs,
+            BackupCategory::VectorFs,
+            BackupCategory::CronTasks,
+            BackupCategory::Wallets,
+            BackupCategory::Preferences,
+        ]
+    }
+
+    /// Whether the category stores embeddings, which only make sense with the same embedding model
+    pub fn has_embeddings(&self) -> bool {
+        matches!(
+            self,
+            BackupCategory::Tools | BackupCategory::Prompts | BackupCategory::VectorFs
+        )
+    }
+}
+
+impl std::fmt::Display for BackupCategory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = serde_json::to_value(self).map_err(|_| std::fmt::Error)?;
+        write!(f, "{}", value.as_str().unwrap_or_default())
+    }
+}
+
+/// `manifest.json` at the root of every backup archive
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct BackupManifest {
+    pub format_version: u32,
+    /// Version of the node that created the backup
+    pub node_version: String,
+    #[schema(value_type = String, format = DateTime)]
+    pub created_at: DateTime<Utc>,
+    pub node_name: String,
+    pub identity_public_key: String,
+    pub embedding_model: String,
+    pub categories: Vec<BackupCategory>,
+    /// Key id of the vault the secrets are sealed with: the vault of the node, or for encrypted
+    /// archives the key derived from their passphrase. `None` when they are stored in plaintext,
+    /// which only happens for nodes without a vault.
+    pub secret_vault_key_id: Option<String>,
+}
+
+/// Options of a new backup
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct CreateBackupRequest {
+    /// Categories to include, all of them when empty
+    #[serde(default)]
+    pub categories: Vec<BackupCategory>,
+    /// Encrypts the archive. Encrypted archives seal their secrets with the passphrase so they can
+    /// be restored on another node; plain ones keep them sealed with the vault key of this node.
+    pub passphrase: Option<String>,
+}
+
+/// Options of a restore
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct RestoreBackupRequest {
+    /// Categories to import, every category in the archive when empty
+    #[serde(default)]
+    pub categories: Vec<BackupCategory>,
+    pub passphrase: Option<String>,
+}
+
+/// What a restore imported
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct BackupRestoreReport {
+    pub categories: Vec<BackupCategory>,
+    pub rows_restored: u64,
+    pub files_restored: u64,
+    /// Node name of the backup, when it differs from this node and the restored data was
+    /// rewritten to use the current name
+    pub renamed_from: Option<String>,
+    /// Wallets are loaded at startup, so the node must be restarted to use restored ones
+    pub restart_required: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backup_category_serialization() {
+        assert_eq!(
+            serde_json::to_string(&BackupCategory::LlmProviders).unwrap(),
+            "\"llm_providers\""
+        );
+        assert_eq!(BackupCategory::VectorFs.to_string(), "vector_fs");
+
+        let request: CreateBackupRequest = serde_json::from_str("{}").unwrap();
+        assert!(request.categories.is_empty());
+        assert!(request.passphrase.is_none());
+    }
+}
@@ -0,0 +1,40 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::shinkai_message::shinkai_message_schemas::JobMessage;
+
+/// Job message held back until `send_at`, when it is sent to its job like any other message.
+/// It is removed once sent or cancelled. A message that can't be sent is kept as failed, with the
+/// error, until it is cancelled.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ScheduledJobMessage {
+    pub id: String,
+    pub job_id: String,
+    pub job_message: JobMessage,
+    #[schema(value_type = String, format = DateTime)]
+    pub send_at: DateTime<Utc>,
+    #[schema(value_type = String, format = DateTime)]
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub status: ScheduledJobMessageStatus,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduledJobMessageStatus {
+    #[default]
+    Pending,
+    /// Claimed by the scheduler, which removes it once it reached its job
+    Sending,
+    Failed,
+}
+
+impl std::fmt::Display for ScheduledJobMessageStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = serde_json::to_value(self).map_err(|_| std::fmt::Error)?;
+        write!(f, "{}", value.as_str().unwrap_or_default())
+    }
+}
@@ -10,6 +10,9 @@ pub struct ToolRouterKey {
     pub author: String,
     pub name: String,
     pub version: Option<String>,
+    /// Optional pinned revision of the tool (see `shinkai_tool_revisions`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revision: Option<u64>,
 }
 
 impl TryFrom<String> for ToolRouterKey {
@@ -33,9 +36,16 @@ impl ToolRouterKey {
             author,
             name,
             version,
+            revision: None,
         }
     }
 
+    /// Returns a copy of the key pinned to a specific tool revision
+    pub fn with_revision(mut self, revision: Option<u64>) -> Self {
+        self.revision = revision;
+        self
+    }
+
     pub fn deserialize_tool_router_keys<'de, D>(deserializer: D) -> Result<Vec<Self>, D::Error>
     where
         D: serde::Deserializer<'de>,
@@ -80,7 +90,7 @@ impl ToolRouterKey {
         let strings: Vec<String> = tools
             .iter()
             .map(|k| {
-                let s = k.to_string_with_version();
+                let s = k.to_string_with_revision();
                 println!("Converted tool router key to string: {}", s);
                 s
             })
@@ -124,6 +134,17 @@ impl ToolRouterKey {
         key.replace('/', "|").to_lowercase()
     }
 
+    /// Same as `to_string_with_version` but appends the pinned revision (if any) as a
+    /// fifth segment: `source:::author:::name:::version:::revision`. The version segment is left
+    /// empty for unversioned tools: `source:::author:::name::::::revision`
+    pub fn to_string_with_revision(&self) -> String {
+        match (&self.version, self.revision) {
+            (Some(_), Some(revision)) => format!("{}:::{}", self.to_string_with_version(), revision),
+            (None, Some(revision)) => format!("{}::::::{}", self.to_string_without_version(), revision),
+            (_, None) => self.to_string_with_version(),
+        }
+    }
+
     pub fn from_string(key: &str) -> Result<Self, String> {
         let parts: Vec<&str> = key.split(":::").collect();
         match parts.len() {
@@ -139,6 +160,19 @@ impl ToolRouterKey {
                 parts[2].to_string(),
                 Some(parts[3].to_string()),
             )),
+            5 => {
+                let revision = parts[4]
+                    .parse::<u64>()
+                    .map_err(|_| "Invalid tool revision in tool router key".to_string())?;
+                let version = Some(parts[3].to_string()).filter(|version| !version.is_empty());
+                Ok(Self::new(
+                    parts[0].to_string(),
+                    parts[1].to_string(),
+                    parts[2].to_string(),
+                    version,
+                )
+                .with_revision(Some(revision)))
+            }
             _ => Err("Invalid tool router key format".to_string()),
         }
     }
@@ -162,6 +196,48 @@ impl ToolRouterKey {
             .as_ref()
             .and_then(|v| IndexableVersion::from_string(v).ok())
     }
+
+    /// Checks an installed `version` against the version of this key used as a dependency
+    /// requirement. Supports exact versions, `*`, `^1.2` (same major), `~1.2.3` (same minor) and
+    /// comma separated comparators such as `>=1.0.0, <2.0.0`. A key without version matches any.
+    pub fn version_requirement_matches(&self, version: &str) -> bool {
+        let Some(requirement) = &self.version else {
+            return true;
+        };
+        let Ok(version) = IndexableVersion::from_string(version.trim()) else {
+            return false;
+        };
+        let version = version.get_version_number();
+
+        requirement
+            .split(',')
+            .map(str::trim)
+            .filter(|comparator| !comparator.is_empty())
+            .all(|comparator| {
+                if comparator == "*" {
+                    return true;
+                }
+                let (operator, required) = [">=", "<=", ">", "<", "=", "^", "~"]
+                    .iter()
+                    .find_map(|op| comparator.strip_prefix(op).map(|rest| (*op, rest.trim())))
+                    .unwrap_or(("=", comparator));
+                let Ok(required) = IndexableVersion::from_string(required) else {
+                    return false;
+                };
+                let required = required.get_version_number();
+                let (major, minor) = (required / 1_000_000, (required % 1_000_000) / 1_000);
+
+                match operator {
+                    ">=" => version >= required,
+                    "<=" => version <= required,
+                    ">" => version > required,
+                    "<" => version < required,
+                    "^" => version >= required && version < (major + 1) * 1_000_000,
+                    "~" => version >= required && version < major * 1_000_000 + (minor + 1) * 1_000,
+                    _ => version == required,
+                }
+            })
+    }
 }
 
 #[cfg(test)]
@@ -226,6 +302,64 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_tool_router_key_from_string_with_revision() {
+        let key_str = "local:::__official_shinkai:::concat_strings:::1.0:::3";
+        let key = ToolRouterKey::from_string(key_str).unwrap();
+        assert_eq!(key.version, Some("1.0".to_string()));
+        assert_eq!(key.revision, Some(3));
+        assert_eq!(key.to_string_with_version(), "local:::__official_shinkai:::concat_strings:::1.0");
+        assert_eq!(key.to_string_with_revision(), key_str);
+
+        assert!(ToolRouterKey::from_string("local:::__official_shinkai:::concat_strings:::1.0:::latest").is_err());
+    }
+
+    #[test]
+    fn test_tool_router_key_revision_without_version() {
+        let key = ToolRouterKey::new(
+            "local".to_string(),
+            "@@official.shinkai".to_string(),
+            "concat_strings".to_string(),
+            None,
+        )
+        .with_revision(Some(2));
+        let key_str = key.to_string_with_revision();
+        assert_eq!(key_str, "local:::__official_shinkai:::concat_strings::::::2");
+
+        let parsed = ToolRouterKey::from_string(&key_str).unwrap();
+        assert_eq!(parsed.version, None);
+        assert_eq!(parsed.revision, Some(2));
+        assert_eq!(parsed.to_string_with_revision(), key_str);
+    }
+
+    #[test]
+    fn test_tool_router_key_version_requirement_matches() {
+        let key = |version: Option<&str>| {
+            ToolRouterKey::new(
+                "local".to_string(),
+                "@@official.shinkai".to_string(),
+                "concat_strings".to_string(),
+                version.map(|v| v.to_string()),
+            )
+        };
+
+        assert!(key(None).version_requirement_matches("3.1.0"));
+        assert!(key(Some("*")).version_requirement_matches("0.0.1"));
+        assert!(key(Some("1.0")).version_requirement_matches("1.0.0"));
+        assert!(!key(Some("1.0")).version_requirement_matches("1.0.1"));
+        assert!(key(Some(">=1.2.0, <2.0.0")).version_requirement_matches("1.9.3"));
+        assert!(!key(Some(">=1.2.0, <2.0.0")).version_requirement_matches("2.0.0"));
+        assert!(key(Some("^1.2")).version_requirement_matches("1.4.0"));
+        assert!(!key(Some("^1.2")).version_requirement_matches("1.1.9"));
+        assert!(key(Some("~1.2.3")).version_requirement_matches("1.2.9"));
+        assert!(!key(Some("~1.2.3")).version_requirement_matches("1.3.0"));
+        assert!(!key(Some(">=1.0.0")).version_requirement_matches("latest"));
+
+        // Requirements survive the string round trip used in tool metadata
+        let parsed = ToolRouterKey::from_string(&key(Some(">=1.2.0")).to_string_with_version()).unwrap();
+        assert!(parsed.version_requirement_matches("1.2.0"));
+    }
+
     #[test]
     fn test_tool_router_key_from_string_invalid_format() {
         let key_str = "invalid_key_format";
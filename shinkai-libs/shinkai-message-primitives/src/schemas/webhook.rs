@@ -0,0 +1,172 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::ToSchema;
+
+/// Deliveries are given up after this many failed attempts
+pub const WEBHOOK_MAX_ATTEMPTS: u32 = 8;
+
+/// Header carrying the signature of a delivery: `t=<unix timestamp>,v1=<hex HMAC-SHA256>` where the
+/// HMAC is computed with the webhook secret over `<timestamp>.<body>`
+pub const WEBHOOK_SIGNATURE_HEADER: &str = "X-Shinkai-Signature";
+
+/// Node event that can be sent to a webhook
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEventType {
+    JobCompleted,
+    JobFailed,
+    /// A job was stopped while processing a message
+    JobCancelled,
+    InboxMessage,
+    CronTaskExecuted,
+    ToolFailed,
+    InvoiceUpdated,
+    /// A file of the vector FS was added or changed
+    FileChanged,
+}
+
+impl std::fmt::Display for WebhookEventType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = serde_json::to_value(self).map_err(|_| std::fmt::Error)?;
+        write!(f, "{}", value.as_str().unwrap_or_default())
+    }
+}
+
+/// Endpoint that receives node events
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Webhook {
+    pub id: String,
+    pub url: String,
+    /// Events sent to the endpoint, every event when empty
+    pub events: Vec<WebhookEventType>,
+    /// Key of the delivery signatures, redacted when listed
+    pub secret: String,
+    pub description: Option<String>,
+    pub enabled: bool,
+    #[schema(value_type = String, format = DateTime)]
+    pub created_at: DateTime<Utc>,
+}
+
+impl Webhook {
+    pub fn accepts(&self, event_type: WebhookEventType) -> bool {
+        self.enabled && (self.events.is_empty() || self.events.contains(&event_type))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookDeliveryStatus {
+    /// Waiting for its first attempt or a retry
+    Pending,
+    Delivered,
+    /// Every attempt failed, only sent again on redelivery
+    Failed,
+}
+
+impl std::fmt::Display for WebhookDeliveryStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = serde_json::to_value(self).map_err(|_| std::fmt::Error)?;
+        write!(f, "{}", value.as_str().unwrap_or_default())
+    }
+}
+
+/// One event queued for one webhook in the outbox
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct WebhookDelivery {
+    pub id: u64,
+    pub webhook_id: String,
+    pub event_type: WebhookEventType,
+    pub payload: Value,
+    pub status: WebhookDeliveryStatus,
+    pub attempts: u32,
+    #[schema(value_type = String, format = DateTime)]
+    pub next_attempt_at: DateTime<Utc>,
+    /// HTTP status of the last attempt, if the endpoint answered
+    pub last_status_code: Option<u16>,
+    pub last_error: Option<String>,
+    #[schema(value_type = String, format = DateTime)]
+    pub created_at: DateTime<Utc>,
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+impl WebhookDelivery {
+    /// Body sent to the endpoint
+    pub fn body(&self) -> Value {
+        serde_json::json!({
+            "id": self.id,
+            "type": self.event_type,
+            "created_at": self.created_at.to_rfc3339(),
+            "data": self.payload,
+        })
+    }
+
+    /// Wait before the next attempt once `attempts` attempts failed: 30 seconds doubling up to an hour
+    pub fn retry_delay(attempts: u32) -> Duration {
+        let seconds = 30u64.saturating_mul(1 << attempts.saturating_sub(1).min(16));
+        Duration::from_secs(seconds.min(60 * 60))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct AddWebhookRequest {
+    pub url: String,
+    #[serde(default)]
+    pub events: Vec<WebhookEventType>,
+    /// Generated when not given. Only returned by this request.
+    pub secret: Option<String>,
+    pub description: Option<String>,
+}
+
+/// Changes to a webhook, fields left out are kept
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct UpdateWebhookRequest {
+    pub id: String,
+    pub url: Option<String>,
+    pub events: Option<Vec<WebhookEventType>>,
+    pub secret: Option<String>,
+    pub description: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+/// Filters for listing deliveries, newest first
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct WebhookDeliveryFilter {
+    pub webhook_id: Option<String>,
+    pub status: Option<WebhookDeliveryStatus>,
+    pub limit: Option<u64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_webhook_event_filter_and_retry_delay() {
+        let mut webhook = Webhook {
+            id: "hook".to_string(),
+            url: "http://localhost:8080/hook".to_string(),
+            events: vec![],
+            secret: "secret".to_string(),
+            description: None,
+            enabled: true,
+            created_at: Utc::now(),
+        };
+        assert!(webhook.accepts(WebhookEventType::InvoiceUpdated));
+
+        webhook.events = vec![WebhookEventType::JobCompleted];
+        assert!(webhook.accepts(WebhookEventType::JobCompleted));
+        assert!(!webhook.accepts(WebhookEventType::JobFailed));
+
+        webhook.enabled = false;
+        assert!(!webhook.accepts(WebhookEventType::JobCompleted));
+
+        assert_eq!(WebhookEventType::CronTaskExecuted.to_string(), "cron_task_executed");
+        assert_eq!(WebhookDelivery::retry_delay(1), Duration::from_secs(30));
+        assert_eq!(WebhookDelivery::retry_delay(3), Duration::from_secs(120));
+        assert_eq!(WebhookDelivery::retry_delay(20), Duration::from_secs(3600));
+    }
+}
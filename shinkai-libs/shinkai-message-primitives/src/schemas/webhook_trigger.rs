@@ -0,0 +1,159 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::ToSchema;
+
+/// Requests a trigger accepts per minute when none is set
+pub const WEBHOOK_TRIGGER_DEFAULT_RATE_LIMIT: u32 = 60;
+
+/// Whether an invocation answers right away with the job id or waits for the agent
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookTriggerMode {
+    #[default]
+    Async,
+    Sync,
+}
+
+/// Inbound URL that runs an agent on the JSON posted to it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct WebhookTrigger {
+    pub id: String,
+    pub name: String,
+    /// Agent or LLM provider that runs the job
+    pub agent_id: String,
+    /// Prompt of the job. `{{payload}}` is replaced by the posted JSON and `{{payload.a.b}}` by
+    /// one of its fields.
+    pub prompt_template: String,
+    /// Authenticates callers, redacted when listed
+    pub secret: String,
+    pub rate_limit_per_minute: u32,
+    pub mode: WebhookTriggerMode,
+    pub enabled: bool,
+    #[schema(value_type = String, format = DateTime)]
+    pub created_at: DateTime<Utc>,
+}
+
+impl WebhookTrigger {
+    /// The prompt for `payload`. Placeholders of missing fields are left empty.
+    pub fn render(&self, payload: &Value) -> String {
+        render_payload_template(&self.prompt_template, "payload", payload)
+    }
+}
+
+/// Replaces `{{<root>}}` in `template` by `payload` and `{{<root>.a.b}}` by one of its fields.
+/// Placeholders of missing fields are left empty, other placeholders are kept as written.
+pub fn render_payload_template(template: &str, root: &str, payload: &Value) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(length) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let placeholder = rest[start + 2..start + 2 + length].trim();
+        rendered.push_str(&rest[..start]);
+        match placeholder.strip_prefix(root) {
+            Some(path) if path.is_empty() || path.starts_with('.') => {
+                if let Some(value) = lookup(payload, path.trim_start_matches('.')) {
+                    rendered.push_str(&value_to_text(value));
+                }
+            }
+            // Not a payload placeholder, kept as written
+            _ => rendered.push_str(&rest[start..start + 4 + length]),
+        }
+        rest = &rest[start + 4 + length..];
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+fn lookup<'a>(payload: &'a Value, path: &str) -> Option<&'a Value> {
+    if path.is_empty() {
+        return Some(payload);
+    }
+    path.split('.').try_fold(payload, |value, key| match value {
+        Value::Object(map) => map.get(key),
+        Value::Array(items) => key.parse::<usize>().ok().and_then(|index| items.get(index)),
+        _ => None,
+    })
+}
+
+fn value_to_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Null => String::new(),
+        Value::Object(_) | Value::Array(_) => serde_json::to_string_pretty(value).unwrap_or_default(),
+        other => other.to_string(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct AddWebhookTriggerRequest {
+    pub name: String,
+    pub agent_id: String,
+    pub prompt_template: String,
+    /// Generated when not given. Only returned by this request.
+    pub secret: Option<String>,
+    pub rate_limit_per_minute: Option<u32>,
+    #[serde(default)]
+    pub mode: WebhookTriggerMode,
+}
+
+/// Changes to a trigger, fields left out are kept
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct UpdateWebhookTriggerRequest {
+    pub id: String,
+    pub name: Option<String>,
+    pub agent_id: Option<String>,
+    pub prompt_template: Option<String>,
+    pub secret: Option<String>,
+    pub rate_limit_per_minute: Option<u32>,
+    pub mode: Option<WebhookTriggerMode>,
+    pub enabled: Option<bool>,
+}
+
+/// Result of an invocation. `answer` is only set when the caller waited for the agent.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct WebhookTriggerRun {
+    pub job_id: String,
+    pub answer: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_render_prompt_template() {
+        let trigger = WebhookTrigger {
+            id: "ci".to_string(),
+            name: "CI failures".to_string(),
+            agent_id: "triage_agent".to_string(),
+            prompt_template: "Build {{ payload.build.id }} of {{payload.repo}} failed at {{payload.steps.1}} \
+                              ({{payload.missing}}). {{other}}\n{{payload.build}}"
+                .to_string(),
+            secret: "secret".to_string(),
+            rate_limit_per_minute: WEBHOOK_TRIGGER_DEFAULT_RATE_LIMIT,
+            mode: WebhookTriggerMode::default(),
+            enabled: true,
+            created_at: Utc::now(),
+        };
+        let payload = json!({
+            "repo": "shinkai-node",
+            "build": { "id": 42 },
+            "steps": ["checkout", "test"],
+        });
+
+        assert_eq!(
+            trigger.render(&payload),
+            "Build 42 of shinkai-node failed at test (). {{other}}\n{\n  \"id\": 42\n}"
+        );
+
+        let trigger = WebhookTrigger {
+            prompt_template: "Summarize: {{payload}} {{unclosed".to_string(),
+            ..trigger
+        };
+        assert_eq!(trigger.render(&json!("text")), "Summarize: text {{unclosed");
+    }
+}
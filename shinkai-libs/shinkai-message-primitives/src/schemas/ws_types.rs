@@ -7,7 +7,9 @@ use tokio::sync::Mutex;
 
 use crate::shinkai_message::shinkai_message_schemas::WSTopic;
 
-use super::{sheet::CellUpdateInfo, shinkai_tool_offering::UsageType};
+use super::{
+    job_pipeline::{JobPipelineRunStatus, JobPipelineStepStatus}, sheet::CellUpdateInfo, shinkai_tool_offering::UsageType
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum MessageType {
@@ -110,10 +112,22 @@ pub enum ToolStatusType {
     RequiresAction,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobPipelineStepMetadata {
+    pub run_id: String,
+    pub pipeline_id: String,
+    pub step_id: String,
+    pub status: JobPipelineStepStatus,
+    pub job_id: Option<String>,
+    pub error: Option<String>,
+    pub run_status: JobPipelineRunStatus,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum WidgetMetadata {
     PaymentRequest(PaymentMetadata),
     ToolRequest(ToolMetadata),
+    JobPipelineStep(JobPipelineStepMetadata),
 }
 
 pub type MessageQueue = Arc<Mutex<VecDeque<(WSTopic, String, String, WSMessageType, bool)>>>;
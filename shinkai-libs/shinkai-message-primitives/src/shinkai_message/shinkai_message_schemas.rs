@@ -314,6 +314,7 @@ pub enum CallbackAction {
     ToolPlayground(ToolPlaygroundAction),
     // ImplementationCheck: (DynamicToolType, available_tools)
     ImplementationCheck(DynamicToolType, Vec<ToolRouterKey>),
+    JobPipelineStep(JobPipelineStepAction),
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, ToSchema)]
@@ -342,6 +343,9 @@ pub struct MessageMetadata {
     pub tps: Option<String>,
     pub duration_ms: Option<String>,
     pub function_calls: Option<Vec<FunctionCallMetadata>>,
+    /// The job was stopped before the answer was complete
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub cancelled: bool,
 }
 
 // New struct for function call metadata
@@ -377,6 +381,12 @@ pub struct ToolPlaygroundAction {
     pub code: String,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, ToSchema)]
+pub struct JobPipelineStepAction {
+    pub run_id: String,
+    pub step_id: String,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, ToSchema)]
 pub struct SheetJobAction {
     pub sheet_id: String,
@@ -906,6 +916,7 @@ mod tests {
                     tool_router_key: Some("router_key".to_string()),
                     response: Some("function response".to_string()),
                 }]),
+                cancelled: false,
             }),
             tool_key: Some("specific_tool".to_string()),
             fs_files_paths: vec![],
@@ -12,4 +12,5 @@ pub mod shinkai_time;
 pub mod search_mode;
 pub mod shinkai_path;
 pub mod shinkai_message_to_prompt;
-pub mod test_utils;
\ No newline at end of file
+pub mod test_utils;
+pub mod secret_vault;
\ No newline at end of file
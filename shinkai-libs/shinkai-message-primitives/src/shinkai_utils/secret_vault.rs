@@ -0,0 +1,185 @@
+use aes_gcm::aead::{generic_array::GenericArray, Aead, Payload};
+use aes_gcm::{Aes256Gcm, KeyInit};
+use argon2::Argon2;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use rand::RngCore;
+use thiserror::Error;
+
+/// Prefix of every value encrypted by the vault: `vault:v1:<key_id>:<base64 nonce + ciphertext>`
+pub const SEALED_SECRET_PREFIX: &str = "vault:v1:";
+
+/// Placeholder that replaces secrets in API responses and exports
+pub const REDACTED_SECRET: &str = "********";
+
+const NONCE_LEN: usize = 12;
+
+#[derive(Debug, Error)]
+pub enum SecretVaultError {
+    #[error("Failed to derive the vault key: {0}")]
+    KeyDerivation(String),
+    #[error("Secret was sealed with key {0}, which is not the current vault key")]
+    UnknownKey(String),
+    #[error("Malformed sealed secret")]
+    Malformed,
+    #[error("Failed to encrypt secret")]
+    Encryption,
+    #[error("Failed to decrypt secret")]
+    Decryption,
+}
+
+/// Encrypts secrets at rest with AES-256-GCM. The key either comes from the OS keyring or is
+/// derived from the node passphrase with Argon2.
+pub struct SecretVault {
+    key_id: String,
+    cipher: Aes256Gcm,
+}
+
+impl std::fmt::Debug for SecretVault {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SecretVault").field("key_id", &self.key_id).finish()
+    }
+}
+
+impl SecretVault {
+    pub fn new(key: [u8; 32]) -> Self {
+        let key_id = hex::encode(&blake3::derive_key("shinkai secret vault key id", &key)[..8]);
+        Self {
+            key_id,
+            cipher: Aes256Gcm::new(GenericArray::from_slice(&key)),
+        }
+    }
+
+    pub fn from_passphrase(passphrase: &str, salt: &[u8]) -> Result<Self, SecretVaultError> {
+        let mut key = [0u8; 32];
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+            .map_err(|e| SecretVaultError::KeyDerivation(e.to_string()))?;
+        Ok(Self::new(key))
+    }
+
+    pub fn random_key() -> [u8; 32] {
+        let mut key = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut key);
+        key
+    }
+
+    pub fn random_salt() -> [u8; 16] {
+        let mut salt = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut salt);
+        salt
+    }
+
+    /// Short fingerprint of the key, stored next to every sealed value and used to check the
+    /// passphrase on unlock
+    pub fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    /// Encrypts `plaintext`. Values that are already sealed are returned unchanged.
+    pub fn seal(&self, plaintext: &str) -> Result<String, SecretVaultError> {
+        if is_sealed_secret(plaintext) {
+            return Ok(plaintext.to_string());
+        }
+
+        let payload = self.encrypt_bytes(plaintext.as_bytes())?;
+        Ok(format!("{}{}:{}", SEALED_SECRET_PREFIX, self.key_id, STANDARD.encode(payload)))
+    }
+
+    /// Decrypts a sealed value. Values that aren't sealed are returned unchanged.
+    pub fn reveal(&self, value: &str) -> Result<String, SecretVaultError> {
+        let Some(sealed) = value.strip_prefix(SEALED_SECRET_PREFIX) else {
+            return Ok(value.to_string());
+        };
+        let (key_id, payload) = sealed.split_once(':').ok_or(SecretVaultError::Malformed)?;
+        if key_id != self.key_id {
+            return Err(SecretVaultError::UnknownKey(key_id.to_string()));
+        }
+
+        let payload = STANDARD.decode(payload).map_err(|_| SecretVaultError::Malformed)?;
+        let plaintext = self.decrypt_bytes(&payload)?;
+        String::from_utf8(plaintext).map_err(|_| SecretVaultError::Decryption)
+    }
+
+    /// Encrypts binary data, e.g. a backup archive. Returns the nonce followed by the ciphertext.
+    pub fn encrypt_bytes(&self, plaintext: &[u8]) -> Result<Vec<u8>, SecretVaultError> {
+        self.encrypt_bytes_with_aad(plaintext, &[])
+    }
+
+    /// Same as `encrypt_bytes`, with `aad` authenticated along the ciphertext. Decrypting fails
+    /// unless the same `aad` is given.
+    pub fn encrypt_bytes_with_aad(&self, plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>, SecretVaultError> {
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let ciphertext = self
+            .cipher
+            .encrypt(GenericArray::from_slice(&nonce), Payload { msg: plaintext, aad })
+            .map_err(|_| SecretVaultError::Encryption)?;
+
+        let mut payload = nonce.to_vec();
+        payload.extend_from_slice(&ciphertext);
+        Ok(payload)
+    }
+
+    pub fn decrypt_bytes(&self, payload: &[u8]) -> Result<Vec<u8>, SecretVaultError> {
+        self.decrypt_bytes_with_aad(payload, &[])
+    }
+
+    pub fn decrypt_bytes_with_aad(&self, payload: &[u8], aad: &[u8]) -> Result<Vec<u8>, SecretVaultError> {
+        if payload.len() < NONCE_LEN {
+            return Err(SecretVaultError::Malformed);
+        }
+        let (nonce, ciphertext) = payload.split_at(NONCE_LEN);
+        self.cipher
+            .decrypt(GenericArray::from_slice(nonce), Payload { msg: ciphertext, aad })
+            .map_err(|_| SecretVaultError::Decryption)
+    }
+}
+
+pub fn is_sealed_secret(value: &str) -> bool {
+    value.starts_with(SEALED_SECRET_PREFIX)
+}
+
+pub fn is_redacted_secret(value: &str) -> bool {
+    value == REDACTED_SECRET
+}
+
+/// `REDACTED_SECRET` in place of a set secret, so clients can still tell whether one is configured
+pub fn redact_secret(secret: &Option<String>) -> Option<String> {
+    match secret {
+        Some(value) if !value.is_empty() => Some(REDACTED_SECRET.to_string()),
+        other => other.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_and_reveal() {
+        let vault = SecretVault::from_passphrase("correct horse battery staple", b"0123456789abcdef").unwrap();
+        let sealed = vault.seal("sk-123").unwrap();
+        assert!(is_sealed_secret(&sealed));
+        assert!(!sealed.contains("sk-123"));
+        assert_eq!(vault.seal(&sealed).unwrap(), sealed);
+        assert_eq!(vault.reveal(&sealed).unwrap(), "sk-123");
+        assert_eq!(vault.reveal("not sealed").unwrap(), "not sealed");
+
+        // Same passphrase and salt give the same key
+        let same = SecretVault::from_passphrase("correct horse battery staple", b"0123456789abcdef").unwrap();
+        assert_eq!(same.key_id(), vault.key_id());
+        assert_eq!(same.reveal(&sealed).unwrap(), "sk-123");
+
+        let other = SecretVault::new(SecretVault::random_key());
+        assert!(matches!(other.reveal(&sealed), Err(SecretVaultError::UnknownKey(_))));
+
+        let (prefix, payload) = sealed.rsplit_once(':').unwrap();
+        let mut payload = STANDARD.decode(payload).unwrap();
+        *payload.last_mut().unwrap() ^= 1;
+        let tampered = format!("{}:{}", prefix, STANDARD.encode(payload));
+        assert!(matches!(vault.reveal(&tampered), Err(SecretVaultError::Decryption)));
+
+        assert_eq!(redact_secret(&Some("sk-123".to_string())).as_deref(), Some(REDACTED_SECRET));
+        assert_eq!(redact_secret(&None), None);
+    }
+}
@@ -192,28 +192,30 @@ pub fn shinkai_log(option: ShinkaiLogOption, level: ShinkaiLogLevel, message: &s
     }
 }
 
+/// Most permissive level found in `RUST_LOG`, used as the tracing filter
+pub fn tracing_filter_level() -> &'static str {
+    let log_var = std::env::var("RUST_LOG").unwrap_or_else(|_| "info".to_string());
+
+    if log_var.contains("trace") {
+        "trace"
+    } else if log_var.contains("debug") {
+        "debug"
+    } else if log_var.contains("info") {
+        "info"
+    } else if log_var.contains("warn") {
+        "warn"
+    } else if log_var.contains("error") {
+        "error"
+    } else {
+        "info" // Default to info if none specified or recognized
+    }
+}
+
 pub fn init_default_tracing() {
     #[cfg(not(target_arch = "wasm32"))]
     {
         INIT.call_once(|| {
-            let log_var = std::env::var("RUST_LOG").unwrap_or_else(|_| "info".to_string());
-
-            // Determine the most permissive log level specified
-            let filter_level = if log_var.contains("trace") {
-                "trace"
-            } else if log_var.contains("debug") {
-                "debug"
-            } else if log_var.contains("info") {
-                "info"
-            } else if log_var.contains("warn") {
-                "warn"
-            } else if log_var.contains("error") {
-                "error"
-            } else {
-                "info" // Default to info if none specified or recognized
-            };
-
-            let filter = tracing_subscriber::EnvFilter::new(filter_level);
+            let filter = tracing_subscriber::EnvFilter::new(tracing_filter_level());
 
             let subscriber = tracing_subscriber::fmt::Subscriber::builder()
                 .with_env_filter(filter)
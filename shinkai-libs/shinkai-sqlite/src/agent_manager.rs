@@ -30,7 +30,7 @@ impl SqliteManager {
 
         let knowledge = serde_json::to_string(&agent.knowledge).unwrap();
         let config = agent.config.map(|c| serde_json::to_string(&c).unwrap());
-        let tools: Vec<String> = agent.tools.iter().map(|t| t.to_string_with_version()).collect();
+        let tools: Vec<String> = agent.tools.iter().map(|t| t.to_string_with_revision()).collect();
         let tools = serde_json::to_string(&tools).unwrap();
         let scope = serde_json::to_string(&agent.scope).unwrap();
         let tools_config_override = agent.tools_config_override.map(|c| serde_json::to_string(&c).unwrap());
@@ -224,7 +224,7 @@ impl SqliteManager {
 
         let knowledge = serde_json::to_string(&updated_agent.knowledge).unwrap();
         let config = updated_agent.config.map(|c| serde_json::to_string(&c).unwrap());
-        let tools: Vec<String> = updated_agent.tools.iter().map(|t| t.to_string_with_version()).collect();
+        let tools: Vec<String> = updated_agent.tools.iter().map(|t| t.to_string_with_revision()).collect();
         let tools = serde_json::to_string(&tools).unwrap();
         let scope = serde_json::to_string(&updated_agent.scope).unwrap();
         let tools_config_override = updated_agent.tools_config_override.map(|c| serde_json::to_string(&c).unwrap());
@@ -0,0 +1,176 @@
+use crate::{SqliteManager, SqliteManagerError};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, OptionalExtension, Result};
+use shinkai_message_primitives::schemas::api_key::ApiKey;
+
+impl SqliteManager {
+    /// Hash under which an API key secret is stored and looked up
+    pub fn hash_api_key(secret: &str) -> String {
+        blake3::hash(secret.as_bytes()).to_hex().to_string()
+    }
+
+    /// Stores a new API key. Only the hash of `secret` is persisted.
+    pub fn add_api_key(&self, api_key: &ApiKey, secret: &str) -> Result<(), SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let scopes =
+            serde_json::to_string(&api_key.scopes).map_err(|e| SqliteManagerError::SerializationError(e.to_string()))?;
+        let agent_ids = api_key
+            .agent_ids
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(|e| SqliteManagerError::SerializationError(e.to_string()))?;
+
+        conn.execute(
+            "INSERT INTO shinkai_api_keys (
+                id,
+                name,
+                key_prefix,
+                key_hash,
+                scopes,
+                agent_ids,
+                expires_at,
+                created_at,
+                last_used_at,
+                revoked
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                api_key.id,
+                api_key.name,
+                api_key.key_prefix,
+                Self::hash_api_key(secret),
+                scopes,
+                agent_ids,
+                api_key.expires_at.map(|date| date.to_rfc3339()),
+                api_key.created_at.to_rfc3339(),
+                api_key.last_used_at.map(|date| date.to_rfc3339()),
+                api_key.revoked,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Finds the key matching the presented secret, revoked and expired keys included
+    pub fn get_api_key_by_secret(&self, secret: &str) -> Result<Option<ApiKey>, SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let api_key = conn
+            .query_row(
+                "SELECT id, name, key_prefix, scopes, agent_ids, expires_at, created_at, last_used_at, revoked
+                 FROM shinkai_api_keys WHERE key_hash = ?1",
+                params![Self::hash_api_key(secret)],
+                Self::row_to_api_key,
+            )
+            .optional()?;
+        Ok(api_key)
+    }
+
+    pub fn list_api_keys(&self) -> Result<Vec<ApiKey>, SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, name, key_prefix, scopes, agent_ids, expires_at, created_at, last_used_at, revoked
+             FROM shinkai_api_keys ORDER BY created_at DESC",
+        )?;
+        let api_keys = stmt
+            .query_map([], Self::row_to_api_key)?
+            .collect::<Result<Vec<ApiKey>, _>>()?;
+        Ok(api_keys)
+    }
+
+    /// Revokes a key. Returns false if no key has that id.
+    pub fn revoke_api_key(&self, id: &str) -> Result<bool, SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let updated = conn.execute("UPDATE shinkai_api_keys SET revoked = 1 WHERE id = ?1", params![id])?;
+        Ok(updated > 0)
+    }
+
+    pub fn update_api_key_last_used(&self, id: &str) -> Result<(), SqliteManagerError> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            "UPDATE shinkai_api_keys SET last_used_at = ?1 WHERE id = ?2",
+            params![Utc::now().to_rfc3339(), id],
+        )?;
+        Ok(())
+    }
+
+    fn row_to_api_key(row: &rusqlite::Row) -> rusqlite::Result<ApiKey> {
+        fn parse_json<T: serde::de::DeserializeOwned>(index: usize, value: String) -> rusqlite::Result<T> {
+            serde_json::from_str(&value)
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, Box::new(e)))
+        }
+        let parse_date = |index: usize, value: String| {
+            DateTime::parse_from_rfc3339(&value)
+                .map(|date| date.with_timezone(&Utc))
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, Box::new(e)))
+        };
+
+        Ok(ApiKey {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            key_prefix: row.get(2)?,
+            scopes: parse_json(3, row.get(3)?)?,
+            agent_ids: row
+                .get::<_, Option<String>>(4)?
+                .map(|value| parse_json(4, value))
+                .transpose()?,
+            expires_at: row
+                .get::<_, Option<String>>(5)?
+                .map(|value| parse_date(5, value))
+                .transpose()?,
+            created_at: parse_date(6, row.get(6)?)?,
+            last_used_at: row
+                .get::<_, Option<String>>(7)?
+                .map(|value| parse_date(7, value))
+                .transpose()?,
+            revoked: row.get(8)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shinkai_embedding::model_type::{EmbeddingModelType, OllamaTextEmbeddingsInference};
+    use shinkai_message_primitives::schemas::api_key::ApiKeyScope;
+    use std::path::PathBuf;
+    use tempfile::NamedTempFile;
+
+    fn setup_test_db() -> SqliteManager {
+        let temp_file = NamedTempFile::new().unwrap();
+        let db_path = PathBuf::from(temp_file.path());
+        let api_url = String::new();
+        let model_type =
+            EmbeddingModelType::OllamaTextEmbeddingsInference(OllamaTextEmbeddingsInference::SnowflakeArcticEmbedM);
+
+        SqliteManager::new(db_path, api_url, model_type).unwrap()
+    }
+
+    #[test]
+    fn test_add_lookup_and_revoke_api_key() {
+        let db = setup_test_db();
+        let secret = "sk_shinkai_0123456789abcdef";
+        let api_key = ApiKey {
+            id: "key-1".to_string(),
+            name: "ci".to_string(),
+            key_prefix: secret[..14].to_string(),
+            scopes: vec![ApiKeyScope::JobsWrite, ApiKeyScope::ToolsExecute],
+            agent_ids: Some(vec!["my_agent".to_string()]),
+            expires_at: None,
+            created_at: Utc::now(),
+            last_used_at: None,
+            revoked: false,
+        };
+        db.add_api_key(&api_key, secret).unwrap();
+
+        assert!(db.get_api_key_by_secret("sk_shinkai_wrong").unwrap().is_none());
+        let stored = db.get_api_key_by_secret(secret).unwrap().unwrap();
+        assert_eq!(stored.scopes, api_key.scopes);
+        assert_eq!(stored.agent_ids, api_key.agent_ids);
+
+        db.update_api_key_last_used(&api_key.id).unwrap();
+        assert!(db.list_api_keys().unwrap()[0].last_used_at.is_some());
+
+        assert!(db.revoke_api_key(&api_key.id).unwrap());
+        assert!(!db.revoke_api_key("missing").unwrap());
+        assert!(db.get_api_key_by_secret(secret).unwrap().unwrap().revoked);
+    }
+}
@@ -0,0 +1,475 @@
+use crate::{SqliteManager, SqliteManagerError};
+use chrono::{DateTime, SecondsFormat, Utc};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use rusqlite::{params, OptionalExtension, Result, ToSql, Transaction, TransactionBehavior};
+use serde_json::Value;
+use shinkai_message_primitives::schemas::audit_log::{
+    AuditAction, AuditLogEntry, AuditLogFilter, AuditLogVerification, AuditOutcome, AUDIT_LOG_GENESIS_HASH,
+};
+
+const AUDIT_LOG_COLUMNS: &str =
+    "id, timestamp, actor, api_key_id, action, target, parameters, outcome, prev_hash, hash";
+
+/// Bytes signed for the head of the chain
+fn audit_log_head_message(entry_count: u64, head_hash: &str) -> Vec<u8> {
+    format!("shinkai_audit_log_head:{}:{}", entry_count, head_hash).into_bytes()
+}
+
+impl SqliteManager {
+    /// Signs the head of the chain with the node identity key from now on. A chain without a
+    /// signed head yet, e.g. one written before heads were signed, is anchored where it ends now.
+    pub fn set_audit_log_signing_key(&self, signing_key: SigningKey) -> Result<(), SqliteManagerError> {
+        let mut conn = self.get_connection()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+
+        let has_head = tx
+            .query_row("SELECT 1 FROM shinkai_audit_log_head WHERE id = 1", [], |_| Ok(()))
+            .optional()?
+            .is_some();
+        if !has_head {
+            let last: Option<(u64, String)> = tx
+                .query_row(
+                    "SELECT id, hash FROM shinkai_audit_log ORDER BY id DESC LIMIT 1",
+                    [],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .optional()?;
+            if let Some((entry_count, head_hash)) = last {
+                Self::write_audit_log_head(&tx, &signing_key, entry_count, &head_hash)?;
+            }
+        }
+        tx.commit()?;
+
+        *self
+            .audit_log_signing_key
+            .write()
+            .map_err(|_| SqliteManagerError::LockError)? = Some(signing_key);
+        Ok(())
+    }
+
+    /// Appends an entry chained to the last one. The write lock is taken up front so concurrent
+    /// appends can't link to the same previous entry.
+    pub fn append_audit_log_entry(
+        &self,
+        actor: &str,
+        api_key_id: Option<&str>,
+        action: AuditAction,
+        target: Option<&str>,
+        parameters: Value,
+        outcome: AuditOutcome,
+    ) -> Result<AuditLogEntry, SqliteManagerError> {
+        let mut conn = self.get_connection()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+
+        let last: Option<(u64, String)> = tx
+            .query_row(
+                "SELECT id, hash FROM shinkai_audit_log ORDER BY id DESC LIMIT 1",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        let (last_id, prev_hash) = last.unwrap_or((0, AUDIT_LOG_GENESIS_HASH.to_string()));
+
+        let mut entry = AuditLogEntry {
+            id: last_id + 1,
+            timestamp: Utc::now(),
+            actor: actor.to_string(),
+            api_key_id: api_key_id.map(str::to_string),
+            action,
+            target: target.map(str::to_string),
+            parameters,
+            outcome,
+            prev_hash,
+            hash: String::new(),
+        };
+        entry.hash = entry.compute_hash();
+
+        tx.execute(
+            "INSERT INTO shinkai_audit_log (
+                id,
+                timestamp,
+                actor,
+                api_key_id,
+                action,
+                target,
+                parameters,
+                outcome,
+                success,
+                prev_hash,
+                hash
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                entry.id,
+                entry.timestamp.to_rfc3339_opts(SecondsFormat::Nanos, true),
+                entry.actor,
+                entry.api_key_id,
+                entry.action.to_string(),
+                entry.target,
+                serde_json::to_string(&entry.parameters)?,
+                serde_json::to_string(&entry.outcome)?,
+                entry.outcome.is_success(),
+                entry.prev_hash,
+                entry.hash,
+            ],
+        )?;
+        if let Some(signing_key) = self
+            .audit_log_signing_key
+            .read()
+            .map_err(|_| SqliteManagerError::LockError)?
+            .as_ref()
+        {
+            Self::write_audit_log_head(&tx, signing_key, entry.id, &entry.hash)?;
+        }
+        tx.commit()?;
+
+        Ok(entry)
+    }
+
+    /// Entries matching the filter, oldest first
+    pub fn query_audit_log(&self, filter: &AuditLogFilter) -> Result<Vec<AuditLogEntry>, SqliteManagerError> {
+        let mut conditions: Vec<&str> = Vec::new();
+        let mut values: Vec<Box<dyn ToSql>> = Vec::new();
+
+        if let Some(action) = filter.action {
+            conditions.push("action = ?");
+            values.push(Box::new(action.to_string()));
+        }
+        if let Some(actor) = &filter.actor {
+            conditions.push("actor = ?");
+            values.push(Box::new(actor.clone()));
+        }
+        if let Some(api_key_id) = &filter.api_key_id {
+            conditions.push("api_key_id = ?");
+            values.push(Box::new(api_key_id.clone()));
+        }
+        if let Some(target) = &filter.target {
+            conditions.push("target = ?");
+            values.push(Box::new(target.clone()));
+        }
+        if let Some(success) = filter.success {
+            conditions.push("success = ?");
+            values.push(Box::new(success));
+        }
+        // Timestamps are stored with a fixed width, so comparing them as text keeps their order
+        if let Some(from) = filter.from {
+            conditions.push("timestamp >= ?");
+            values.push(Box::new(from.to_rfc3339_opts(SecondsFormat::Nanos, true)));
+        }
+        if let Some(to) = filter.to {
+            conditions.push("timestamp <= ?");
+            values.push(Box::new(to.to_rfc3339_opts(SecondsFormat::Nanos, true)));
+        }
+
+        let mut query = format!("SELECT {} FROM shinkai_audit_log", AUDIT_LOG_COLUMNS);
+        if !conditions.is_empty() {
+            query.push_str(" WHERE ");
+            query.push_str(&conditions.join(" AND "));
+        }
+        query.push_str(" ORDER BY id ASC");
+        if filter.limit.is_some() || filter.offset.is_some() {
+            // SQLite needs a LIMIT to use OFFSET, -1 means no limit
+            query.push_str(" LIMIT ? OFFSET ?");
+            values.push(Box::new(filter.limit.map_or(-1, |limit| limit as i64)));
+            values.push(Box::new(filter.offset.unwrap_or(0) as i64));
+        }
+
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(&query)?;
+        let entries = stmt
+            .query_map(
+                rusqlite::params_from_iter(values.iter().map(|value| value.as_ref())),
+                Self::row_to_audit_log_entry,
+            )?
+            .collect::<Result<Vec<AuditLogEntry>, _>>()?;
+        Ok(entries)
+    }
+
+    /// Walks the whole chain checking each hash and its link to the previous entry, then checks
+    /// that the chain ends at the signed head once the node set its signing key
+    pub fn verify_audit_log(&self) -> Result<AuditLogVerification, SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM shinkai_audit_log ORDER BY id ASC",
+            AUDIT_LOG_COLUMNS
+        ))?;
+        let mut rows = stmt.query([])?;
+
+        let mut entries_checked = 0;
+        let mut expected_id = 1;
+        let mut expected_prev_hash = AUDIT_LOG_GENESIS_HASH.to_string();
+        while let Some(row) = rows.next()? {
+            // An entry that no longer parses has been tampered with as well
+            let (id, entry) = match Self::row_to_audit_log_entry(row) {
+                Ok(entry) => (entry.id, Some(entry)),
+                Err(_) => (row.get(0)?, None),
+            };
+            let valid = entry.as_ref().map_or(false, |entry| {
+                entry.id == expected_id && entry.prev_hash == expected_prev_hash && entry.compute_hash() == entry.hash
+            });
+            if !valid {
+                return Ok(AuditLogVerification {
+                    valid: false,
+                    entries_checked,
+                    first_invalid_id: Some(id),
+                    anchored: false,
+                });
+            }
+
+            entries_checked += 1;
+            expected_id += 1;
+            expected_prev_hash = entry.map(|entry| entry.hash).unwrap_or_default();
+        }
+
+        let verifying_key = self
+            .audit_log_signing_key
+            .read()
+            .map_err(|_| SqliteManagerError::LockError)?
+            .as_ref()
+            .map(SigningKey::verifying_key);
+        let Some(verifying_key) = verifying_key else {
+            return Ok(AuditLogVerification {
+                valid: true,
+                entries_checked,
+                first_invalid_id: None,
+                anchored: false,
+            });
+        };
+
+        let head: Option<(u64, String, String)> = conn
+            .query_row(
+                "SELECT entry_count, head_hash, signature FROM shinkai_audit_log_head WHERE id = 1",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .optional()?;
+        let (head_count, head_hash) = match head {
+            Some((count, hash, signature)) if Self::audit_log_head_signed(&verifying_key, count, &hash, &signature) => {
+                (count, hash)
+            }
+            None if entries_checked == 0 => (0, AUDIT_LOG_GENESIS_HASH.to_string()),
+            // A missing or forged head can't vouch for where the chain ends
+            _ => {
+                return Ok(AuditLogVerification {
+                    valid: false,
+                    entries_checked,
+                    first_invalid_id: None,
+                    anchored: false,
+                })
+            }
+        };
+        if head_count != entries_checked || head_hash != expected_prev_hash {
+            return Ok(AuditLogVerification {
+                valid: false,
+                entries_checked,
+                first_invalid_id: (head_count != entries_checked).then(|| head_count.min(entries_checked) + 1),
+                anchored: false,
+            });
+        }
+
+        Ok(AuditLogVerification {
+            valid: true,
+            entries_checked,
+            first_invalid_id: None,
+            anchored: true,
+        })
+    }
+
+    fn write_audit_log_head(
+        tx: &Transaction,
+        signing_key: &SigningKey,
+        entry_count: u64,
+        head_hash: &str,
+    ) -> Result<(), SqliteManagerError> {
+        let signature = signing_key.sign(&audit_log_head_message(entry_count, head_hash));
+        tx.execute(
+            "INSERT OR REPLACE INTO shinkai_audit_log_head (id, entry_count, head_hash, signature)
+             VALUES (1, ?1, ?2, ?3)",
+            params![entry_count, head_hash, hex::encode(signature.to_bytes())],
+        )?;
+        Ok(())
+    }
+
+    fn audit_log_head_signed(verifying_key: &VerifyingKey, entry_count: u64, head_hash: &str, signature: &str) -> bool {
+        hex::decode(signature)
+            .ok()
+            .and_then(|bytes| Signature::from_slice(&bytes).ok())
+            .map_or(false, |signature| {
+                verifying_key
+                    .verify(&audit_log_head_message(entry_count, head_hash), &signature)
+                    .is_ok()
+            })
+    }
+
+    fn row_to_audit_log_entry(row: &rusqlite::Row) -> rusqlite::Result<AuditLogEntry> {
+        fn parse_json<T: serde::de::DeserializeOwned>(index: usize, value: String) -> rusqlite::Result<T> {
+            serde_json::from_str(&value)
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, Box::new(e)))
+        }
+
+        let timestamp: String = row.get(1)?;
+        Ok(AuditLogEntry {
+            id: row.get(0)?,
+            timestamp: DateTime::parse_from_rfc3339(&timestamp)
+                .map(|date| date.with_timezone(&Utc))
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(1, rusqlite::types::Type::Text, Box::new(e)))?,
+            actor: row.get(2)?,
+            api_key_id: row.get(3)?,
+            action: serde_json::from_value(Value::String(row.get(4)?))
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(4, rusqlite::types::Type::Text, Box::new(e)))?,
+            target: row.get(5)?,
+            parameters: parse_json(6, row.get(6)?)?,
+            outcome: parse_json(7, row.get(7)?)?,
+            prev_hash: row.get(8)?,
+            hash: row.get(9)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use shinkai_embedding::model_type::{EmbeddingModelType, OllamaTextEmbeddingsInference};
+    use std::path::PathBuf;
+    use tempfile::NamedTempFile;
+
+    fn setup_test_db() -> SqliteManager {
+        let temp_file = NamedTempFile::new().unwrap();
+        let db_path = PathBuf::from(temp_file.path());
+        let api_url = String::new();
+        let model_type =
+            EmbeddingModelType::OllamaTextEmbeddingsInference(OllamaTextEmbeddingsInference::SnowflakeArcticEmbedM);
+
+        SqliteManager::new(db_path, api_url, model_type).unwrap()
+    }
+
+    #[test]
+    fn test_audit_log_chain_query_and_tampering() {
+        let db = setup_test_db();
+        db.append_audit_log_entry(
+            "node",
+            None,
+            AuditAction::AgentCreated,
+            Some("my_agent"),
+            json!({ "llm_provider_id": "gpt" }),
+            AuditOutcome::Success,
+        )
+        .unwrap();
+        db.append_audit_log_entry(
+            "ci",
+            Some("key-1"),
+            AuditAction::FileDeleted,
+            Some("/docs/report.pdf"),
+            json!({}),
+            AuditOutcome::Failure {
+                code: 403,
+                message: "Forbidden".to_string(),
+            },
+        )
+        .unwrap();
+        let third = db
+            .append_audit_log_entry(
+                "node",
+                None,
+                AuditAction::AgentRemoved,
+                Some("my_agent"),
+                json!({}),
+                AuditOutcome::Success,
+            )
+            .unwrap();
+
+        let entries = db.query_audit_log(&AuditLogFilter::default()).unwrap();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[1].prev_hash, entries[0].hash);
+        assert_eq!(entries[2], third);
+
+        let filter = AuditLogFilter {
+            target: Some("my_agent".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(db.query_audit_log(&filter).unwrap().len(), 2);
+        let filter = AuditLogFilter {
+            success: Some(false),
+            ..Default::default()
+        };
+        assert_eq!(db.query_audit_log(&filter).unwrap()[0].api_key_id.as_deref(), Some("key-1"));
+        let filter = AuditLogFilter {
+            offset: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(db.query_audit_log(&filter).unwrap()[0].id, 3);
+
+        assert!(db.verify_audit_log().unwrap().valid);
+
+        // Updates and deletes are rejected by the triggers
+        let conn = db.get_connection().unwrap();
+        assert!(conn
+            .execute("UPDATE shinkai_audit_log SET target = 'other' WHERE id = 2", [])
+            .is_err());
+        assert!(conn.execute("DELETE FROM shinkai_audit_log WHERE id = 2", []).is_err());
+
+        // Tampering with the file directly is caught by the chain
+        conn.execute("DROP TRIGGER shinkai_audit_log_no_update", []).unwrap();
+        conn.execute("UPDATE shinkai_audit_log SET target = 'other' WHERE id = 2", [])
+            .unwrap();
+        let verification = db.verify_audit_log().unwrap();
+        assert!(!verification.valid);
+        assert_eq!(verification.entries_checked, 1);
+        assert_eq!(verification.first_invalid_id, Some(2));
+    }
+
+    #[test]
+    fn test_audit_log_signed_head() {
+        let db = setup_test_db();
+        let append = |target: &str| {
+            db.append_audit_log_entry(
+                "node",
+                None,
+                AuditAction::AgentCreated,
+                Some(target),
+                json!({}),
+                AuditOutcome::Success,
+            )
+            .unwrap()
+        };
+        append("first");
+        append("second");
+        let verification = db.verify_audit_log().unwrap();
+        assert!(verification.valid);
+        assert!(!verification.anchored);
+
+        // The existing chain is anchored when the key is set, later appends move the head
+        let signing_key = SigningKey::from_bytes(&[7; 32]);
+        db.set_audit_log_signing_key(signing_key.clone()).unwrap();
+        append("third");
+        let verification = db.verify_audit_log().unwrap();
+        assert!(verification.valid);
+        assert!(verification.anchored);
+        assert_eq!(verification.entries_checked, 3);
+
+        // Dropping the newest entry keeps the chain itself valid, but not its end
+        let conn = db.get_connection().unwrap();
+        conn.execute("DROP TRIGGER shinkai_audit_log_no_delete", []).unwrap();
+        conn.execute("DELETE FROM shinkai_audit_log WHERE id = 3", []).unwrap();
+        let verification = db.verify_audit_log().unwrap();
+        assert!(!verification.valid);
+        assert!(!verification.anchored);
+        assert_eq!(verification.entries_checked, 2);
+        assert_eq!(verification.first_invalid_id, Some(3));
+
+        // Moving the head back needs the node key
+        let forged = SigningKey::from_bytes(&[8; 32]);
+        let tx = conn.unchecked_transaction().unwrap();
+        let head_hash: String = tx
+            .query_row("SELECT hash FROM shinkai_audit_log WHERE id = 2", [], |row| row.get(0))
+            .unwrap();
+        SqliteManager::write_audit_log_head(&tx, &forged, 2, &head_hash).unwrap();
+        tx.commit().unwrap();
+        assert!(!db.verify_audit_log().unwrap().valid);
+
+        let tx = conn.unchecked_transaction().unwrap();
+        SqliteManager::write_audit_log_head(&tx, &signing_key, 2, &head_hash).unwrap();
+        tx.commit().unwrap();
+        assert!(db.verify_audit_log().unwrap().anchored);
+    }
+}
@@ -0,0 +1,342 @@
+use crate::{SqliteManager, SqliteManagerError};
+use rusqlite::{params, DatabaseName, Transaction, TransactionBehavior};
+use shinkai_message_primitives::schemas::node_backup::BackupCategory;
+use shinkai_message_primitives::shinkai_utils::secret_vault::{is_sealed_secret, SecretVault};
+use std::path::Path;
+
+/// Tables that belong to the node itself: identities, keys, API keys, the audit log, the vault,
+/// webhooks, webhook triggers and queued node events. They are left out of backups and never
+/// overwritten by a restore.
+const NODE_ONLY_TABLES: &[&str] = &[
+    "cron_task_events",
+    "device_identities",
+    "standard_identities",
+    "local_node_keys",
+    "message_box_symmetric_keys",
+    "registration_code",
+    "retry_messages",
+    "shinkai_api_keys",
+    "shinkai_audit_log",
+    "shinkai_audit_log_head",
+    "shinkai_secret_vault",
+    "shinkai_webhook_deliveries",
+    "shinkai_webhook_triggers",
+    "shinkai_webhooks",
+];
+
+/// Tables holding each category, parents before the tables that reference them
+pub fn backup_category_tables(category: BackupCategory) -> &'static [&'static str] {
+    match category {
+        BackupCategory::Agents => &["shinkai_agents"],
+        BackupCategory::LlmProviders => &["llm_providers", "job_concurrency_limits"],
+        BackupCategory::Tools => &[
+            "shinkai_tools",
+            "shinkai_tools_vec_items",
+            "shinkai_tool_revisions",
+            "shinkai_tool_network_approvals",
+            "shinkai_tool_egress_blocks",
+            "shinkai_wasm_tool_grants",
+            "tool_playground",
+            "tool_playground_code_history",
+            "oauth_tokens",
+        ],
+        BackupCategory::Prompts => &["shinkai_prompts", "prompt_vec_items"],
+        BackupCategory::Jobs => &[
+            "inboxes",
+            "inbox_messages",
+            "inbox_message_search",
+            "inbox_message_vec_items",
+            "inbox_profile_permissions",
+            "jobs",
+            "forked_jobs",
+            "job_queues",
+            "job_pipelines",
+            "job_pipeline_runs",
+            "job_pipeline_step_runs",
+            "job_dead_letters",
+            "job_checkpoints",
+            "scheduled_job_messages",
+            "file_inboxes",
+            "shinkai_sheets",
+        ],
+        BackupCategory::VectorFs => &["parsed_files", "chunks", "chunk_vec"],
+        BackupCategory::CronTasks => &["cron_tasks", "cron_task_executions"],
+        BackupCategory::Wallets => &[
+            "shinkai_wallet",
+            "tool_micropayments_requirements",
+            "invoice_requests",
+            "invoices",
+            "invoice_network_errors",
+        ],
+        BackupCategory::Preferences => &["preferences", "shinkai_settings", "regex_patterns"],
+    }
+}
+
+impl SqliteManager {
+    /// Writes a consistent copy of the database to `path` with the SQLite online backup API.
+    /// The node keeps serving requests while the copy is made.
+    pub fn backup_database(&self, path: &Path) -> Result<(), SqliteManagerError> {
+        let conn = self.get_connection()?;
+        conn.backup(DatabaseName::Main, path, None)?;
+        Ok(())
+    }
+
+    /// Trims a copy made by `backup_database` down to `categories`. With `backup_vault` the
+    /// secrets are sealed again with it, for archives encrypted with a passphrase that may be
+    /// restored on another node. They are never written to the copy in plaintext.
+    pub fn prepare_database_backup(
+        &self,
+        path: &Path,
+        categories: &[BackupCategory],
+        backup_vault: Option<&SecretVault>,
+    ) -> Result<(), SqliteManagerError> {
+        let mut conn = rusqlite::Connection::open(path)?;
+        conn.execute_batch("PRAGMA journal_mode=DELETE;")?;
+
+        let tx = conn.transaction()?;
+        let excluded_tables = BackupCategory::all()
+            .into_iter()
+            .filter(|category| !categories.contains(category))
+            .flat_map(|category| backup_category_tables(category).iter().copied());
+        for table in NODE_ONLY_TABLES.iter().copied().chain(excluded_tables) {
+            tx.execute(&format!("DELETE FROM {}", table), [])?;
+        }
+        if let Some(backup_vault) = backup_vault {
+            Self::reseal_secrets(&tx, |value| Ok(backup_vault.seal(&self.reveal_secret(value)?)?))?;
+        }
+        tx.commit()?;
+
+        conn.execute_batch("VACUUM;")?;
+        Ok(())
+    }
+
+    /// Imports `categories` from a copy made by `backup_database`, replacing the current rows of
+    /// those categories in a single transaction. With `rename`, text mentioning the node name of
+    /// the backup is rewritten to the current one. The secrets of the backup are sealed with
+    /// `backup_vault`, or with the vault of this node when it is `None`, and are restored sealed
+    /// with the vault of this node. Returns the number of rows imported.
+    pub fn restore_database_backup(
+        &self,
+        path: &Path,
+        categories: &[BackupCategory],
+        rename: Option<(&str, &str)>,
+        backup_vault: Option<&SecretVault>,
+    ) -> Result<u64, SqliteManagerError> {
+        let mut conn = self.get_connection()?;
+        conn.execute("ATTACH DATABASE ?1 AS backup", params![path.to_string_lossy()])?;
+        let result = self.restore_attached_backup(&mut conn, categories, rename, backup_vault);
+        // Pooled connections are reused, so the backup is detached even if the restore failed
+        let detached = conn.execute("DETACH DATABASE backup", []);
+        let rows = result?;
+        detached?;
+
+        self.sync_tools_fts_table()?;
+        self.sync_prompts_fts_table()?;
+        self.sync_inbox_message_search()?;
+        Ok(rows)
+    }
+
+    fn restore_attached_backup(
+        &self,
+        conn: &mut rusqlite::Connection,
+        categories: &[BackupCategory],
+        rename: Option<(&str, &str)>,
+        backup_vault: Option<&SecretVault>,
+    ) -> Result<u64, SqliteManagerError> {
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        // Rows are deleted and inserted table by table, references only have to hold at commit
+        tx.execute_batch("PRAGMA defer_foreign_keys = ON;")?;
+
+        let mut rows = 0;
+        for category in categories {
+            for table in backup_category_tables(*category) {
+                rows += Self::restore_table(&tx, table, rename)?;
+            }
+        }
+
+        let vault = self.secret_vault.read().map_err(|_| SqliteManagerError::LockError)?;
+        Self::reseal_secrets(&tx, |value| {
+            let value = match (backup_vault, vault.as_ref()) {
+                (Some(backup_vault), _) | (None, Some(backup_vault)) => backup_vault.reveal(value)?,
+                (None, None) if is_sealed_secret(value) => return Err(SqliteManagerError::SecretVaultLocked),
+                (None, None) => value.to_string(),
+            };
+            match vault.as_ref() {
+                Some(vault) => Ok(vault.seal(&value)?),
+                None => Ok(value),
+            }
+        })?;
+
+        tx.commit()?;
+        Ok(rows)
+    }
+
+    fn restore_table(tx: &Transaction, table: &str, rename: Option<(&str, &str)>) -> Result<u64, SqliteManagerError> {
+        // Backups from older nodes may miss tables or columns, which keep their defaults
+        let backup_columns = Self::table_columns(tx, "backup", table)?;
+        if backup_columns.is_empty() {
+            return Ok(0);
+        }
+        let columns: Vec<(String, String)> = Self::table_columns(tx, "main", table)?
+            .into_iter()
+            .filter(|(name, _)| backup_columns.iter().any(|(backup_name, _)| backup_name == name))
+            .collect();
+        let column_list = columns
+            .iter()
+            .map(|(name, _)| format!("\"{}\"", name))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        tx.execute(&format!("DELETE FROM main.{}", table), [])?;
+        let rows = tx.execute(
+            &format!(
+                "INSERT INTO main.{table} (rowid, {columns}) SELECT rowid, {columns} FROM backup.{table}",
+                table = table,
+                columns = column_list
+            ),
+            [],
+        )?;
+
+        // Virtual tables only hold embeddings and ids
+        let is_virtual: bool = tx.query_row(
+            "SELECT sql LIKE 'CREATE VIRTUAL TABLE%' FROM main.sqlite_master WHERE type = 'table' AND name = ?1",
+            params![table],
+            |row| row.get(0),
+        )?;
+        if let Some((old_name, new_name)) = rename.filter(|_| !is_virtual) {
+            for (name, column_type) in &columns {
+                if !column_type.to_uppercase().starts_with("TEXT") {
+                    continue;
+                }
+                tx.execute(
+                    &format!(
+                        "UPDATE main.{table} SET \"{column}\" = REPLACE(\"{column}\", ?1, ?2) WHERE instr(\"{column}\", ?1) > 0",
+                        table = table,
+                        column = name
+                    ),
+                    params![old_name, new_name],
+                )?;
+            }
+        }
+
+        Ok(rows as u64)
+    }
+
+    // Name and declared type of each column, empty if the table doesn't exist
+    fn table_columns(tx: &Transaction, schema: &str, table: &str) -> Result<Vec<(String, String)>, SqliteManagerError> {
+        let mut stmt = tx.prepare(&format!("PRAGMA {}.table_info({})", schema, table))?;
+        let columns = stmt
+            .query_map([], |row| Ok((row.get::<_, String>(1)?, row.get::<_, String>(2)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(columns)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shinkai_embedding::model_type::{EmbeddingModelType, OllamaTextEmbeddingsInference};
+    use shinkai_message_primitives::schemas::{
+        custom_prompt::CustomPrompt,
+        llm_providers::serialized_llm_provider::{LLMProviderInterface, OpenAI, SerializedLLMProvider},
+        shinkai_name::ShinkaiName,
+    };
+
+    fn open_test_db(db_path: &Path) -> SqliteManager {
+        let api_url = String::new();
+        let model_type =
+            EmbeddingModelType::OllamaTextEmbeddingsInference(OllamaTextEmbeddingsInference::SnowflakeArcticEmbedM);
+
+        SqliteManager::new(db_path, api_url, model_type).unwrap()
+    }
+
+    #[test]
+    fn test_backup_and_selective_restore() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = open_test_db(&dir.path().join("source.db"));
+        source.unlock_secret_vault_with_passphrase("source passphrase").unwrap();
+
+        let identity = ShinkaiName::new("@@alice.shinkai/main/agent/gpt".to_string()).unwrap();
+        let profile = identity.extract_profile().unwrap();
+        source
+            .add_llm_provider(
+                SerializedLLMProvider {
+                    id: "gpt".to_string(),
+                    full_identity_name: identity,
+                    external_url: Some("https://api.openai.com".to_string()),
+                    api_key: Some("sk-backup".to_string()),
+                    model: LLMProviderInterface::OpenAI(OpenAI {
+                        model_type: "gpt-4o".to_string(),
+                    }),
+                },
+                &profile,
+            )
+            .unwrap();
+
+        let sealed_path = dir.path().join("sealed.db");
+        source.backup_database(&sealed_path).unwrap();
+        source
+            .prepare_database_backup(&sealed_path, &[BackupCategory::LlmProviders], None)
+            .unwrap();
+        let passphrase_vault = SecretVault::from_passphrase("backup passphrase", &SecretVault::random_salt()).unwrap();
+        let passphrase_path = dir.path().join("passphrase.db");
+        source.backup_database(&passphrase_path).unwrap();
+        source
+            .prepare_database_backup(
+                &passphrase_path,
+                &[BackupCategory::LlmProviders],
+                Some(&passphrase_vault),
+            )
+            .unwrap();
+
+        // The copy of an encrypted archive keeps the secrets sealed, with the passphrase vault
+        let copied_api_key: String = rusqlite::Connection::open(&passphrase_path)
+            .unwrap()
+            .query_row("SELECT api_key FROM llm_providers", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(passphrase_vault.reveal(&copied_api_key).unwrap(), "sk-backup");
+
+        let target = open_test_db(&dir.path().join("target.db"));
+        target.unlock_secret_vault_with_passphrase("target passphrase").unwrap();
+        let prompt = CustomPrompt {
+            rowid: None,
+            name: "kept".to_string(),
+            prompt: "Untouched by the restore".to_string(),
+            is_system: false,
+            is_enabled: true,
+            version: "1".to_string(),
+            is_favorite: false,
+        };
+        target.add_prompt_with_vector(&prompt, vec![0.1; 384]).unwrap();
+
+        // Secrets sealed with another vault can't be restored, and nothing is imported
+        let rename = Some(("@@alice.shinkai", "@@bob.shinkai"));
+        assert!(target
+            .restore_database_backup(&sealed_path, &[BackupCategory::LlmProviders], rename, None)
+            .is_err());
+        assert!(target.get_all_llm_providers().unwrap().is_empty());
+
+        let rows = target
+            .restore_database_backup(
+                &passphrase_path,
+                &[BackupCategory::LlmProviders],
+                rename,
+                Some(&passphrase_vault),
+            )
+            .unwrap();
+        assert_eq!(rows, 1);
+
+        let bob_profile = ShinkaiName::new("@@bob.shinkai/main".to_string()).unwrap();
+        let restored = target.get_llm_provider("gpt", &bob_profile).unwrap().unwrap();
+        assert_eq!(restored.full_identity_name.full_name, "@@bob.shinkai/main/agent/gpt");
+        assert!(is_sealed_secret(restored.api_key.as_deref().unwrap()));
+        assert_eq!(
+            target.reveal_optional_secret(&restored.api_key).unwrap().as_deref(),
+            Some("sk-backup")
+        );
+
+        let prompts = target.get_all_prompts().unwrap();
+        assert_eq!(prompts.len(), 1);
+        assert_eq!(prompts[0].name, "kept");
+    }
+}
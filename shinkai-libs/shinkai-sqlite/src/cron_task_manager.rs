@@ -1,28 +1,59 @@
 use crate::SqliteManager;
 use crate::SqliteManagerError;
-use rusqlite::params;
-use serde_json;
-use shinkai_message_primitives::schemas::crontab::{CronTask, CronTaskAction};
+use chrono::SecondsFormat;
+use rusqlite::{params, OptionalExtension, Transaction};
+use serde::de::DeserializeOwned;
+use serde_json::{self, json, Value};
+use shinkai_message_primitives::schemas::crontab::{
+    CronEventTrigger, CronExecutionStatus, CronTask, CronTaskAction, CronTaskExecution, CronTaskPolicy,
+};
+use shinkai_message_primitives::schemas::inbox_name::InboxName;
+use shinkai_message_primitives::schemas::webhook::WebhookEventType;
+use shinkai_message_primitives::shinkai_message::shinkai_message_schemas::AssociatedUI;
 
 impl SqliteManager {
+    #[allow(clippy::too_many_arguments)]
     pub fn add_cron_task(
         &self,
         name: &str,
         description: Option<&str>,
         cron: &str,
+        timezone: Option<&str>,
+        policy: &CronTaskPolicy,
+        event_trigger: Option<&CronEventTrigger>,
         action: &CronTaskAction,
     ) -> Result<i64, SqliteManagerError> {
         let mut conn = self.get_connection()?;
         let tx = conn.transaction()?;
 
-        let created_at = chrono::Utc::now().to_rfc3339();
+        let now = chrono::Utc::now();
+        let created_at = now.to_rfc3339();
         let last_modified = created_at.clone();
         let action_json = serde_json::to_string(action)?;
+        let policy_json = serde_json::to_string(policy)?;
+        let event_trigger_json = event_trigger.map(serde_json::to_string).transpose()?;
+        let trigger_event = event_trigger.map(|trigger| trigger.event.event_type().to_string());
+        // Runs due before the task existed are not missed runs
+        let last_scheduled_run = now.to_rfc3339_opts(SecondsFormat::Millis, true);
 
         tx.execute(
-            "INSERT INTO cron_tasks (name, description, cron, created_at, last_modified, action, paused) 
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-            params![name, description, cron, created_at, last_modified, action_json, false],
+            "INSERT INTO cron_tasks (name, description, cron, created_at, last_modified, action, paused, timezone, policy, 
+                                     last_scheduled_run, event_trigger, trigger_event) 
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+            params![
+                name,
+                description,
+                cron,
+                created_at,
+                last_modified,
+                action_json,
+                false,
+                timezone,
+                policy_json,
+                last_scheduled_run,
+                event_trigger_json,
+                trigger_event
+            ],
         )?;
 
         let task_id = tx.last_insert_rowid();
@@ -45,7 +76,7 @@ impl SqliteManager {
     pub fn get_cron_task(&self, task_id: i64) -> Result<Option<CronTask>, SqliteManagerError> {
         let conn = self.get_connection()?;
         let mut stmt = conn.prepare(
-            "SELECT task_id, name, description, cron, created_at, last_modified, action, paused 
+            "SELECT task_id, name, description, cron, created_at, last_modified, action, paused, timezone, policy, event_trigger 
              FROM cron_tasks WHERE task_id = ?1",
         )?;
         let mut rows = stmt.query(params![task_id])?;
@@ -63,6 +94,9 @@ impl SqliteManager {
                 last_modified: row.get(5)?,
                 action,
                 paused: row.get(7)?,
+                timezone: row.get(8)?,
+                policy: Self::json_column_from_row(row, 9)?.unwrap_or_default(),
+                event_trigger: Self::json_column_from_row(row, 10)?,
             }))
         } else {
             Ok(None)
@@ -75,7 +109,7 @@ impl SqliteManager {
     ) -> Result<Vec<CronTask>, SqliteManagerError> {
         let conn = self.get_connection()?;
         let mut stmt = conn.prepare(
-            "SELECT task_id, name, description, cron, created_at, last_modified, action, paused 
+            "SELECT task_id, name, description, cron, created_at, last_modified, action, paused, timezone, policy, event_trigger 
              FROM cron_tasks",
         )?;
         let cron_task_iter = stmt.query_map([], |row| {
@@ -99,6 +133,9 @@ impl SqliteManager {
                     last_modified: row.get(5)?,
                     action,
                     paused: row.get(7)?,
+                    timezone: row.get(8)?,
+                    policy: Self::json_column_from_row(row, 9)?.unwrap_or_default(),
+                    event_trigger: Self::json_column_from_row(row, 10)?,
                 }))
             } else {
                 Ok(None)
@@ -116,12 +153,16 @@ impl SqliteManager {
         Ok(matching_tasks)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn update_cron_task(
         &self,
         task_id: i64,
         name: &str,
         description: Option<&str>,
         cron: &str,
+        timezone: Option<&str>,
+        policy: Option<&CronTaskPolicy>,
+        event_trigger: Option<&CronEventTrigger>,
         action: &CronTaskAction,
         paused: bool,
     ) -> Result<(), SqliteManagerError> {
@@ -130,12 +171,29 @@ impl SqliteManager {
 
         let last_modified = chrono::Utc::now().to_rfc3339();
         let action_json = serde_json::to_string(action)?;
+        let policy_json = policy.map(serde_json::to_string).transpose()?;
+        let event_trigger_json = event_trigger.map(serde_json::to_string).transpose()?;
+        let trigger_event = event_trigger.map(|trigger| trigger.event.event_type().to_string());
 
+        // Without a policy the current one is kept
         tx.execute(
             "UPDATE cron_tasks 
-             SET name = ?1, description = ?2, cron = ?3, last_modified = ?4, action = ?5, paused = ?6 
-             WHERE task_id = ?7",
-            params![name, description, cron, last_modified, action_json, paused, task_id],
+             SET name = ?1, description = ?2, cron = ?3, last_modified = ?4, action = ?5, paused = ?6, timezone = ?7,
+                 policy = COALESCE(?8, policy), event_trigger = ?9, trigger_event = ?10
+             WHERE task_id = ?11",
+            params![
+                name,
+                description,
+                cron,
+                last_modified,
+                action_json,
+                paused,
+                timezone,
+                policy_json,
+                event_trigger_json,
+                trigger_event,
+                task_id
+            ],
         )?;
 
         tx.commit()?;
@@ -145,7 +203,7 @@ impl SqliteManager {
     pub fn get_all_cron_tasks(&self) -> Result<Vec<CronTask>, SqliteManagerError> {
         let conn = self.get_connection()?;
         let mut stmt = conn.prepare(
-            "SELECT task_id, name, description, cron, created_at, last_modified, action, paused 
+            "SELECT task_id, name, description, cron, created_at, last_modified, action, paused, timezone, policy, event_trigger 
              FROM cron_tasks",
         )?;
         let cron_task_iter = stmt.query_map([], |row| {
@@ -162,6 +220,9 @@ impl SqliteManager {
                 last_modified: row.get(5)?,
                 action,
                 paused: row.get(7)?,
+                timezone: row.get(8)?,
+                policy: Self::json_column_from_row(row, 9)?.unwrap_or_default(),
+                event_trigger: Self::json_column_from_row(row, 10)?,
             })
         })?;
 
@@ -170,6 +231,166 @@ impl SqliteManager {
             .map_err(SqliteManagerError::DatabaseError)
     }
 
+    /// Tasks that are not paused and run on node events of `event_type`
+    pub fn get_event_triggered_cron_tasks(
+        &self,
+        event_type: WebhookEventType,
+    ) -> Result<Vec<CronTask>, SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(
+            "SELECT task_id, name, description, cron, created_at, last_modified, action, paused, timezone, policy, event_trigger 
+             FROM cron_tasks WHERE paused = 0 AND trigger_event = ?1",
+        )?;
+        let cron_task_iter = stmt.query_map(params![event_type.to_string()], |row| {
+            let action_json: String = row.get(6)?;
+            let action: CronTaskAction =
+                serde_json::from_str(&action_json).map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+
+            Ok(CronTask {
+                task_id: row.get(0)?,
+                name: row.get(1)?,
+                description: row.get(2)?,
+                cron: row.get(3)?,
+                created_at: row.get(4)?,
+                last_modified: row.get(5)?,
+                action,
+                paused: row.get(7)?,
+                timezone: row.get(8)?,
+                policy: Self::json_column_from_row(row, 9)?.unwrap_or_default(),
+                event_trigger: Self::json_column_from_row(row, 10)?,
+            })
+        })?;
+
+        cron_task_iter
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(SqliteManagerError::DatabaseError)
+    }
+
+    /// Queues a node event for the event-triggered tasks, inside the transaction that produced it.
+    /// Events no task listens to are not kept. Events of the job of a task are tagged with its id in
+    /// `cron_task_ids`, so they don't trigger the task that caused them.
+    pub(crate) fn enqueue_cron_task_event_tx(
+        tx: &Transaction,
+        event_type: WebhookEventType,
+        payload: &Value,
+    ) -> Result<(), SqliteManagerError> {
+        let event_type = event_type.to_string();
+        let listened: bool = tx.query_row(
+            "SELECT EXISTS(SELECT 1 FROM cron_tasks WHERE paused = 0 AND trigger_event = ?1)",
+            params![event_type],
+            |row| row.get(0),
+        )?;
+        if !listened {
+            return Ok(());
+        }
+
+        let mut payload = payload.clone();
+        let task_ids = Self::event_cron_task_ids_tx(tx, &payload)?;
+        if let (false, Some(fields)) = (task_ids.is_empty(), payload.as_object_mut()) {
+            fields.insert("cron_task_ids".to_string(), json!(task_ids));
+        }
+        tx.execute(
+            "INSERT INTO cron_task_events (event_type, payload, created_at) VALUES (?1, ?2, ?3)",
+            params![
+                event_type,
+                serde_json::to_string(&payload)?,
+                chrono::Utc::now().to_rfc3339()
+            ],
+        )?;
+        Ok(())
+    }
+
+    // Tasks whose job the event comes from: the job a task created, or the one it sends its
+    // message to
+    fn event_cron_task_ids_tx(tx: &Transaction, payload: &Value) -> Result<Vec<i64>, SqliteManagerError> {
+        let job_id = match payload.get("job_id").and_then(Value::as_str) {
+            Some(job_id) => Some(job_id.to_string()),
+            None => payload
+                .get("inbox_name")
+                .and_then(Value::as_str)
+                .and_then(|inbox_name| InboxName::new(inbox_name.to_string()).ok())
+                .and_then(|inbox_name| inbox_name.get_job_id()),
+        };
+        let Some(job_id) = job_id else {
+            return Ok(vec![]);
+        };
+
+        let mut task_ids = Vec::new();
+        let associated_ui: Option<String> = tx
+            .query_row(
+                "SELECT associated_ui FROM jobs WHERE job_id = ?1",
+                params![job_id],
+                |row| row.get(0),
+            )
+            .optional()?
+            .flatten();
+        if let Some(AssociatedUI::Cron(task_id)) = associated_ui.and_then(|ui| serde_json::from_str(&ui).ok()) {
+            task_ids.extend(task_id.parse::<i64>().ok());
+        }
+
+        let actions = tx
+            .prepare("SELECT task_id, action FROM cron_tasks WHERE trigger_event IS NOT NULL")?
+            .query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        for (task_id, action) in actions {
+            if let Ok(CronTaskAction::SendMessageToJob {
+                job_id: target_job_id, ..
+            }) = serde_json::from_str(&action)
+            {
+                if target_job_id == job_id && !task_ids.contains(&task_id) {
+                    task_ids.push(task_id);
+                }
+            }
+        }
+        Ok(task_ids)
+    }
+
+    /// Queued node events after `after_event_id`, oldest first, with their id. They stay queued
+    /// until `delete_cron_task_events` is called once the runs they triggered have started.
+    pub fn get_cron_task_events(
+        &self,
+        after_event_id: i64,
+        limit: u64,
+    ) -> Result<Vec<(i64, WebhookEventType, Value)>, SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let events = conn
+            .prepare(
+                "SELECT event_id, event_type, payload FROM cron_task_events WHERE event_id > ?1
+                 ORDER BY event_id ASC LIMIT ?2",
+            )?
+            .query_map(params![after_event_id, limit as i64], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut queued = Vec::with_capacity(events.len());
+        for (event_id, event_type, payload) in events {
+            let event_type = serde_json::from_value(Value::String(event_type))?;
+            queued.push((event_id, event_type, serde_json::from_str(&payload)?));
+        }
+        Ok(queued)
+    }
+
+    pub fn delete_cron_task_events(&self, event_ids: &[i64]) -> Result<(), SqliteManagerError> {
+        if event_ids.is_empty() {
+            return Ok(());
+        }
+        let mut conn = self.get_connection()?;
+        let tx = conn.transaction()?;
+        {
+            let mut stmt = tx.prepare("DELETE FROM cron_task_events WHERE event_id = ?1")?;
+            for event_id in event_ids {
+                stmt.execute(params![event_id])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
     // Add a new execution record for a cron task
     pub fn add_cron_task_execution(
         &self,
@@ -178,15 +399,196 @@ impl SqliteManager {
         success: bool,
         error_message: Option<&str>,
         job_id: Option<String>,
+    ) -> Result<i64, SqliteManagerError> {
+        let status = if success {
+            CronExecutionStatus::Success
+        } else {
+            CronExecutionStatus::Failed
+        };
+        let mut conn = self.get_connection()?;
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT INTO cron_task_executions (task_id, execution_time, success, error_message, job_id, status, finished_at) 
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?2)",
+            params![
+                task_id,
+                execution_time,
+                success as i32,
+                error_message,
+                job_id.as_deref(),
+                status.to_string()
+            ],
+        )?;
+        let execution_id = tx.last_insert_rowid();
+
+        Self::enqueue_webhook_event_tx(
+            &tx,
+            WebhookEventType::CronTaskExecuted,
+            &json!({
+                "task_id": task_id,
+                "execution_id": execution_id,
+                "execution_time": execution_time,
+                "status": status,
+                "success": success,
+                "error_message": error_message,
+                "job_id": job_id,
+            }),
+        )?;
+        tx.commit()?;
+        Ok(execution_id)
+    }
+
+    /// Records an attempt that is starting, to be closed with `finish_cron_task_execution`
+    pub fn start_cron_task_execution(
+        &self,
+        task_id: i64,
+        scheduled_time: Option<&str>,
+        attempt: u32,
+        misfired: bool,
     ) -> Result<i64, SqliteManagerError> {
         let conn = self.get_connection()?;
+        let execution_time = chrono::Utc::now().to_rfc3339();
         conn.execute(
-            "INSERT INTO cron_task_executions (task_id, execution_time, success, error_message, job_id) VALUES (?1, ?2, ?3, ?4, ?5)",
-            params![task_id, execution_time, success as i32, error_message, job_id.as_deref()],
+            "INSERT INTO cron_task_executions (task_id, execution_time, success, scheduled_time, attempt, misfired, status) 
+             VALUES (?1, ?2, 0, ?3, ?4, ?5, ?6)",
+            params![
+                task_id,
+                execution_time,
+                scheduled_time,
+                attempt,
+                misfired,
+                CronExecutionStatus::Running.to_string()
+            ],
         )?;
         Ok(conn.last_insert_rowid())
     }
 
+    pub fn finish_cron_task_execution(
+        &self,
+        execution_id: i64,
+        status: CronExecutionStatus,
+        error_message: Option<&str>,
+        job_id: Option<&str>,
+    ) -> Result<(), SqliteManagerError> {
+        let mut conn = self.get_connection()?;
+        let tx = conn.transaction()?;
+
+        let finished_at = chrono::Utc::now().to_rfc3339();
+        let success = status == CronExecutionStatus::Success;
+        tx.execute(
+            "UPDATE cron_task_executions 
+             SET status = ?1, success = ?2, error_message = ?3, job_id = COALESCE(?4, job_id), finished_at = ?5 
+             WHERE execution_id = ?6",
+            params![
+                status.to_string(),
+                success as i32,
+                error_message,
+                job_id,
+                finished_at,
+                execution_id
+            ],
+        )?;
+        let (task_id, execution_time, scheduled_time, attempt, misfired, job_id): (
+            i64,
+            String,
+            Option<String>,
+            u32,
+            bool,
+            Option<String>,
+        ) = tx.query_row(
+            "SELECT task_id, execution_time, scheduled_time, attempt, misfired, job_id 
+             FROM cron_task_executions WHERE execution_id = ?1",
+            params![execution_id],
+            |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                    row.get(5)?,
+                ))
+            },
+        )?;
+
+        Self::enqueue_webhook_event_tx(
+            &tx,
+            WebhookEventType::CronTaskExecuted,
+            &json!({
+                "task_id": task_id,
+                "execution_id": execution_id,
+                "execution_time": execution_time,
+                "scheduled_time": scheduled_time,
+                "attempt": attempt,
+                "misfired": misfired,
+                "status": status,
+                "success": success,
+                "error_message": error_message,
+                "job_id": job_id,
+            }),
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Closes the attempts left running, e.g. by a node that stopped in the middle of them
+    pub fn fail_running_cron_task_executions(&self, error_message: &str) -> Result<usize, SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let finished_at = chrono::Utc::now().to_rfc3339();
+        let updated = conn.execute(
+            "UPDATE cron_task_executions 
+             SET status = ?1, success = 0, error_message = ?2, finished_at = ?3 
+             WHERE status = ?4",
+            params![
+                CronExecutionStatus::Failed.to_string(),
+                error_message,
+                finished_at,
+                CronExecutionStatus::Running.to_string()
+            ],
+        )?;
+        Ok(updated)
+    }
+
+    /// Execution log of a task, most recent first
+    pub fn get_cron_task_execution_logs(&self, task_id: i64) -> Result<Vec<CronTaskExecution>, SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(
+            "SELECT execution_id, task_id, execution_time, scheduled_time, attempt, misfired, status, success, 
+                    error_message, job_id, finished_at 
+             FROM cron_task_executions 
+             WHERE task_id = ?1 
+             ORDER BY execution_time DESC, execution_id DESC",
+        )?;
+        let execution_iter = stmt.query_map(params![task_id], |row| {
+            let success = row.get::<_, i32>(7)? != 0;
+            // Executions logged before statuses existed only have `success`
+            let status = match row.get::<_, Option<String>>(6)? {
+                Some(status) => serde_json::from_value(serde_json::Value::String(status)).map_err(|e| {
+                    rusqlite::Error::FromSqlConversionFailure(6, rusqlite::types::Type::Text, Box::new(e))
+                })?,
+                None if success => CronExecutionStatus::Success,
+                None => CronExecutionStatus::Failed,
+            };
+            Ok(CronTaskExecution {
+                execution_id: row.get(0)?,
+                task_id: row.get(1)?,
+                execution_time: row.get(2)?,
+                scheduled_time: row.get(3)?,
+                attempt: row.get(4)?,
+                misfired: row.get(5)?,
+                status,
+                success,
+                error_message: row.get(8)?,
+                job_id: row.get(9)?,
+                finished_at: row.get(10)?,
+            })
+        })?;
+
+        execution_iter
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(SqliteManagerError::DatabaseError)
+    }
+
     // Get all execution records
     pub fn get_all_cron_task_executions(
         &self,
@@ -254,6 +656,42 @@ impl SqliteManager {
         }
     }
 
+    /// Latest run that was started or given up on, runs after it and before now were missed
+    pub fn get_cron_task_last_scheduled_run(&self, task_id: i64) -> Result<Option<String>, SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let last_scheduled_run = conn
+            .query_row(
+                "SELECT last_scheduled_run FROM cron_tasks WHERE task_id = ?1",
+                params![task_id],
+                |row| row.get::<_, Option<String>>(0),
+            )
+            .optional()?;
+        Ok(last_scheduled_run.flatten())
+    }
+
+    /// Moves the last scheduled run forward, never back. `scheduled_run` has to be formatted like
+    /// the stored runs, RFC 3339 in UTC with milliseconds, so that they compare as text.
+    pub fn set_cron_task_last_scheduled_run(
+        &self,
+        task_id: i64,
+        scheduled_run: &str,
+    ) -> Result<(), SqliteManagerError> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            "UPDATE cron_tasks SET last_scheduled_run = ?1 
+             WHERE task_id = ?2 AND (last_scheduled_run IS NULL OR last_scheduled_run < ?1)",
+            params![scheduled_run, task_id],
+        )?;
+        Ok(())
+    }
+
+    fn json_column_from_row<T: DeserializeOwned>(row: &rusqlite::Row, index: usize) -> rusqlite::Result<Option<T>> {
+        row.get::<_, Option<String>>(index)?
+            .map(|value| serde_json::from_str(&value))
+            .transpose()
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, Box::new(e)))
+    }
+
     pub fn update_cron_task_last_executed(&self, task_id: i64, last_executed: &str) -> Result<(), SqliteManagerError> {
         let conn = self.get_connection()?;
         conn.execute(
@@ -268,6 +706,7 @@ impl SqliteManager {
 mod tests {
     use super::*;
     use shinkai_embedding::model_type::{EmbeddingModelType, OllamaTextEmbeddingsInference};
+    use shinkai_message_primitives::schemas::crontab::{CronMisfirePolicy, CronTaskEvent};
     use shinkai_message_primitives::{
         shinkai_message::shinkai_message_schemas::JobMessage, shinkai_utils::shinkai_path::ShinkaiPath
     };
@@ -306,7 +745,9 @@ mod tests {
         let description = Some("Test Description");
         let cron = "* * * * *";
 
-        let task_id = manager.add_cron_task(name, description, cron, &action).unwrap();
+        let task_id = manager
+            .add_cron_task(name, description, cron, None, &CronTaskPolicy::default(), None, &action)
+            .unwrap();
         let retrieved_task = manager.get_cron_task(task_id).unwrap().unwrap();
 
         assert_eq!(retrieved_task.name, name);
@@ -337,7 +778,9 @@ mod tests {
         let description = Some("Test Description");
         let cron = "* * * * *";
 
-        let task_id = manager.add_cron_task(name, description, cron, &action).unwrap();
+        let task_id = manager
+            .add_cron_task(name, description, cron, None, &CronTaskPolicy::default(), None, &action)
+            .unwrap();
         manager.remove_cron_task(task_id).unwrap();
         let retrieved_task = manager.get_cron_task(task_id).unwrap();
 
@@ -383,8 +826,28 @@ mod tests {
         let cron1 = "0 0 * * *";
         let cron2 = "0 12 * * *";
 
-        manager.add_cron_task(name1, description, cron1, &action1).unwrap();
-        manager.add_cron_task(name2, description, cron2, &action2).unwrap();
+        manager
+            .add_cron_task(
+                name1,
+                description,
+                cron1,
+                None,
+                &CronTaskPolicy::default(),
+                None,
+                &action1,
+            )
+            .unwrap();
+        manager
+            .add_cron_task(
+                name2,
+                description,
+                cron2,
+                None,
+                &CronTaskPolicy::default(),
+                None,
+                &action2,
+            )
+            .unwrap();
 
         let all_tasks = manager.get_all_cron_tasks().unwrap();
         assert_eq!(all_tasks.len(), 2);
@@ -416,7 +879,9 @@ mod tests {
         let description = Some("Initial Description");
         let cron = "* * * * *";
 
-        let task_id = manager.add_cron_task(name, description, cron, &action).unwrap();
+        let task_id = manager
+            .add_cron_task(name, description, cron, None, &CronTaskPolicy::default(), None, &action)
+            .unwrap();
 
         let updated_name = "Updated Task";
         let updated_description = Some("Updated Description");
@@ -444,6 +909,9 @@ mod tests {
                 updated_name,
                 updated_description,
                 updated_cron,
+                Some("Europe/Berlin"),
+                None,
+                None,
                 &updated_action,
                 updated_paused,
             )
@@ -455,6 +923,7 @@ mod tests {
         assert_eq!(updated_task.cron, updated_cron);
         assert_eq!(updated_task.action, updated_action);
         assert_eq!(updated_task.paused, updated_paused);
+        assert_eq!(updated_task.timezone.as_deref(), Some("Europe/Berlin"));
     }
 
     #[test]
@@ -479,7 +948,9 @@ mod tests {
         let description = Some("Test Description");
         let cron = "* * * * *";
 
-        let task_id = manager.add_cron_task(name, description, cron, &action).unwrap();
+        let task_id = manager
+            .add_cron_task(name, description, cron, None, &CronTaskPolicy::default(), None, &action)
+            .unwrap();
         let execution_time = chrono::Utc::now().to_rfc3339();
         let success = true;
         let error_message: Option<&str> = None;
@@ -519,7 +990,9 @@ mod tests {
         let description = Some("Test Description");
         let cron = "* * * * *";
 
-        let task_id = manager.add_cron_task(name, description, cron, &action).unwrap();
+        let task_id = manager
+            .add_cron_task(name, description, cron, None, &CronTaskPolicy::default(), None, &action)
+            .unwrap();
         let execution_time1 = chrono::Utc::now().to_rfc3339();
         let execution_time2 = chrono::Utc::now().to_rfc3339();
         let success = true;
@@ -561,7 +1034,9 @@ mod tests {
         let description = Some("Test Description");
         let cron = "* * * * *";
 
-        let task_id = manager.add_cron_task(name, description, cron, &action).unwrap();
+        let task_id = manager
+            .add_cron_task(name, description, cron, None, &CronTaskPolicy::default(), None, &action)
+            .unwrap();
         let execution_time1 = chrono::Utc::now().to_rfc3339();
         let execution_time2 = chrono::Utc::now().to_rfc3339();
         let success = true;
@@ -580,4 +1055,207 @@ mod tests {
         assert_eq!(task_executions[0].3, job_id.clone().map(|s| s.to_string()));
         assert_eq!(task_executions[1].3, job_id.clone().map(|s| s.to_string()));
     }
+
+    #[test]
+    fn test_cron_task_policy_and_execution_logs() {
+        let manager = setup_test_db();
+        let action = CronTaskAction::SendMessageToJob {
+            job_id: "test_job_id".to_string(),
+            message: JobMessage {
+                job_id: "test_job_id".to_string(),
+                content: "test_message".to_string(),
+                fs_files_paths: vec![],
+                job_filenames: vec![],
+                parent: None,
+                sheet_job_data: None,
+                callback: None,
+                metadata: None,
+                tool_key: None,
+                tools: None,
+            },
+        };
+        let policy = CronTaskPolicy {
+            misfire: CronMisfirePolicy::RunAll,
+            timeout_secs: Some(300),
+            ..Default::default()
+        };
+
+        let task_id = manager
+            .add_cron_task("Test Task", None, "* * * * *", None, &policy, None, &action)
+            .unwrap();
+        assert_eq!(manager.get_cron_task(task_id).unwrap().unwrap().policy, policy);
+        assert!(manager.get_cron_task_last_scheduled_run(task_id).unwrap().is_some());
+
+        // The last scheduled run only moves forward
+        manager
+            .set_cron_task_last_scheduled_run(task_id, "2999-01-01T00:00:00.000Z")
+            .unwrap();
+        manager
+            .set_cron_task_last_scheduled_run(task_id, "2024-01-01T00:00:00.000Z")
+            .unwrap();
+        assert_eq!(
+            manager.get_cron_task_last_scheduled_run(task_id).unwrap().as_deref(),
+            Some("2999-01-01T00:00:00.000Z")
+        );
+
+        let first = manager
+            .start_cron_task_execution(task_id, Some("2024-01-01T00:00:00.000Z"), 1, true)
+            .unwrap();
+        manager
+            .finish_cron_task_execution(first, CronExecutionStatus::TimedOut, Some("No answer"), Some("job_1"))
+            .unwrap();
+        let second = manager
+            .start_cron_task_execution(task_id, Some("2024-01-01T00:00:00.000Z"), 2, true)
+            .unwrap();
+
+        let logs = manager.get_cron_task_execution_logs(task_id).unwrap();
+        assert_eq!(logs.len(), 2);
+        assert_eq!(logs[0].execution_id, second);
+        assert_eq!(logs[0].status, CronExecutionStatus::Running);
+        assert_eq!(logs[0].finished_at, None);
+        assert_eq!(logs[1].attempt, 1);
+        assert!(logs[1].misfired);
+        assert_eq!(logs[1].status, CronExecutionStatus::TimedOut);
+        assert!(!logs[1].success);
+        assert_eq!(logs[1].job_id.as_deref(), Some("job_1"));
+
+        assert_eq!(manager.fail_running_cron_task_executions("Stopped").unwrap(), 1);
+        let logs = manager.get_cron_task_execution_logs(task_id).unwrap();
+        assert_eq!(logs[0].status, CronExecutionStatus::Failed);
+        assert_eq!(logs[0].error_message.as_deref(), Some("Stopped"));
+
+        // Keeps the policy when none is given
+        manager
+            .update_cron_task(task_id, "Renamed", None, "* * * * *", None, None, None, &action, false)
+            .unwrap();
+        assert_eq!(manager.get_cron_task(task_id).unwrap().unwrap().policy, policy);
+    }
+
+    #[test]
+    fn test_event_triggered_cron_tasks() {
+        let manager = setup_test_db();
+        let action = CronTaskAction::SendMessageToJob {
+            job_id: "test_job_id".to_string(),
+            message: JobMessage {
+                job_id: "test_job_id".to_string(),
+                content: "New file: {{event.path}}".to_string(),
+                fs_files_paths: vec![],
+                job_filenames: vec![],
+                parent: None,
+                sheet_job_data: None,
+                callback: None,
+                metadata: None,
+                tool_key: None,
+                tools: None,
+            },
+        };
+        let trigger = CronEventTrigger {
+            event: CronTaskEvent::FileChanged {
+                folder: "/reports".to_string(),
+            },
+            debounce_secs: 10,
+        };
+
+        // Nothing listens yet, so events are not queued
+        manager
+            .enqueue_webhook_event(WebhookEventType::FileChanged, &json!({ "path": "/reports/q1.pdf" }))
+            .unwrap();
+        assert!(manager.get_cron_task_events(0, 10).unwrap().is_empty());
+
+        let task_id = manager
+            .add_cron_task(
+                "On Report",
+                None,
+                "",
+                None,
+                &CronTaskPolicy::default(),
+                Some(&trigger),
+                &action,
+            )
+            .unwrap();
+        assert_eq!(
+            manager.get_cron_task(task_id).unwrap().unwrap().event_trigger,
+            Some(trigger.clone())
+        );
+        let tasks = manager
+            .get_event_triggered_cron_tasks(WebhookEventType::FileChanged)
+            .unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert!(manager
+            .get_event_triggered_cron_tasks(WebhookEventType::InboxMessage)
+            .unwrap()
+            .is_empty());
+
+        for path in ["/reports/q1.pdf", "/reports/q2.pdf"] {
+            manager
+                .enqueue_webhook_event(WebhookEventType::FileChanged, &json!({ "path": path }))
+                .unwrap();
+        }
+        manager
+            .enqueue_webhook_event(WebhookEventType::InboxMessage, &json!({ "inbox_name": "inbox" }))
+            .unwrap();
+        let events = manager.get_cron_task_events(0, 10).unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(
+            (events[1].1, events[1].2.clone()),
+            (WebhookEventType::FileChanged, json!({ "path": "/reports/q2.pdf" }))
+        );
+        assert!(manager.get_cron_task_events(events[1].0, 10).unwrap().is_empty());
+
+        // Events stay queued until they are deleted
+        manager.delete_cron_task_events(&[events[0].0]).unwrap();
+        let events = manager.get_cron_task_events(0, 10).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].2["path"], "/reports/q2.pdf");
+        manager.delete_cron_task_events(&[events[0].0]).unwrap();
+
+        // Events of the job a task sends its message to are tagged with the task
+        let job_completed_trigger = CronEventTrigger {
+            event: CronTaskEvent::JobCompleted {
+                agent_id: "agent".to_string(),
+            },
+            debounce_secs: 0,
+        };
+        let other_task_id = manager
+            .add_cron_task(
+                "On Job",
+                None,
+                "",
+                None,
+                &CronTaskPolicy::default(),
+                Some(&job_completed_trigger),
+                &action,
+            )
+            .unwrap();
+        for job_id in ["test_job_id", "other_job_id"] {
+            manager
+                .enqueue_webhook_event(
+                    WebhookEventType::JobCompleted,
+                    &json!({ "job_id": job_id, "agent_id": "agent" }),
+                )
+                .unwrap();
+        }
+        let events = manager.get_cron_task_events(0, 10).unwrap();
+        assert_eq!(events[0].2["cron_task_ids"], json!([task_id, other_task_id]));
+        assert!(events[1].2.get("cron_task_ids").is_none());
+
+        // Tasks put back on a schedule no longer listen
+        manager
+            .update_cron_task(
+                task_id,
+                "On Report",
+                None,
+                "* * * * *",
+                None,
+                None,
+                None,
+                &action,
+                false,
+            )
+            .unwrap();
+        assert!(manager
+            .get_event_triggered_cron_tasks(WebhookEventType::FileChanged)
+            .unwrap()
+            .is_empty());
+    }
 }
@@ -1,3 +1,4 @@
+use shinkai_message_primitives::shinkai_utils::secret_vault::SecretVaultError;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -90,6 +91,12 @@ pub enum SqliteManagerError {
     ValidationError(String),
     #[error("Tool type mismatch")]
     ToolTypeMismatch,
+    #[error("Tool revision not found: {0}")]
+    ToolRevisionNotFound(String),
+    #[error("Secret vault error: {0}")]
+    SecretVaultError(String),
+    #[error("Secret vault is locked, set a passphrase or keyring key to read secrets")]
+    SecretVaultLocked,
     // Add other error variants as needed
 }
 
@@ -99,6 +106,12 @@ impl From<&str> for SqliteManagerError {
     }
 }
 
+impl From<SecretVaultError> for SqliteManagerError {
+    fn from(err: SecretVaultError) -> SqliteManagerError {
+        SqliteManagerError::SecretVaultError(err.to_string())
+    }
+}
+
 impl From<chrono::ParseError> for SqliteManagerError {
     fn from(err: chrono::ParseError) -> SqliteManagerError {
         SqliteManagerError::ChronoParseError(err)
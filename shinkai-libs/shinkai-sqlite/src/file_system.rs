@@ -1,7 +1,11 @@
 use crate::{SqliteManager, SqliteManagerError};
 use rusqlite::params;
+use serde_json::json;
 use shinkai_message_primitives::{
-    schemas::shinkai_fs::{ParsedFile, ShinkaiFileChunk},
+    schemas::{
+        shinkai_fs::{ParsedFile, ShinkaiFileChunk},
+        webhook::WebhookEventType,
+    },
     shinkai_utils::shinkai_path::ShinkaiPath,
 };
 
@@ -108,6 +112,12 @@ impl SqliteManager {
             ],
         )?;
 
+        Self::enqueue_webhook_event_tx(
+            &tx,
+            WebhookEventType::FileChanged,
+            &json!({ "path": relative_path, "change": "added" }),
+        )?;
+
         tx.commit()?;
         Ok(())
     }
@@ -185,6 +195,12 @@ impl SqliteManager {
             ],
         )?;
 
+        Self::enqueue_webhook_event_tx(
+            &tx,
+            WebhookEventType::FileChanged,
+            &json!({ "path": relative_path, "change": "updated" }),
+        )?;
+
         tx.commit()?;
         Ok(())
     }
@@ -2,10 +2,10 @@ use std::{collections::HashMap, str::FromStr, sync::Arc};
 
 use chrono::{DateTime, Utc};
 use rusqlite::params;
-use serde_json::Value;
+use serde_json::{json, Value};
 use shinkai_message_primitives::{
     schemas::{
-        identity::StandardIdentity, inbox_name::InboxName, inbox_permission::InboxPermission, job_config::JobConfig, shinkai_name::ShinkaiName, smart_inbox::{LLMProviderSubset, ProviderType, SmartInbox}, ws_types::{WSMessageType, WSUpdateHandler}
+        identity::StandardIdentity, inbox_name::InboxName, inbox_permission::InboxPermission, job_config::JobConfig, shinkai_name::ShinkaiName, smart_inbox::{LLMProviderSubset, ProviderType, SmartInbox}, webhook::WebhookEventType, ws_types::{WSMessageType, WSUpdateHandler}
     }, shinkai_message::{
         shinkai_message::{NodeApiData, ShinkaiMessage}, shinkai_message_schemas::WSTopic
     }, shinkai_utils::shinkai_time::ShinkaiStringTime
@@ -135,6 +135,22 @@ impl SqliteManager {
                 params![time_key, inbox_name],
             )?;
 
+            Self::index_inbox_message_tx(&tx, message, &hash_key, &inbox_name, &time_key)?;
+
+            // Queued with the message so webhooks only hear about messages that were stored
+            Self::enqueue_webhook_event_tx(
+                &tx,
+                WebhookEventType::InboxMessage,
+                &json!({
+                    "inbox_name": inbox_name,
+                    "message_hash": hash_key,
+                    "parent_message_hash": parent_key,
+                    "sender": message.external_metadata.sender,
+                    "content": message.get_message_content().ok(),
+                    "time_key": time_key,
+                }),
+            )?;
+
             // Commit the transaction
             tx.commit()?;
         }
@@ -0,0 +1,557 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use bytemuck::cast_slice;
+use chrono::SecondsFormat;
+use rusqlite::{params, ToSql, Transaction};
+use serde_json::Value;
+use shinkai_message_primitives::schemas::inbox_search::{
+    InboxMessageSearchHit, InboxMessageSearchRequest, InboxMessageSearchResults, INBOX_SEARCH_HIGHLIGHT_END,
+    INBOX_SEARCH_HIGHLIGHT_START,
+};
+use shinkai_message_primitives::shinkai_message::shinkai_message::ShinkaiMessage;
+use shinkai_message_primitives::shinkai_message::shinkai_message_schemas::MessageSchemaType;
+
+use crate::{SqliteManager, SqliteManagerError};
+
+/// Characters of a message sent to the embedding model
+const INBOX_EMBEDDING_MAX_CHARS: usize = 2000;
+/// Length of the snippet of a semantic match without matching terms
+const INBOX_SEARCH_SNIPPET_CHARS: usize = 200;
+/// Nearest neighbours fetched before filters are applied, per requested result
+const INBOX_VECTOR_CANDIDATES_PER_RESULT: u64 = 5;
+const INBOX_VECTOR_MIN_CANDIDATES: u64 = 200;
+/// Upper bound of `k` in sqlite-vec
+const INBOX_VECTOR_MAX_CANDIDATES: u64 = 4096;
+
+/// Set while a batch of embeddings is computed so overlapping polls skip instead of racing
+static EMBEDDING_IN_PROGRESS: AtomicBool = AtomicBool::new(false);
+
+/// Text of a message that is indexed, the prompt or answer for job messages. Encrypted messages
+/// have none.
+fn searchable_text(message: &ShinkaiMessage) -> String {
+    let Ok(content) = message.get_message_content() else {
+        return String::new();
+    };
+    if let Ok(MessageSchemaType::JobMessageSchema) = message.get_message_content_schema() {
+        if let Ok(Value::Object(job_message)) = serde_json::from_str::<Value>(&content) {
+            if let Some(Value::String(text)) = job_message.get("content") {
+                return text.clone();
+            }
+        }
+    }
+    content
+}
+
+fn full_sender(message: &ShinkaiMessage) -> String {
+    match message
+        .get_sender_subidentity()
+        .filter(|subidentity| !subidentity.is_empty())
+    {
+        Some(subidentity) => format!("{}/{}", message.external_metadata.sender, subidentity),
+        None => message.external_metadata.sender.clone(),
+    }
+}
+
+/// Terms of a free text query as an FTS5 expression matching all of them. Each term is quoted so
+/// operators and punctuation typed by the user are searched for literally.
+fn fts_match_query(query: &str) -> Option<String> {
+    let terms: Vec<String> = query
+        .split_whitespace()
+        .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+        .collect();
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+fn plain_snippet(content: &str) -> String {
+    let mut snippet: String = content.chars().take(INBOX_SEARCH_SNIPPET_CHARS).collect();
+    if snippet.len() < content.len() {
+        snippet.push('…');
+    }
+    snippet
+}
+
+impl SqliteManager {
+    /// Indexes a message for search inside the transaction that stores it
+    pub(crate) fn index_inbox_message_tx(
+        tx: &Transaction,
+        message: &ShinkaiMessage,
+        message_hash: &str,
+        inbox_name: &str,
+        time_key: &str,
+    ) -> Result<(), SqliteManagerError> {
+        // Explicit delete so the FTS triggers see a replaced message
+        tx.execute(
+            "DELETE FROM inbox_message_search WHERE message_hash = ?1",
+            params![message_hash],
+        )?;
+        tx.execute(
+            "INSERT INTO inbox_message_search (message_hash, inbox_name, sender, time_key, content)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                message_hash,
+                inbox_name,
+                full_sender(message),
+                time_key,
+                searchable_text(message)
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Indexes the messages stored before the search index existed or restored from a backup
+    /// without it, and drops embeddings of messages that are gone. Returns how many messages were
+    /// indexed.
+    pub fn sync_inbox_message_search(&self) -> Result<u64, SqliteManagerError> {
+        let mut conn = self.get_connection()?;
+        let tx = conn.transaction()?;
+
+        tx.execute(
+            "DELETE FROM inbox_message_search WHERE message_hash NOT IN (SELECT message_hash FROM inbox_messages)",
+            [],
+        )?;
+        tx.execute(
+            "DELETE FROM inbox_message_vec_items WHERE rowid NOT IN (SELECT id FROM inbox_message_search)",
+            [],
+        )?;
+
+        let missing = tx
+            .prepare(
+                "SELECT message_hash, inbox_name, shinkai_message, time_key FROM inbox_messages
+                 WHERE message_hash NOT IN (SELECT message_hash FROM inbox_message_search)",
+            )?
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Vec<u8>>(2)?,
+                    row.get::<_, String>(3)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut indexed = 0;
+        for (message_hash, inbox_name, encoded_message, time_key) in missing {
+            let message = ShinkaiMessage::decode_message_result(encoded_message)
+                .map_err(|e| SqliteManagerError::SomeError(e.to_string()))?;
+            Self::index_inbox_message_tx(&tx, &message, &message_hash, &inbox_name, &time_key)?;
+            indexed += 1;
+        }
+
+        tx.commit()?;
+        Ok(indexed)
+    }
+
+    /// Messages ranked by how well their content matches the terms of the query
+    pub fn search_inbox_messages(
+        &self,
+        request: &InboxMessageSearchRequest,
+    ) -> Result<InboxMessageSearchResults, SqliteManagerError> {
+        let Some(match_query) = fts_match_query(&request.query) else {
+            return Ok(InboxMessageSearchResults {
+                hits: Vec::new(),
+                has_more: false,
+            });
+        };
+
+        let mut values: Vec<Box<dyn ToSql>> = vec![
+            Box::new(INBOX_SEARCH_HIGHLIGHT_START),
+            Box::new(INBOX_SEARCH_HIGHLIGHT_END),
+            Box::new(match_query),
+        ];
+        let conditions = Self::inbox_search_conditions(request, &mut values);
+        let limit = request.limit();
+        values.push(Box::new((limit + 1) as i64));
+        values.push(Box::new(request.offset() as i64));
+
+        let query = format!(
+            "SELECT s.message_hash, s.inbox_name, j.parent_agent_or_llm_provider_id, s.sender, s.time_key,
+                    snippet(inbox_message_search_fts, 0, ?1, ?2, '…', 24), bm25(inbox_message_search_fts)
+             FROM inbox_message_search_fts
+             JOIN inbox_message_search s ON s.id = inbox_message_search_fts.rowid
+             LEFT JOIN jobs j ON j.conversation_inbox_name = s.inbox_name
+             WHERE inbox_message_search_fts MATCH ?3{}
+             ORDER BY bm25(inbox_message_search_fts)
+             LIMIT ? OFFSET ?",
+            conditions
+        );
+
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(&query)?;
+        let mut hits = stmt
+            .query_map(
+                rusqlite::params_from_iter(values.iter().map(|value| value.as_ref())),
+                |row| {
+                    Ok(InboxMessageSearchHit {
+                        message_hash: row.get(0)?,
+                        inbox_name: row.get(1)?,
+                        agent_id: row.get(2)?,
+                        sender: row.get(3)?,
+                        time_key: row.get(4)?,
+                        snippet: row.get(5)?,
+                        // bm25 is lower for better matches
+                        score: -row.get::<_, f64>(6)?,
+                    })
+                },
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let has_more = hits.len() as u64 > limit;
+        hits.truncate(limit as usize);
+        Ok(InboxMessageSearchResults { hits, has_more })
+    }
+
+    /// Messages ranked by how close their embedding is to `vector`, the embedding of the query
+    pub fn search_inbox_messages_by_vector(
+        &self,
+        request: &InboxMessageSearchRequest,
+        vector: Vec<f32>,
+    ) -> Result<InboxMessageSearchResults, SqliteManagerError> {
+        let limit = request.limit();
+        // Filters are applied to the nearest neighbours, so more of them are fetched than returned
+        let candidates = ((request.offset() + limit + 1) * INBOX_VECTOR_CANDIDATES_PER_RESULT)
+            .clamp(INBOX_VECTOR_MIN_CANDIDATES, INBOX_VECTOR_MAX_CANDIDATES);
+
+        let mut values: Vec<Box<dyn ToSql>> =
+            vec![Box::new(serde_json::to_string(&vector)?), Box::new(candidates as i64)];
+        let conditions = Self::inbox_search_conditions(request, &mut values);
+        values.push(Box::new((limit + 1) as i64));
+        values.push(Box::new(request.offset() as i64));
+
+        let query = format!(
+            "WITH nearest AS (
+                SELECT rowid, distance FROM inbox_message_vec_items
+                WHERE embedding MATCH json(?1)
+                ORDER BY distance
+                LIMIT ?2
+             )
+             SELECT s.id, s.message_hash, s.inbox_name, j.parent_agent_or_llm_provider_id, s.sender, s.time_key,
+                    s.content, nearest.distance
+             FROM nearest
+             JOIN inbox_message_search s ON s.id = nearest.rowid
+             LEFT JOIN jobs j ON j.conversation_inbox_name = s.inbox_name
+             WHERE 1 = 1{}
+             ORDER BY nearest.distance
+             LIMIT ? OFFSET ?",
+            conditions
+        );
+
+        let conn = self.get_connection()?;
+        let rows = conn
+            .prepare(&query)?
+            .query_map(
+                rusqlite::params_from_iter(values.iter().map(|value| value.as_ref())),
+                |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        InboxMessageSearchHit {
+                            message_hash: row.get(1)?,
+                            inbox_name: row.get(2)?,
+                            agent_id: row.get(3)?,
+                            sender: row.get(4)?,
+                            time_key: row.get(5)?,
+                            snippet: row.get(6)?,
+                            score: 1.0 / (1.0 + row.get::<_, f64>(7)?),
+                        },
+                    ))
+                },
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Highlight the query terms when the message contains them
+        let match_query = fts_match_query(&request.query);
+        let mut snippet_stmt = conn.prepare(
+            "SELECT snippet(inbox_message_search_fts, 0, ?1, ?2, '…', 24) FROM inbox_message_search_fts
+             WHERE inbox_message_search_fts MATCH ?3 AND rowid = ?4",
+        )?;
+        let mut hits = Vec::with_capacity(rows.len());
+        for (id, mut hit) in rows {
+            let highlighted = match &match_query {
+                Some(match_query) => snippet_stmt
+                    .query_row(
+                        params![
+                            INBOX_SEARCH_HIGHLIGHT_START,
+                            INBOX_SEARCH_HIGHLIGHT_END,
+                            match_query,
+                            id
+                        ],
+                        |row| row.get::<_, String>(0),
+                    )
+                    .ok(),
+                None => None,
+            };
+            hit.snippet = highlighted.unwrap_or_else(|| plain_snippet(&hit.snippet));
+            hits.push(hit);
+        }
+
+        let has_more = hits.len() as u64 > limit;
+        hits.truncate(limit as usize);
+        Ok(InboxMessageSearchResults { hits, has_more })
+    }
+
+    /// Embeds the query and runs `search_inbox_messages_by_vector`
+    pub async fn search_inbox_messages_semantic(
+        &self,
+        request: &InboxMessageSearchRequest,
+    ) -> Result<InboxMessageSearchResults, SqliteManagerError> {
+        if request.query.trim().is_empty() {
+            return Ok(InboxMessageSearchResults {
+                hits: Vec::new(),
+                has_more: false,
+            });
+        }
+        let vector = self.generate_embeddings(&request.query).await?;
+        self.search_inbox_messages_by_vector(request, vector)
+    }
+
+    pub fn add_inbox_message_embedding(&self, search_id: i64, vector: Vec<f32>) -> Result<(), SqliteManagerError> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            "INSERT INTO inbox_message_vec_items (rowid, embedding) VALUES (?1, ?2)",
+            params![search_id, cast_slice(&vector)],
+        )?;
+        Ok(())
+    }
+
+    /// Computes the embeddings of up to `limit` indexed messages that have none yet, newest first.
+    /// Returns how many were added. Messages whose embedding fails are tried again on the next
+    /// call.
+    pub async fn embed_pending_inbox_messages(&self, limit: u64) -> Result<u64, SqliteManagerError> {
+        if EMBEDDING_IN_PROGRESS.swap(true, Ordering::AcqRel) {
+            return Ok(0);
+        }
+        let result = self.embed_pending_inbox_messages_batch(limit).await;
+        EMBEDDING_IN_PROGRESS.store(false, Ordering::Release);
+        result
+    }
+
+    async fn embed_pending_inbox_messages_batch(&self, limit: u64) -> Result<u64, SqliteManagerError> {
+        let pending = {
+            let conn = self.get_connection()?;
+            let mut stmt = conn.prepare(
+                "SELECT s.id, s.content FROM inbox_message_search s
+                 WHERE s.content != ''
+                 AND NOT EXISTS (SELECT 1 FROM inbox_message_vec_items v WHERE v.rowid = s.id)
+                 ORDER BY s.id DESC
+                 LIMIT ?1",
+            )?;
+            let pending = stmt
+                .query_map(params![limit as i64], |row| {
+                    Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+                })?
+                .collect::<Result<Vec<_>, _>>()?;
+            pending
+        };
+
+        let mut embedded = 0;
+        let mut last_error = None;
+        for (search_id, content) in pending {
+            let text: String = content.chars().take(INBOX_EMBEDDING_MAX_CHARS).collect();
+            match self.generate_embeddings(&text).await {
+                Ok(vector) => {
+                    self.add_inbox_message_embedding(search_id, vector)?;
+                    embedded += 1;
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+
+        match last_error {
+            // Nothing went through, most likely the embedding service is unavailable
+            Some(e) if embedded == 0 => Err(e),
+            _ => Ok(embedded),
+        }
+    }
+
+    fn inbox_search_conditions(request: &InboxMessageSearchRequest, values: &mut Vec<Box<dyn ToSql>>) -> String {
+        let mut conditions = String::new();
+        if let Some(inbox_name) = &request.inbox_name {
+            conditions.push_str(" AND s.inbox_name = ?");
+            values.push(Box::new(inbox_name.clone()));
+        }
+        if let Some(agent_id) = &request.agent_id {
+            conditions.push_str(" AND j.parent_agent_or_llm_provider_id = ?");
+            values.push(Box::new(agent_id.clone()));
+        }
+        if let Some(sender) = &request.sender {
+            conditions.push_str(" AND (s.sender = ? OR s.sender LIKE ? ESCAPE '\\')");
+            values.push(Box::new(sender.clone()));
+            let escaped = sender.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+            values.push(Box::new(format!("{}/%", escaped)));
+        }
+        // Time keys are RFC 3339 with milliseconds, so they compare as text
+        if let Some(from) = request.from {
+            conditions.push_str(" AND s.time_key >= ?");
+            values.push(Box::new(from.to_rfc3339_opts(SecondsFormat::Millis, true)));
+        }
+        if let Some(to) = request.to {
+            conditions.push_str(" AND s.time_key <= ?");
+            values.push(Box::new(to.to_rfc3339_opts(SecondsFormat::Millis, true)));
+        }
+        conditions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shinkai_embedding::model_type::{EmbeddingModelType, OllamaTextEmbeddingsInference};
+    use shinkai_message_primitives::schemas::inbox_name::InboxName;
+    use shinkai_message_primitives::shinkai_utils::shinkai_message_builder::ShinkaiMessageBuilder;
+    use shinkai_message_primitives::shinkai_utils::signatures::unsafe_deterministic_signature_keypair;
+    use std::path::PathBuf;
+    use tempfile::NamedTempFile;
+
+    fn setup_test_db() -> SqliteManager {
+        let temp_file = NamedTempFile::new().unwrap();
+        let db_path = PathBuf::from(temp_file.path());
+        let api_url = String::new();
+        let model_type =
+            EmbeddingModelType::OllamaTextEmbeddingsInference(OllamaTextEmbeddingsInference::SnowflakeArcticEmbedM);
+
+        SqliteManager::new(db_path, api_url, model_type).unwrap()
+    }
+
+    fn job_message(job_id: &str, text: &str, time: &str) -> ShinkaiMessage {
+        let (my_identity_sk, _) = unsafe_deterministic_signature_keypair(0);
+        let node_name = "@@node1.shinkai";
+        let mut message = ShinkaiMessageBuilder::job_message_unencrypted(
+            job_id.to_string(),
+            text.to_string(),
+            vec![],
+            "".to_string(),
+            my_identity_sk,
+            node_name.to_string(),
+            "main".to_string(),
+            node_name.to_string(),
+            "main/agent/my_agent".to_string(),
+        )
+        .unwrap();
+        message.external_metadata.scheduled_time = time.to_string();
+        message
+    }
+
+    #[tokio::test]
+    async fn test_search_inbox_messages() {
+        let db = setup_test_db();
+        let messages = [
+            (
+                "job_a",
+                "The quarterly invoice for ACME is overdue",
+                "2024-01-01T10:00:00.000Z",
+            ),
+            ("job_a", "Remind me to call the dentist", "2024-01-02T10:00:00.000Z"),
+            ("job_b", "Send the invoice reminder again", "2024-02-01T10:00:00.000Z"),
+        ];
+        for (job_id, text, time) in messages {
+            db.unsafe_insert_inbox_message(&job_message(job_id, text, time), None, None)
+                .await
+                .unwrap();
+        }
+
+        let request = InboxMessageSearchRequest {
+            query: "invoice".to_string(),
+            ..Default::default()
+        };
+        let results = db.search_inbox_messages(&request).unwrap();
+        assert_eq!(results.hits.len(), 2);
+        assert!(!results.has_more);
+        assert!(results.hits[0].snippet.contains("<mark>invoice</mark>"));
+        assert_eq!(results.hits[0].sender, "@@node1.shinkai/main");
+
+        // Filters and paging
+        let job_b_inbox = InboxName::get_job_inbox_name_from_params("job_b".to_string())
+            .unwrap()
+            .to_string();
+        let filtered = db
+            .search_inbox_messages(&InboxMessageSearchRequest {
+                inbox_name: Some(job_b_inbox.clone()),
+                ..request.clone()
+            })
+            .unwrap();
+        assert_eq!(filtered.hits.len(), 1);
+        assert_eq!(filtered.hits[0].inbox_name, job_b_inbox);
+        let before_february = db
+            .search_inbox_messages(&InboxMessageSearchRequest {
+                to: Some("2024-01-31T00:00:00Z".parse().unwrap()),
+                ..request.clone()
+            })
+            .unwrap();
+        assert_eq!(before_february.hits.len(), 1);
+        let first_page = db
+            .search_inbox_messages(&InboxMessageSearchRequest {
+                limit: Some(1),
+                ..request.clone()
+            })
+            .unwrap();
+        assert_eq!(first_page.hits.len(), 1);
+        assert!(first_page.has_more);
+        assert_eq!(
+            db.search_inbox_messages(&InboxMessageSearchRequest {
+                sender: Some("@@node1.shinkai".to_string()),
+                ..request.clone()
+            })
+            .unwrap()
+            .hits
+            .len(),
+            2
+        );
+
+        // Operators are searched for as terms, no message contains "or"
+        let quoted = db
+            .search_inbox_messages(&InboxMessageSearchRequest {
+                query: "dentist OR invoice".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(quoted.hits.is_empty());
+
+        // Semantic search over stored embeddings, highlighting terms when they appear
+        let pending: Vec<i64> = db
+            .get_connection()
+            .unwrap()
+            .prepare("SELECT id FROM inbox_message_search ORDER BY id")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        for (index, search_id) in pending.iter().enumerate() {
+            db.add_inbox_message_embedding(
+                *search_id,
+                SqliteManager::generate_vector_for_testing(index as f32 * 0.1),
+            )
+            .unwrap();
+        }
+        let semantic = db
+            .search_inbox_messages_by_vector(
+                &InboxMessageSearchRequest {
+                    query: "dentist".to_string(),
+                    semantic: true,
+                    ..Default::default()
+                },
+                SqliteManager::generate_vector_for_testing(0.1),
+            )
+            .unwrap();
+        assert_eq!(semantic.hits.len(), 3);
+        assert_eq!(semantic.hits[0].snippet, "Remind me to call the <mark>dentist</mark>");
+        assert!(!semantic.hits[1].snippet.contains("<mark>"));
+
+        // Removing the job drops its messages from the index
+        db.clear_inbox_messages(&job_b_inbox).unwrap();
+        assert_eq!(db.search_inbox_messages(&request).unwrap().hits.len(), 1);
+        assert_eq!(db.sync_inbox_message_search().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_fts_match_query_quotes_terms() {
+        assert_eq!(fts_match_query("  "), None);
+        assert_eq!(
+            fts_match_query("invoice \"ACME\" OR"),
+            Some("\"invoice\" \"\"\"ACME\"\"\" \"OR\"".to_string())
+        );
+    }
+}
@@ -1,7 +1,9 @@
 use rusqlite::params;
+use serde_json::json;
 use shinkai_message_primitives::schemas::{
     invoices::{Invoice, InvoiceRequestNetworkError},
     shinkai_name::ShinkaiName,
+    webhook::WebhookEventType,
 };
 
 use crate::{SqliteManager, SqliteManagerError};
@@ -64,6 +66,17 @@ impl SqliteManager {
             invoice.result_str,
         ])?;
 
+        self.enqueue_webhook_event(
+            WebhookEventType::InvoiceUpdated,
+            &json!({
+                "invoice_id": invoice.invoice_id,
+                "status": invoice.status,
+                "provider_name": invoice.provider_name.full_name,
+                "requester_name": invoice.requester_name.full_name,
+                "tool_key": invoice.shinkai_offering.tool_key,
+            }),
+        )?;
+
         Ok(())
     }
 
@@ -0,0 +1,122 @@
+use crate::{SqliteManager, SqliteManagerError};
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::{params, OptionalExtension, Result};
+use shinkai_message_primitives::schemas::job_checkpoint::JobCheckpoint;
+
+const JOB_CHECKPOINT_COLUMNS: &str = "job_id, message_hash_id, iteration, state, updated_at";
+
+impl SqliteManager {
+    /// Saves the progress of the message a job is answering, replacing the previous checkpoint
+    pub fn save_job_checkpoint(&self, checkpoint: &JobCheckpoint) -> Result<(), SqliteManagerError> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            "INSERT OR REPLACE INTO job_checkpoints (job_id, message_hash_id, iteration, state, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                checkpoint.job_id,
+                checkpoint.message_hash_id,
+                checkpoint.iteration as i64,
+                serde_json::to_string(&checkpoint.state)?,
+                checkpoint.updated_at.to_rfc3339_opts(SecondsFormat::Nanos, true),
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_job_checkpoint(&self, job_id: &str) -> Result<Option<JobCheckpoint>, SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let checkpoint = conn
+            .query_row(
+                &format!(
+                    "SELECT {} FROM job_checkpoints WHERE job_id = ?1",
+                    JOB_CHECKPOINT_COLUMNS
+                ),
+                params![job_id],
+                Self::row_to_job_checkpoint,
+            )
+            .optional()?;
+        Ok(checkpoint)
+    }
+
+    /// Checkpoints left by messages that were being answered, oldest first
+    pub fn get_all_job_checkpoints(&self) -> Result<Vec<JobCheckpoint>, SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM job_checkpoints ORDER BY updated_at ASC",
+            JOB_CHECKPOINT_COLUMNS
+        ))?;
+        let checkpoints = stmt
+            .query_map([], Self::row_to_job_checkpoint)?
+            .collect::<Result<Vec<JobCheckpoint>, _>>()?;
+        Ok(checkpoints)
+    }
+
+    /// Removes the checkpoint of a job, if it has one
+    pub fn remove_job_checkpoint(&self, job_id: &str) -> Result<(), SqliteManagerError> {
+        let conn = self.get_connection()?;
+        conn.execute("DELETE FROM job_checkpoints WHERE job_id = ?1", params![job_id])?;
+        Ok(())
+    }
+
+    fn row_to_job_checkpoint(row: &rusqlite::Row) -> rusqlite::Result<JobCheckpoint> {
+        let state: String = row.get(3)?;
+        let updated_at: String = row.get(4)?;
+        Ok(JobCheckpoint {
+            job_id: row.get(0)?,
+            message_hash_id: row.get(1)?,
+            iteration: row.get::<_, i64>(2)? as u64,
+            state: serde_json::from_str(&state)
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(3, rusqlite::types::Type::Text, Box::new(e)))?,
+            updated_at: DateTime::parse_from_rfc3339(&updated_at)
+                .map(|time| time.with_timezone(&Utc))
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(4, rusqlite::types::Type::Text, Box::new(e)))?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use shinkai_embedding::model_type::{EmbeddingModelType, OllamaTextEmbeddingsInference};
+    use std::path::PathBuf;
+    use tempfile::NamedTempFile;
+
+    fn setup_test_db() -> SqliteManager {
+        let temp_file = NamedTempFile::new().unwrap();
+        let db_path = PathBuf::from(temp_file.path());
+        let api_url = String::new();
+        let model_type =
+            EmbeddingModelType::OllamaTextEmbeddingsInference(OllamaTextEmbeddingsInference::SnowflakeArcticEmbedM);
+
+        SqliteManager::new(db_path, api_url, model_type).unwrap()
+    }
+
+    #[test]
+    fn test_job_checkpoints() {
+        let db = setup_test_db();
+        let checkpoint = JobCheckpoint {
+            job_id: "jobid_1".to_string(),
+            message_hash_id: Some("hash_1".to_string()),
+            iteration: 0,
+            state: json!({ "pending_function_calls": [] }),
+            updated_at: Utc::now(),
+        };
+        db.save_job_checkpoint(&checkpoint).unwrap();
+
+        // A job keeps only its latest checkpoint
+        let checkpoint = JobCheckpoint {
+            iteration: 2,
+            state: json!({ "pending_function_calls": [{ "name": "search" }] }),
+            updated_at: Utc::now(),
+            ..checkpoint
+        };
+        db.save_job_checkpoint(&checkpoint).unwrap();
+        assert_eq!(db.get_job_checkpoint("jobid_1").unwrap(), Some(checkpoint.clone()));
+        assert_eq!(db.get_all_job_checkpoints().unwrap(), vec![checkpoint]);
+
+        db.remove_job_checkpoint("jobid_1").unwrap();
+        assert_eq!(db.get_job_checkpoint("jobid_1").unwrap(), None);
+        db.remove_job_checkpoint("jobid_1").unwrap();
+    }
+}
@@ -0,0 +1,123 @@
+use crate::{SqliteManager, SqliteManagerError};
+use rusqlite::{params, Result};
+use serde_json::Value;
+use shinkai_message_primitives::schemas::job_concurrency_limit::{JobConcurrencyLimit, JobLimitTarget};
+
+const JOB_CONCURRENCY_LIMIT_COLUMNS: &str = "target, target_id, max_concurrent, requests_per_minute, tokens_per_minute";
+
+impl SqliteManager {
+    /// Adds the limit of a provider or an agent, replacing the one it had
+    pub fn set_job_concurrency_limit(&self, limit: &JobConcurrencyLimit) -> Result<(), SqliteManagerError> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            "INSERT OR REPLACE INTO job_concurrency_limits (target, target_id, max_concurrent, requests_per_minute, tokens_per_minute)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                limit.target.to_string(),
+                limit.target_id,
+                limit.max_concurrent,
+                limit.requests_per_minute,
+                limit.tokens_per_minute.map(|tokens| tokens as i64),
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn remove_job_concurrency_limit(
+        &self,
+        target: JobLimitTarget,
+        target_id: &str,
+    ) -> Result<(), SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let removed = conn.execute(
+            "DELETE FROM job_concurrency_limits WHERE target = ?1 AND target_id = ?2",
+            params![target.to_string(), target_id],
+        )?;
+        if removed == 0 {
+            return Err(SqliteManagerError::DataNotFound);
+        }
+        Ok(())
+    }
+
+    pub fn get_all_job_concurrency_limits(&self) -> Result<Vec<JobConcurrencyLimit>, SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM job_concurrency_limits ORDER BY target ASC, target_id ASC",
+            JOB_CONCURRENCY_LIMIT_COLUMNS
+        ))?;
+        let limits = stmt
+            .query_map([], Self::row_to_job_concurrency_limit)?
+            .collect::<Result<Vec<JobConcurrencyLimit>, _>>()?;
+        Ok(limits)
+    }
+
+    fn row_to_job_concurrency_limit(row: &rusqlite::Row) -> rusqlite::Result<JobConcurrencyLimit> {
+        let target: String = row.get(0)?;
+        Ok(JobConcurrencyLimit {
+            target: serde_json::from_value(Value::String(target))
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e)))?,
+            target_id: row.get(1)?,
+            max_concurrent: row.get(2)?,
+            requests_per_minute: row.get(3)?,
+            tokens_per_minute: row.get::<_, Option<i64>>(4)?.map(|tokens| tokens as u64),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shinkai_embedding::model_type::{EmbeddingModelType, OllamaTextEmbeddingsInference};
+    use std::path::PathBuf;
+    use tempfile::NamedTempFile;
+
+    fn setup_test_db() -> SqliteManager {
+        let temp_file = NamedTempFile::new().unwrap();
+        let db_path = PathBuf::from(temp_file.path());
+        let api_url = String::new();
+        let model_type =
+            EmbeddingModelType::OllamaTextEmbeddingsInference(OllamaTextEmbeddingsInference::SnowflakeArcticEmbedM);
+
+        SqliteManager::new(db_path, api_url, model_type).unwrap()
+    }
+
+    #[test]
+    fn test_job_concurrency_limits() {
+        let db = setup_test_db();
+        let provider_limit = JobConcurrencyLimit {
+            target: JobLimitTarget::LlmProvider,
+            target_id: "ollama".to_string(),
+            max_concurrent: Some(2),
+            requests_per_minute: Some(60),
+            tokens_per_minute: Some(100_000),
+        };
+        let agent_limit = JobConcurrencyLimit {
+            target: JobLimitTarget::Agent,
+            target_id: "ollama".to_string(),
+            max_concurrent: Some(1),
+            requests_per_minute: None,
+            tokens_per_minute: None,
+        };
+        db.set_job_concurrency_limit(&provider_limit).unwrap();
+        db.set_job_concurrency_limit(&agent_limit).unwrap();
+
+        // A target has one limit, replaced when set again
+        let provider_limit = JobConcurrencyLimit {
+            max_concurrent: Some(4),
+            ..provider_limit
+        };
+        db.set_job_concurrency_limit(&provider_limit).unwrap();
+        assert_eq!(
+            db.get_all_job_concurrency_limits().unwrap(),
+            vec![agent_limit, provider_limit.clone()]
+        );
+
+        db.remove_job_concurrency_limit(JobLimitTarget::Agent, "ollama")
+            .unwrap();
+        assert!(matches!(
+            db.remove_job_concurrency_limit(JobLimitTarget::Agent, "ollama"),
+            Err(SqliteManagerError::DataNotFound)
+        ));
+        assert_eq!(db.get_all_job_concurrency_limits().unwrap(), vec![provider_limit]);
+    }
+}
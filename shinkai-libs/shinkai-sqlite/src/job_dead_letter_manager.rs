@@ -0,0 +1,147 @@
+use crate::{SqliteManager, SqliteManagerError};
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::{params, OptionalExtension, Result};
+use shinkai_message_primitives::schemas::{job_dead_letter::JobDeadLetter, shinkai_name::ShinkaiName};
+
+const JOB_DEAD_LETTER_COLUMNS: &str = "id, job_id, job_message, profile, message_hash_id, attempts, error, created_at";
+
+impl SqliteManager {
+    pub fn add_job_dead_letter(&self, dead_letter: &JobDeadLetter) -> Result<(), SqliteManagerError> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            "INSERT INTO job_dead_letters (id, job_id, job_message, profile, message_hash_id, attempts, error, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                dead_letter.id,
+                dead_letter.job_id,
+                serde_json::to_string(&dead_letter.job_message)?,
+                dead_letter.profile.full_name,
+                dead_letter.message_hash_id,
+                dead_letter.attempts,
+                dead_letter.error,
+                dead_letter.created_at.to_rfc3339_opts(SecondsFormat::Nanos, true),
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_job_dead_letter(&self, id: &str) -> Result<Option<JobDeadLetter>, SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let dead_letter = conn
+            .query_row(
+                &format!("SELECT {} FROM job_dead_letters WHERE id = ?1", JOB_DEAD_LETTER_COLUMNS),
+                params![id],
+                Self::row_to_job_dead_letter,
+            )
+            .optional()?;
+        Ok(dead_letter)
+    }
+
+    /// Every dead-lettered message, or those of one job, newest first
+    pub fn get_job_dead_letters(&self, job_id: Option<&str>) -> Result<Vec<JobDeadLetter>, SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM job_dead_letters WHERE ?1 IS NULL OR job_id = ?1 ORDER BY created_at DESC",
+            JOB_DEAD_LETTER_COLUMNS
+        ))?;
+        let dead_letters = stmt
+            .query_map(params![job_id], Self::row_to_job_dead_letter)?
+            .collect::<Result<Vec<JobDeadLetter>, _>>()?;
+        Ok(dead_letters)
+    }
+
+    pub fn remove_job_dead_letter(&self, id: &str) -> Result<(), SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let removed = conn.execute("DELETE FROM job_dead_letters WHERE id = ?1", params![id])?;
+        if removed == 0 {
+            return Err(SqliteManagerError::DataNotFound);
+        }
+        Ok(())
+    }
+
+    fn row_to_job_dead_letter(row: &rusqlite::Row) -> rusqlite::Result<JobDeadLetter> {
+        let job_message: String = row.get(2)?;
+        let profile: String = row.get(3)?;
+        let created_at: String = row.get(7)?;
+        Ok(JobDeadLetter {
+            id: row.get(0)?,
+            job_id: row.get(1)?,
+            job_message: serde_json::from_str(&job_message)
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(2, rusqlite::types::Type::Text, Box::new(e)))?,
+            profile: ShinkaiName::new(profile)
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(3, rusqlite::types::Type::Text, Box::from(e)))?,
+            message_hash_id: row.get(4)?,
+            attempts: row.get(5)?,
+            error: row.get(6)?,
+            created_at: DateTime::parse_from_rfc3339(&created_at)
+                .map(|time| time.with_timezone(&Utc))
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(7, rusqlite::types::Type::Text, Box::new(e)))?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shinkai_embedding::model_type::{EmbeddingModelType, OllamaTextEmbeddingsInference};
+    use shinkai_message_primitives::shinkai_message::shinkai_message_schemas::JobMessage;
+    use std::path::PathBuf;
+    use tempfile::NamedTempFile;
+
+    fn setup_test_db() -> SqliteManager {
+        let temp_file = NamedTempFile::new().unwrap();
+        let db_path = PathBuf::from(temp_file.path());
+        let api_url = String::new();
+        let model_type =
+            EmbeddingModelType::OllamaTextEmbeddingsInference(OllamaTextEmbeddingsInference::SnowflakeArcticEmbedM);
+
+        SqliteManager::new(db_path, api_url, model_type).unwrap()
+    }
+
+    fn dead_letter(id: &str, job_id: &str) -> JobDeadLetter {
+        JobDeadLetter {
+            id: id.to_string(),
+            job_id: job_id.to_string(),
+            job_message: JobMessage {
+                job_id: job_id.to_string(),
+                content: "Summarize the news".to_string(),
+                parent: None,
+                sheet_job_data: None,
+                tools: None,
+                callback: None,
+                metadata: None,
+                tool_key: None,
+                fs_files_paths: vec![],
+                job_filenames: vec![],
+            },
+            profile: ShinkaiName::new("@@node1.shinkai/main".to_string()).unwrap(),
+            message_hash_id: Some("hash".to_string()),
+            attempts: 4,
+            error: "Network error: connection refused".to_string(),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_job_dead_letters() {
+        let db = setup_test_db();
+        let first = dead_letter("dead_1", "job_1");
+        let second = dead_letter("dead_2", "job_2");
+        db.add_job_dead_letter(&first).unwrap();
+        db.add_job_dead_letter(&second).unwrap();
+
+        assert_eq!(db.get_job_dead_letter("dead_1").unwrap(), Some(first.clone()));
+        assert_eq!(
+            db.get_job_dead_letters(None).unwrap(),
+            vec![second.clone(), first.clone()]
+        );
+        assert_eq!(db.get_job_dead_letters(Some("job_1")).unwrap(), vec![first]);
+
+        db.remove_job_dead_letter("dead_1").unwrap();
+        assert!(matches!(
+            db.remove_job_dead_letter("dead_1"),
+            Err(SqliteManagerError::DataNotFound)
+        ));
+        assert_eq!(db.get_job_dead_letters(None).unwrap(), vec![second]);
+    }
+}
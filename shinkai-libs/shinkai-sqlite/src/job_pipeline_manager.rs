@@ -0,0 +1,415 @@
+use crate::{SqliteManager, SqliteManagerError};
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::{params, OptionalExtension, Result, TransactionBehavior};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use shinkai_message_primitives::schemas::job_pipeline::{
+    JobPipeline, JobPipelineRun, JobPipelineRunStatus, JobPipelineStepRun, JobPipelineStepStatus,
+};
+
+const JOB_PIPELINE_COLUMNS: &str = "id, name, description, steps, created_at";
+const JOB_PIPELINE_RUN_COLUMNS: &str = "id, pipeline, input, status, error, created_at, finished_at";
+const JOB_PIPELINE_STEP_RUN_COLUMNS: &str = "step_id, status, job_id, output, error, started_at, finished_at";
+
+fn now() -> String {
+    Utc::now().to_rfc3339_opts(SecondsFormat::Nanos, true)
+}
+
+impl SqliteManager {
+    pub fn add_job_pipeline(&self, pipeline: &JobPipeline) -> Result<(), SqliteManagerError> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            "INSERT INTO job_pipelines (id, name, description, steps, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                pipeline.id,
+                pipeline.name,
+                pipeline.description,
+                serde_json::to_string(&pipeline.steps)?,
+                pipeline.created_at.to_rfc3339_opts(SecondsFormat::Nanos, true),
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn remove_job_pipeline(&self, pipeline_id: &str) -> Result<(), SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let removed = conn.execute("DELETE FROM job_pipelines WHERE id = ?1", params![pipeline_id])?;
+        if removed == 0 {
+            return Err(SqliteManagerError::DataNotFound);
+        }
+        Ok(())
+    }
+
+    pub fn get_job_pipeline(&self, pipeline_id: &str) -> Result<Option<JobPipeline>, SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let pipeline = conn
+            .query_row(
+                &format!("SELECT {} FROM job_pipelines WHERE id = ?1", JOB_PIPELINE_COLUMNS),
+                params![pipeline_id],
+                Self::row_to_job_pipeline,
+            )
+            .optional()?;
+        Ok(pipeline)
+    }
+
+    /// Every pipeline, oldest first
+    pub fn get_all_job_pipelines(&self) -> Result<Vec<JobPipeline>, SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM job_pipelines ORDER BY created_at ASC",
+            JOB_PIPELINE_COLUMNS
+        ))?;
+        let pipelines = stmt
+            .query_map([], Self::row_to_job_pipeline)?
+            .collect::<Result<Vec<JobPipeline>, _>>()?;
+        Ok(pipelines)
+    }
+
+    /// Stores a new run with all of its steps
+    pub fn add_job_pipeline_run(&self, run: &JobPipelineRun) -> Result<(), SqliteManagerError> {
+        let mut conn = self.get_connection()?;
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT INTO job_pipeline_runs (id, pipeline_id, pipeline, input, status, error, created_at, finished_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                run.id,
+                run.pipeline.id,
+                serde_json::to_string(&run.pipeline)?,
+                serde_json::to_string(&run.input)?,
+                run.status.to_string(),
+                run.error,
+                run.created_at.to_rfc3339_opts(SecondsFormat::Nanos, true),
+                run.finished_at
+                    .map(|finished_at| finished_at.to_rfc3339_opts(SecondsFormat::Nanos, true)),
+            ],
+        )?;
+        for (position, step_run) in run.steps.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO job_pipeline_step_runs (run_id, step_id, position, status, job_id, output, error, started_at, finished_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                params![
+                    run.id,
+                    step_run.step_id,
+                    position as i64,
+                    step_run.status.to_string(),
+                    step_run.job_id,
+                    step_run.output,
+                    step_run.error,
+                    step_run
+                        .started_at
+                        .map(|started_at| started_at.to_rfc3339_opts(SecondsFormat::Nanos, true)),
+                    step_run
+                        .finished_at
+                        .map(|finished_at| finished_at.to_rfc3339_opts(SecondsFormat::Nanos, true)),
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn get_job_pipeline_run(&self, run_id: &str) -> Result<Option<JobPipelineRun>, SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let Some(mut run) = conn
+            .query_row(
+                &format!(
+                    "SELECT {} FROM job_pipeline_runs WHERE id = ?1",
+                    JOB_PIPELINE_RUN_COLUMNS
+                ),
+                params![run_id],
+                Self::row_to_job_pipeline_run,
+            )
+            .optional()?
+        else {
+            return Ok(None);
+        };
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM job_pipeline_step_runs WHERE run_id = ?1 ORDER BY position ASC",
+            JOB_PIPELINE_STEP_RUN_COLUMNS
+        ))?;
+        run.steps = stmt
+            .query_map(params![run_id], Self::row_to_job_pipeline_step_run)?
+            .collect::<Result<Vec<JobPipelineStepRun>, _>>()?;
+        Ok(Some(run))
+    }
+
+    /// Marks a pending step of a running run as running. Returns false if the step was already
+    /// started, so each step is started once even when steps finish at the same time.
+    pub fn claim_job_pipeline_step(&self, run_id: &str, step_id: &str) -> Result<bool, SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let claimed = conn.execute(
+            "UPDATE job_pipeline_step_runs SET status = ?1, started_at = ?2
+             WHERE run_id = ?3 AND step_id = ?4 AND status = ?5
+             AND EXISTS (SELECT 1 FROM job_pipeline_runs WHERE id = ?3 AND status = ?6)",
+            params![
+                JobPipelineStepStatus::Running.to_string(),
+                now(),
+                run_id,
+                step_id,
+                JobPipelineStepStatus::Pending.to_string(),
+                JobPipelineRunStatus::Running.to_string(),
+            ],
+        )?;
+        Ok(claimed > 0)
+    }
+
+    pub fn set_job_pipeline_step_job(
+        &self,
+        run_id: &str,
+        step_id: &str,
+        job_id: &str,
+    ) -> Result<(), SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let updated = conn.execute(
+            "UPDATE job_pipeline_step_runs SET job_id = ?1 WHERE run_id = ?2 AND step_id = ?3",
+            params![job_id, run_id, step_id],
+        )?;
+        if updated == 0 {
+            return Err(SqliteManagerError::DataNotFound);
+        }
+        Ok(())
+    }
+
+    /// Records the answer or the error of a running step. Returns false if the step was not
+    /// running, e.g. when its result was already recorded.
+    pub fn finish_job_pipeline_step(
+        &self,
+        run_id: &str,
+        step_id: &str,
+        result: Result<&str, &str>,
+    ) -> Result<bool, SqliteManagerError> {
+        let (status, output, error) = match result {
+            Ok(output) => (JobPipelineStepStatus::Completed, Some(output), None),
+            Err(error) => (JobPipelineStepStatus::Failed, None, Some(error)),
+        };
+        let conn = self.get_connection()?;
+        let finished = conn.execute(
+            "UPDATE job_pipeline_step_runs SET status = ?1, output = ?2, error = ?3, finished_at = ?4
+             WHERE run_id = ?5 AND step_id = ?6 AND status = ?7",
+            params![
+                status.to_string(),
+                output,
+                error,
+                now(),
+                run_id,
+                step_id,
+                JobPipelineStepStatus::Running.to_string(),
+            ],
+        )?;
+        Ok(finished > 0)
+    }
+
+    /// Completes or fails a running run. Returns false if it was already finished.
+    pub fn finish_job_pipeline_run(
+        &self,
+        run_id: &str,
+        status: JobPipelineRunStatus,
+        error: Option<&str>,
+    ) -> Result<bool, SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let finished = conn.execute(
+            "UPDATE job_pipeline_runs SET status = ?1, error = ?2, finished_at = ?3 WHERE id = ?4 AND status = ?5",
+            params![
+                status.to_string(),
+                error,
+                now(),
+                run_id,
+                JobPipelineRunStatus::Running.to_string(),
+            ],
+        )?;
+        Ok(finished > 0)
+    }
+
+    /// Sets a failed run running again with its failed steps pending. Completed steps keep their
+    /// answers. Returns false if the run had not failed.
+    pub fn resume_job_pipeline_run(&self, run_id: &str) -> Result<bool, SqliteManagerError> {
+        let mut conn = self.get_connection()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let resumed = tx.execute(
+            "UPDATE job_pipeline_runs SET status = ?1, error = NULL, finished_at = NULL WHERE id = ?2 AND status = ?3",
+            params![
+                JobPipelineRunStatus::Running.to_string(),
+                run_id,
+                JobPipelineRunStatus::Failed.to_string(),
+            ],
+        )?;
+        if resumed == 0 {
+            return Ok(false);
+        }
+        tx.execute(
+            "UPDATE job_pipeline_step_runs
+             SET status = ?1, job_id = NULL, output = NULL, error = NULL, started_at = NULL, finished_at = NULL
+             WHERE run_id = ?2 AND status = ?3",
+            params![
+                JobPipelineStepStatus::Pending.to_string(),
+                run_id,
+                JobPipelineStepStatus::Failed.to_string(),
+            ],
+        )?;
+        tx.commit()?;
+        Ok(true)
+    }
+
+    fn row_to_job_pipeline(row: &rusqlite::Row) -> rusqlite::Result<JobPipeline> {
+        let steps: String = row.get(3)?;
+        Ok(JobPipeline {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            description: row.get(2)?,
+            steps: serde_json::from_str(&steps)
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(3, rusqlite::types::Type::Text, Box::new(e)))?,
+            created_at: time_from_row(row, 4)?.unwrap_or_default(),
+        })
+    }
+
+    /// The run without its steps
+    fn row_to_job_pipeline_run(row: &rusqlite::Row) -> rusqlite::Result<JobPipelineRun> {
+        let pipeline: String = row.get(1)?;
+        let input: String = row.get(2)?;
+        Ok(JobPipelineRun {
+            id: row.get(0)?,
+            pipeline: serde_json::from_str(&pipeline)
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(1, rusqlite::types::Type::Text, Box::new(e)))?,
+            input: serde_json::from_str(&input)
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(2, rusqlite::types::Type::Text, Box::new(e)))?,
+            status: status_from_row(row, 3)?,
+            error: row.get(4)?,
+            steps: Vec::new(),
+            created_at: time_from_row(row, 5)?.unwrap_or_default(),
+            finished_at: time_from_row(row, 6)?,
+        })
+    }
+
+    fn row_to_job_pipeline_step_run(row: &rusqlite::Row) -> rusqlite::Result<JobPipelineStepRun> {
+        Ok(JobPipelineStepRun {
+            step_id: row.get(0)?,
+            status: status_from_row(row, 1)?,
+            job_id: row.get(2)?,
+            output: row.get(3)?,
+            error: row.get(4)?,
+            started_at: time_from_row(row, 5)?,
+            finished_at: time_from_row(row, 6)?,
+        })
+    }
+}
+
+fn status_from_row<T: DeserializeOwned>(row: &rusqlite::Row, idx: usize) -> rusqlite::Result<T> {
+    let status: String = row.get(idx)?;
+    serde_json::from_value(Value::String(status))
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(idx, rusqlite::types::Type::Text, Box::new(e)))
+}
+
+fn time_from_row(row: &rusqlite::Row, idx: usize) -> rusqlite::Result<Option<DateTime<Utc>>> {
+    let Some(time) = row.get::<_, Option<String>>(idx)? else {
+        return Ok(None);
+    };
+    DateTime::parse_from_rfc3339(&time)
+        .map(|time| Some(time.with_timezone(&Utc)))
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(idx, rusqlite::types::Type::Text, Box::new(e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use shinkai_embedding::model_type::{EmbeddingModelType, OllamaTextEmbeddingsInference};
+    use shinkai_message_primitives::schemas::job_pipeline::JobPipelineStep;
+    use std::path::PathBuf;
+    use tempfile::NamedTempFile;
+
+    fn setup_test_db() -> SqliteManager {
+        let temp_file = NamedTempFile::new().unwrap();
+        let db_path = PathBuf::from(temp_file.path());
+        let api_url = String::new();
+        let model_type =
+            EmbeddingModelType::OllamaTextEmbeddingsInference(OllamaTextEmbeddingsInference::SnowflakeArcticEmbedM);
+
+        SqliteManager::new(db_path, api_url, model_type).unwrap()
+    }
+
+    #[test]
+    fn test_job_pipeline_runs() {
+        let db = setup_test_db();
+        let pipeline = JobPipeline {
+            id: "digest".to_string(),
+            name: "Digest".to_string(),
+            description: Some("Summarize then translate".to_string()),
+            steps: ["summarize", "translate"]
+                .into_iter()
+                .map(|id| JobPipelineStep {
+                    id: id.to_string(),
+                    agent_id: "agent".to_string(),
+                    prompt: format!("{} {{{{input.text}}}}", id),
+                    depends_on: None,
+                    tools: None,
+                })
+                .collect(),
+            created_at: Utc::now(),
+        };
+        db.add_job_pipeline(&pipeline).unwrap();
+        assert_eq!(db.get_all_job_pipelines().unwrap(), vec![pipeline.clone()]);
+
+        let run = JobPipelineRun {
+            id: "run".to_string(),
+            pipeline: pipeline.clone(),
+            input: json!({ "text": "hello" }),
+            status: JobPipelineRunStatus::Running,
+            error: None,
+            steps: pipeline
+                .steps
+                .iter()
+                .map(|step| JobPipelineStepRun {
+                    step_id: step.id.clone(),
+                    status: JobPipelineStepStatus::Pending,
+                    job_id: None,
+                    output: None,
+                    error: None,
+                    started_at: None,
+                    finished_at: None,
+                })
+                .collect(),
+            created_at: Utc::now(),
+            finished_at: None,
+        };
+        db.add_job_pipeline_run(&run).unwrap();
+
+        // Runs keep the pipeline they started with
+        db.remove_job_pipeline("digest").unwrap();
+        assert_eq!(db.get_job_pipeline_run("run").unwrap().unwrap(), run);
+
+        // Steps are started once and finished once
+        assert!(db.claim_job_pipeline_step("run", "summarize").unwrap());
+        assert!(!db.claim_job_pipeline_step("run", "summarize").unwrap());
+        db.set_job_pipeline_step_job("run", "summarize", "job_1").unwrap();
+        assert!(db.finish_job_pipeline_step("run", "summarize", Ok("hi")).unwrap());
+        assert!(!db.finish_job_pipeline_step("run", "summarize", Err("late")).unwrap());
+
+        assert!(db.claim_job_pipeline_step("run", "translate").unwrap());
+        assert!(db
+            .finish_job_pipeline_step("run", "translate", Err("model offline"))
+            .unwrap());
+        assert!(db
+            .finish_job_pipeline_run("run", JobPipelineRunStatus::Failed, Some("model offline"))
+            .unwrap());
+        assert!(!db.claim_job_pipeline_step("run", "translate").unwrap());
+
+        // Resuming only retries the failed steps
+        assert!(db.resume_job_pipeline_run("run").unwrap());
+        assert!(!db.resume_job_pipeline_run("run").unwrap());
+        let resumed = db.get_job_pipeline_run("run").unwrap().unwrap();
+        assert_eq!(resumed.status, JobPipelineRunStatus::Running);
+        assert_eq!(resumed.steps[0].status, JobPipelineStepStatus::Completed);
+        assert_eq!(resumed.steps[0].job_id.as_deref(), Some("job_1"));
+        assert_eq!(resumed.steps[0].output.as_deref(), Some("hi"));
+        assert_eq!(resumed.steps[1].status, JobPipelineStepStatus::Pending);
+        assert_eq!(resumed.steps[1].error, None);
+        assert_eq!(resumed.ready_steps()[0].id, "translate");
+
+        assert!(matches!(
+            db.remove_job_pipeline("digest"),
+            Err(SqliteManagerError::DataNotFound)
+        ));
+    }
+}
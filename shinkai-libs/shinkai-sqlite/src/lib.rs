@@ -1,15 +1,20 @@
+use ed25519_dalek::SigningKey;
 use embedding_function::EmbeddingFunction;
 use errors::SqliteManagerError;
 use r2d2::Pool;
 use r2d2_sqlite::SqliteConnectionManager;
 use rusqlite::{ffi::sqlite3_auto_extension, Result, Row, ToSql};
 use shinkai_embedding::model_type::EmbeddingModelType;
+use shinkai_message_primitives::shinkai_utils::secret_vault::SecretVault;
 use sqlite_vec::sqlite3_vec_init;
 use std::path::Path;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 use std::time::Duration;
 
 pub mod agent_manager;
+pub mod api_key_manager;
+pub mod audit_log_manager;
+pub mod backup_manager;
 pub mod cron_task_manager;
 pub mod embedding_function;
 pub mod errors;
@@ -19,9 +24,14 @@ pub mod files;
 pub mod identity_manager;
 pub mod identity_registration;
 pub mod inbox_manager;
+pub mod inbox_search_manager;
 pub mod invoice_manager;
 pub mod invoice_request_manager;
+pub mod job_checkpoint_manager;
+pub mod job_concurrency_limit_manager;
+pub mod job_dead_letter_manager;
 pub mod job_manager;
+pub mod job_pipeline_manager;
 pub mod job_queue_manager;
 pub mod keys_manager;
 pub mod llm_provider_manager;
@@ -30,19 +40,39 @@ pub mod preferences;
 pub mod prompt_manager;
 pub mod regex_pattern_manager;
 pub mod retry_manager;
+pub mod scheduled_job_message_manager;
+pub mod secret_vault_manager;
 pub mod settings_manager;
 pub mod sheet_manager;
 pub mod shinkai_tool_manager;
 pub mod source_file_manager;
 pub mod tool_payment_req_manager;
+pub mod tool_network_manager;
 pub mod tool_playground;
+pub mod tool_revision_manager;
 pub mod wallet_manager;
+pub mod wasm_tool_grant_manager;
+pub mod webhook_manager;
+pub mod webhook_trigger_manager;
 
 // Updated struct to manage SQLite connections using a connection pool
 pub struct SqliteManager {
     pool: Arc<Pool<SqliteConnectionManager>>,
     fts_pool: Arc<Pool<SqliteConnectionManager>>,
     api_url: String,
+    // Key for secrets stored at rest, unset until the node unlocks it
+    secret_vault: RwLock<Option<SecretVault>>,
+    // Node identity key signing the head of the audit log, unset until the node starts
+    audit_log_signing_key: RwLock<Option<SigningKey>>,
+}
+
+/// Connection usage of one of the sqlite connection pools
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SqlitePoolUsage {
+    pub pool: &'static str,
+    pub connections: u32,
+    pub idle_connections: u32,
+    pub max_size: u32,
 }
 
 impl std::fmt::Debug for SqliteManager {
@@ -125,6 +155,8 @@ impl SqliteManager {
             pool: Arc::new(pool),
             fts_pool: Arc::new(fts_pool), // Use the in-memory connection pool
             api_url,
+            secret_vault: RwLock::new(None),
+            audit_log_signing_key: RwLock::new(None),
         };
         let fts_sync_result = manager.sync_tools_fts_table();
         if let Err(e) = fts_sync_result {
@@ -136,6 +168,10 @@ impl SqliteManager {
             eprintln!("Error synchronizing Prompts FTS table: {}", e);
         }
 
+        if let Err(e) = manager.sync_inbox_message_search() {
+            eprintln!("Error synchronizing inbox message search index: {}", e);
+        }
+
         manager.update_default_embedding_model(model_type)?;
 
         Ok(manager)
@@ -144,14 +180,18 @@ impl SqliteManager {
     // Initializes the required tables in the SQLite database
     fn initialize_tables(conn: &rusqlite::Connection) -> Result<()> {
         Self::initialize_agents_table(conn)?;
+        Self::initialize_api_keys_table(conn)?;
+        Self::initialize_audit_log_table(conn)?;
         Self::initialize_cron_tasks_table(conn)?;
         Self::initialize_cron_task_executions_table(conn)?;
+        Self::initialize_cron_task_events_table(conn)?;
         Self::initialize_device_identities_table(conn)?;
         Self::initialize_standard_identities_table(conn)?;
         // TODO: remove this
         Self::initialize_file_inboxes_table(conn)?;
         Self::initialize_inboxes_table(conn)?;
         Self::initialize_inbox_messages_table(conn)?;
+        Self::initialize_inbox_search_tables(conn)?;
         Self::initialize_inbox_profile_permissions_table(conn)?;
         Self::initialize_invoice_network_errors_table(conn)?;
         Self::initialize_invoice_requests_table(conn)?;
@@ -159,6 +199,10 @@ impl SqliteManager {
         Self::initialize_jobs_table(conn)?;
         Self::initialize_forked_jobs_table(conn)?;
         Self::initialize_job_queue_table(conn)?;
+        Self::initialize_job_pipeline_tables(conn)?;
+        Self::initialize_job_dead_letters_table(conn)?;
+        Self::initialize_job_checkpoints_table(conn)?;
+        Self::initialize_job_concurrency_limits_table(conn)?;
         Self::initialize_llm_providers_table(conn)?;
         Self::initialize_local_node_keys_table(conn)?;
         Self::initialize_message_box_symmetric_keys_table(conn)?;
@@ -167,14 +211,20 @@ impl SqliteManager {
         Self::initialize_prompt_vector_tables(conn)?;
         Self::initialize_registration_code_table(conn)?;
         Self::initialize_retry_messages_table(conn)?;
+        Self::initialize_scheduled_job_messages_table(conn)?;
+        Self::initialize_secret_vault_table(conn)?;
         Self::initialize_settings_table(conn)?;
         Self::initialize_sheets_table(conn)?;
         Self::initialize_tools_table(conn)?;
+        Self::initialize_tool_revisions_table(conn)?;
+        Self::initialize_tool_network_tables(conn)?;
+        Self::initialize_wasm_tool_grants_table(conn)?;
         Self::initialize_tool_micropayments_requirements_table(conn)?;
         Self::initialize_tool_playground_table(conn)?;
         Self::initialize_tool_playground_code_history_table(conn)?;
         Self::initialize_version_table(conn)?;
         Self::initialize_wallets_table(conn)?;
+        Self::initialize_webhook_tables(conn)?;
         Self::initialize_filesystem_tables(conn)?;
         Self::initialize_oauth_table(conn)?;
         Self::initialize_regex_patterns_table(conn)?;
@@ -336,6 +386,64 @@ impl SqliteManager {
         Ok(())
     }
 
+    // Search index over the content of inbox messages. Ids are never reused so embeddings keyed by
+    // them stay attached to the same message.
+    fn initialize_inbox_search_tables(conn: &rusqlite::Connection) -> Result<()> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS inbox_message_search (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                message_hash TEXT NOT NULL UNIQUE,
+                inbox_name TEXT NOT NULL,
+                sender TEXT NOT NULL,
+                time_key TEXT NOT NULL,
+                content TEXT NOT NULL
+            );",
+            [],
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_inbox_message_search_inbox_name ON inbox_message_search (inbox_name);",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS inbox_message_search_fts USING fts5(
+                content,
+                content='inbox_message_search',
+                content_rowid='id'
+            )",
+            [],
+        )?;
+
+        // Keep the FTS index in sync with the content table
+        conn.execute_batch(
+            "CREATE TRIGGER IF NOT EXISTS inbox_message_search_ai AFTER INSERT ON inbox_message_search BEGIN
+                INSERT INTO inbox_message_search_fts (rowid, content) VALUES (new.id, new.content);
+            END;
+            CREATE TRIGGER IF NOT EXISTS inbox_message_search_ad AFTER DELETE ON inbox_message_search BEGIN
+                INSERT INTO inbox_message_search_fts (inbox_message_search_fts, rowid, content)
+                VALUES ('delete', old.id, old.content);
+            END;
+            CREATE TRIGGER IF NOT EXISTS inbox_message_search_au AFTER UPDATE ON inbox_message_search BEGIN
+                INSERT INTO inbox_message_search_fts (inbox_message_search_fts, rowid, content)
+                VALUES ('delete', old.id, old.content);
+                INSERT INTO inbox_message_search_fts (rowid, content) VALUES (new.id, new.content);
+            END;
+            CREATE TRIGGER IF NOT EXISTS inbox_messages_search_ad AFTER DELETE ON inbox_messages BEGIN
+                DELETE FROM inbox_message_search WHERE message_hash = old.message_hash;
+            END;",
+        )?;
+
+        // Embeddings of the indexed messages, the rowid is the id in inbox_message_search
+        conn.execute(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS inbox_message_vec_items USING vec0(
+                embedding float[384]
+            )",
+            [],
+        )?;
+
+        Ok(())
+    }
+
     fn initialize_inbox_profile_permissions_table(conn: &rusqlite::Connection) -> Result<()> {
         conn.execute(
             "CREATE TABLE IF NOT EXISTS inbox_profile_permissions (
@@ -397,6 +505,129 @@ impl SqliteManager {
         Ok(())
     }
 
+    fn initialize_job_pipeline_tables(conn: &rusqlite::Connection) -> Result<()> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS job_pipelines (
+                id TEXT NOT NULL PRIMARY KEY,
+                name TEXT NOT NULL,
+                description TEXT,
+                steps TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            );",
+            [],
+        )?;
+
+        // Runs keep the pipeline they started with, so editing or removing it does not affect them
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS job_pipeline_runs (
+                id TEXT NOT NULL PRIMARY KEY,
+                pipeline_id TEXT NOT NULL,
+                pipeline TEXT NOT NULL,
+                input TEXT NOT NULL,
+                status TEXT NOT NULL,
+                error TEXT,
+                created_at TEXT NOT NULL,
+                finished_at TEXT
+            );",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS job_pipeline_step_runs (
+                run_id TEXT NOT NULL,
+                step_id TEXT NOT NULL,
+                position INTEGER NOT NULL,
+                status TEXT NOT NULL,
+                job_id TEXT,
+                output TEXT,
+                error TEXT,
+                started_at TEXT,
+                finished_at TEXT,
+                PRIMARY KEY (run_id, step_id)
+            );",
+            [],
+        )?;
+
+        Ok(())
+    }
+
+    fn initialize_job_dead_letters_table(conn: &rusqlite::Connection) -> Result<()> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS job_dead_letters (
+                id TEXT NOT NULL PRIMARY KEY,
+                job_id TEXT NOT NULL,
+                job_message TEXT NOT NULL,
+                profile TEXT NOT NULL,
+                message_hash_id TEXT,
+                attempts INTEGER NOT NULL,
+                error TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            );",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_job_dead_letters_job_id ON job_dead_letters (job_id);",
+            [],
+        )?;
+
+        Ok(())
+    }
+
+    fn initialize_job_checkpoints_table(conn: &rusqlite::Connection) -> Result<()> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS job_checkpoints (
+                job_id TEXT NOT NULL PRIMARY KEY,
+                message_hash_id TEXT,
+                iteration INTEGER NOT NULL,
+                state TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );",
+            [],
+        )?;
+
+        Ok(())
+    }
+
+    fn initialize_scheduled_job_messages_table(conn: &rusqlite::Connection) -> Result<()> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS scheduled_job_messages (
+                id TEXT NOT NULL PRIMARY KEY,
+                job_id TEXT NOT NULL,
+                job_message TEXT NOT NULL,
+                send_at TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                status TEXT NOT NULL DEFAULT 'pending',
+                error TEXT
+            );",
+            [],
+        )?;
+        Self::add_column_if_missing(conn, "scheduled_job_messages", "status", "TEXT NOT NULL DEFAULT 'pending'")?;
+        Self::add_column_if_missing(conn, "scheduled_job_messages", "error", "TEXT")?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_scheduled_job_messages_send_at ON scheduled_job_messages (send_at);",
+            [],
+        )?;
+
+        Ok(())
+    }
+
+    fn initialize_job_concurrency_limits_table(conn: &rusqlite::Connection) -> Result<()> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS job_concurrency_limits (
+                target TEXT NOT NULL,
+                target_id TEXT NOT NULL,
+                max_concurrent INTEGER,
+                requests_per_minute INTEGER,
+                tokens_per_minute INTEGER,
+                PRIMARY KEY (target, target_id)
+            );",
+            [],
+        )?;
+        Ok(())
+    }
+
     fn initialize_llm_providers_table(conn: &rusqlite::Connection) -> Result<()> {
         conn.execute(
             "CREATE TABLE IF NOT EXISTS llm_providers (
@@ -463,6 +694,22 @@ impl SqliteManager {
         Ok(())
     }
 
+    // Single row describing the key the secrets at rest are sealed with. The salt is only set
+    // when the key is derived from a passphrase.
+    fn initialize_secret_vault_table(conn: &rusqlite::Connection) -> Result<()> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS shinkai_secret_vault (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                key_id TEXT NOT NULL,
+                kdf_salt TEXT,
+                updated_at TEXT NOT NULL
+            );",
+            [],
+        )?;
+
+        Ok(())
+    }
+
     fn initialize_settings_table(conn: &rusqlite::Connection) -> Result<()> {
         conn.execute(
             "CREATE TABLE IF NOT EXISTS shinkai_settings (
@@ -554,6 +801,70 @@ impl SqliteManager {
         Ok(())
     }
 
+    // Append-only history of every stored version of a tool
+    fn initialize_tool_revisions_table(conn: &rusqlite::Connection) -> Result<()> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS shinkai_tool_revisions (
+                tool_key TEXT NOT NULL,
+                revision INTEGER NOT NULL,
+                version INTEGER NOT NULL,
+                version_str TEXT NOT NULL,
+                tool_data BLOB NOT NULL,
+                created_at TEXT NOT NULL,
+                PRIMARY KEY(tool_key, revision)
+            );",
+            [],
+        )?;
+
+        Ok(())
+    }
+
+    // Network hosts approved by the user and outbound attempts blocked by the egress proxy
+    fn initialize_tool_network_tables(conn: &rusqlite::Connection) -> Result<()> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS shinkai_tool_network_approvals (
+                tool_key TEXT NOT NULL PRIMARY KEY,
+                allowed_hosts TEXT NOT NULL,
+                approved_at TEXT NOT NULL
+            );",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS shinkai_tool_egress_blocks (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                tool_key TEXT NOT NULL,
+                host TEXT NOT NULL,
+                port INTEGER NOT NULL,
+                reason TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            );",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_shinkai_tool_egress_blocks_tool_key ON shinkai_tool_egress_blocks (tool_key);",
+            [],
+        )?;
+
+        Ok(())
+    }
+
+    // Capabilities of WASM tools approved by the user, kept apart from the tool definitions
+    fn initialize_wasm_tool_grants_table(conn: &rusqlite::Connection) -> Result<()> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS shinkai_wasm_tool_grants (
+                tool_key TEXT NOT NULL PRIMARY KEY,
+                capabilities TEXT NOT NULL,
+                allow_outside_storage INTEGER NOT NULL DEFAULT 0,
+                approved_at TEXT NOT NULL
+            );",
+            [],
+        )?;
+
+        Ok(())
+    }
+
     fn migrate_tools_table(conn: &rusqlite::Connection) -> Result<()> {
         // Check if the mcp_enabled column already exists
         let columns = conn
@@ -660,6 +971,68 @@ impl SqliteManager {
         Ok(())
     }
 
+    fn initialize_webhook_tables(conn: &rusqlite::Connection) -> Result<()> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS shinkai_webhooks (
+                id TEXT NOT NULL PRIMARY KEY,
+                url TEXT NOT NULL,
+                events TEXT NOT NULL,
+                secret TEXT NOT NULL,
+                description TEXT,
+                enabled INTEGER NOT NULL,
+                created_at TEXT NOT NULL
+            );",
+            [],
+        )?;
+
+        // Outbox of the deliveries, kept after they are sent as the delivery log
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS shinkai_webhook_deliveries (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                webhook_id TEXT NOT NULL,
+                event_type TEXT NOT NULL,
+                payload TEXT NOT NULL,
+                status TEXT NOT NULL,
+                attempts INTEGER NOT NULL DEFAULT 0,
+                next_attempt_at TEXT NOT NULL,
+                last_status_code INTEGER,
+                last_error TEXT,
+                created_at TEXT NOT NULL,
+                delivered_at TEXT,
+                FOREIGN KEY(webhook_id) REFERENCES shinkai_webhooks(id) ON DELETE CASCADE
+            );",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_shinkai_webhook_deliveries_due ON shinkai_webhook_deliveries (status, next_attempt_at);",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_shinkai_webhook_deliveries_webhook_id ON shinkai_webhook_deliveries (webhook_id);",
+            [],
+        )?;
+
+        // Inbound URLs that start agent jobs
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS shinkai_webhook_triggers (
+                id TEXT NOT NULL PRIMARY KEY,
+                name TEXT NOT NULL,
+                agent_id TEXT NOT NULL,
+                prompt_template TEXT NOT NULL,
+                secret TEXT NOT NULL,
+                rate_limit_per_minute INTEGER NOT NULL,
+                mode TEXT NOT NULL,
+                enabled INTEGER NOT NULL,
+                created_at TEXT NOT NULL
+            );",
+            [],
+        )?;
+
+        Ok(())
+    }
+
     fn initialize_tool_micropayments_requirements_table(conn: &rusqlite::Connection) -> Result<()> {
         conn.execute(
             "CREATE TABLE IF NOT EXISTS tool_micropayments_requirements (
@@ -745,6 +1118,83 @@ impl SqliteManager {
         Ok(())
     }
 
+    // Append-only, hash-chained log of security-relevant actions
+    fn initialize_audit_log_table(conn: &rusqlite::Connection) -> Result<()> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS shinkai_audit_log (
+                id INTEGER NOT NULL PRIMARY KEY,
+                timestamp TEXT NOT NULL,
+                actor TEXT NOT NULL,
+                api_key_id TEXT,
+                action TEXT NOT NULL,
+                target TEXT,
+                parameters TEXT NOT NULL,
+                outcome TEXT NOT NULL,
+                success INTEGER NOT NULL,
+                prev_hash TEXT NOT NULL,
+                hash TEXT NOT NULL
+            );",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_shinkai_audit_log_action ON shinkai_audit_log (action);",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_shinkai_audit_log_timestamp ON shinkai_audit_log (timestamp);",
+            [],
+        )?;
+
+        // Reject edits and deletions made through the node itself
+        conn.execute(
+            "CREATE TRIGGER IF NOT EXISTS shinkai_audit_log_no_update BEFORE UPDATE ON shinkai_audit_log
+             BEGIN SELECT RAISE(ABORT, 'shinkai_audit_log is append-only'); END;",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TRIGGER IF NOT EXISTS shinkai_audit_log_no_delete BEFORE DELETE ON shinkai_audit_log
+             BEGIN SELECT RAISE(ABORT, 'shinkai_audit_log is append-only'); END;",
+            [],
+        )?;
+
+        // Signed count and last hash of the chain, so entries dropped from its end are noticed
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS shinkai_audit_log_head (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                entry_count INTEGER NOT NULL,
+                head_hash TEXT NOT NULL,
+                signature TEXT NOT NULL
+            );",
+            [],
+        )?;
+
+        Ok(())
+    }
+
+    // Named API keys with scopes, stored hashed
+    fn initialize_api_keys_table(conn: &rusqlite::Connection) -> Result<()> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS shinkai_api_keys (
+                id TEXT NOT NULL PRIMARY KEY,
+                name TEXT NOT NULL,
+                key_prefix TEXT NOT NULL,
+                key_hash TEXT NOT NULL UNIQUE,
+                scopes TEXT NOT NULL,
+                agent_ids TEXT,
+                expires_at TEXT,
+                created_at TEXT NOT NULL,
+                last_used_at TEXT,
+                revoked INTEGER NOT NULL DEFAULT 0
+            );",
+            [],
+        )?;
+
+        Ok(())
+    }
+
     // Updated method to initialize the cron_tasks table
     fn initialize_cron_tasks_table(conn: &rusqlite::Connection) -> Result<()> {
         conn.execute(
@@ -757,10 +1207,22 @@ impl SqliteManager {
                 last_modified TEXT NOT NULL,
                 last_executed TEXT, -- Field to track the last execution time
                 action TEXT NOT NULL, -- Store serialized CronTaskAction
-                paused INTEGER NOT NULL DEFAULT 0 -- New field to track if the task is paused
+                paused INTEGER NOT NULL DEFAULT 0, -- New field to track if the task is paused
+                timezone TEXT, -- IANA timezone of the schedule, the host timezone if NULL
+                policy TEXT, -- Serialized CronTaskPolicy, the default policy if NULL
+                last_scheduled_run TEXT, -- Due time of the latest run that was started or skipped
+                event_trigger TEXT, -- Serialized CronEventTrigger of tasks run on node events
+                trigger_event TEXT -- Node event type the trigger listens to
             );",
             [],
         )?;
+
+        // Tables created by older versions
+        Self::add_column_if_missing(conn, "cron_tasks", "timezone", "TEXT")?;
+        Self::add_column_if_missing(conn, "cron_tasks", "policy", "TEXT")?;
+        Self::add_column_if_missing(conn, "cron_tasks", "last_scheduled_run", "TEXT")?;
+        Self::add_column_if_missing(conn, "cron_tasks", "event_trigger", "TEXT")?;
+        Self::add_column_if_missing(conn, "cron_tasks", "trigger_event", "TEXT")?;
         Ok(())
     }
 
@@ -774,10 +1236,48 @@ impl SqliteManager {
                 success INTEGER NOT NULL CHECK (success IN (0, 1)),
                 error_message TEXT,
                 job_id TEXT,
+                scheduled_time TEXT,
+                attempt INTEGER NOT NULL DEFAULT 1,
+                misfired INTEGER NOT NULL DEFAULT 0,
+                status TEXT, -- Serialized CronExecutionStatus, derived from success if NULL
+                finished_at TEXT,
                 FOREIGN KEY(task_id) REFERENCES cron_tasks(task_id)
             );",
             [],
         )?;
+
+        Self::add_column_if_missing(conn, "cron_task_executions", "scheduled_time", "TEXT")?;
+        Self::add_column_if_missing(conn, "cron_task_executions", "attempt", "INTEGER NOT NULL DEFAULT 1")?;
+        Self::add_column_if_missing(conn, "cron_task_executions", "misfired", "INTEGER NOT NULL DEFAULT 0")?;
+        Self::add_column_if_missing(conn, "cron_task_executions", "status", "TEXT")?;
+        Self::add_column_if_missing(conn, "cron_task_executions", "finished_at", "TEXT")?;
+        Ok(())
+    }
+
+    // Node events waiting to be matched against event-triggered cron tasks
+    fn initialize_cron_task_events_table(conn: &rusqlite::Connection) -> Result<()> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS cron_task_events (
+                event_id INTEGER PRIMARY KEY AUTOINCREMENT,
+                event_type TEXT NOT NULL,
+                payload TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            );",
+            [],
+        )?;
+        Ok(())
+    }
+
+    // Adds a column to a table created before the column existed
+    fn add_column_if_missing(conn: &rusqlite::Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+        let column_exists: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM pragma_table_info(?1) WHERE name = ?2",
+            rusqlite::params![table, column],
+            |row| row.get(0),
+        )?;
+        if column_exists == 0 {
+            conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition), [])?;
+        }
         Ok(())
     }
 
@@ -919,6 +1419,22 @@ impl SqliteManager {
         })
     }
 
+    // Returns the connection usage of the main and fts pools
+    pub fn pool_usage(&self) -> Vec<SqlitePoolUsage> {
+        [("main", &self.pool), ("fts", &self.fts_pool)]
+            .into_iter()
+            .map(|(name, pool)| {
+                let state = pool.state();
+                SqlitePoolUsage {
+                    pool: name,
+                    connections: state.connections,
+                    idle_connections: state.idle_connections,
+                    max_size: pool.max_size(),
+                }
+            })
+            .collect()
+    }
+
     // Execute a SQL query with parameters
     pub fn execute(&self, sql: &str, params: &[&dyn ToSql]) -> Result<usize> {
         let conn = self.get_connection()?;
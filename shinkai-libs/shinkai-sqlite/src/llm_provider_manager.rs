@@ -1,7 +1,8 @@
-use rusqlite::params;
+use rusqlite::{params, OptionalExtension};
 use shinkai_message_primitives::schemas::{
     llm_providers::serialized_llm_provider::SerializedLLMProvider, shinkai_name::ShinkaiName
 };
+use shinkai_message_primitives::shinkai_utils::secret_vault::is_redacted_secret;
 
 use crate::{SqliteManager, SqliteManagerError};
 
@@ -54,12 +55,13 @@ impl SqliteManager {
         let llm_provider_id = Self::db_llm_provider_id(&llm_provider.id, profile)?;
         let model = serde_json::to_string(&llm_provider.model)
             .map_err(|e| SqliteManagerError::SerializationError(e.to_string()))?;
+        let api_key = self.seal_optional_secret(&llm_provider.api_key)?;
         stmt.execute(params![
             &llm_provider_id,
             &llm_provider.id,
             &llm_provider.full_identity_name.full_name,
             &llm_provider.external_url,
-            &llm_provider.api_key,
+            &api_key,
             &model,
         ])?;
 
@@ -77,11 +79,23 @@ impl SqliteManager {
         let llm_provider_id = Self::db_llm_provider_id(&updated_llm_provider.id, profile)?;
         let model = serde_json::to_string(&updated_llm_provider.model)
             .map_err(|e| SqliteManagerError::SerializationError(e.to_string()))?;
+        // Clients get the API key redacted, sending it back unchanged keeps the stored one
+        let api_key = match &updated_llm_provider.api_key {
+            Some(api_key) if is_redacted_secret(api_key) => conn
+                .query_row(
+                    "SELECT api_key FROM llm_providers WHERE db_llm_provider_id = ?1",
+                    params![&llm_provider_id],
+                    |row| row.get::<_, Option<String>>(0),
+                )
+                .optional()?
+                .flatten(),
+            api_key => self.seal_optional_secret(api_key)?,
+        };
         stmt.execute(params![
             &llm_provider_id,
             &updated_llm_provider.full_identity_name.full_name,
             &updated_llm_provider.external_url,
-            &updated_llm_provider.api_key,
+            &api_key,
             &model,
         ])?;
 
@@ -70,6 +70,9 @@ impl SqliteManager {
             return Err(SqliteManagerError::MissingValue("Token URL is empty".to_string()));
         }
 
+        let access_token = self.seal_optional_secret(&token.access_token)?;
+        let refresh_token = self.seal_optional_secret(&token.refresh_token)?;
+        let token_secret = self.seal_optional_secret(&token.token_secret)?;
         tx.execute(
             "INSERT INTO oauth_tokens (
                 connection_name,response_type, state, code, app_id, tool_id, tool_key,
@@ -89,12 +92,12 @@ impl SqliteManager {
                 token.app_id,
                 token.tool_id,
                 token.tool_key,
-                token.access_token,
+                access_token,
                 token.access_token_expires_at.map(|dt| dt.to_rfc3339()),
-                token.refresh_token,
+                refresh_token,
                 token.refresh_token_enabled,
                 token.refresh_token_expires_at.map(|dt| dt.to_rfc3339()),
-                token.token_secret,
+                token_secret,
                 token.id_token,
                 token.scope,
                 token.pkce_type,
@@ -196,6 +199,10 @@ impl SqliteManager {
         let mut conn = self.get_connection()?;
         let tx = conn.transaction()?;
 
+        let access_token = self.seal_optional_secret(&token.access_token)?;
+        let refresh_token = self.seal_optional_secret(&token.refresh_token)?;
+        let token_secret = self.seal_optional_secret(&token.token_secret)?;
+
         tx.execute(
             "UPDATE oauth_tokens SET 
                 state = ?1,
@@ -230,12 +237,12 @@ impl SqliteManager {
                 token.code,
                 token.app_id,
                 token.tool_id,
-                token.access_token,
+                access_token,
                 token.access_token_expires_at.map(|dt| dt.to_rfc3339()),
-                token.refresh_token,
+                refresh_token,
                 token.refresh_token_enabled,
                 token.refresh_token_expires_at.map(|dt| dt.to_rfc3339()),
-                token.token_secret,
+                token_secret,
                 token.response_type,
                 token.id_token,
                 token.scope,
@@ -0,0 +1,241 @@
+use crate::{SqliteManager, SqliteManagerError};
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::{params, OptionalExtension, Result};
+use serde_json::Value;
+use shinkai_message_primitives::schemas::scheduled_job_message::{ScheduledJobMessage, ScheduledJobMessageStatus};
+
+const SCHEDULED_JOB_MESSAGE_COLUMNS: &str = "id, job_id, job_message, send_at, created_at, status, error";
+
+impl SqliteManager {
+    pub fn add_scheduled_job_message(&self, scheduled: &ScheduledJobMessage) -> Result<(), SqliteManagerError> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            "INSERT INTO scheduled_job_messages (id, job_id, job_message, send_at, created_at, status, error)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                scheduled.id,
+                scheduled.job_id,
+                serde_json::to_string(&scheduled.job_message)?,
+                scheduled.send_at.to_rfc3339_opts(SecondsFormat::Nanos, true),
+                scheduled.created_at.to_rfc3339_opts(SecondsFormat::Nanos, true),
+                scheduled.status.to_string(),
+                scheduled.error,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_scheduled_job_message(&self, id: &str) -> Result<Option<ScheduledJobMessage>, SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let scheduled = conn
+            .query_row(
+                &format!(
+                    "SELECT {} FROM scheduled_job_messages WHERE id = ?1",
+                    SCHEDULED_JOB_MESSAGE_COLUMNS
+                ),
+                params![id],
+                Self::row_to_scheduled_job_message,
+            )
+            .optional()?;
+        Ok(scheduled)
+    }
+
+    /// Every scheduled message not sent yet, or those of one job, next to be sent first
+    pub fn get_scheduled_job_messages(
+        &self,
+        job_id: Option<&str>,
+    ) -> Result<Vec<ScheduledJobMessage>, SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM scheduled_job_messages WHERE ?1 IS NULL OR job_id = ?1 ORDER BY send_at ASC",
+            SCHEDULED_JOB_MESSAGE_COLUMNS
+        ))?;
+        let scheduled = stmt
+            .query_map(params![job_id], Self::row_to_scheduled_job_message)?
+            .collect::<Result<Vec<ScheduledJobMessage>, _>>()?;
+        Ok(scheduled)
+    }
+
+    /// Pending scheduled messages due at `now`, including the ones that came due while the node was
+    /// stopped
+    pub fn get_due_scheduled_job_messages(
+        &self,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<ScheduledJobMessage>, SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM scheduled_job_messages WHERE status = ?1 AND send_at <= ?2 ORDER BY send_at ASC",
+            SCHEDULED_JOB_MESSAGE_COLUMNS
+        ))?;
+        let scheduled = stmt
+            .query_map(
+                params![
+                    ScheduledJobMessageStatus::Pending.to_string(),
+                    now.to_rfc3339_opts(SecondsFormat::Nanos, true)
+                ],
+                Self::row_to_scheduled_job_message,
+            )?
+            .collect::<Result<Vec<ScheduledJobMessage>, _>>()?;
+        Ok(scheduled)
+    }
+
+    /// Marks a pending message as being sent. False if it is no longer pending, e.g. cancelled.
+    pub fn claim_scheduled_job_message(&self, id: &str) -> Result<bool, SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let claimed = conn.execute(
+            "UPDATE scheduled_job_messages SET status = ?1 WHERE id = ?2 AND status = ?3",
+            params![
+                ScheduledJobMessageStatus::Sending.to_string(),
+                id,
+                ScheduledJobMessageStatus::Pending.to_string(),
+            ],
+        )?;
+        Ok(claimed == 1)
+    }
+
+    /// Keeps a message that could not be sent, with the error
+    pub fn fail_scheduled_job_message(&self, id: &str, error: &str) -> Result<(), SqliteManagerError> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            "UPDATE scheduled_job_messages SET status = ?1, error = ?2 WHERE id = ?3",
+            params![ScheduledJobMessageStatus::Failed.to_string(), error, id],
+        )?;
+        Ok(())
+    }
+
+    /// Makes the messages that were being sent when the node stopped pending again, so they are
+    /// sent on startup
+    pub fn release_sending_scheduled_job_messages(&self) -> Result<usize, SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let released = conn.execute(
+            "UPDATE scheduled_job_messages SET status = ?1 WHERE status = ?2",
+            params![
+                ScheduledJobMessageStatus::Pending.to_string(),
+                ScheduledJobMessageStatus::Sending.to_string(),
+            ],
+        )?;
+        Ok(released)
+    }
+
+    pub fn remove_scheduled_job_message(&self, id: &str) -> Result<(), SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let removed = conn.execute("DELETE FROM scheduled_job_messages WHERE id = ?1", params![id])?;
+        if removed == 0 {
+            return Err(SqliteManagerError::DataNotFound);
+        }
+        Ok(())
+    }
+
+    fn row_to_scheduled_job_message(row: &rusqlite::Row) -> rusqlite::Result<ScheduledJobMessage> {
+        let job_message: String = row.get(2)?;
+        let send_at: String = row.get(3)?;
+        let created_at: String = row.get(4)?;
+        let status: String = row.get(5)?;
+        Ok(ScheduledJobMessage {
+            id: row.get(0)?,
+            job_id: row.get(1)?,
+            job_message: serde_json::from_str(&job_message)
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(2, rusqlite::types::Type::Text, Box::new(e)))?,
+            send_at: DateTime::parse_from_rfc3339(&send_at)
+                .map(|time| time.with_timezone(&Utc))
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(3, rusqlite::types::Type::Text, Box::new(e)))?,
+            created_at: DateTime::parse_from_rfc3339(&created_at)
+                .map(|time| time.with_timezone(&Utc))
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(4, rusqlite::types::Type::Text, Box::new(e)))?,
+            status: serde_json::from_value(Value::String(status))
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(5, rusqlite::types::Type::Text, Box::new(e)))?,
+            error: row.get(6)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use shinkai_embedding::model_type::{EmbeddingModelType, OllamaTextEmbeddingsInference};
+    use shinkai_message_primitives::shinkai_message::shinkai_message_schemas::JobMessage;
+    use std::path::PathBuf;
+    use tempfile::NamedTempFile;
+
+    fn setup_test_db() -> SqliteManager {
+        let temp_file = NamedTempFile::new().unwrap();
+        let db_path = PathBuf::from(temp_file.path());
+        let api_url = String::new();
+        let model_type =
+            EmbeddingModelType::OllamaTextEmbeddingsInference(OllamaTextEmbeddingsInference::SnowflakeArcticEmbedM);
+
+        SqliteManager::new(db_path, api_url, model_type).unwrap()
+    }
+
+    fn scheduled_message(id: &str, job_id: &str, send_at: DateTime<Utc>) -> ScheduledJobMessage {
+        ScheduledJobMessage {
+            id: id.to_string(),
+            job_id: job_id.to_string(),
+            job_message: JobMessage {
+                job_id: job_id.to_string(),
+                content: "Run the weekly analysis".to_string(),
+                parent: None,
+                sheet_job_data: None,
+                tools: None,
+                callback: None,
+                metadata: None,
+                tool_key: None,
+                fs_files_paths: vec![],
+                job_filenames: vec![],
+            },
+            send_at,
+            created_at: Utc::now(),
+            status: ScheduledJobMessageStatus::Pending,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_scheduled_job_messages() {
+        let db = setup_test_db();
+        let now = Utc::now();
+        let tomorrow = scheduled_message("scheduled_1", "jobid_1", now + Duration::days(1));
+        let overdue = scheduled_message("scheduled_2", "jobid_2", now - Duration::minutes(5));
+        db.add_scheduled_job_message(&tomorrow).unwrap();
+        db.add_scheduled_job_message(&overdue).unwrap();
+
+        assert_eq!(
+            db.get_scheduled_job_messages(None).unwrap(),
+            vec![overdue.clone(), tomorrow.clone()]
+        );
+        assert_eq!(
+            db.get_scheduled_job_messages(Some("jobid_1")).unwrap(),
+            vec![tomorrow.clone()]
+        );
+        assert_eq!(db.get_due_scheduled_job_messages(now).unwrap(), vec![overdue.clone()]);
+
+        // A claimed message is no longer due and stays until it is removed once sent
+        assert!(db.claim_scheduled_job_message("scheduled_2").unwrap());
+        assert!(!db.claim_scheduled_job_message("scheduled_2").unwrap());
+        assert!(db.get_due_scheduled_job_messages(now).unwrap().is_empty());
+        assert_eq!(
+            db.get_scheduled_job_message("scheduled_2").unwrap().unwrap().status,
+            ScheduledJobMessageStatus::Sending
+        );
+
+        // Messages being sent when the node stopped are sent again
+        assert_eq!(db.release_sending_scheduled_job_messages().unwrap(), 1);
+        assert_eq!(db.get_due_scheduled_job_messages(now).unwrap(), vec![overdue.clone()]);
+
+        // A message that could not be sent is kept with the error
+        assert!(db.claim_scheduled_job_message("scheduled_2").unwrap());
+        db.fail_scheduled_job_message("scheduled_2", "Job not found").unwrap();
+        let failed = db.get_scheduled_job_message("scheduled_2").unwrap().unwrap();
+        assert_eq!(failed.status, ScheduledJobMessageStatus::Failed);
+        assert_eq!(failed.error.as_deref(), Some("Job not found"));
+        assert!(db.get_due_scheduled_job_messages(now).unwrap().is_empty());
+
+        db.remove_scheduled_job_message("scheduled_2").unwrap();
+        assert_eq!(db.get_scheduled_job_message("scheduled_2").unwrap(), None);
+        assert!(matches!(
+            db.remove_scheduled_job_message("scheduled_2"),
+            Err(SqliteManagerError::DataNotFound)
+        ));
+    }
+}
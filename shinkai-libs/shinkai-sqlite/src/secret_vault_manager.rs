@@ -0,0 +1,463 @@
+use crate::oauth_manager::OAuthToken;
+use crate::{SqliteManager, SqliteManagerError};
+use rusqlite::{params, OptionalExtension, TransactionBehavior};
+use serde_json::Value;
+use shinkai_message_primitives::shinkai_utils::secret_vault::{is_redacted_secret, is_sealed_secret, SecretVault};
+use shinkai_tools_primitives::tools::shinkai_tool::{ShinkaiTool, ShinkaiToolHeader};
+use shinkai_tools_primitives::tools::tool_config::ToolConfig;
+
+impl SqliteManager {
+    /// Unlocks the vault with a key derived from `passphrase`. The first unlock generates the salt
+    /// and seals the secrets that were stored in plaintext until then.
+    pub fn unlock_secret_vault_with_passphrase(&self, passphrase: &str) -> Result<u64, SqliteManagerError> {
+        let salt = match self.get_secret_vault_salt()? {
+            Some(Some(salt)) => hex::decode(salt).map_err(|e| SqliteManagerError::SecretVaultError(e.to_string()))?,
+            Some(None) => {
+                return Err(SqliteManagerError::SecretVaultError(
+                    "The secret vault is sealed with a keyring key, not a passphrase".to_string(),
+                ))
+            }
+            None => SecretVault::random_salt().to_vec(),
+        };
+        let vault = SecretVault::from_passphrase(passphrase, &salt)?;
+        self.unlock_secret_vault(vault, Some(salt))
+    }
+
+    /// Unlocks the vault with a random key kept outside of the database, e.g. in the OS keyring
+    pub fn unlock_secret_vault_with_key(&self, key: [u8; 32]) -> Result<u64, SqliteManagerError> {
+        if let Some(Some(_)) = self.get_secret_vault_salt()? {
+            return Err(SqliteManagerError::SecretVaultError(
+                "The secret vault is sealed with a passphrase, not a keyring key".to_string(),
+            ));
+        }
+        self.unlock_secret_vault(SecretVault::new(key), None)
+    }
+
+    /// Re-encrypts every secret with a key derived from `new_passphrase`.
+    /// Returns the number of values that were re-encrypted.
+    pub fn rotate_secret_vault_passphrase(&self, new_passphrase: &str) -> Result<u64, SqliteManagerError> {
+        let salt = SecretVault::random_salt().to_vec();
+        let new_vault = SecretVault::from_passphrase(new_passphrase, &salt)?;
+        self.rotate_secret_vault(new_vault, Some(salt))
+    }
+
+    /// Re-encrypts every secret with `new_key`.
+    /// Returns the number of values that were re-encrypted.
+    pub fn rotate_secret_vault_key(&self, new_key: [u8; 32]) -> Result<u64, SqliteManagerError> {
+        self.rotate_secret_vault(SecretVault::new(new_key), None)
+    }
+
+    pub fn is_secret_vault_unlocked(&self) -> bool {
+        self.secret_vault.read().map(|vault| vault.is_some()).unwrap_or(false)
+    }
+
+    /// Key id of the unlocked vault, `None` while secrets are stored in plaintext
+    pub fn secret_vault_key_id(&self) -> Option<String> {
+        self.secret_vault
+            .read()
+            .ok()
+            .and_then(|vault| vault.as_ref().map(|vault| vault.key_id().to_string()))
+    }
+
+    /// Seals `value` for storage. Without a vault configured secrets are stored as they are.
+    pub fn seal_secret(&self, value: &str) -> Result<String, SqliteManagerError> {
+        let vault = self.secret_vault.read().map_err(|_| SqliteManagerError::LockError)?;
+        match vault.as_ref() {
+            Some(vault) if !value.is_empty() && !is_redacted_secret(value) => Ok(vault.seal(value)?),
+            _ => Ok(value.to_string()),
+        }
+    }
+
+    /// Decrypts a value read from storage. Only call this right before the secret is used.
+    pub fn reveal_secret(&self, value: &str) -> Result<String, SqliteManagerError> {
+        if !is_sealed_secret(value) {
+            return Ok(value.to_string());
+        }
+        let vault = self.secret_vault.read().map_err(|_| SqliteManagerError::LockError)?;
+        let vault = vault.as_ref().ok_or(SqliteManagerError::SecretVaultLocked)?;
+        Ok(vault.reveal(value)?)
+    }
+
+    pub fn seal_optional_secret(&self, value: &Option<String>) -> Result<Option<String>, SqliteManagerError> {
+        value.as_deref().map(|value| self.seal_secret(value)).transpose()
+    }
+
+    pub fn reveal_optional_secret(&self, value: &Option<String>) -> Result<Option<String>, SqliteManagerError> {
+        value.as_deref().map(|value| self.reveal_secret(value)).transpose()
+    }
+
+    /// Seals the string key-values of the tool config
+    pub fn seal_tool_config(&self, tool: &mut ShinkaiTool) -> Result<(), SqliteManagerError> {
+        Self::map_tool_secrets(tool, |value| self.seal_secret(value))?;
+        Ok(())
+    }
+
+    /// Decrypts the key-values of the tool config before the tool is run
+    pub fn reveal_tool_config(&self, tool: &mut ShinkaiTool) -> Result<(), SqliteManagerError> {
+        Self::map_tool_secrets(tool, |value| self.reveal_secret(value))?;
+        Ok(())
+    }
+
+    /// Decrypts the access and refresh tokens and the token secret before they are handed to a
+    /// tool or refreshed
+    pub fn reveal_oauth_token(&self, token: &mut OAuthToken) -> Result<(), SqliteManagerError> {
+        token.access_token = self.reveal_optional_secret(&token.access_token)?;
+        token.refresh_token = self.reveal_optional_secret(&token.refresh_token)?;
+        token.token_secret = self.reveal_optional_secret(&token.token_secret)?;
+        Ok(())
+    }
+
+    // Outer None when the vault was never set up, inner None when it uses a keyring key
+    fn get_secret_vault_salt(&self) -> Result<Option<Option<String>>, SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let salt = conn
+            .query_row("SELECT kdf_salt FROM shinkai_secret_vault WHERE id = 1", [], |row| {
+                row.get(0)
+            })
+            .optional()?;
+        Ok(salt)
+    }
+
+    fn unlock_secret_vault(&self, vault: SecretVault, kdf_salt: Option<Vec<u8>>) -> Result<u64, SqliteManagerError> {
+        let mut conn = self.get_connection()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+
+        let key_id: Option<String> = tx
+            .query_row("SELECT key_id FROM shinkai_secret_vault WHERE id = 1", [], |row| {
+                row.get(0)
+            })
+            .optional()?;
+        match key_id {
+            Some(key_id) if key_id != vault.key_id() => {
+                return Err(SqliteManagerError::SecretVaultError(
+                    "Wrong passphrase or key for the secret vault".to_string(),
+                ));
+            }
+            Some(_) => {}
+            None => {
+                tx.execute(
+                    "INSERT INTO shinkai_secret_vault (id, key_id, kdf_salt, updated_at) VALUES (1, ?1, ?2, ?3)",
+                    params![vault.key_id(), kdf_salt.map(hex::encode), chrono::Utc::now().to_rfc3339()],
+                )?;
+            }
+        }
+
+        // Secrets stored before the vault was set up, or while it was locked, are still in plaintext
+        let sealed = Self::reseal_secrets(&tx, |value| Ok(vault.seal(value)?))?;
+        tx.commit()?;
+
+        *self.secret_vault.write().map_err(|_| SqliteManagerError::LockError)? = Some(vault);
+        Ok(sealed)
+    }
+
+    fn rotate_secret_vault(&self, new_vault: SecretVault, kdf_salt: Option<Vec<u8>>) -> Result<u64, SqliteManagerError> {
+        // Held for the whole rotation so nothing is sealed with the old key in the meantime
+        let mut current = self.secret_vault.write().map_err(|_| SqliteManagerError::LockError)?;
+        let old_vault = current.as_ref().ok_or(SqliteManagerError::SecretVaultLocked)?;
+
+        let mut conn = self.get_connection()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let resealed = Self::reseal_secrets(&tx, |value| Ok(new_vault.seal(&old_vault.reveal(value)?)?))?;
+        tx.execute(
+            "INSERT OR REPLACE INTO shinkai_secret_vault (id, key_id, kdf_salt, updated_at) VALUES (1, ?1, ?2, ?3)",
+            params![new_vault.key_id(), kdf_salt.map(hex::encode), chrono::Utc::now().to_rfc3339()],
+        )?;
+        tx.commit()?;
+
+        *current = Some(new_vault);
+        Ok(resealed)
+    }
+
+    /// Applies `reseal` to every stored secret and returns how many values changed
+    pub(crate) fn reseal_secrets<F>(tx: &rusqlite::Transaction, seal: F) -> Result<u64, SqliteManagerError>
+    where
+        F: Fn(&str) -> Result<String, SqliteManagerError>,
+    {
+        let reseal = |value: &str| -> Result<String, SqliteManagerError> {
+            if value.is_empty() || is_redacted_secret(value) {
+                return Ok(value.to_string());
+            }
+            seal(value)
+        };
+        let mut resealed = 0;
+
+        let api_keys = tx
+            .prepare("SELECT db_llm_provider_id, api_key FROM llm_providers WHERE api_key IS NOT NULL")?
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        for (llm_provider_id, api_key) in api_keys {
+            let sealed = reseal(&api_key)?;
+            if sealed != api_key {
+                tx.execute(
+                    "UPDATE llm_providers SET api_key = ?1 WHERE db_llm_provider_id = ?2",
+                    params![sealed, llm_provider_id],
+                )?;
+                resealed += 1;
+            }
+        }
+
+        let oauth_tokens = tx
+            .prepare("SELECT id, access_token, refresh_token, token_secret FROM oauth_tokens")?
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, Option<String>>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        for (id, access_token, refresh_token, token_secret) in oauth_tokens {
+            let sealed_access_token = access_token.as_deref().map(|token| reseal(token)).transpose()?;
+            let sealed_refresh_token = refresh_token.as_deref().map(|token| reseal(token)).transpose()?;
+            let sealed_token_secret = token_secret.as_deref().map(|secret| reseal(secret)).transpose()?;
+            if sealed_access_token != access_token
+                || sealed_refresh_token != refresh_token
+                || sealed_token_secret != token_secret
+            {
+                tx.execute(
+                    "UPDATE oauth_tokens SET access_token = ?1, refresh_token = ?2, token_secret = ?3 WHERE id = ?4",
+                    params![sealed_access_token, sealed_refresh_token, sealed_token_secret, id],
+                )?;
+                resealed += 1;
+            }
+        }
+
+        // Tool headers are returned by the tool listings, so they only keep redacted values
+        let tools = tx
+            .prepare("SELECT rowid, tool_data, tool_header FROM shinkai_tools")?
+            .query_map([], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, Vec<u8>>(1)?, row.get::<_, Vec<u8>>(2)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        for (rowid, tool_data, tool_header) in tools {
+            let mut tool: ShinkaiTool = serde_json::from_slice(&tool_data)?;
+            if Self::map_tool_secrets(&mut tool, |value| reseal(value))? == 0 {
+                continue;
+            }
+            let mut header: ShinkaiToolHeader = serde_json::from_slice(&tool_header)?;
+            header.redact_config();
+            tx.execute(
+                "UPDATE shinkai_tools SET tool_data = ?1, tool_header = ?2 WHERE rowid = ?3",
+                params![serde_json::to_vec(&tool)?, serde_json::to_vec(&header)?, rowid],
+            )?;
+            resealed += 1;
+        }
+
+        let revisions = tx
+            .prepare("SELECT rowid, tool_data FROM shinkai_tool_revisions")?
+            .query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, Vec<u8>>(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        for (rowid, tool_data) in revisions {
+            let mut tool: ShinkaiTool = serde_json::from_slice(&tool_data)?;
+            if Self::map_tool_secrets(&mut tool, |value| reseal(value))? == 0 {
+                continue;
+            }
+            tx.execute(
+                "UPDATE shinkai_tool_revisions SET tool_data = ?1 WHERE rowid = ?2",
+                params![serde_json::to_vec(&tool)?, rowid],
+            )?;
+            resealed += 1;
+        }
+
+        let webhook_secrets = tx
+            .prepare("SELECT id, secret FROM shinkai_webhooks")?
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        for (webhook_id, secret) in webhook_secrets {
+            let sealed = reseal(&secret)?;
+            if sealed != secret {
+                tx.execute(
+                    "UPDATE shinkai_webhooks SET secret = ?1 WHERE id = ?2",
+                    params![sealed, webhook_id],
+                )?;
+                resealed += 1;
+            }
+        }
+
+        let trigger_secrets = tx
+            .prepare("SELECT id, secret FROM shinkai_webhook_triggers")?
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        for (trigger_id, secret) in trigger_secrets {
+            let sealed = reseal(&secret)?;
+            if sealed != secret {
+                tx.execute(
+                    "UPDATE shinkai_webhook_triggers SET secret = ?1 WHERE id = ?2",
+                    params![sealed, trigger_id],
+                )?;
+                resealed += 1;
+            }
+        }
+
+        Ok(resealed)
+    }
+
+    // Applies `map` to the string key-values of the tool config and returns how many changed
+    fn map_tool_secrets<F>(tool: &mut ShinkaiTool, map: F) -> Result<usize, SqliteManagerError>
+    where
+        F: Fn(&str) -> Result<String, SqliteManagerError>,
+    {
+        let mut changed = 0;
+        if let Some(configs) = tool.get_config_mut() {
+            for config in configs.iter_mut() {
+                let ToolConfig::BasicConfig(basic) = config;
+                if let Some(Value::String(value)) = &basic.key_value {
+                    let mapped = map(value)?;
+                    if &mapped != value {
+                        basic.key_value = Some(Value::String(mapped));
+                        changed += 1;
+                    }
+                }
+            }
+        }
+        Ok(changed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use shinkai_embedding::model_type::{EmbeddingModelType, OllamaTextEmbeddingsInference};
+    use shinkai_message_primitives::schemas::{
+        llm_providers::serialized_llm_provider::{LLMProviderInterface, OpenAI, SerializedLLMProvider},
+        shinkai_name::ShinkaiName,
+    };
+    use std::path::Path;
+
+    fn open_test_db(db_path: &Path) -> SqliteManager {
+        let api_url = String::new();
+        let model_type =
+            EmbeddingModelType::OllamaTextEmbeddingsInference(OllamaTextEmbeddingsInference::SnowflakeArcticEmbedM);
+
+        SqliteManager::new(db_path, api_url, model_type).unwrap()
+    }
+
+    fn create_test_token() -> OAuthToken {
+        OAuthToken {
+            id: 0,
+            connection_name: "test_connection".to_string(),
+            state: "test_state".to_string(),
+            code: None,
+            app_id: "1".to_string(),
+            tool_id: "2".to_string(),
+            tool_key: "test_tool".to_string(),
+            access_token: Some("access_token".to_string()),
+            access_token_expires_at: Some(Utc::now()),
+            refresh_token: Some("refresh_token".to_string()),
+            refresh_token_enabled: Some(true),
+            refresh_token_expires_at: Some(Utc::now()),
+            token_secret: Some("token_secret".to_string()),
+            response_type: "code".to_string(),
+            id_token: None,
+            scope: None,
+            pkce_type: None,
+            pkce_code_verifier: None,
+            expires_at: Some(Utc::now()),
+            metadata_json: None,
+            authorization_url: Some("https://example.com/oauth/authorize".to_string()),
+            token_url: Some("https://example.com/oauth/token".to_string()),
+            client_id: Some("client123".to_string()),
+            client_secret: Some("secret456".to_string()),
+            redirect_url: Some("https://example.com/callback".to_string()),
+            version: "1.0.0".to_string(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            request_token_auth_header: None,
+            request_token_content_type: None,
+        }
+    }
+
+    #[test]
+    fn test_secret_vault_migration_and_rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("vault.db");
+        let db = open_test_db(&db_path);
+
+        let identity = ShinkaiName::new("@@alice.shinkai/main/agent/gpt".to_string()).unwrap();
+        let profile = identity.extract_profile().unwrap();
+        let provider = SerializedLLMProvider {
+            id: "gpt".to_string(),
+            full_identity_name: identity,
+            external_url: Some("https://api.openai.com".to_string()),
+            api_key: Some("sk-plaintext".to_string()),
+            model: LLMProviderInterface::OpenAI(OpenAI {
+                model_type: "gpt-4o".to_string(),
+            }),
+        };
+        db.add_llm_provider(provider.clone(), &profile).unwrap();
+        db.add_oauth_token(&create_test_token()).unwrap();
+
+        // Unlocking seals what was stored in plaintext
+        assert_eq!(db.unlock_secret_vault_with_passphrase("first passphrase").unwrap(), 2);
+        let stored = db.get_llm_provider("gpt", &profile).unwrap().unwrap();
+        let sealed_api_key = stored.api_key.clone().unwrap();
+        assert!(is_sealed_secret(&sealed_api_key));
+        assert_eq!(db.reveal_secret(&sealed_api_key).unwrap(), "sk-plaintext");
+        let mut token = db
+            .get_oauth_token("test_connection".to_string(), "test_tool".to_string())
+            .unwrap()
+            .unwrap();
+        assert!(is_sealed_secret(token.access_token.as_deref().unwrap()));
+        assert!(is_sealed_secret(token.token_secret.as_deref().unwrap()));
+        db.reveal_oauth_token(&mut token).unwrap();
+        assert_eq!(token.access_token.as_deref(), Some("access_token"));
+        assert_eq!(token.refresh_token.as_deref(), Some("refresh_token"));
+        assert_eq!(token.token_secret.as_deref(), Some("token_secret"));
+
+        // Tokens written after unlocking are sealed too
+        db.update_oauth_token(&token).unwrap();
+        let stored_token = db
+            .get_oauth_token("test_connection".to_string(), "test_tool".to_string())
+            .unwrap()
+            .unwrap();
+        assert!(is_sealed_secret(stored_token.token_secret.as_deref().unwrap()));
+
+        // Writes after unlocking are sealed right away
+        db.update_llm_provider(
+            SerializedLLMProvider {
+                api_key: Some("sk-updated".to_string()),
+                ..provider.clone()
+            },
+            &profile,
+        )
+        .unwrap();
+        let stored = db.get_llm_provider("gpt", &profile).unwrap().unwrap();
+        assert_eq!(db.reveal_optional_secret(&stored.api_key).unwrap().as_deref(), Some("sk-updated"));
+
+        // A redacted key sent back by a client keeps the stored one
+        db.update_llm_provider(
+            SerializedLLMProvider {
+                api_key: Some(shinkai_message_primitives::shinkai_utils::secret_vault::REDACTED_SECRET.to_string()),
+                ..provider.clone()
+            },
+            &profile,
+        )
+        .unwrap();
+        let stored = db.get_llm_provider("gpt", &profile).unwrap().unwrap();
+        assert_eq!(db.reveal_optional_secret(&stored.api_key).unwrap().as_deref(), Some("sk-updated"));
+
+        // Sealed values can't be read without the vault, nor with the wrong passphrase
+        let reopened = open_test_db(&db_path);
+        assert!(matches!(
+            reopened.reveal_optional_secret(&stored.api_key),
+            Err(SqliteManagerError::SecretVaultLocked)
+        ));
+        assert!(reopened.unlock_secret_vault_with_passphrase("wrong passphrase").is_err());
+        assert!(reopened.unlock_secret_vault_with_key(SecretVault::random_key()).is_err());
+
+        assert_eq!(db.rotate_secret_vault_passphrase("second passphrase").unwrap(), 2);
+        let rotated = db.get_llm_provider("gpt", &profile).unwrap().unwrap();
+        assert_ne!(rotated.api_key, stored.api_key);
+        assert_eq!(db.reveal_optional_secret(&rotated.api_key).unwrap().as_deref(), Some("sk-updated"));
+
+        let reopened = open_test_db(&db_path);
+        assert!(reopened.unlock_secret_vault_with_passphrase("first passphrase").is_err());
+        assert_eq!(reopened.unlock_secret_vault_with_passphrase("second passphrase").unwrap(), 0);
+        assert_eq!(
+            reopened.reveal_optional_secret(&rotated.api_key).unwrap().as_deref(),
+            Some("sk-updated")
+        );
+    }
+}
@@ -24,6 +24,10 @@ impl SqliteManager {
         tool: ShinkaiTool,
         embedding: Vec<f32>,
     ) -> Result<ShinkaiTool, SqliteManagerError> {
+        // Config values are sealed at rest, the header keeps them redacted
+        let mut tool = tool;
+        self.seal_tool_config(&mut tool)?;
+
         let mut conn = self.get_connection()?;
         let tx = conn.transaction()?;
 
@@ -43,7 +47,9 @@ impl SqliteManager {
 
         let tool_seos = tool.format_embedding_string();
         let tool_type = tool.tool_type().to_string();
-        let tool_header = serde_json::to_vec(&tool.to_header()).unwrap();
+        let mut tool_header = tool.to_header();
+        tool_header.redact_config();
+        let tool_header = serde_json::to_vec(&tool_header).unwrap();
 
         // Clone the tool to make it mutable
         let mut tool_clone = tool.clone();
@@ -124,6 +130,9 @@ impl SqliteManager {
             params![cast_slice(&embedding), is_enabled as i32, is_network as i32, tool_key],
         )?;
 
+        // Keep an immutable snapshot of what was stored
+        self.record_tool_revision(&tx, &tool_clone)?;
+
         // Update the FTS table using the in-memory connection
         self.update_tools_fts(&tool)?;
 
@@ -147,103 +156,23 @@ impl SqliteManager {
         let tool_key = new_tool.tool_router_key().to_string_without_version();
         let old_tool = self.get_tool_by_key(&tool_key)?;
 
-        // Get configurations based on tool type
-        let (_old_config, upgraded): (Vec<ToolConfig>, ShinkaiTool) = match (old_tool, new_tool) {
+        // Carry the configured values of the old version over to the new one
+        let upgraded = match (old_tool, new_tool) {
             (ShinkaiTool::Deno(old_deno, _), ShinkaiTool::Deno(mut new_deno, is_enabled)) => {
-                let old_config = old_deno.config.clone();
-
-                // Merge configuration
-                let merged_config: Vec<ToolConfig> = new_deno
-                    .config
-                    .into_iter()
-                    .map(|new_entry| match new_entry {
-                        ToolConfig::BasicConfig(new_basic) => {
-                            let preserved_value = old_config.iter().find_map(|old_entry| match old_entry {
-                                ToolConfig::BasicConfig(old_basic) => {
-                                    if old_basic.key_name == new_basic.key_name {
-                                        return old_basic.key_value.clone();
-                                    }
-                                    None
-                                }
-                                _ => None,
-                            });
-                            ToolConfig::BasicConfig(BasicConfig {
-                                key_name: new_basic.key_name,
-                                description: new_basic.description,
-                                required: new_basic.required,
-                                type_name: new_basic.type_name,
-                                key_value: preserved_value,
-                            })
-                        }
-                    })
-                    .collect();
-
-                new_deno.config = merged_config;
-                (old_config, ShinkaiTool::Deno(new_deno, is_enabled))
+                new_deno.config = Self::merge_tool_config(&old_deno.config, new_deno.config);
+                ShinkaiTool::Deno(new_deno, is_enabled)
             }
             (ShinkaiTool::Network(old_network, _), ShinkaiTool::Network(mut new_network, is_enabled)) => {
-                let old_config = old_network.config.clone();
-
-                // Merge configuration
-                let merged_config: Vec<ToolConfig> = new_network
-                    .config
-                    .into_iter()
-                    .map(|new_entry| match new_entry {
-                        ToolConfig::BasicConfig(new_basic) => {
-                            let preserved_value = old_config.iter().find_map(|old_entry| match old_entry {
-                                ToolConfig::BasicConfig(old_basic) => {
-                                    if old_basic.key_name == new_basic.key_name {
-                                        return old_basic.key_value.clone();
-                                    }
-                                    None
-                                }
-                                _ => None,
-                            });
-                            ToolConfig::BasicConfig(BasicConfig {
-                                key_name: new_basic.key_name,
-                                description: new_basic.description,
-                                required: new_basic.required,
-                                type_name: new_basic.type_name,
-                                key_value: preserved_value,
-                            })
-                        }
-                    })
-                    .collect();
-
-                new_network.config = merged_config;
-                (old_config, ShinkaiTool::Network(new_network, is_enabled))
+                new_network.config = Self::merge_tool_config(&old_network.config, new_network.config);
+                ShinkaiTool::Network(new_network, is_enabled)
             }
             (ShinkaiTool::Python(old_python, _), ShinkaiTool::Python(mut new_python, is_enabled)) => {
-                let old_config = old_python.config.clone();
-
-                // Merge configuration
-                let merged_config: Vec<ToolConfig> = new_python
-                    .config
-                    .into_iter()
-                    .map(|new_entry| match new_entry {
-                        ToolConfig::BasicConfig(new_basic) => {
-                            let preserved_value = old_config.iter().find_map(|old_entry| match old_entry {
-                                ToolConfig::BasicConfig(old_basic) => {
-                                    if old_basic.key_name == new_basic.key_name {
-                                        return old_basic.key_value.clone();
-                                    }
-                                    None
-                                }
-                                _ => None,
-                            });
-                            ToolConfig::BasicConfig(BasicConfig {
-                                key_name: new_basic.key_name,
-                                description: new_basic.description,
-                                required: new_basic.required,
-                                type_name: new_basic.type_name,
-                                key_value: preserved_value,
-                            })
-                        }
-                    })
-                    .collect();
-
-                new_python.config = merged_config;
-                (old_config, ShinkaiTool::Python(new_python, is_enabled))
+                new_python.config = Self::merge_tool_config(&old_python.config, new_python.config);
+                ShinkaiTool::Python(new_python, is_enabled)
+            }
+            (ShinkaiTool::Wasm(old_wasm, _), ShinkaiTool::Wasm(mut new_wasm, is_enabled)) => {
+                new_wasm.config = Self::merge_tool_config(&old_wasm.config, new_wasm.config);
+                ShinkaiTool::Wasm(new_wasm, is_enabled)
             }
             _ => return Err(SqliteManagerError::ToolTypeMismatch),
         };
@@ -252,6 +181,28 @@ impl SqliteManager {
         self.add_tool_with_vector(upgraded.clone(), embedding)
     }
 
+    /// Config entries of the new version, keeping the values set on the entries of the old
+    /// version with the same key name
+    fn merge_tool_config(old_config: &[ToolConfig], new_config: Vec<ToolConfig>) -> Vec<ToolConfig> {
+        new_config
+            .into_iter()
+            .map(|new_entry| match new_entry {
+                ToolConfig::BasicConfig(new_basic) => {
+                    let preserved_value = old_config.iter().find_map(|old_entry| match old_entry {
+                        ToolConfig::BasicConfig(old_basic) if old_basic.key_name == new_basic.key_name => {
+                            old_basic.key_value.clone()
+                        }
+                        _ => None,
+                    });
+                    ToolConfig::BasicConfig(BasicConfig {
+                        key_value: preserved_value,
+                        ..new_basic
+                    })
+                }
+            })
+            .collect()
+    }
+
     // Performs a vector search for tools using a precomputed vector
     pub fn tool_vector_search_with_vector(
         &self,
@@ -406,6 +357,10 @@ impl SqliteManager {
         tool: ShinkaiTool,
         embedding: Vec<f32>,
     ) -> Result<ShinkaiTool, SqliteManagerError> {
+        // Config values are sealed at rest, the header keeps them redacted
+        let mut tool = tool;
+        self.seal_tool_config(&mut tool)?;
+
         let mut conn = self.get_connection()?;
         let tx = conn.transaction()?;
 
@@ -435,7 +390,9 @@ impl SqliteManager {
         })?;
 
         // Generate the tool header
-        let tool_header = serde_json::to_vec(&tool.to_header()).unwrap();
+        let mut tool_header = tool.to_header();
+        tool_header.redact_config();
+        let tool_header = serde_json::to_vec(&tool_header).unwrap();
 
         // Determine if the tool can be enabled
         let is_enabled = tool.is_enabled() && tool.can_be_enabled();
@@ -487,6 +444,11 @@ impl SqliteManager {
             ],
         )?;
 
+        // Keep an immutable snapshot of what was stored
+        let mut revision_tool = tool.clone();
+        revision_tool.set_embedding(embedding.clone());
+        self.record_tool_revision(&tx, &revision_tool)?;
+
         // Update the vector using the same transaction
         self.update_tools_vector(&tx, &tool_key, embedding)?;
 
@@ -1003,6 +965,8 @@ mod tests {
             runner: RunnerType::OnlyHost,
             operating_system: vec![OperatingSystem::Windows],
             tool_set: None,
+            test_cases: None,
+            network_allowlist: None,
         };
 
         // Wrap the DenoTool in a ShinkaiTool::Deno variant
@@ -1087,6 +1051,8 @@ mod tests {
             runner: RunnerType::OnlyHost,
             operating_system: vec![OperatingSystem::Windows],
             tool_set: None,
+            test_cases: None,
+            network_allowlist: None,
         };
 
         let tool_router_key = ToolRouterKey::new(
@@ -1121,6 +1087,8 @@ mod tests {
             runner: RunnerType::OnlyHost,
             operating_system: vec![OperatingSystem::Windows],
             tool_set: None,
+            test_cases: None,
+            network_allowlist: None,
         };
 
         let tool_router_key = ToolRouterKey::new(
@@ -1155,6 +1123,8 @@ mod tests {
             runner: RunnerType::OnlyHost,
             operating_system: vec![OperatingSystem::Windows],
             tool_set: None,
+            test_cases: None,
+            network_allowlist: None,
         };
 
         let shinkai_tool_1 = ShinkaiTool::Deno(deno_tool_1, true);
@@ -1226,6 +1196,8 @@ mod tests {
             runner: RunnerType::OnlyHost,
             operating_system: vec![OperatingSystem::Windows],
             tool_set: None,
+            test_cases: None,
+            network_allowlist: None,
         };
 
         let tool_router_key = ToolRouterKey::new(
@@ -1260,6 +1232,8 @@ mod tests {
             runner: RunnerType::OnlyHost,
             operating_system: vec![OperatingSystem::Windows],
             tool_set: None,
+            test_cases: None,
+            network_allowlist: None,
         };
 
         let tool_router_key = ToolRouterKey::new(
@@ -1294,6 +1268,8 @@ mod tests {
             runner: RunnerType::OnlyHost,
             operating_system: vec![OperatingSystem::Windows],
             tool_set: None,
+            test_cases: None,
+            network_allowlist: None,
         };
 
         // Wrap the DenoTools in ShinkaiTool::Deno variants
@@ -1395,6 +1371,8 @@ mod tests {
             runner: RunnerType::OnlyHost,
             operating_system: vec![OperatingSystem::Windows],
             tool_set: None,
+            test_cases: None,
+            network_allowlist: None,
         };
 
         // Wrap the DenoTool in a ShinkaiTool::Deno variant
@@ -1467,6 +1445,8 @@ mod tests {
                 runner: RunnerType::OnlyHost,
                 operating_system: vec![OperatingSystem::Windows],
                 tool_set: None,
+                test_cases: None,
+                network_allowlist: None,
             },
             DenoTool {
                 name: "Text Analysis Helper".to_string(),
@@ -1493,6 +1473,8 @@ mod tests {
                 runner: RunnerType::OnlyHost,
                 operating_system: vec![OperatingSystem::Windows],
                 tool_set: None,
+                test_cases: None,
+                network_allowlist: None,
             },
             DenoTool {
                 name: "Data Visualization Tool".to_string(),
@@ -1519,6 +1501,8 @@ mod tests {
                 runner: RunnerType::OnlyHost,
                 operating_system: vec![OperatingSystem::Windows],
                 tool_set: None,
+                test_cases: None,
+                network_allowlist: None,
             },
         ];
 
@@ -1595,6 +1579,8 @@ mod tests {
             runner: RunnerType::OnlyHost,
             operating_system: vec![OperatingSystem::Windows],
             tool_set: None,
+            test_cases: None,
+            network_allowlist: None,
         };
 
         let tool_router_key = ToolRouterKey::new(
@@ -1629,6 +1615,8 @@ mod tests {
             runner: RunnerType::OnlyHost,
             operating_system: vec![OperatingSystem::Windows],
             tool_set: None,
+            test_cases: None,
+            network_allowlist: None,
         };
 
         // Add both tools to the database
@@ -1730,6 +1718,8 @@ mod tests {
             runner: RunnerType::OnlyHost,
             operating_system: vec![OperatingSystem::Windows],
             tool_set: None,
+            test_cases: None,
+            network_allowlist: None,
         };
 
         let tool_router_key = ToolRouterKey::new(
@@ -1764,6 +1754,8 @@ mod tests {
             runner: RunnerType::OnlyHost,
             operating_system: vec![OperatingSystem::Windows],
             tool_set: None,
+            test_cases: None,
+            network_allowlist: None,
         };
 
         let usage_type = UsageType::PerUse(ToolPrice::Payment(vec![AssetPayment {
@@ -1907,6 +1899,8 @@ mod tests {
             runner: RunnerType::OnlyHost,
             operating_system: vec![OperatingSystem::Windows],
             tool_set: None,
+            test_cases: None,
+            network_allowlist: None,
         };
 
         let tool_router_key = ToolRouterKey::new(
@@ -1941,6 +1935,8 @@ mod tests {
             runner: RunnerType::OnlyHost,
             operating_system: vec![OperatingSystem::Windows],
             tool_set: None,
+            test_cases: None,
+            network_allowlist: None,
         };
 
         let tool_router_key = ToolRouterKey::new(
@@ -1975,6 +1971,8 @@ mod tests {
             runner: RunnerType::OnlyHost,
             operating_system: vec![OperatingSystem::Windows],
             tool_set: None,
+            test_cases: None,
+            network_allowlist: None,
         };
 
         // Add tools to database with specific vectors
@@ -2069,6 +2067,8 @@ mod tests {
             runner: RunnerType::OnlyHost,
             operating_system: vec![OperatingSystem::Windows],
             tool_set: None,
+            test_cases: None,
+            network_allowlist: None,
         };
 
         let tool_router_key = ToolRouterKey::new(
@@ -2103,6 +2103,8 @@ mod tests {
             runner: RunnerType::OnlyHost,
             operating_system: vec![OperatingSystem::Windows],
             tool_set: None,
+            test_cases: None,
+            network_allowlist: None,
         };
 
         // Wrap the DenoTools in ShinkaiTool::Deno variants
@@ -2209,6 +2211,8 @@ mod tests {
             runner: RunnerType::OnlyHost,
             operating_system: vec![OperatingSystem::Windows],
             tool_set: None,
+            test_cases: None,
+            network_allowlist: None,
         };
         let shinkai_tool_v1 = ShinkaiTool::Deno(deno_tool_v1.clone(), true);
         let vector_v1 = SqliteManager::generate_vector_for_testing(0.1);
@@ -2255,6 +2259,8 @@ mod tests {
             runner: RunnerType::OnlyHost,
             operating_system: vec![OperatingSystem::Windows],
             tool_set: None,
+            test_cases: None,
+            network_allowlist: None,
         };
         let shinkai_tool_v2 = ShinkaiTool::Deno(deno_tool_v2.clone(), true);
 
@@ -2333,6 +2339,8 @@ mod tests {
             runner: RunnerType::OnlyHost,
             operating_system: vec![OperatingSystem::Windows],
             tool_set: None,
+            test_cases: None,
+            network_allowlist: None,
         };
         let shinkai_tool_v1 = ShinkaiTool::Python(python_tool_v1, true);
         manager
@@ -2376,6 +2384,8 @@ mod tests {
             runner: RunnerType::OnlyHost,
             operating_system: vec![OperatingSystem::Windows],
             tool_set: None,
+            test_cases: None,
+            network_allowlist: None,
         };
         let shinkai_tool_v2 = ShinkaiTool::Python(python_tool_v2, true);
         let upgraded = manager
@@ -0,0 +1,157 @@
+use crate::{SqliteManager, SqliteManagerError};
+use rusqlite::{params, OptionalExtension, Result};
+use shinkai_tools_primitives::tools::tool_network::{ToolEgressBlock, ToolNetworkApproval};
+
+impl SqliteManager {
+    /// Stores the hosts the user approved for a tool, replacing any previous approval
+    pub fn set_tool_network_approval(
+        &self,
+        tool_key: &str,
+        allowed_hosts: &[String],
+    ) -> Result<ToolNetworkApproval, SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let tool_key = tool_key.to_lowercase();
+        let approved_at = chrono::Utc::now().to_rfc3339();
+        let hosts_json =
+            serde_json::to_string(allowed_hosts).map_err(|e| SqliteManagerError::SerializationError(e.to_string()))?;
+
+        conn.execute(
+            "INSERT OR REPLACE INTO shinkai_tool_network_approvals (tool_key, allowed_hosts, approved_at)
+             VALUES (?1, ?2, ?3)",
+            params![tool_key, hosts_json, approved_at],
+        )?;
+
+        Ok(ToolNetworkApproval {
+            tool_router_key: tool_key,
+            allowed_hosts: allowed_hosts.to_vec(),
+            approved_at,
+        })
+    }
+
+    /// Removes the network approval of a tool. Returns whether an approval existed.
+    pub fn remove_tool_network_approval(&self, tool_key: &str) -> Result<bool, SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let removed = conn.execute(
+            "DELETE FROM shinkai_tool_network_approvals WHERE tool_key = ?1",
+            params![tool_key.to_lowercase()],
+        )?;
+        Ok(removed > 0)
+    }
+
+    pub fn get_tool_network_approval(&self, tool_key: &str) -> Result<Option<ToolNetworkApproval>, SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let row = conn
+            .query_row(
+                "SELECT tool_key, allowed_hosts, approved_at FROM shinkai_tool_network_approvals WHERE tool_key = ?1",
+                params![tool_key.to_lowercase()],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?,
+                    ))
+                },
+            )
+            .optional()?;
+
+        row.map(|(tool_router_key, hosts_json, approved_at)| {
+            let allowed_hosts = serde_json::from_str(&hosts_json)
+                .map_err(|e| SqliteManagerError::SerializationError(e.to_string()))?;
+            Ok(ToolNetworkApproval {
+                tool_router_key,
+                allowed_hosts,
+                approved_at,
+            })
+        })
+        .transpose()
+    }
+
+    /// Records an outbound connection attempt rejected by the egress proxy
+    pub fn record_tool_egress_block(
+        &self,
+        tool_key: &str,
+        host: &str,
+        port: u16,
+        reason: &str,
+    ) -> Result<(), SqliteManagerError> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            "INSERT INTO shinkai_tool_egress_blocks (tool_key, host, port, reason, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                tool_key.to_lowercase(),
+                host,
+                port,
+                reason,
+                chrono::Utc::now().to_rfc3339()
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Returns the most recent blocked attempts of a tool, newest first
+    pub fn get_tool_egress_blocks(&self, tool_key: &str, limit: u32) -> Result<Vec<ToolEgressBlock>, SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(
+            "SELECT tool_key, host, port, reason, created_at FROM shinkai_tool_egress_blocks
+             WHERE tool_key = ?1 ORDER BY id DESC LIMIT ?2",
+        )?;
+
+        let blocks = stmt
+            .query_map(params![tool_key.to_lowercase(), limit], |row| {
+                Ok(ToolEgressBlock {
+                    tool_router_key: row.get(0)?,
+                    host: row.get(1)?,
+                    port: row.get(2)?,
+                    reason: row.get(3)?,
+                    created_at: row.get(4)?,
+                })
+            })?
+            .collect::<Result<Vec<ToolEgressBlock>, _>>()?;
+
+        Ok(blocks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shinkai_embedding::model_type::{EmbeddingModelType, OllamaTextEmbeddingsInference};
+    use std::path::PathBuf;
+    use tempfile::NamedTempFile;
+
+    fn setup_test_db() -> SqliteManager {
+        let temp_file = NamedTempFile::new().unwrap();
+        let db_path = PathBuf::from(temp_file.path());
+        let api_url = String::new();
+        let model_type =
+            EmbeddingModelType::OllamaTextEmbeddingsInference(OllamaTextEmbeddingsInference::SnowflakeArcticEmbedM);
+
+        SqliteManager::new(db_path, api_url, model_type).unwrap()
+    }
+
+    #[test]
+    fn test_tool_network_approvals_and_blocks() {
+        let db = setup_test_db();
+        let tool_key = "local:::__test_shinkai:::fetcher";
+
+        assert!(db.get_tool_network_approval(tool_key).unwrap().is_none());
+
+        let hosts = vec!["api.example.com".to_string(), "10.0.0.0/8".to_string()];
+        db.set_tool_network_approval(tool_key, &hosts).unwrap();
+        let approval = db.get_tool_network_approval(&tool_key.to_uppercase()).unwrap().unwrap();
+        assert_eq!(approval.allowed_hosts, hosts);
+
+        db.record_tool_egress_block(tool_key, "evil.io", 443, "Host not in allowlist")
+            .unwrap();
+        db.record_tool_egress_block(tool_key, "192.168.1.1", 80, "Host not in allowlist")
+            .unwrap();
+        let blocks = db.get_tool_egress_blocks(tool_key, 10).unwrap();
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].host, "192.168.1.1");
+        assert_eq!(blocks[1].port, 443);
+
+        assert!(db.remove_tool_network_approval(tool_key).unwrap());
+        assert!(db.get_tool_network_approval(tool_key).unwrap().is_none());
+    }
+}
@@ -218,6 +218,8 @@ impl SqliteManager {
                     vec![OperatingSystem::Linux, OperatingSystem::MacOS, OperatingSystem::Windows];
                 let mut runner = RunnerType::Any;
                 let mut tool_set = None;
+                let mut test_cases = None;
+                let mut network_allowlist = None;
                 if let Ok(tool_data) = self.get_tool_by_key(tool_router_key) {
                     // found data
                     sql_queries = tool_data.sql_queries();
@@ -229,6 +231,8 @@ impl SqliteManager {
                     operating_system = tool_data.get_operating_system();
                     runner = tool_data.get_runner();
                     tool_set = tool_data.get_tool_set();
+                    test_cases = tool_data.get_test_cases();
+                    network_allowlist = tool_data.get_network_allowlist();
                 }
 
                 Ok(ToolPlayground {
@@ -250,6 +254,8 @@ impl SqliteManager {
                         operating_system,
                         runner,
                         tool_set,
+                        test_cases,
+                        network_allowlist,
                     },
                     tool_router_key: row.get(7)?,
                     job_id: row.get(8)?,
@@ -323,6 +329,8 @@ impl SqliteManager {
                     operating_system: vec![OperatingSystem::Linux, OperatingSystem::MacOS, OperatingSystem::Windows],
                     runner: RunnerType::Any,
                     tool_set: None,
+                    test_cases: None,
+                    network_allowlist: None,
                 },
                 tool_router_key: row.get(7)?,
                 job_id: row.get(8)?,
@@ -430,6 +438,8 @@ mod tests {
             runner: RunnerType::Any,
             operating_system: vec![OperatingSystem::Linux],
             tool_set: None,
+            test_cases: None,
+            network_allowlist: None,
         };
 
         let shinkai_tool = ShinkaiTool::Deno(deno_tool, true);
@@ -462,6 +472,8 @@ mod tests {
                 operating_system: vec![OperatingSystem::Linux],
                 runner: RunnerType::Any,
                 tool_set: None,
+                test_cases: None,
+                network_allowlist: None,
             },
             tool_router_key: Some(tool_router_key),
             job_id: "job_123".to_string(),
@@ -569,6 +581,8 @@ mod tests {
             runner: RunnerType::Any,
             operating_system: vec![OperatingSystem::Linux],
             tool_set: None,
+            test_cases: None,
+            network_allowlist: None,
         };
 
         let shinkai_tool = ShinkaiTool::Deno(deno_tool, true);
@@ -654,6 +668,8 @@ mod tests {
             runner: RunnerType::Any,
             operating_system: vec![OperatingSystem::Linux],
             tool_set: None,
+            test_cases: None,
+            network_allowlist: None,
         };
 
         let shinkai_tool = ShinkaiTool::Deno(deno_tool, true);
@@ -0,0 +1,235 @@
+use crate::{SqliteManager, SqliteManagerError};
+use rusqlite::{params, Result};
+use shinkai_message_primitives::schemas::indexable_version::IndexableVersion;
+use shinkai_tools_primitives::tools::shinkai_tool::ShinkaiTool;
+use shinkai_tools_primitives::tools::tool_revision::{ToolRevision, ToolRevisionDiff, ToolRevisionHeader};
+
+impl SqliteManager {
+    /// Stores an immutable snapshot of the tool inside the given transaction.
+    /// Revisions are numbered per tool_key starting at 1.
+    pub(crate) fn record_tool_revision(
+        &self,
+        tx: &rusqlite::Transaction,
+        tool: &ShinkaiTool,
+    ) -> Result<u64, SqliteManagerError> {
+        let tool_key = tool.tool_router_key().to_string_without_version().to_lowercase();
+        let version_number = tool.version_number()?;
+
+        let last_revision: Option<i64> = tx.query_row(
+            "SELECT MAX(revision) FROM shinkai_tool_revisions WHERE tool_key = ?1",
+            params![tool_key],
+            |row| row.get(0),
+        )?;
+        let revision = last_revision.unwrap_or(0) + 1;
+
+        let tool_data = serde_json::to_vec(tool).map_err(|e| {
+            eprintln!("Serialization error: {}", e);
+            SqliteManagerError::SerializationError(e.to_string())
+        })?;
+
+        tx.execute(
+            "INSERT INTO shinkai_tool_revisions (
+                tool_key,
+                revision,
+                version,
+                version_str,
+                tool_data,
+                created_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                tool_key,
+                revision,
+                version_number,
+                tool.version(),
+                tool_data,
+                chrono::Utc::now().to_rfc3339(),
+            ],
+        )?;
+
+        Ok(revision as u64)
+    }
+
+    /// Lists all the revisions of a tool, newest first
+    pub fn get_tool_revisions(&self, tool_key: &str) -> Result<Vec<ToolRevisionHeader>, SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(
+            "SELECT tool_key, revision, version_str, tool_data, created_at
+             FROM shinkai_tool_revisions WHERE tool_key = ?1 ORDER BY revision DESC",
+        )?;
+
+        let revisions = stmt
+            .query_map(params![tool_key.to_lowercase()], Self::row_to_tool_revision)?
+            .collect::<Result<Vec<ToolRevision>, _>>()?;
+
+        Ok(revisions.iter().map(|revision| revision.to_header()).collect())
+    }
+
+    /// Retrieves a specific revision of a tool
+    pub fn get_tool_revision(&self, tool_key: &str, revision: u64) -> Result<ToolRevision, SqliteManagerError> {
+        let conn = self.get_connection()?;
+        conn.query_row(
+            "SELECT tool_key, revision, version_str, tool_data, created_at
+             FROM shinkai_tool_revisions WHERE tool_key = ?1 AND revision = ?2",
+            params![tool_key.to_lowercase(), revision as i64],
+            Self::row_to_tool_revision,
+        )
+        .map_err(|e| {
+            if e == rusqlite::Error::QueryReturnedNoRows {
+                SqliteManagerError::ToolRevisionNotFound(format!("{} (revision {})", tool_key, revision))
+            } else {
+                SqliteManagerError::DatabaseError(e)
+            }
+        })
+    }
+
+    /// Computes the differences between two revisions of the same tool
+    pub fn diff_tool_revisions(
+        &self,
+        tool_key: &str,
+        from_revision: u64,
+        to_revision: u64,
+    ) -> Result<ToolRevisionDiff, SqliteManagerError> {
+        let mut from = self.get_tool_revision(tool_key, from_revision)?;
+        let mut to = self.get_tool_revision(tool_key, to_revision)?;
+        // The diff is returned to clients, so config values are compared redacted
+        from.tool.redact_config();
+        to.tool.redact_config();
+
+        from.diff(&to)
+            .map_err(|e| SqliteManagerError::SerializationError(e.to_string()))
+    }
+
+    /// Restores the tool stored in `revision`. The restore itself is recorded as a new
+    /// revision so the history is never rewritten.
+    pub async fn rollback_tool_to_revision(
+        &self,
+        tool_key: &str,
+        revision: u64,
+    ) -> Result<ShinkaiTool, SqliteManagerError> {
+        let target = self.get_tool_revision(tool_key, revision)?;
+        let version =
+            IndexableVersion::from_string(&target.version).map_err(SqliteManagerError::VersionConversionError)?;
+
+        if self.tool_exists(&target.tool_router_key, Some(version))? {
+            self.update_tool(target.tool).await
+        } else {
+            self.add_tool(target.tool).await
+        }
+    }
+
+    fn row_to_tool_revision(row: &rusqlite::Row) -> Result<ToolRevision> {
+        let tool_data: Vec<u8> = row.get(3)?;
+        let tool: ShinkaiTool = serde_json::from_slice(&tool_data).map_err(|e| {
+            eprintln!("Deserialization error: {}", e);
+            rusqlite::Error::ToSqlConversionFailure(Box::new(SqliteManagerError::SerializationError(e.to_string())))
+        })?;
+
+        Ok(ToolRevision {
+            tool_router_key: row.get(0)?,
+            revision: row.get::<_, i64>(1)? as u64,
+            version: row.get(2)?,
+            created_at: row.get(4)?,
+            tool,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shinkai_embedding::model_type::{EmbeddingModelType, OllamaTextEmbeddingsInference};
+    use shinkai_message_primitives::schemas::tool_router_key::ToolRouterKey;
+    use shinkai_tools_primitives::tools::deno_tools::DenoTool;
+    use shinkai_tools_primitives::tools::parameters::Parameters;
+    use shinkai_tools_primitives::tools::tool_output_arg::ToolOutputArg;
+    use shinkai_tools_primitives::tools::tool_types::{OperatingSystem, RunnerType, ToolResult};
+    use std::path::PathBuf;
+    use tempfile::NamedTempFile;
+
+    async fn setup_test_db() -> SqliteManager {
+        let temp_file = NamedTempFile::new().unwrap();
+        let db_path = PathBuf::from(temp_file.path());
+        let api_url = String::new();
+        let model_type =
+            EmbeddingModelType::OllamaTextEmbeddingsInference(OllamaTextEmbeddingsInference::SnowflakeArcticEmbedM);
+
+        SqliteManager::new(db_path, api_url, model_type).unwrap()
+    }
+
+    fn deno_tool(code: &str) -> ShinkaiTool {
+        let tool_router_key = ToolRouterKey::new(
+            "local".to_string(),
+            "Deno Author".to_string(),
+            "Revision Tool".to_string(),
+            None,
+        );
+
+        ShinkaiTool::Deno(
+            DenoTool {
+                name: "Revision Tool".to_string(),
+                tool_router_key: Some(tool_router_key),
+                homepage: None,
+                author: "Deno Author".to_string(),
+                version: "1.0.0".to_string(),
+                mcp_enabled: Some(false),
+                js_code: code.to_string(),
+                tools: vec![],
+                config: vec![],
+                oauth: None,
+                description: "A Deno tool with history".to_string(),
+                keywords: vec![],
+                input_args: Parameters::new(),
+                output_arg: ToolOutputArg::empty(),
+                activated: true,
+                embedding: None,
+                result: ToolResult::new("object".to_string(), serde_json::Value::Null, vec![]),
+                sql_tables: Some(vec![]),
+                sql_queries: Some(vec![]),
+                file_inbox: None,
+                assets: None,
+                runner: RunnerType::OnlyHost,
+                operating_system: vec![OperatingSystem::Linux],
+                tool_set: None,
+                test_cases: None,
+                network_allowlist: None,
+            },
+            true,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_tool_revisions_are_recorded_and_rolled_back() {
+        let manager = setup_test_db().await;
+        let vector = SqliteManager::generate_vector_for_testing(0.1);
+
+        let original = deno_tool("console.log('v1');");
+        let tool_key = original.tool_router_key().to_string_without_version();
+        manager.add_tool_with_vector(original, vector.clone()).unwrap();
+        manager
+            .update_tool_with_vector(deno_tool("console.log('v2');"), vector.clone())
+            .unwrap();
+
+        // Both the insert and the in-place update are kept
+        let revisions = manager.get_tool_revisions(&tool_key).unwrap();
+        assert_eq!(revisions.iter().map(|r| r.revision).collect::<Vec<_>>(), vec![2, 1]);
+        assert_eq!(
+            manager.get_tool_revision(&tool_key, 1).unwrap().tool.get_code(),
+            "console.log('v1');"
+        );
+
+        let diff = manager.diff_tool_revisions(&tool_key, 1, 2).unwrap();
+        assert_eq!(diff.changes.len(), 1);
+        assert_eq!(diff.changes[0].path, "/content/0/js_code");
+
+        // Rolling back restores the code and appends a new revision
+        let restored = manager.rollback_tool_to_revision(&tool_key, 1).await.unwrap();
+        assert_eq!(restored.get_code(), "console.log('v1');");
+        assert_eq!(manager.get_tool_by_key(&tool_key).unwrap().get_code(), "console.log('v1');");
+        assert_eq!(manager.get_tool_revisions(&tool_key).unwrap()[0].revision, 3);
+
+        assert!(matches!(
+            manager.get_tool_revision(&tool_key, 42),
+            Err(SqliteManagerError::ToolRevisionNotFound(_))
+        ));
+    }
+}
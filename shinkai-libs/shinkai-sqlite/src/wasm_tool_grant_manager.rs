@@ -0,0 +1,123 @@
+use crate::{SqliteManager, SqliteManagerError};
+use rusqlite::{params, OptionalExtension, Result};
+use shinkai_tools_primitives::tools::wasm_tools::{WasmCapabilities, WasmCapabilityGrant};
+
+impl SqliteManager {
+    /// Stores the capabilities the user granted to a WASM tool, replacing any previous grant
+    pub fn set_wasm_tool_grant(
+        &self,
+        tool_key: &str,
+        capabilities: &WasmCapabilities,
+        allow_outside_storage: bool,
+    ) -> Result<WasmCapabilityGrant, SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let tool_key = tool_key.to_lowercase();
+        let approved_at = chrono::Utc::now().to_rfc3339();
+        let capabilities_json =
+            serde_json::to_string(capabilities).map_err(|e| SqliteManagerError::SerializationError(e.to_string()))?;
+
+        conn.execute(
+            "INSERT OR REPLACE INTO shinkai_wasm_tool_grants (tool_key, capabilities, allow_outside_storage, approved_at)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![tool_key, capabilities_json, allow_outside_storage, approved_at],
+        )?;
+
+        Ok(WasmCapabilityGrant {
+            tool_router_key: tool_key,
+            capabilities: capabilities.clone(),
+            allow_outside_storage,
+            approved_at,
+        })
+    }
+
+    /// Removes the grant of a WASM tool. Returns whether a grant existed.
+    pub fn remove_wasm_tool_grant(&self, tool_key: &str) -> Result<bool, SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let removed = conn.execute(
+            "DELETE FROM shinkai_wasm_tool_grants WHERE tool_key = ?1",
+            params![tool_key.to_lowercase()],
+        )?;
+        Ok(removed > 0)
+    }
+
+    pub fn get_wasm_tool_grant(&self, tool_key: &str) -> Result<Option<WasmCapabilityGrant>, SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let row = conn
+            .query_row(
+                "SELECT tool_key, capabilities, allow_outside_storage, approved_at FROM shinkai_wasm_tool_grants
+                 WHERE tool_key = ?1",
+                params![tool_key.to_lowercase()],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, bool>(2)?,
+                        row.get::<_, String>(3)?,
+                    ))
+                },
+            )
+            .optional()?;
+
+        row.map(
+            |(tool_router_key, capabilities_json, allow_outside_storage, approved_at)| {
+                let capabilities = serde_json::from_str(&capabilities_json)
+                    .map_err(|e| SqliteManagerError::SerializationError(e.to_string()))?;
+                Ok(WasmCapabilityGrant {
+                    tool_router_key,
+                    capabilities,
+                    allow_outside_storage,
+                    approved_at,
+                })
+            },
+        )
+        .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shinkai_embedding::model_type::{EmbeddingModelType, OllamaTextEmbeddingsInference};
+    use shinkai_tools_primitives::tools::wasm_tools::WasmFilesystemGrant;
+    use std::path::PathBuf;
+    use tempfile::NamedTempFile;
+
+    fn setup_test_db() -> SqliteManager {
+        let temp_file = NamedTempFile::new().unwrap();
+        let db_path = PathBuf::from(temp_file.path());
+        let api_url = String::new();
+        let model_type =
+            EmbeddingModelType::OllamaTextEmbeddingsInference(OllamaTextEmbeddingsInference::SnowflakeArcticEmbedM);
+
+        SqliteManager::new(db_path, api_url, model_type).unwrap()
+    }
+
+    #[test]
+    fn test_wasm_tool_grants() {
+        let db = setup_test_db();
+        let tool_key = "local:::__test_shinkai:::wasm_tool";
+
+        assert!(db.get_wasm_tool_grant(tool_key).unwrap().is_none());
+
+        let capabilities = WasmCapabilities {
+            filesystem: vec![WasmFilesystemGrant {
+                host_path: "data".to_string(),
+                guest_path: None,
+                read_only: true,
+            }],
+            env: vec![],
+            hosts: vec!["api.example.com".to_string()],
+        };
+        db.set_wasm_tool_grant(tool_key, &capabilities, false).unwrap();
+        let grant = db.get_wasm_tool_grant(&tool_key.to_uppercase()).unwrap().unwrap();
+        assert_eq!(grant.capabilities, capabilities);
+        assert!(!grant.allow_outside_storage);
+
+        db.set_wasm_tool_grant(tool_key, &capabilities, true).unwrap();
+        assert!(db.get_wasm_tool_grant(tool_key).unwrap().unwrap().allow_outside_storage);
+
+        assert!(db.remove_wasm_tool_grant(tool_key).unwrap());
+        assert!(db.get_wasm_tool_grant(tool_key).unwrap().is_none());
+        assert!(!db.remove_wasm_tool_grant(tool_key).unwrap());
+    }
+}
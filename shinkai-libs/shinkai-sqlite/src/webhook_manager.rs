@@ -0,0 +1,494 @@
+use crate::{SqliteManager, SqliteManagerError};
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::{params, OptionalExtension, Result, ToSql, Transaction, TransactionBehavior};
+use serde_json::Value;
+use shinkai_message_primitives::schemas::webhook::{
+    Webhook, WebhookDelivery, WebhookDeliveryFilter, WebhookDeliveryStatus, WebhookEventType, WEBHOOK_MAX_ATTEMPTS,
+};
+use std::time::Duration;
+
+const WEBHOOK_COLUMNS: &str = "id, url, events, secret, description, enabled, created_at";
+
+const WEBHOOK_DELIVERY_COLUMNS: &str = "id, webhook_id, event_type, payload, status, attempts, next_attempt_at, \
+     last_status_code, last_error, created_at, delivered_at";
+
+// Fixed width, so comparing timestamps as text keeps their order
+fn format_time(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Nanos, true)
+}
+
+impl SqliteManager {
+    pub fn add_webhook(&self, webhook: &Webhook) -> Result<(), SqliteManagerError> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            "INSERT INTO shinkai_webhooks (id, url, events, secret, description, enabled, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                webhook.id,
+                webhook.url,
+                serde_json::to_string(&webhook.events)?,
+                self.seal_secret(&webhook.secret)?,
+                webhook.description,
+                webhook.enabled,
+                format_time(webhook.created_at),
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn update_webhook(&self, webhook: &Webhook) -> Result<(), SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let updated = conn.execute(
+            "UPDATE shinkai_webhooks SET url = ?1, events = ?2, secret = ?3, description = ?4, enabled = ?5
+             WHERE id = ?6",
+            params![
+                webhook.url,
+                serde_json::to_string(&webhook.events)?,
+                self.seal_secret(&webhook.secret)?,
+                webhook.description,
+                webhook.enabled,
+                webhook.id,
+            ],
+        )?;
+        if updated == 0 {
+            return Err(SqliteManagerError::DataNotFound);
+        }
+        Ok(())
+    }
+
+    /// Removes the webhook along with its deliveries
+    pub fn remove_webhook(&self, webhook_id: &str) -> Result<(), SqliteManagerError> {
+        let mut conn = self.get_connection()?;
+        let tx = conn.transaction()?;
+        tx.execute(
+            "DELETE FROM shinkai_webhook_deliveries WHERE webhook_id = ?1",
+            params![webhook_id],
+        )?;
+        let removed = tx.execute("DELETE FROM shinkai_webhooks WHERE id = ?1", params![webhook_id])?;
+        if removed == 0 {
+            return Err(SqliteManagerError::DataNotFound);
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// The webhook with its secret decrypted
+    pub fn get_webhook(&self, webhook_id: &str) -> Result<Option<Webhook>, SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let webhook = conn
+            .query_row(
+                &format!("SELECT {} FROM shinkai_webhooks WHERE id = ?1", WEBHOOK_COLUMNS),
+                params![webhook_id],
+                Self::row_to_webhook,
+            )
+            .optional()?;
+        webhook.map(|webhook| self.reveal_webhook_secret(webhook)).transpose()
+    }
+
+    /// Every webhook with its secret decrypted, oldest first
+    pub fn get_all_webhooks(&self) -> Result<Vec<Webhook>, SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM shinkai_webhooks ORDER BY created_at ASC",
+            WEBHOOK_COLUMNS
+        ))?;
+        let webhooks = stmt
+            .query_map([], Self::row_to_webhook)?
+            .collect::<Result<Vec<Webhook>, _>>()?;
+        webhooks
+            .into_iter()
+            .map(|webhook| self.reveal_webhook_secret(webhook))
+            .collect()
+    }
+
+    /// Queues the event for every enabled webhook subscribed to it and returns how many
+    /// deliveries were queued
+    pub fn enqueue_webhook_event(
+        &self,
+        event_type: WebhookEventType,
+        payload: &Value,
+    ) -> Result<u64, SqliteManagerError> {
+        let mut conn = self.get_connection()?;
+        let tx = conn.transaction()?;
+        let queued = Self::enqueue_webhook_event_tx(&tx, event_type, payload)?;
+        tx.commit()?;
+        Ok(queued)
+    }
+
+    /// Same as `enqueue_webhook_event`, inside the transaction that produced the event so the
+    /// delivery is only queued if the change is committed
+    pub(crate) fn enqueue_webhook_event_tx(
+        tx: &Transaction,
+        event_type: WebhookEventType,
+        payload: &Value,
+    ) -> Result<u64, SqliteManagerError> {
+        // Node events also start the cron tasks triggered by them
+        Self::enqueue_cron_task_event_tx(tx, event_type, payload)?;
+
+        let webhooks = tx
+            .prepare(&format!(
+                "SELECT {} FROM shinkai_webhooks WHERE enabled = 1",
+                WEBHOOK_COLUMNS
+            ))?
+            .query_map([], Self::row_to_webhook)?
+            .collect::<Result<Vec<Webhook>, _>>()?;
+
+        let now = format_time(Utc::now());
+        let payload = serde_json::to_string(payload)?;
+        let mut queued = 0;
+        for webhook in webhooks.iter().filter(|webhook| webhook.accepts(event_type)) {
+            tx.execute(
+                "INSERT INTO shinkai_webhook_deliveries (webhook_id, event_type, payload, status, attempts, next_attempt_at, created_at)
+                 VALUES (?1, ?2, ?3, ?4, 0, ?5, ?5)",
+                params![
+                    webhook.id,
+                    event_type.to_string(),
+                    payload,
+                    WebhookDeliveryStatus::Pending.to_string(),
+                    now,
+                ],
+            )?;
+            queued += 1;
+        }
+        Ok(queued)
+    }
+
+    /// Pending deliveries that are due, oldest first. Their next attempt is pushed back by `lease`
+    /// so a delivery still in flight isn't picked up again by the next poll.
+    pub fn claim_due_webhook_deliveries(
+        &self,
+        limit: u64,
+        lease: Duration,
+    ) -> Result<Vec<WebhookDelivery>, SqliteManagerError> {
+        let mut conn = self.get_connection()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+
+        let now = Utc::now();
+        let deliveries = tx
+            .prepare(&format!(
+                "SELECT {} FROM shinkai_webhook_deliveries WHERE status = ?1 AND next_attempt_at <= ?2
+                 ORDER BY next_attempt_at ASC, id ASC LIMIT ?3",
+                WEBHOOK_DELIVERY_COLUMNS
+            ))?
+            .query_map(
+                params![
+                    WebhookDeliveryStatus::Pending.to_string(),
+                    format_time(now),
+                    limit as i64
+                ],
+                Self::row_to_webhook_delivery,
+            )?
+            .collect::<Result<Vec<WebhookDelivery>, _>>()?;
+
+        let lease_until = format_time(now + chrono::Duration::from_std(lease).unwrap_or_default());
+        for delivery in &deliveries {
+            tx.execute(
+                "UPDATE shinkai_webhook_deliveries SET next_attempt_at = ?1 WHERE id = ?2",
+                params![lease_until, delivery.id],
+            )?;
+        }
+        tx.commit()?;
+
+        Ok(deliveries)
+    }
+
+    pub fn mark_webhook_delivery_delivered(
+        &self,
+        delivery_id: u64,
+        status_code: u16,
+    ) -> Result<(), SqliteManagerError> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            "UPDATE shinkai_webhook_deliveries
+             SET status = ?1, attempts = attempts + 1, last_status_code = ?2, last_error = NULL, delivered_at = ?3
+             WHERE id = ?4",
+            params![
+                WebhookDeliveryStatus::Delivered.to_string(),
+                status_code,
+                format_time(Utc::now()),
+                delivery_id,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Records a failed attempt and schedules the next one with backoff. Returns `Failed` once the
+    /// delivery ran out of attempts.
+    pub fn mark_webhook_delivery_failed(
+        &self,
+        delivery_id: u64,
+        status_code: Option<u16>,
+        error: &str,
+    ) -> Result<WebhookDeliveryStatus, SqliteManagerError> {
+        let mut conn = self.get_connection()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+
+        let attempts: u32 = tx
+            .query_row(
+                "SELECT attempts FROM shinkai_webhook_deliveries WHERE id = ?1",
+                params![delivery_id],
+                |row| row.get(0),
+            )
+            .optional()?
+            .ok_or(SqliteManagerError::DataNotFound)?;
+        let attempts = attempts + 1;
+        let status = if attempts >= WEBHOOK_MAX_ATTEMPTS {
+            WebhookDeliveryStatus::Failed
+        } else {
+            WebhookDeliveryStatus::Pending
+        };
+        let retry_delay = chrono::Duration::from_std(WebhookDelivery::retry_delay(attempts)).unwrap_or_default();
+
+        tx.execute(
+            "UPDATE shinkai_webhook_deliveries
+             SET status = ?1, attempts = ?2, next_attempt_at = ?3, last_status_code = ?4, last_error = ?5
+             WHERE id = ?6",
+            params![
+                status.to_string(),
+                attempts,
+                format_time(Utc::now() + retry_delay),
+                status_code,
+                error,
+                delivery_id,
+            ],
+        )?;
+        tx.commit()?;
+
+        Ok(status)
+    }
+
+    pub fn get_webhook_delivery(&self, delivery_id: u64) -> Result<Option<WebhookDelivery>, SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let delivery = conn
+            .query_row(
+                &format!(
+                    "SELECT {} FROM shinkai_webhook_deliveries WHERE id = ?1",
+                    WEBHOOK_DELIVERY_COLUMNS
+                ),
+                params![delivery_id],
+                Self::row_to_webhook_delivery,
+            )
+            .optional()?;
+        Ok(delivery)
+    }
+
+    /// Deliveries matching the filter, newest first
+    pub fn get_webhook_deliveries(
+        &self,
+        filter: &WebhookDeliveryFilter,
+    ) -> Result<Vec<WebhookDelivery>, SqliteManagerError> {
+        let mut conditions: Vec<&str> = Vec::new();
+        let mut values: Vec<Box<dyn ToSql>> = Vec::new();
+
+        if let Some(webhook_id) = &filter.webhook_id {
+            conditions.push("webhook_id = ?");
+            values.push(Box::new(webhook_id.clone()));
+        }
+        if let Some(status) = filter.status {
+            conditions.push("status = ?");
+            values.push(Box::new(status.to_string()));
+        }
+
+        let mut query = format!("SELECT {} FROM shinkai_webhook_deliveries", WEBHOOK_DELIVERY_COLUMNS);
+        if !conditions.is_empty() {
+            query.push_str(" WHERE ");
+            query.push_str(&conditions.join(" AND "));
+        }
+        query.push_str(" ORDER BY id DESC");
+        if let Some(limit) = filter.limit {
+            query.push_str(" LIMIT ?");
+            values.push(Box::new(limit as i64));
+        }
+
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(&query)?;
+        let deliveries = stmt
+            .query_map(
+                rusqlite::params_from_iter(values.iter().map(|value| value.as_ref())),
+                Self::row_to_webhook_delivery,
+            )?
+            .collect::<Result<Vec<WebhookDelivery>, _>>()?;
+        Ok(deliveries)
+    }
+
+    /// Queues a delivery again with a fresh set of attempts, whatever its status
+    pub fn redeliver_webhook_delivery(&self, delivery_id: u64) -> Result<WebhookDelivery, SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let updated = conn.execute(
+            "UPDATE shinkai_webhook_deliveries
+             SET status = ?1, attempts = 0, next_attempt_at = ?2, delivered_at = NULL
+             WHERE id = ?3",
+            params![
+                WebhookDeliveryStatus::Pending.to_string(),
+                format_time(Utc::now()),
+                delivery_id,
+            ],
+        )?;
+        if updated == 0 {
+            return Err(SqliteManagerError::DataNotFound);
+        }
+        self.get_webhook_delivery(delivery_id)?
+            .ok_or(SqliteManagerError::DataNotFound)
+    }
+
+    fn reveal_webhook_secret(&self, mut webhook: Webhook) -> Result<Webhook, SqliteManagerError> {
+        webhook.secret = self.reveal_secret(&webhook.secret)?;
+        Ok(webhook)
+    }
+
+    fn row_to_webhook(row: &rusqlite::Row) -> rusqlite::Result<Webhook> {
+        let events: String = row.get(2)?;
+        Ok(Webhook {
+            id: row.get(0)?,
+            url: row.get(1)?,
+            events: serde_json::from_str(&events)
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(2, rusqlite::types::Type::Text, Box::new(e)))?,
+            secret: row.get(3)?,
+            description: row.get(4)?,
+            enabled: row.get(5)?,
+            created_at: parse_time(6, row.get(6)?)?,
+        })
+    }
+
+    fn row_to_webhook_delivery(row: &rusqlite::Row) -> rusqlite::Result<WebhookDelivery> {
+        fn parse_enum<T: serde::de::DeserializeOwned>(index: usize, value: String) -> rusqlite::Result<T> {
+            serde_json::from_value(Value::String(value))
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, Box::new(e)))
+        }
+
+        let payload: String = row.get(3)?;
+        Ok(WebhookDelivery {
+            id: row.get(0)?,
+            webhook_id: row.get(1)?,
+            event_type: parse_enum(2, row.get(2)?)?,
+            payload: serde_json::from_str(&payload)
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(3, rusqlite::types::Type::Text, Box::new(e)))?,
+            status: parse_enum(4, row.get(4)?)?,
+            attempts: row.get(5)?,
+            next_attempt_at: parse_time(6, row.get(6)?)?,
+            last_status_code: row.get(7)?,
+            last_error: row.get(8)?,
+            created_at: parse_time(9, row.get(9)?)?,
+            delivered_at: row
+                .get::<_, Option<String>>(10)?
+                .map(|value| parse_time(10, value))
+                .transpose()?,
+        })
+    }
+}
+
+fn parse_time(index: usize, value: String) -> rusqlite::Result<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(&value)
+        .map(|date| date.with_timezone(&Utc))
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, Box::new(e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use shinkai_embedding::model_type::{EmbeddingModelType, OllamaTextEmbeddingsInference};
+    use shinkai_message_primitives::shinkai_utils::secret_vault::is_sealed_secret;
+    use std::path::PathBuf;
+    use tempfile::NamedTempFile;
+
+    fn setup_test_db() -> SqliteManager {
+        let temp_file = NamedTempFile::new().unwrap();
+        let db_path = PathBuf::from(temp_file.path());
+        let api_url = String::new();
+        let model_type =
+            EmbeddingModelType::OllamaTextEmbeddingsInference(OllamaTextEmbeddingsInference::SnowflakeArcticEmbedM);
+
+        SqliteManager::new(db_path, api_url, model_type).unwrap()
+    }
+
+    fn test_webhook(id: &str, events: Vec<WebhookEventType>) -> Webhook {
+        Webhook {
+            id: id.to_string(),
+            url: format!("http://127.0.0.1:9550/{}", id),
+            events,
+            secret: format!("{}-secret", id),
+            description: None,
+            enabled: true,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_webhook_outbox_retries_and_redelivery() {
+        let db = setup_test_db();
+        db.unlock_secret_vault_with_passphrase("webhooks").unwrap();
+
+        db.add_webhook(&test_webhook("jobs", vec![WebhookEventType::JobCompleted]))
+            .unwrap();
+        db.add_webhook(&test_webhook("all", vec![])).unwrap();
+
+        // Secrets are sealed at rest and decrypted when read
+        let stored_secret: String = db
+            .get_connection()
+            .unwrap()
+            .query_row("SELECT secret FROM shinkai_webhooks WHERE id = 'jobs'", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert!(is_sealed_secret(&stored_secret));
+        assert_eq!(db.get_webhook("jobs").unwrap().unwrap().secret, "jobs-secret");
+
+        let payload = json!({ "job_id": "jobid_1" });
+        assert_eq!(
+            db.enqueue_webhook_event(WebhookEventType::JobCompleted, &payload)
+                .unwrap(),
+            2
+        );
+        assert_eq!(
+            db.enqueue_webhook_event(WebhookEventType::ToolFailed, &payload)
+                .unwrap(),
+            1
+        );
+
+        let lease = Duration::from_secs(60);
+        let due = db.claim_due_webhook_deliveries(10, lease).unwrap();
+        assert_eq!(due.len(), 3);
+        // Claimed deliveries aren't handed out twice
+        assert!(db.claim_due_webhook_deliveries(10, lease).unwrap().is_empty());
+
+        db.mark_webhook_delivery_delivered(due[0].id, 200).unwrap();
+        assert_eq!(
+            db.mark_webhook_delivery_failed(due[1].id, Some(500), "Internal Server Error")
+                .unwrap(),
+            WebhookDeliveryStatus::Pending
+        );
+        let retried = db.get_webhook_delivery(due[1].id).unwrap().unwrap();
+        assert_eq!(retried.attempts, 1);
+        assert_eq!(retried.last_status_code, Some(500));
+        assert!(retried.next_attempt_at > Utc::now());
+
+        for _ in 1..WEBHOOK_MAX_ATTEMPTS {
+            db.mark_webhook_delivery_failed(due[2].id, None, "connection refused")
+                .unwrap();
+        }
+        assert_eq!(
+            db.mark_webhook_delivery_failed(due[2].id, None, "connection refused")
+                .unwrap(),
+            WebhookDeliveryStatus::Failed
+        );
+
+        let failed = db
+            .get_webhook_deliveries(&WebhookDeliveryFilter {
+                status: Some(WebhookDeliveryStatus::Failed),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].attempts, WEBHOOK_MAX_ATTEMPTS);
+
+        let redelivered = db.redeliver_webhook_delivery(failed[0].id).unwrap();
+        assert_eq!(redelivered.status, WebhookDeliveryStatus::Pending);
+        assert_eq!(redelivered.attempts, 0);
+        assert_eq!(db.claim_due_webhook_deliveries(10, lease).unwrap().len(), 1);
+
+        db.remove_webhook("all").unwrap();
+        let remaining = db.get_webhook_deliveries(&WebhookDeliveryFilter::default()).unwrap();
+        assert!(remaining.iter().all(|delivery| delivery.webhook_id == "jobs"));
+    }
+}
@@ -0,0 +1,186 @@
+use crate::{SqliteManager, SqliteManagerError};
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::{params, OptionalExtension, Result};
+use serde_json::Value;
+use shinkai_message_primitives::schemas::webhook_trigger::WebhookTrigger;
+
+const WEBHOOK_TRIGGER_COLUMNS: &str =
+    "id, name, agent_id, prompt_template, secret, rate_limit_per_minute, mode, enabled, created_at";
+
+impl SqliteManager {
+    pub fn add_webhook_trigger(&self, trigger: &WebhookTrigger) -> Result<(), SqliteManagerError> {
+        let conn = self.get_connection()?;
+        conn.execute(
+            "INSERT INTO shinkai_webhook_triggers (id, name, agent_id, prompt_template, secret, rate_limit_per_minute, mode, enabled, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                trigger.id,
+                trigger.name,
+                trigger.agent_id,
+                trigger.prompt_template,
+                self.seal_secret(&trigger.secret)?,
+                trigger.rate_limit_per_minute,
+                serde_json::to_value(trigger.mode)?.as_str().unwrap_or_default(),
+                trigger.enabled,
+                trigger.created_at.to_rfc3339_opts(SecondsFormat::Nanos, true),
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn update_webhook_trigger(&self, trigger: &WebhookTrigger) -> Result<(), SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let updated = conn.execute(
+            "UPDATE shinkai_webhook_triggers
+             SET name = ?1, agent_id = ?2, prompt_template = ?3, secret = ?4, rate_limit_per_minute = ?5, mode = ?6, enabled = ?7
+             WHERE id = ?8",
+            params![
+                trigger.name,
+                trigger.agent_id,
+                trigger.prompt_template,
+                self.seal_secret(&trigger.secret)?,
+                trigger.rate_limit_per_minute,
+                serde_json::to_value(trigger.mode)?.as_str().unwrap_or_default(),
+                trigger.enabled,
+                trigger.id,
+            ],
+        )?;
+        if updated == 0 {
+            return Err(SqliteManagerError::DataNotFound);
+        }
+        Ok(())
+    }
+
+    pub fn remove_webhook_trigger(&self, trigger_id: &str) -> Result<(), SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let removed = conn.execute(
+            "DELETE FROM shinkai_webhook_triggers WHERE id = ?1",
+            params![trigger_id],
+        )?;
+        if removed == 0 {
+            return Err(SqliteManagerError::DataNotFound);
+        }
+        Ok(())
+    }
+
+    /// The trigger with its secret decrypted
+    pub fn get_webhook_trigger(&self, trigger_id: &str) -> Result<Option<WebhookTrigger>, SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let trigger = conn
+            .query_row(
+                &format!(
+                    "SELECT {} FROM shinkai_webhook_triggers WHERE id = ?1",
+                    WEBHOOK_TRIGGER_COLUMNS
+                ),
+                params![trigger_id],
+                Self::row_to_webhook_trigger,
+            )
+            .optional()?;
+        trigger
+            .map(|trigger| self.reveal_webhook_trigger_secret(trigger))
+            .transpose()
+    }
+
+    /// Every trigger with its secret decrypted, oldest first
+    pub fn get_all_webhook_triggers(&self) -> Result<Vec<WebhookTrigger>, SqliteManagerError> {
+        let conn = self.get_connection()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM shinkai_webhook_triggers ORDER BY created_at ASC",
+            WEBHOOK_TRIGGER_COLUMNS
+        ))?;
+        let triggers = stmt
+            .query_map([], Self::row_to_webhook_trigger)?
+            .collect::<Result<Vec<WebhookTrigger>, _>>()?;
+        triggers
+            .into_iter()
+            .map(|trigger| self.reveal_webhook_trigger_secret(trigger))
+            .collect()
+    }
+
+    fn reveal_webhook_trigger_secret(&self, mut trigger: WebhookTrigger) -> Result<WebhookTrigger, SqliteManagerError> {
+        trigger.secret = self.reveal_secret(&trigger.secret)?;
+        Ok(trigger)
+    }
+
+    fn row_to_webhook_trigger(row: &rusqlite::Row) -> rusqlite::Result<WebhookTrigger> {
+        let mode: String = row.get(6)?;
+        let created_at: String = row.get(8)?;
+        Ok(WebhookTrigger {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            agent_id: row.get(2)?,
+            prompt_template: row.get(3)?,
+            secret: row.get(4)?,
+            rate_limit_per_minute: row.get(5)?,
+            mode: serde_json::from_value(Value::String(mode))
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(6, rusqlite::types::Type::Text, Box::new(e)))?,
+            enabled: row.get(7)?,
+            created_at: DateTime::parse_from_rfc3339(&created_at)
+                .map(|date| date.with_timezone(&Utc))
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(8, rusqlite::types::Type::Text, Box::new(e)))?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shinkai_embedding::model_type::{EmbeddingModelType, OllamaTextEmbeddingsInference};
+    use shinkai_message_primitives::schemas::webhook_trigger::WebhookTriggerMode;
+    use shinkai_message_primitives::shinkai_utils::secret_vault::is_sealed_secret;
+    use std::path::PathBuf;
+    use tempfile::NamedTempFile;
+
+    fn setup_test_db() -> SqliteManager {
+        let temp_file = NamedTempFile::new().unwrap();
+        let db_path = PathBuf::from(temp_file.path());
+        let api_url = String::new();
+        let model_type =
+            EmbeddingModelType::OllamaTextEmbeddingsInference(OllamaTextEmbeddingsInference::SnowflakeArcticEmbedM);
+
+        SqliteManager::new(db_path, api_url, model_type).unwrap()
+    }
+
+    #[test]
+    fn test_webhook_trigger_crud() {
+        let db = setup_test_db();
+        db.unlock_secret_vault_with_passphrase("triggers").unwrap();
+
+        let mut trigger = WebhookTrigger {
+            id: "ci".to_string(),
+            name: "CI failures".to_string(),
+            agent_id: "triage_agent".to_string(),
+            prompt_template: "Triage {{payload}}".to_string(),
+            secret: "ci-secret".to_string(),
+            rate_limit_per_minute: 10,
+            mode: WebhookTriggerMode::Async,
+            enabled: true,
+            created_at: Utc::now(),
+        };
+        db.add_webhook_trigger(&trigger).unwrap();
+
+        let stored_secret: String = db
+            .get_connection()
+            .unwrap()
+            .query_row(
+                "SELECT secret FROM shinkai_webhook_triggers WHERE id = 'ci'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert!(is_sealed_secret(&stored_secret));
+        assert_eq!(db.get_webhook_trigger("ci").unwrap().unwrap(), trigger);
+
+        trigger.mode = WebhookTriggerMode::Sync;
+        trigger.enabled = false;
+        db.update_webhook_trigger(&trigger).unwrap();
+        assert_eq!(db.get_all_webhook_triggers().unwrap(), vec![trigger]);
+
+        db.remove_webhook_trigger("ci").unwrap();
+        assert!(db.get_webhook_trigger("ci").unwrap().is_none());
+        assert!(matches!(
+            db.remove_webhook_trigger("ci"),
+            Err(SqliteManagerError::DataNotFound)
+        ));
+    }
+}
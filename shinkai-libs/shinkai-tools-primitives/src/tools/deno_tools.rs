@@ -2,6 +2,7 @@ use super::parameters::Parameters;
 use super::tool_config::{OAuth, ToolConfig};
 use super::tool_output_arg::ToolOutputArg;
 use super::tool_playground::{SqlQuery, SqlTable};
+use super::tool_test_case::ToolTestCase;
 use super::tool_types::{OperatingSystem, RunnerType, ToolResult};
 use crate::tools::error::ToolError;
 use crate::tools::shared_execution::{get_files_after_with_protocol, update_result_with_modified_files};
@@ -22,6 +23,10 @@ use std::hash::RandomState;
 use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
 
+/// Env var of an execution pointing to the launcher that runs Deno with the network permissions
+/// of the tool and in a process group the node can kill, used instead of the Deno binary when set
+pub const DENO_LAUNCHER_ENV: &str = "SHINKAI_TOOL_LAUNCHER_DENO";
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct DenoTool {
     pub name: String,
@@ -48,6 +53,10 @@ pub struct DenoTool {
     pub runner: RunnerType,
     pub operating_system: Vec<OperatingSystem>,
     pub tool_set: Option<String>,
+    pub test_cases: Option<Vec<ToolTestCase>>,
+    /// Hosts, domains (`*.example.com`) or IP ranges (`10.0.0.0/8`) the tool can reach.
+    /// `None` keeps the legacy unrestricted access.
+    pub network_allowlist: Option<Vec<String>>,
 }
 
 impl<'de> serde::Deserialize<'de> for DenoTool {
@@ -85,6 +94,8 @@ impl<'de> serde::Deserialize<'de> for DenoTool {
             runner: RunnerType,
             operating_system: Vec<OperatingSystem>,
             tool_set: Option<String>,
+            test_cases: Option<Vec<ToolTestCase>>,
+            network_allowlist: Option<Vec<String>>,
         }
 
         let helper = Helper::deserialize(deserializer)?;
@@ -124,6 +135,8 @@ impl<'de> serde::Deserialize<'de> for DenoTool {
             runner: helper.runner,
             operating_system: helper.operating_system,
             tool_set: helper.tool_set,
+            test_cases: helper.test_cases,
+            network_allowlist: helper.network_allowlist,
         })
     }
 }
@@ -134,7 +147,7 @@ impl serde::Serialize for DenoTool {
         S: serde::Serializer,
     {
         use serde::ser::SerializeStruct;
-        let mut state = serializer.serialize_struct("DenoTool", 24)?;
+        let mut state = serializer.serialize_struct("DenoTool", 26)?;
         state.serialize_field("name", &self.name)?;
         if let Some(key) = &self.tool_router_key {
             state.serialize_field("tool_router_key", &key.to_string_with_version())?;
@@ -164,6 +177,8 @@ impl serde::Serialize for DenoTool {
         state.serialize_field("runner", &self.runner)?;
         state.serialize_field("operating_system", &self.operating_system)?;
         state.serialize_field("tool_set", &self.tool_set)?;
+        state.serialize_field("test_cases", &self.test_cases)?;
+        state.serialize_field("network_allowlist", &self.network_allowlist)?;
         state.end()
     }
 }
@@ -221,6 +236,8 @@ impl DenoTool {
             runner,
             operating_system,
             tool_set,
+            test_cases: None,
+            network_allowlist: None,
         }
     }
 
@@ -386,10 +403,13 @@ impl DenoTool {
                     assets_files,
                     mount_files,
                 },
-                deno_binary_path: PathBuf::from(
-                    env::var("SHINKAI_TOOLS_RUNNER_DENO_BINARY_PATH")
-                        .unwrap_or_else(|_| "./shinkai-tools-runner-resources/deno".to_string()),
-                ),
+                deno_binary_path: match envs.get(DENO_LAUNCHER_ENV) {
+                    Some(launcher) => PathBuf::from(launcher),
+                    None => PathBuf::from(
+                        env::var("SHINKAI_TOOLS_RUNNER_DENO_BINARY_PATH")
+                            .unwrap_or_else(|_| "./shinkai-tools-runner-resources/deno".to_string()),
+                    ),
+                },
                 shinkai_node_location: ShinkaiNodeLocation {
                     protocol: String::from("http"),
                     host: api_ip,
@@ -883,6 +903,8 @@ mod tests {
             runner: RunnerType::Any,
             operating_system: vec![OperatingSystem::Linux],
             tool_set: None,
+            test_cases: None,
+            network_allowlist: None,
         };
 
         let serialized = serde_json::to_string_pretty(&tool).expect("Failed to serialize DenoTool");
@@ -1009,6 +1031,8 @@ mod tests {
             runner: RunnerType::OnlyDocker,
             operating_system: vec![],
             tool_set: None,
+            test_cases: None,
+            network_allowlist: None,
         };
 
         // Test serialization/deserialization with RunnerType
@@ -1057,6 +1081,8 @@ mod tests {
             runner: RunnerType::Any,
             operating_system: vec![OperatingSystem::Linux, OperatingSystem::Windows],
             tool_set: None,
+            test_cases: None,
+            network_allowlist: None,
         };
 
         // Test serialization/deserialization with operating systems
@@ -1100,6 +1126,8 @@ mod tests {
             runner: RunnerType::Any,
             operating_system: vec![],
             tool_set: Some("test-tool-set".to_string()),
+            test_cases: None,
+            network_allowlist: None,
         };
 
         // Test serialization/deserialization with tool_set
@@ -1115,4 +1143,50 @@ mod tests {
         let deserialized: DenoTool = serde_json::from_str(&serialized).expect("Failed to deserialize DenoTool");
         assert_eq!(deserialized.tool_set, None);
     }
+
+    #[test]
+    fn test_deno_tool_test_cases() {
+        let json_data = r#"{
+            "name": "Test Tool",
+            "homepage": null,
+            "author": "Test Author",
+            "version": "1.0.0",
+            "mcp_enabled": false,
+            "js_code": "",
+            "tools": [],
+            "config": [],
+            "description": "Test description",
+            "keywords": [],
+            "input_args": {"type": "object", "properties": {}, "required": []},
+            "output_arg": {"json": ""},
+            "activated": false,
+            "embedding": null,
+            "result": {"type": "object", "properties": {}, "required": []},
+            "sql_tables": null,
+            "sql_queries": null,
+            "file_inbox": null,
+            "oauth": null,
+            "assets": null,
+            "runner": "any",
+            "operating_system": [],
+            "tool_set": null,
+            "test_cases": [{
+                "name": "echoes input",
+                "input": {"message": "hi"},
+                "assertions": [{"path": "$.message", "expected": "hi"}],
+                "tags": ["network"]
+            }]
+        }"#;
+
+        let tool: DenoTool = serde_json::from_str(json_data).expect("Failed to deserialize DenoTool");
+        let test_cases = tool.test_cases.clone().unwrap();
+        assert_eq!(test_cases.len(), 1);
+        assert!(test_cases[0].requires_network());
+        assert!(test_cases[0].evaluate(&json!({"message": "hi"})).is_empty());
+
+        // Round trip keeps the test cases
+        let serialized = serde_json::to_string(&tool).expect("Failed to serialize DenoTool");
+        let deserialized: DenoTool = serde_json::from_str(&serialized).expect("Failed to deserialize DenoTool");
+        assert_eq!(deserialized.test_cases, tool.test_cases);
+    }
 }
@@ -9,7 +9,11 @@ pub mod rust_tools;
 pub mod shared_execution;
 pub mod shinkai_tool;
 pub mod tool_config;
+pub mod tool_network;
 pub mod tool_output_arg;
 pub mod tool_playground;
+pub mod tool_revision;
 pub mod tool_router_dep;
+pub mod tool_test_case;
 pub mod tool_types;
+pub mod wasm_tools;
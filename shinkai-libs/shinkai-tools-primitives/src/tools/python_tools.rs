@@ -3,6 +3,7 @@ use super::shared_execution::update_result_with_modified_files;
 use super::tool_config::{OAuth, ToolConfig};
 use super::tool_output_arg::ToolOutputArg;
 use super::tool_playground::{SqlQuery, SqlTable};
+use super::tool_test_case::ToolTestCase;
 use super::tool_types::{OperatingSystem, RunnerType, ToolResult};
 use crate::tools::error::ToolError;
 use crate::tools::shared_execution::get_files_after_with_protocol;
@@ -21,6 +22,10 @@ use std::fs::create_dir_all;
 use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
 
+/// Env var of an execution pointing to the launcher that runs uv in a process group the node can
+/// kill, used instead of the uv binary when set
+pub const UV_LAUNCHER_ENV: &str = "SHINKAI_TOOL_LAUNCHER_UV";
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct PythonTool {
     pub version: String,
@@ -47,6 +52,10 @@ pub struct PythonTool {
     pub runner: RunnerType,
     pub operating_system: Vec<OperatingSystem>,
     pub tool_set: Option<String>,
+    pub test_cases: Option<Vec<ToolTestCase>>,
+    /// Hosts, domains (`*.example.com`) or IP ranges (`10.0.0.0/8`) the tool can reach.
+    /// `None` keeps the legacy unrestricted access.
+    pub network_allowlist: Option<Vec<String>>,
 }
 
 impl PythonTool {
@@ -327,10 +336,13 @@ impl PythonTool {
                         .map(|mount| PathBuf::from(mount))
                         .collect(),
                 },
-                uv_binary_path: PathBuf::from(
-                    env::var("SHINKAI_TOOLS_RUNNER_UV_BINARY_PATH")
-                        .unwrap_or_else(|_| "./shinkai-tools-runner-resources/uv".to_string()),
-                ),
+                uv_binary_path: match envs.get(UV_LAUNCHER_ENV) {
+                    Some(launcher) => PathBuf::from(launcher),
+                    None => PathBuf::from(
+                        env::var("SHINKAI_TOOLS_RUNNER_UV_BINARY_PATH")
+                            .unwrap_or_else(|_| "./shinkai-tools-runner-resources/uv".to_string()),
+                    ),
+                },
                 shinkai_node_location: ShinkaiNodeLocation {
                     protocol: String::from("http"),
                     host: api_ip,
@@ -430,6 +442,8 @@ impl<'de> serde::Deserialize<'de> for PythonTool {
             runner: RunnerType,
             operating_system: Vec<OperatingSystem>,
             tool_set: Option<String>,
+            test_cases: Option<Vec<ToolTestCase>>,
+            network_allowlist: Option<Vec<String>>,
         }
 
         let helper = Helper::deserialize(deserializer)?;
@@ -469,6 +483,8 @@ impl<'de> serde::Deserialize<'de> for PythonTool {
             runner: helper.runner,
             operating_system: helper.operating_system,
             tool_set: helper.tool_set,
+            test_cases: helper.test_cases,
+            network_allowlist: helper.network_allowlist,
         })
     }
 }
@@ -479,7 +495,7 @@ impl serde::Serialize for PythonTool {
         S: serde::Serializer,
     {
         use serde::ser::SerializeStruct;
-        let mut state = serializer.serialize_struct("PythonTool", 24)?;
+        let mut state = serializer.serialize_struct("PythonTool", 26)?;
         state.serialize_field("name", &self.name)?;
         if let Some(key) = &self.tool_router_key {
             state.serialize_field("tool_router_key", &key.to_string_with_version())?;
@@ -509,6 +525,8 @@ impl serde::Serialize for PythonTool {
         state.serialize_field("runner", &self.runner)?;
         state.serialize_field("operating_system", &self.operating_system)?;
         state.serialize_field("tool_set", &self.tool_set)?;
+        state.serialize_field("test_cases", &self.test_cases)?;
+        state.serialize_field("network_allowlist", &self.network_allowlist)?;
         state.end()
     }
 }
@@ -549,6 +567,8 @@ mod tests {
             runner: RunnerType::OnlyHost,
             operating_system: vec![OperatingSystem::Windows],
             tool_set: None,
+            test_cases: None,
+            network_allowlist: None,
         };
 
         assert_eq!(tool.runner, RunnerType::OnlyHost);
@@ -586,6 +606,8 @@ mod tests {
             runner: RunnerType::Any,
             operating_system: vec![OperatingSystem::Linux, OperatingSystem::Windows],
             tool_set: None,
+            test_cases: None,
+            network_allowlist: None,
         };
 
         assert_eq!(tool.operating_system.len(), 2);
@@ -625,6 +647,8 @@ mod tests {
             runner: RunnerType::OnlyHost,
             operating_system: vec![OperatingSystem::Linux],
             tool_set: Some("test_set".to_string()),
+            test_cases: None,
+            network_allowlist: None,
         };
 
         assert_eq!(tool.tool_set, Some("test_set".to_string()));
@@ -662,6 +686,8 @@ mod tests {
             runner: RunnerType::OnlyHost,
             operating_system: vec![OperatingSystem::Linux],
             tool_set: Some("test_set".to_string()),
+            test_cases: None,
+            network_allowlist: None,
         };
 
         let json = tool.to_json().unwrap();
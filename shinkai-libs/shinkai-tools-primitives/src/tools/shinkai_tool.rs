@@ -5,6 +5,7 @@ use crate::tools::rust_tools::RustTool;
 use serde_json::{self, Value};
 
 use shinkai_message_primitives::schemas::tool_router_key::ToolRouterKey;
+use shinkai_message_primitives::shinkai_utils::secret_vault::is_redacted_secret;
 use shinkai_message_primitives::schemas::{
     indexable_version::IndexableVersion,
     shinkai_tool_offering::{ShinkaiToolOffering, UsageType},
@@ -13,10 +14,11 @@ use shinkai_message_primitives::schemas::{
 use super::agent_tool_wrapper::AgentToolWrapper;
 use super::tool_config::OAuth;
 use super::tool_playground::{SqlQuery, SqlTable};
+use super::tool_test_case::ToolTestCase;
 use super::tool_types::{OperatingSystem, RunnerType};
 use super::{
     deno_tools::DenoTool, network_tool::NetworkTool, parameters::Parameters, python_tools::PythonTool,
-    tool_config::ToolConfig, tool_output_arg::ToolOutputArg,
+    tool_config::ToolConfig, tool_output_arg::ToolOutputArg, wasm_tools::WasmTool,
 };
 
 pub type IsEnabled = bool;
@@ -28,6 +30,7 @@ pub enum ShinkaiTool {
     Network(NetworkTool, IsEnabled),
     Deno(DenoTool, IsEnabled),
     Python(PythonTool, IsEnabled),
+    Wasm(WasmTool, IsEnabled),
     Agent(AgentToolWrapper, IsEnabled),
 }
 
@@ -69,6 +72,13 @@ impl ShinkaiToolHeader {
             self.config = Some(configs.iter().map(|config| config.sanitize()).collect());
         }
     }
+
+    /// Replace the string key-values of BasicConfig with a redacted placeholder
+    pub fn redact_config(&mut self) {
+        if let Some(configs) = &self.config {
+            self.config = Some(configs.iter().map(|config| config.redact()).collect());
+        }
+    }
 }
 
 impl ShinkaiTool {
@@ -113,6 +123,13 @@ impl ShinkaiTool {
                     ToolRouterKey::new("local".to_string(), p.author.clone(), p.name.clone(), None)
                 }
             }
+            ShinkaiTool::Wasm(w, _) => {
+                if let Some(key) = &w.tool_router_key {
+                    key.clone()
+                } else {
+                    ToolRouterKey::new("local".to_string(), w.author.clone(), w.name.clone(), None)
+                }
+            }
             ShinkaiTool::Agent(a, _) => {
                 ToolRouterKey::new("local".to_string(), a.author.clone(), a.agent_id.clone(), None)
             }
@@ -128,10 +145,40 @@ impl ShinkaiTool {
             ShinkaiTool::Python(p, _) => {
                 p.config = p.config.clone().iter().map(|config| config.sanitize()).collect();
             }
+            ShinkaiTool::Wasm(w, _) => {
+                w.config = w.config.clone().iter().map(|config| config.sanitize()).collect();
+            }
             _ => (),
         }
     }
 
+    /// Replace the string key-values of BasicConfig with a redacted placeholder
+    pub fn redact_config(&mut self) {
+        if let Some(configs) = self.get_config_mut() {
+            *configs = configs.iter().map(|config| config.redact()).collect();
+        }
+    }
+
+    /// Puts back the stored key-values of configs that a client sent back still redacted
+    pub fn restore_redacted_config(&mut self, stored: &ShinkaiTool) {
+        let stored_configs = stored.get_config();
+        let Some(configs) = self.get_config_mut() else {
+            return;
+        };
+        for config in configs.iter_mut() {
+            let ToolConfig::BasicConfig(basic) = config;
+            if !matches!(&basic.key_value, Some(Value::String(value)) if is_redacted_secret(value)) {
+                continue;
+            }
+            basic.key_value = stored_configs.iter().find_map(|stored_config| match stored_config {
+                ToolConfig::BasicConfig(stored_basic) if stored_basic.key_name == basic.key_name => {
+                    stored_basic.key_value.clone()
+                }
+                _ => None,
+            });
+        }
+    }
+
     /// Tool name
     pub fn name(&self) -> String {
         match self {
@@ -139,6 +186,7 @@ impl ShinkaiTool {
             ShinkaiTool::Network(n, _) => n.name.clone(),
             ShinkaiTool::Deno(d, _) => d.name.clone(),
             ShinkaiTool::Python(p, _) => p.name.clone(),
+            ShinkaiTool::Wasm(w, _) => w.name.clone(),
             ShinkaiTool::Agent(a, _) => a.name.clone(),
         }
     }
@@ -149,6 +197,7 @@ impl ShinkaiTool {
             ShinkaiTool::Network(n, _) => n.description.clone(),
             ShinkaiTool::Deno(d, _) => d.description.clone(),
             ShinkaiTool::Python(p, _) => p.description.clone(),
+            ShinkaiTool::Wasm(w, _) => w.description.clone(),
             ShinkaiTool::Agent(a, _) => a.description.clone(),
         }
     }
@@ -160,6 +209,7 @@ impl ShinkaiTool {
             ShinkaiTool::Network(n, _) => n.input_args.clone(),
             ShinkaiTool::Deno(d, _) => d.input_args.clone(),
             ShinkaiTool::Python(p, _) => p.input_args.clone(),
+            ShinkaiTool::Wasm(w, _) => w.input_args.clone(),
             ShinkaiTool::Agent(a, _) => a.input_args.clone(),
         }
     }
@@ -171,6 +221,7 @@ impl ShinkaiTool {
             ShinkaiTool::Network(n, _) => n.output_arg.clone(),
             ShinkaiTool::Deno(d, _) => d.output_arg.clone(),
             ShinkaiTool::Python(p, _) => p.output_arg.clone(),
+            ShinkaiTool::Wasm(w, _) => w.output_arg.clone(),
             ShinkaiTool::Agent(a, _) => a.output_arg.clone(),
         }
     }
@@ -182,6 +233,7 @@ impl ShinkaiTool {
             ShinkaiTool::Network(_, _) => "Network",
             ShinkaiTool::Deno(_, _) => "Deno",
             ShinkaiTool::Python(_, _) => "Python",
+            ShinkaiTool::Wasm(_, _) => "Wasm",
             ShinkaiTool::Agent(_, _) => "Agent",
         }
     }
@@ -208,6 +260,7 @@ impl ShinkaiTool {
         match self {
             ShinkaiTool::Deno(d, _) => d.oauth.clone(),
             ShinkaiTool::Python(p, _) => p.oauth.clone(),
+            ShinkaiTool::Wasm(w, _) => w.oauth.clone(),
             _ => None,
         }
     }
@@ -216,6 +269,7 @@ impl ShinkaiTool {
         match self {
             ShinkaiTool::Deno(d, _) => d.tools.clone(),
             ShinkaiTool::Python(p, _) => p.tools.clone(),
+            ShinkaiTool::Wasm(w, _) => w.tools.clone(),
             _ => vec![],
         }
     }
@@ -224,6 +278,7 @@ impl ShinkaiTool {
         match self {
             ShinkaiTool::Deno(d, _) => d.assets.clone(),
             ShinkaiTool::Python(p, _) => p.assets.clone(),
+            ShinkaiTool::Wasm(w, _) => w.assets.clone(),
             _ => None,
         }
     }
@@ -232,6 +287,7 @@ impl ShinkaiTool {
         match self {
             ShinkaiTool::Deno(d, _) => d.homepage.clone(),
             ShinkaiTool::Python(p, _) => p.homepage.clone(),
+            ShinkaiTool::Wasm(w, _) => w.homepage.clone(),
             _ => None,
         }
     }
@@ -250,6 +306,7 @@ impl ShinkaiTool {
         match self {
             ShinkaiTool::Deno(d, _) => d.js_code.clone(),
             ShinkaiTool::Python(p, _) => p.py_code.clone(),
+            ShinkaiTool::Wasm(w, _) => w.wasm_code.clone(),
             _ => unreachable!(),
         }
     }
@@ -258,6 +315,7 @@ impl ShinkaiTool {
         match self {
             ShinkaiTool::Deno(d, _) => d.name = name,
             ShinkaiTool::Python(p, _) => p.name = name,
+            ShinkaiTool::Wasm(w, _) => w.name = name,
             _ => unreachable!(),
         }
     }
@@ -266,6 +324,7 @@ impl ShinkaiTool {
         match self {
             ShinkaiTool::Deno(d, _) => d.author = author,
             ShinkaiTool::Python(p, _) => p.author = author,
+            ShinkaiTool::Wasm(w, _) => w.author = author,
             _ => unreachable!(),
         }
     }
@@ -290,6 +349,26 @@ impl ShinkaiTool {
         match self {
             ShinkaiTool::Deno(d, _) => d.tool_set.clone(),
             ShinkaiTool::Python(p, _) => p.tool_set.clone(),
+            ShinkaiTool::Wasm(w, _) => w.tool_set.clone(),
+            _ => None,
+        }
+    }
+
+    /// Returns the declarative test cases of the tool, if any
+    pub fn get_test_cases(&self) -> Option<Vec<ToolTestCase>> {
+        match self {
+            ShinkaiTool::Deno(d, _) => d.test_cases.clone(),
+            ShinkaiTool::Python(p, _) => p.test_cases.clone(),
+            ShinkaiTool::Wasm(w, _) => w.test_cases.clone(),
+            _ => None,
+        }
+    }
+
+    /// Returns the outbound network allowlist declared by the tool, if any
+    pub fn get_network_allowlist(&self) -> Option<Vec<String>> {
+        match self {
+            ShinkaiTool::Deno(d, _) => d.network_allowlist.clone(),
+            ShinkaiTool::Python(p, _) => p.network_allowlist.clone(),
             _ => None,
         }
     }
@@ -301,6 +380,7 @@ impl ShinkaiTool {
             ShinkaiTool::Network(n, _) => n.embedding = Some(embedding),
             ShinkaiTool::Deno(d, _) => d.embedding = Some(embedding),
             ShinkaiTool::Python(p, _) => p.embedding = Some(embedding),
+            ShinkaiTool::Wasm(w, _) => w.embedding = Some(embedding),
             ShinkaiTool::Agent(a, _) => a.embedding = Some(embedding),
         }
     }
@@ -344,6 +424,7 @@ impl ShinkaiTool {
             ShinkaiTool::Network(n, _) => n.embedding.clone(),
             ShinkaiTool::Deno(d, _) => d.embedding.clone(),
             ShinkaiTool::Python(p, _) => p.embedding.clone(),
+            ShinkaiTool::Wasm(w, _) => w.embedding.clone(),
             ShinkaiTool::Agent(a, _) => a.embedding.clone(),
         }
     }
@@ -372,6 +453,7 @@ impl ShinkaiTool {
             ShinkaiTool::Network(n, _) => n.author.clone(),
             ShinkaiTool::Deno(d, _) => d.author.clone(),
             ShinkaiTool::Python(p, _) => p.author.clone(),
+            ShinkaiTool::Wasm(w, _) => w.author.clone(),
             ShinkaiTool::Agent(a, _) => a.author.clone(),
         }
     }
@@ -383,6 +465,7 @@ impl ShinkaiTool {
             ShinkaiTool::Network(n, _) => n.version.clone(),
             ShinkaiTool::Deno(d, _) => d.version.clone(),
             ShinkaiTool::Python(p, _) => p.version.clone(),
+            ShinkaiTool::Wasm(w, _) => w.version.clone(),
             ShinkaiTool::Agent(_a, _) => "1.0.0".to_string(),
         }
     }
@@ -403,6 +486,7 @@ impl ShinkaiTool {
             ShinkaiTool::Network(_, enabled) => *enabled,
             ShinkaiTool::Deno(_, enabled) => *enabled,
             ShinkaiTool::Python(_, enabled) => *enabled,
+            ShinkaiTool::Wasm(_, enabled) => *enabled,
             ShinkaiTool::Agent(_a, enabled) => *enabled,
         }
     }
@@ -414,6 +498,7 @@ impl ShinkaiTool {
             ShinkaiTool::Network(tool, is_enabled) => *is_enabled && tool.mcp_enabled.unwrap_or(false),
             ShinkaiTool::Deno(tool, is_enabled) => *is_enabled && tool.mcp_enabled.unwrap_or(false),
             ShinkaiTool::Python(tool, is_enabled) => *is_enabled && tool.mcp_enabled.unwrap_or(false),
+            ShinkaiTool::Wasm(tool, is_enabled) => *is_enabled && tool.mcp_enabled.unwrap_or(false),
             ShinkaiTool::Agent(a, is_enabled) => *is_enabled && a.mcp_enabled.unwrap_or(false),
         }
     }
@@ -425,6 +510,7 @@ impl ShinkaiTool {
             ShinkaiTool::Network(_, enabled) => *enabled = true,
             ShinkaiTool::Deno(_, enabled) => *enabled = true,
             ShinkaiTool::Python(_, enabled) => *enabled = true,
+            ShinkaiTool::Wasm(_, enabled) => *enabled = true,
             ShinkaiTool::Agent(_, enabled) => *enabled = true,
         }
     }
@@ -435,6 +521,7 @@ impl ShinkaiTool {
             ShinkaiTool::Network(tool, _) => tool.mcp_enabled = Some(true),
             ShinkaiTool::Deno(tool, _) => tool.mcp_enabled = Some(true),
             ShinkaiTool::Python(tool, _) => tool.mcp_enabled = Some(true),
+            ShinkaiTool::Wasm(tool, _) => tool.mcp_enabled = Some(true),
             ShinkaiTool::Agent(tool, _) => tool.mcp_enabled = Some(true),
         }
     }
@@ -446,6 +533,7 @@ impl ShinkaiTool {
             ShinkaiTool::Network(_, enabled) => *enabled = false,
             ShinkaiTool::Deno(_, enabled) => *enabled = false,
             ShinkaiTool::Python(_, enabled) => *enabled = false,
+            ShinkaiTool::Wasm(_, enabled) => *enabled = false,
             ShinkaiTool::Agent(_, enabled) => *enabled = false,
         }
     }
@@ -456,6 +544,7 @@ impl ShinkaiTool {
             ShinkaiTool::Network(tool, _) => tool.mcp_enabled = Some(false),
             ShinkaiTool::Deno(tool, _) => tool.mcp_enabled = Some(false),
             ShinkaiTool::Python(tool, _) => tool.mcp_enabled = Some(false),
+            ShinkaiTool::Wasm(tool, _) => tool.mcp_enabled = Some(false),
             ShinkaiTool::Agent(tool, _) => tool.mcp_enabled = Some(false),
         }
     }
@@ -469,12 +558,23 @@ impl ShinkaiTool {
         }
     }
 
+    /// Mutable config of the tool types that have one
+    pub fn get_config_mut(&mut self) -> Option<&mut Vec<ToolConfig>> {
+        match self {
+            ShinkaiTool::Deno(js_tool, _) => Some(&mut js_tool.config),
+            ShinkaiTool::Python(python_tool, _) => Some(&mut python_tool.config),
+            ShinkaiTool::Wasm(wasm_tool, _) => Some(&mut wasm_tool.config),
+            _ => None,
+        }
+    }
+
     pub fn get_config(&self) -> Vec<ToolConfig> {
         match self {
             ShinkaiTool::Rust(_, _) => vec![],
             ShinkaiTool::Network(_, _) => vec![],
             ShinkaiTool::Deno(js_tool, _) => js_tool.config.clone(),
             ShinkaiTool::Python(python_tool, _) => python_tool.config.clone(),
+            ShinkaiTool::Wasm(wasm_tool, _) => wasm_tool.config.clone(),
             ShinkaiTool::Agent(_a, _) => vec![],
         }
     }
@@ -486,6 +586,7 @@ impl ShinkaiTool {
             ShinkaiTool::Network(n_tool, _) => n_tool.check_required_config_fields(),
             ShinkaiTool::Deno(deno_tool, _) => deno_tool.check_required_config_fields(),
             ShinkaiTool::Python(_, _) => true,
+            ShinkaiTool::Wasm(wasm_tool, _) => wasm_tool.check_required_config_fields(),
             ShinkaiTool::Agent(_, _) => true,
         }
     }
@@ -559,6 +660,7 @@ impl ShinkaiTool {
             ShinkaiTool::Network(_, _) => vec![],
             ShinkaiTool::Deno(d, _) => d.keywords.clone(),
             ShinkaiTool::Python(p, _) => p.keywords.clone(),
+            ShinkaiTool::Wasm(w, _) => w.keywords.clone(),
             ShinkaiTool::Agent(_a, _) => vec![],
         }
     }
@@ -576,6 +678,12 @@ impl From<DenoTool> for ShinkaiTool {
     }
 }
 
+impl From<WasmTool> for ShinkaiTool {
+    fn from(tool: WasmTool) -> Self {
+        ShinkaiTool::Wasm(tool, true)
+    }
+}
+
 impl From<NetworkTool> for ShinkaiTool {
     fn from(tool: NetworkTool) -> Self {
         ShinkaiTool::Network(tool, true)
@@ -633,6 +741,8 @@ mod tests {
             runner: RunnerType::OnlyHost,
             operating_system: vec![OperatingSystem::Linux],
             tool_set: None,
+            test_cases: None,
+            network_allowlist: None,
         };
 
         // Create a ShinkaiTool instance
@@ -724,6 +834,8 @@ mod tests {
             runner: RunnerType::OnlyHost,
             operating_system: vec![OperatingSystem::Windows],
             tool_set: None,
+            test_cases: None,
+            network_allowlist: None,
         };
 
         let shinkai_tool = ShinkaiTool::Deno(deno_tool, true);
@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use shinkai_message_primitives::shinkai_utils::secret_vault::REDACTED_SECRET;
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ToolConfig {
@@ -47,6 +48,20 @@ impl ToolConfig {
         }
     }
 
+    /// Returns a copy of the ToolConfig with string key-values replaced by `REDACTED_SECRET`,
+    /// so the UI can still show which values are set
+    pub fn redact(&self) -> ToolConfig {
+        match self {
+            ToolConfig::BasicConfig(config) => {
+                let mut config = config.clone();
+                if matches!(&config.key_value, Some(Value::String(value)) if !value.is_empty()) {
+                    config.key_value = Some(Value::String(REDACTED_SECRET.to_string()));
+                }
+                ToolConfig::BasicConfig(config)
+            }
+        }
+    }
+
     /// Creates a vector of ToolConfig::BasicConfig instances from a serde_json::Value
     pub fn basic_config_from_value(value: &Value) -> Vec<ToolConfig> {
         let mut configs = Vec::new();
@@ -0,0 +1,126 @@
+use ipnet::IpNet;
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+
+/// Approval entry letting a tool that declares no network allowlist reach any host
+pub const ANY_HOST: &str = "*";
+
+/// Outbound network allowlist of a tool. Entries are one of:
+/// - a host name, optionally with a port: `api.example.com`, `api.example.com:8443`
+/// - a wildcard domain matching the domain and its subdomains: `*.example.com`
+/// - an IP address or range: `203.0.113.7`, `10.0.0.0/8`, `2001:db8::/32`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NetworkAllowlist {
+    domains: Vec<(DomainPattern, Option<u16>)>,
+    ranges: Vec<IpNet>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum DomainPattern {
+    Exact(String),
+    Wildcard(String),
+}
+
+/// Network access approved by the user for an installed tool
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolNetworkApproval {
+    pub tool_router_key: String,
+    pub allowed_hosts: Vec<String>,
+    pub approved_at: String,
+}
+
+/// Outbound connection attempt rejected by the egress proxy
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolEgressBlock {
+    pub tool_router_key: String,
+    pub host: String,
+    pub port: u16,
+    pub reason: String,
+    pub created_at: String,
+}
+
+impl NetworkAllowlist {
+    pub fn parse(entries: &[String]) -> Result<Self, String> {
+        let mut allowlist = NetworkAllowlist::default();
+        for entry in entries {
+            let entry = entry.trim().to_lowercase();
+            if entry.is_empty() {
+                continue;
+            }
+            if let Ok(range) = entry.parse::<IpNet>() {
+                allowlist.ranges.push(range);
+                continue;
+            }
+            if let Ok(ip) = entry.trim_matches(['[', ']']).parse::<IpAddr>() {
+                allowlist.ranges.push(IpNet::from(ip));
+                continue;
+            }
+
+            let (host, port) = match entry.rsplit_once(':') {
+                Some((host, port)) => (
+                    host.to_string(),
+                    Some(port.parse::<u16>().map_err(|_| format!("Invalid port in {}", entry))?),
+                ),
+                None => (entry.clone(), None),
+            };
+            if host.is_empty() || host.contains('/') || host.contains(' ') {
+                return Err(format!("Invalid network allowlist entry: {}", entry));
+            }
+            let pattern = match host.strip_prefix("*.") {
+                Some(domain) => DomainPattern::Wildcard(domain.to_string()),
+                None => DomainPattern::Exact(host),
+            };
+            allowlist.domains.push((pattern, port));
+        }
+        Ok(allowlist)
+    }
+
+    /// Whether the host name (and port) is allowed by a domain entry
+    pub fn allows_host(&self, host: &str, port: u16) -> bool {
+        let host = host.trim_end_matches('.').to_lowercase();
+        self.domains.iter().any(|(pattern, allowed_port)| {
+            let host_matches = match pattern {
+                DomainPattern::Exact(domain) => &host == domain,
+                DomainPattern::Wildcard(domain) => &host == domain || host.ends_with(&format!(".{}", domain)),
+            };
+            host_matches && allowed_port.map_or(true, |allowed_port| allowed_port == port)
+        })
+    }
+
+    /// Whether the resolved address is inside one of the allowed IP ranges
+    pub fn allows_ip(&self, ip: IpAddr) -> bool {
+        self.ranges.iter().any(|range| range.contains(&ip))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_network_allowlist_matching() {
+        let allowlist = NetworkAllowlist::parse(&[
+            "api.example.com".to_string(),
+            "*.shinkai.com".to_string(),
+            "internal.local:8443".to_string(),
+            "10.0.0.0/8".to_string(),
+            "2001:db8::1".to_string(),
+        ])
+        .unwrap();
+
+        assert!(allowlist.allows_host("API.example.com", 443));
+        assert!(!allowlist.allows_host("evil-api.example.com", 443));
+        assert!(allowlist.allows_host("store.shinkai.com", 443));
+        assert!(allowlist.allows_host("shinkai.com", 80));
+        assert!(!allowlist.allows_host("shinkai.com.evil.io", 443));
+        assert!(allowlist.allows_host("internal.local", 8443));
+        assert!(!allowlist.allows_host("internal.local", 80));
+
+        assert!(allowlist.allows_ip("10.1.2.3".parse().unwrap()));
+        assert!(!allowlist.allows_ip("192.168.1.1".parse().unwrap()));
+        assert!(allowlist.allows_ip("2001:db8::1".parse().unwrap()));
+
+        assert!(NetworkAllowlist::parse(&["example.com:http".to_string()]).is_err());
+        assert_eq!(NetworkAllowlist::parse(&[]).unwrap(), NetworkAllowlist::default());
+    }
+}
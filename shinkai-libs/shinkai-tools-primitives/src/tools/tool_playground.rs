@@ -1,6 +1,7 @@
 use super::{
     parameters::Parameters,
     tool_config::{BasicConfig, OAuth, ToolConfig},
+    tool_test_case::ToolTestCase,
     tool_types::{OperatingSystem, RunnerType, ToolResult},
 };
 use serde::{Deserialize, Deserializer, Serialize};
@@ -63,6 +64,10 @@ pub struct ToolPlaygroundMetadata {
     pub runner: RunnerType,
     pub operating_system: Vec<OperatingSystem>,
     pub tool_set: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub test_cases: Option<Vec<ToolTestCase>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network_allowlist: Option<Vec<String>>,
 }
 
 fn deserialize_configurations<'de, D>(deserializer: D) -> Result<Vec<ToolConfig>, D::Error>
@@ -653,6 +658,8 @@ mod tests {
             runner: RunnerType::OnlyHost,
             operating_system: vec![OperatingSystem::Linux],
             tool_set: None,
+            test_cases: None,
+            network_allowlist: None,
         };
 
         let serialized = serde_json::to_value(&metadata).unwrap();
@@ -792,6 +799,8 @@ mod tests {
             runner: RunnerType::Any,
             operating_system: vec![OperatingSystem::Windows],
             tool_set: None,
+            test_cases: None,
+            network_allowlist: None,
         };
 
         let serialized = serde_json::to_value(&metadata).unwrap();
@@ -877,6 +886,8 @@ mod tests {
             runner: RunnerType::Any,
             operating_system: vec![OperatingSystem::Linux, OperatingSystem::MacOS],
             tool_set: Some("some cool set".to_string()),
+            test_cases: None,
+            network_allowlist: None,
         };
 
         let serialized = serde_json::to_value(&metadata).unwrap();
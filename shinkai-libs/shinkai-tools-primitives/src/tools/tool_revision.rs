@@ -0,0 +1,226 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{error::ToolError, shinkai_tool::ShinkaiTool};
+
+/// Immutable snapshot of a tool. A new revision is recorded every time a tool is
+/// added or updated, so previous code, config and metadata can always be recovered.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolRevision {
+    pub tool_router_key: String,
+    pub version: String,
+    pub revision: u64,
+    pub created_at: String,
+    pub tool: ShinkaiTool,
+}
+
+/// Lightweight view of a revision (without the tool payload) used for listings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolRevisionHeader {
+    pub tool_router_key: String,
+    pub version: String,
+    pub revision: u64,
+    pub created_at: String,
+    pub name: String,
+    pub description: String,
+}
+
+/// A single changed field between two revisions. `path` is a JSON pointer into the
+/// serialized tool (e.g. `/content/0/config`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolRevisionChange {
+    pub path: String,
+    pub from: Option<Value>,
+    pub to: Option<Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolRevisionDiff {
+    pub tool_router_key: String,
+    pub from_revision: u64,
+    pub to_revision: u64,
+    pub changes: Vec<ToolRevisionChange>,
+    /// Line based diff of the tool code. Lines are prefixed with "+ ", "- " or "  ".
+    pub code_diff: Vec<String>,
+}
+
+impl ToolRevision {
+    pub fn to_header(&self) -> ToolRevisionHeader {
+        ToolRevisionHeader {
+            tool_router_key: self.tool_router_key.clone(),
+            version: self.version.clone(),
+            revision: self.revision,
+            created_at: self.created_at.clone(),
+            name: self.tool.name(),
+            description: self.tool.description(),
+        }
+    }
+
+    /// Computes the differences needed to go from `self` to `other`
+    pub fn diff(&self, other: &ToolRevision) -> Result<ToolRevisionDiff, ToolError> {
+        let from = Self::comparable_value(&self.tool)?;
+        let to = Self::comparable_value(&other.tool)?;
+
+        let mut changes = Vec::new();
+        Self::diff_values("", Some(&from), Some(&to), &mut changes);
+
+        Ok(ToolRevisionDiff {
+            tool_router_key: self.tool_router_key.clone(),
+            from_revision: self.revision,
+            to_revision: other.revision,
+            changes,
+            code_diff: Self::diff_lines(&self.tool.get_code(), &other.tool.get_code()),
+        })
+    }
+
+    // Serializes the tool skipping the embedding, which changes on every update and is not
+    // meaningful for a human reading the diff.
+    fn comparable_value(tool: &ShinkaiTool) -> Result<Value, ToolError> {
+        let mut value = serde_json::to_value(tool).map_err(|e| ToolError::SerializationError(e.to_string()))?;
+        if let Some(Value::Array(content)) = value.get_mut("content") {
+            for item in content.iter_mut() {
+                if let Value::Object(map) = item {
+                    map.remove("embedding");
+                }
+            }
+        }
+        Ok(value)
+    }
+
+    fn diff_values(path: &str, from: Option<&Value>, to: Option<&Value>, changes: &mut Vec<ToolRevisionChange>) {
+        match (from, to) {
+            (Some(Value::Object(a)), Some(Value::Object(b))) => {
+                let mut keys: Vec<&String> = a.keys().chain(b.keys()).collect();
+                keys.sort();
+                keys.dedup();
+                for key in keys {
+                    let child_path = format!("{}/{}", path, key);
+                    Self::diff_values(&child_path, a.get(key), b.get(key), changes);
+                }
+            }
+            (Some(Value::Array(a)), Some(Value::Array(b))) if a.len() == b.len() => {
+                for (index, (item_a, item_b)) in a.iter().zip(b.iter()).enumerate() {
+                    let child_path = format!("{}/{}", path, index);
+                    Self::diff_values(&child_path, Some(item_a), Some(item_b), changes);
+                }
+            }
+            (a, b) if a != b => changes.push(ToolRevisionChange {
+                path: path.to_string(),
+                from: a.cloned(),
+                to: b.cloned(),
+            }),
+            _ => {}
+        }
+    }
+
+    // Minimal LCS based line diff
+    fn diff_lines(from: &str, to: &str) -> Vec<String> {
+        let a: Vec<&str> = from.lines().collect();
+        let b: Vec<&str> = to.lines().collect();
+
+        let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+        for i in (0..a.len()).rev() {
+            for j in (0..b.len()).rev() {
+                lcs[i][j] = if a[i] == b[j] {
+                    lcs[i + 1][j + 1] + 1
+                } else {
+                    lcs[i + 1][j].max(lcs[i][j + 1])
+                };
+            }
+        }
+
+        let mut result = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < a.len() && j < b.len() {
+            if a[i] == b[j] {
+                result.push(format!("  {}", a[i]));
+                i += 1;
+                j += 1;
+            } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+                result.push(format!("- {}", a[i]));
+                i += 1;
+            } else {
+                result.push(format!("+ {}", b[j]));
+                j += 1;
+            }
+        }
+        result.extend(a[i..].iter().map(|line| format!("- {}", line)));
+        result.extend(b[j..].iter().map(|line| format!("+ {}", line)));
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::deno_tools::DenoTool;
+    use crate::tools::parameters::Parameters;
+    use crate::tools::tool_output_arg::ToolOutputArg;
+    use crate::tools::tool_types::{OperatingSystem, RunnerType, ToolResult};
+
+    fn revision(revision: u64, code: &str, description: &str) -> ToolRevision {
+        let deno_tool = DenoTool {
+            name: "Revisioned Tool".to_string(),
+            tool_router_key: None,
+            homepage: None,
+            author: "@@test.shinkai".to_string(),
+            version: "1.0.0".to_string(),
+            mcp_enabled: Some(false),
+            js_code: code.to_string(),
+            tools: vec![],
+            config: vec![],
+            oauth: None,
+            description: description.to_string(),
+            keywords: vec![],
+            input_args: Parameters::new(),
+            output_arg: ToolOutputArg::empty(),
+            activated: true,
+            embedding: Some(vec![revision as f32; 4]),
+            result: ToolResult::new("object".to_string(), serde_json::Value::Null, vec![]),
+            sql_tables: None,
+            sql_queries: None,
+            file_inbox: None,
+            assets: None,
+            runner: RunnerType::OnlyHost,
+            operating_system: vec![OperatingSystem::Linux],
+            tool_set: None,
+            test_cases: None,
+            network_allowlist: None,
+        };
+
+        ToolRevision {
+            tool_router_key: "local:::__test_shinkai:::revisioned_tool".to_string(),
+            version: "1.0.0".to_string(),
+            revision,
+            created_at: "2025-01-01T00:00:00Z".to_string(),
+            tool: ShinkaiTool::Deno(deno_tool, true),
+        }
+    }
+
+    #[test]
+    fn test_diff_reports_changed_fields_and_code() {
+        let first = revision(1, "const a = 1;\nconsole.log(a);", "first");
+        let second = revision(2, "const a = 2;\nconsole.log(a);", "second");
+
+        let diff = first.diff(&second).unwrap();
+        assert_eq!(diff.from_revision, 1);
+        assert_eq!(diff.to_revision, 2);
+
+        let paths: Vec<&str> = diff.changes.iter().map(|c| c.path.as_str()).collect();
+        assert_eq!(paths, vec!["/content/0/description", "/content/0/js_code"]);
+        assert_eq!(
+            diff.code_diff,
+            vec!["- const a = 1;", "+ const a = 2;", "  console.log(a);"]
+        );
+    }
+
+    #[test]
+    fn test_diff_identical_revisions_is_empty() {
+        let first = revision(1, "console.log(1);", "same");
+        let second = revision(2, "console.log(1);", "same");
+
+        let diff = first.diff(&second).unwrap();
+        assert!(diff.changes.is_empty());
+        assert!(diff.code_diff.iter().all(|line| line.starts_with("  ")));
+    }
+}
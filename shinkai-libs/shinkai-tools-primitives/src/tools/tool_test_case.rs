@@ -0,0 +1,254 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Tag used to mark test cases that need network access. These are skipped when
+/// running tests offline.
+pub const NETWORK_TEST_TAG: &str = "network";
+
+/// A declarative test case stored alongside the tool metadata.
+///
+/// Example:
+/// ```json
+/// {
+///   "name": "fetches the title",
+///   "input": { "url": "https://example.com" },
+///   "config": { "api_key": "test" },
+///   "assertions": [{ "path": "$.title", "matcher": "equals", "expected": "Example Domain" }],
+///   "tags": ["network"]
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolTestCase {
+    pub name: String,
+    /// Input arguments passed to the tool
+    #[serde(default)]
+    pub input: Map<String, Value>,
+    /// Config values overriding the tool configuration for this test only
+    #[serde(default)]
+    pub config: Option<Map<String, Value>>,
+    #[serde(default)]
+    pub assertions: Vec<ToolTestAssertion>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolTestAssertion {
+    /// JSON path into the tool output, e.g. `$.items[0].title`
+    pub path: String,
+    #[serde(default)]
+    pub matcher: ToolTestMatcher,
+    #[serde(default)]
+    pub expected: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolTestMatcher {
+    #[default]
+    Equals,
+    NotEquals,
+    /// Substring for strings, element for arrays and key for objects
+    Contains,
+    /// `expected` is a regular expression the string value must match
+    Regex,
+    /// The path must resolve to a value, `expected` is ignored
+    Exists,
+    /// `expected` is one of: null, boolean, number, string, array, object
+    Type,
+    GreaterThan,
+    LessThan,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolTestStatus {
+    Passed,
+    Failed,
+    Skipped,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolTestCaseResult {
+    pub name: String,
+    pub status: ToolTestStatus,
+    /// Failed assertions, execution error or the reason the test was skipped
+    pub messages: Vec<String>,
+    pub output: Option<Value>,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolTestReport {
+    pub tool_router_key: String,
+    pub passed: usize,
+    pub failed: usize,
+    pub skipped: usize,
+    pub results: Vec<ToolTestCaseResult>,
+}
+
+impl ToolTestCase {
+    pub fn requires_network(&self) -> bool {
+        self.tags.iter().any(|tag| tag.eq_ignore_ascii_case(NETWORK_TEST_TAG))
+    }
+
+    /// Checks every assertion against the tool output and returns the failures
+    pub fn evaluate(&self, output: &Value) -> Vec<String> {
+        self.assertions
+            .iter()
+            .filter_map(|assertion| assertion.check(output).err())
+            .collect()
+    }
+}
+
+impl ToolTestAssertion {
+    pub fn check(&self, output: &Value) -> Result<(), String> {
+        let actual = resolve_json_path(output, &self.path)?;
+
+        let passed = match (&self.matcher, actual) {
+            (ToolTestMatcher::Exists, actual) => actual.is_some(),
+            (_, None) => return Err(format!("{}: path not found in output", self.path)),
+            (ToolTestMatcher::Equals, Some(actual)) => *actual == self.expected,
+            (ToolTestMatcher::NotEquals, Some(actual)) => *actual != self.expected,
+            (ToolTestMatcher::Contains, Some(actual)) => match (actual, &self.expected) {
+                (Value::String(s), Value::String(needle)) => s.contains(needle.as_str()),
+                (Value::Array(items), expected) => items.contains(expected),
+                (Value::Object(map), Value::String(key)) => map.contains_key(key),
+                _ => false,
+            },
+            (ToolTestMatcher::Regex, Some(actual)) => {
+                let pattern = self
+                    .expected
+                    .as_str()
+                    .ok_or_else(|| format!("{}: regex matcher expects a string pattern", self.path))?;
+                let regex = Regex::new(pattern).map_err(|e| format!("{}: invalid regex: {}", self.path, e))?;
+                actual.as_str().map(|s| regex.is_match(s)).unwrap_or(false)
+            }
+            (ToolTestMatcher::Type, Some(actual)) => Some(json_type_name(actual)) == self.expected.as_str(),
+            (ToolTestMatcher::GreaterThan, Some(actual)) => match (actual.as_f64(), self.expected.as_f64()) {
+                (Some(a), Some(b)) => a > b,
+                _ => false,
+            },
+            (ToolTestMatcher::LessThan, Some(actual)) => match (actual.as_f64(), self.expected.as_f64()) {
+                (Some(a), Some(b)) => a < b,
+                _ => false,
+            },
+        };
+
+        if passed {
+            Ok(())
+        } else {
+            Err(format!(
+                "{}: expected {:?} {}, got {}",
+                self.path,
+                self.matcher,
+                self.expected,
+                actual.map(|v| v.to_string()).unwrap_or_else(|| "nothing".to_string())
+            ))
+        }
+    }
+}
+
+fn json_type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Resolves a simple JSON path (`$`, `.key`, `["key"]` and `[index]` segments).
+/// Returns `Ok(None)` when the path is valid but does not exist in `value`.
+pub fn resolve_json_path<'a>(value: &'a Value, path: &str) -> Result<Option<&'a Value>, String> {
+    let path = path.trim();
+    let mut bare_key = !path.starts_with('$');
+    let mut rest = path.strip_prefix('$').unwrap_or(path);
+    let mut current = value;
+
+    while !rest.is_empty() {
+        let (next, remaining) = if let Some(after_dot) = rest.strip_prefix('.') {
+            let end = after_dot.find(['.', '[']).unwrap_or(after_dot.len());
+            let key = &after_dot[..end];
+            if key.is_empty() {
+                return Err(format!("Invalid JSON path: {}", path));
+            }
+            (current.get(key), &after_dot[end..])
+        } else if let Some(after_bracket) = rest.strip_prefix('[') {
+            let end = after_bracket
+                .find(']')
+                .ok_or_else(|| format!("Invalid JSON path: {}", path))?;
+            let segment = after_bracket[..end].trim();
+            let next = if let Ok(index) = segment.parse::<usize>() {
+                current.get(index)
+            } else {
+                current.get(segment.trim_matches(|c| c == '"' || c == '\''))
+            };
+            (next, &after_bracket[end + 1..])
+        } else if bare_key {
+            // Paths without a leading `$` or `.` (e.g. `items[0]`)
+            let end = rest.find(['.', '[']).unwrap_or(rest.len());
+            (current.get(&rest[..end]), &rest[end..])
+        } else {
+            return Err(format!("Invalid JSON path: {}", path));
+        };
+
+        match next {
+            Some(next) => current = next,
+            None => return Ok(None),
+        }
+        rest = remaining;
+        bare_key = false;
+    }
+
+    Ok(Some(current))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_resolve_json_path() {
+        let output = json!({ "items": [{ "title": "Rust" }, { "title": "Deno" }], "count": 2 });
+
+        assert_eq!(resolve_json_path(&output, "$").unwrap(), Some(&output));
+        assert_eq!(resolve_json_path(&output, "$.count").unwrap(), Some(&json!(2)));
+        assert_eq!(resolve_json_path(&output, "$.items[1].title").unwrap(), Some(&json!("Deno")));
+        assert_eq!(resolve_json_path(&output, "items[0][\"title\"]").unwrap(), Some(&json!("Rust")));
+        assert_eq!(resolve_json_path(&output, "$.missing.field").unwrap(), None);
+        assert!(resolve_json_path(&output, "$.items[0").is_err());
+    }
+
+    #[test]
+    fn test_evaluate_assertions() {
+        let test_case: ToolTestCase = serde_json::from_value(json!({
+            "name": "search",
+            "input": { "query": "rust" },
+            "assertions": [
+                { "path": "$.count", "expected": 2 },
+                { "path": "$.items[0].title", "matcher": "contains", "expected": "Ru" },
+                { "path": "$.items", "matcher": "type", "expected": "array" },
+                { "path": "$.items[1].title", "matcher": "regex", "expected": "^D.*o$" },
+                { "path": "$.count", "matcher": "greater_than", "expected": 5 },
+                { "path": "$.missing", "matcher": "exists" }
+            ],
+            "tags": ["Network"]
+        }))
+        .unwrap();
+
+        assert!(test_case.requires_network());
+        assert_eq!(test_case.config, None);
+
+        let output = json!({ "items": [{ "title": "Rust" }, { "title": "Deno" }], "count": 2 });
+        let failures = test_case.evaluate(&output);
+        assert_eq!(failures.len(), 2);
+        assert!(failures[0].starts_with("$.count"));
+        assert!(failures[1].starts_with("$.missing"));
+    }
+}
@@ -0,0 +1,777 @@
+use super::parameters::Parameters;
+use super::shared_execution::{get_files_after_with_protocol, update_result_with_modified_files};
+use super::tool_config::{OAuth, ToolConfig};
+use super::tool_output_arg::ToolOutputArg;
+use super::tool_test_case::ToolTestCase;
+use super::tool_types::ToolResult;
+use crate::tools::error::ToolError;
+use base64::Engine as _;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use shinkai_message_primitives::schemas::shinkai_name::ShinkaiName;
+use shinkai_message_primitives::schemas::tool_router_key::ToolRouterKey;
+use shinkai_tools_runner::tools::run_result::RunResult;
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use wasmtime::component::{Component, Linker as ComponentLinker, ResourceTable};
+use wasmtime::{Config, Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder};
+use wasmtime_wasi::p2::bindings::Command;
+use wasmtime_wasi::p2::pipe::{MemoryInputPipe, MemoryOutputPipe};
+use wasmtime_wasi::preview1::WasiP1Ctx;
+use wasmtime_wasi::{DirPerms, FilePerms, I32Exit, SocketAddrUse, WasiCtx, WasiCtxBuilder, WasiCtxView, WasiView};
+
+/// Maximum time a WASM tool is allowed to run before being interrupted
+const WASM_TOOL_TIMEOUT: Duration = Duration::from_secs(300);
+/// Maximum linear memory a WASM tool can allocate
+const WASM_TOOL_MAX_MEMORY_BYTES: usize = 512 * 1024 * 1024;
+/// Maximum size of the captured stdout / stderr
+const WASM_TOOL_MAX_OUTPUT_BYTES: usize = 16 * 1024 * 1024;
+
+/// A tool compiled to WebAssembly and executed in-process with a WASI sandbox.
+///
+/// The module receives `{"configurations": {...}, "parameters": {...}}` as JSON on stdin and
+/// must print the JSON result on stdout. Both core modules (`wasm32-wasip1`) and components
+/// (`wasm32-wasip2`) are supported. Outbound network is only available to components, as
+/// WASIp1 has no sockets.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WasmTool {
+    pub name: String,
+    #[serde(default, with = "optional_tool_router_key")]
+    pub tool_router_key: Option<ToolRouterKey>,
+    pub homepage: Option<String>,
+    pub author: String,
+    pub version: String,
+    pub mcp_enabled: Option<bool>,
+    /// Base64 encoded WASM binary
+    pub wasm_code: String,
+    #[serde(default)]
+    #[serde(deserialize_with = "ToolRouterKey::deserialize_tool_router_keys")]
+    #[serde(serialize_with = "ToolRouterKey::serialize_tool_router_keys")]
+    pub tools: Vec<ToolRouterKey>,
+    pub config: Vec<ToolConfig>,
+    pub description: String,
+    pub keywords: Vec<String>,
+    pub input_args: Parameters,
+    pub output_arg: ToolOutputArg,
+    pub activated: bool,
+    pub embedding: Option<Vec<f32>>,
+    pub result: ToolResult,
+    pub file_inbox: Option<String>,
+    pub oauth: Option<Vec<OAuth>>,
+    pub assets: Option<Vec<String>>,
+    #[serde(default)]
+    pub capabilities: WasmCapabilities,
+    pub tool_set: Option<String>,
+    pub test_cases: Option<Vec<ToolTestCase>>,
+}
+
+/// Capabilities a WASM tool asks for. They only take effect once the user approves them, see
+/// `WasmCapabilityGrant`. Anything not listed here is denied.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WasmCapabilities {
+    /// Host directories made available to the tool. Relative paths are resolved against the
+    /// storage folder of the tool.
+    #[serde(default)]
+    pub filesystem: Vec<WasmFilesystemGrant>,
+    /// Names of node environment variables forwarded to the tool
+    #[serde(default)]
+    pub env: Vec<String>,
+    /// Hosts the tool can connect to, as `host` or `host:port`
+    #[serde(default)]
+    pub hosts: Vec<String>,
+}
+
+impl WasmCapabilities {
+    pub fn is_empty(&self) -> bool {
+        self.filesystem.is_empty() && self.env.is_empty() && self.hosts.is_empty()
+    }
+
+    /// Capabilities both requested and granted, so a grant never covers more than what the
+    /// running version of the tool asks for
+    pub fn granted(&self, grant: &WasmCapabilities) -> WasmCapabilities {
+        WasmCapabilities {
+            filesystem: self
+                .filesystem
+                .iter()
+                .filter(|entry| grant.filesystem.contains(entry))
+                .cloned()
+                .collect(),
+            env: self
+                .env
+                .iter()
+                .filter(|name| grant.env.contains(name))
+                .cloned()
+                .collect(),
+            hosts: self
+                .hosts
+                .iter()
+                .filter(|host| grant.hosts.contains(host))
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WasmCapabilityGrant {
+    pub tool_router_key: String,
+    pub capabilities: WasmCapabilities,
+    /// Whether the filesystem grants can reach host paths outside the storage folder of the tool
+    pub allow_outside_storage: bool,
+    pub approved_at: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WasmFilesystemGrant {
+    pub host_path: String,
+    /// Path seen by the tool. Defaults to `host_path`.
+    pub guest_path: Option<String>,
+    #[serde(default)]
+    pub read_only: bool,
+}
+
+mod optional_tool_router_key {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use shinkai_message_primitives::schemas::tool_router_key::ToolRouterKey;
+
+    pub fn serialize<S>(key: &Option<ToolRouterKey>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match key {
+            Some(key) => serializer.serialize_some(&key.to_string_with_version()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<ToolRouterKey>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Option::<String>::deserialize(deserializer)?
+            .map(|key| ToolRouterKey::from_string(&key).map_err(serde::de::Error::custom))
+            .transpose()
+    }
+}
+
+struct WasmP1State {
+    wasi: WasiP1Ctx,
+    limits: StoreLimits,
+}
+
+struct WasmP2State {
+    ctx: WasiCtx,
+    table: ResourceTable,
+    limits: StoreLimits,
+}
+
+impl WasiView for WasmP2State {
+    fn ctx(&mut self) -> WasiCtxView<'_> {
+        WasiCtxView {
+            ctx: &mut self.ctx,
+            table: &mut self.table,
+        }
+    }
+}
+
+/// The engine is shared by all the WASM tools so compilation settings and caches are reused
+fn wasm_engine() -> Result<&'static Engine, ToolError> {
+    static ENGINE: OnceLock<Engine> = OnceLock::new();
+    if let Some(engine) = ENGINE.get() {
+        return Ok(engine);
+    }
+
+    let mut config = Config::new();
+    config.async_support(true);
+    config.wasm_component_model(true);
+    config.epoch_interruption(true);
+    let engine =
+        Engine::new(&config).map_err(|e| ToolError::ExecutionError(format!("Failed to start WASM engine: {}", e)))?;
+    Ok(ENGINE.get_or_init(|| engine))
+}
+
+// Only the protocol variables of the node reach the tool, the same the Deno and Python runners
+// get, never the rest of its environment
+fn shinkai_envs(envs: &HashMap<String, String>) -> Vec<(&String, &String)> {
+    envs.iter()
+        .filter(|(name, _)| name.starts_with("SHINKAI_") || name.starts_with("X_SHINKAI_") || *name == "BEARER")
+        .collect()
+}
+
+// Components use a different layer in the preamble than core modules
+fn is_component(bytes: &[u8]) -> bool {
+    bytes.len() >= 8 && bytes.starts_with(b"\0asm") && bytes[4..8] != [1, 0, 0, 0]
+}
+
+impl WasmTool {
+    /// Convert to json
+    pub fn to_json(&self) -> Result<String, ToolError> {
+        serde_json::to_string(self).map_err(|_| ToolError::FailedJSONParsing)
+    }
+
+    /// Convert from json
+    pub fn from_json(json: &str) -> Result<Self, ToolError> {
+        let deserialized: Self = serde_json::from_str(json)?;
+        Ok(deserialized)
+    }
+
+    pub fn check_required_config_fields(&self) -> bool {
+        self.config.iter().all(|config| {
+            let ToolConfig::BasicConfig(basic_config) = config;
+            !basic_config.required || basic_config.key_value.is_some()
+        })
+    }
+
+    fn wasm_bytes(&self) -> Result<Vec<u8>, ToolError> {
+        base64::engine::general_purpose::STANDARD
+            .decode(self.wasm_code.trim())
+            .map_err(|e| ToolError::ParseError(format!("Invalid WASM binary: {}", e)))
+    }
+
+    pub async fn run(
+        &self,
+        envs: HashMap<String, String>,
+        api_ip: String,
+        api_port: u16,
+        parameters: Map<String, Value>,
+        extra_config: Vec<ToolConfig>,
+        node_storage_path: String,
+        app_id: String,
+        tool_id: String,
+        node_name: ShinkaiName,
+        files_tool_router_key: Option<String>,
+        mounts: Option<Vec<String>>,
+        grant: Option<WasmCapabilityGrant>,
+    ) -> Result<RunResult, ToolError> {
+        println!("[Running WasmTool] Named: {}, Input: {:?}", self.name, parameters);
+
+        // Create a hashmap with key_name and key_value, extra_config takes precedence
+        let mut config: HashMap<String, Value> = HashMap::new();
+        for c in self.config.iter().chain(extra_config.iter()) {
+            let ToolConfig::BasicConfig(basic_config) = c;
+            if let Some(value) = &basic_config.key_value {
+                config.insert(basic_config.key_name.clone(), value.clone());
+            }
+        }
+
+        // Same storage layout used by the Deno and Python runners
+        let full_path = Path::new(&node_storage_path).join("tools_storage");
+        let home_path = full_path.join(&app_id).join("home");
+        let logs_path = full_path.join(&app_id).join("logs");
+        std::fs::create_dir_all(&home_path)
+            .and_then(|_| std::fs::create_dir_all(&logs_path))
+            .map_err(|e| ToolError::ExecutionError(format!("Failed to create directory structure: {}", e)))?;
+
+        let start_time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        let stdin = serde_json::to_vec(&json!({ "configurations": config, "parameters": parameters }))
+            .map_err(|e| ToolError::SerializationError(e.to_string()))?;
+        let stdout = MemoryOutputPipe::new(WASM_TOOL_MAX_OUTPUT_BYTES);
+        let stderr = MemoryOutputPipe::new(WASM_TOOL_MAX_OUTPUT_BYTES);
+
+        let mut builder = WasiCtxBuilder::new();
+        builder
+            .stdin(MemoryInputPipe::new(stdin))
+            .stdout(stdout.clone())
+            .stderr(stderr.clone())
+            .arg(&self.name)
+            .envs(&shinkai_envs(&envs))
+            .env("SHINKAI_NODE_LOCATION", format!("http://{}:{}", api_ip, api_port))
+            .env("SHINKAI_HOME", "/home")
+            .env("SHINKAI_CONTEXT_ID", &app_id)
+            .env("SHINKAI_EXECUTION_ID", &tool_id);
+        builder
+            .preopened_dir(&home_path, "/home", DirPerms::all(), FilePerms::all())
+            .map_err(|e| ToolError::ExecutionError(format!("Failed to mount home directory: {}", e)))?;
+
+        let tool_storage_path = self.storage_path(&node_storage_path);
+        self.grant_assets(&mut builder, &node_storage_path, files_tool_router_key)?;
+        if let Some(grant) = grant {
+            let capabilities = self.capabilities.granted(&grant.capabilities);
+            Self::grant_capabilities(
+                &mut builder,
+                &capabilities,
+                &tool_storage_path,
+                grant.allow_outside_storage,
+            )
+            .await?;
+        }
+
+        let bytes = self.wasm_bytes()?;
+        let engine = wasm_engine()?;
+        // Private to this execution and removed once it is over
+        let mounts_path = full_path.join(&app_id).join("mounts").join(&tool_id);
+        if let Err(e) = Self::grant_mounts(&mut builder, mounts.unwrap_or_default(), &mounts_path) {
+            let _ = std::fs::remove_dir_all(&mounts_path);
+            return Err(e);
+        }
+
+        let execution = tokio::time::timeout(WASM_TOOL_TIMEOUT, async {
+            if is_component(&bytes) {
+                Self::run_component(&bytes, builder).await
+            } else {
+                Self::run_module(&bytes, builder).await
+            }
+        });
+
+        // Epoch ticks let the guest yield so the timeout above can interrupt it
+        let ticker = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_millis(10));
+            loop {
+                interval.tick().await;
+                engine.increment_epoch();
+            }
+        });
+        let result = execution.await;
+        ticker.abort();
+        let _ = std::fs::remove_dir_all(&mounts_path);
+
+        let logs = String::from_utf8_lossy(&stderr.contents()).to_string();
+        if !logs.is_empty() {
+            let _ = std::fs::write(logs_path.join(format!("log_{}.log", tool_id)), &logs);
+        }
+
+        let result = match result {
+            Ok(result) => result,
+            Err(_) => Err(format!(
+                "Execution timed out after {} seconds",
+                WASM_TOOL_TIMEOUT.as_secs()
+            )),
+        }
+        .and_then(|_| {
+            let output = stdout.contents();
+            serde_json::from_slice::<Value>(&output).map_err(|e| format!("Tool output is not valid JSON: {}", e))
+        });
+
+        match result {
+            Ok(data) => update_result_with_modified_files(
+                RunResult { data },
+                start_time,
+                &home_path,
+                &logs_path,
+                &node_name,
+                &app_id,
+            ),
+            Err(error) => {
+                let files = get_files_after_with_protocol(start_time, &home_path, &logs_path, &node_name, &app_id)
+                    .into_iter()
+                    .map(|file| file.as_str().unwrap_or_default().to_string())
+                    .collect::<Vec<String>>()
+                    .join(" ");
+
+                let title = format!("**Tool {} execution failed.**", self.name);
+                let parameters = format!(
+                    "*Inputs:*\n `{}`",
+                    serde_json::to_string(&parameters).unwrap_or_default()
+                );
+                let error = format!("```\n{}\n{}\n```", error, logs.trim());
+                Err(ToolError::AutocontainedError(format!(
+                    "{}\n\n  {}\n\n  {}\n\n  Files: {}",
+                    title, parameters, error, files
+                )))
+            }
+        }
+    }
+
+    // Assets of an installed tool are exposed read-only under /assets
+    fn grant_assets(
+        &self,
+        builder: &mut WasiCtxBuilder,
+        node_storage_path: &str,
+        files_tool_router_key: Option<String>,
+    ) -> Result<(), ToolError> {
+        let (Some(tool_router_key), Some(assets)) = (files_tool_router_key, &self.assets) else {
+            return Ok(());
+        };
+        let tool_key = ToolRouterKey::from_string(&tool_router_key)?;
+        let assets_path = PathBuf::from(node_storage_path)
+            .join(".tools_storage")
+            .join("tools")
+            .join(tool_key.convert_to_path());
+        if !assets_path.exists() {
+            return Ok(());
+        }
+
+        builder
+            .preopened_dir(&assets_path, "/assets", DirPerms::READ, FilePerms::READ)
+            .map_err(|e| ToolError::ExecutionError(format!("Failed to mount assets: {}", e)))?;
+        let guest_assets: Vec<String> = assets.iter().map(|asset| format!("/assets/{}", asset)).collect();
+        builder.env("SHINKAI_ASSETS", guest_assets.join(","));
+        Ok(())
+    }
+
+    // Mounted files are exposed read-only, each one linked, or copied, alone into its own private
+    // directory, so the rest of the folder the file comes from stays out of reach
+    fn grant_mounts(builder: &mut WasiCtxBuilder, mounts: Vec<String>, mounts_path: &Path) -> Result<(), ToolError> {
+        let mut guest_mounts = Vec::new();
+        for (index, mount) in mounts.iter().enumerate() {
+            let mount = PathBuf::from(mount);
+            let Some(file_name) = mount.file_name() else {
+                continue;
+            };
+            let host_dir = mounts_path.join(index.to_string());
+            let private_file = host_dir.join(file_name);
+            std::fs::create_dir_all(&host_dir)
+                .and_then(|_| {
+                    std::fs::hard_link(&mount, &private_file)
+                        .or_else(|_| std::fs::copy(&mount, &private_file).map(|_| ()))
+                })
+                .map_err(|e| ToolError::ExecutionError(format!("Failed to mount {}: {}", mount.display(), e)))?;
+            let guest_dir = format!("/mount/{}", index);
+            builder
+                .preopened_dir(&host_dir, &guest_dir, DirPerms::READ, FilePerms::READ)
+                .map_err(|e| ToolError::ExecutionError(format!("Failed to mount {}: {}", mount.display(), e)))?;
+            guest_mounts.push(format!("{}/{}", guest_dir, file_name.to_string_lossy()));
+        }
+        if !guest_mounts.is_empty() {
+            builder.env("SHINKAI_MOUNT", guest_mounts.join(","));
+        }
+        Ok(())
+    }
+
+    // Storage folder of the installed tool, where its assets live
+    fn storage_path(&self, node_storage_path: &str) -> PathBuf {
+        let tool_key = self
+            .tool_router_key
+            .clone()
+            .unwrap_or_else(|| ToolRouterKey::new("local".to_string(), self.author.clone(), self.name.clone(), None));
+        PathBuf::from(node_storage_path)
+            .join(".tools_storage")
+            .join("tools")
+            .join(tool_key.convert_to_path())
+    }
+
+    // Host directories are resolved against the tool storage and must stay inside it unless the
+    // user allowed otherwise
+    fn resolve_host_path(
+        host_path: &str,
+        tool_storage_path: &Path,
+        allow_outside_storage: bool,
+    ) -> Result<PathBuf, ToolError> {
+        std::fs::create_dir_all(tool_storage_path)
+            .map_err(|e| ToolError::ExecutionError(format!("Failed to create tool storage: {}", e)))?;
+        let storage = tool_storage_path
+            .canonicalize()
+            .map_err(|e| ToolError::ExecutionError(format!("Failed to resolve tool storage: {}", e)))?;
+        let path = storage
+            .join(host_path)
+            .canonicalize()
+            .map_err(|e| ToolError::ExecutionError(format!("Failed to resolve {}: {}", host_path, e)))?;
+        if !allow_outside_storage && !path.starts_with(&storage) {
+            return Err(ToolError::ExecutionError(format!(
+                "Access to {} is outside the tool storage and was not allowed",
+                host_path
+            )));
+        }
+        Ok(path)
+    }
+
+    async fn grant_capabilities(
+        builder: &mut WasiCtxBuilder,
+        capabilities: &WasmCapabilities,
+        tool_storage_path: &Path,
+        allow_outside_storage: bool,
+    ) -> Result<(), ToolError> {
+        for grant in &capabilities.filesystem {
+            let (dir_perms, file_perms) = if grant.read_only {
+                (DirPerms::READ, FilePerms::READ)
+            } else {
+                (DirPerms::all(), FilePerms::all())
+            };
+            let host_path = Self::resolve_host_path(&grant.host_path, tool_storage_path, allow_outside_storage)?;
+            let guest_path = grant.guest_path.clone().unwrap_or_else(|| grant.host_path.clone());
+            builder
+                .preopened_dir(&host_path, guest_path, dir_perms, file_perms)
+                .map_err(|e| {
+                    ToolError::ExecutionError(format!("Failed to grant access to {}: {}", grant.host_path, e))
+                })?;
+        }
+
+        for name in &capabilities.env {
+            if let Ok(value) = std::env::var(name) {
+                builder.env(name, value);
+            }
+        }
+
+        if capabilities.hosts.is_empty() {
+            return Ok(());
+        }
+
+        // Hosts are resolved once, connections are only allowed to the resulting addresses
+        let mut allowed: HashSet<(IpAddr, Option<u16>)> = HashSet::new();
+        for host in &capabilities.hosts {
+            let (name, port) = match host.rsplit_once(':') {
+                Some((name, port)) if port.parse::<u16>().is_ok() => (name.to_string(), port.parse::<u16>().ok()),
+                _ => (host.clone(), None),
+            };
+            // The port only matters to the connection check, the lookup resolves addresses alone
+            let addresses = tokio::net::lookup_host((name.trim_matches(['[', ']']), 0))
+                .await
+                .map_err(|e| ToolError::ExecutionError(format!("Failed to resolve allowed host {}: {}", host, e)))?;
+            allowed.extend(addresses.map(|address| (address.ip(), port)));
+        }
+        let allowed = Arc::new(allowed);
+
+        builder
+            .allow_ip_name_lookup(true)
+            .allow_tcp(true)
+            .socket_addr_check(move |address, address_use| {
+                let allowed = allowed.clone();
+                Box::pin(async move {
+                    match address_use {
+                        SocketAddrUse::TcpConnect => {
+                            allowed.contains(&(address.ip(), None))
+                                || allowed.contains(&(address.ip(), Some(address.port())))
+                        }
+                        SocketAddrUse::TcpBind
+                        | SocketAddrUse::UdpBind
+                        | SocketAddrUse::UdpConnect
+                        | SocketAddrUse::UdpOutgoingDatagram => false,
+                    }
+                })
+            });
+        Ok(())
+    }
+
+    async fn run_module(bytes: &[u8], mut builder: WasiCtxBuilder) -> Result<(), String> {
+        let engine = wasm_engine().map_err(|e| e.to_string())?;
+        let module = Module::new(engine, bytes).map_err(|e| format!("Invalid WASM module: {}", e))?;
+
+        let mut linker: Linker<WasmP1State> = Linker::new(engine);
+        wasmtime_wasi::preview1::add_to_linker_async(&mut linker, |state| &mut state.wasi)
+            .map_err(|e| e.to_string())?;
+
+        let mut store = Store::new(
+            engine,
+            WasmP1State {
+                wasi: builder.build_p1(),
+                limits: StoreLimitsBuilder::new()
+                    .memory_size(WASM_TOOL_MAX_MEMORY_BYTES)
+                    .build(),
+            },
+        );
+        store.limiter(|state| &mut state.limits);
+        store.epoch_deadline_async_yield_and_update(1);
+
+        let instance = linker
+            .instantiate_async(&mut store, &module)
+            .await
+            .map_err(|e| format!("Failed to instantiate WASM module: {}", e))?;
+        let start = instance
+            .get_typed_func::<(), ()>(&mut store, "_start")
+            .map_err(|e| format!("WASM module has no _start function: {}", e))?;
+
+        match start.call_async(&mut store, ()).await {
+            Ok(()) => Ok(()),
+            Err(e) => match e.downcast_ref::<I32Exit>() {
+                Some(I32Exit(0)) => Ok(()),
+                Some(I32Exit(code)) => Err(format!("Tool exited with status {}", code)),
+                None => Err(format!("Tool trapped: {}", e)),
+            },
+        }
+    }
+
+    async fn run_component(bytes: &[u8], mut builder: WasiCtxBuilder) -> Result<(), String> {
+        let engine = wasm_engine().map_err(|e| e.to_string())?;
+        let component = Component::new(engine, bytes).map_err(|e| format!("Invalid WASM component: {}", e))?;
+
+        let mut linker: ComponentLinker<WasmP2State> = ComponentLinker::new(engine);
+        wasmtime_wasi::p2::add_to_linker_async(&mut linker).map_err(|e| e.to_string())?;
+
+        let mut store = Store::new(
+            engine,
+            WasmP2State {
+                ctx: builder.build(),
+                table: ResourceTable::new(),
+                limits: StoreLimitsBuilder::new()
+                    .memory_size(WASM_TOOL_MAX_MEMORY_BYTES)
+                    .build(),
+            },
+        );
+        store.limiter(|state| &mut state.limits);
+        store.epoch_deadline_async_yield_and_update(1);
+
+        let command = Command::instantiate_async(&mut store, &component, &linker)
+            .await
+            .map_err(|e| format!("Failed to instantiate WASM component: {}", e))?;
+        match command.wasi_cli_run().call_run(&mut store).await {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(())) => Err("Tool exited with an error".to_string()),
+            Err(e) => match e.downcast_ref::<I32Exit>() {
+                Some(I32Exit(0)) => Ok(()),
+                Some(I32Exit(code)) => Err(format!("Tool exited with status {}", code)),
+                None => Err(format!("Tool trapped: {}", e)),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Writes a fixed JSON document to stdout using WASIp1 `fd_write`
+    const ECHO_WAT: &str = r#"
+        (module
+          (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+          (memory (export "memory") 1)
+          (data (i32.const 16) "{\"ok\":true}")
+          (func (export "_start")
+            (i32.store (i32.const 0) (i32.const 16))
+            (i32.store (i32.const 4) (i32.const 11))
+            (drop (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 8)))))
+    "#;
+
+    fn wasm_tool(code: &str, capabilities: WasmCapabilities) -> WasmTool {
+        WasmTool {
+            name: "Wasm Tool".to_string(),
+            tool_router_key: None,
+            homepage: None,
+            author: "@@test.shinkai".to_string(),
+            version: "1.0.0".to_string(),
+            mcp_enabled: Some(false),
+            wasm_code: base64::engine::general_purpose::STANDARD.encode(code),
+            tools: vec![],
+            config: vec![],
+            description: "A WASM tool".to_string(),
+            keywords: vec![],
+            input_args: Parameters::new(),
+            output_arg: ToolOutputArg::empty(),
+            activated: true,
+            embedding: None,
+            result: ToolResult::new("object".to_string(), Value::Null, vec![]),
+            file_inbox: None,
+            oauth: None,
+            assets: None,
+            capabilities,
+            tool_set: None,
+            test_cases: None,
+        }
+    }
+
+    #[test]
+    fn test_wasm_tool_serialization() {
+        let mut tool = wasm_tool(
+            ECHO_WAT,
+            WasmCapabilities {
+                filesystem: vec![],
+                env: vec!["HOME".to_string()],
+                hosts: vec!["api.example.com:443".to_string()],
+            },
+        );
+        tool.tool_router_key = Some(ToolRouterKey::new(
+            "local".to_string(),
+            tool.author.clone(),
+            tool.name.clone(),
+            None,
+        ));
+
+        let serialized = serde_json::to_value(&tool).unwrap();
+        assert_eq!(
+            serialized["tool_router_key"],
+            json!("local:::__test_shinkai:::wasm_tool")
+        );
+        assert_eq!(serialized["capabilities"]["hosts"], json!(["api.example.com:443"]));
+
+        let deserialized: WasmTool = serde_json::from_value(serialized.clone()).unwrap();
+        assert_eq!(deserialized.capabilities, tool.capabilities);
+        assert_eq!(serde_json::to_value(&deserialized).unwrap(), serialized);
+    }
+
+    #[tokio::test]
+    async fn test_wasm_tool_runs_in_process() {
+        let storage = tempfile::tempdir().unwrap();
+        let tool = wasm_tool(ECHO_WAT, WasmCapabilities::default());
+
+        let result = tool
+            .run(
+                HashMap::new(),
+                "127.0.0.1".to_string(),
+                9550,
+                Map::new(),
+                vec![],
+                storage.path().to_string_lossy().to_string(),
+                "app".to_string(),
+                "tool".to_string(),
+                ShinkaiName::new("@@test.shinkai".to_string()).unwrap(),
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(result.data["ok"], json!(true));
+        assert_eq!(result.data["__created_files__"], json!([]));
+    }
+
+    #[test]
+    fn test_wasm_tool_grants_stay_within_request_and_storage() {
+        let requested = WasmCapabilities {
+            filesystem: vec![WasmFilesystemGrant {
+                host_path: "data".to_string(),
+                guest_path: Some("/data".to_string()),
+                read_only: true,
+            }],
+            env: vec!["HOME".to_string()],
+            hosts: vec!["api.example.com:443".to_string()],
+        };
+        let grant = WasmCapabilities {
+            filesystem: requested.filesystem.clone(),
+            env: vec!["HOME".to_string(), "AWS_SECRET_ACCESS_KEY".to_string()],
+            hosts: vec![],
+        };
+        let granted = requested.granted(&grant);
+        assert_eq!(granted.filesystem, requested.filesystem);
+        assert_eq!(granted.env, vec!["HOME".to_string()]);
+        assert!(granted.hosts.is_empty());
+
+        let envs = HashMap::from([
+            ("SHINKAI_TOOLS_PATH".to_string(), "/tools".to_string()),
+            ("BEARER".to_string(), "caller_key".to_string()),
+            ("X_SHINKAI_TOOL_ID".to_string(), "tool".to_string()),
+            ("OPENAI_API_KEY".to_string(), "secret".to_string()),
+        ]);
+        let mut forwarded: Vec<&String> = shinkai_envs(&envs).into_iter().map(|(name, _)| name).collect();
+        forwarded.sort();
+        assert_eq!(forwarded, vec!["BEARER", "SHINKAI_TOOLS_PATH", "X_SHINKAI_TOOL_ID"]);
+
+        let storage = tempfile::tempdir().unwrap();
+        let tool_storage = storage.path().join("tool");
+        std::fs::create_dir_all(tool_storage.join("data")).unwrap();
+        assert!(WasmTool::resolve_host_path("data", &tool_storage, false).is_ok());
+        let outside = storage.path().to_string_lossy().to_string();
+        assert!(WasmTool::resolve_host_path(&outside, &tool_storage, false).is_err());
+        assert!(WasmTool::resolve_host_path("..", &tool_storage, false).is_err());
+        assert!(WasmTool::resolve_host_path(&outside, &tool_storage, true).is_ok());
+    }
+
+    #[test]
+    fn test_wasm_tool_mounts_only_expose_the_file() {
+        let storage = tempfile::tempdir().unwrap();
+        let shared = storage.path().join("shared");
+        std::fs::create_dir_all(&shared).unwrap();
+        std::fs::write(shared.join("input.txt"), "mounted").unwrap();
+        std::fs::write(shared.join("other.txt"), "private").unwrap();
+
+        let mounts_path = storage.path().join("mounts");
+        let mount = shared.join("input.txt").to_string_lossy().to_string();
+        WasmTool::grant_mounts(&mut WasiCtxBuilder::new(), vec![mount], &mounts_path).unwrap();
+
+        let exposed: Vec<_> = std::fs::read_dir(mounts_path.join("0"))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(exposed, vec![std::ffi::OsString::from("input.txt")]);
+        assert_eq!(
+            std::fs::read_to_string(mounts_path.join("0").join("input.txt")).unwrap(),
+            "mounted"
+        );
+    }
+}